@@ -1,6 +1,36 @@
-use std::sync::atomic::{AtomicBool, Ordering::{Acquire, Relaxed, Release}};
-use std::cell::UnsafeCell;
 use std::mem::MaybeUninit;
+#[cfg(not(loom))]
+use std::time::{Duration, Instant};
+
+// Taken from loom when built with `--cfg loom`, so the `concurrency-tests` crate can model check the channels
+#[cfg(loom)]
+use loom::{
+    cell::UnsafeCell,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering::{Acquire, Relaxed, Release}},
+    sync::{Arc, Mutex},
+    thread::{self, Thread},
+};
+#[cfg(not(loom))]
+use std::{
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering::{Acquire, Relaxed, Release}},
+    sync::{Arc, Mutex},
+    thread::{self, Thread},
+};
+
+/// `UnsafeCell` with the closure based API of `loom::cell::UnsafeCell`, so the same code runs under loom.
+#[cfg(not(loom))]
+struct UnsafeCell<T>(std::cell::UnsafeCell<T>);
+
+#[cfg(not(loom))]
+impl<T> UnsafeCell<T> {
+    fn new(value: T) -> Self {
+        Self(std::cell::UnsafeCell::new(value))
+    }
+
+    fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+        f(self.0.get())
+    }
+}
 
 pub struct Sender<'a, T> {
     channel: &'a Channel<T>,
@@ -12,6 +42,43 @@ pub struct Receiver<'a, T> {
 pub struct Channel<T> {
     message: UnsafeCell<MaybeUninit<T>>,
     ready: AtomicBool,
+    /// Set when the sender is dropped without sending, the message will never arrive.
+    /// `send` consumes the sender and forgets it, so `ready` and `disconnected` are never both set: the sender
+    /// either sends or is dropped, there is no concurrent send to race with.
+    disconnected: AtomicBool,
+    /// Receiver parked in `receive_blocking` or `recv_timeout`, taken and unparked by `send` or the sender drop.
+    /// The lock orders the registration before the `ready` check against the `ready` store before the wakeup,
+    /// so either the receiver sees the message or the sender sees the thread to unpark.
+    receiving_thread: Mutex<Option<Thread>>,
+}
+
+/// Error of a receive that got no message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecvError {
+    /// Nothing sent yet, the message may still arrive.
+    NotReady,
+    /// The sender was dropped without sending, the message will never arrive.
+    Disconnected,
+}
+
+impl std::fmt::Display for RecvError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecvError::NotReady => write!(f, "the message was not sent yet"),
+            RecvError::Disconnected => write!(f, "the sender was dropped without sending"),
+        }
+    }
+}
+
+impl std::error::Error for RecvError {}
+
+/// Error of `Receiver::recv_timeout`.
+#[cfg(not(loom))]
+pub enum RecvTimeoutError<'a, T> {
+    /// Nothing sent yet, the receiver is handed back to retry.
+    Timeout(Receiver<'a, T>),
+    /// The sender was dropped without sending.
+    Disconnected,
 }
 
 unsafe impl<T> Sync for Channel<T> where T: Send {}
@@ -19,32 +86,140 @@ unsafe impl<T> Sync for Channel<T> where T: Send {}
 
 impl<'a, T> Sender<'a, T> {
     pub fn send(self, value: T) {
-        unsafe {
-            (*self.channel.message.get()).write(value);
-        }
+        self.channel.message.with_mut(|message| unsafe {
+            (*message).write(value);
+        });
         self.channel.ready.store(true, Release);
+        self.channel.wake_receiver();
+        // Sent, the drop must not flag the channel as disconnected
+        std::mem::forget(self);
+    }
+}
+
+impl<T> Drop for Sender<'_, T> {
+    /// Only runs for a sender dropped without sending.
+    fn drop(&mut self) {
+        self.channel.disconnected.store(true, Release);
+        self.channel.wake_receiver();
     }
 }
 
 impl<'a, T> Receiver<'a, T> {
+    /// Acquire, so a `true` makes the message written by `send` visible to `receive`.
     pub fn is_ready(&self) -> bool {
-        self.channel.ready.load(Relaxed)
+        self.channel.ready.load(Acquire)
+    }
+
+    /// Whether the sender was dropped without sending.
+    pub fn is_disconnected(&self) -> bool {
+        self.channel.disconnected.load(Acquire)
+    }
+
+    /// Whether the channel is closed: the sender is gone without sending and no message will ever arrive.
+    /// For shutdown loops polling the receiver, a sent message keeps the channel open until it is received.
+    pub fn is_closed(&self) -> bool {
+        self.is_disconnected()
     }
 
     pub fn receive(self) -> T {
         if !self.channel.ready.swap(false, Acquire) {
             panic!("Attempted to receive a message before it was ready");
         }
-        unsafe { (*self.channel.message.get()).assume_init_read() }
+        self.channel.message.with_mut(|message| unsafe { (*message).assume_init_read() })
+    }
+
+    /// Takes the message without blocking or panicking.
+    /// Returns `Err(RecvError::NotReady)` if it was not sent yet, `Err(RecvError::Disconnected)` if it never will be.
+    /// Consumes the receiver like `receive`, use `try_receive` to retry.
+    pub fn recv(mut self) -> Result<T, RecvError> {
+        self.try_receive()?.ok_or(RecvError::NotReady)
+    }
+
+    /// Takes the message if it was sent, without blocking.
+    /// Returns `Ok(None)` while the message may still arrive, `Err(RecvError::Disconnected)` once it never will.
+    /// Borrows the receiver, so unlike `receive` it can be retried until the message is there.
+    pub fn try_receive(&mut self) -> Result<Option<T>, RecvError> {
+        // Loaded before the swap: a swap on every poll writes `false` each time, and those writes may all be ordered
+        // before the `true` of `send`, so a polling receiver could miss the message forever
+        if self.is_ready() && self.channel.ready.swap(false, Acquire) {
+            return Ok(Some(self.channel.message.with_mut(|message| unsafe { (*message).assume_init_read() })));
+        }
+        if self.is_disconnected() {
+            return Err(RecvError::Disconnected);
+        }
+        Ok(None)
+    }
+
+    /// Parks the receiving thread until the message is sent, or returns `Err(RecvError::Disconnected)` once the sender
+    /// is dropped.
+    pub fn receive_blocking(self) -> Result<T, RecvError> {
+        self.register_thread();
+        loop {
+            if self.is_ready() {
+                return Ok(self.receive());
+            }
+            if self.is_disconnected() {
+                return Err(RecvError::Disconnected);
+            }
+            // Spurious wakeups are possible, the loop checks again
+            thread::park();
+        }
+    }
+
+    /// Parks the receiving thread until the message is sent, woken by `send`, without a manual park/unpark loop.
+    /// Reads immediately without registering the thread if the message is already there.
+    /// Panics if the sender is dropped without sending, like `receive` before the message is ready;
+    /// use `receive_blocking` to handle the disconnect.
+    pub fn recv_blocking(self) -> T {
+        if self.is_ready() {
+            return self.receive();
+        }
+        match self.receive_blocking() {
+            Ok(message) => message,
+            Err(e) => panic!("Attempted to receive a message that will never arrive: {}", e),
+        }
+    }
+
+    /// Parks the receiving thread until the message is sent, the sender is dropped or `timeout` expires.
+    /// Returns the receiver back on timeout, the message stays in the channel until a later receive.
+    /// Not built under loom, which has no `park_timeout`.
+    #[cfg(not(loom))]
+    pub fn recv_timeout(self, timeout: Duration) -> Result<T, RecvTimeoutError<'a, T>> {
+        let deadline = Instant::now() + timeout;
+        self.register_thread();
+        loop {
+            if self.is_ready() {
+                return Ok(self.receive());
+            }
+            if self.is_disconnected() {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                *self.channel.receiving_thread.lock().unwrap() = None;
+                // The message may have arrived between the last check and the deregistration
+                if self.is_ready() {
+                    return Ok(self.receive());
+                }
+                if self.is_disconnected() {
+                    return Err(RecvTimeoutError::Disconnected);
+                }
+                return Err(RecvTimeoutError::Timeout(self));
+            }
+            thread::park_timeout(deadline - now);
+        }
+    }
+
+    fn register_thread(&self) {
+        *self.channel.receiving_thread.lock().unwrap() = Some(thread::current());
     }
 }
 
 impl<T> Drop for Channel<T> {
     fn drop(&mut self) {
-        if *self.ready.get_mut() {
-            unsafe {
-                (*self.message.get()).assume_init_drop();
-            }
+        // Relaxed is enough, the exclusive borrow already orders it with the send
+        if self.ready.load(Relaxed) {
+            self.message.with_mut(|message| unsafe { (*message).assume_init_drop() });
         }
     }
 }
@@ -55,6 +230,8 @@ impl<T> Channel<T> {
         Self {
             message: UnsafeCell::new(MaybeUninit::uninit()),
             ready: AtomicBool::new(false),
+            disconnected: AtomicBool::new(false),
+            receiving_thread: Mutex::new(None),
         }
     }
 
@@ -62,12 +239,258 @@ impl<T> Channel<T> {
         *self = Self::new();
         (Sender { channel: self }, Receiver { channel: self })
     }
+
+    /// Unparks the receiver if it is parked, after `ready` or `disconnected` was set.
+    fn wake_receiver(&self) {
+        if let Some(thread) = self.receiving_thread.lock().unwrap().take() {
+            thread.unpark();
+        }
+    }
+}
+
+/// Bounded multi-producer single-consumer channel: the one-shot `Channel` generalized to a ring of `capacity` slots.
+/// `send` parks while the ring is full, `recv` parks while it is empty.
+/// Panics if `capacity` is zero, there is no rendezvous mode.
+pub fn sync_channel<T>(capacity: usize) -> (SyncSender<T>, SyncReceiver<T>) {
+    assert!(capacity > 0, "a sync channel needs at least one slot");
+    let channel = Arc::new(SyncChannel {
+        slots: (0..capacity)
+            .map(|i| Slot { sequence: AtomicUsize::new(2 * i), message: UnsafeCell::new(MaybeUninit::uninit()) })
+            .collect(),
+        send_position: AtomicUsize::new(0),
+        receive_position: AtomicUsize::new(0),
+        senders: AtomicUsize::new(1),
+        receiver_dropped: AtomicBool::new(false),
+        receiving_thread: Mutex::new(None),
+        sending_threads: Mutex::new(Vec::new()),
+    });
+    (SyncSender { channel: Arc::clone(&channel) }, SyncReceiver { channel })
+}
+
+/// One slot of the ring.
+/// `sequence` is twice the position the slot is free for, plus one once its message is written: a sender at position
+/// `p` may write when it reads `2p`, the receiver may read when it reads `2p + 1` and then frees the slot for position
+/// `p + capacity`, the next lap. Doubling keeps "written at `p`" apart from "free for `p + 1`" with a single slot.
+struct Slot<T> {
+    sequence: AtomicUsize,
+    message: UnsafeCell<MaybeUninit<T>>,
+}
+
+struct SyncChannel<T> {
+    slots: Box<[Slot<T>]>,
+    /// Next position to write, claimed by the senders with a compare-exchange.
+    send_position: AtomicUsize,
+    /// Next position to read, only moved by the receiver.
+    receive_position: AtomicUsize,
+    /// Number of live senders, the channel is disconnected once it drops to zero.
+    senders: AtomicUsize,
+    receiver_dropped: AtomicBool,
+    /// Receiver parked on an empty ring. Registered before checking again, like `Channel::receiving_thread`.
+    receiving_thread: Mutex<Option<Thread>>,
+    /// Senders parked on a full ring, all woken by the receive that frees a slot.
+    sending_threads: Mutex<Vec<Thread>>,
+}
+
+unsafe impl<T> Sync for SyncChannel<T> where T: Send {}
+unsafe impl<T> Send for SyncChannel<T> where T: Send {}
+
+/// Sending half of `sync_channel`, cloned for every producer.
+pub struct SyncSender<T> {
+    channel: Arc<SyncChannel<T>>,
+}
+
+/// Receiving half of `sync_channel`, the single consumer.
+pub struct SyncReceiver<T> {
+    channel: Arc<SyncChannel<T>>,
+}
+
+/// Error of `SyncSender::send`: the receiver is gone, the message is handed back.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+impl<T> std::fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the receiver was dropped")
+    }
+}
+
+impl<T: std::fmt::Debug> std::error::Error for SendError<T> {}
+
+/// Error of `SyncSender::try_send`, with the message handed back.
+#[derive(Debug, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    /// Every slot is taken, the message may be sent once the receiver catches up.
+    Full(T),
+    /// The receiver was dropped.
+    Disconnected(T),
+}
+
+impl<T> SyncChannel<T> {
+    /// Writes `value` into the next free slot, or hands it back if the ring is full.
+    fn push(&self, value: T) -> Result<(), T> {
+        let capacity = self.slots.len();
+        let mut position = self.send_position.load(Relaxed);
+        loop {
+            let slot = &self.slots[position % capacity];
+            // Acquire, so the receive that freed the slot is done reading it
+            let sequence = slot.sequence.load(Acquire);
+            if sequence == 2 * position {
+                match self.send_position.compare_exchange_weak(position, position + 1, Relaxed, Relaxed) {
+                    Ok(_) => {
+                        slot.message.with_mut(|message| unsafe { (*message).write(value) });
+                        // Release, publishes the message to the receiver
+                        slot.sequence.store(2 * position + 1, Release);
+                        return Ok(());
+                    }
+                    Err(current) => position = current,
+                }
+            } else if sequence < 2 * position {
+                // Still holding the message of the previous lap
+                return Err(value);
+            } else {
+                // Another sender claimed this position
+                position = self.send_position.load(Relaxed);
+            }
+        }
+    }
+
+    /// Takes the message of the next slot, None if it was not written yet.
+    fn pop(&self) -> Option<T> {
+        let capacity = self.slots.len();
+        let position = self.receive_position.load(Relaxed);
+        let slot = &self.slots[position % capacity];
+        if slot.sequence.load(Acquire) != 2 * position + 1 {
+            return None;
+        }
+        let value = slot.message.with_mut(|message| unsafe { (*message).assume_init_read() });
+        self.receive_position.store(position + 1, Relaxed);
+        // Release, the slot is free for the sender of the next lap once the message is read
+        slot.sequence.store(2 * (position + capacity), Release);
+        Some(value)
+    }
+
+    fn is_disconnected(&self) -> bool {
+        self.senders.load(Acquire) == 0
+    }
+
+    fn wake_receiver(&self) {
+        if let Some(thread) = self.receiving_thread.lock().unwrap().take() {
+            thread.unpark();
+        }
+    }
+
+    fn wake_senders(&self) {
+        for thread in self.sending_threads.lock().unwrap().drain(..) {
+            thread.unpark();
+        }
+    }
+}
+
+impl<T> Drop for SyncChannel<T> {
+    /// Drops the messages sent but never received.
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+impl<T> SyncSender<T> {
+    /// Sends `value`, parking while every slot is taken.
+    /// Returns `Err(SendError(value))` if the receiver was dropped, before or while waiting for a slot.
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        let mut value = match self.try_send(value) {
+            Ok(()) => return Ok(()),
+            Err(TrySendError::Disconnected(value)) => return Err(SendError(value)),
+            Err(TrySendError::Full(value)) => value,
+        };
+        loop {
+            // Registered before the retry, so a receive freeing a slot after it sees this thread to unpark
+            self.channel.sending_threads.lock().unwrap().push(thread::current());
+            value = match self.try_send(value) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Disconnected(value)) => return Err(SendError(value)),
+                Err(TrySendError::Full(value)) => value,
+            };
+            // Spurious wakeups are possible, the loop registers and checks again
+            thread::park();
+        }
+    }
+
+    /// Sends `value` if a slot is free, without blocking.
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        if self.channel.receiver_dropped.load(Acquire) {
+            return Err(TrySendError::Disconnected(value));
+        }
+        self.channel.push(value).map_err(TrySendError::Full)?;
+        self.channel.wake_receiver();
+        Ok(())
+    }
 }
 
-#[cfg(test)]
+impl<T> Clone for SyncSender<T> {
+    fn clone(&self) -> Self {
+        self.channel.senders.fetch_add(1, Relaxed);
+        Self { channel: Arc::clone(&self.channel) }
+    }
+}
+
+impl<T> Drop for SyncSender<T> {
+    /// The last sender disconnects the channel, the receiver gets the messages left then `RecvError::Disconnected`.
+    fn drop(&mut self) {
+        if self.channel.senders.fetch_sub(1, Release) == 1 {
+            self.channel.wake_receiver();
+        }
+    }
+}
+
+impl<T> SyncReceiver<T> {
+    /// Takes the next message, parking while the ring is empty.
+    /// Returns `Err(RecvError::Disconnected)` once every sender is dropped and every message received.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        loop {
+            match self.try_recv() {
+                Err(RecvError::NotReady) => {}
+                result => return result,
+            }
+            // Registered before the retry, so a send or the last sender drop after it sees this thread to unpark
+            *self.channel.receiving_thread.lock().unwrap() = Some(thread::current());
+            match self.try_recv() {
+                Err(RecvError::NotReady) => thread::park(),
+                result => return result,
+            }
+        }
+    }
+
+    /// Takes the next message without blocking.
+    /// Returns `Err(RecvError::NotReady)` while the ring is empty, `Err(RecvError::Disconnected)` once no message
+    /// will ever arrive.
+    pub fn try_recv(&self) -> Result<T, RecvError> {
+        // Checked before the pop: the messages sent before the last sender dropped are still received
+        let disconnected = self.channel.is_disconnected();
+        match self.channel.pop() {
+            Some(value) => {
+                self.channel.wake_senders();
+                Ok(value)
+            }
+            None if disconnected => Err(RecvError::Disconnected),
+            None => Err(RecvError::NotReady),
+        }
+    }
+}
+
+impl<T> Drop for SyncReceiver<T> {
+    /// Parked senders get their message back with a `SendError`.
+    fn drop(&mut self) {
+        self.channel.receiver_dropped.store(true, Release);
+        self.channel.wake_senders();
+    }
+}
+
+#[cfg(all(test, not(loom)))]
 mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
     use std::thread;
-    use super::Channel;
+    use std::time::Duration;
+    use super::{Channel, RecvError, RecvTimeoutError, SendError, TrySendError, sync_channel};
 
 
     #[test]
@@ -91,4 +514,361 @@ mod tests {
             assert_eq!(received, "Hello, world!");
         });
     }
+
+    #[test]
+    fn test_receive_blocking_after_send() {
+        let mut channel = Channel::new();
+        let (sender, receiver) = channel.split();
+        sender.send(42);
+        assert_eq!(receiver.receive_blocking(), Ok(42));
+    }
+
+    #[test]
+    fn test_receive_blocking_before_send() {
+        let mut channel = Channel::new();
+
+        thread::scope(|s| {
+            let (sender, receiver) = channel.split();
+
+            s.spawn(move || {
+                thread::sleep(Duration::from_millis(50));
+                sender.send("Hello, world!");
+            });
+
+            assert_eq!(receiver.receive_blocking(), Ok("Hello, world!"));
+        });
+    }
+
+    #[test]
+    fn test_recv_blocking_without_manual_parking() {
+        let mut channel = Channel::new();
+
+        thread::scope(|s| {
+            let (sender, receiver) = channel.split();
+
+            s.spawn(move || {
+                thread::sleep(Duration::from_millis(50));
+                sender.send("Hello, world!");
+            });
+
+            assert_eq!(receiver.recv_blocking(), "Hello, world!");
+        });
+
+        // Already sent, read on the fast path
+        let (sender, receiver) = channel.split();
+        sender.send("again");
+        assert_eq!(receiver.recv_blocking(), "again");
+    }
+
+    #[test]
+    #[should_panic(expected = "the sender was dropped without sending")]
+    fn test_recv_blocking_panics_on_disconnect() {
+        let mut channel = Channel::<u32>::new();
+        let (sender, receiver) = channel.split();
+        drop(sender);
+        receiver.recv_blocking();
+    }
+
+    #[test]
+    fn test_recv_timeout_before_send() {
+        let mut channel = Channel::new();
+
+        thread::scope(|s| {
+            let (sender, receiver) = channel.split();
+
+            s.spawn(move || {
+                thread::sleep(Duration::from_millis(50));
+                sender.send(7);
+            });
+
+            assert_eq!(receiver.recv_timeout(Duration::from_secs(10)).ok(), Some(7));
+        });
+    }
+
+    struct Counted<'a>(&'a AtomicUsize);
+
+    impl Drop for Counted<'_> {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Relaxed);
+        }
+    }
+
+    #[test]
+    fn test_recv_timeout_expiry_keeps_the_message() {
+        let drops = AtomicUsize::new(0);
+        let mut channel = Channel::new();
+        let (sender, receiver) = channel.split();
+
+        // Nothing sent yet, the receiver comes back
+        let receiver = match receiver.recv_timeout(Duration::from_millis(20)) {
+            Err(RecvTimeoutError::Timeout(receiver)) => receiver,
+            _ => panic!("expected a timeout"),
+        };
+        assert!(!receiver.is_ready());
+
+        // The retry gets the message, dropped exactly once
+        sender.send(Counted(&drops));
+        let message = receiver.recv_timeout(Duration::from_millis(20)).ok().unwrap();
+        assert_eq!(drops.load(Relaxed), 0);
+        drop(message);
+        drop(channel);
+        assert_eq!(drops.load(Relaxed), 1);
+    }
+
+    #[test]
+    fn test_unreceived_message_is_dropped_with_the_channel() {
+        let drops = AtomicUsize::new(0);
+        let mut channel = Channel::new();
+        let (sender, receiver) = channel.split();
+        sender.send(Counted(&drops));
+        // A message already sent is received even with no time left
+        drop(receiver.recv_timeout(Duration::ZERO).ok().unwrap());
+        assert_eq!(drops.load(Relaxed), 1);
+
+        let (sender, receiver) = channel.split();
+        sender.send(Counted(&drops));
+        drop(receiver);
+        drop(channel);
+        assert_eq!(drops.load(Relaxed), 2);
+    }
+
+    #[test]
+    fn test_dropped_sender_disconnects_the_receiver() {
+        let mut channel = Channel::<u32>::new();
+
+        // Dropped before the receive
+        let (sender, mut receiver) = channel.split();
+        assert_eq!(receiver.try_receive(), Ok(None));
+        drop(sender);
+        assert!(receiver.is_disconnected());
+        assert_eq!(receiver.try_receive(), Err(RecvError::Disconnected));
+        assert_eq!(receiver.receive_blocking(), Err(RecvError::Disconnected));
+
+        // Dropped while the receiver is parked
+        thread::scope(|s| {
+            let (sender, receiver) = channel.split();
+            s.spawn(move || {
+                thread::sleep(Duration::from_millis(50));
+                drop(sender);
+            });
+            assert_eq!(receiver.receive_blocking(), Err(RecvError::Disconnected));
+        });
+
+        let (sender, receiver) = channel.split();
+        drop(sender);
+        assert!(matches!(receiver.recv_timeout(Duration::from_secs(10)), Err(RecvTimeoutError::Disconnected)));
+    }
+
+    #[test]
+    fn test_sent_message_is_not_a_disconnect() {
+        let mut channel = Channel::new();
+        let (sender, mut receiver) = channel.split();
+        sender.send(3);
+        assert!(!receiver.is_disconnected());
+        assert_eq!(receiver.try_receive(), Ok(Some(3)));
+        // Taken, but the sender did send
+        assert_eq!(receiver.try_receive(), Ok(None));
+    }
+
+    #[test]
+    fn test_is_closed_once_the_sender_is_dropped_without_sending() {
+        let mut channel = Channel::<u32>::new();
+        let (sender, receiver) = channel.split();
+        assert!(!receiver.is_closed());
+        drop(sender);
+        assert!(receiver.is_closed());
+        assert_eq!(receiver.recv(), Err(RecvError::Disconnected));
+    }
+
+    #[test]
+    fn test_is_not_closed_when_the_sender_sends_then_drops() {
+        let drops = AtomicUsize::new(0);
+        let mut channel = Channel::new();
+        thread::scope(|s| {
+            let (sender, receiver) = channel.split();
+            // `send` consumes the sender, its drop at the end of the thread is the one after sending
+            let drops = &drops;
+            s.spawn(move || sender.send(Counted(drops)));
+            let message = receiver.receive_blocking().ok().unwrap();
+            assert_eq!(drops.load(Relaxed), 0);
+            drop(message);
+        });
+        assert_eq!(drops.load(Relaxed), 1);
+
+        let (sender, receiver) = channel.split();
+        sender.send(Counted(&drops));
+        assert!(!receiver.is_closed());
+        // The unreceived message is dropped once, with the channel
+        drop(channel);
+        assert_eq!(drops.load(Relaxed), 2);
+    }
+
+    #[test]
+    fn test_is_not_closed_on_a_normal_receive() {
+        let mut channel = Channel::new();
+        let (sender, mut receiver) = channel.split();
+        assert!(!receiver.is_closed());
+        sender.send(5);
+        assert!(!receiver.is_closed());
+        assert_eq!(receiver.try_receive(), Ok(Some(5)));
+        assert!(!receiver.is_closed());
+    }
+
+    #[test]
+    fn test_try_receive_before_and_after_send() {
+        let mut channel = Channel::new();
+
+        thread::scope(|s| {
+            let (sender, mut receiver) = channel.split();
+            // Not sent yet, the receiver is kept for the retry
+            assert_eq!(receiver.try_receive(), Ok(None));
+
+            s.spawn(move || sender.send("Hello, world!"));
+
+            let received = loop {
+                if let Some(message) = receiver.try_receive().unwrap() {
+                    break message;
+                }
+                thread::yield_now();
+            };
+            assert_eq!(received, "Hello, world!");
+        });
+    }
+
+    #[test]
+    fn test_recv_after_send() {
+        let mut channel = Channel::new();
+        let (sender, receiver) = channel.split();
+        sender.send("Hello, world!");
+        assert_eq!(receiver.recv(), Ok("Hello, world!"));
+    }
+
+    #[test]
+    fn test_recv_before_send_is_not_ready() {
+        let drops = AtomicUsize::new(0);
+        let mut channel = Channel::new();
+        let (sender, receiver) = channel.split();
+        assert_eq!(receiver.recv().err(), Some(RecvError::NotReady));
+
+        // Sent after the receiver gave up, dropped with the channel
+        sender.send(Counted(&drops));
+        drop(channel);
+        assert_eq!(drops.load(Relaxed), 1);
+    }
+
+    #[test]
+    fn test_recv_after_sender_dropped_is_disconnected() {
+        let mut channel = Channel::<u32>::new();
+        let (sender, receiver) = channel.split();
+        drop(sender);
+        assert_eq!(receiver.recv(), Err(RecvError::Disconnected));
+        assert_eq!(RecvError::Disconnected.to_string(), "the sender was dropped without sending");
+    }
+
+    #[test]
+    fn test_send_then_drop_receiver_drops_the_message_once() {
+        let drops = AtomicUsize::new(0);
+        let mut channel = Channel::new();
+        let (sender, receiver) = channel.split();
+        sender.send(Counted(&drops));
+        drop(receiver);
+        assert_eq!(drops.load(Relaxed), 0);
+        drop(channel);
+        assert_eq!(drops.load(Relaxed), 1);
+    }
+
+    #[test]
+    fn test_sync_channel_three_producers_lose_no_message() {
+        const PRODUCERS: usize = 3;
+        const MESSAGES: usize = 10_000;
+        // Far fewer slots than messages, the producers park on a full ring
+        let (sender, receiver) = sync_channel(4);
+
+        thread::scope(|s| {
+            for producer in 0..PRODUCERS {
+                let sender = sender.clone();
+                s.spawn(move || {
+                    for n in 0..MESSAGES {
+                        sender.send((producer, n)).unwrap();
+                    }
+                });
+            }
+            drop(sender);
+
+            let mut next = [0; PRODUCERS];
+            while let Ok((producer, n)) = receiver.recv() {
+                // Each producer's messages arrive in order, none is lost or duplicated
+                assert_eq!(n, next[producer]);
+                next[producer] += 1;
+            }
+            assert_eq!(next, [MESSAGES; PRODUCERS]);
+        });
+    }
+
+    #[test]
+    fn test_sync_channel_send_parks_while_full() {
+        let (sender, receiver) = sync_channel(2);
+        sender.send(1).unwrap();
+        sender.send(2).unwrap();
+        assert_eq!(sender.try_send(3), Err(TrySendError::Full(3)));
+
+        thread::scope(|s| {
+            let sender = &sender;
+            let blocked = s.spawn(move || sender.send(3));
+            thread::sleep(Duration::from_millis(50));
+            assert!(!blocked.is_finished());
+
+            assert_eq!(receiver.recv(), Ok(1));
+            assert_eq!(blocked.join().unwrap(), Ok(()));
+        });
+        assert_eq!(receiver.try_recv(), Ok(2));
+        assert_eq!(receiver.try_recv(), Ok(3));
+        assert_eq!(receiver.try_recv(), Err(RecvError::NotReady));
+    }
+
+    #[test]
+    fn test_sync_channel_recv_parks_until_send() {
+        let (sender, receiver) = sync_channel(1);
+        thread::scope(|s| {
+            s.spawn(move || {
+                thread::sleep(Duration::from_millis(50));
+                sender.send("Hello, world!").unwrap();
+            });
+            assert_eq!(receiver.recv(), Ok("Hello, world!"));
+            // The sender is gone once its thread is done
+            assert_eq!(receiver.recv(), Err(RecvError::Disconnected));
+        });
+    }
+
+    #[test]
+    fn test_sync_channel_disconnects() {
+        let drops = AtomicUsize::new(0);
+
+        // Messages sent before the last sender dropped are still received
+        let (sender, receiver) = sync_channel(4);
+        let other = sender.clone();
+        assert!(sender.send(Counted(&drops)).is_ok());
+        drop(sender);
+        assert!(receiver.try_recv().is_ok());
+        assert_eq!(receiver.try_recv().err(), Some(RecvError::NotReady));
+        drop(other);
+        assert_eq!(receiver.recv().err(), Some(RecvError::Disconnected));
+        assert_eq!(drops.load(Relaxed), 1);
+
+        // A dropped receiver hands the message back, the unreceived ones are dropped with the channel
+        let (sender, receiver) = sync_channel(1);
+        assert!(sender.send(Counted(&drops)).is_ok());
+        thread::scope(|s| {
+            let (sender, drops) = (&sender, &drops);
+            let blocked = s.spawn(move || sender.send(Counted(drops)).is_err());
+            thread::sleep(Duration::from_millis(50));
+            drop(receiver);
+            assert!(blocked.join().unwrap());
+        });
+        assert_eq!(drops.load(Relaxed), 2);
+        assert!(matches!(sender.send(Counted(&drops)), Err(SendError(_))));
+        assert_eq!(drops.load(Relaxed), 3);
+        drop(sender);
+        assert_eq!(drops.load(Relaxed), 4);
+    }
 }
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use chrono::{DateTime, SecondsFormat, TimeDelta, Utc};
+use indexmap::IndexMap;
+use utils::CatVisionData;
+
+/// Input column with the UTC time (RFC 3339) the descriptions of a domain were generated
+pub const DESCRIPTION_UPDATED_AT: &str = "description_updated_at";
+
+/// Descriptions of a domain as written to `domains.json`, by key
+pub type Descriptions = HashMap<&'static str, String>;
+
+/// Options of the describe command on the descriptions already in its input
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DescribeOptions {
+    /// Describe every domain, even the ones already described in the input
+    pub force: bool,
+    /// Describe again the domains described more than this many days ago, or at an unknown time
+    pub refresh_older_than: Option<u64>,
+}
+
+impl DescribeOptions {
+    /// Whether the input descriptions of `data` are kept instead of asking the LLM again
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Input row of the domain
+    /// * `now` - Time of the run, the age of the descriptions is taken from it
+    pub fn keeps(&self, data: &CatVisionData, now: DateTime<Utc>) -> bool {
+        let described = [&data.description_fr_by_gemini, &data.description_en_by_gemini]
+            .iter()
+            .all(|description| description.as_deref().is_some_and(|text| !text.trim().is_empty()));
+        if self.force || !described {
+            return false;
+        }
+
+        let Some(days) = self.refresh_older_than else {
+            return true;
+        };
+        data.description_updated_at
+            .as_deref()
+            .and_then(|updated_at| DateTime::parse_from_rfc3339(updated_at).ok())
+            .is_some_and(|updated_at| now.signed_duration_since(updated_at) < TimeDelta::days(days as i64))
+    }
+}
+
+/// Takes out of `domains_name` the domains whose input descriptions are kept, see [`DescribeOptions::keeps`]
+///
+/// # Arguments
+///
+/// * `domains_name` - Domains to describe, left with the ones to send to the LLM
+/// * `domains` - Parsed input rows by domain
+/// * `options` - Options of the run
+/// * `now` - Time of the run
+///
+/// # Returns
+/// * The kept descriptions by domain, with their timestamp when the input has one
+pub fn skip_described_domains(
+    domains_name: &mut Vec<String>,
+    domains: &IndexMap<String, CatVisionData>,
+    options: &DescribeOptions,
+    now: DateTime<Utc>,
+) -> HashMap<String, Descriptions> {
+    let mut skipped = HashMap::new();
+    domains_name.retain(|domain| match domains.get(domain).filter(|data| options.keeps(data, now)) {
+        Some(data) => {
+            let mut descriptions = HashMap::from([
+                ("description_fr", data.description_fr_by_gemini.clone().unwrap_or_default()),
+                ("description_en", data.description_en_by_gemini.clone().unwrap_or_default()),
+            ]);
+            if let Some(updated_at) = &data.description_updated_at {
+                descriptions.insert(DESCRIPTION_UPDATED_AT, updated_at.clone());
+            }
+            skipped.insert(domain.clone(), descriptions);
+            false
+        }
+        None => true,
+    });
+    skipped
+}
+
+/// Stamps the descriptions generated by the run with `now`
+pub fn stamp_descriptions(descriptions: &mut HashMap<String, Descriptions>, now: DateTime<Utc>) {
+    let updated_at = now.to_rfc3339_opts(SecondsFormat::Secs, true);
+    for descriptions in descriptions.values_mut() {
+        descriptions.insert(DESCRIPTION_UPDATED_AT, updated_at.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(fr: Option<&str>, en: Option<&str>, updated_at: Option<&str>) -> CatVisionData {
+        let mut data = CatVisionData::new(None, None, None, None, None, fr.map(str::to_string), en.map(str::to_string));
+        data.description_updated_at = updated_at.map(str::to_string);
+        data
+    }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2025-06-30T12:00:00Z").unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_keeps_only_fully_described_rows() {
+        let options = DescribeOptions::default();
+        assert!(options.keeps(&row(Some("Messagerie"), Some("Email"), None), now()));
+        assert!(!options.keeps(&row(Some("Messagerie"), None, None), now()));
+        assert!(!options.keeps(&row(Some("Messagerie"), Some("  "), None), now()));
+        assert!(!options.keeps(&row(None, None, None), now()));
+
+        let force = DescribeOptions { force: true, ..Default::default() };
+        assert!(!force.keeps(&row(Some("Messagerie"), Some("Email"), None), now()));
+    }
+
+    #[test]
+    fn test_refresh_older_than_describes_stale_rows_again() {
+        let options = DescribeOptions { force: false, refresh_older_than: Some(30) };
+        assert!(options.keeps(&row(Some("fr"), Some("en"), Some("2025-06-20T08:00:00Z")), now()));
+        assert!(!options.keeps(&row(Some("fr"), Some("en"), Some("2025-05-01T08:00:00Z")), now()));
+        // An unknown age counts as stale
+        assert!(!options.keeps(&row(Some("fr"), Some("en"), None), now()));
+        assert!(!options.keeps(&row(Some("fr"), Some("en"), Some("last week")), now()));
+    }
+
+    #[test]
+    fn test_skip_described_domains() {
+        let domains = IndexMap::from([
+            ("described.com".to_string(), row(Some("Messagerie"), Some("Email"), Some("2025-06-20T08:00:00Z"))),
+            ("half.com".to_string(), row(Some("Jeux"), None, None)),
+            ("empty.com".to_string(), row(None, None, None)),
+        ]);
+        let mut domains_name = domains.keys().cloned().collect::<Vec<_>>();
+
+        let skipped = skip_described_domains(&mut domains_name, &domains, &DescribeOptions::default(), now());
+        assert_eq!(domains_name, ["half.com", "empty.com"]);
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped["described.com"]["description_en"], "Email");
+        assert_eq!(skipped["described.com"][DESCRIPTION_UPDATED_AT], "2025-06-20T08:00:00Z");
+
+        let mut fresh = HashMap::from([("empty.com".to_string(), Descriptions::new())]);
+        stamp_descriptions(&mut fresh, now());
+        assert_eq!(fresh["empty.com"][DESCRIPTION_UPDATED_AT], "2025-06-30T12:00:00Z");
+    }
+}
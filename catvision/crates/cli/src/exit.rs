@@ -0,0 +1,188 @@
+use std::path::{Path, PathBuf};
+use utils::error::CatVisionError;
+
+/// Name of the error summary written into the run directory on a non-zero exit
+pub const ERROR_SUMMARY_FILE: &str = "error.json";
+
+/// Exit status of the CLI, for the callers to branch on without parsing the logs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitCode {
+    /// Invalid configuration file or command line arguments
+    ConfigError = 2,
+    /// Input file missing, unreadable or without the expected columns
+    InputParseError = 3,
+    /// Provider rejected the credentials
+    ProviderAuthError = 4,
+    /// Run stopped by the budget cap, outputs are written for the processed domains
+    BudgetStop = 5,
+    /// More domains than `max_failure_ratio` left without a result, outputs are written for the others
+    PartialFailure = 6,
+    /// Run cancelled with Ctrl-C, outputs are written for the processed domains
+    Cancelled = 7,
+    /// Output or statistics file that cannot be written
+    OutputError = 8,
+}
+
+impl ExitCode {
+    const ALL: [ExitCode; 7] = [
+        ExitCode::ConfigError,
+        ExitCode::InputParseError,
+        ExitCode::ProviderAuthError,
+        ExitCode::BudgetStop,
+        ExitCode::PartialFailure,
+        ExitCode::Cancelled,
+        ExitCode::OutputError,
+    ];
+
+    /// Process exit status
+    pub fn code(self) -> i32 {
+        self as i32
+    }
+
+    /// Exit code of a process exit status, None for a success or an unknown status
+    pub fn from_code(code: i32) -> Option<Self> {
+        Self::ALL.into_iter().find(|exit_code| exit_code.code() == code)
+    }
+
+    /// Machine-readable cause, the `cause` of the error summary
+    pub fn cause(self) -> &'static str {
+        match self {
+            ExitCode::ConfigError => "config_error",
+            ExitCode::InputParseError => "input_parse_error",
+            ExitCode::ProviderAuthError => "provider_auth_error",
+            ExitCode::BudgetStop => "budget_stop",
+            ExitCode::PartialFailure => "partial_failure",
+            ExitCode::Cancelled => "cancelled",
+            ExitCode::OutputError => "output_error",
+        }
+    }
+
+    /// What to do before running again, the `remediation` of the error summary
+    pub fn remediation(self) -> &'static str {
+        match self {
+            ExitCode::ConfigError => "Fix the configuration file or the command line arguments named in the message",
+            ExitCode::InputParseError => "Check the input file exists and has a domain column, or set strict_columns to false to accept missing category columns",
+            ExitCode::ProviderAuthError => "Check the MY_GEMINI_API_KEY environment variable or the api_key file of the configuration directory",
+            ExitCode::BudgetStop => "Raise max_cost_eur, or run the domains of budget_checkpoint.txt with a new budget",
+            ExitCode::PartialFailure => "Run the domains of garbage.csv again, or raise max_failure_ratio",
+            ExitCode::Cancelled => "Run again, the outputs only cover the domains processed before the cancellation",
+            ExitCode::OutputError => "Check the output directory exists, is writable and has free space",
+        }
+    }
+}
+
+/// Error ending a run with a non-zero exit status
+#[derive(Debug)]
+pub struct RunError {
+    pub code: ExitCode,
+    pub message: String,
+}
+
+impl RunError {
+    pub fn new(code: ExitCode, message: impl Into<String>) -> Self {
+        RunError { code, message: message.into() }
+    }
+}
+
+impl RunError {
+    /// Error ending the run on `error`, with the exit code of its variant
+    ///
+    /// # Arguments
+    ///
+    /// * `error` - Error returned by the catvision crates
+    /// * `fallback` - Exit code of the file and downcast errors, whose side (input or output) only the caller knows
+    /// * `context` - What the run was doing, the start of the message
+    pub fn from_error(error: CatVisionError, fallback: ExitCode, context: &str) -> Self {
+        let code = match &error {
+            CatVisionError::Config(_) => ExitCode::ConfigError,
+            CatVisionError::Input(_) | CatVisionError::Csv(_) => ExitCode::InputParseError,
+            CatVisionError::Output(_) => ExitCode::OutputError,
+            e if e.is_auth() => ExitCode::ProviderAuthError,
+            CatVisionError::Io(_) | CatVisionError::Downcast(_) | CatVisionError::LlmRequest { .. } | CatVisionError::LlmParse(_) => fallback,
+        };
+        RunError::new(code, format!("{}: {}", context, error))
+    }
+}
+
+impl std::fmt::Display for RunError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({}): {}", self.code.cause(), self.code.code(), self.message)
+    }
+}
+
+impl std::error::Error for RunError {}
+
+/// Writes the error summary of a failed run into `run_dir`
+///
+/// # Arguments
+///
+/// * `run_dir` - Output directory of the run
+/// * `error` - Error ending the run
+///
+/// # Returns
+/// * The path of the error summary
+///
+/// # Errors
+///
+/// Returns an error if the run directory cannot be created or the file cannot be written
+pub fn write_error_summary(run_dir: &Path, error: &RunError) -> std::io::Result<PathBuf> {
+    let summary = serde_json::json!({
+        "exit_code": error.code.code(),
+        "cause": error.code.cause(),
+        "message": error.message,
+        "remediation": error.code.remediation(),
+    });
+
+    std::fs::create_dir_all(run_dir)?;
+    let path = run_dir.join(ERROR_SUMMARY_FILE);
+    std::fs::write(&path, format!("{:#}\n", summary))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes_round_trip() {
+        for exit_code in ExitCode::ALL {
+            assert_eq!(ExitCode::from_code(exit_code.code()), Some(exit_code));
+        }
+        assert_eq!(ExitCode::from_code(0), None);
+        assert_eq!(ExitCode::from_code(101), None);
+        assert_eq!(ExitCode::Cancelled.code(), llm::core::CANCELLED_EXIT_CODE);
+    }
+
+    #[test]
+    fn test_from_error_maps_variants_to_exit_codes() {
+        let code = |error: CatVisionError| RunError::from_error(error, ExitCode::InputParseError, "Run failed").code;
+        let missing = std::io::Error::new(std::io::ErrorKind::NotFound, "No such file or directory");
+        assert_eq!(code(CatVisionError::Io(missing)), ExitCode::InputParseError);
+        assert_eq!(code(CatVisionError::Config("Invalid CSV output delimiter".to_string())), ExitCode::ConfigError);
+        assert_eq!(code(CatVisionError::Output("No domain to write".to_string())), ExitCode::OutputError);
+        assert_eq!(code(CatVisionError::LlmRequest { status: Some(401), body: String::new() }), ExitCode::ProviderAuthError);
+        assert_eq!(code(CatVisionError::LlmRequest { status: Some(429), body: String::new() }), ExitCode::InputParseError);
+
+        let error = RunError::from_error(CatVisionError::Input("Required header 'domain' not found".to_string()), ExitCode::ConfigError, "Failed to parse input.csv");
+        assert_eq!(error.code, ExitCode::InputParseError);
+        assert_eq!(error.message, "Failed to parse input.csv: Invalid input: Required header 'domain' not found");
+    }
+
+    #[test]
+    fn test_write_error_summary() {
+        let dir = std::env::temp_dir().join(format!("catvision-error-summary-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let error = RunError::new(ExitCode::PartialFailure, "3 of 4 domains without a result");
+        let path = write_error_summary(&dir.join("outputs"), &error).unwrap();
+        assert_eq!(path, dir.join("outputs").join(ERROR_SUMMARY_FILE));
+
+        let summary: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(summary["exit_code"], 6);
+        assert_eq!(summary["cause"], "partial_failure");
+        assert_eq!(summary["message"], "3 of 4 domains without a result");
+        assert_eq!(summary["remediation"], ExitCode::PartialFailure.remediation());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -1,15 +1,41 @@
-use futures::io;
 use indexmap::IndexMap;
 
-use statistics::Statistics;
+use statistics::{BudgetStopSummary, LocalCacheSummary, RetryPassSummary, Statistics};
 use clap::Parser;
-use std::{path::PathBuf};
-use std::collections::HashMap;
-use llm::core::{sync_llm_runtime};
+use std::path::{Path, PathBuf};
+use std::collections::{HashMap, HashSet};
+use llm::core::{sync_llm_reparse, sync_llm_runtime};
 use llm::core::LLMCommand;
-use utils::seconds_to_pretty;
+use llm::core::responses::{ResponseStore, RESPONSE_STORE_DIR};
+use llm::core::result::LLMRunResult;
+use llm::providers::heuristic::{heuristic_runtime, is_heuristic_model};
+use llm::telemetry;
+use llm::core::metrics::{expand_processing_hints, slowest_chunks_report, write_processing_hints};
+use llm::core::prompt::check_extra_instructions;
+use llm::core::estimate::estimate_run;
+use llm::error::LlmError;
+use utils::duration_to_pretty;
 use utils::CatVisionData;
-use core::Ctx;
+use utils::error::CatVisionError;
+use core::{run_output_dir, Ctx, InputFormat};
+use core::dictionary::write_conflicts_report;
+use core::olfeo_mapping::OlfeoMapping;
+use core::watch;
+use config::{Config, ProviderKind};
+use format::csv::delimiter_from_char;
+use format::html::RunComparison;
+use format::schema::{schema, SCHEMA_VERSION};
+
+mod exit;
+use exit::{write_error_summary, ExitCode, RunError, ERROR_SUMMARY_FILE};
+mod reparse;
+use reparse::{write_requery_domains, RunManifest};
+mod notify;
+use notify::{notify_run, run_event, run_summary, RunReport};
+mod rerun;
+use rerun::{DomainFilter, MergeTarget, RerunOptions};
+mod describe;
+use describe::{skip_described_domains, stamp_descriptions, DescribeOptions, DESCRIPTION_UPDATED_AT};
 
 /// Aggregates original data with LLM results into a single IndexMap
 ///
@@ -19,6 +45,7 @@ use core::Ctx;
 /// * `llm_data` - Reference to the LLM results map
 /// * `stats` - Mutable reference to statistics object for updating stats
 /// * `nb_propositions` - Number of LLM category propositions to consider
+/// * `olfeo_mapping` - Mapping of Olfeo categories to the LLM taxonomy, for the Olfeo agreement statistics
 ///
 /// # Returns
 /// * An IndexMap containing the aggregated data
@@ -28,6 +55,7 @@ fn aggregate_data(
     llm_data: HashMap<String, Vec<&'static str>>,
     stats: &mut Statistics,
     nb_propositions: usize,
+    olfeo_mapping: &OlfeoMapping,
 ) -> indexmap::IndexMap<String, CatVisionData> {
     let mut aggregated = indexmap::IndexMap::new();
 
@@ -44,6 +72,8 @@ fn aggregate_data(
             original_categories.description_fr_by_gemini,
             original_categories.description_en_by_gemini,
         );
+        tmp_categories.url = original_categories.url;
+        tmp_categories.host = original_categories.host;
 
         if let Some(categories) = llm_data.get(domain.as_str()) {
             // Process LLM categories
@@ -68,6 +98,10 @@ fn aggregate_data(
                 llm_categories.push(cat_to_push);
             }
 
+            if let Some(olfeo_category) = &original_categories.category_olfeo {
+                stats.record_olfeo_agreement(olfeo_mapping.map_category(olfeo_category), &llm_categories);
+            }
+
             tmp_categories.categories_llm = if llm_categories.is_empty() { None } else { Some(llm_categories) };
         }
 
@@ -81,141 +115,762 @@ fn aggregate_data(
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Input file path
+    /// Input file path, required except for the watch and reparse commands
     #[arg(short, long)]
-    input: String,
+    input: Option<String>,
+    /// Input file format (csv, txt or json), selected from the input file extension when omitted
+    #[arg(long)]
+    input_format: Option<InputFormat>,
     #[arg(long)]
     config: Option<String>,
+    /// Dictionary file or directory of dictionary files, can be repeated (later files override earlier ones)
+    #[arg(long)]
+    dict: Vec<String>,
+    /// Optional path of a CSV report listing dictionary conflicts
+    #[arg(long)]
+    dict_conflicts: Option<String>,
+    /// Optional path of a CSV file with per-domain processing hints (describe only)
+    #[arg(long)]
+    processing_hints: Option<String>,
+    /// Number of slowest chunks listed in the describe run summary
+    #[arg(long, default_value_t = 5)]
+    slowest_chunks: usize,
+    /// Cost cap of the run in euros, overrides `max_cost_eur` from the configuration
+    #[arg(long)]
+    max_cost_eur: Option<f64>,
+    /// Extra instructions appended to the prompt, the path of a file or the text itself (overrides `extra_instructions` from the configuration)
+    #[arg(long)]
+    extra_instructions: Option<String>,
+    /// Output directory of the runs to parse again, required by the reparse command
     #[arg(long)]
-    dict: Option<String>,
+    run_dir: Option<String>,
+    /// Output schema version listed by the schema command, the current one when omitted
+    #[arg(long)]
+    schema_version: Option<u32>,
+    /// Skip the domains already classified in the CSV output of a previous classify run on the same input
+    #[arg(long)]
+    resume: bool,
+    /// Configuration override `key=value`, applied after the configuration lookup, can be repeated (`--set use_gemini_google_search=true`)
+    #[arg(long = "set")]
+    overrides: Vec<String>,
+    /// File listing the only domains of the input to process, one per line
+    #[arg(long)]
+    only_domains: Option<String>,
+    /// CSV output of a previous classify run whose rows of the processed domains are replaced by the fresh ones
+    #[arg(long)]
+    merge_into: Option<String>,
+    /// No progress report during the LLM requests, same as `--set progress=quiet`
+    #[arg(long)]
+    quiet: bool,
+    /// Describe every domain, also the ones whose input row already has both descriptions (describe only)
+    #[arg(long)]
+    force_describe: bool,
+    /// Describe again the domains whose `description_updated_at` is older than this many days (describe only)
+    #[arg(long)]
+    refresh_older_than: Option<u64>,
+    /// Estimate the tokens and the cost of the LLM requests of classify or describe without sending any,
+    /// as a table (`text`) or as JSON on the last line of the output (`json`)
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "text")]
+    dry_run: Option<DryRunFormat>,
+    /// Chunk sizes compared by --dry-run, the configured chunk_size when omitted (`--chunk-sizes 50,200`)
+    #[arg(long, value_delimiter = ',')]
+    chunk_sizes: Vec<usize>,
     #[arg(long)]
     command: String,
 }
 
+/// Output of the estimate of `--dry-run`
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum DryRunFormat {
+    Text,
+    Json,
+}
+
+/// Writes the dictionary conflicts report if a path was requested
+fn report_dictionary_conflicts(ctx: &Ctx, dict_conflicts: Option<&PathBuf>) {
+    if let Some(path) = dict_conflicts {
+        match write_conflicts_report(ctx.dict_conflicts(), path) {
+            Ok(()) => println!("{} dictionary conflicts written to {}", ctx.dict_conflicts().len(), path.display()),
+            Err(e) => eprintln!("Failed to write dictionary conflicts to {}: {}", path.display(), e),
+        }
+    }
+}
+
+/// Where the LLM results of a run come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LlmSource {
+    /// Requests to the provider, every response is stored in the run directory
+    Provider,
+    /// Responses stored by a previous run, parsed again without any request
+    StoredResponses,
+}
+
+/// Runs the LLM on `domains` under a root span, exported over OTLP when built with the `otel` feature
+///
+/// # Arguments
+///
+/// * `input_file` - Input file recorded on the run span, its run directory holds the stored responses
+/// * `domains` - Domain names to process
+/// * `config` - Configuration, also providing the OTLP endpoint
+/// * `command` - LLM command to run
+/// * `source` - Provider or responses stored by a previous run
+/// * `dictionary` - Dictionary of the run, only used by the heuristic provider
+fn traced_llm_runtime(
+    input_file: &PathBuf,
+    domains: Vec<String>,
+    config: &Config,
+    command: LLMCommand,
+    source: LlmSource,
+    dictionary: Option<&HashMap<String, String>>,
+) -> Result<LLMRunResult, CatVisionError> {
+    #[cfg(feature = "otel")]
+    let _telemetry = match telemetry::init_telemetry(config.otel_endpoint.as_deref()) {
+        Ok(guard) => guard,
+        Err(e) => {
+            eprintln!("Failed to initialize OpenTelemetry, traces are disabled: {}", e);
+            None
+        }
+    };
+
+    let _run = telemetry::run_span(&input_file.display().to_string(), &config.model[0], config.chunk_size).entered();
+    let store = ResponseStore::new(&run_output_dir(input_file).join(RESPONSE_STORE_DIR));
+    // The heuristic provider stores no response, a reparse runs it again from the manifest of the run
+    if is_heuristic_model(&config.model[0]) {
+        std::fs::create_dir_all(store.dir())?;
+        return Ok(heuristic_runtime(domains, config, &command, dictionary));
+    }
+    match source {
+        LlmSource::Provider => sync_llm_runtime(domains, config, command, &store),
+        LlmSource::StoredResponses => sync_llm_reparse(domains, config, command, &store),
+    }
+}
+
+/// Writes the domains a reparse left without a stored response, for a run on them alone
+fn report_requery_domains(input_file: &Path, llm_results: &LLMRunResult, source: LlmSource) {
+    if source != LlmSource::StoredResponses {
+        return;
+    }
+    let run_dir = run_output_dir(input_file);
+    match write_requery_domains(&run_dir, &llm_results.requery_domains) {
+        Ok(Some(path)) => println!("{} domains without a stored response written to {}", llm_results.requery_domains.len(), path.display()),
+        Ok(None) => {}
+        Err(e) => eprintln!("Failed to write the domains to query again to {}: {}", run_dir.display(), e),
+    }
+}
+
+/// Flags the budget stop of a run in its statistics
+fn record_budget_stop(stats: &mut Statistics, llm_results: &LLMRunResult) {
+    stats.budget_stop = llm_results.budget_stop.as_ref().map(|stop| BudgetStopSummary {
+        max_cost_eur: stop.max_cost_eur,
+        remaining_domains: stop.remaining_domains.len(),
+    });
+}
+
+/// Adds the retry pass of the failed domains to the statistics, the batches of a run each have theirs
+fn record_retry_pass(stats: &mut Statistics, llm_results: &LLMRunResult) {
+    if let Some(retry) = &llm_results.retry_pass {
+        let summary = stats.retry_pass.get_or_insert_with(RetryPassSummary::default);
+        summary.retried += retry.retried;
+        summary.processed += retry.processed;
+    }
+}
+
+/// Copies the cost and the number of domains processed by each model of the run to the statistics
+fn record_models(stats: &mut Statistics, llm_results: &LLMRunResult) {
+    for (model, cost) in &llm_results.cost_per_model {
+        *stats.cost_per_model.entry(model.clone()).or_insert(0.0) += cost;
+    }
+    for (model, processed) in &llm_results.processed_per_model {
+        *stats.processed_per_model.entry(model.clone()).or_insert(0) += processed;
+    }
+}
+
+/// Adds the time the requests of the run waited for the rate limiter to the statistics
+fn record_throttling(stats: &mut Statistics, llm_results: &LLMRunResult) {
+    stats.throttled_time += llm_results.throttled;
+}
+
+/// Adds the local cache lookups of the run to the statistics, the batches of a run each have theirs
+fn record_local_cache(stats: &mut Statistics, llm_results: &LLMRunResult) {
+    if llm_results.cache_hits + llm_results.cache_misses > 0 {
+        let summary = stats.local_cache.get_or_insert_with(LocalCacheSummary::default);
+        summary.hits += llm_results.cache_hits;
+        summary.misses += llm_results.cache_misses;
+    }
+}
+
+/// Copies the categories auto-corrected and the labels outside of the taxonomy of the run to the statistics
+fn record_category_corrections(stats: &mut Statistics, llm_results: &LLMRunResult) {
+    for ((original, corrected), count) in &llm_results.category_corrections {
+        *stats.category_corrections.entry((original.clone(), corrected.to_string())).or_insert(0) += count;
+    }
+    for (label, count) in &llm_results.invalid_categories {
+        stats.invalid_category_count += count;
+        *stats.rejected_categories.entry(label.clone()).or_insert(0) += count;
+    }
+}
+
+/// Copies the number of LLM outputs parsed by a fallback during the run to the statistics
+fn record_parse_fallbacks(stats: &mut Statistics, llm_results: &LLMRunResult) {
+    for (fallback, count) in &llm_results.parse_fallbacks {
+        *stats.parse_fallbacks.entry(fallback.to_string()).or_insert(0) += count;
+    }
+}
+
+/// Reads the `--extra-instructions` argument: the content of the file it names, or the argument itself
+fn read_extra_instructions(arg: &str) -> Result<String, RunError> {
+    let path = Path::new(arg);
+    if !path.is_file() {
+        return Ok(arg.to_string());
+    }
+    std::fs::read_to_string(path)
+        .map_err(|e| RunError::new(ExitCode::ConfigError, format!("Cannot read extra instructions from {}: {}", path.display(), e)))
+}
+
+/// Sets the extra instructions of the run, overriding the configuration, and shows them in the report footer
+///
+/// # Arguments
+///
+/// * `ctx` - Context of the run
+/// * `extra_instructions` - Instructions of the command line, the ones of the configuration are kept when `None`
+fn apply_extra_instructions(ctx: &mut Ctx, extra_instructions: Option<String>) -> Result<(), RunError> {
+    if extra_instructions.is_some() {
+        ctx.config.extra_instructions = extra_instructions;
+    }
+    let Some(text) = &ctx.config.extra_instructions else {
+        return Ok(());
+    };
+
+    let sanitized = check_extra_instructions(text).map_err(|e| RunError::new(ExitCode::ConfigError, e))?;
+    println!("Extra instructions appended to the prompt:\n{}", sanitized);
+    ctx.prompt = format!("Extra instructions:\n{}", sanitized);
+    ctx.config.extra_instructions = Some(sanitized);
+    Ok(())
+}
+
+/// Fails before the first LLM request when no API key is set, instead of failing every chunk.
+/// The heuristic provider runs offline and needs none, the key of an OpenAI-compatible server is optional.
+fn check_api_key(config: &Config) -> Result<(), RunError> {
+    if config.provider == ProviderKind::OpenAi || utils::env::has_api_key() || is_heuristic_model(&config.model[0]) {
+        return Ok(());
+    }
+    Err(RunError::new(
+        ExitCode::ProviderAuthError,
+        "No Gemini API key, set MY_GEMINI_API_KEY or write the key to $XDG_CONFIG_HOME/catvision/api_key",
+    ))
+}
+
+/// Maps the outcome of the LLM run to its exit code, once its outputs are written
+///
+/// A cancellation comes first, then the budget stop, then an authentication error and last the failure ratio.
+///
+/// # Arguments
+///
+/// * `llm_results` - Results of the LLM run
+/// * `total_domains` - Number of domains of the input
+/// * `answered_domains` - Number of domains with a result
+/// * `max_failure_ratio` - Share of the domains without a result above which the run fails
+fn check_run_outcome(
+    llm_results: &LLMRunResult,
+    total_domains: usize,
+    answered_domains: usize,
+    max_failure_ratio: f64,
+) -> Result<(), RunError> {
+    if llm_results.cancelled {
+        return Err(RunError::new(
+            ExitCode::Cancelled,
+            format!("Run cancelled, {} of {} domains processed", answered_domains, total_domains),
+        ));
+    }
+
+    if let Some(stop) = &llm_results.budget_stop {
+        if let Some(checkpoint) = &stop.checkpoint {
+            eprintln!("Remaining domains written to {}", checkpoint.display());
+        }
+        return Err(RunError::new(
+            ExitCode::BudgetStop,
+            format!("Run stopped by the budget cap of {}€: {}€ spent, {} domains remaining",
+                stop.max_cost_eur,
+                llm_results.cost.load(std::sync::atomic::Ordering::Relaxed),
+                stop.remaining_domains.len()
+            ),
+        ));
+    }
+
+    if let Some(e) = llm_results.chunk_errors.iter().find(|e| matches!(e, LlmError::Auth(_))) {
+        return Err(RunError::from_error(e.clone().into(), ExitCode::ProviderAuthError, "Provider rejected the credentials"));
+    }
+
+    let failed = total_domains.saturating_sub(answered_domains);
+    let failure_ratio = if total_domains == 0 { 0.0 } else { failed as f64 / total_domains as f64 };
+    if failure_ratio > max_failure_ratio {
+        return Err(RunError::new(
+            ExitCode::PartialFailure,
+            format!("{} of {} domains left without a result ({:.1}%), over the max_failure_ratio of {:.1}%",
+                failed,
+                total_domains,
+                failure_ratio * 100.0,
+                max_failure_ratio * 100.0
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Creates the context of a run with the `--set` overrides applied, configuration errors map to `ExitCode::ConfigError`
+fn create_ctx(
+    input_file: &Path,
+    config_path: Option<PathBuf>,
+    dict: Vec<PathBuf>,
+    input_format: Option<InputFormat>,
+    overrides: &[String],
+) -> Result<Ctx, RunError> {
+    let mut config = Config::new(config_path).map_err(|e| RunError::from_error(e, ExitCode::ConfigError, "Cannot load the configuration"))?;
+    config.apply_overrides(overrides).map_err(|e| RunError::new(ExitCode::ConfigError, e.to_string()))?;
+    Ctx::with_config(input_file, config, dict, input_format)
+        .map_err(|e| RunError::from_error(e, ExitCode::ConfigError, "Cannot set up the run"))
+}
+
+/// Reads the output to merge into before the run, which may write its own output to the same file
+fn read_merge_target(ctx: &Ctx, rerun: &RerunOptions) -> Result<Option<MergeTarget>, RunError> {
+    let Some(path) = &rerun.merge_into else {
+        return Ok(None);
+    };
+    if ctx.csv_output().is_none() {
+        return Err(RunError::new(ExitCode::ConfigError, "--merge-into needs the CSV output, enable support_csv.output"));
+    }
+    MergeTarget::read(path).map(Some)
+}
+
+/// First propositions of the output to merge into, compared with the results of the run in the HTML output.
+/// `None` without output to merge into or without HTML output.
+fn read_previous_run(ctx: &Ctx, merge_target: Option<&MergeTarget>) -> Result<Option<HashMap<String, Option<String>>>, RunError> {
+    let Some(target) = merge_target.filter(|_| ctx.config.support_html.output) else {
+        return Ok(None);
+    };
+    let delimiter = delimiter_from_char(ctx.config.support_csv.delimiter)
+        .map_err(|e| RunError::from_error(e, ExitCode::ConfigError, "Invalid CSV output delimiter"))?;
+    target.first_propositions(delimiter).map(Some)
+}
+
+/// Merges the rows of the `answered` domains of the CSV output of the run into `merge_target`
+fn merge_results(ctx: &Ctx, merge_target: Option<MergeTarget>, answered: &HashSet<String>, report: &mut RunReport) -> Result<(), RunError> {
+    let (Some(target), Some(csv_output)) = (merge_target, ctx.csv_output()) else {
+        return Ok(());
+    };
+    let delimiter = delimiter_from_char(ctx.config.support_csv.delimiter)
+        .map_err(|e| RunError::from_error(e, ExitCode::ConfigError, "Invalid CSV output delimiter"))?;
+    let path = target.path().to_path_buf();
+    target.merge(csv_output, delimiter, answered)?;
+    report.outputs.push(path);
+    Ok(())
+}
+
+/// Domains classified in the CSV output of a previous run of `ctx`, empty unless `resume` is set
+fn load_classified_domains(ctx: &Ctx, resume: bool) -> Result<IndexMap<String, Vec<&'static str>>, RunError> {
+    if !resume {
+        return Ok(IndexMap::new());
+    }
+    let classified = ctx
+        .classified_domains()
+        .map_err(|e| RunError::from_error(e, ExitCode::InputParseError, "Cannot resume from the previous output"))?;
+    println!("Resuming the previous run, {} domains already classified are skipped", classified.len());
+    Ok(classified)
+}
+
+/// Leaves out of `domains_name` the domains classified by a previous run
+///
+/// # Returns
+/// * The categories of the domains left out, merged into the LLM results before the aggregation
+fn skip_classified_domains(
+    domains_name: &mut Vec<String>,
+    classified: &IndexMap<String, Vec<&'static str>>,
+) -> HashMap<String, Vec<&'static str>> {
+    let mut skipped = HashMap::new();
+    domains_name.retain(|domain| match classified.get(domain) {
+        Some(categories) => {
+            skipped.insert(domain.clone(), categories.clone());
+            false
+        }
+        None => true,
+    });
+    skipped
+}
+
+/// Parses the input file of `ctx`, parse errors map to `ExitCode::InputParseError`
+fn parse_input(ctx: &mut Ctx, input_file: &Path) -> Result<Box<IndexMap<String, CatVisionData>>, RunError> {
+    ctx.parse()
+        .map_err(|e| RunError::from_error(e, ExitCode::InputParseError, &format!("Failed to parse input file {}", input_file.display())))?
+        .downcast::<IndexMap<String, CatVisionData>>()
+        .map_err(|_| RunError::new(ExitCode::InputParseError, format!("Unexpected data parsed from input file {}", input_file.display())))
+}
+
 fn process_classification(
     input_file: PathBuf,
     config_path: Option<PathBuf>,
-    dict: Option<PathBuf>)
-     -> io::Result<()> 
+    dict: Vec<PathBuf>,
+    dict_conflicts: Option<PathBuf>,
+    max_cost_eur: Option<f64>,
+    extra_instructions: Option<String>,
+    input_format: Option<InputFormat>,
+    source: LlmSource,
+    resume: bool,
+    rerun: &RerunOptions,
+    report: &mut RunReport)
+     -> Result<(), RunError> 
      {
 
     // Initialize context wihth input file and optional config and dictionary
-    let mut ctx = Ctx::new(&input_file, config_path, dict);
+    let mut ctx = create_ctx(&input_file, config_path, dict, input_format, &rerun.overrides)?;
+    if max_cost_eur.is_some() {
+        ctx.config.max_cost_eur = max_cost_eur;
+    }
+    apply_extra_instructions(&mut ctx, extra_instructions)?;
+    report_dictionary_conflicts(&ctx, dict_conflicts.as_ref());
+    let olfeo_mapping = ctx
+        .load_olfeo_mapping()
+        .map_err(|e| RunError::new(ExitCode::ConfigError, format!("Failed to load Olfeo mapping file: {}", e)))?;
+
+    // Read before the outputs of this run replace them
+    let classified = load_classified_domains(&ctx, resume)?;
+    let merge_target = read_merge_target(&ctx, rerun)?;
+    let only_domains = rerun.only_domains.as_deref().map(DomainFilter::read).transpose()?;
+
+    if ctx.stream_batch_size().is_some() {
+        // No HTML output in batches, nothing to compare with the previous run
+        return classify_in_batches(ctx, &input_file, &olfeo_mapping, source, &classified, only_domains, merge_target, report);
+    }
+
+    let previous_run = read_previous_run(&ctx, merge_target.as_ref())?;
 
     // Parse input data
-    let domains = ctx
-        .parse()
-        .expect("Failed to parse input CSV")
-        .downcast::<IndexMap<String, CatVisionData>>()
-        .expect("Failed to downcast parsed data to IndexMap<String, CatVisionData>>");
+    let mut domains = parse_input(&mut ctx, &input_file)?;
+    if let Some(mut filter) = only_domains {
+        filter.retain(&mut domains);
+        filter.report_missing();
+    }
+    if source == LlmSource::Provider {
+        check_api_key(&ctx.config)?;
+    }
     
     // Create domais name list from input file
-    let domains_name = domains
+    let mut domains_name = domains
         .keys()
         .map(|s| s.to_string())
         .collect::<Vec<String>>();
+    let resumed = skip_classified_domains(&mut domains_name, &classified);
 
     let start_time = std::time::Instant::now();
     // Generate prompt and call LLM based on caching configuration for Gemini
 
     // Calling Gemini LLM synchronously to get categories
-    let llm_results = match sync_llm_runtime(domains_name, &ctx.config, LLMCommand::CategorizeDomains) {
-        Ok(res) => res,
-        Err(e) => {
-            eprintln!("Error during LLM processing: {}", e);
-            return Err(io::Error::new(io::ErrorKind::Other, "LLM processing failed"));
-        }
+    // The runtime only fails before the first chunk, on a missing anonymization key
+    let total_domains = domains_name.len();
+    report.total_domains = total_domains;
+    let mut llm_results = if domains_name.is_empty() && !resumed.is_empty() {
+        // Everything was classified by the previous run
+        LLMRunResult::new()
+    } else {
+        traced_llm_runtime(&input_file, domains_name, &ctx.config, LLMCommand::CategorizeDomains, source, ctx.dict.as_ref().map(|snapshot| &snapshot.dictionary.entries))
+            .map_err(|e| RunError::from_error(e, ExitCode::ConfigError, "LLM processing failed"))?
     };
+    report_requery_domains(&input_file, &llm_results, source);
 
     // Update statistics based on Gemini results
     ctx.stats.update_llm_statistics(
-        llm_results.processed,
-        llm_results.cost,
-        llm_results.retried,
-        llm_results.failed,
+        &llm_results.processed,
+        &llm_results.cost,
+        &llm_results.retried,
+        &llm_results.failed,
         ctx.config.chunk_size,
         ctx.config.thinking_budget
     );
+    ctx.stats.anonymized = ctx.config.anonymize_domains;
+    record_budget_stop(&mut ctx.stats, &llm_results);
+    record_retry_pass(&mut ctx.stats, &llm_results);
+    record_models(&mut ctx.stats, &llm_results);
+    record_throttling(&mut ctx.stats, &llm_results);
+    record_local_cache(&mut ctx.stats, &llm_results);
+    record_category_corrections(&mut ctx.stats, &llm_results);
+    record_parse_fallbacks(&mut ctx.stats, &llm_results);
 
-    // Aggregate original data with LLM results
-    let aggregated = aggregate_data(*domains, llm_results.categories, &mut ctx.stats, ctx.config.max_domain_propositions);
+    // Aggregate original data with LLM results, and the results of the previous run when resuming
+    let answered_domains = llm_results.categories.len();
+    let answered = llm_results.categories.keys().cloned().collect::<HashSet<String>>();
+    llm_results.categories.extend(resumed);
+    let aggregated = aggregate_data(*domains, std::mem::take(&mut llm_results.categories), &mut ctx.stats, ctx.config.max_domain_propositions, &olfeo_mapping);
 
     ctx.stats.elapsed_time = start_time.elapsed();
+    report.answered_domains = answered_domains;
+    report.cost = ctx.stats.cost;
+    report.duration = ctx.stats.elapsed_time;
 
     println!("Classification of {} domains finished in {} for {}€",
         ctx.stats.processed,
-        seconds_to_pretty(ctx.stats.elapsed_time.as_secs()).unwrap(),
+        duration_to_pretty(ctx.stats.elapsed_time).unwrap(),
         ctx.stats.cost
     );
 
+    if let Some(previous_run) = previous_run {
+        ctx.set_run_comparison(RunComparison::new(&previous_run, &aggregated));
+    }
+
     // Write categories to output files (HTML, CSV, JSON...)
-    ctx.write(&aggregated).expect("Failed to write output data");
+    ctx.write(&aggregated)
+        .map_err(|e| RunError::from_error(e, ExitCode::OutputError, "Failed to write output data"))?;
+    report.outputs = ctx.output_files();
+    merge_results(&ctx, merge_target, &answered, report)?;
+    check_run_outcome(&llm_results, total_domains, answered_domains, ctx.config.max_failure_ratio)
+}
 
-    Ok(())
+/// Classifies the input batch by batch, each batch is sent to the LLM and appended to the outputs before the next one
+/// is read, so only one batch is in memory. The cost cap applies to the whole run.
+///
+/// # Arguments
+///
+/// * `ctx` - Context of the run, with `stream_batch_size` set
+/// * `input_file` - Input file of the run
+/// * `olfeo_mapping` - Mapping of Olfeo categories to the LLM taxonomy
+/// * `source` - Provider or responses stored by a previous run
+/// * `classified` - Domains classified by the previous run, skipped when resuming
+/// * `only_domains` - Domains the run is restricted to, all of them when `None`
+/// * `merge_target` - Output of a previous run receiving the rows of the classified domains
+/// * `report` - Report of the run, for the notification
+#[allow(clippy::too_many_arguments)]
+fn classify_in_batches(
+    mut ctx: Ctx,
+    input_file: &PathBuf,
+    olfeo_mapping: &OlfeoMapping,
+    source: LlmSource,
+    classified: &IndexMap<String, Vec<&'static str>>,
+    mut only_domains: Option<DomainFilter>,
+    merge_target: Option<MergeTarget>,
+    report: &mut RunReport,
+) -> Result<(), RunError> {
+    if source == LlmSource::Provider {
+        check_api_key(&ctx.config)?;
+    }
+
+    let start_time = std::time::Instant::now();
+    let max_cost_eur = ctx.config.max_cost_eur;
+    let mut total_domains = 0;
+    let mut answered_domains = 0;
+    let mut requery_domains = Vec::new();
+    let mut answered = HashSet::new();
+    let mut llm_results = LLMRunResult::new();
+
+    while let Some(batch) = ctx.parse_batch()
+        .map_err(|e| RunError::from_error(e, ExitCode::InputParseError, &format!("Failed to parse input file {}", input_file.display())))?
+    {
+        let mut domains = batch
+            .downcast::<IndexMap<String, CatVisionData>>()
+            .map_err(|_| RunError::new(ExitCode::InputParseError, format!("Unexpected data parsed from input file {}", input_file.display())))?;
+        if let Some(filter) = &mut only_domains {
+            filter.retain(&mut domains);
+        }
+        let mut domains_name = domains.keys().cloned().collect::<Vec<String>>();
+        let resumed = skip_classified_domains(&mut domains_name, classified);
+        total_domains += domains_name.len();
+
+        // What the previous batches spent is taken off the cost cap
+        let mut config = ctx.config.clone();
+        config.max_cost_eur = max_cost_eur.map(|max_cost_eur| (max_cost_eur - ctx.stats.cost).max(0.0));
+        llm_results = if domains_name.is_empty() && !resumed.is_empty() {
+            LLMRunResult::new()
+        } else {
+            traced_llm_runtime(input_file, domains_name, &config, LLMCommand::CategorizeDomains, source, ctx.dict.as_ref().map(|snapshot| &snapshot.dictionary.entries))
+                .map_err(|e| RunError::from_error(e, ExitCode::ConfigError, "LLM processing failed"))?
+        };
+        requery_domains.append(&mut llm_results.requery_domains);
+
+        ctx.stats.update_llm_statistics(
+            &llm_results.processed,
+            &llm_results.cost,
+            &llm_results.retried,
+            &llm_results.failed,
+            ctx.config.chunk_size,
+            ctx.config.thinking_budget
+        );
+        record_budget_stop(&mut ctx.stats, &llm_results);
+        record_retry_pass(&mut ctx.stats, &llm_results);
+        record_models(&mut ctx.stats, &llm_results);
+        record_throttling(&mut ctx.stats, &llm_results);
+        record_local_cache(&mut ctx.stats, &llm_results);
+        record_category_corrections(&mut ctx.stats, &llm_results);
+        record_parse_fallbacks(&mut ctx.stats, &llm_results);
+
+        answered_domains += llm_results.categories.len();
+        if merge_target.is_some() {
+            answered.extend(llm_results.categories.keys().cloned());
+        }
+        llm_results.categories.extend(resumed);
+        let aggregated = aggregate_data(*domains, std::mem::take(&mut llm_results.categories), &mut ctx.stats, ctx.config.max_domain_propositions, olfeo_mapping);
+        ctx.write_batch(&aggregated)
+            .map_err(|e| RunError::from_error(e, ExitCode::OutputError, "Failed to write output data"))?;
+
+        // The next batches are not read once the run is cancelled, stopped by the budget or its key rejected
+        if llm_results.cancelled
+            || llm_results.budget_stop.is_some()
+            || llm_results.chunk_errors.iter().any(|e| matches!(e, LlmError::Auth(_)))
+        {
+            break;
+        }
+    }
+
+    llm_results.requery_domains = requery_domains;
+    report_requery_domains(input_file, &llm_results, source);
+    if let Some(filter) = &only_domains {
+        filter.report_missing();
+    }
+    ctx.stats.anonymized = ctx.config.anonymize_domains;
+    ctx.stats.elapsed_time = start_time.elapsed();
+    report.total_domains = total_domains;
+    report.answered_domains = answered_domains;
+    report.cost = ctx.stats.cost;
+    report.duration = ctx.stats.elapsed_time;
+
+    println!("Classification of {} domains finished in {} for {}€",
+        ctx.stats.processed,
+        duration_to_pretty(ctx.stats.elapsed_time).unwrap(),
+        ctx.stats.cost
+    );
+
+    // An empty batch creates the outputs of an input without domains and writes the final statistics
+    ctx.write_batch(&IndexMap::<String, CatVisionData>::new())
+        .map_err(|e| RunError::from_error(e, ExitCode::OutputError, "Failed to write output data"))?;
+    report.outputs = ctx.output_files();
+    merge_results(&ctx, merge_target, &answered, report)?;
+    check_run_outcome(&llm_results, total_domains, answered_domains, ctx.config.max_failure_ratio)
 }
 
 fn process_description(
     input_file: PathBuf,
     config_path: Option<PathBuf>,
-    dict: Option<PathBuf>,
-) -> io::Result<()> {
+    dict: Vec<PathBuf>,
+    dict_conflicts: Option<PathBuf>,
+    processing_hints: Option<PathBuf>,
+    slowest_chunks: usize,
+    max_cost_eur: Option<f64>,
+    extra_instructions: Option<String>,
+    input_format: Option<InputFormat>,
+    source: LlmSource,
+    rerun: &RerunOptions,
+    describe: &DescribeOptions,
+    report: &mut RunReport,
+) -> Result<(), RunError> {
       // Initialize context wihth input file and optional config and dictionary
-    let mut ctx = Ctx::new(&input_file, config_path, dict);
+    let mut ctx = create_ctx(&input_file, config_path, dict, input_format, &rerun.overrides)?;
+    if max_cost_eur.is_some() {
+        ctx.config.max_cost_eur = max_cost_eur;
+    }
+    apply_extra_instructions(&mut ctx, extra_instructions)?;
+    report_dictionary_conflicts(&ctx, dict_conflicts.as_ref());
 
     // Parse input data
-    let domains = ctx
-        .parse()
-        .expect("Failed to parse input CSV")
-        .downcast::<IndexMap<String, CatVisionData>>()
-        .expect("Failed to downcast parsed data to IndexMap<String, CatVisionData>>");
+    let mut domains = parse_input(&mut ctx, &input_file)?;
+    if let Some(mut filter) = rerun.only_domains.as_deref().map(DomainFilter::read).transpose()? {
+        filter.retain(&mut domains);
+        filter.report_missing();
+    }
+    if source == LlmSource::Provider {
+        check_api_key(&ctx.config)?;
+    }
     
+    if describe.refresh_older_than.is_some() && !ctx.has_input_column(DESCRIPTION_UPDATED_AT) {
+        return Err(RunError::new(
+            ExitCode::ConfigError,
+            format!("--refresh-older-than needs a {} column in {}", DESCRIPTION_UPDATED_AT, input_file.display()),
+        ));
+    }
+
     // Create domais name list from input file
-    let domains_name = domains
+    let mut domains_name = domains
         .keys()
         .map(|s| s.to_string())
         .collect::<Vec<String>>();
+    let now = chrono::Utc::now();
+    let described = skip_described_domains(&mut domains_name, &domains, describe, now);
+    ctx.stats.already_described = described.len();
+    if !described.is_empty() {
+        println!("{} domains already described are skipped", described.len());
+    }
 
     let start_time = std::time::Instant::now();
     // Generate prompt and call LLM based on caching configuration for Gemini
-    ctx.prompt = String::new();
 
     // Calling Gemini LLM synchronously to get categories
-    let llm_results = match sync_llm_runtime(domains_name, &ctx.config, LLMCommand::DescribeDomains) {
-        Ok(res) => res,
-        Err(e) => {
-            eprintln!("Error during LLM processing: {}", e);
-            return Err(io::Error::new(io::ErrorKind::Other, "LLM processing failed"));
-        }
+    // The runtime only fails before the first chunk, on a missing anonymization key
+    let total_domains = domains_name.len();
+    report.total_domains = total_domains;
+    let mut llm_results = if domains_name.is_empty() && !described.is_empty() {
+        // Every domain was described in the input
+        LLMRunResult::new()
+    } else {
+        traced_llm_runtime(&input_file, domains_name, &ctx.config, LLMCommand::DescribeDomains, source, ctx.dict.as_ref().map(|snapshot| &snapshot.dictionary.entries))
+            .map_err(|e| RunError::from_error(e, ExitCode::ConfigError, "LLM processing failed"))?
     };
+    report_requery_domains(&input_file, &llm_results, source);
 
     // Update statistics based on Gemini results
     ctx.stats.update_llm_statistics(
-        llm_results.processed,
-        llm_results.cost,
-        llm_results.retried,
-        llm_results.failed,
+        &llm_results.processed,
+        &llm_results.cost,
+        &llm_results.retried,
+        &llm_results.failed,
         ctx.config.chunk_size,
         ctx.config.thinking_budget
     );
+    ctx.stats.anonymized = ctx.config.anonymize_domains;
+    record_budget_stop(&mut ctx.stats, &llm_results);
+    record_retry_pass(&mut ctx.stats, &llm_results);
+    record_models(&mut ctx.stats, &llm_results);
+    record_throttling(&mut ctx.stats, &llm_results);
+    record_local_cache(&mut ctx.stats, &llm_results);
+    record_parse_fallbacks(&mut ctx.stats, &llm_results);
 
 
     println!("{:?}", llm_results.descriptions);
 
     ctx.stats.elapsed_time = start_time.elapsed();
+    report.answered_domains = llm_results.descriptions.len();
+    report.cost = ctx.stats.cost;
+    report.duration = ctx.stats.elapsed_time;
 
     println!("Descriptions of {} domains finished in {} for {}€",
         ctx.stats.processed,
-        seconds_to_pretty(ctx.stats.elapsed_time.as_secs()).unwrap(),
+        duration_to_pretty(ctx.stats.elapsed_time).unwrap(),
         ctx.stats.cost
     );
 
-    let _ = write_descriptions_to_file(&llm_results.descriptions, "domains.json");
+    if slowest_chunks > 0 && !llm_results.chunk_metrics.is_empty() {
+        println!("Slowest chunks:");
+        print!("{}", slowest_chunks_report(&llm_results.chunk_metrics, slowest_chunks));
+    }
+
+    if let Some(path) = processing_hints {
+        let hints = expand_processing_hints(&llm_results.chunk_metrics);
+        match write_processing_hints(&hints, &path) {
+            Ok(()) => println!("{} processing hints written to {}", hints.len(), path.display()),
+            Err(e) => eprintln!("Failed to write processing hints to {}: {}", path.display(), e),
+        }
+    }
+
+    // The input descriptions are carried through with the fresh ones
+    let answered_domains = llm_results.descriptions.len();
+    stamp_descriptions(&mut llm_results.descriptions, now);
+    llm_results.descriptions.extend(described);
+
+    match write_descriptions_to_file(&llm_results.descriptions, "domains.json") {
+        Ok(()) => report.outputs.push(PathBuf::from("domains.json")),
+        Err(e) => eprintln!("Failed to write the descriptions to domains.json: {}", e),
+    }
+    ctx.check_snapshots();
 
 
     // Write categories to output files (HTML, CSV, JSON...)
     // ctx.write(&aggregated).expect("Failed to write output data");
-
-    Ok(())
+    check_run_outcome(&llm_results, total_domains, answered_domains, ctx.config.max_failure_ratio)
 }
 
 fn write_descriptions_to_file(
@@ -237,28 +892,325 @@ fn write_descriptions_to_file(
     Ok(())
 }
 
-fn main() -> io::Result<()> {
-    // Parse command-line arguments
-    let args = Args::parse();
-    let input_file = PathBuf::from(&args.input);
-    let config_path = args.config.map(PathBuf::from);
-    let dict = args.dict.map(PathBuf::from);
+/// Commands the watch command can run on a new input file
+const WATCH_COMMANDS: [&str; 2] = ["classify", "describe"];
+
+/// Number of stderr lines of a failed run kept in its error summary
+const ERROR_SUMMARY_LINES: usize = 20;
+
+/// Runs `command` on `input` in a child process, so a failed or panicking run cannot stop the watcher
+///
+/// # Arguments
+///
+/// * `command` - Command to run on the input file
+/// * `input` - Input file to process
+/// * `forwarded_args` - Configuration, dictionary, cost cap and extra instructions arguments of the watch command
+///
+/// # Returns
+/// * The last lines of the run stderr when it failed
+fn run_watched_input(command: &str, input: &Path, forwarded_args: &[String]) -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|e| format!("Cannot find the catvision executable: {}", e))?;
+    println!("Running {} on {}", command, input.display());
+
+    let output = std::process::Command::new(exe)
+        .arg("--command")
+        .arg(command)
+        .arg("--input")
+        .arg(input)
+        .args(forwarded_args)
+        .stdout(std::process::Stdio::inherit())
+        .output()
+        .map_err(|e| format!("Failed to start {} on {}: {}", command, input.display(), e))?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    eprint!("{}", stderr);
+    if output.status.success() {
+        return Ok(());
+    }
+
+    let lines = stderr.lines().collect::<Vec<_>>();
+    let summary = match output.status.code() {
+        Some(code) => match ExitCode::from_code(code) {
+            Some(exit_code) => format!("{} exited with status {} ({})", command, code, exit_code.cause()),
+            None => format!("{} exited with status {}", command, code),
+        },
+        None => format!("{} was killed ({})", command, output.status),
+    };
+    Err(format!("{}\n{}", summary, lines[lines.len().saturating_sub(ERROR_SUMMARY_LINES)..].join("\n")))
+}
+
+/// Watches the directory configured in `watch` and runs its command on every new input file, one at a time
+fn process_watch(
+    config_path: Option<PathBuf>,
+    dict: Vec<PathBuf>,
+    max_cost_eur: Option<f64>,
+    extra_instructions: Option<String>,
+) -> Result<(), RunError> {
+    let config = Config::new(config_path.clone()).map_err(|e| RunError::from_error(e, ExitCode::ConfigError, "Cannot load the configuration"))?;
+    let watch = config.watch.ok_or_else(|| {
+        RunError::new(ExitCode::ConfigError, "The watch command needs a watch section in the configuration")
+    })?;
+    if !WATCH_COMMANDS.contains(&watch.command.as_str()) {
+        return Err(RunError::new(
+            ExitCode::ConfigError,
+            format!("Unsupported watch command: {}, expected one of {:?}", watch.command, WATCH_COMMANDS),
+        ));
+    }
+
+    let mut forwarded_args = Vec::new();
+    if let Some(path) = &config_path {
+        forwarded_args.extend(["--config".to_string(), path.display().to_string()]);
+    }
+    for path in &dict {
+        forwarded_args.extend(["--dict".to_string(), path.display().to_string()]);
+    }
+    if let Some(max_cost_eur) = max_cost_eur {
+        forwarded_args.extend(["--max-cost-eur".to_string(), max_cost_eur.to_string()]);
+    }
+    if let Some(extra_instructions) = extra_instructions {
+        forwarded_args.extend(["--extra-instructions".to_string(), extra_instructions]);
+    }
+
+    watch::watch(&watch, |input| run_watched_input(&watch.command, input, &forwarded_args))
+        .map_err(|e| RunError::new(ExitCode::ConfigError, format!("Cannot watch {}: {}", watch.dir.display(), e)))
+}
+
+/// Runs the classify and describe runs whose responses are stored in `run_dir` again from these responses
+///
+/// Every run is parsed again even if a previous one failed, the first error is returned.
+fn process_reparse(run_dir: &Path) -> Result<(), RunError> {
+    let manifests = RunManifest::read_all(run_dir).map_err(|e| RunError::new(ExitCode::ConfigError, e))?;
+    if manifests.is_empty() {
+        return Err(RunError::new(
+            ExitCode::ConfigError,
+            format!("No run manifest in {}, run classify or describe first", run_dir.join(RESPONSE_STORE_DIR).display()),
+        ));
+    }
+
+    let mut first_error = None;
+    for manifest in manifests {
+        println!("Parsing the stored responses of the {} run on {}", manifest.command, manifest.input.display());
+        let result = match manifest.command.as_str() {
+            "classify" => process_classification(manifest.input, manifest.config, manifest.dict, None, None, manifest.extra_instructions, manifest.input_format, LlmSource::StoredResponses, false, &RerunOptions::default(), &mut RunReport::default()),
+            "describe" => process_description(manifest.input, manifest.config, manifest.dict, None, None, 0, None, manifest.extra_instructions, manifest.input_format, LlmSource::StoredResponses, &RerunOptions::default(), &DescribeOptions::default(), &mut RunReport::default()),
+            command => Err(RunError::new(ExitCode::ConfigError, format!("Unsupported command in run manifest: {}", command))),
+        };
+        if let Err(error) = result {
+            eprintln!("Error: {}", error);
+            first_error.get_or_insert(error);
+        }
+    }
+
+    first_error.map_or(Ok(()), Err)
+}
+
+/// Prints the output columns of schema `version` and its migration notes
+fn process_schema(version: u32) -> Result<(), RunError> {
+    let change = schema(version).ok_or_else(|| {
+        RunError::new(
+            ExitCode::ConfigError,
+            format!("Unknown output schema version {}, the current one is {}", version, SCHEMA_VERSION),
+        )
+    })?;
+    println!("Output schema version {}", change.version);
+    println!("Columns: {}", change.columns.join(", "));
+    println!("Notes: {}", change.notes);
+    Ok(())
+}
+
+/// Estimates the tokens and the cost of the LLM requests of a classify or describe run, without sending any request
+/// nor creating any cached content. The domains left out by the run (`--resume`, `--only-domains`, the domains
+/// already described, the local cache) are left out of the estimate.
+///
+/// # Arguments
+///
+/// * `command` - LLM command of the run
+/// * `input_file` - Input file of the run
+/// * `config_path` - Configuration file, looked up when `None`
+/// * `dict` - Dictionary files of the run
+/// * `extra_instructions` - Instructions of the command line, the ones of the configuration are kept when `None`
+/// * `input_format` - Input format, from the input file extension when `None`
+/// * `resume` - Whether the domains classified by the previous run are left out
+/// * `rerun` - Overrides and domain filter of the run
+/// * `describe` - Options of the describe command on the descriptions of the input
+/// * `chunk_sizes` - Chunk sizes to compare, the configured one when empty
+/// * `format` - Output of the estimate
+fn process_dry_run(
+    command: LLMCommand,
+    input_file: &Path,
+    config_path: Option<PathBuf>,
+    dict: Vec<PathBuf>,
+    extra_instructions: Option<String>,
+    input_format: Option<InputFormat>,
+    resume: bool,
+    rerun: &RerunOptions,
+    describe: &DescribeOptions,
+    chunk_sizes: Vec<usize>,
+    format: DryRunFormat,
+) -> Result<(), RunError> {
+    let mut ctx = create_ctx(input_file, config_path, dict, input_format, &rerun.overrides)?;
+    apply_extra_instructions(&mut ctx, extra_instructions)?;
+    if is_heuristic_model(&ctx.config.model[0]) {
+        return Err(RunError::new(ExitCode::ConfigError, "The heuristic model sends no request, there is nothing to estimate"));
+    }
+    let chunk_sizes = if chunk_sizes.is_empty() { vec![ctx.config.chunk_size] } else { chunk_sizes };
+    if chunk_sizes.contains(&0) {
+        return Err(RunError::new(ExitCode::ConfigError, "--chunk-sizes must all be at least 1"));
+    }
+
+    let classified = match command {
+        LLMCommand::CategorizeDomains => load_classified_domains(&ctx, resume)?,
+        LLMCommand::DescribeDomains => IndexMap::new(),
+    };
+    let mut domains = parse_input(&mut ctx, input_file)?;
+    if let Some(mut filter) = rerun.only_domains.as_deref().map(DomainFilter::read).transpose()? {
+        filter.retain(&mut domains);
+        filter.report_missing();
+    }
+    let mut domains_name = domains.keys().cloned().collect::<Vec<String>>();
+    match command {
+        LLMCommand::CategorizeDomains => {
+            skip_classified_domains(&mut domains_name, &classified);
+        }
+        LLMCommand::DescribeDomains => {
+            skip_described_domains(&mut domains_name, &domains, describe, chrono::Utc::now());
+        }
+    }
+
+    let estimate = estimate_run(&domains_name, &ctx.config, &command, &chunk_sizes);
+    match format {
+        DryRunFormat::Text => println!("{}", estimate.report()),
+        DryRunFormat::Json => println!(
+            "{}",
+            serde_json::to_string(&estimate).map_err(|e| RunError::new(ExitCode::OutputError, e.to_string()))?
+        ),
+    }
+    Ok(())
+}
+
+/// Runs the command of `args`
+fn run(args: Args) -> Result<(), RunError> {
     let command = args.command.as_str();
+    if command == "schema" {
+        return process_schema(args.schema_version.unwrap_or(SCHEMA_VERSION));
+    }
+    let dict = args.dict.into_iter().map(PathBuf::from).collect::<Vec<PathBuf>>();
+    if command == "watch" {
+        return process_watch(args.config.map(PathBuf::from), dict, args.max_cost_eur, args.extra_instructions);
+    }
+    if command == "reparse" {
+        let Some(run_dir) = &args.run_dir else {
+            return Err(RunError::new(ExitCode::ConfigError, "The reparse command needs --run-dir"));
+        };
+        return process_reparse(Path::new(run_dir));
+    }
+
+    let Some(input) = &args.input else {
+        return Err(RunError::new(ExitCode::ConfigError, format!("The {} command needs --input", command)));
+    };
+    let input_file = PathBuf::from(input);
+    let config_path = args.config.map(PathBuf::from);
+    let dict_conflicts = args.dict_conflicts.map(PathBuf::from);
+    let processing_hints = args.processing_hints.map(PathBuf::from);
  
-    match command {
+    let extra_instructions = args.extra_instructions.as_deref().map(read_extra_instructions).transpose()?;
+    // An invalid configuration fails the run later on, with nothing to notify
+    let file_config = Config::new(config_path.clone()).ok();
+    // The manifest records the instructions in effect, from the command line or else from the configuration
+    let manifest_instructions = extra_instructions
+        .clone()
+        .or_else(|| file_config.as_ref().and_then(|config| config.extra_instructions.clone()));
+    let manifest = RunManifest::new(command, &input_file, args.input_format, config_path.as_ref(), &dict, manifest_instructions);
+    let mut overrides = args.overrides;
+    if args.quiet {
+        overrides.push("progress=quiet".to_string());
+    }
+    let rerun = RerunOptions {
+        overrides,
+        only_domains: args.only_domains.map(PathBuf::from),
+        merge_into: args.merge_into.map(PathBuf::from),
+    };
+    if rerun.merge_into.is_some() && command != "classify" {
+        return Err(RunError::new(ExitCode::ConfigError, format!("--merge-into only applies to classify, not to {}", command)));
+    }
+    let describe = DescribeOptions {
+        force: args.force_describe,
+        refresh_older_than: args.refresh_older_than,
+    };
+    if let Some(format) = args.dry_run {
+        let command = match command {
+            "classify" => LLMCommand::CategorizeDomains,
+            "describe" => LLMCommand::DescribeDomains,
+            _ => return Err(RunError::new(ExitCode::ConfigError, format!("--dry-run only applies to classify and describe, not to {}", command))),
+        };
+        return process_dry_run(command, &input_file, config_path, dict, extra_instructions, args.input_format, args.resume, &rerun, &describe, args.chunk_sizes, format);
+    }
+    let mut report = RunReport::default();
+    let result = match command {
         "classify" => {
-            process_classification(input_file, config_path, dict)?;
-            Ok(())
+            process_classification(input_file, config_path, dict, dict_conflicts, args.max_cost_eur, extra_instructions, args.input_format, LlmSource::Provider, args.resume, &rerun, &mut report)
         },
         "describe" => {
-            process_description(input_file, config_path, dict)?;
-            Ok(())
+            process_description(input_file, config_path, dict, dict_conflicts, processing_hints, args.slowest_chunks, args.max_cost_eur, extra_instructions, args.input_format, LlmSource::Provider, &rerun, &describe, &mut report)
         },
-        _ => {
-            eprintln!("Unsupported command: {}", command);
-            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Unsupported command"));
+        _ => return Err(RunError::new(ExitCode::ConfigError, format!("Unsupported command: {}", command))),
+    };
+
+    // Only runs that stored responses can be parsed again
+    let run_dir = run_output_dir(&manifest.input);
+    if run_dir.join(RESPONSE_STORE_DIR).is_dir() {
+        if let Err(e) = manifest.write(&run_dir) {
+            eprintln!("Failed to write the run manifest to {}: {}", run_dir.display(), e);
         }
     }
 
+    if let Some(notify) = file_config.and_then(|config| config.notify) {
+        let summary = run_summary(command, &manifest.input, &run_dir, &result, &report);
+        notify_run(&notify, &summary, run_event(&result));
+    }
+    result
+}
+
+/// Exits with the code of `error`, after writing its summary into `run_dir`
+fn exit_with_error(run_dir: &Path, error: RunError) -> ! {
+    eprintln!("Error: {}", error);
+    match write_error_summary(run_dir, &error) {
+        Ok(path) => eprintln!("Error summary written to {}", path.display()),
+        Err(e) => eprintln!("Failed to write the error summary to {}: {}", run_dir.display(), e),
+    }
+    std::process::exit(error.code.code())
+}
+
+fn main() {
+    // Runs without an input file (watch, invalid arguments) report their errors in the current directory
+    let default_run_dir = PathBuf::from("outputs");
+
+    // Parse command-line arguments
+    let args = match Args::try_parse() {
+        Ok(args) => args,
+        // --help and --version
+        Err(e) if !e.use_stderr() => e.exit(),
+        Err(e) => {
+            let _ = e.print();
+            exit_with_error(&default_run_dir, RunError::new(ExitCode::ConfigError, e.kind().to_string()));
+        }
+    };
+
+    let run_dir = match (&args.input, &args.run_dir) {
+        (Some(input), _) => Some(run_output_dir(Path::new(input))),
+        (None, Some(run_dir)) if args.command == "reparse" => Some(PathBuf::from(run_dir)),
+        _ => None,
+    };
+    let run_dir = match run_dir {
+        Some(run_dir) => {
+            // A summary left by a previous run of the same input would be mistaken for this one
+            let _ = std::fs::remove_file(run_dir.join(ERROR_SUMMARY_FILE));
+            run_dir
+        }
+        None => default_run_dir,
+    };
 
-}
\ No newline at end of file
+    if let Err(error) = run(args) {
+        exit_with_error(&run_dir, error);
+    }
+}
@@ -0,0 +1,230 @@
+use config::{NotifyConfig, NotifyEvent, NotifyKind};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use utils::duration_to_pretty;
+
+use crate::exit::{ExitCode, RunError};
+
+/// Number of attempts to deliver a notification before giving up
+const NOTIFY_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry of a notification, doubled after each failed attempt
+const NOTIFY_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Timeout of one notification request, a slow webhook must not hold the end of the run
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Plain-text message used when the configuration sets no template
+const DEFAULT_TEMPLATE: &str = "CatVision {command} on {input}: {status}, {answered}/{total} domains in {duration} for {cost}€ {message}";
+
+/// Counts and outputs of a run, filled as it goes so a failed run reports what it got to
+#[derive(Debug, Clone, Default)]
+pub struct RunReport {
+    /// Number of domains of the input
+    pub total_domains: usize,
+    /// Number of domains with a result
+    pub answered_domains: usize,
+    /// Cost of the run, in euros
+    pub cost: f64,
+    /// Duration of the LLM run
+    pub duration: Duration,
+    /// Files written by the run
+    pub outputs: Vec<PathBuf>,
+}
+
+/// Outcome of a run, as matched against the `on` list of the notification
+pub fn run_event(result: &Result<(), RunError>) -> NotifyEvent {
+    match result {
+        Ok(()) => NotifyEvent::Success,
+        Err(error) if error.code == ExitCode::BudgetStop => NotifyEvent::BudgetStop,
+        Err(_) => NotifyEvent::Failure,
+    }
+}
+
+/// Name of an outcome in the notification payload, as written in the configuration
+fn event_name(event: NotifyEvent) -> &'static str {
+    match event {
+        NotifyEvent::Success => "success",
+        NotifyEvent::Failure => "failure",
+        NotifyEvent::BudgetStop => "budget_stop",
+    }
+}
+
+/// Summary of a finished run, the payload of the notification
+///
+/// # Arguments
+///
+/// * `command` - Command of the run
+/// * `input` - Input file of the run
+/// * `output_dir` - Output directory of the run
+/// * `result` - Outcome of the run
+/// * `report` - Counts and outputs of the run
+pub fn run_summary(
+    command: &str,
+    input: &Path,
+    output_dir: &Path,
+    result: &Result<(), RunError>,
+    report: &RunReport,
+) -> serde_json::Value {
+    let error = result.as_ref().err();
+    serde_json::json!({
+        "status": event_name(run_event(result)),
+        "command": command,
+        "input": input.display().to_string(),
+        "exit_code": error.map_or(0, |error| error.code.code()),
+        "cause": error.map(|error| error.code.cause()),
+        "message": error.map(|error| error.message.as_str()),
+        "counts": {
+            "total": report.total_domains,
+            "answered": report.answered_domains,
+            "failed": report.total_domains.saturating_sub(report.answered_domains),
+        },
+        "cost_eur": report.cost,
+        "duration_seconds": report.duration.as_secs_f64(),
+        "output_dir": output_dir.display().to_string(),
+        "outputs": report.outputs.iter().map(|path| path.display().to_string()).collect::<Vec<_>>(),
+    })
+}
+
+/// Fills the `{placeholder}` of `template` from the summary of the run
+///
+/// # Arguments
+///
+/// * `template` - Message template, unknown placeholders are kept as is
+/// * `summary` - Summary of the run, see [`run_summary`]
+pub fn render_message(template: &str, summary: &serde_json::Value) -> String {
+    let text = |value: &serde_json::Value| match value {
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Null => String::new(),
+        value => value.to_string(),
+    };
+    let duration = Duration::from_secs_f64(summary["duration_seconds"].as_f64().unwrap_or_default());
+    let placeholders = [
+        ("{command}", text(&summary["command"])),
+        ("{input}", text(&summary["input"])),
+        ("{status}", text(&summary["status"])),
+        ("{exit_code}", text(&summary["exit_code"])),
+        ("{message}", text(&summary["message"])),
+        ("{total}", text(&summary["counts"]["total"])),
+        ("{answered}", text(&summary["counts"]["answered"])),
+        ("{failed}", text(&summary["counts"]["failed"])),
+        ("{cost}", format!("{:.4}", summary["cost_eur"].as_f64().unwrap_or_default())),
+        ("{duration}", duration_to_pretty(duration).unwrap_or_else(|| format!("{:?}", duration))),
+        ("{output_dir}", text(&summary["output_dir"])),
+    ];
+
+    let message = placeholders
+        .iter()
+        .fold(template.to_string(), |message, (placeholder, value)| message.replace(placeholder, value));
+    message.trim_end().to_string()
+}
+
+/// POSTs `payload` to `url`, retrying a refused connection or an error status a few times
+///
+/// # Errors
+///
+/// Returns the error of the last attempt
+fn post_with_retry(url: &str, payload: &serde_json::Value) -> Result<(), String> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(NOTIFY_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Cannot create the HTTP client: {}", e))?;
+
+    let mut delay = NOTIFY_RETRY_DELAY;
+    let mut last_error = String::new();
+    for attempt in 1..=NOTIFY_ATTEMPTS {
+        match client.post(url).json(payload).send() {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) => last_error = format!("webhook answered {}", response.status()),
+            Err(e) => last_error = e.to_string(),
+        }
+        if attempt < NOTIFY_ATTEMPTS {
+            std::thread::sleep(delay);
+            delay *= 2;
+        }
+    }
+    Err(format!("{} after {} attempts", last_error, NOTIFY_ATTEMPTS))
+}
+
+/// Sends the notification of a finished run when its outcome is in the `on` list of `notify`
+///
+/// Failures to notify are logged and never change the exit code of the run.
+///
+/// # Arguments
+///
+/// * `notify` - Notification configuration
+/// * `summary` - Summary of the run, see [`run_summary`]
+/// * `event` - Outcome of the run
+pub fn notify_run(notify: &NotifyConfig, summary: &serde_json::Value, event: NotifyEvent) {
+    if notify.kind == NotifyKind::None || !notify.on.contains(&event) {
+        return;
+    }
+    let Some(url) = &notify.url else {
+        eprintln!("Failed to send the run notification: notify.url is required by the webhook kind");
+        return;
+    };
+
+    let mut payload = summary.clone();
+    payload["text"] = serde_json::Value::String(render_message(notify.template.as_deref().unwrap_or(DEFAULT_TEMPLATE), summary));
+    match post_with_retry(url, &payload) {
+        Ok(()) => println!("Run notification sent to {}", url),
+        Err(e) => eprintln!("Failed to send the run notification to {}: {}", url, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> RunReport {
+        RunReport {
+            total_domains: 4,
+            answered_domains: 3,
+            cost: 0.0125,
+            duration: Duration::from_secs(2),
+            outputs: vec![PathBuf::from("outputs/domains.csv")],
+        }
+    }
+
+    #[test]
+    fn test_run_event() {
+        assert_eq!(run_event(&Ok(())), NotifyEvent::Success);
+        assert_eq!(run_event(&Err(RunError::new(ExitCode::BudgetStop, "cap"))), NotifyEvent::BudgetStop);
+        assert_eq!(run_event(&Err(RunError::new(ExitCode::Cancelled, "ctrl-c"))), NotifyEvent::Failure);
+    }
+
+    #[test]
+    fn test_run_summary_of_a_failed_run() {
+        let result = Err(RunError::new(ExitCode::PartialFailure, "1 of 4 domains left without a result"));
+        let summary = run_summary("classify", Path::new("in/domains.csv"), Path::new("in/outputs"), &result, &report());
+
+        assert_eq!(summary["status"], "failure");
+        assert_eq!(summary["exit_code"], 6);
+        assert_eq!(summary["cause"], "partial_failure");
+        assert_eq!(summary["counts"], serde_json::json!({ "total": 4, "answered": 3, "failed": 1 }));
+        assert_eq!(summary["cost_eur"], 0.0125);
+        assert_eq!(summary["duration_seconds"], 2.0);
+        assert_eq!(summary["outputs"], serde_json::json!(["outputs/domains.csv"]));
+
+        let summary = run_summary("classify", Path::new("in/domains.csv"), Path::new("in/outputs"), &Ok(()), &report());
+        assert_eq!(summary["status"], "success");
+        assert_eq!(summary["exit_code"], 0);
+        assert!(summary["cause"].is_null());
+    }
+
+    #[test]
+    fn test_render_message() {
+        let result = Err(RunError::new(ExitCode::BudgetStop, "cap reached"));
+        let summary = run_summary("describe", Path::new("domains.csv"), Path::new("outputs"), &result, &report());
+
+        assert_eq!(
+            render_message("{status}: {answered}/{total} ({failed} failed) for {cost}€, exit {exit_code} {unknown}", &summary),
+            "budget_stop: 3/4 (1 failed) for 0.0125€, exit 5 {unknown}"
+        );
+        assert!(render_message(DEFAULT_TEMPLATE, &summary).ends_with("cap reached"));
+
+        // No trailing space when a successful run has no message
+        let summary = run_summary("describe", Path::new("domains.csv"), Path::new("outputs"), &Ok(()), &report());
+        assert!(render_message(DEFAULT_TEMPLATE, &summary).ends_with("€"));
+    }
+}
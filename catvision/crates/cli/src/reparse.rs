@@ -0,0 +1,191 @@
+use std::path::{Path, PathBuf};
+use core::InputFormat;
+use llm::core::budget::write_checkpoint;
+use llm::core::responses::{REQUERY_FILE, RESPONSE_STORE_DIR};
+
+/// Suffix of the run manifests, written next to the stored responses as `<input file name>.run.json`
+pub const RUN_MANIFEST_SUFFIX: &str = ".run.json";
+
+/// Arguments of a classify or describe run, stored with its responses so the reparse command can parse them again
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunManifest {
+    pub command: String,
+    pub input: PathBuf,
+    pub input_format: Option<InputFormat>,
+    pub config: Option<PathBuf>,
+    pub dict: Vec<PathBuf>,
+    /// Extra instructions in effect during the run
+    pub extra_instructions: Option<String>,
+}
+
+/// Absolute path of `path`, left as is if it cannot be resolved
+fn absolute(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+impl RunManifest {
+    /// Creates the manifest of a run, the paths are made absolute so the reparse can run from any directory
+    pub fn new(
+        command: &str,
+        input: &Path,
+        input_format: Option<InputFormat>,
+        config: Option<&PathBuf>,
+        dict: &[PathBuf],
+        extra_instructions: Option<String>,
+    ) -> Self {
+        RunManifest {
+            command: command.to_string(),
+            input: absolute(input),
+            input_format,
+            config: config.map(|path| absolute(path)),
+            dict: dict.iter().map(|path| absolute(path)).collect(),
+            extra_instructions,
+        }
+    }
+
+    /// Writes the manifest into the response store of `run_dir`, replacing the one of a previous run of the same input
+    ///
+    /// # Returns
+    /// * The path of the manifest
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the response store cannot be created or the file cannot be written
+    pub fn write(&self, run_dir: &Path) -> std::io::Result<PathBuf> {
+        let manifest = serde_json::json!({
+            "command": self.command,
+            "input": self.input.to_string_lossy(),
+            "input_format": self.input_format.map(|format| format.to_string()),
+            "config": self.config.as_ref().map(|path| path.to_string_lossy()),
+            "dict": self.dict.iter().map(|path| path.to_string_lossy()).collect::<Vec<_>>(),
+            "extra_instructions": self.extra_instructions,
+        });
+
+        let dir = run_dir.join(RESPONSE_STORE_DIR);
+        std::fs::create_dir_all(&dir)?;
+        let file_name = self.input.file_name().unwrap_or_default().to_string_lossy();
+        let path = dir.join(format!("{}{}", file_name, RUN_MANIFEST_SUFFIX));
+        std::fs::write(&path, format!("{:#}\n", manifest))?;
+        Ok(path)
+    }
+
+    /// Reads a manifest written by [`RunManifest::write`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, is not JSON or misses the command or the input
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| format!("Cannot read run manifest {}: {}", path.display(), e))?;
+        let manifest: serde_json::Value = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid run manifest {}: {}", path.display(), e))?;
+
+        let field = |name: &str| manifest[name].as_str().map(str::to_string);
+        let (Some(command), Some(input)) = (field("command"), field("input")) else {
+            return Err(format!("Invalid run manifest {}: missing command or input", path.display()));
+        };
+        let input_format = match field("input_format") {
+            Some(format) => Some(format.parse::<InputFormat>().map_err(|e| format!("Invalid run manifest {}: {}", path.display(), e))?),
+            None => None,
+        };
+        let dict = manifest["dict"]
+            .as_array()
+            .map(|paths| paths.iter().filter_map(|path| path.as_str()).map(PathBuf::from).collect())
+            .unwrap_or_default();
+
+        Ok(RunManifest {
+            command,
+            input: PathBuf::from(input),
+            input_format,
+            config: field("config").map(PathBuf::from),
+            dict,
+            extra_instructions: field("extra_instructions"),
+        })
+    }
+
+    /// Reads the manifests of the runs whose responses are stored in `run_dir`, sorted by input file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the response store cannot be listed or a manifest cannot be read
+    pub fn read_all(run_dir: &Path) -> Result<Vec<Self>, String> {
+        let dir = run_dir.join(RESPONSE_STORE_DIR);
+        let entries = std::fs::read_dir(&dir).map_err(|e| format!("Cannot read the response store {}: {}", dir.display(), e))?;
+
+        let mut paths = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.to_string_lossy().ends_with(RUN_MANIFEST_SUFFIX))
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths.iter().map(|path| Self::read(path)).collect()
+    }
+}
+
+/// Writes the domains a reparse left without a result into `run_dir`, removing the list of a previous reparse when
+/// every domain was found
+///
+/// # Returns
+/// * The path of the list, None when it is empty
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be written
+pub fn write_requery_domains(run_dir: &Path, domains: &[String]) -> std::io::Result<Option<PathBuf>> {
+    let path = run_dir.join(REQUERY_FILE);
+    if domains.is_empty() {
+        let _ = std::fs::remove_file(&path);
+        return Ok(None);
+    }
+    write_checkpoint(domains, &path)?;
+    Ok(Some(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_manifest_round_trip() {
+        let dir = std::env::temp_dir().join(format!("catvision-run-manifest-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("domains.txt");
+        std::fs::write(&input, "example.com\n").unwrap();
+        let run_dir = dir.join("outputs");
+
+        assert!(RunManifest::read_all(&run_dir).is_err());
+
+        let manifest = RunManifest::new(
+            "classify",
+            &input,
+            Some(InputFormat::Txt),
+            None,
+            &[dir.join("dict.csv")],
+            Some("Préférer Enseignement".to_string()),
+        );
+        let path = manifest.write(&run_dir).unwrap();
+        assert_eq!(path, run_dir.join(RESPONSE_STORE_DIR).join("domains.txt.run.json"));
+        // Stored responses are not manifests
+        std::fs::write(run_dir.join(RESPONSE_STORE_DIR).join("0123.json"), "{}").unwrap();
+
+        let manifests = RunManifest::read_all(&run_dir).unwrap();
+        assert_eq!(manifests, vec![manifest]);
+        assert!(manifests[0].input.is_absolute());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_write_requery_domains() {
+        let dir = std::env::temp_dir().join(format!("catvision-requery-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let path = write_requery_domains(&dir, &["a.com".to_string(), "b.org".to_string()]).unwrap().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "a.com\nb.org\n");
+
+        assert_eq!(write_requery_domains(&dir, &[]).unwrap(), None);
+        assert!(!path.exists());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use indexmap::{IndexMap, IndexSet};
+use format::csv::{merge_rows, read_classified_domains, MergeSummary};
+use format::schema::{check_schema_version, read_csv_schema_version};
+use crate::exit::{ExitCode, RunError};
+
+/// Options of a run on some domains of a previous one, to fix the results a review found wrong
+#[derive(Debug, Clone, Default)]
+pub struct RerunOptions {
+    /// `key=value` configuration overrides, applied after the configuration lookup
+    pub overrides: Vec<String>,
+    /// File listing the domains the run is restricted to
+    pub only_domains: Option<PathBuf>,
+    /// CSV output of the previous run receiving the fresh rows (classify only)
+    pub merge_into: Option<PathBuf>,
+}
+
+/// Domains a run is restricted to, with the ones found in the input
+#[derive(Debug, Clone)]
+pub struct DomainFilter {
+    /// Listed domains in lower case, in the order of the list
+    listed: IndexSet<String>,
+    found: HashSet<String>,
+}
+
+impl DomainFilter {
+    /// Filter on `domains`, compared without case
+    pub fn new<I: IntoIterator<Item = String>>(domains: I) -> Self {
+        DomainFilter {
+            listed: domains.into_iter().map(|domain| domain.trim().to_lowercase()).filter(|domain| !domain.is_empty()).collect(),
+            found: HashSet::new(),
+        }
+    }
+
+    /// Reads the list of domains of `--only-domains`, one per line, blank lines and `#` comments skipped
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or lists no domain
+    pub fn read(path: &Path) -> Result<Self, RunError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| RunError::new(ExitCode::InputParseError, format!("Cannot read the domain list {}: {}", path.display(), e)))?;
+        let filter = Self::new(content.lines().filter(|line| !line.trim_start().starts_with('#')).map(str::to_string));
+        if filter.listed.is_empty() {
+            return Err(RunError::new(ExitCode::InputParseError, format!("No domain listed in {}", path.display())));
+        }
+        Ok(filter)
+    }
+
+    /// Leaves out of `domains` the domains not listed
+    pub fn retain<V>(&mut self, domains: &mut IndexMap<String, V>) {
+        domains.retain(|domain, _| {
+            let domain = domain.to_lowercase();
+            let listed = self.listed.contains(&domain);
+            if listed {
+                self.found.insert(domain);
+            }
+            listed
+        });
+    }
+
+    /// Listed domains not found in the input so far, in the order of the list
+    pub fn missing(&self) -> Vec<&str> {
+        self.listed.iter().filter(|domain| !self.found.contains(*domain)).map(String::as_str).collect()
+    }
+
+    /// Reports the listed domains missing from the input
+    pub fn report_missing(&self) {
+        let missing = self.missing();
+        println!("Run restricted to {} of the {} listed domains", self.listed.len() - missing.len(), self.listed.len());
+        if !missing.is_empty() {
+            eprintln!("{} listed domains are not in the input: {}", missing.len(), missing.join(", "));
+        }
+    }
+}
+
+/// CSV output of a previous run receiving the rows of the domains classified again.
+/// Read before the run, whose own output may be the same file.
+#[derive(Debug, Clone)]
+pub struct MergeTarget {
+    path: PathBuf,
+    content: Vec<u8>,
+}
+
+impl MergeTarget {
+    /// Reads the output to merge into
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or was written with another output schema version
+    pub fn read(path: &Path) -> Result<Self, RunError> {
+        let error = |e: String| RunError::new(ExitCode::InputParseError, format!("Cannot merge into {}: {}", path.display(), e));
+        if let Some(version) = read_csv_schema_version(path).map_err(|e| error(e.to_string()))? {
+            check_schema_version(path, version).map_err(error)?;
+        }
+        let content = std::fs::read(path).map_err(|e| error(e.to_string()))?;
+        Ok(MergeTarget { path: path.to_path_buf(), content })
+    }
+
+    /// Path of the output to merge into
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// First LLM proposition of every domain classified in the output to merge into, the previous run of the
+    /// "Changed since last run" section of the HTML output. To be called before the run writes its outputs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be parsed
+    pub fn first_propositions(&self, delimiter: u8) -> Result<HashMap<String, Option<String>>, RunError> {
+        let classified = read_classified_domains(&self.path, delimiter)
+            .map_err(|e| RunError::from_error(e, ExitCode::InputParseError, &format!("Cannot compare with {}", self.path.display())))?;
+        Ok(classified
+            .into_iter()
+            .map(|(domain, categories)| (domain, categories.first().map(|category| category.to_string())))
+            .collect())
+    }
+
+    /// Replaces the rows of the domains of `answered` with their rows in the output of the run, the other rows
+    /// are left untouched
+    ///
+    /// # Arguments
+    ///
+    /// * `fresh` - CSV output of the run
+    /// * `delimiter` - Field delimiter of both outputs
+    /// * `answered` - Domains with a result in the run, the others keep the rows of the previous run
+    pub fn merge(self, fresh: &Path, delimiter: u8, answered: &HashSet<String>) -> Result<MergeSummary, RunError> {
+        let error = |e: String| RunError::new(ExitCode::ConfigError, format!("Cannot merge into {}: {}", self.path.display(), e));
+        let fresh_content = std::fs::read(fresh).map_err(|e| error(e.to_string()))?;
+        let (content, summary) = merge_rows(&self.content, &fresh_content, delimiter, |domain| answered.contains(domain))
+            .map_err(|e| error(e.to_string()))?;
+        std::fs::write(&self.path, content).map_err(|e| error(e.to_string()))?;
+        println!(
+            "{} rows updated and {} appended in {}",
+            summary.updated,
+            summary.appended,
+            self.path.display()
+        );
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domain_filter_intersects_the_input() {
+        let mut filter = DomainFilter::new(["www.lemonde.fr", " Mail.Google.com ", "", "absent.example.com"].map(String::from));
+        let mut domains: IndexMap<String, usize> = ["mail.google.com", "www.example.com", "www.lemonde.fr"]
+            .into_iter()
+            .enumerate()
+            .map(|(idx, domain)| (domain.to_string(), idx))
+            .collect();
+
+        filter.retain(&mut domains);
+        // Input order and values are kept
+        assert_eq!(domains.into_iter().collect::<Vec<_>>(), vec![("mail.google.com".to_string(), 0), ("www.lemonde.fr".to_string(), 2)]);
+        assert_eq!(filter.missing(), vec!["absent.example.com"]);
+
+        // Found in a later batch
+        let mut batch: IndexMap<String, usize> = IndexMap::from([("absent.example.com".to_string(), 3)]);
+        filter.retain(&mut batch);
+        assert_eq!(batch.len(), 1);
+        assert!(filter.missing().is_empty());
+    }
+
+    #[test]
+    fn test_read_domain_list() {
+        let path = std::env::temp_dir().join(format!("catvision-only-domains-{}.txt", std::process::id()));
+        std::fs::write(&path, "# reviewed on 2026-10-01\nwww.lemonde.fr\n\nmail.google.com\n").unwrap();
+        assert_eq!(DomainFilter::read(&path).unwrap().missing(), vec!["www.lemonde.fr", "mail.google.com"]);
+
+        std::fs::write(&path, "# nothing yet\n").unwrap();
+        assert_eq!(DomainFilter::read(&path).unwrap_err().code, ExitCode::InputParseError);
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,141 @@
+//! Helpers shared by the integration tests of the CLI
+
+// Each test binary compiles this module and uses only some of the helpers
+#![allow(dead_code)]
+
+use assert_cmd::Command;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+
+/// Creates an empty working directory for a test, garbage files of failed domains land in it
+pub fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("catvision-cli-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+pub fn config_json(model: &str, thinking_budget: &str) -> String {
+    format!(r#"{{
+        "max_threads": 1,
+        "support_csv": {{ "input": true, "output": true }},
+        "support_html": {{ "input": false, "output": false }},
+        "max_domain_propositions": 1,
+        "model": ["{}"],
+        "chunk_size": 10,
+        "thinking_budget": {},
+        "use_gemini_explicit_caching": false,
+        "use_gemini_url_context": false,
+        "use_gemini_google_search": false,
+        "use_gemini_custom_cache_duration": null,
+        "strict_columns": false,
+        "max_failure_ratio": 0.5
+    }}"#, model, thinking_budget)
+}
+
+/// Mock Gemini provider answering every request with `status` and the model output `text`
+///
+/// # Returns
+/// * The base URL of the provider, for `CATVISION_GEMINI_ENDPOINT`
+pub fn mock_provider(status: u16, text: &'static str) -> String {
+    recording_mock_provider(status, text).0
+}
+
+/// [`mock_provider`] also recording the requests it receives
+///
+/// # Returns
+/// * The base URL of the provider and the bodies of the requests it received, in order
+pub fn recording_mock_provider(status: u16, text: &'static str) -> (String, std::sync::mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let (sender, receiver) = std::sync::mpsc::channel();
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            // Request line and headers, then the body so the client is not reset
+            let mut content_length = 0;
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap_or(0) > 0 && line != "\r\n" {
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap_or(0);
+                    }
+                }
+                line.clear();
+            }
+            let mut body = vec![0; content_length];
+            let _ = reader.read_exact(&mut body);
+            let _ = sender.send(String::from_utf8_lossy(&body).into_owned());
+
+            let response = serde_json::json!({
+                "candidates": [{
+                    "content": { "role": "model", "parts": [{ "text": text }] },
+                    "finishReason": "STOP",
+                }],
+                "usageMetadata": { "promptTokenCount": 10, "candidatesTokenCount": 10, "totalTokenCount": 20 },
+                "modelVersion": "gemini-2.5-flash",
+                "createTime": "2025-01-01T00:00:00Z",
+                "responseId": "mock",
+            })
+            .to_string();
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                response.len(),
+                response
+            );
+        }
+    });
+
+    (endpoint, receiver)
+}
+
+pub fn catvision(dir: &Path) -> Command {
+    let mut cmd = Command::cargo_bin("cli").unwrap();
+    cmd.current_dir(dir)
+        .env("XDG_CONFIG_HOME", dir)
+        .env("MY_GEMINI_API_KEY", "test-key")
+        .env_remove("CATVISION_GEMINI_ENDPOINT");
+    cmd
+}
+
+/// Mock webhook answering the first `failures` requests with a 500 and the next ones with a 200
+///
+/// # Returns
+/// * The URL of the webhook and the JSON bodies of the requests it received, in order
+pub fn mock_webhook(failures: usize) -> (String, std::sync::mpsc::Receiver<serde_json::Value>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let (sender, receiver) = std::sync::mpsc::channel();
+
+    std::thread::spawn(move || {
+        for (index, stream) in listener.incoming().enumerate() {
+            let Ok(mut stream) = stream else { continue };
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut content_length = 0;
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap_or(0) > 0 && line != "\r\n" {
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap_or(0);
+                    }
+                }
+                line.clear();
+            }
+            let mut body = vec![0; content_length];
+            let _ = reader.read_exact(&mut body);
+            let _ = sender.send(serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null));
+
+            let status = if index < failures { 500 } else { 200 };
+            let _ = write!(stream, "HTTP/1.1 {} Mock\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+        }
+    });
+
+    (url, receiver)
+}
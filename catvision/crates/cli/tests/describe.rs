@@ -0,0 +1,136 @@
+mod common;
+
+use common::{catvision, config_json, recording_mock_provider, test_dir};
+use std::path::Path;
+
+/// Provider answer with descriptions for every domain of the tests
+const DESCRIPTIONS: &str = r#"{
+    "described.com": ["Nouvelle messagerie", "New email"],
+    "half.com": ["Jeux en ligne", "Online games"],
+    "empty.com": ["Banque", "Bank"]
+}"#;
+
+/// Input mixing a described domain, a domain with one description and an undescribed one
+fn write_input(dir: &Path, updated_at: Option<&str>) -> std::path::PathBuf {
+    let input = dir.join("domains.csv");
+    let content = match updated_at {
+        Some(updated_at) => format!(
+            "domain;description_fr_by_gemini;description_en_by_gemini;description_updated_at\n\
+             described.com;Messagerie;Email;{}\nhalf.com;Jeux;;\nempty.com;;;\n",
+            updated_at
+        ),
+        None => "domain;description_fr_by_gemini;description_en_by_gemini\ndescribed.com;Messagerie;Email\nhalf.com;Jeux;\nempty.com;;\n".to_string(),
+    };
+    std::fs::write(&input, content).unwrap();
+    input
+}
+
+fn read_descriptions(dir: &Path) -> serde_json::Value {
+    serde_json::from_str(&std::fs::read_to_string(dir.join("domains.json")).unwrap()).unwrap()
+}
+
+#[test]
+fn test_describe_skips_the_domains_described_in_the_input() {
+    let dir = test_dir("describe-skip");
+    let input = write_input(&dir, None);
+    let config = dir.join("config.json");
+    std::fs::write(&config, config_json("gemini-2.5-flash", "0")).unwrap();
+    let (endpoint, requests) = recording_mock_provider(200, DESCRIPTIONS);
+
+    let output = catvision(&dir)
+        .env("CATVISION_GEMINI_ENDPOINT", endpoint)
+        .args(["--command", "describe", "--input"])
+        .arg(&input)
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .success()
+        .get_output()
+        .clone();
+    assert!(String::from_utf8(output.stdout).unwrap().contains("1 domains already described are skipped"));
+
+    // Only the domains missing a description reach the provider
+    let requests = requests.try_iter().collect::<Vec<_>>();
+    assert!(!requests.is_empty());
+    assert!(requests.iter().all(|body| !body.contains("described.com")));
+    assert!(requests.iter().any(|body| body.contains("half.com")));
+    assert!(requests.iter().any(|body| body.contains("empty.com")));
+
+    // The input descriptions are carried through, the fresh ones are stamped
+    let descriptions = read_descriptions(&dir);
+    assert_eq!(descriptions["described.com"]["description_fr"], "Messagerie");
+    assert_eq!(descriptions["described.com"]["description_en"], "Email");
+    assert!(descriptions["described.com"].get("description_updated_at").is_none());
+    assert_eq!(descriptions["half.com"]["description_en"], "Online games");
+    assert!(descriptions["empty.com"]["description_updated_at"].as_str().unwrap().ends_with('Z'));
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_force_describe_sends_every_domain() {
+    let dir = test_dir("describe-force");
+    let input = write_input(&dir, None);
+    let config = dir.join("config.json");
+    std::fs::write(&config, config_json("gemini-2.5-flash", "0")).unwrap();
+    let (endpoint, requests) = recording_mock_provider(200, DESCRIPTIONS);
+
+    catvision(&dir)
+        .env("CATVISION_GEMINI_ENDPOINT", endpoint)
+        .args(["--command", "describe", "--force-describe", "--input"])
+        .arg(&input)
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .success();
+
+    assert!(requests.try_iter().any(|body| body.contains("described.com")));
+    assert_eq!(read_descriptions(&dir)["described.com"]["description_en"], "New email");
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_refresh_older_than_describes_stale_domains_again() {
+    let dir = test_dir("describe-refresh");
+    let config = dir.join("config.json");
+    std::fs::write(&config, config_json("gemini-2.5-flash", "0")).unwrap();
+
+    // Without the timestamp column the age of the descriptions is unknown
+    let input = write_input(&dir, None);
+    catvision(&dir)
+        .args(["--command", "describe", "--refresh-older-than", "30", "--input"])
+        .arg(&input)
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .code(2);
+
+    // A recent description is kept, a stale one is asked again
+    let input = write_input(&dir, Some(&chrono::Utc::now().to_rfc3339()));
+    let (endpoint, requests) = recording_mock_provider(200, DESCRIPTIONS);
+    catvision(&dir)
+        .env("CATVISION_GEMINI_ENDPOINT", endpoint)
+        .args(["--command", "describe", "--refresh-older-than", "30", "--input"])
+        .arg(&input)
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .success();
+    assert!(requests.try_iter().all(|body| !body.contains("described.com")));
+
+    let input = write_input(&dir, Some("2020-01-01T00:00:00Z"));
+    let (endpoint, requests) = recording_mock_provider(200, DESCRIPTIONS);
+    catvision(&dir)
+        .env("CATVISION_GEMINI_ENDPOINT", endpoint)
+        .args(["--command", "describe", "--refresh-older-than", "30", "--input"])
+        .arg(&input)
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .success();
+    assert!(requests.try_iter().any(|body| body.contains("described.com")));
+    assert_eq!(read_descriptions(&dir)["described.com"]["description_fr"], "Nouvelle messagerie");
+
+    let _ = std::fs::remove_dir_all(dir);
+}
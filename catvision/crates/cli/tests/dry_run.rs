@@ -0,0 +1,91 @@
+mod common;
+
+use common::{catvision, config_json, recording_mock_provider, test_dir};
+use std::path::Path;
+
+/// Input of 12 domains, split in chunks of 10 by the configuration of the tests
+fn write_input(dir: &Path) -> std::path::PathBuf {
+    let input = dir.join("domains.csv");
+    let domains = (0..12).map(|i| format!("domain{}.com", i)).collect::<Vec<_>>().join("\n");
+    std::fs::write(&input, format!("domain\n{}\n", domains)).unwrap();
+    input
+}
+
+#[test]
+fn test_dry_run_compares_chunk_sizes_without_any_request() {
+    let dir = test_dir("dry-run-json");
+    let input = write_input(&dir);
+    let config = dir.join("config.json");
+    std::fs::write(&config, config_json("gemini-2.5-flash", "0")).unwrap();
+    let (endpoint, requests) = recording_mock_provider(200, "{}");
+
+    let output = catvision(&dir)
+        .env("CATVISION_GEMINI_ENDPOINT", endpoint)
+        .args(["--command", "classify", "--dry-run", "json", "--chunk-sizes", "5,20", "--input"])
+        .arg(&input)
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .success()
+        .get_output()
+        .clone();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let estimate: serde_json::Value = serde_json::from_str(stdout.lines().last().unwrap()).unwrap();
+
+    assert_eq!(estimate["command"], "categorize");
+    assert_eq!(estimate["domains"], 12);
+    let chunk_sizes = estimate["chunk_sizes"].as_array().unwrap();
+    assert_eq!(chunk_sizes[0]["chunk_size"], 5);
+    assert_eq!(chunk_sizes[0]["requests"], 3);
+    assert_eq!(chunk_sizes[1]["requests"], 1);
+    // The instructions are sent once per request, fewer requests send fewer prompt tokens
+    assert!(chunk_sizes[0]["prompt_tokens"].as_u64().unwrap() > chunk_sizes[1]["prompt_tokens"].as_u64().unwrap());
+    assert!(chunk_sizes[1]["total_cost_eur"].as_f64().unwrap() > 0.0);
+
+    assert!(requests.try_iter().next().is_none());
+    assert!(!dir.join("domains.json").exists());
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_dry_run_prints_a_table_of_the_configured_chunk_size() {
+    let dir = test_dir("dry-run-text");
+    let input = write_input(&dir);
+    let config = dir.join("config.json");
+    std::fs::write(&config, config_json("gemini-2.5-flash", "0")).unwrap();
+    let (endpoint, requests) = recording_mock_provider(200, "{}");
+
+    let output = catvision(&dir)
+        .env("CATVISION_GEMINI_ENDPOINT", endpoint)
+        .args(["--command", "describe", "--dry-run", "--input"])
+        .arg(&input)
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .success()
+        .get_output()
+        .clone();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(stdout.contains("Dry run of describe on 12 domains"));
+    assert!(stdout.lines().any(|line| line.trim_start().starts_with("10 |")));
+    assert!(requests.try_iter().next().is_none());
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_dry_run_rejects_the_heuristic_model() {
+    let dir = test_dir("dry-run-heuristic");
+    let input = write_input(&dir);
+    let config = dir.join("config.json");
+    std::fs::write(&config, config_json("heuristic", "0")).unwrap();
+
+    catvision(&dir)
+        .args(["--command", "classify", "--dry-run", "--input"])
+        .arg(&input)
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .code(2);
+    let _ = std::fs::remove_dir_all(dir);
+}
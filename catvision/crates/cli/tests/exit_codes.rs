@@ -0,0 +1,221 @@
+mod common;
+
+use common::{catvision, config_json, mock_provider, test_dir};
+use std::path::Path;
+
+/// Reads the error summary of the run on `input`
+fn error_summary(input: &Path) -> serde_json::Value {
+    let path = input.parent().unwrap().join("outputs").join("error.json");
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+#[test]
+fn test_invalid_configuration_exits_with_config_error() {
+    let dir = test_dir("config");
+    let input = dir.join("domains.csv");
+    std::fs::write(&input, "domain\nexample.com\n").unwrap();
+    // gemini-2.0-flash has no thinking
+    let config = dir.join("config.json");
+    std::fs::write(&config, config_json("gemini-2.0-flash", "1024")).unwrap();
+
+    catvision(&dir)
+        .args(["--command", "classify", "--input"])
+        .arg(&input)
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .code(2);
+
+    let summary = error_summary(&input);
+    assert_eq!(summary["exit_code"], 2);
+    assert_eq!(summary["cause"], "config_error");
+    assert!(summary["message"].as_str().unwrap().contains("thinking_budget"));
+    assert!(!summary["remediation"].as_str().unwrap().is_empty());
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_zero_chunk_size_exits_with_config_error() {
+    let dir = test_dir("chunk-size");
+    let input = dir.join("domains.csv");
+    std::fs::write(&input, "domain\nexample.com\n").unwrap();
+    let config = dir.join("config.json");
+    std::fs::write(&config, config_json("gemini-2.5-flash", "0")).unwrap();
+
+    let output = catvision(&dir)
+        .args(["--command", "classify", "--input"])
+        .arg(&input)
+        .arg("--config")
+        .arg(&config)
+        .args(["--set", "chunk_size=0"])
+        .assert()
+        .code(2)
+        .get_output()
+        .clone();
+
+    // An error message, no panic
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("chunk_size must be at least 1"), "{}", stderr);
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_missing_input_file_exits_with_input_parse_error() {
+    let dir = test_dir("missing-input");
+    let input = dir.join("domains.csv");
+    let config = dir.join("config.json");
+    std::fs::write(&config, config_json("gemini-2.5-flash", "0")).unwrap();
+
+    let output = catvision(&dir)
+        .args(["--command", "classify", "--input"])
+        .arg(&input)
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .code(3)
+        .get_output()
+        .clone();
+    assert!(!String::from_utf8(output.stderr).unwrap().contains("panicked"));
+
+    let summary = error_summary(&input);
+    assert_eq!(summary["cause"], "input_parse_error");
+    assert!(summary["message"].as_str().unwrap().contains("domains.csv not found"));
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_missing_domain_column_exits_with_input_parse_error() {
+    let dir = test_dir("parse");
+    let input = dir.join("domains.csv");
+    std::fs::write(&input, "host;category_by_olfeo\nexample.com;Email\n").unwrap();
+    let config = dir.join("config.json");
+    std::fs::write(&config, config_json("gemini-2.5-flash", "0")).unwrap();
+
+    catvision(&dir)
+        .args(["--command", "classify", "--input"])
+        .arg(&input)
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .code(3);
+
+    let summary = error_summary(&input);
+    assert_eq!(summary["exit_code"], 3);
+    assert_eq!(summary["cause"], "input_parse_error");
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_older_output_schema_exits_with_input_parse_error() {
+    let dir = test_dir("schema");
+    let input = dir.join("domains.csv");
+    std::fs::write(&input, "# schema_version: 0\ndomain;llm_category_1\nexample.com;Email\n").unwrap();
+    let config = dir.join("config.json");
+    std::fs::write(&config, config_json("heuristic", "null")).unwrap();
+
+    catvision(&dir)
+        .args(["--command", "classify", "--input"])
+        .arg(&input)
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .code(3);
+
+    let summary = error_summary(&input);
+    assert_eq!(summary["cause"], "input_parse_error");
+    assert!(summary["message"].as_str().unwrap().contains("written with output schema version 0, older than version 3"));
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_schema_command_lists_the_columns() {
+    let dir = test_dir("schema-command");
+
+    let output = catvision(&dir).args(["--command", "schema"]).assert().success().get_output().stdout.clone();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.starts_with("Output schema version 3\n"));
+    assert!(output.contains("llm_category_<n>"));
+
+    catvision(&dir).args(["--command", "schema", "--schema-version", "0"]).assert().code(2);
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_rejected_credentials_exit_with_provider_auth_error() {
+    let dir = test_dir("auth");
+    let input = dir.join("domains.csv");
+    std::fs::write(&input, "domain\nexample.com\n").unwrap();
+    let config = dir.join("config.json");
+    std::fs::write(&config, config_json("gemini-2.5-flash", "0")).unwrap();
+
+    catvision(&dir)
+        .env("CATVISION_GEMINI_ENDPOINT", mock_provider(401, ""))
+        .args(["--command", "classify", "--input"])
+        .arg(&input)
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .code(4);
+
+    assert_eq!(error_summary(&input)["cause"], "provider_auth_error");
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_failures_over_threshold_exit_with_partial_failure() {
+    let dir = test_dir("partial");
+    let input = dir.join("domains.csv");
+    std::fs::write(&input, "domain\nok.example.com\nlost.example.com\nlost.example.org\nlost.example.net\n").unwrap();
+    let config = dir.join("config.json");
+    std::fs::write(&config, config_json("gemini-2.5-flash", "0")).unwrap();
+
+    // The provider only ever answers for one domain out of four
+    catvision(&dir)
+        .env("CATVISION_GEMINI_ENDPOINT", mock_provider(200, r#"{"ok.example.com": ["Email"]}"#))
+        .args(["--command", "classify", "--input"])
+        .arg(&input)
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .code(6);
+
+    let summary = error_summary(&input);
+    assert_eq!(summary["exit_code"], 6);
+    assert_eq!(summary["cause"], "partial_failure");
+    assert!(summary["message"].as_str().unwrap().starts_with("3 of 4 domains"));
+    // Outputs are still written for the answered domain
+    let outputs = std::fs::read_dir(dir.join("outputs")).unwrap().count();
+    assert!(outputs > 1);
+    // The lost domains were retried once, then listed with the reason of their last failure
+    let garbage = std::fs::read_to_string(dir.join("garbage.csv")).unwrap();
+    let mut rows: Vec<&str> = garbage.lines().collect();
+    assert_eq!(rows.remove(0), "domain,reason");
+    rows.sort();
+    assert_eq!(rows.iter().map(|row| row.split(',').next().unwrap()).collect::<Vec<_>>(), ["lost.example.com", "lost.example.net", "lost.example.org"]);
+    assert!(rows.iter().all(|row| row.ends_with(",Max retries reached after 3 attempts")), "{:?}", rows);
+    let stats: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(dir.join("outputs").join("domains.stats.json")).unwrap()).unwrap();
+    assert_eq!(stats["first_pass_processed"], 1);
+    assert_eq!(stats["retry_pass"], serde_json::json!({ "retried": 3, "processed": 0 }));
+
+    // A successful run of the same input clears the summary
+    std::fs::write(&input, "domain\nok.example.com\n").unwrap();
+    catvision(&dir)
+        .env("CATVISION_GEMINI_ENDPOINT", mock_provider(200, r#"{"ok.example.com": ["Email"]}"#))
+        .args(["--command", "classify", "--input"])
+        .arg(&input)
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .success();
+    assert!(!dir.join("outputs").join("error.json").exists());
+
+    let _ = std::fs::remove_dir_all(dir);
+}
@@ -0,0 +1,210 @@
+mod common;
+
+use common::{catvision, config_json, test_dir};
+use std::path::{Path, PathBuf};
+
+/// CSV output of the run in `run_dir`
+fn csv_output(run_dir: &Path) -> PathBuf {
+    std::fs::read_dir(run_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.extension().is_some_and(|ext| ext == "csv"))
+        .unwrap()
+}
+
+#[test]
+fn test_classification_with_heuristic_provider_runs_offline() {
+    let dir = test_dir("heuristic");
+    let input = dir.join("domains.csv");
+    std::fs::write(&input, "domain\nmail.google.com\nwww.lemonde.fr\nwww.impots.gouv.fr\nwww.example.com\n").unwrap();
+    let config = dir.join("config.json");
+    std::fs::write(&config, config_json("heuristic", "null")).unwrap();
+
+    // No API key and nothing listening: any request would fail
+    catvision(&dir)
+        .env_remove("MY_GEMINI_API_KEY")
+        .env("CATVISION_GEMINI_ENDPOINT", "http://127.0.0.1:9")
+        .args(["--command", "classify", "--input"])
+        .arg(&input)
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .success();
+
+    let output = std::fs::read_to_string(csv_output(&dir.join("outputs"))).unwrap();
+    assert_eq!(output.lines().collect::<Vec<_>>(), vec![
+        "# schema_version: 3",
+        "domain;llm_category_1",
+        "mail.google.com;Email",
+        "www.lemonde.fr;Médias / Actualités",
+        "www.impots.gouv.fr;Gouvernement / Administration",
+        "www.example.com;Autres",
+    ]);
+    // Nothing was sent, so only the manifest of the run is stored
+    let stored = std::fs::read_dir(dir.join("outputs").join("responses"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(stored, vec!["domains.csv.run.json"]);
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_classification_in_batches_appends_every_batch() {
+    let dir = test_dir("heuristic-batches");
+    let input = dir.join("domains.csv");
+    std::fs::write(&input, "domain\nmail.google.com\nwww.lemonde.fr\nwww.impots.gouv.fr\nwww.example.com\nmail.google.com\n").unwrap();
+    let config = dir.join("config.json");
+    let batched = config_json("heuristic", "null").replacen("\"chunk_size\": 10,", "\"chunk_size\": 10, \"stream_batch_size\": 2,", 1);
+    std::fs::write(&config, batched).unwrap();
+
+    catvision(&dir)
+        .env_remove("MY_GEMINI_API_KEY")
+        .env("CATVISION_GEMINI_ENDPOINT", "http://127.0.0.1:9")
+        .args(["--command", "classify", "--input"])
+        .arg(&input)
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .success();
+
+    // Same rows as a whole-file run, the domain repeated in another batch is not merged
+    let output = std::fs::read_to_string(csv_output(&dir.join("outputs"))).unwrap();
+    assert_eq!(output.lines().collect::<Vec<_>>(), vec![
+        "# schema_version: 3",
+        "domain;llm_category_1",
+        "mail.google.com;Email",
+        "www.lemonde.fr;Médias / Actualités",
+        "www.impots.gouv.fr;Gouvernement / Administration",
+        "www.example.com;Autres",
+        "mail.google.com;Email",
+    ]);
+    let stats: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(dir.join("outputs").join("domains.stats.json")).unwrap()).unwrap();
+    assert_eq!(stats["domains"], 5);
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_resume_skips_the_domains_already_classified() {
+    for batched in [false, true] {
+        let dir = test_dir(if batched { "resume-batches" } else { "resume" });
+        let input = dir.join("domains.csv");
+        std::fs::write(&input, "domain\nmail.google.com\nwww.lemonde.fr\nwww.impots.gouv.fr\nwww.example.com\n").unwrap();
+        let config = dir.join("config.json");
+        let mut config_content = config_json("heuristic", "null");
+        if batched {
+            config_content = config_content.replacen("\"chunk_size\": 10,", "\"chunk_size\": 10, \"stream_batch_size\": 2,", 1);
+        }
+        std::fs::write(&config, config_content).unwrap();
+        let classify = |resume: bool| {
+            let mut command = catvision(&dir);
+            command
+                .env_remove("MY_GEMINI_API_KEY")
+                .env("CATVISION_GEMINI_ENDPOINT", "http://127.0.0.1:9")
+                .args(["--command", "classify", "--input"])
+                .arg(&input)
+                .arg("--config")
+                .arg(&config);
+            if resume {
+                command.arg("--resume");
+            }
+            String::from_utf8(command.assert().success().get_output().stdout.clone()).unwrap()
+        };
+
+        // A previous run that died before www.impots.gouv.fr, www.example.com left without category
+        classify(false);
+        let output = csv_output(&dir.join("outputs"));
+        std::fs::write(&output, [
+            "# schema_version: 3",
+            "domain;llm_category_1",
+            "mail.google.com;Email",
+            "www.lemonde.fr;*RED*Santé",
+            "www.example.com;",
+        ].join("\n") + "\n").unwrap();
+
+        let stdout = classify(true);
+        assert!(stdout.contains("2 domains already classified are skipped"), "{}", stdout);
+        // The previous categories are kept, the other domains classified again, in the input order
+        let content = std::fs::read_to_string(&output).unwrap();
+        assert_eq!(content.lines().collect::<Vec<_>>(), vec![
+            "# schema_version: 3",
+            "domain;llm_category_1",
+            "mail.google.com;Email",
+            "www.lemonde.fr;Santé",
+            "www.impots.gouv.fr;Gouvernement / Administration",
+            "www.example.com;Autres",
+        ], "batched: {}", batched);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
+
+#[test]
+fn test_only_domains_merged_into_a_previous_output() {
+    for batched in [false, true] {
+        let dir = test_dir(if batched { "only-domains-batches" } else { "only-domains" });
+        let input = dir.join("domains.csv");
+        std::fs::write(&input, "domain\nmail.google.com\nwww.lemonde.fr\nwww.impots.gouv.fr\nwww.example.com\n").unwrap();
+        let config = dir.join("config.json");
+        let mut config_content = config_json("heuristic", "null");
+        if batched {
+            config_content = config_content.replacen("\"chunk_size\": 10,", "\"chunk_size\": 10, \"stream_batch_size\": 2,", 1);
+        }
+        std::fs::write(&config, config_content).unwrap();
+        let only_domains = dir.join("review.txt");
+        std::fs::write(&only_domains, "# looked wrong\nwww.lemonde.fr\nwww.impots.gouv.fr\nabsent.example.com\n").unwrap();
+        // Reviewed output with edits of its own, left untouched but for the listed domains
+        let reviewed = dir.join("reviewed.csv");
+        let reviewed_content = [
+            "# schema_version: 3",
+            "domain;llm_category_1",
+            "mail.google.com;Email",
+            "www.lemonde.fr;*RED*Santé",
+            "www.example.com;Jeux",
+            "www.impots.gouv.fr;Autres",
+            "",
+        ].join("\n");
+        std::fs::write(&reviewed, &reviewed_content).unwrap();
+
+        let output = catvision(&dir)
+            .env_remove("MY_GEMINI_API_KEY")
+            .env("CATVISION_GEMINI_ENDPOINT", "http://127.0.0.1:9")
+            .args(["--command", "classify", "--input"])
+            .arg(&input)
+            .arg("--config")
+            .arg(&config)
+            .arg("--only-domains")
+            .arg(&only_domains)
+            .arg("--merge-into")
+            .arg(&reviewed)
+            .args(["--set", "chunk_size=1"])
+            .assert()
+            .success()
+            .get_output()
+            .clone();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stdout.contains("Run restricted to 2 of the 3 listed domains"), "{}", stdout);
+        assert!(stderr.contains("1 listed domains are not in the input: absent.example.com"), "{}", stderr);
+        assert!(stdout.contains("2 rows updated and 0 appended"), "{}", stdout);
+
+        // The run output only has the listed domains, named after the overridden chunk size
+        let run_output = csv_output(&dir.join("outputs"));
+        assert!(run_output.to_string_lossy().contains("chunk_1"), "{}", run_output.display());
+        assert_eq!(std::fs::read_to_string(run_output).unwrap().lines().skip(2).collect::<Vec<_>>(), vec![
+            "www.lemonde.fr;Médias / Actualités",
+            "www.impots.gouv.fr;Gouvernement / Administration",
+        ], "batched: {}", batched);
+        assert_eq!(
+            std::fs::read_to_string(&reviewed).unwrap(),
+            reviewed_content
+                .replace("www.lemonde.fr;*RED*Santé", "www.lemonde.fr;Médias / Actualités")
+                .replace("www.impots.gouv.fr;Autres", "www.impots.gouv.fr;Gouvernement / Administration"),
+            "batched: {}", batched
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use common::{catvision, config_json, mock_provider, mock_webhook, test_dir};
+use std::path::Path;
+use std::time::Duration;
+
+/// Writes a configuration for `model` notifying `notify`
+fn write_config(dir: &Path, model: &str, notify: serde_json::Value) -> std::path::PathBuf {
+    let mut config: serde_json::Value = serde_json::from_str(&config_json(model, "0")).unwrap();
+    config["notify"] = notify;
+    let path = dir.join("config.json");
+    std::fs::write(&path, config.to_string()).unwrap();
+    path
+}
+
+#[test]
+fn test_successful_run_is_notified_after_a_retry() {
+    let dir = test_dir("notify-success");
+    let input = dir.join("domains.csv");
+    std::fs::write(&input, "domain\nmail.google.com\nwww.lemonde.fr\n").unwrap();
+    // The first attempt is answered with a 500
+    let (url, payloads) = mock_webhook(1);
+    let config = write_config(&dir, "heuristic", serde_json::json!({ "kind": "webhook", "url": url, "on": ["success"] }));
+
+    catvision(&dir)
+        .args(["--command", "classify", "--input"])
+        .arg(&input)
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .success();
+
+    let first = payloads.recv_timeout(Duration::from_secs(5)).unwrap();
+    let payload = payloads.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(first, payload);
+    assert_eq!(payload["status"], "success");
+    assert_eq!(payload["command"], "classify");
+    assert_eq!(payload["exit_code"], 0);
+    assert!(payload["cause"].is_null());
+    assert_eq!(payload["counts"], serde_json::json!({ "total": 2, "answered": 2, "failed": 0 }));
+    assert_eq!(payload["cost_eur"], 0.0);
+    assert_eq!(payload["output_dir"], dir.join("outputs").display().to_string());
+    let outputs = payload["outputs"].as_array().unwrap();
+    assert!(outputs.iter().any(|output| output.as_str().unwrap().ends_with(".csv")));
+    assert!(outputs.iter().any(|output| output.as_str().unwrap().ends_with("domains.stats.json")));
+    assert!(payload["text"].as_str().unwrap().starts_with("CatVision classify on "));
+    assert!(payload["text"].as_str().unwrap().contains("success, 2/2 domains"));
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_failed_run_is_notified_with_its_exit_code() {
+    let dir = test_dir("notify-failure");
+    let input = dir.join("domains.csv");
+    std::fs::write(&input, "domain\nexample.com\n").unwrap();
+    let (url, payloads) = mock_webhook(0);
+    let config = write_config(&dir, "gemini-2.5-flash", serde_json::json!({
+        "kind": "webhook",
+        "url": url,
+        "template": "{status} ({exit_code}) on {input}: {message}",
+    }));
+
+    catvision(&dir)
+        .env("CATVISION_GEMINI_ENDPOINT", mock_provider(401, ""))
+        .args(["--command", "classify", "--input"])
+        .arg(&input)
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .code(4);
+
+    let payload = payloads.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(payload["status"], "failure");
+    assert_eq!(payload["exit_code"], 4);
+    assert_eq!(payload["cause"], "provider_auth_error");
+    assert_eq!(payload["counts"], serde_json::json!({ "total": 1, "answered": 0, "failed": 1 }));
+    assert!(payload["text"]
+        .as_str()
+        .unwrap()
+        .starts_with(&format!("failure (4) on {}: ", input.display())));
+    // Only one run, only one notification
+    assert!(payloads.recv_timeout(Duration::from_millis(200)).is_err());
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_unreachable_webhook_keeps_the_exit_code() {
+    let dir = test_dir("notify-unreachable");
+    let input = dir.join("domains.csv");
+    std::fs::write(&input, "domain\nmail.google.com\n").unwrap();
+    // Nothing listens on the discard port
+    let config = write_config(&dir, "heuristic", serde_json::json!({ "kind": "webhook", "url": "http://127.0.0.1:9/hook", "on": ["success"] }));
+
+    let output = catvision(&dir)
+        .args(["--command", "classify", "--input"])
+        .arg(&input)
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .success()
+        .get_output()
+        .clone();
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Failed to send the run notification to http://127.0.0.1:9/hook"));
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_outcomes_outside_the_on_list_are_not_notified() {
+    let dir = test_dir("notify-filtered");
+    let input = dir.join("domains.csv");
+    std::fs::write(&input, "domain\nmail.google.com\n").unwrap();
+    let (url, payloads) = mock_webhook(0);
+    // Failures and budget stops only, by default
+    let config = write_config(&dir, "heuristic", serde_json::json!({ "kind": "webhook", "url": url }));
+
+    catvision(&dir)
+        .args(["--command", "classify", "--input"])
+        .arg(&input)
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .success();
+    assert!(payloads.recv_timeout(Duration::from_millis(200)).is_err());
+
+    let _ = std::fs::remove_dir_all(dir);
+}
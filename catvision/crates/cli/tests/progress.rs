@@ -0,0 +1,38 @@
+mod common;
+
+use common::{catvision, config_json, mock_provider, test_dir};
+
+#[test]
+fn test_quiet_suppresses_the_progress_report() {
+    let dir = test_dir("progress");
+    let input = dir.join("domains.csv");
+    std::fs::write(&input, "domain\nok.example.com\nlost.example.com\n").unwrap();
+    let config = dir.join("config.json");
+    std::fs::write(&config, config_json("gemini-2.5-flash", "0")).unwrap();
+    let endpoint = mock_provider(200, r#"{"ok.example.com": ["Email"]}"#);
+
+    // The final summary is printed even though a domain failed
+    let output = catvision(&dir)
+        .env("CATVISION_GEMINI_ENDPOINT", &endpoint)
+        .args(["--command", "classify", "--input"])
+        .arg(&input)
+        .arg("--config")
+        .arg(&config)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("LLM requests done in"), "{}", stdout);
+    assert!(stdout.contains("1 of 2 domains processed, 1 failed (50.0%)"), "{}", stdout);
+
+    let output = catvision(&dir)
+        .env("CATVISION_GEMINI_ENDPOINT", &endpoint)
+        .args(["--command", "classify", "--quiet", "--input"])
+        .arg(&input)
+        .arg("--config")
+        .arg(&config)
+        .output()
+        .unwrap();
+    assert!(!String::from_utf8(output.stdout).unwrap().contains("LLM requests done"));
+
+    let _ = std::fs::remove_dir_all(dir);
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use common::{catvision, config_json, mock_provider, test_dir};
+use std::path::{Path, PathBuf};
+
+/// CSV output of the run in `run_dir`
+fn csv_output(run_dir: &Path) -> PathBuf {
+    std::fs::read_dir(run_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.extension().is_some_and(|ext| ext == "csv"))
+        .unwrap()
+}
+
+#[test]
+fn test_reparse_regenerates_outputs_from_stored_responses() {
+    let dir = test_dir("reparse");
+    let input = dir.join("domains.csv");
+    std::fs::write(&input, "domain\na.example.com\nb.example.com\nc.example.com\n").unwrap();
+    let config = dir.join("config.json");
+    // Without a fallback category, a label outside of the taxonomy sends the domain again
+    let config_body = config_json("gemini-2.5-flash", "0").replace(r#""max_failure_ratio""#, r#""invalid_category_fallback": null, "max_failure_ratio""#);
+    std::fs::write(&config, config_body).unwrap();
+    let run_dir = dir.join("outputs");
+
+    catvision(&dir)
+        .env("CATVISION_GEMINI_ENDPOINT", mock_provider(200, r#"{"a.example.com": ["Email"], "b.example.com": ["Email"], "c.example.com": ["Email"]}"#))
+        .args(["--command", "classify", "--input"])
+        .arg(&input)
+        .arg("--config")
+        .arg(&config)
+        .assert()
+        .success();
+
+    // One response for the single chunk, next to the manifest of the run
+    let store = run_dir.join("responses");
+    assert!(store.join("domains.csv.run.json").exists());
+    let responses = std::fs::read_dir(&store)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| !path.to_string_lossy().ends_with(".run.json"))
+        .collect::<Vec<_>>();
+    assert_eq!(responses.len(), 1);
+
+    // Corrupt two labels: one the parser corrects, one it rejects
+    let body = std::fs::read_to_string(&responses[0]).unwrap();
+    let corrupted = body
+        .replace(r#"a.example.com\": [\"Email\"]"#, r#"a.example.com\": [\"EMAIL\"]"#)
+        .replace(r#"c.example.com\": [\"Email\"]"#, r#"c.example.com\": [\"Not a category at all\"]"#);
+    assert_ne!(body, corrupted);
+    std::fs::write(&responses[0], corrupted).unwrap();
+
+    // No API key and nothing listening: any request would fail
+    let reparse = || {
+        catvision(&dir)
+            .env_remove("MY_GEMINI_API_KEY")
+            .env("CATVISION_GEMINI_ENDPOINT", "http://127.0.0.1:9")
+            .args(["--command", "reparse", "--run-dir"])
+            .arg(&run_dir)
+            .assert()
+            .success();
+        std::fs::read_to_string(csv_output(&run_dir)).unwrap()
+    };
+
+    let output = reparse();
+    assert_eq!(output.lines().collect::<Vec<_>>(), vec![
+        "# schema_version: 3",
+        "domain;llm_category_1",
+        "a.example.com;Email",
+        "b.example.com;Email",
+        "c.example.com;",
+    ]);
+    // The rejected domain has no stored response for its retry
+    assert_eq!(std::fs::read_to_string(run_dir.join("requery_domains.txt")).unwrap(), "c.example.com\n");
+
+    assert_eq!(reparse(), output);
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_reparse_without_stored_run_exits_with_config_error() {
+    let dir = test_dir("reparse-empty");
+
+    catvision(&dir)
+        .args(["--command", "reparse", "--run-dir"])
+        .arg(dir.join("outputs"))
+        .assert()
+        .code(2);
+    assert!(dir.join("outputs").join("error.json").exists());
+
+    let _ = std::fs::remove_dir_all(dir);
+}
@@ -1,7 +1,14 @@
-use std::path::{PathBuf};
+use std::path::{Path, PathBuf};
+use utils::category::check_category_validity;
+use utils::env::xdg_config_dir;
+use utils::error::CatVisionError;
 
-#[derive(Debug, Clone, serde::Deserialize)]
+/// Name of the configuration file looked up in the XDG configuration directory
+pub const CONFIG_FILE_NAME: &str = "config.json";
+
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
 /// Supported format for input and output
+#[serde(deny_unknown_fields)]
 pub struct SupportedFormat {
     /// Whether the format is supported for input
     pub input: bool,
@@ -9,23 +16,195 @@ pub struct SupportedFormat {
     pub output: bool,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+/// Supported CSV format for input and output, with dialect options
+#[serde(deny_unknown_fields)]
+pub struct SupportedCsvFormat {
+    /// Whether the format is supported for input
+    pub input: bool,
+    /// Whether the format is supported for output
+    pub output: bool,
+    /// Field delimiter used when writing output files
+    #[serde(default = "default_csv_delimiter")]
+    pub delimiter: char,
+    /// Field delimiter used when reading input files
+    #[serde(default = "default_csv_delimiter")]
+    pub input_delimiter: char,
+    /// Whether numeric output fields use a comma as decimal separator
+    #[serde(default)]
+    pub decimal_comma: bool,
+    /// Whether output records are terminated with CRLF instead of LF
+    #[serde(default)]
+    pub crlf_line_endings: bool,
+}
+
+/// Default CSV delimiter, matching the files produced so far
+fn default_csv_delimiter() -> char {
+    ';'
+}
+
+impl Default for SupportedCsvFormat {
+    fn default() -> Self {
+        Self {
+            input: true,
+            output: true,
+            delimiter: default_csv_delimiter(),
+            input_delimiter: default_csv_delimiter(),
+            decimal_comma: false,
+            crlf_line_endings: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+/// Directory watched by the `watch` command for new input files
+#[serde(deny_unknown_fields)]
+pub struct WatchConfig {
+    /// Directory receiving the input drops
+    pub dir: PathBuf,
+    /// Glob matched against the file names, `*` matches any sequence of characters and `?` a single one
+    #[serde(default = "default_watch_pattern")]
+    pub pattern: String,
+    /// Command run on each new file, `classify` or `describe`
+    #[serde(default = "default_watch_command")]
+    pub command: String,
+    /// Seconds the size of a file must stay unchanged before it is processed
+    #[serde(default = "default_watch_stable_secs")]
+    pub stable_secs: u64,
+    /// Seconds between two scans of the directory, the only wake-up when file events are unavailable
+    #[serde(default = "default_watch_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+}
+
+/// Weekly exports are CSV files
+fn default_watch_pattern() -> String {
+    "*.csv".to_string()
+}
+
+fn default_watch_command() -> String {
+    "classify".to_string()
+}
+
+fn default_watch_stable_secs() -> u64 {
+    10
+}
+
+fn default_watch_poll_interval_secs() -> u64 {
+    5
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+/// Channel notified at the end of a run
+pub enum NotifyKind {
+    /// POST of the run summary to a webhook (Slack and Teams incoming webhooks read its `text` field)
+    Webhook,
+    /// No notification
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+/// Outcome of a run that triggers a notification
+pub enum NotifyEvent {
+    /// Run finished with all its domains, or few enough left without a result
+    Success,
+    /// Run ended with any other non-zero exit code
+    Failure,
+    /// Run stopped by the budget cap
+    BudgetStop,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+/// Notification sent at the end of a classify or describe run
+#[serde(deny_unknown_fields)]
+pub struct NotifyConfig {
+    /// Channel of the notification
+    pub kind: NotifyKind,
+    /// URL of the webhook, required by the `webhook` kind
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Outcomes notified, failures and budget stops by default
+    #[serde(default = "default_notify_on")]
+    pub on: Vec<NotifyEvent>,
+    /// Template of the plain-text message, see the README for its placeholders
+    #[serde(default)]
+    pub template: Option<String>,
+}
+
+/// Successful runs are not notified by default, nobody needs to be woken up for them
+fn default_notify_on() -> Vec<NotifyEvent> {
+    vec![NotifyEvent::Failure, NotifyEvent::BudgetStop]
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+/// API the LLM requests are sent to
+pub enum ProviderKind {
+    /// Gemini generateContent API, with explicit caching, URL context and Google search
+    #[default]
+    Gemini,
+    /// OpenAI `/v1/chat/completions` API, also served by vLLM, Ollama and other compatible servers
+    #[serde(rename = "openai")]
+    OpenAi,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+/// How the progress of the LLM requests of a run is reported
+pub enum ProgressMode {
+    /// Progress bar redrawn on stderr
+    Bar,
+    /// Single-line summary printed periodically, readable in log files
+    #[default]
+    Log,
+    /// No progress report
+    Quiet,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 /// Configuration for the CatVision application
+#[serde(deny_unknown_fields)]
 pub struct Config {
     /// Maximum number of threads to use
     pub max_threads: usize,
     /// Supported CSV format for input and output
-    pub support_csv: SupportedFormat,
+    pub support_csv: SupportedCsvFormat,
     /// Supported HTML format for input and output
     pub support_html: SupportedFormat,
+    /// Supported JSON lines format, `input` reads every input file as JSON lines; JSON output is not supported
+    #[serde(default)]
+    pub support_json: SupportedFormat,
     /// Maximum number of domain propositions to consider
     pub max_domain_propositions: usize,
+    /// Category given in place of a label outside of the taxonomy returned by the LLM,
+    /// the domain is sent again instead when unset
+    #[serde(default = "default_invalid_category_fallback")]
+    pub invalid_category_fallback: Option<String>,
     /// Models to use for LLM classification
     pub model: Vec<String>,
+    /// API serving the model, Gemini when unset
+    #[serde(default)]
+    pub provider: ProviderKind,
+    /// Base URL of the OpenAI-compatible server, without the `/v1` path (e.g. `http://localhost:11434` for Ollama)
+    #[serde(default = "default_openai_base_url")]
+    pub openai_base_url: String,
+    /// Price of the OpenAI-compatible model in dollars per thousand tokens, zero for a self-hosted server
+    #[serde(default)]
+    pub openai_usd_per_1k_tokens: f64,
     /// Size of chunks to process
     pub chunk_size: usize,
-    /// Thinking budget for the LLM
-    pub thinking_budget: i64,
+    /// Whether the input values are URLs: they are classified and deduplicated on their host and first path segment,
+    /// the dictionary still applies to the host
+    #[serde(default)]
+    pub url_mode: bool,
+    /// Number of domains of a CSV input read, classified and written at once, the whole input when unset.
+    /// Caps the memory of large inputs; domains repeated in different batches are not merged
+    #[serde(default)]
+    pub stream_batch_size: Option<usize>,
+    /// Thinking budget for the LLM, no thinking configuration is sent when unset
+    #[serde(default)]
+    pub thinking_budget: Option<i64>,
     /// Whether to use explicit caching for Gemini
     pub use_gemini_explicit_caching: bool,
     /// Whether to use URL context for Gemini
@@ -34,6 +213,83 @@ pub struct Config {
     pub use_gemini_google_search: bool,
     /// Custom cache duration for Gemini
     pub use_gemini_custom_cache_duration: Option<String>,
+    /// Whether to replace domains with HMAC pseudonyms before sending them to the LLM
+    #[serde(default)]
+    pub anonymize_domains: bool,
+    /// OTLP/HTTP endpoint receiving traces, `OTEL_EXPORTER_OTLP_ENDPOINT` is used when unset
+    #[serde(default)]
+    pub otel_endpoint: Option<String>,
+    /// Cost cap of a run in euros, no new chunk is launched once it is exceeded
+    #[serde(default)]
+    pub max_cost_eur: Option<f64>,
+    /// Factor by which the projected cost of a run may exceed `max_cost_eur` before it is stopped early
+    #[serde(default = "default_budget_projection_factor")]
+    pub budget_projection_factor: f64,
+    /// Whether a CSV input missing the `categories_manual` or Olfeo category columns is rejected,
+    /// when false the run goes on with a warning and without accuracy statistics
+    #[serde(default = "default_strict_columns")]
+    pub strict_columns: bool,
+    /// Mapping file from Olfeo categories to the LLM taxonomy (`olfeo_category;category`), Olfeo categories are compared as is when unset
+    #[serde(default)]
+    pub olfeo_mapping: Option<PathBuf>,
+    /// Whether request fields unsupported by the model are dropped with a warning instead of rejecting the configuration
+    #[serde(default)]
+    pub lenient_capabilities: bool,
+    /// Directory watched for new input files by the `watch` command
+    #[serde(default)]
+    pub watch: Option<WatchConfig>,
+    /// Share of the domains left without a result above which the run fails with the partial failure exit code
+    #[serde(default = "default_max_failure_ratio")]
+    pub max_failure_ratio: f64,
+    /// Analyst guidance for this run, appended to the prompt of every request (overridden by `--extra-instructions`)
+    #[serde(default)]
+    pub extra_instructions: Option<String>,
+    /// Notification sent at the end of a run, none when unset
+    #[serde(default)]
+    pub notify: Option<NotifyConfig>,
+    /// Number of LLM requests sent per minute at most, shared by all the threads; not limited when unset
+    #[serde(default)]
+    pub max_requests_per_minute: Option<u64>,
+    /// Number of estimated prompt tokens sent to the LLM per minute at most, shared by all the threads; not limited when unset
+    #[serde(default)]
+    pub max_tokens_per_minute: Option<u64>,
+    /// Whether the categories of the domains classified by previous runs are reused instead of being requested again
+    #[serde(default)]
+    pub use_local_cache: bool,
+    /// Directory of the local cache of categories, shared by the runs with the same model and prompt
+    #[serde(default = "default_local_cache_path")]
+    pub local_cache_path: PathBuf,
+    /// Progress report of the LLM requests: processed domains, ETA, cost and failure rate
+    #[serde(default)]
+    pub progress: ProgressMode,
+}
+
+/// Labels outside of the taxonomy are classified in "Autres" by default
+fn default_invalid_category_fallback() -> Option<String> {
+    Some("Autres".to_string())
+}
+
+fn default_openai_base_url() -> String {
+    "https://api.openai.com".to_string()
+}
+
+/// Default projection factor, stops runs projected to cost more than twice the cap
+fn default_budget_projection_factor() -> f64 {
+    2.0
+}
+
+/// Missing input columns are rejected by default
+fn default_strict_columns() -> bool {
+    true
+}
+
+fn default_local_cache_path() -> PathBuf {
+    PathBuf::from(".catvision_cache")
+}
+
+/// Default failure ratio, runs leaving more than 10% of the domains without a result fail
+fn default_max_failure_ratio() -> f64 {
+    0.1
 }
 
 /// Default configuration values
@@ -42,16 +298,39 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             max_threads: 1,
-            support_csv: SupportedFormat { input: true, output: true },
+            support_csv: SupportedCsvFormat::default(),
             support_html: SupportedFormat { input: false, output: true },
+            support_json: SupportedFormat::default(),
             max_domain_propositions: 3,
+            invalid_category_fallback: default_invalid_category_fallback(),
             model: vec!["Qwen2.5-Coder-32B-Instruct-AWQ".to_string()],
+            provider: ProviderKind::default(),
+            openai_base_url: default_openai_base_url(),
+            openai_usd_per_1k_tokens: 0.0,
             chunk_size: 100,
-            thinking_budget: 1024,
+            url_mode: false,
+            stream_batch_size: None,
+            thinking_budget: Some(1024),
             use_gemini_explicit_caching: false,
             use_gemini_url_context: false,
             use_gemini_google_search: false,
             use_gemini_custom_cache_duration: None,
+            anonymize_domains: false,
+            otel_endpoint: None,
+            max_cost_eur: None,
+            budget_projection_factor: default_budget_projection_factor(),
+            strict_columns: default_strict_columns(),
+            olfeo_mapping: None,
+            lenient_capabilities: false,
+            watch: None,
+            max_failure_ratio: default_max_failure_ratio(),
+            extra_instructions: None,
+            notify: None,
+            max_requests_per_minute: None,
+            max_tokens_per_minute: None,
+            use_local_cache: false,
+            local_cache_path: default_local_cache_path(),
+            progress: ProgressMode::default(),
         }
     }
 }
@@ -61,18 +340,96 @@ impl Config {
 
     /// Creates a new Config instance, loading from a file if provided
     ///
+    /// The configuration is searched in this order:
+    /// 1. `config_file`, when provided
+    /// 2. `$XDG_CONFIG_HOME/catvision/config.json` (or `$HOME/.config/catvision/config.json`)
+    /// 3. `Config::default()`
+    ///
     /// # Arguments
     ///
     /// * `config_file` - Optional path to a JSON configuration file
     ///
     /// # Errors
     ///
-    /// Panics if the configuration file cannot be read or parsed
-    pub fn new(config_file: Option<PathBuf>) -> Self {
-        match config_file {
-            Some(path) => Self::load_from_file(path),
+    /// Returns a `Config` error if the configuration file cannot be read or parsed, has a field this version does not
+    /// know, or fails [`Config::validate`]
+    pub fn new(config_file: Option<PathBuf>) -> Result<Self, CatVisionError> {
+        let config = match config_file.or_else(Self::find_user_config) {
+            Some(path) => Self::try_load_from_file(&path)?,
             None => Self::default(),
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks the values a run cannot start with, which the file format alone does not rule out
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Config` error naming the field if `chunk_size` is 0, `model` is empty or `invalid_category_fallback`
+    /// is not a category
+    pub fn validate(&self) -> Result<(), CatVisionError> {
+        if self.chunk_size == 0 {
+            return Err(CatVisionError::Config("chunk_size must be at least 1".to_string()));
+        }
+        if self.model.is_empty() {
+            return Err(CatVisionError::Config("model must list at least one model".to_string()));
+        }
+        if let Some(fallback) = &self.invalid_category_fallback {
+            if check_category_validity(fallback).is_none() {
+                return Err(CatVisionError::Config(format!("invalid_category_fallback {} is not a category of the taxonomy", fallback)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the `key=value` overrides of the command line, on top of the configuration found by the lookup
+    ///
+    /// Keys are the fields of the configuration file, with dots for the fields of a section
+    /// (`support_csv.delimiter=,`). Values are read as JSON, and as a plain string when they are not valid JSON;
+    /// a single value sets a list field to a list of that value.
+    ///
+    /// # Arguments
+    ///
+    /// * `overrides` - Overrides in command line order, a later one wins over an earlier one on the same field
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the override if it has no `=`, names an unknown field or a section not set
+    /// in the configuration, or gives a field a value of the wrong type
+    pub fn apply_overrides(&mut self, overrides: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        if overrides.is_empty() {
+            return Ok(());
+        }
+
+        let mut json = serde_json::to_value(&*self)?;
+        for item in overrides {
+            let (key, value) = item
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid override {}, expected key=value", item))?;
+            let mut field = &mut json;
+            for name in key.trim().split('.') {
+                if field.is_null() {
+                    return Err(format!("Invalid override {}: the section of {} is not set in the configuration", item, name).into());
+                }
+                field = field
+                    .as_object_mut()
+                    .and_then(|section| section.get_mut(name))
+                    .ok_or_else(|| format!("Invalid override {}: unknown configuration field {}", item, key.trim()))?;
+            }
+            let value = serde_json::from_str(value.trim()).unwrap_or_else(|_| serde_json::Value::String(value.to_string()));
+            // A single value of a list field, such as the model, is the whole list
+            *field = if field.is_array() && !value.is_array() { serde_json::Value::Array(vec![value]) } else { value };
         }
+
+        *self = serde_json::from_value(json).map_err(|e| format!("Invalid override: {}", e))?;
+        Ok(())
+    }
+
+    /// Returns the user configuration file from the XDG configuration directory, if it exists
+    pub fn find_user_config() -> Option<PathBuf> {
+        let path = xdg_config_dir()?.join(CONFIG_FILE_NAME);
+        path.is_file().then_some(path)
     }
 
     /// Loads configuration from a JSON file
@@ -83,12 +440,12 @@ impl Config {
     ///
     /// # Errors
     ///
-    /// Panics if the file cannot be read or parsed.
-    fn load_from_file(config_file: PathBuf) -> Self {
+    /// Returns a `Config` error naming the file if it cannot be read or parsed.
+    fn try_load_from_file(config_file: &Path) -> Result<Self, CatVisionError> {
         let config_data = std::fs::read_to_string(config_file)
-            .expect("Failed to read config file");
+            .map_err(|e| CatVisionError::Config(format!("Failed to read config file {}: {}", config_file.display(), e)))?;
         serde_json::from_str(&config_data)
-            .expect("Failed to parse config file")
+            .map_err(|e| CatVisionError::Config(format!("Failed to parse config file {}: {}", config_file.display(), e)))
     }
 }
 
@@ -102,25 +459,47 @@ mod tests {
         assert_eq!(config.max_threads, 1);
         assert!(config.support_csv.input);
         assert!(config.support_csv.output);
+        assert_eq!(config.support_csv.delimiter, ';');
+        assert_eq!(config.support_csv.input_delimiter, ';');
+        assert!(!config.support_csv.decimal_comma);
+        assert!(!config.support_csv.crlf_line_endings);
         assert!(!config.support_html.input);
         assert!(config.support_html.output);
+        assert!(!config.support_json.input);
         assert_eq!(config.max_domain_propositions, 3);
+        assert_eq!(config.invalid_category_fallback.as_deref(), Some("Autres"));
         assert_eq!(config.model, vec!["Qwen2.5-Coder-32B-Instruct-AWQ".to_string()]);
+        assert_eq!(config.provider, ProviderKind::Gemini);
+        assert_eq!(config.openai_base_url, "https://api.openai.com");
+        assert_eq!(config.openai_usd_per_1k_tokens, 0.0);
         assert_eq!(config.chunk_size, 100);
-        assert_eq!(config.thinking_budget, 1024);
+        assert!(!config.url_mode);
+        assert!(config.stream_batch_size.is_none());
+        assert_eq!(config.thinking_budget, Some(1024));
         assert!(!config.use_gemini_explicit_caching);
         assert!(!config.use_gemini_url_context);
         assert!(!config.use_gemini_google_search);
         assert!(config.use_gemini_custom_cache_duration.is_none());
+        assert!(!config.anonymize_domains);
+        assert!(config.strict_columns);
+        assert!(!config.lenient_capabilities);
+        assert!(config.watch.is_none());
+        assert_eq!(config.max_failure_ratio, 0.1);
+        assert!(config.extra_instructions.is_none());
+        assert!(config.notify.is_none());
     }
 
     #[test]
     fn test_load_config_from_file() {
-        let test_config_path = PathBuf::from("src/config/test/config.json");
-        let config = Config::load_from_file(test_config_path);
+        let test_config_path = PathBuf::from("src/test/config.json");
+        let config = Config::try_load_from_file(&test_config_path).unwrap();
         assert_eq!(config.max_threads, 1);
         assert!(config.support_csv.input);
         assert!(config.support_csv.output);
+        assert_eq!(config.support_csv.delimiter, ',');
+        assert_eq!(config.support_csv.input_delimiter, ';');
+        assert!(config.support_csv.decimal_comma);
+        assert!(config.support_csv.crlf_line_endings);
         assert!(!config.support_html.input);
         assert!(config.support_html.output);
         assert_eq!(config.max_domain_propositions, 3);
@@ -129,10 +508,172 @@ mod tests {
         assert_eq!(config.model[2], "claude-sonnet-4".to_string());
         assert_eq!(config.model[3], "gemini-2.5-flash".to_string());
         assert_eq!(config.chunk_size, 50);
-        assert_eq!(config.thinking_budget, 2048);
+        assert_eq!(config.thinking_budget, Some(2048));
         assert!(config.use_gemini_explicit_caching);
         assert!(config.use_gemini_url_context);
         assert!(config.use_gemini_google_search);
         assert_eq!(config.use_gemini_custom_cache_duration.unwrap(), "3600s".to_string());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_load_config_from_xdg_config_home() {
+        let xdg_home = std::env::temp_dir().join(format!("catvision-xdg-{}", std::process::id()));
+        let config_dir = xdg_home.join("catvision");
+        std::fs::create_dir_all(&config_dir).unwrap();
+        std::fs::write(
+            config_dir.join(CONFIG_FILE_NAME),
+            r#"{
+                "max_threads": 4,
+                "support_csv": { "input": true, "output": true },
+                "support_html": { "input": false, "output": false },
+                "max_domain_propositions": 2,
+                "model": ["gemini-2.5-flash"],
+                "chunk_size": 25,
+                "thinking_budget": 0,
+                "use_gemini_explicit_caching": false,
+                "use_gemini_url_context": false,
+                "use_gemini_google_search": false,
+                "use_gemini_custom_cache_duration": null
+            }"#,
+        )
+        .unwrap();
+
+        std::env::set_var("XDG_CONFIG_HOME", &xdg_home);
+        assert_eq!(Config::find_user_config(), Some(config_dir.join(CONFIG_FILE_NAME)));
+
+        // No --config given, the XDG configuration is used instead of the default one
+        let config = Config::new(None).unwrap();
+        assert_eq!(config.max_threads, 4);
+        assert_eq!(config.chunk_size, 25);
+        assert_eq!(config.model, vec!["gemini-2.5-flash".to_string()]);
+        assert!(!config.support_html.output);
+
+        // Without a config file in the XDG directory, fall back to the default
+        std::fs::remove_file(config_dir.join(CONFIG_FILE_NAME)).unwrap();
+        assert_eq!(Config::find_user_config(), None);
+        assert_eq!(Config::new(None).unwrap().chunk_size, 100);
+
+        std::env::remove_var("XDG_CONFIG_HOME");
+        let _ = std::fs::remove_dir_all(xdg_home);
+    }
+
+    #[test]
+    fn test_notify_section() {
+        let config: Config = serde_json::from_str(r#"{
+            "max_threads": 1,
+            "support_csv": { "input": true, "output": true },
+            "support_html": { "input": false, "output": false },
+            "max_domain_propositions": 1,
+            "model": ["gemini-2.5-flash"],
+            "chunk_size": 10,
+            "use_gemini_explicit_caching": false,
+            "use_gemini_url_context": false,
+            "use_gemini_google_search": false,
+            "use_gemini_custom_cache_duration": null,
+            "notify": { "kind": "webhook", "url": "http://127.0.0.1:8080/hook", "on": ["success", "budget_stop"] }
+        }"#).unwrap();
+        let notify = config.notify.unwrap();
+        assert_eq!(notify.kind, NotifyKind::Webhook);
+        assert_eq!(notify.url.as_deref(), Some("http://127.0.0.1:8080/hook"));
+        assert_eq!(notify.on, vec![NotifyEvent::Success, NotifyEvent::BudgetStop]);
+        assert!(notify.template.is_none());
+
+        // Failures and budget stops are notified when `on` is omitted
+        let notify: NotifyConfig = serde_json::from_str(r#"{ "kind": "none" }"#).unwrap();
+        assert_eq!(notify.kind, NotifyKind::None);
+        assert_eq!(notify.on, vec![NotifyEvent::Failure, NotifyEvent::BudgetStop]);
+        assert!(serde_json::from_str::<NotifyConfig>(r#"{ "kind": "email" }"#).is_err());
+    }
+
+    #[test]
+    fn test_new_reports_unreadable_and_invalid_files() {
+        let path = std::env::temp_dir().join(format!("catvision-invalid-config-{}.json", std::process::id()));
+        std::fs::write(&path, "{ not json").unwrap();
+        let err = Config::new(Some(path.clone())).unwrap_err();
+        assert!(matches!(err, CatVisionError::Config(_)));
+        assert!(err.to_string().contains("Failed to parse config file"));
+
+        std::fs::remove_file(&path).unwrap();
+        let err = Config::new(Some(path)).unwrap_err();
+        assert!(err.to_string().contains("Failed to read config file"));
+    }
+
+    #[test]
+    fn test_new_rejects_unknown_fields() {
+        let path = std::env::temp_dir().join(format!("catvision-unknown-field-config-{}.json", std::process::id()));
+        let mut json: serde_json::Value = serde_json::to_value(Config::default()).unwrap();
+        // A misspelt field would otherwise be ignored and its default used
+        json["chunk_sise"] = 10.into();
+        std::fs::write(&path, json.to_string()).unwrap();
+        let err = Config::new(Some(path.clone())).unwrap_err();
+        assert!(err.to_string().contains("unknown field `chunk_sise`"));
+
+        // In a section too
+        let mut json: serde_json::Value = serde_json::to_value(Config::default()).unwrap();
+        json["support_csv"]["delimeter"] = ",".into();
+        std::fs::write(&path, json.to_string()).unwrap();
+        let err = Config::new(Some(path.clone())).unwrap_err();
+        assert!(err.to_string().contains("unknown field `delimeter`"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_new_rejects_zero_chunk_size_and_no_model() {
+        let path = std::env::temp_dir().join(format!("catvision-zero-chunk-config-{}.json", std::process::id()));
+        let mut json: serde_json::Value = serde_json::to_value(Config::default()).unwrap();
+        json["chunk_size"] = 0.into();
+        std::fs::write(&path, json.to_string()).unwrap();
+        let err = Config::new(Some(path.clone())).unwrap_err();
+        assert!(matches!(err, CatVisionError::Config(_)));
+        assert!(err.to_string().contains("chunk_size must be at least 1"));
+
+        json["chunk_size"] = 10.into();
+        json["model"] = serde_json::json!([]);
+        std::fs::write(&path, json.to_string()).unwrap();
+        let err = Config::new(Some(path.clone())).unwrap_err();
+        assert!(err.to_string().contains("model must list at least one model"));
+
+        json["model"] = serde_json::json!(["gemini-2.5-flash"]);
+        json["invalid_category_fallback"] = "Divers".into();
+        std::fs::write(&path, json.to_string()).unwrap();
+        let err = Config::new(Some(path.clone())).unwrap_err();
+        assert!(err.to_string().contains("invalid_category_fallback Divers is not a category"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_apply_overrides() {
+        let mut config = Config::default();
+        config
+            .apply_overrides(&[
+                "use_gemini_google_search=true".to_string(),
+                "chunk_size=25".to_string(),
+                "model=gemini-2.5-pro".to_string(),
+                "support_csv.delimiter=,".to_string(),
+                "max_cost_eur=2.5".to_string(),
+                "provider=openai".to_string(),
+                "chunk_size=10".to_string(),
+            ])
+            .unwrap();
+        assert!(config.use_gemini_google_search);
+        // The last override of a field wins
+        assert_eq!(config.chunk_size, 10);
+        assert_eq!(config.model, vec!["gemini-2.5-pro".to_string()]);
+        assert_eq!(config.support_csv.delimiter, ',');
+        assert_eq!(config.max_cost_eur, Some(2.5));
+        assert_eq!(config.provider, ProviderKind::OpenAi);
+        // Untouched fields keep the configuration values
+        assert_eq!(config.thinking_budget, Some(1024));
+        assert!(config.support_csv.output);
+
+        let err = config.apply_overrides(&["use_google_search=true".to_string()]).unwrap_err();
+        assert!(err.to_string().contains("unknown configuration field use_google_search"));
+        assert!(config.apply_overrides(&["chunk_size".to_string()]).unwrap_err().to_string().contains("expected key=value"));
+        assert!(config.apply_overrides(&["chunk_size=many".to_string()]).is_err());
+        assert!(config.apply_overrides(&["watch.dir=/tmp".to_string()]).unwrap_err().to_string().contains("not set"));
+        // A failed override leaves the configuration as it was
+        assert_eq!(config.chunk_size, 10);
+    }
+}
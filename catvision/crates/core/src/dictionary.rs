@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use crate::snapshot::SnapshotFile;
+
+/// A domain defined with different categories in two dictionary files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DictionaryConflict {
+    /// Conflicting domain.
+    pub domain: String,
+    /// Category kept before the override.
+    pub previous_category: String,
+    /// File the previous category came from.
+    pub previous_file: PathBuf,
+    /// Category that overrides the previous one.
+    pub category: String,
+    /// File the overriding category came from.
+    pub file: PathBuf,
+}
+
+/// Dictionary merged from one or more dictionary files.
+#[derive(Debug, Clone, Default)]
+pub struct MergedDictionary {
+    /// Domain to category mapping.
+    pub entries: HashMap<String, String>,
+    /// Conflicts found while merging, in load order.
+    pub conflicts: Vec<DictionaryConflict>,
+}
+
+/// Dictionary loaded from copies of the dictionary files, taken when the dictionary was loaded.
+/// Reloading the dictionaries takes a new snapshot, an existing one is never updated.
+#[derive(Debug, Clone, Default)]
+pub struct DictionarySnapshot {
+    /// Merged dictionary, conflicts reference the source files.
+    pub dictionary: MergedDictionary,
+    /// Copies the dictionary was loaded from, in load order.
+    pub files: Vec<SnapshotFile>,
+}
+
+/// Load a single dictionary file (`;`-delimited, with `domain` and `llm_category_1` columns).
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be opened or parsed, or if a required column is missing.
+pub fn load_dictionary(dict_path: &Path) -> Result<HashMap<String, String>, Box<dyn std::error::Error>> {
+    let file: File = match File::open(dict_path) {
+        Err(e) => {
+            eprintln!("Error opening file {}: {}", dict_path.display(), e);
+            return Err(Box::new(e));
+        },
+        Ok(f) => f,
+    };
+
+    // Previous outputs make dictionaries, their columns are the same in every schema version
+    let mut rdr = csv::ReaderBuilder::new()
+        .delimiter(b';')
+        .comment(Some(b'#'))
+        .from_reader(file);
+
+    let headers: Vec<String> = rdr.headers()?.iter().map(|s| s.trim().to_string()).collect();
+
+    let mut header_map = HashMap::new();
+
+    for (index, header) in headers.iter().enumerate() {
+        header_map.insert(header.clone(), index);
+    }
+
+    let domain_idx = *header_map
+        .get("domain")
+        .ok_or_else(|| format!("Required header 'domain' not found in {}", dict_path.display()))?;
+    let category_idx = *header_map
+        .get("llm_category_1")
+        .ok_or_else(|| format!("Required header 'llm_category_1' not found in {}", dict_path.display()))?;
+
+    let mut res: HashMap<String, String> = HashMap::new();
+
+    for record in rdr.records() {
+        let record = record?;
+        let domain = record.get(domain_idx).unwrap_or("").trim();
+        let category = record.get(category_idx).unwrap_or("").trim();
+
+        res.insert(domain.to_string(), category.to_string());
+    }
+
+    Ok(res)
+}
+
+/// Expand dictionary arguments: files are kept as is, directories are replaced by
+/// the `.csv` files they contain, sorted by file name.
+///
+/// # Errors
+///
+/// Returns an error if a directory cannot be read.
+pub fn expand_dictionary_paths(paths: &[PathBuf]) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut expanded = Vec::with_capacity(paths.len());
+
+    for path in paths {
+        if path.is_dir() {
+            let mut files: Vec<PathBuf> = std::fs::read_dir(path)?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "csv"))
+                .collect();
+            files.sort();
+            expanded.extend(files);
+        } else {
+            expanded.push(path.clone());
+        }
+    }
+
+    Ok(expanded)
+}
+
+/// Merge several dictionary files into one.
+///
+/// Files are loaded in order and later files override earlier ones. Every domain
+/// whose category changes because of an override is recorded as a conflict.
+///
+/// # Errors
+///
+/// Returns an error if any dictionary file cannot be loaded.
+pub fn merge_dictionaries(paths: &[PathBuf]) -> Result<MergedDictionary, Box<dyn std::error::Error>> {
+    let files: Vec<(PathBuf, PathBuf)> = expand_dictionary_paths(paths)?
+        .into_iter()
+        .map(|path| (path.clone(), path))
+        .collect();
+    merge_files(&files)
+}
+
+/// Copy the dictionary files into `dir`, then merge the copies like [`merge_dictionaries`].
+///
+/// # Errors
+///
+/// Returns an error if a dictionary file cannot be copied or loaded.
+pub fn snapshot_dictionaries(paths: &[PathBuf], dir: &Path) -> Result<DictionarySnapshot, Box<dyn std::error::Error>> {
+    let mut files = Vec::new();
+    for (index, path) in expand_dictionary_paths(paths)?.iter().enumerate() {
+        files.push(SnapshotFile::take(path, dir, index)?);
+    }
+
+    let sources: Vec<(PathBuf, PathBuf)> = files
+        .iter()
+        .map(|file| (file.source.clone(), file.copy.clone()))
+        .collect();
+    let dictionary = merge_files(&sources)?;
+
+    Ok(DictionarySnapshot { dictionary, files })
+}
+
+/// Merge dictionary files given as (source file, file to read) pairs, conflicts reference the source files.
+fn merge_files(files: &[(PathBuf, PathBuf)]) -> Result<MergedDictionary, Box<dyn std::error::Error>> {
+    let mut merged = MergedDictionary::default();
+    let mut origins: HashMap<String, PathBuf> = HashMap::new();
+
+    for (path, read_from) in files {
+        let dict = load_dictionary(read_from)?;
+        println!("Loaded {} entries from dictionary {}", dict.len(), path.display());
+
+        // Sorted so the conflict report is deterministic
+        let mut entries: Vec<(String, String)> = dict.into_iter().collect();
+        entries.sort();
+
+        for (domain, category) in entries {
+            if let Some(previous_category) = merged.entries.get(&domain) {
+                if *previous_category != category {
+                    merged.conflicts.push(DictionaryConflict {
+                        domain: domain.clone(),
+                        previous_category: previous_category.clone(),
+                        previous_file: origins.get(&domain).cloned().unwrap_or_default(),
+                        category: category.clone(),
+                        file: path.clone(),
+                    });
+                }
+            }
+            origins.insert(domain.clone(), path.clone());
+            merged.entries.insert(domain, category);
+        }
+    }
+
+    Ok(merged)
+}
+
+/// Write dictionary conflicts to a `;`-delimited CSV report.
+///
+/// # Errors
+///
+/// Returns an error if the report cannot be written.
+pub fn write_conflicts_report(conflicts: &[DictionaryConflict], path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let mut wtr = csv::WriterBuilder::new()
+        .delimiter(b';')
+        .from_path(path)?;
+
+    wtr.write_record(["domain", "previous_category", "previous_file", "category", "file"])?;
+    for conflict in conflicts {
+        let previous_file = conflict.previous_file.to_string_lossy();
+        let file = conflict.file.to_string_lossy();
+        wtr.write_record([
+            conflict.domain.as_str(),
+            conflict.previous_category.as_str(),
+            previous_file.as_ref(),
+            conflict.category.as_str(),
+            file.as_ref(),
+        ])?;
+    }
+    wtr.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_dictionary(dir: &Path, name: &str, rows: &[(&str, &str)]) -> PathBuf {
+        let path = dir.join(name);
+        let mut content = String::from("domain;llm_category_1\n");
+        for (domain, category) in rows {
+            content.push_str(&format!("{};{}\n", domain, category));
+        }
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("catvision-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_merge_dictionaries_later_file_wins() {
+        let dir = temp_dir("dict-merge");
+        let first = write_dictionary(&dir, "a.csv", &[("example.com", "Business"), ("news.org", "News")]);
+        let second = write_dictionary(&dir, "b.csv", &[("example.com", "Technology"), ("shop.com", "Shopping")]);
+
+        let merged = merge_dictionaries(&[first.clone(), second.clone()]).unwrap();
+        assert_eq!(merged.entries.len(), 3);
+        assert_eq!(merged.entries["example.com"], "Technology");
+        assert_eq!(merged.entries["news.org"], "News");
+        assert_eq!(merged.entries["shop.com"], "Shopping");
+
+        assert_eq!(merged.conflicts.len(), 1);
+        assert_eq!(
+            merged.conflicts[0],
+            DictionaryConflict {
+                domain: "example.com".to_string(),
+                previous_category: "Business".to_string(),
+                previous_file: first.clone(),
+                category: "Technology".to_string(),
+                file: second.clone(),
+            }
+        );
+
+        // Reversed order reverses precedence
+        let merged = merge_dictionaries(&[second, first]).unwrap();
+        assert_eq!(merged.entries["example.com"], "Business");
+
+        let report = dir.join("conflicts.csv");
+        write_conflicts_report(&merged.conflicts, &report).unwrap();
+        let content = std::fs::read_to_string(&report).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "domain;previous_category;previous_file;category;file");
+        assert!(lines[1].starts_with("example.com;Technology;"));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_merge_dictionaries_from_directory() {
+        let dir = temp_dir("dict-dir");
+        write_dictionary(&dir, "01-base.csv", &[("example.com", "Business")]);
+        write_dictionary(&dir, "02-override.csv", &[("example.com", "Technology")]);
+        std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let merged = merge_dictionaries(&[dir.clone()]).unwrap();
+        assert_eq!(merged.entries.len(), 1);
+        assert_eq!(merged.entries["example.com"], "Technology");
+        assert_eq!(merged.conflicts.len(), 1);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_load_dictionary_from_versioned_output() {
+        let dir = temp_dir("dict-versioned");
+        let path = dir.join("output.csv");
+        std::fs::write(&path, "# schema_version: 3\ndomain;llm_category_1;llm_category_2\nexample.com;Business;Technology\n").unwrap();
+
+        let dictionary = load_dictionary(&path).unwrap();
+        assert_eq!(dictionary.len(), 1);
+        assert_eq!(dictionary["example.com"], "Business");
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
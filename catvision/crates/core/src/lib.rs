@@ -1,11 +1,32 @@
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use config::Config;
-use traits::{Input, Output};
+use indexmap::IndexMap;
+use traits::{Input, Output, StreamingInput};
 use statistics::{Statistics};
-use format::csv::{MyCSVInput, MyCSVOutput};
+use format::csv::{delimiter_from_char, read_classified_domains, CsvDialect, MyCSVInput, MyCSVOutput};
 use format::html;
-use std::fs::File;
+use format::json::MyJSONInput;
+use format::schema::SCHEMA_VERSION;
+use format::txt::TxtInput;
+use llm::core::capabilities::apply_model_capabilities;
+use utils::urls::url_key;
+use utils::{trim_domain_by_llm, CatVisionData};
+use utils::error::CatVisionError;
+
+pub mod dictionary;
+pub mod olfeo_mapping;
+pub mod snapshot;
+pub mod watch;
+
+use dictionary::{snapshot_dictionaries, DictionaryConflict, DictionarySnapshot};
+use olfeo_mapping::OlfeoMapping;
+use snapshot::{modified_sources, SnapshotFile, RUN_SNAPSHOT_DIR};
+
+/// Prometheus metrics of the runs in the text exposition format, for the host service to serve
+#[cfg(feature = "metrics")]
+pub use llm::metrics::metrics_handler;
 
 #[derive(Clone)]
 pub struct Ctx
@@ -14,134 +35,1016 @@ pub struct Ctx
     output_path: std::path::PathBuf,
     inputs: Vec<Box<dyn Input>>,
     outputs: Vec<Box<dyn Output>>,
+    /// Files written by `outputs`, in the same order
+    output_files: Vec<PathBuf>,
     pub stats: Statistics,
     pub config: Config,
     pub prompt: String,
-    pub dict: Option<HashMap<String, String>>,
+    /// Dictionary of the run, loaded from copies of the dictionary files
+    pub dict: Option<Arc<DictionarySnapshot>>,
+    /// Dictionary files or directories, in precedence order (later overrides earlier)
+    pub dict_paths: Vec<PathBuf>,
+    /// Number of dictionary snapshots taken, each one is copied into its own directory
+    dict_generation: usize,
+    /// Snapshots of the taxonomy files (Olfeo mapping) used by the run
+    taxonomy_files: Vec<SnapshotFile>,
+    /// Input values that are not valid URLs, in URL mode, they are not classified
+    unclassifiable: Vec<String>,
+}
+
+/// Format of the input file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputFormat {
+    /// Delimited file with a `domain` column and optional Olfeo columns.
+    Csv,
+    /// Plain text file, one domain per line.
+    Txt,
+    /// JSON lines file, one object with a `domain` field and optional Olfeo fields per line.
+    Json,
+}
+
+impl InputFormat {
+    /// Select the input format from the file extension, `.txt` files are plain text, `.jsonl` and `.ndjson` files
+    /// are JSON lines and anything else is CSV.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("txt") => InputFormat::Txt,
+            Some(ext) if ext.eq_ignore_ascii_case("jsonl") || ext.eq_ignore_ascii_case("ndjson") => InputFormat::Json,
+            _ => InputFormat::Csv,
+        }
+    }
+}
+
+impl std::str::FromStr for InputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Ok(InputFormat::Csv),
+            "txt" => Ok(InputFormat::Txt),
+            "json" | "jsonl" => Ok(InputFormat::Json),
+            _ => Err(format!("Unsupported input format '{}', expected csv, txt or json", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for InputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputFormat::Csv => write!(f, "csv"),
+            InputFormat::Txt => write!(f, "txt"),
+            InputFormat::Json => write!(f, "json"),
+        }
+    }
 }
 
 fn extract_directory_from_path(file_path: &Path) -> Option<PathBuf> {
     file_path.parent().map(|parent| parent.to_path_buf())
 }
 
+/// Statistics of the run in JSON, with the output schema version
+fn stats_json(stats: &Statistics) -> Result<String, serde_json::Error> {
+    let mut json: serde_json::Value = serde_json::from_str(&stats.to_json())?;
+    json["schema_version"] = SCHEMA_VERSION.into();
+    serde_json::to_string_pretty(&json)
+}
+
+/// Directory receiving the outputs of the run on `input_path`: `outputs/` next to the input file
+pub fn run_output_dir(input_path: &Path) -> PathBuf {
+    extract_directory_from_path(input_path).unwrap_or_else(|| PathBuf::from("/outputs/")).join("outputs")
+}
+
 impl Ctx
 {
-    pub fn new(input_path: &Path, config: Option<PathBuf>, dict: Option<PathBuf>) -> Self {
-        let config = Config::new(config);
+    /// Creates the context of a run.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_path` - Input file
+    /// * `config` - Optional configuration file
+    /// * `dict_paths` - Dictionary files or directories, in precedence order
+    /// * `input_format` - Input format, selected from the input file extension when `None`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configuration cannot be read or parsed, or for the reasons of [`Ctx::with_config`]
+    pub fn new(input_path: &Path, config: Option<PathBuf>, dict_paths: Vec<PathBuf>, input_format: Option<InputFormat>) -> Result<Self, CatVisionError> {
+        let config = Config::new(config)?;
+        Self::with_config(input_path, config, dict_paths, input_format)
+    }
+
+    /// Creates the context of a run with a configuration already loaded, such as one with command line overrides.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_path` - Input file
+    /// * `config` - Configuration of the run
+    /// * `dict_paths` - Dictionary files or directories, in precedence order
+    /// * `input_format` - Input format, selected from the input file extension when `None`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configuration fails [`Config::validate`], sets fields its models do not support
+    /// (unless `lenient_capabilities` is set), has an invalid CSV delimiter or enables no input or no output for the
+    /// input file, if the input file does not exist, or if the output directory cannot be created
+    pub fn with_config(input_path: &Path, mut config: Config, dict_paths: Vec<PathBuf>, input_format: Option<InputFormat>) -> Result<Self, CatVisionError> {
+        config.validate()?;
+        if let Err(mismatches) = apply_model_capabilities(&mut config) {
+            for mismatch in &mismatches {
+                eprintln!("Error: {}", mismatch);
+            }
+            let mismatches = mismatches.iter().map(|mismatch| mismatch.to_string()).collect::<Vec<_>>();
+            return Err(CatVisionError::Config(format!(
+                "Configuration not supported by the model, set lenient_capabilities to drop the unsupported fields: {}",
+                mismatches.join(", ")
+            )));
+        }
+        if !input_path.is_file() {
+            return Err(CatVisionError::Input(format!("Input file {} not found", input_path.display())));
+        }
+        let thinking = config.thinking_budget.map_or_else(|| "none".to_string(), |budget| budget.to_string());
         
         let mut ctx = Ctx {
             input_path: input_path.to_path_buf(),
             inputs: vec![],
-            output_path: extract_directory_from_path(input_path).unwrap_or_else(|| PathBuf::from("/outputs/")).join("outputs"),
+            output_path: run_output_dir(input_path),
             outputs: vec![],
+            output_files: vec![],
             stats: Statistics::new(config.max_domain_propositions),
             config: config,
             prompt: String::from(""),
             dict: None,
+            dict_paths,
+            dict_generation: 0,
+            taxonomy_files: vec![],
+            unclassifiable: vec![],
         };
             
-        if ctx.config.support_csv.input {
+        std::fs::create_dir_all(&ctx.output_path).map_err(|e| {
+            CatVisionError::Output(format!("Cannot create the output directory {}: {}", ctx.output_path.display(), e))
+        })?;
+
+        // support_json.input reads every input as JSON lines, an explicit input format still wins
+        let input_format = input_format.unwrap_or_else(|| {
+            if ctx.config.support_json.input { InputFormat::Json } else { InputFormat::from_path(input_path) }
+        });
+
+        if input_format == InputFormat::Txt {
+            println!("Plain text input is enabled.");
+            ctx.inputs.push(Box::new(TxtInput::new(&ctx.input_path)));
+        } else if input_format == InputFormat::Json {
+            println!("JSON lines input is enabled.");
+            ctx.inputs.push(Box::new(MyJSONInput::new(&ctx.input_path)));
+        } else if ctx.config.support_csv.input {
             println!("CSV input is enabled.");
-            let input = MyCSVInput::new(&ctx.input_path);
+            let delimiter = delimiter_from_char(ctx.config.support_csv.input_delimiter)
+                .map_err(|e| CatVisionError::Config(format!("Invalid CSV input delimiter: {}", e)))?;
+            let input = MyCSVInput::new(&ctx.input_path)
+                .with_delimiter(delimiter)
+                .with_strict_columns(ctx.config.strict_columns);
             ctx.inputs.push(Box::new(input));
         }
 
         if ctx.config.support_csv.output {
             println!("CSV output is enabled.");
-            let output = MyCSVOutput::new(&ctx.output_path.join(ctx.input_path.file_name().unwrap()).with_extension(format!("{}-chunk_{}-thinking_{}.{}", ctx.config.model[0], ctx.config.chunk_size, ctx.config.thinking_budget, "csv")));
-            ctx.outputs.push(Box::new(output.unwrap()));
+            let dialect = CsvDialect {
+                delimiter: delimiter_from_char(ctx.config.support_csv.delimiter)
+                    .map_err(|e| CatVisionError::Config(format!("Invalid CSV output delimiter: {}", e)))?,
+                decimal_comma: ctx.config.support_csv.decimal_comma,
+                crlf_line_endings: ctx.config.support_csv.crlf_line_endings,
+            };
+            let path = ctx.output_path.join(ctx.input_path.file_name().unwrap_or_default()).with_extension(format!("{}-chunk_{}-thinking_{}.{}", ctx.config.model[0], ctx.config.chunk_size, thinking, "csv"));
+            let output = MyCSVOutput::new(&path);
+            ctx.outputs.push(Box::new(output?.with_dialect(dialect)));
+            ctx.output_files.push(path);
         }
         
         if ctx.config.support_html.input {
             println!("HTML input is enabled but not supported.");
         }
 
+        if ctx.config.support_json.output {
+            println!("JSON output is enabled but not supported.");
+        }
+
+        if let Some(batch_size) = ctx.config.stream_batch_size {
+            if batch_size == 0 {
+                return Err(CatVisionError::Config("stream_batch_size must be at least 1".to_string()));
+            }
+            if ctx.config.support_html.output {
+                return Err(CatVisionError::Config("The HTML output needs the whole input, disable support_html.output to set stream_batch_size".to_string()));
+            }
+            if !ctx.inputs.first().is_some_and(|input| input.as_any().is::<MyCSVInput>()) {
+                println!("Only CSV inputs are read in batches, the whole input is read.");
+                ctx.config.stream_batch_size = None;
+            }
+        }
+
         if ctx.config.support_html.output {
             println!("HTML output is enabled.");
-            let output = html::HTMLGenerator::new(&ctx.output_path.join(ctx.input_path.file_name().unwrap()).with_extension(format!("{}-chunk_{}-thinking_{}.{}", ctx.config.model[0], ctx.config.chunk_size, ctx.config.thinking_budget, "html")));
-            ctx.outputs.push(Box::new(output.unwrap()));
+            let path = ctx.output_path.join(ctx.input_path.file_name().unwrap_or_default()).with_extension(format!("{}-chunk_{}-thinking_{}.{}", ctx.config.model[0], ctx.config.chunk_size, thinking, "html"));
+            let output = html::HTMLGenerator::new(&path);
+            ctx.outputs.push(Box::new(output?));
+            ctx.output_files.push(path);
         }
 
-        if dict.is_some() {
-            let dict_path = dict.unwrap();
-            let dict_map = ctx.load_dictionary(&dict_path);
-            ctx.dict = match dict_map {
-                Ok(d) => Some(d.clone()),
-                Err(e) => {
-                    eprintln!("Failed to load dictionary from {}: {}", dict_path.display(), e);
-                    None
-                }
-            };
+        if ctx.inputs.is_empty() {
+            return Err(CatVisionError::Config(format!(
+                "No input enabled for {}, enable support_csv.input or support_json.input",
+                ctx.input_path.display()
+            )));
+        }
+        if ctx.outputs.is_empty() {
+            return Err(CatVisionError::Config("No output enabled, enable support_csv.output or support_html.output".to_string()));
+        }
+
+        if !ctx.dict_paths.is_empty() {
+            if let Err(e) = ctx.reload_dictionary() {
+                eprintln!("Failed to load dictionaries: {}", e);
+            }
         }
 
-        return ctx;
+        Ok(ctx)
     }
 
     pub fn load_dictionary(&self, dict_path: &PathBuf) -> Result<HashMap<String, String>, Box<dyn std::error::Error>> {
-        let file: File = match File::open(dict_path) {
-            Err(e) => {
-                eprintln!("Error opening file {}: {}", dict_path.display(), e);
-                return Err(Box::new(e));
-            },
-            Ok(f) => f,
+        dictionary::load_dictionary(dict_path)
+    }
+
+    /// Directory of the run receiving the copies of the dictionaries and taxonomy files.
+    pub fn snapshot_dir(&self) -> PathBuf {
+        self.output_path.join(RUN_SNAPSHOT_DIR)
+    }
+
+    /// Take a new snapshot of the dictionaries from `dict_paths`, so updated files are picked up without restarting.
+    /// The files are copied into a new directory of the run and the dictionary is loaded from the copies;
+    /// the previous snapshot is left untouched, and kept on error.
+    ///
+    /// # Returns
+    /// * Number of entries in the merged dictionary
+    pub fn reload_dictionary(&mut self) -> Result<usize, Box<dyn std::error::Error>> {
+        let generation = self.dict_generation + 1;
+        let snapshot = snapshot_dictionaries(&self.dict_paths, &self.snapshot_dir().join(format!("dict-{}", generation)))?;
+        let count = snapshot.dictionary.entries.len();
+
+        if !snapshot.dictionary.conflicts.is_empty() {
+            println!("{} dictionary conflicts resolved in favor of the later file", snapshot.dictionary.conflicts.len());
+        }
+
+        self.dict_generation = generation;
+        self.dict = Some(Arc::new(snapshot));
+        Ok(count)
+    }
+
+    /// Conflicts found while merging the dictionaries
+    pub fn dict_conflicts(&self) -> &[DictionaryConflict] {
+        self.dict.as_ref().map(|snapshot| snapshot.dictionary.conflicts.as_slice()).unwrap_or_default()
+    }
+
+    /// Load the Olfeo mapping file of the configuration from a copy taken into the run directory,
+    /// the identity mapping when none is configured.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the mapping file cannot be copied or parsed.
+    pub fn load_olfeo_mapping(&mut self) -> Result<OlfeoMapping, Box<dyn std::error::Error>> {
+        let Some(path) = self.config.olfeo_mapping.clone() else {
+            return Ok(OlfeoMapping::identity());
         };
 
-        let mut rdr = csv::ReaderBuilder::new()
-            .delimiter(b';') // Set the delimiter to tab
-            .from_reader(file);
+        let snapshot = SnapshotFile::take(&path, &self.snapshot_dir().join("olfeo_mapping"), self.taxonomy_files.len())?;
+        let mapping = olfeo_mapping::load_olfeo_mapping(&snapshot.copy)?;
+        self.taxonomy_files.push(snapshot);
+        Ok(mapping)
+    }
 
-        let headers: Vec<String> = rdr.headers()?.iter().map(|s| s.trim().to_string()).collect();
-    
-        let mut header_map = HashMap::new();
-        
-        for (index, header) in headers.iter().enumerate() {
-            header_map.insert(header.clone(), index);
+    /// Check whether a dictionary or taxonomy file was modified since the run loaded it.
+    /// Modified files taint the run: they are listed in the statistics and a warning is printed.
+    ///
+    /// # Returns
+    /// * Source files modified during the run
+    pub fn check_snapshots(&mut self) -> Vec<PathBuf> {
+        let mut modified = self.dict.as_ref().map(|snapshot| modified_sources(&snapshot.files)).unwrap_or_default();
+        modified.extend(modified_sources(&self.taxonomy_files));
+
+        for path in &modified {
+            eprintln!("WARNING: {} was modified during the run, results were computed from the copy taken at startup", path.display());
         }
-        
+        self.stats.modified_inputs = modified.iter().map(|path| path.to_string_lossy().into_owned()).collect();
+        modified
+    }
 
-        let mut res: HashMap<String, String> = HashMap::new();
+    /// Files written by [`Ctx::write`]: the configured outputs, then the statistics
+    pub fn output_files(&self) -> Vec<PathBuf> {
+        let mut files = self.output_files.clone();
+        files.push(self.stats_path());
+        if !self.unclassifiable.is_empty() {
+            files.push(self.unclassifiable_path());
+        }
+        files
+    }
 
-        for record in rdr.records() {
-            let record = record?;
-            let domain = record.get(*header_map.get("domain").unwrap()).unwrap().trim();
-            let category = record.get(*header_map.get("llm_category_1").unwrap()).unwrap().trim();
+    /// Path of the CSV output of the run, `None` when the CSV output is disabled
+    pub fn csv_output(&self) -> Option<&Path> {
+        self.output_files.iter().find(|path| path.extension().is_some_and(|ext| ext == "csv")).map(PathBuf::as_path)
+    }
+
+    /// LLM categories of the domains already classified in the CSV output of the run, to resume it.
+    /// Empty without CSV output or before the first run.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the CSV output exists but cannot be read.
+    pub fn classified_domains(&self) -> Result<IndexMap<String, Vec<&'static str>>, CatVisionError> {
+        let Some(path) = self.csv_output() else {
+            return Ok(IndexMap::new());
+        };
+        if !path.is_file() {
+            return Ok(IndexMap::new());
+        }
+        read_classified_domains(path, delimiter_from_char(self.config.support_csv.delimiter)?)
+    }
+
+    /// Path of the file listing the unclassifiable input values of the run, one per line
+    pub fn unclassifiable_path(&self) -> PathBuf {
+        self.output_path.join(self.input_path.file_name().unwrap_or_default()).with_extension("unclassifiable.txt")
+    }
+
+    /// Input values left unclassified because they are not valid URLs, in URL mode
+    pub fn unclassifiable(&self) -> &[String] {
+        &self.unclassifiable
+    }
 
-            // Assuming the first column is the domain and the fourth column is the expected category
-            res.insert(domain.to_string(), category.to_string());
+    /// Write the statistics of the run, and the unclassifiable input values if any
+    fn write_run_files(&self) -> Result<(), CatVisionError> {
+        std::fs::create_dir_all(&self.output_path)?;
+        let stats = stats_json(&self.stats).map_err(|e| CatVisionError::Output(format!("Cannot serialize the statistics: {}", e)))?;
+        std::fs::write(self.stats_path(), stats)?;
+        if !self.unclassifiable.is_empty() {
+            std::fs::write(self.unclassifiable_path(), self.unclassifiable.join("\n") + "\n")?;
         }
+        Ok(())
+    }
 
-        Ok(res)
+    /// Path of the statistics JSON file of the run
+    pub fn stats_path(&self) -> PathBuf {
+        self.output_path.join(self.input_path.file_name().unwrap_or_default()).with_extension("stats.json")
     }
 
-    pub fn write(&mut self, data: &dyn std::any::Any) -> Result<(), Box<dyn std::error::Error>> {
-        let infos = traits::Infos::new(            
+    /// Metadata of the outputs, with the current statistics summary
+    fn infos(&self) -> traits::Infos {
+        traits::Infos::new(
             &(self.config.model[0].clone() + " LLM Classification Results for " + &self.input_path.to_string_lossy()),
             &self.stats.generate_output_summary(),
             &self.prompt,
             self.config.max_domain_propositions,
-        );
+        )
+    }
+
+    /// Lists `comparison` in the HTML output, as the changes since the previous run
+    pub fn set_run_comparison(&mut self, comparison: html::RunComparison) {
+        for output in &mut self.outputs {
+            if let Some(generator) = output.as_any_mut().downcast_mut::<html::HTMLGenerator>() {
+                generator.comparison = Some(comparison.clone());
+            }
+        }
+    }
+
+    pub fn write(&mut self, data: &dyn std::any::Any) -> Result<(), CatVisionError> {
+        self.check_snapshots();
+
+        let infos = self.infos();
     
         for output in &mut self.outputs {
             if let Err(e) = output.write(data, &infos) {
                 eprintln!("Error writing output: {}", e);
             }
         }
-        Ok(())
+
+        self.write_run_files()
     }
 
-    pub fn parse(&mut self) -> Result<Box<dyn std::any::Any>, Box<dyn std::error::Error>> {
-        let input = self.inputs.first_mut().ok_or("No input defined")?;
-        let res = input.parse(&mut self.stats, self.dict.as_ref());
+    /// Append a batch parsed by [`Ctx::parse_batch`] to the outputs and write the statistics so far.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the statistics cannot be written, output errors are printed.
+    pub fn write_batch(&mut self, data: &dyn std::any::Any) -> Result<(), CatVisionError> {
+        self.check_snapshots();
 
-        let csv_input = input
-            .as_any()
-            .downcast_ref::<MyCSVInput>()
-            .ok_or("Input is not a MyCSVInput")?;
+        let infos = self.infos();
 
         for output in &mut self.outputs {
-            output.create_output_header(&csv_input.headers, self.config.max_domain_propositions);
+            if let Err(e) = output.append(data, &infos) {
+                eprintln!("Error writing output: {}", e);
+            }
         }
 
+        self.write_run_files()
+    }
+
+    /// Number of domains read at once by [`Ctx::parse_batch`], `None` when the whole input is read by [`Ctx::parse`]
+    pub fn stream_batch_size(&self) -> Option<usize> {
+        self.config.stream_batch_size
+    }
+
+    /// Parse the next batch of `stream_batch_size` domains of the input, `None` once it is exhausted.
+    /// The first batch reads the input header and creates the output headers.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the input cannot be read, or is not a CSV input read in batches.
+    pub fn parse_batch(&mut self) -> Result<Option<Box<dyn std::any::Any>>, CatVisionError> {
+        let batch_size = self.config.stream_batch_size.ok_or_else(|| CatVisionError::Config("stream_batch_size is not set".to_string()))?;
+        let input = self.inputs.first_mut().ok_or_else(|| CatVisionError::Input("No input defined".to_string()))?;
+        let csv_input = input
+            .as_any_mut()
+            .downcast_mut::<MyCSVInput>()
+            .ok_or(CatVisionError::Downcast("a CSV input, only CSV inputs are read in batches"))?;
+
+        let first_batch = csv_input.headers.is_empty();
+        let res = csv_input.parse_batch(&mut self.stats, self.dict.as_ref().map(|snapshot| &snapshot.dictionary.entries), batch_size)?;
+
+        if first_batch {
+            prepare_outputs(&csv_input.headers, &mut self.stats, &mut self.outputs, self.config.max_domain_propositions, self.config.url_mode);
+        }
+
+        match res {
+            Some(res) if self.config.url_mode => Ok(Some(self.key_by_url(res)?)),
+            res => Ok(res),
+        }
+    }
+
+    pub fn parse(&mut self) -> Result<Box<dyn std::any::Any>, CatVisionError> {
+        let input = self.inputs.first_mut().ok_or_else(|| CatVisionError::Input("No input defined".to_string()))?;
+        let res = input.parse(&mut self.stats, self.dict.as_ref().map(|snapshot| &snapshot.dictionary.entries));
+
+        let input_headers = input_headers(input.as_ref())?;
+
+        prepare_outputs(input_headers, &mut self.stats, &mut self.outputs, self.config.max_domain_propositions, self.config.url_mode);
+
+        if self.config.url_mode {
+            return self.key_by_url(res?);
+        }
         res
     }
-}
\ No newline at end of file
+
+    /// Whether the parsed input has the column `column`, always false before [`Ctx::parse`] or [`Ctx::parse_batch`]
+    pub fn has_input_column(&self, column: &str) -> bool {
+        self.inputs
+            .first()
+            .and_then(|input| input_headers(input.as_ref()).ok())
+            .is_some_and(|headers| headers.contains_key(column))
+    }
+
+    /// Key parsed data by URL: each value becomes its `host/first_path_segment` key, with the original URL and host.
+    /// The first URL of a key is kept, values that are not URLs are set aside as unclassifiable.
+    /// The dictionary applies to the host.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is not an `IndexMap<String, CatVisionData>`.
+    fn key_by_url(&mut self, data: Box<dyn std::any::Any>) -> Result<Box<dyn std::any::Any>, CatVisionError> {
+        let data = data
+            .downcast::<IndexMap<String, CatVisionData>>()
+            .map_err(|_| CatVisionError::Downcast("IndexMap<String, CatVisionData>"))?;
+        let dict = self.dict.as_ref().map(|snapshot| &snapshot.dictionary.entries);
+
+        let mut res: IndexMap<String, CatVisionData> = IndexMap::with_capacity(data.len());
+        for (input, mut new_data) in *data {
+            let key = match url_key(&input) {
+                Ok(key) => key,
+                Err(e) => {
+                    eprintln!("Warning: {}, left unclassified", e);
+                    self.stats.unclassifiable_inputs += 1;
+                    self.unclassifiable.push(input);
+                    continue;
+                }
+            };
+            if res.contains_key(&key.key) {
+                continue;
+            }
+
+            if let Some(dict) = dict {
+                new_data.appsite_name_by_gemini = trim_domain_by_llm(dict, &key.host).0;
+            }
+            new_data.url = Some(input);
+            new_data.host = Some(key.host);
+            res.insert(key.key, new_data);
+        }
+
+        Ok(Box::new(res))
+    }
+}
+
+/// Columns of the header of `input`, by name
+///
+/// # Errors
+///
+/// Returns an error if `input` is not a CSV, TXT or JSON input.
+fn input_headers(input: &dyn Input) -> Result<&HashMap<String, usize>, CatVisionError> {
+    if let Some(csv_input) = input.as_any().downcast_ref::<MyCSVInput>() {
+        Ok(&csv_input.headers)
+    } else if let Some(txt_input) = input.as_any().downcast_ref::<TxtInput>() {
+        Ok(&txt_input.headers)
+    } else if let Some(json_input) = input.as_any().downcast_ref::<MyJSONInput>() {
+        Ok(&json_input.headers)
+    } else {
+        Err(CatVisionError::Downcast("a MyCSVInput, a TxtInput or a MyJSONInput"))
+    }
+}
+
+/// Create the output headers from the input headers and flag the accuracy statistics they allow.
+/// In URL mode, the `url` and `host` columns follow the input columns.
+fn prepare_outputs(input_headers: &HashMap<String, usize>, stats: &mut Statistics, outputs: &mut [Box<dyn Output>], levels_count: usize, url_mode: bool) {
+    // Accuracy statistics need the columns to compare the LLM answer with
+    stats.expected_categories = input_headers.contains_key("categories_manual");
+    stats.olfeo_categories = input_headers.contains_key("category_by_olfeo") || input_headers.contains_key("old_category");
+
+    let mut headers = input_headers.clone();
+    if url_mode {
+        for column in ["url", "host"] {
+            headers.insert(column.to_string(), headers.len());
+        }
+    }
+
+    for output in outputs {
+        output.create_output_header(&headers, levels_count);
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_txt_input_pipeline_with_mocked_llm() {
+        let dir = std::env::temp_dir().join(format!("catvision-txt-pipeline-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("domains.txt");
+        std::fs::write(&input, "# weekly list\nexample.com\n\nnews.example.org # press\nexample.com\n").unwrap();
+        let config = dir.join("config.json");
+        std::fs::write(&config, r#"{
+            "max_threads": 1,
+            "support_csv": { "input": true, "output": true },
+            "support_html": { "input": false, "output": false },
+            "max_domain_propositions": 2,
+            "model": ["mock-model"],
+            "chunk_size": 10,
+            "thinking_budget": 0,
+            "use_gemini_explicit_caching": false,
+            "use_gemini_url_context": false,
+            "use_gemini_google_search": false,
+            "use_gemini_custom_cache_duration": null
+        }"#).unwrap();
+
+        assert_eq!(InputFormat::from_path(&input), InputFormat::Txt);
+        assert_eq!("TXT".parse::<InputFormat>(), Ok(InputFormat::Txt));
+        assert!("xml".parse::<InputFormat>().is_err());
+        assert_eq!(InputFormat::Txt.to_string().parse::<InputFormat>(), Ok(InputFormat::Txt));
+
+        let mut ctx = Ctx::new(&input, Some(config), vec![], None).unwrap();
+        let mut domains = ctx
+            .parse()
+            .unwrap()
+            .downcast::<IndexMap<String, CatVisionData>>()
+            .unwrap();
+        assert_eq!(domains.keys().collect::<Vec<_>>(), vec!["example.com", "news.example.org"]);
+
+        // Mocked LLM answer
+        domains["example.com"].categories_llm = Some(vec!["Business", "Technology"]);
+        domains["news.example.org"].categories_llm = Some(vec!["News"]);
+        ctx.write(&*domains).unwrap();
+
+        let output = dir.join("outputs").join("domains.mock-model-chunk_10-thinking_0.csv");
+        assert_eq!(ctx.output_files(), vec![output.clone(), dir.join("outputs").join("domains.stats.json")]);
+        let content = std::fs::read_to_string(&output).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines, vec![
+            "# schema_version: 3",
+            "domain;llm_category_1;llm_category_2",
+            "example.com;Business;Technology",
+            "news.example.org;News;",
+        ]);
+        let stats: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(ctx.stats_path()).unwrap()).unwrap();
+        assert_eq!(stats["schema_version"], 3);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_url_mode_keys_on_host_and_first_path_segment() {
+        let dir = std::env::temp_dir().join(format!("catvision-url-mode-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("urls.csv");
+        std::fs::write(&input, [
+            "domain",
+            "https://www.google.com:443/maps/place?q=paris#map",
+            "www.google.com/maps/dir",
+            "https://www.google.com/search?q=rust",
+            "http://Example.COM:8080",
+            "not a url",
+            "someone@example.com",
+        ].join("\n") + "\n").unwrap();
+        let config = dir.join("config.json");
+        std::fs::write(&config, r#"{
+            "max_threads": 1,
+            "support_csv": { "input": true, "output": true },
+            "support_html": { "input": false, "output": false },
+            "max_domain_propositions": 1,
+            "model": ["mock-model"],
+            "chunk_size": 10,
+            "thinking_budget": 0,
+            "use_gemini_explicit_caching": false,
+            "use_gemini_url_context": false,
+            "use_gemini_google_search": false,
+            "use_gemini_custom_cache_duration": null,
+            "strict_columns": false,
+            "url_mode": true
+        }"#).unwrap();
+
+        let mut ctx = Ctx::new(&input, Some(config), vec![], None).unwrap();
+        let mut domains = ctx
+            .parse()
+            .unwrap()
+            .downcast::<IndexMap<String, CatVisionData>>()
+            .unwrap();
+        // Port, query string and fragment dropped, the first URL of a key is kept
+        assert_eq!(domains.keys().collect::<Vec<_>>(), vec!["www.google.com/maps", "www.google.com/search", "example.com"]);
+        assert_eq!(domains["www.google.com/maps"].url.as_deref(), Some("https://www.google.com:443/maps/place?q=paris#map"));
+        assert_eq!(domains["example.com"].host.as_deref(), Some("example.com"));
+        assert_eq!(ctx.unclassifiable(), ["not a url", "someone@example.com"]);
+        assert_eq!(ctx.stats.unclassifiable_inputs, 2);
+
+        // Mocked LLM answer
+        domains["www.google.com/maps"].categories_llm = Some(vec!["Itinéraires / Cartographie"]);
+        domains["www.google.com/search"].categories_llm = Some(vec!["Moteur de recherche"]);
+        domains["example.com"].categories_llm = Some(vec!["Autres"]);
+        ctx.write(&*domains).unwrap();
+
+        let output = dir.join("outputs").join("urls.mock-model-chunk_10-thinking_0.csv");
+        let unclassifiable = dir.join("outputs").join("urls.unclassifiable.txt");
+        assert_eq!(ctx.output_files(), vec![output.clone(), dir.join("outputs").join("urls.stats.json"), unclassifiable.clone()]);
+        let content = std::fs::read_to_string(&output).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines, vec![
+            "# schema_version: 3",
+            "domain;url;host;llm_category_1",
+            "www.google.com/maps;https://www.google.com:443/maps/place?q=paris#map;www.google.com;Itinéraires / Cartographie",
+            "www.google.com/search;https://www.google.com/search?q=rust;www.google.com;Moteur de recherche",
+            "example.com;http://Example.COM:8080;example.com;Autres",
+        ]);
+        assert_eq!(std::fs::read_to_string(&unclassifiable).unwrap(), "not a url\nsomeone@example.com\n");
+        let stats: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(ctx.stats_path()).unwrap()).unwrap();
+        assert_eq!(stats["unclassifiable_inputs"], 2);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_domain_only_csv_pipeline_in_lenient_mode() {
+        let dir = std::env::temp_dir().join(format!("catvision-lenient-pipeline-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("domains.csv");
+        std::fs::write(&input, "domain\nexample.com\nnews.example.org\n").unwrap();
+        let config_json = |strict_columns: bool| format!(r#"{{
+            "max_threads": 1,
+            "support_csv": {{ "input": true, "output": true }},
+            "support_html": {{ "input": false, "output": false }},
+            "max_domain_propositions": 2,
+            "model": ["mock-model"],
+            "chunk_size": 10,
+            "thinking_budget": 0,
+            "use_gemini_explicit_caching": false,
+            "use_gemini_url_context": false,
+            "use_gemini_google_search": false,
+            "use_gemini_custom_cache_duration": null,
+            "strict_columns": {}
+        }}"#, strict_columns);
+
+        // Strict mode rejects the input
+        let strict_config = dir.join("strict.json");
+        std::fs::write(&strict_config, config_json(true)).unwrap();
+        let mut ctx = Ctx::new(&input, Some(strict_config), vec![], None).unwrap();
+        let err = ctx.parse().unwrap_err();
+        assert!(err.to_string().contains("categories_manual"));
+
+        let lenient_config = dir.join("lenient.json");
+        std::fs::write(&lenient_config, config_json(false)).unwrap();
+        let mut ctx = Ctx::new(&input, Some(lenient_config), vec![], None).unwrap();
+        let mut domains = ctx
+            .parse()
+            .unwrap()
+            .downcast::<IndexMap<String, CatVisionData>>()
+            .unwrap();
+        assert_eq!(domains.keys().collect::<Vec<_>>(), vec!["example.com", "news.example.org"]);
+        assert!(domains.values().all(|d| d.categories_manual.is_none() && d.category_olfeo.is_none()));
+
+        // Mocked LLM answer
+        domains["example.com"].categories_llm = Some(vec!["Business"]);
+        domains["news.example.org"].categories_llm = Some(vec!["News", "Society"]);
+        for _ in domains.keys() {
+            ctx.stats.increment_domain_count();
+        }
+        ctx.write(&*domains).unwrap();
+
+        let output = dir.join("outputs").join("domains.mock-model-chunk_10-thinking_0.csv");
+        let content = std::fs::read_to_string(&output).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines, vec![
+            "# schema_version: 3",
+            "domain;llm_category_1;llm_category_2",
+            "example.com;Business;",
+            "news.example.org;News;Society",
+        ]);
+
+        let summary = ctx.stats.generate_output_summary();
+        assert!(summary.contains("Olfeo match percentage: n/a"));
+        assert!(summary.contains("Level 1 match percentage: n/a"));
+        assert!(summary.contains("Total LLM match percentage: n/a"));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_jsonl_input_selected_by_extension_or_flag() {
+        let dir = std::env::temp_dir().join(format!("catvision-jsonl-pipeline-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let lines = "{\"domain\": \"example.com\", \"categories_manual\": \"Email\"}\n{\"domain\": 42}\n{\"domain\": \"news.example.org\"}\n";
+        let config_json = |support_json_input: bool| format!(r#"{{
+            "max_threads": 1,
+            "support_csv": {{ "input": true, "output": true }},
+            "support_html": {{ "input": false, "output": false }},
+            "support_json": {{ "input": {}, "output": false }},
+            "max_domain_propositions": 1,
+            "model": ["mock-model"],
+            "chunk_size": 10,
+            "thinking_budget": 0,
+            "use_gemini_explicit_caching": false,
+            "use_gemini_url_context": false,
+            "use_gemini_google_search": false,
+            "use_gemini_custom_cache_duration": null
+        }}"#, support_json_input);
+
+        assert_eq!(InputFormat::from_path(Path::new("domains.jsonl")), InputFormat::Json);
+        assert_eq!("jsonl".parse::<InputFormat>(), Ok(InputFormat::Json));
+        assert_eq!(InputFormat::Json.to_string().parse::<InputFormat>(), Ok(InputFormat::Json));
+
+        let by_extension = dir.join("domains.jsonl");
+        std::fs::write(&by_extension, lines).unwrap();
+        let by_flag = dir.join("domains.dump");
+        std::fs::write(&by_flag, lines).unwrap();
+        let config = dir.join("config.json");
+        let flag_config = dir.join("flag.json");
+        std::fs::write(&config, config_json(false)).unwrap();
+        std::fs::write(&flag_config, config_json(true)).unwrap();
+
+        for (input, config) in [(by_extension, config), (by_flag, flag_config)] {
+            let mut ctx = Ctx::new(&input, Some(config), vec![], None).unwrap();
+            let domains = ctx
+                .parse()
+                .unwrap()
+                .downcast::<IndexMap<String, CatVisionData>>()
+                .unwrap();
+            assert_eq!(domains.keys().collect::<Vec<_>>(), vec!["example.com", "news.example.org"]);
+            assert_eq!(ctx.stats.malformed_input_lines, 1);
+            assert!(ctx.stats.expected_categories);
+            assert!(!ctx.stats.olfeo_categories);
+        }
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_large_csv_input_streamed_in_batches() {
+        let dir = std::env::temp_dir().join(format!("catvision-stream-pipeline-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("domains.csv");
+        let mut content = String::from("domain;categories_manual;category_by_olfeo\n");
+        for i in 0..100_000 {
+            content.push_str(&format!("domain-{}.example.com;Email;\n", i));
+        }
+        std::fs::write(&input, content).unwrap();
+        let config_json = |support_html_output: bool| format!(r#"{{
+            "max_threads": 1,
+            "support_csv": {{ "input": true, "output": true }},
+            "support_html": {{ "input": false, "output": {} }},
+            "max_domain_propositions": 1,
+            "model": ["mock-model"],
+            "chunk_size": 100,
+            "stream_batch_size": 7000,
+            "thinking_budget": 0,
+            "use_gemini_explicit_caching": false,
+            "use_gemini_url_context": false,
+            "use_gemini_google_search": false,
+            "use_gemini_custom_cache_duration": null
+        }}"#, support_html_output);
+
+        // The HTML report needs every domain at once
+        let html_config = dir.join("html.json");
+        std::fs::write(&html_config, config_json(true)).unwrap();
+        let err = Ctx::new(&input, Some(html_config), vec![], None).err().unwrap();
+        assert!(err.to_string().contains("stream_batch_size"));
+
+        let config = dir.join("config.json");
+        std::fs::write(&config, config_json(false)).unwrap();
+        let mut ctx = Ctx::new(&input, Some(config), vec![], None).unwrap();
+        assert_eq!(ctx.stream_batch_size(), Some(7000));
+
+        let mut batch_sizes = Vec::new();
+        while let Some(batch) = ctx.parse_batch().unwrap() {
+            let mut domains = batch.downcast::<IndexMap<String, CatVisionData>>().unwrap();
+            batch_sizes.push(domains.len());
+            // Mocked LLM answer
+            for data in domains.values_mut() {
+                data.categories_llm = Some(vec!["Email"]);
+                ctx.stats.increment_domain_count();
+            }
+            ctx.write_batch(&*domains).unwrap();
+        }
+
+        // Never more than one batch in memory
+        assert_eq!(batch_sizes.len(), 15);
+        assert!(batch_sizes.iter().all(|&size| size <= 7000));
+        assert_eq!(batch_sizes.iter().sum::<usize>(), 100_000);
+        assert!(ctx.stats.expected_categories);
+
+        let output = dir.join("outputs").join("domains.mock-model-chunk_100-thinking_0.csv");
+        let content = std::fs::read_to_string(&output).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 100_002);
+        assert_eq!(lines[0], "# schema_version: 3");
+        assert_eq!(lines[1], "domain;categories_manual;category_by_olfeo;llm_category_1");
+        assert_eq!(lines[2], "domain-0.example.com;Email;;Email");
+        assert_eq!(lines[100_001], "domain-99999.example.com;Email;;Email");
+        let stats: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(ctx.stats_path()).unwrap()).unwrap();
+        assert_eq!(stats["schema_version"], 3);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_dictionary_overwritten_mid_run_taints_the_run() {
+        let dir = std::env::temp_dir().join(format!("catvision-dict-snapshot-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("domains.txt");
+        std::fs::write(&input, "example.com\nshop.example.org\n").unwrap();
+        let dict = dir.join("dict.csv");
+        std::fs::write(&dict, "domain;llm_category_1\nexample.com;Business\nexample.org;Shopping\n").unwrap();
+        let config = dir.join("config.json");
+        std::fs::write(&config, r#"{
+            "max_threads": 1,
+            "support_csv": { "input": true, "output": true },
+            "support_html": { "input": false, "output": false },
+            "max_domain_propositions": 1,
+            "model": ["mock-model"],
+            "chunk_size": 10,
+            "thinking_budget": 0,
+            "use_gemini_explicit_caching": false,
+            "use_gemini_url_context": false,
+            "use_gemini_google_search": false,
+            "use_gemini_custom_cache_duration": null
+        }"#).unwrap();
+
+        let mut ctx = Ctx::new(&input, Some(config), vec![dict.clone()], None).unwrap();
+        let snapshot = Arc::clone(ctx.dict.as_ref().unwrap());
+        assert_eq!(snapshot.files.len(), 1);
+        assert_eq!(snapshot.files[0].copy, ctx.snapshot_dir().join("dict-1").join("0-dict.csv"));
+        assert!(ctx.check_snapshots().is_empty());
+
+        // Another job overwrites the dictionary mid-run
+        std::fs::write(&dict, "domain;llm_category_1\nexample.com;Technology\n").unwrap();
+
+        // Lookups still go through the snapshot taken at startup
+        let entries = &ctx.dict.as_ref().unwrap().dictionary.entries;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries["example.com"], "Business");
+        assert_eq!(entries["example.org"], "Shopping");
+
+        let mut domains: IndexMap<String, CatVisionData> = IndexMap::new();
+        for domain in ["example.com", "shop.example.org"] {
+            let mut data = CatVisionData::new(None, None, None, None, None, None, None);
+            data.categories_llm = Some(vec!["Business"]);
+            domains.insert(domain.to_string(), data);
+        }
+        ctx.write(&domains).unwrap();
+
+        assert!(ctx.stats.tainted());
+        assert_eq!(ctx.stats.modified_inputs, vec![dict.to_string_lossy().into_owned()]);
+        assert!(ctx.stats.generate_output_summary().starts_with("WARNING: TAINTED RUN"));
+        let stats: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(ctx.stats_path()).unwrap()).unwrap();
+        assert_eq!(stats["tainted"], true);
+        assert_eq!(stats["modified_inputs"][0], dict.to_string_lossy().as_ref());
+
+        // Reloading takes a new snapshot, the previous one is left as is
+        assert_eq!(ctx.reload_dictionary().unwrap(), 1);
+        let reloaded = ctx.dict.as_ref().unwrap();
+        assert!(!Arc::ptr_eq(&snapshot, reloaded));
+        assert_eq!(reloaded.files[0].copy, ctx.snapshot_dir().join("dict-2").join("0-dict.csv"));
+        assert_eq!(reloaded.dictionary.entries["example.com"], "Technology");
+        assert_eq!(snapshot.dictionary.entries["example.com"], "Business");
+        assert!(ctx.check_snapshots().is_empty());
+        assert!(!ctx.stats.tainted());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_new_rejects_unsupported_configuration() {
+        let dir = std::env::temp_dir().join(format!("catvision-unsupported-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("domains.csv");
+        let config = dir.join("config.json");
+        std::fs::write(&config, r#"{
+            "max_threads": 1,
+            "support_csv": { "input": true, "output": false },
+            "support_html": { "input": false, "output": false },
+            "max_domain_propositions": 2,
+            "model": ["gemini-2.0-flash"],
+            "chunk_size": 10,
+            "thinking_budget": 1024,
+            "use_gemini_explicit_caching": false,
+            "use_gemini_url_context": false,
+            "use_gemini_google_search": false,
+            "use_gemini_custom_cache_duration": null
+        }"#).unwrap();
+
+        let err = Ctx::new(&input, Some(config), vec![], None).err().unwrap();
+        assert!(err.to_string().contains("gemini-2.0-flash does not support thinking_budget"));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    /// Configuration of the run tests below, with the given outputs
+    fn outputs_config(csv_output: bool, html_output: bool) -> String {
+        format!(r#"{{
+            "max_threads": 1,
+            "support_csv": {{ "input": true, "output": {} }},
+            "support_html": {{ "input": false, "output": {} }},
+            "max_domain_propositions": 1,
+            "model": ["mock-model"],
+            "chunk_size": 10,
+            "thinking_budget": 0,
+            "use_gemini_explicit_caching": false,
+            "use_gemini_url_context": false,
+            "use_gemini_google_search": false,
+            "use_gemini_custom_cache_duration": null
+        }}"#, csv_output, html_output)
+    }
+
+    #[test]
+    fn test_new_rejects_missing_input_file() {
+        let dir = std::env::temp_dir().join(format!("catvision-missing-input-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = dir.join("config.json");
+        std::fs::write(&config, outputs_config(true, false)).unwrap();
+
+        let err = Ctx::new(&dir.join("domains.csv"), Some(config), vec![], None).err().unwrap();
+        assert!(matches!(err, CatVisionError::Input(_)));
+        assert!(err.to_string().contains("domains.csv not found"));
+        // Nothing is created for a run that cannot start
+        assert!(!dir.join("outputs").exists());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_new_creates_output_directory_or_reports_it() {
+        let dir = std::env::temp_dir().join(format!("catvision-output-dir-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("domains.txt");
+        std::fs::write(&input, "example.com\n").unwrap();
+        let config = dir.join("config.json");
+        std::fs::write(&config, outputs_config(true, false)).unwrap();
+
+        Ctx::new(&input, Some(config.clone()), vec![], None).unwrap();
+        assert!(dir.join("outputs").is_dir());
+
+        // A read-only file where the output directory goes; permission bits do not stop the tests run as root
+        std::fs::remove_dir_all(dir.join("outputs")).unwrap();
+        std::fs::write(dir.join("outputs"), "").unwrap();
+        let mut permissions = std::fs::metadata(dir.join("outputs")).unwrap().permissions();
+        permissions.set_readonly(true);
+        std::fs::set_permissions(dir.join("outputs"), permissions).unwrap();
+        let err = Ctx::new(&input, Some(config), vec![], None).err().unwrap();
+        assert!(matches!(err, CatVisionError::Output(_)));
+        assert!(err.to_string().contains("Cannot create the output directory"));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_new_rejects_run_without_output() {
+        let dir = std::env::temp_dir().join(format!("catvision-no-output-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("domains.txt");
+        std::fs::write(&input, "example.com\n").unwrap();
+        let config = dir.join("config.json");
+        std::fs::write(&config, outputs_config(false, false)).unwrap();
+
+        let err = Ctx::new(&input, Some(config), vec![], None).err().unwrap();
+        assert!(matches!(err, CatVisionError::Config(_)));
+        assert!(err.to_string().contains("No output enabled"));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
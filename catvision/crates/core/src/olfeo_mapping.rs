@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+
+/// Mapping from Olfeo categories to the LLM taxonomy, used to measure how often the LLM agrees with Olfeo.
+#[derive(Debug, Clone, Default)]
+pub struct OlfeoMapping {
+    /// Olfeo category to LLM category, `None` when every Olfeo category is compared as is.
+    entries: Option<HashMap<String, String>>,
+}
+
+impl OlfeoMapping {
+    /// Mapping comparing Olfeo categories as is, used when no mapping file is configured.
+    pub fn identity() -> Self {
+        Self { entries: None }
+    }
+
+    /// Number of mapped Olfeo categories, 0 for the identity mapping.
+    pub fn len(&self) -> usize {
+        self.entries.as_ref().map_or(0, |entries| entries.len())
+    }
+
+    /// Whether no Olfeo category is mapped, true for the identity mapping.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// LLM category corresponding to an Olfeo category.
+    ///
+    /// # Returns
+    /// * `None` if the Olfeo category is missing from the mapping file
+    pub fn map_category<'a>(&'a self, olfeo_category: &'a str) -> Option<&'a str> {
+        match &self.entries {
+            Some(entries) => entries.get(olfeo_category).map(|category| category.as_str()),
+            None => Some(olfeo_category),
+        }
+    }
+}
+
+/// Load an Olfeo mapping file (`;`-delimited, with `olfeo_category` and `category` columns).
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be opened or parsed, or if a required column is missing.
+pub fn load_olfeo_mapping(path: &Path) -> Result<OlfeoMapping, Box<dyn std::error::Error>> {
+    let file = File::open(path).map_err(|e| {
+        eprintln!("Error opening file {}: {}", path.display(), e);
+        e
+    })?;
+
+    let mut rdr = csv::ReaderBuilder::new()
+        .delimiter(b';')
+        .from_reader(file);
+
+    let headers: Vec<String> = rdr.headers()?.iter().map(|s| s.trim().to_string()).collect();
+    let olfeo_idx = headers
+        .iter()
+        .position(|h| h == "olfeo_category")
+        .ok_or_else(|| format!("Required header 'olfeo_category' not found in {}", path.display()))?;
+    let category_idx = headers
+        .iter()
+        .position(|h| h == "category")
+        .ok_or_else(|| format!("Required header 'category' not found in {}", path.display()))?;
+
+    let mut entries = HashMap::new();
+
+    for record in rdr.records() {
+        let record = record?;
+        let olfeo_category = record.get(olfeo_idx).unwrap_or("").trim();
+        let category = record.get(category_idx).unwrap_or("").trim();
+
+        if olfeo_category.is_empty() || category.is_empty() {
+            continue;
+        }
+
+        entries.insert(olfeo_category.to_string(), category.to_string());
+    }
+
+    Ok(OlfeoMapping { entries: Some(entries) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use statistics::Statistics;
+
+    #[test]
+    fn test_olfeo_agreement_with_mapping_fixture() {
+        let path = std::env::temp_dir().join(format!("catvision-olfeo-mapping-{}.csv", std::process::id()));
+        std::fs::write(&path, "olfeo_category;category\n\
+            Réseaux sociaux;Réseaux sociaux\n\
+            Hebergement web / FAI;Hébergement de fichiers\n\
+            Email;Chat / Communication\n\
+            ;Autres\n").unwrap();
+
+        let mapping = load_olfeo_mapping(&path).unwrap();
+        assert_eq!(mapping.len(), 3);
+        assert_eq!(mapping.map_category("Hebergement web / FAI"), Some("Hébergement de fichiers"));
+        assert_eq!(mapping.map_category("Immobilier"), None);
+        assert_eq!(OlfeoMapping::identity().map_category("Immobilier"), Some("Immobilier"));
+
+        // (Olfeo category, LLM propositions)
+        let domains: [(&str, [&str; 2]); 5] = [
+            ("Réseaux sociaux", ["Réseaux sociaux", "Chat / Communication"]),
+            ("Hebergement web / FAI", ["Domaine technique", "Hébergement de fichiers"]),
+            ("Email", ["Email", "Autres"]),
+            ("Réseaux sociaux", ["Réseaux sociaux", "Blogs / Forums"]),
+            ("Immobilier", ["Immobilier", "Autres"]),
+        ];
+
+        let mut stats = Statistics::new(2);
+        for (olfeo_category, llm_categories) in domains {
+            stats.record_olfeo_agreement(mapping.map_category(olfeo_category), &llm_categories);
+        }
+
+        // 4 domains compared, the Immobilier one has no mapping
+        assert_eq!(stats.process_olfeo_agreement_percentage(0), 50.0);
+        assert_eq!(stats.process_olfeo_agreement_percentage(1), 25.0);
+        let summary = stats.generate_output_summary();
+        assert!(summary.contains("Olfeo agreement level 1: 50.00 % (4 domains compared)"));
+        assert!(summary.contains("Olfeo agreement level 2: 25.00 % (4 domains compared)"));
+        assert!(summary.contains("Olfeo unknown mappings: 1"));
+
+        let _ = std::fs::remove_file(path);
+    }
+}
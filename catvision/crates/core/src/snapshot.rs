@@ -0,0 +1,93 @@
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Name of the directory, inside the output directory, receiving the copies of the run inputs.
+pub const RUN_SNAPSHOT_DIR: &str = "run_snapshot";
+
+/// Input file (dictionary, Olfeo mapping) copied into the run directory when it was loaded.
+///
+/// The run only reads the copy, so another job overwriting the source file cannot change its results;
+/// the hash tells afterwards whether the source changed while the run was going on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotFile {
+    /// File the snapshot was taken from.
+    pub source: PathBuf,
+    /// Copy in the run directory.
+    pub copy: PathBuf,
+    /// SHA-256 of the content, in hexadecimal.
+    pub sha256: String,
+}
+
+impl SnapshotFile {
+    /// Hash `source` and copy it into `dir`, as `<index>-<file name>` so files with the same name do not collide.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the source cannot be read or the copy cannot be written.
+    pub fn take(source: &Path, dir: &Path, index: usize) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = std::fs::read(source).map_err(|e| {
+            eprintln!("Error opening file {}: {}", source.display(), e);
+            e
+        })?;
+
+        let file_name = source.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        std::fs::create_dir_all(dir)?;
+        let copy = dir.join(format!("{}-{}", index, file_name));
+        std::fs::write(&copy, &content)?;
+
+        Ok(SnapshotFile {
+            source: source.to_path_buf(),
+            copy,
+            sha256: sha256_hex(&content),
+        })
+    }
+
+    /// Whether the source file changed, or disappeared, since the snapshot was taken.
+    pub fn is_modified(&self) -> bool {
+        match std::fs::read(&self.source) {
+            Ok(content) => sha256_hex(&content) != self.sha256,
+            Err(_) => true,
+        }
+    }
+}
+
+/// Sources of `files` that changed since their snapshot was taken.
+pub fn modified_sources(files: &[SnapshotFile]) -> Vec<PathBuf> {
+    files
+        .iter()
+        .filter(|file| file.is_modified())
+        .map(|file| file.source.clone())
+        .collect()
+}
+
+fn sha256_hex(content: &[u8]) -> String {
+    Sha256::digest(content).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_detects_modified_source() {
+        let dir = std::env::temp_dir().join(format!("catvision-snapshot-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("dict.csv");
+        std::fs::write(&source, "domain;llm_category_1\nexample.com;Business\n").unwrap();
+
+        let snapshot = SnapshotFile::take(&source, &dir.join(RUN_SNAPSHOT_DIR), 0).unwrap();
+        assert_eq!(snapshot.copy, dir.join(RUN_SNAPSHOT_DIR).join("0-dict.csv"));
+        assert_eq!(std::fs::read(&snapshot.copy).unwrap(), std::fs::read(&source).unwrap());
+        assert_eq!(snapshot.sha256.len(), 64);
+        assert!(!snapshot.is_modified());
+
+        std::fs::write(&source, "domain;llm_category_1\nexample.com;Technology\n").unwrap();
+        assert!(snapshot.is_modified());
+        assert_eq!(modified_sources(std::slice::from_ref(&snapshot)), vec![source.clone()]);
+
+        std::fs::remove_file(&source).unwrap();
+        assert!(snapshot.is_modified());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
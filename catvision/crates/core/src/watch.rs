@@ -0,0 +1,343 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use config::WatchConfig;
+
+/// Subfolder of the watched directory receiving the inputs processed successfully.
+pub const DONE_DIR: &str = "done";
+
+/// Subfolder of the watched directory receiving the inputs whose run failed, next to their error summary.
+pub const FAILED_DIR: &str = "failed";
+
+/// Whether the file name `name` matches the glob `pattern`.
+///
+/// `*` matches any sequence of characters, `?` a single character, anything else matches itself.
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Last `*` seen and the position in the name it matches up to, to backtrack on a mismatch
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    star = Some((star_p, star_n + 1));
+                    p = star_p + 1;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Directory receiving input drops, handing them out one at a time once fully written.
+///
+/// A file is queued once its size stayed the same for `stable_secs` across two scans, so a copy still in progress
+/// is not picked up. Processed files are moved to `done/`, failed ones to `failed/`.
+pub struct DropFolder {
+    dir: PathBuf,
+    pattern: String,
+    stable_for: Duration,
+    /// Files seen but not stable yet, with their size and when it was first seen
+    candidates: HashMap<PathBuf, (u64, Instant)>,
+    /// Stable files waiting for their run, in drop order
+    queue: VecDeque<PathBuf>,
+}
+
+impl DropFolder {
+    /// Creates the `done/` and `failed/` subfolders of the watched directory
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Watched directory, file name glob and stability delay
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the watched directory does not exist or the subfolders cannot be created
+    pub fn new(config: &WatchConfig) -> std::io::Result<Self> {
+        if !config.dir.is_dir() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Watched directory {} not found", config.dir.display()),
+            ));
+        }
+        std::fs::create_dir_all(config.dir.join(DONE_DIR))?;
+        std::fs::create_dir_all(config.dir.join(FAILED_DIR))?;
+
+        Ok(DropFolder {
+            dir: config.dir.clone(),
+            pattern: config.pattern.clone(),
+            stable_for: Duration::from_secs(config.stable_secs),
+            candidates: HashMap::new(),
+            queue: VecDeque::new(),
+        })
+    }
+
+    /// Files waiting for their run
+    pub fn queued(&self) -> impl Iterator<Item = &PathBuf> {
+        self.queue.iter()
+    }
+
+    /// Scans the directory and queues the matching files whose size is stable
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be read
+    pub fn scan(&mut self) -> std::io::Result<()> {
+        let now = Instant::now();
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let Ok(metadata) = entry.metadata() else { continue };
+            if metadata.is_file() && glob_match(&self.pattern, &entry.file_name().to_string_lossy()) {
+                files.push((entry.path(), metadata.len()));
+            }
+        }
+        // Files dropped together are processed in name order
+        files.sort();
+
+        self.candidates.retain(|path, _| files.iter().any(|(file, _)| file == path));
+        for (path, size) in files {
+            if self.queue.contains(&path) {
+                continue;
+            }
+            match self.candidates.get(&path) {
+                Some(&(seen_size, since)) if seen_size == size => {
+                    if now.duration_since(since) >= self.stable_for {
+                        self.candidates.remove(&path);
+                        self.queue.push_back(path);
+                    }
+                }
+                // New file, or still being written
+                _ => {
+                    self.candidates.insert(path, (size, now));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Runs `run` on each queued file in turn, and files the inputs by outcome
+    ///
+    /// # Arguments
+    ///
+    /// * `run` - Processes an input file, returns the error summary of a failed run
+    ///
+    /// # Returns
+    /// * The new location of each processed input
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an input cannot be moved
+    pub fn process_queue(
+        &mut self,
+        run: &mut impl FnMut(&Path) -> Result<(), String>,
+    ) -> std::io::Result<Vec<PathBuf>> {
+        let mut filed = Vec::new();
+        while let Some(input) = self.queue.pop_front() {
+            let outcome = run(&input);
+            filed.push(self.file_input(&input, outcome)?);
+        }
+        Ok(filed)
+    }
+
+    /// Moves `input` to `done/`, or to `failed/` with the error summary in `<file name>.error.txt`
+    fn file_input(&self, input: &Path, outcome: Result<(), String>) -> std::io::Result<PathBuf> {
+        let file_name = input.file_name().unwrap_or_default();
+        let destination = match &outcome {
+            Ok(()) => self.dir.join(DONE_DIR).join(file_name),
+            Err(_) => self.dir.join(FAILED_DIR).join(file_name),
+        };
+        std::fs::rename(input, &destination)?;
+
+        if let Err(summary) = outcome {
+            let mut summary_name = file_name.to_os_string();
+            summary_name.push(".error.txt");
+            std::fs::write(self.dir.join(FAILED_DIR).join(summary_name), format!("{}\n", summary))?;
+        }
+        Ok(destination)
+    }
+}
+
+/// Wakes the watch loop up when the watched directory changes: inotify on Linux, the poll interval elsewhere
+struct DirectoryEvents {
+    #[cfg(target_os = "linux")]
+    events: Option<(notify::INotifyWatcher, std::sync::mpsc::Receiver<notify::Result<notify::Event>>)>,
+    poll_interval: Duration,
+}
+
+impl DirectoryEvents {
+    fn new(dir: &Path, poll_interval: Duration) -> Self {
+        #[cfg(target_os = "linux")]
+        let events = {
+            use notify::Watcher;
+
+            let (tx, rx) = std::sync::mpsc::channel();
+            let watcher = notify::INotifyWatcher::new(tx, notify::Config::default())
+                .and_then(|mut watcher| watcher.watch(dir, notify::RecursiveMode::NonRecursive).map(|()| watcher));
+            match watcher {
+                Ok(watcher) => Some((watcher, rx)),
+                Err(e) => {
+                    eprintln!("Warning: cannot watch {} for changes ({}), falling back to polling", dir.display(), e);
+                    None
+                }
+            }
+        };
+        #[cfg(not(target_os = "linux"))]
+        let _ = dir;
+
+        DirectoryEvents {
+            #[cfg(target_os = "linux")]
+            events,
+            poll_interval,
+        }
+    }
+
+    /// Blocks until the directory changes or the poll interval elapses
+    fn wait(&self) {
+        #[cfg(target_os = "linux")]
+        if let Some((_, rx)) = &self.events {
+            if rx.recv_timeout(self.poll_interval).is_ok() {
+                // A single drop raises a burst of events, one scan handles them all
+                while rx.try_recv().is_ok() {}
+            }
+            return;
+        }
+        std::thread::sleep(self.poll_interval);
+    }
+}
+
+/// Watches the directory of `config` forever, running `run` on each new input file, one run at a time
+///
+/// Files dropped while a run is going on wait in the queue for the next one.
+///
+/// # Arguments
+///
+/// * `config` - Watched directory, file name glob, stability delay and poll interval
+/// * `run` - Processes an input file, returns the error summary of a failed run
+///
+/// # Errors
+///
+/// Returns an error if the directory cannot be read or an input cannot be moved
+pub fn watch(config: &WatchConfig, mut run: impl FnMut(&Path) -> Result<(), String>) -> std::io::Result<()> {
+    let mut folder = DropFolder::new(config)?;
+    // Scans at least every second, the stability check needs them
+    let events = DirectoryEvents::new(&config.dir, Duration::from_secs(config.poll_interval_secs.max(1)));
+    println!("Watching {} for {} files to {}", config.dir.display(), config.pattern, config.command);
+
+    loop {
+        folder.scan()?;
+        for filed in folder.process_queue(&mut run)? {
+            println!("Filed {}", filed.display());
+        }
+        events.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn watch_config(dir: &Path) -> WatchConfig {
+        WatchConfig {
+            dir: dir.to_path_buf(),
+            pattern: "*.csv".to_string(),
+            command: "classify".to_string(),
+            stable_secs: 0,
+            poll_interval_secs: 1,
+        }
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.csv", "export-2024-06-03.csv"));
+        assert!(glob_match("export-*.csv", "export-w23.csv"));
+        assert!(glob_match("export-w??.csv", "export-w23.csv"));
+        assert!(glob_match("*", "anything"));
+        assert!(!glob_match("*.csv", "export.csv.part"));
+        assert!(!glob_match("export-*.csv", "report-w23.csv"));
+        assert!(!glob_match("export-w??.csv", "export-w2.csv"));
+    }
+
+    #[test]
+    fn test_drops_are_processed_sequentially_and_filed() {
+        let dir = std::env::temp_dir().join(format!("catvision-watch-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut folder = DropFolder::new(&watch_config(&dir)).unwrap();
+
+        std::fs::write(dir.join("week-01.csv"), "domain\nexample.com\n").unwrap();
+        std::fs::write(dir.join("week-02.csv"), "domain\nexample.org\n").unwrap();
+        std::fs::write(dir.join("notes.txt"), "not an export\n").unwrap();
+
+        // The first scan only records the sizes
+        folder.scan().unwrap();
+        assert_eq!(folder.queued().count(), 0);
+        folder.scan().unwrap();
+        assert_eq!(folder.queued().collect::<Vec<_>>(), vec![&dir.join("week-01.csv"), &dir.join("week-02.csv")]);
+
+        let running = Cell::new(false);
+        let mut runs = Vec::new();
+        let filed = folder
+            .process_queue(&mut |input: &Path| {
+                assert!(!running.replace(true), "runs overlap");
+                runs.push(input.file_name().unwrap().to_string_lossy().into_owned());
+                running.set(false);
+                match runs.len() {
+                    1 => Ok(()),
+                    _ => Err("LLM processing failed".to_string()),
+                }
+            })
+            .unwrap();
+
+        assert_eq!(runs, vec!["week-01.csv", "week-02.csv"]);
+        assert_eq!(filed, vec![dir.join(DONE_DIR).join("week-01.csv"), dir.join(FAILED_DIR).join("week-02.csv")]);
+        assert!(!dir.join("week-01.csv").exists());
+        assert!(!dir.join("week-02.csv").exists());
+        assert_eq!(
+            std::fs::read_to_string(dir.join(FAILED_DIR).join("week-02.csv.error.txt")).unwrap(),
+            "LLM processing failed\n"
+        );
+        assert!(dir.join("notes.txt").exists());
+
+        // Processed files are not picked up again
+        folder.scan().unwrap();
+        folder.scan().unwrap();
+        assert_eq!(folder.queued().count(), 0);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_growing_file_waits_until_stable() {
+        let dir = std::env::temp_dir().join(format!("catvision-watch-growing-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut folder = DropFolder::new(&watch_config(&dir)).unwrap();
+
+        std::fs::write(dir.join("week-03.csv"), "domain\n").unwrap();
+        folder.scan().unwrap();
+        std::fs::write(dir.join("week-03.csv"), "domain\nexample.com\n").unwrap();
+        folder.scan().unwrap();
+        assert_eq!(folder.queued().count(), 0);
+        folder.scan().unwrap();
+        assert_eq!(folder.queued().count(), 1);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -1,13 +1,63 @@
-use std::{collections::HashMap, fs::File, path::PathBuf};
-use utils::category::check_category_validity;
-use traits::{Infos, Input, Output};
+use std::{collections::HashMap, fs::File, io::Write, path::PathBuf};
+use utils::category::{check_category_validity, normalize};
+use traits::{Infos, Input, Output, StreamingInput};
 use statistics::Statistics;
 use utils::{trim_domain_by_llm,category::main_domain_for};
 use csv::{Reader, StringRecord};
 use indexmap::IndexMap;
 use itertools::Itertools;
 use utils::CatVisionData;
+use utils::error::CatVisionError;
 use std::any::Any;
+use crate::schema::{check_schema_version, csv_schema_version_line, read_csv_schema_version};
+
+/// Default CSV field delimiter.
+pub const DEFAULT_DELIMITER: u8 = b';';
+
+/// CSV dialect used when writing output files.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CsvDialect {
+    /// Field delimiter.
+    pub delimiter: u8,
+    /// Whether decimal numbers use a comma instead of a dot.
+    pub decimal_comma: bool,
+    /// Whether records are terminated with CRLF instead of LF.
+    pub crlf_line_endings: bool,
+}
+
+impl Default for CsvDialect {
+    fn default() -> Self {
+        CsvDialect {
+            delimiter: DEFAULT_DELIMITER,
+            decimal_comma: false,
+            crlf_line_endings: false,
+        }
+    }
+}
+
+/// Convert a delimiter character from the configuration into a CSV delimiter byte.
+///
+/// # Errors
+///
+/// Returns an error if the delimiter is not a single ASCII character.
+pub fn delimiter_from_char(delimiter: char) -> Result<u8, CatVisionError> {
+    if delimiter.is_ascii() {
+        Ok(delimiter as u8)
+    } else {
+        Err(CatVisionError::Config(format!("CSV delimiter '{}' must be an ASCII character", delimiter)))
+    }
+}
+
+/// Format a numeric field according to the dialect decimal separator.
+///
+/// Fields that are not decimal numbers are returned unchanged.
+pub fn format_numeric_field(field: &str, decimal_comma: bool) -> std::borrow::Cow<'_, str> {
+    if decimal_comma && field.contains('.') && field.parse::<f64>().is_ok() {
+        std::borrow::Cow::Owned(field.replacen('.', ",", 1))
+    } else {
+        std::borrow::Cow::Borrowed(field)
+    }
+}
 
 /// CSV input handler.
 #[derive(Debug)]
@@ -16,6 +66,12 @@ pub struct MyCSVInput {
     pub filename: PathBuf,
     /// Mapping of header names to their column indices.
     pub headers: HashMap<String, usize>,
+    /// Field delimiter.
+    pub delimiter: u8,
+    /// Whether missing `categories_manual` or Olfeo category columns are an error rather than a warning.
+    pub strict_columns: bool,
+    /// Reader of the batches parsed so far by `parse_batch`, past the header.
+    reader: Option<Reader<File>>,
 }
 
 impl Input for MyCSVInput {
@@ -24,6 +80,9 @@ impl Input for MyCSVInput {
         Box::new(Self {
             filename: self.filename.clone(),
             headers: self.headers.clone(),
+            delimiter: self.delimiter,
+            strict_columns: self.strict_columns,
+            reader: None,
         })
     }
 
@@ -51,14 +110,110 @@ impl Input for MyCSVInput {
         &mut self,
         stats: &mut Statistics,
         dict: Option<&HashMap<String, String>>,
-    ) -> Result<Box<dyn Any>, Box<dyn std::error::Error>> {
+    ) -> Result<Box<dyn Any>, CatVisionError> {
+        let mut rdr = self.open_reader(dict)?;
+        let mut res: IndexMap<String, CatVisionData> = IndexMap::new();
+
+        for record in rdr.records() {
+            let (domain, new_data) = self.parse_record(&record?, stats, dict);
+            res.insert(domain, new_data);
+        }
+
+        Ok(Box::new(res))
+    }
+
+    /// Create a new `MyCSVInput` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - Path to the CSV file to parse.
+    fn new(filename: &PathBuf) -> Self {
+        MyCSVInput {
+            filename: filename.to_path_buf(),
+            headers: HashMap::new(),
+            delimiter: DEFAULT_DELIMITER,
+            strict_columns: true,
+            reader: None,
+        }
+    }
+}
+
+impl StreamingInput for MyCSVInput {
+    /// Parse the next batch of records, the whole-file `parse` in pieces of `batch_size` domains.
+    /// Domains repeated in different batches are not merged.
+    ///
+    /// # Arguments
+    ///
+    /// * `stats` - Mutable reference to statistics object to track processing stats.
+    /// * `dict` - Optional mapping used to enrich data with `appsite_name_by_gemini`.
+    /// * `batch_size` - Maximum number of records in the batch.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the CSV cannot be opened or read, or if required headers are missing.
+    ///
+    /// # Returns
+    /// * `None` once every record was parsed
+    fn parse_batch(
+        &mut self,
+        stats: &mut Statistics,
+        dict: Option<&HashMap<String, String>>,
+        batch_size: usize,
+    ) -> Result<Option<Box<dyn Any>>, CatVisionError> {
+        let mut rdr = match self.reader.take() {
+            Some(rdr) => rdr,
+            None => self.open_reader(dict)?,
+        };
+
+        let mut res: IndexMap<String, CatVisionData> = IndexMap::with_capacity(batch_size);
+        let mut record = StringRecord::new();
+        while res.len() < batch_size && rdr.read_record(&mut record)? {
+            let (domain, new_data) = self.parse_record(&record, stats, dict);
+            res.insert(domain, new_data);
+        }
+
+        // Kept once exhausted, the next calls return `None` too
+        self.reader = Some(rdr);
+        if res.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Box::new(res)))
+    }
+}
+
+impl MyCSVInput {
+    /// Set the field delimiter used to read the input file.
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Set whether missing `categories_manual` or Olfeo category columns make the parsing fail.
+    pub fn with_strict_columns(mut self, strict_columns: bool) -> Self {
+        self.strict_columns = strict_columns;
+        self
+    }
+
+    /// Open the CSV file, check its schema version and read its header into `headers`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the CSV cannot be opened, if it was written with another output schema version,
+    /// or if required headers are missing.
+    fn open_reader(&mut self, dict: Option<&HashMap<String, String>>) -> Result<Reader<File>, CatVisionError> {
         let file = File::open(&self.filename).map_err(|e| {
             eprintln!("Error opening file {}: {}", self.filename.display(), e);
             e
         })?;
 
+        // A previous output classified again must have the columns of the current schema
+        if let Some(version) = read_csv_schema_version(&self.filename)? {
+            check_schema_version(&self.filename, version).map_err(CatVisionError::Input)?;
+        }
+
         let mut rdr = csv::ReaderBuilder::new()
-            .delimiter(b';')
+            .delimiter(self.delimiter)
+            .comment(Some(b'#'))
             .from_reader(file);
 
         let mut input_headers = self.parse_header(&mut rdr)?;
@@ -68,71 +223,72 @@ impl Input for MyCSVInput {
         }
 
         self.headers = input_headers;
-        let mut res: IndexMap<String, CatVisionData> = IndexMap::new();
+        Ok(rdr)
+    }
 
-        for record in rdr.records() {
-            let record = record?;
-            let mut new_data = CatVisionData::new(None, None, None, None, None, None, None);
-            let domain = record.get(*self.headers.get("domain").unwrap()).unwrap().trim();
+    /// Convert a CSV record into its domain and data, using the columns of `headers`.
+    fn parse_record(
+        &self,
+        record: &StringRecord,
+        stats: &mut Statistics,
+        dict: Option<&HashMap<String, String>>,
+    ) -> (String, CatVisionData) {
+        let mut new_data = CatVisionData::new(None, None, None, None, None, None, None);
+        let domain = record.get(*self.headers.get("domain").unwrap()).unwrap().trim();
+
+        if let Some(dict) = dict {
+            let (appsite_name_by_gemini, _) = trim_domain_by_llm(dict, domain);
+            new_data.appsite_name_by_gemini = appsite_name_by_gemini;
+        }
 
-            if let Some(dict) = dict {
-                let (appsite_name_by_gemini, _) = trim_domain_by_llm(dict, domain);
-                new_data.appsite_name_by_gemini = appsite_name_by_gemini;
+        if let Some(idx) = self.headers.get("appsite_name_by_olfeo") {
+            let appsite_name = record.get(*idx).unwrap().trim();
+            if !appsite_name.is_empty() {
+                new_data.appsite_name_by_olfeo = Some(appsite_name.to_string());
             }
+        }
 
-            if let Some(idx) = self.headers.get("appsite_name_by_olfeo") {
-                let appsite_name = record.get(*idx).unwrap().trim();
-                if !appsite_name.is_empty() {
-                    new_data.appsite_name_by_olfeo = Some(appsite_name.to_string());
-                }
+        if let Some(idx) = self.headers.get("category_by_olfeo") {
+            let olfeo_category = record.get(*idx).unwrap().trim();
+            if !olfeo_category.is_empty() {
+                new_data.category_olfeo = main_domain_for(olfeo_category);
             }
+        }
 
-            if let Some(idx) = self.headers.get("category_by_olfeo") {
-                let olfeo_category = record.get(*idx).unwrap().trim();
-                if !olfeo_category.is_empty() {
-                    new_data.category_olfeo = main_domain_for(olfeo_category);
+        for (column, field) in [
+            ("description_fr_by_gemini", &mut new_data.description_fr_by_gemini),
+            ("description_en_by_gemini", &mut new_data.description_en_by_gemini),
+            ("description_updated_at", &mut new_data.description_updated_at),
+        ] {
+            if let Some(value) = self.headers.get(column).and_then(|idx| record.get(*idx)).map(str::trim) {
+                if !value.is_empty() {
+                    *field = Some(value.to_string());
                 }
             }
+        }
 
-            if let Some(idx) = self.headers.get("categories_manual") {
-                let expected_category = record.get(*idx).unwrap().trim();
-                if !expected_category.is_empty() {
-                    new_data.categories_manual = check_category_validity(expected_category);
-                }
+        if let Some(idx) = self.headers.get("categories_manual") {
+            let expected_category = record.get(*idx).unwrap().trim();
+            if !expected_category.is_empty() {
+                new_data.categories_manual = normalize(expected_category);
             }
+        }
 
-            if let Some(idx) = self.headers.get("old_category") {
-                let olfeo_cat = record.get(*idx).unwrap().trim();
-                if !olfeo_cat.is_empty() {
-                    new_data.category_olfeo = check_category_validity(olfeo_cat);
-                    if let Some(ref expected_category) = new_data.categories_manual {
-                        if expected_category.contains(olfeo_cat) {
-                            stats.increment_olfeo_match_count();
-                        }
+        if let Some(idx) = self.headers.get("old_category") {
+            let olfeo_cat = record.get(*idx).unwrap().trim();
+            if !olfeo_cat.is_empty() {
+                new_data.category_olfeo = check_category_validity(olfeo_cat);
+                if let Some(ref expected_category) = new_data.categories_manual {
+                    if expected_category.contains(olfeo_cat) {
+                        stats.increment_olfeo_match_count();
                     }
                 }
             }
-
-            res.insert(domain.to_string(), new_data);
         }
 
-        Ok(Box::new(res))
-    }
-
-    /// Create a new `MyCSVInput` instance.
-    ///
-    /// # Arguments
-    ///
-    /// * `filename` - Path to the CSV file to parse.
-    fn new(filename: &PathBuf) -> Self {
-        MyCSVInput {
-            filename: filename.to_path_buf(),
-            headers: HashMap::new(),
-        }
+        (domain.to_string(), new_data)
     }
-}
 
-impl MyCSVInput {
     /// Parse the CSV header and return a mapping of header names to column indices.
     ///
     /// # Arguments
@@ -141,11 +297,12 @@ impl MyCSVInput {
     ///
     /// # Errors
     ///
-    /// Returns an error if required headers (e.g., `"domain"`) are missing.
+    /// Returns an error if required headers (e.g., `"domain"`) are missing, or if the `categories_manual`
+    /// or Olfeo category columns are missing with `strict_columns` set.
     pub(crate) fn parse_header(
         &mut self,
         rdr: &mut Reader<File>,
-    ) -> Result<HashMap<String, usize>, Box<dyn std::error::Error>> {
+    ) -> Result<HashMap<String, usize>, CatVisionError> {
         let headers: Vec<String> = rdr.headers()?.iter().map(|s| s.trim().to_string()).collect();
         let mut header_map = HashMap::new();
 
@@ -156,8 +313,22 @@ impl MyCSVInput {
         let required_headers = vec!["domain"];
         for req_header in required_headers {
             if !header_map.contains_key(req_header) {
-                return Err(format!("Required header '{}' not found in CSV file", req_header).into());
+                return Err(CatVisionError::Input(format!("Required header '{}' not found in CSV file", req_header)));
+            }
+        }
+
+        let mut missing_headers = Vec::new();
+        if !header_map.contains_key("categories_manual") {
+            missing_headers.push("categories_manual");
+        }
+        if !header_map.contains_key("category_by_olfeo") && !header_map.contains_key("old_category") {
+            missing_headers.push("category_by_olfeo");
+        }
+        for missing_header in missing_headers {
+            if self.strict_columns {
+                return Err(CatVisionError::Input(format!("Required header '{}' not found in CSV file, set strict_columns to false to classify without it", missing_header)));
             }
+            eprintln!("Warning: header '{}' not found in CSV file, accuracy statistics will not be computed", missing_header);
         }
 
         Ok(header_map)
@@ -171,6 +342,10 @@ pub struct MyCSVOutput {
     pub filename: PathBuf,
     /// Mapping of header names to column indices.
     pub headers: HashMap<String, usize>,
+    /// Dialect used to write the file.
+    pub dialect: CsvDialect,
+    /// Whether a batch was appended since the header was created, the next batches go after it.
+    appending: bool,
 }
 
 impl Output for MyCSVOutput {
@@ -179,9 +354,16 @@ impl Output for MyCSVOutput {
         Box::new(Self {
             filename: self.filename.clone(),
             headers: self.headers.clone(),
+            dialect: self.dialect,
+            appending: self.appending,
         })
     }
 
+    /// Get a mutable reference to self as Any.
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
     /// Write structured data to the CSV file.
     ///
     /// # Arguments
@@ -192,18 +374,122 @@ impl Output for MyCSVOutput {
     /// # Errors
     ///
     /// Returns an error if the CSV cannot be written or flushed.
-    fn write(&mut self, data: &dyn Any, _infos: &Infos) -> Result<(), Box<dyn std::error::Error>> {
+    fn write(&mut self, data: &dyn Any, _infos: &Infos) -> Result<(), CatVisionError> {
+        let file = self.create_file()?;
+        self.write_records(file, data)?;
+
+        println!("Output written to {}", self.filename.display());
+        Ok(())
+    }
+
+    /// Create a new `MyCSVOutput` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - Path to output CSV file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if parent directories cannot be created.
+    fn new(filename: &PathBuf) -> Result<Self, CatVisionError> {
+        if let Some(parent) = filename.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Ok(MyCSVOutput { filename: filename.to_path_buf(), headers: HashMap::new(), dialect: CsvDialect::default(), appending: false })
+    }
+
+    /// Append a batch of structured data to the CSV file, the first batch creates it with its header.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Data as `IndexMap<String, CatVisionData>`.
+    /// * `_infos` - Metadata info (unused here).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the CSV cannot be opened, written or flushed.
+    fn append(&mut self, data: &dyn Any, _infos: &Infos) -> Result<(), CatVisionError> {
+        let file = if self.appending {
+            std::fs::OpenOptions::new().append(true).open(&self.filename)?
+        } else {
+            self.create_file()?
+        };
+        self.appending = true;
+        self.write_records(file, data)
+    }
+
+    /// Create output headers including LLM category columns.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_headers` - Mapping of input CSV headers.
+    /// * `levels_count` - Number of LLM category levels to add.
+    fn create_output_header(&mut self, input_headers: &HashMap<String, usize>, levels_count: usize) {
+        let mut headers = input_headers.clone();
+        let offset = headers.len();
+        for i in 0..levels_count {
+            headers.insert(format!("llm_category_{}", i + 1), offset + i);
+        }
+        self.headers = headers;
+        self.appending = false;
+    }
+}
+
+impl MyCSVOutput {
+    /// Set the dialect used to write the output file.
+    pub fn with_dialect(mut self, dialect: CsvDialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Terminator of the records and line ending of the dialect.
+    fn line_endings(&self) -> (csv::Terminator, &'static str) {
+        if self.dialect.crlf_line_endings {
+            (csv::Terminator::CRLF, "\r\n")
+        } else {
+            (csv::Terminator::Any(b'\n'), "\n")
+        }
+    }
+
+    /// Create the CSV file with its schema version line and header.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be created or written.
+    fn create_file(&self) -> Result<File, CatVisionError> {
+        let (terminator, line_ending) = self.line_endings();
+        let mut file = File::create(&self.filename)?;
+        write!(file, "{}{}", csv_schema_version_line(), line_ending)?;
+
         let mut wtr = csv::WriterBuilder::new()
-            .delimiter(b';')
-            .from_path(&self.filename)?;
+            .delimiter(self.dialect.delimiter)
+            .terminator(terminator)
+            .from_writer(file);
+        wtr.write_record(&self.generate_header())?;
+        Ok(wtr.into_inner()?)
+    }
 
-        let headers = self.generate_header();
-        wtr.write_record(&headers)?;
+    /// Write one row per domain of `data` at the end of `file`.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - File positioned after the header or the previous rows.
+    /// * `data` - Data as `IndexMap<String, CatVisionData>`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is not an `IndexMap<String, CatVisionData>` or cannot be flushed.
+    fn write_records(&self, file: File, data: &dyn Any) -> Result<(), CatVisionError> {
+        let (terminator, _) = self.line_endings();
+        let mut wtr = csv::WriterBuilder::new()
+            .delimiter(self.dialect.delimiter)
+            .terminator(terminator)
+            .from_writer(file);
 
         let mut fails = 0;
         let data = data
             .downcast_ref::<IndexMap<String, CatVisionData>>()
-            .ok_or("Failed to downcast data to IndexMap<String, CatVisionData>")?;
+            .ok_or(CatVisionError::Downcast("IndexMap<String, CatVisionData>"))?;
 
         for (domain, categories) in data {
             if fails > 10 {
@@ -220,6 +506,11 @@ impl Output for MyCSVOutput {
                     "appsite_name_by_gemini" => new_row.push_field(categories.appsite_name_by_gemini.as_deref().unwrap_or("")),
                     "categories_manual" => new_row.push_field(categories.categories_manual.as_deref().unwrap_or("")),
                     "category_olfeo" => new_row.push_field(categories.category_olfeo.as_deref().unwrap_or("")),
+                    "url" => new_row.push_field(categories.url.as_deref().unwrap_or("")),
+                    "host" => new_row.push_field(categories.host.as_deref().unwrap_or("")),
+                    "description_fr_by_gemini" => new_row.push_field(categories.description_fr_by_gemini.as_deref().unwrap_or("")),
+                    "description_en_by_gemini" => new_row.push_field(categories.description_en_by_gemini.as_deref().unwrap_or("")),
+                    "description_updated_at" => new_row.push_field(categories.description_updated_at.as_deref().unwrap_or("")),
                     _ if header.starts_with("llm_category_") => {
                         let level_str = header.trim_start_matches("llm_category_");
                         if let Ok(level) = level_str.parse::<usize>() {
@@ -236,6 +527,13 @@ impl Output for MyCSVOutput {
                 }
             }
 
+            if self.dialect.decimal_comma {
+                new_row = new_row
+                    .iter()
+                    .map(|field| format_numeric_field(field, true))
+                    .collect();
+            }
+
             if let Err(e) = wtr.write_record(&new_row) {
                 eprintln!("Error writing record for domain {}: {}", domain, e);
                 fails += 1;
@@ -247,43 +545,9 @@ impl Output for MyCSVOutput {
             e
         })?;
 
-        println!("Output written to {}", self.filename.display());
         Ok(())
     }
 
-    /// Create a new `MyCSVOutput` instance.
-    ///
-    /// # Arguments
-    ///
-    /// * `filename` - Path to output CSV file.
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if parent directories cannot be created.
-    fn new(filename: &PathBuf) -> Result<Self, Box<dyn std::error::Error>> {
-        if let Some(parent) = filename.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
-        Ok(MyCSVOutput { filename: filename.to_path_buf(), headers: HashMap::new() })
-    }
-
-    /// Create output headers including LLM category columns.
-    ///
-    /// # Arguments
-    ///
-    /// * `input_headers` - Mapping of input CSV headers.
-    /// * `levels_count` - Number of LLM category levels to add.
-    fn create_output_header(&mut self, input_headers: &HashMap<String, usize>, levels_count: usize) {
-        let mut headers = input_headers.clone();
-        let offset = headers.len();
-        for i in 0..levels_count {
-            headers.insert(format!("llm_category_{}", i + 1), offset + i);
-        }
-        self.headers = headers;
-    }
-}
-
-impl MyCSVOutput {
     /// Generate the CSV header record based on the current headers mapping.
     pub fn generate_header(&self) -> StringRecord {
         let mut new_header = StringRecord::new();
@@ -295,3 +559,389 @@ impl MyCSVOutput {
         new_header
     }
 }
+
+/// Read the LLM categories of a CSV output, to resume the run that wrote it.
+/// The `*RED*` comparison annotations are removed and the categories outside of the taxonomy are dropped.
+///
+/// # Arguments
+///
+/// * `path` - Path to the CSV output.
+/// * `delimiter` - Field delimiter of the output.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read, was written with another output schema version,
+/// or has no `domain` column.
+///
+/// # Returns
+/// * The categories of every domain with at least one, in the order of the file
+pub fn read_classified_domains(
+    path: &std::path::Path,
+    delimiter: u8,
+) -> Result<IndexMap<String, Vec<&'static str>>, CatVisionError> {
+    if let Some(version) = read_csv_schema_version(path)? {
+        check_schema_version(path, version).map_err(CatVisionError::Input)?;
+    }
+
+    let mut rdr = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .comment(Some(b'#'))
+        .from_path(path)?;
+
+    let headers = rdr.headers()?.clone();
+    let domain_idx = headers
+        .iter()
+        .position(|header| header.trim() == "domain")
+        .ok_or_else(|| CatVisionError::Input(format!("Required header 'domain' not found in {}", path.display())))?;
+    // LLM columns by level, the header order is not trusted
+    let category_indexes = headers
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, header)| {
+            let level = header.trim().strip_prefix("llm_category_")?.parse::<usize>().ok()?;
+            Some((level, idx))
+        })
+        .sorted()
+        .map(|(_level, idx)| idx)
+        .collect::<Vec<usize>>();
+
+    let mut res = IndexMap::new();
+    for record in rdr.records() {
+        let record = record?;
+        let domain = record.get(domain_idx).unwrap_or("").trim();
+        let categories = category_indexes
+            .iter()
+            .filter_map(|idx| check_category_validity(record.get(*idx)?.replace("*RED*", "").trim()))
+            .collect::<Vec<&'static str>>();
+        if !domain.is_empty() && !categories.is_empty() {
+            res.insert(domain.to_string(), categories);
+        }
+    }
+
+    Ok(res)
+}
+
+/// Rows changed by [`merge_rows`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MergeSummary {
+    /// Rows of the target replaced by the fresh row of their domain.
+    pub updated: usize,
+    /// Fresh rows of domains missing from the target, appended at its end.
+    pub appended: usize,
+}
+
+/// `range` of `content` without the line breaks at its ends.
+fn trim_line_breaks(content: &[u8], mut range: std::ops::Range<usize>) -> std::ops::Range<usize> {
+    while range.start < range.end && matches!(content[range.start], b'\r' | b'\n') {
+        range.start += 1;
+    }
+    while range.end > range.start && matches!(content[range.end - 1], b'\r' | b'\n') {
+        range.end -= 1;
+    }
+    range
+}
+
+/// Rows of a CSV output, each with its domain and its bytes in the output.
+type OutputRows = Vec<(String, std::ops::Range<usize>)>;
+
+/// Header and rows of a CSV output, each row with its domain and its bytes in `content`, line breaks left out.
+fn output_rows(content: &[u8], delimiter: u8) -> Result<(StringRecord, OutputRows), Box<dyn std::error::Error>> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .comment(Some(b'#'))
+        .from_reader(content);
+
+    let headers = rdr.headers()?.clone();
+    let domain_idx = headers
+        .iter()
+        .position(|header| header.trim() == "domain")
+        .ok_or("Required header 'domain' not found")?;
+
+    let mut starts = Vec::new();
+    let mut domains = Vec::new();
+    let mut record = StringRecord::new();
+    while rdr.read_record(&mut record)? {
+        starts.push(record.position().map_or(0, |position| position.byte() as usize));
+        domains.push(record.get(domain_idx).unwrap_or("").trim().to_string());
+    }
+
+    // A row runs until the next one starts
+    let rows = domains
+        .into_iter()
+        .enumerate()
+        .map(|(idx, domain)| {
+            let end = starts.get(idx + 1).copied().unwrap_or(content.len());
+            (domain, trim_line_breaks(content, starts[idx]..end))
+        })
+        .collect();
+    Ok((headers, rows))
+}
+
+/// Merge the rows of a fresh CSV output into an existing one, for a run on some of its domains only.
+/// The rows of the other domains, the header and the line endings of the target are kept byte for byte.
+///
+/// # Arguments
+///
+/// * `target` - Content of the existing output.
+/// * `fresh` - Content of the output of the run, written with the same columns.
+/// * `delimiter` - Field delimiter of both outputs.
+/// * `merged` - Whether the fresh row of a domain is merged, so a domain the run left without a result keeps its rows.
+///
+/// # Errors
+///
+/// Returns an error if either output cannot be parsed, has no `domain` column, or if their columns differ.
+///
+/// # Returns
+/// * The merged content, every row of a fresh domain replaced by the fresh one and the unknown domains appended
+pub fn merge_rows(
+    target: &[u8],
+    fresh: &[u8],
+    delimiter: u8,
+    merged: impl Fn(&str) -> bool,
+) -> Result<(Vec<u8>, MergeSummary), Box<dyn std::error::Error>> {
+    let (target_headers, target_rows) = output_rows(target, delimiter).map_err(|e| format!("Cannot read the output to merge into: {}", e))?;
+    let (fresh_headers, fresh_rows) = output_rows(fresh, delimiter).map_err(|e| format!("Cannot read the output of the run: {}", e))?;
+    if target_headers != fresh_headers {
+        return Err(format!(
+            "The output to merge into has other columns than the output of the run: {} instead of {}",
+            target_headers.iter().join(", "),
+            fresh_headers.iter().join(", ")
+        ).into());
+    }
+
+    // The first row of a domain repeated in the fresh output wins
+    let mut fresh_by_domain: IndexMap<String, &[u8]> = IndexMap::new();
+    for (domain, range) in fresh_rows.into_iter().filter(|(domain, _)| merged(domain)) {
+        fresh_by_domain.entry(domain).or_insert(&fresh[range]);
+    }
+
+    let mut summary = MergeSummary { updated: 0, appended: 0 };
+    let mut content = Vec::with_capacity(target.len() + fresh.len());
+    let mut cursor = 0;
+    let mut merged_domains = std::collections::HashSet::new();
+    for (domain, range) in target_rows {
+        let Some(row) = fresh_by_domain.get(&domain) else {
+            continue;
+        };
+        content.extend_from_slice(&target[cursor..range.start]);
+        content.extend_from_slice(row);
+        cursor = range.end;
+        summary.updated += 1;
+        merged_domains.insert(domain);
+    }
+    content.extend_from_slice(&target[cursor..]);
+
+    let line_break: &[u8] = if target.windows(2).any(|bytes| bytes == b"\r\n") { b"\r\n" } else { b"\n" };
+    for (domain, row) in fresh_by_domain {
+        if merged_domains.contains(&domain) {
+            continue;
+        }
+        if content.last().is_some_and(|byte| *byte != b'\n') {
+            content.extend_from_slice(line_break);
+        }
+        content.extend_from_slice(row);
+        content.extend_from_slice(line_break);
+        summary.appended += 1;
+    }
+
+    Ok((content, summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_data() -> IndexMap<String, CatVisionData> {
+        let mut data = IndexMap::new();
+        data.insert(
+            "example.com".to_string(),
+            CatVisionData::new(None, None, Some(vec!["Business"]), Some("0.85".to_string()), None, None, None),
+        );
+        data.insert(
+            "news.example.org".to_string(),
+            CatVisionData::new(None, None, Some(vec!["News"]), Some("Example News".to_string()), None, None, None),
+        );
+        data
+    }
+
+    fn write_with_dialect(name: &str, dialect: CsvDialect) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("catvision-{}-{}.csv", name, std::process::id()));
+        let mut output = MyCSVOutput::new(&path).unwrap().with_dialect(dialect);
+        let input_headers = HashMap::from([
+            ("domain".to_string(), 0),
+            ("appsite_name_by_olfeo".to_string(), 1),
+        ]);
+        output.create_output_header(&input_headers, 1);
+        output.write(&sample_data(), &Infos::new("", "", "", 1)).unwrap();
+        path
+    }
+
+    fn read_back(path: &PathBuf, delimiter: u8, decimal_comma: bool) -> Vec<Vec<String>> {
+        let mut rdr = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .has_headers(false)
+            .comment(Some(b'#'))
+            .from_path(path)
+            .unwrap();
+        rdr.records()
+            .map(|record| {
+                record
+                    .unwrap()
+                    .iter()
+                    .map(|field| match field.replacen(',', ".", 1) {
+                        normalized if decimal_comma && normalized.parse::<f64>().is_ok() => normalized,
+                        _ => field.to_string(),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_format_numeric_field() {
+        assert_eq!(format_numeric_field("0.85", true), "0,85");
+        assert_eq!(format_numeric_field("0.85", false), "0.85");
+        assert_eq!(format_numeric_field("42", true), "42");
+        assert_eq!(format_numeric_field("example.com", true), "example.com");
+    }
+
+    #[test]
+    fn test_delimiter_from_char() {
+        assert_eq!(delimiter_from_char(';').unwrap(), b';');
+        assert_eq!(delimiter_from_char(',').unwrap(), b',');
+        assert!(delimiter_from_char('é').is_err());
+    }
+
+    #[test]
+    fn test_write_both_dialects_and_parse_back() {
+        let default_dialect = CsvDialect { delimiter: b',', ..CsvDialect::default() };
+        let fr_dialect = CsvDialect { delimiter: b';', decimal_comma: true, crlf_line_endings: true };
+
+        let default_path = write_with_dialect("default", default_dialect);
+        let fr_path = write_with_dialect("fr", fr_dialect);
+
+        let fr_raw = std::fs::read_to_string(&fr_path).unwrap();
+        assert!(fr_raw.contains("\r\n"));
+        assert!(fr_raw.contains("0,85"));
+        let default_raw = std::fs::read_to_string(&default_path).unwrap();
+        assert!(!default_raw.contains("\r\n"));
+        assert!(default_raw.contains("0.85"));
+        assert!(default_raw.starts_with("# schema_version: 3\n"));
+        assert!(fr_raw.starts_with("# schema_version: 3\r\n"));
+
+        let default_records = read_back(&default_path, b',', false);
+        let fr_records = read_back(&fr_path, b';', true);
+        assert_eq!(default_records.len(), 3);
+        assert_eq!(default_records, fr_records);
+        assert_eq!(default_records[0], vec!["domain", "appsite_name_by_olfeo", "llm_category_1"]);
+        assert_eq!(default_records[1], vec!["example.com", "0.85", "Business"]);
+
+        let _ = std::fs::remove_file(default_path);
+        let _ = std::fs::remove_file(fr_path);
+    }
+
+    #[test]
+    fn test_parse_previous_output_checks_schema_version() {
+        let path = write_with_dialect("reparse", CsvDialect::default());
+        let mut input = MyCSVInput::new(&path).with_strict_columns(false);
+        let data = input.parse(&mut Statistics::new(1), None).unwrap();
+        let data = data.downcast::<IndexMap<String, CatVisionData>>().unwrap();
+        assert_eq!(data.keys().collect::<Vec<_>>(), vec!["example.com", "news.example.org"]);
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, content.replacen("# schema_version: 3", "# schema_version: 2", 1)).unwrap();
+        let error = MyCSVInput::new(&path).with_strict_columns(false).parse(&mut Statistics::new(1), None).unwrap_err();
+        assert!(error.to_string().contains("was written with output schema version 2, older than version 3"));
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_read_classified_domains() {
+        let path = std::env::temp_dir().join(format!("catvision-resume-{}.csv", std::process::id()));
+        std::fs::write(&path, [
+            csv_schema_version_line().as_str(),
+            "domain;categories_manual;llm_category_2;llm_category_1",
+            "mail.example.com;Email;*RED*Médias / Actualités;Email",
+            "news.example.org;Médias / Actualités;;*RED*Médias / Actualités",
+            "pending.example.com;Email;;",
+            "unknown.example.com;;Not a category;",
+        ].join("\n") + "\n").unwrap();
+
+        let classified = read_classified_domains(&path, b';').unwrap();
+        assert_eq!(classified.into_iter().collect::<Vec<_>>(), vec![
+            ("mail.example.com".to_string(), vec!["Email", "Médias / Actualités"]),
+            ("news.example.org".to_string(), vec!["Médias / Actualités"]),
+        ]);
+
+        std::fs::write(&path, "# schema_version: 1\ndomain;llm_category_1\nexample.com;Email\n").unwrap();
+        assert!(read_classified_domains(&path, b';').unwrap_err().to_string().contains("older than version"));
+        std::fs::write(&path, "host;llm_category_1\nexample.com;Email\n").unwrap();
+        assert!(read_classified_domains(&path, b';').is_err());
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_parse_batches_and_append_them() {
+        let input_path = std::env::temp_dir().join(format!("catvision-batches-input-{}.csv", std::process::id()));
+        std::fs::write(&input_path, "domain;categories_manual\na.com;Email\nb.com;\nc.com;Emploi\nd.com;\ne.com;\n").unwrap();
+        let output_path = std::env::temp_dir().join(format!("catvision-batches-output-{}.csv", std::process::id()));
+
+        let mut input = MyCSVInput::new(&input_path).with_strict_columns(false);
+        let mut output = MyCSVOutput::new(&output_path).unwrap();
+        let mut stats = Statistics::new(1);
+        let mut batches = Vec::new();
+        while let Some(batch) = input.parse_batch(&mut stats, None, 2).unwrap() {
+            if batches.is_empty() {
+                output.create_output_header(&input.headers, 1);
+            }
+            let batch = batch.downcast::<IndexMap<String, CatVisionData>>().unwrap();
+            output.append(&*batch, &Infos::new("", "", "", 1)).unwrap();
+            batches.push(batch.keys().cloned().collect::<Vec<_>>());
+        }
+        assert_eq!(batches, vec![vec!["a.com", "b.com"], vec!["c.com", "d.com"], vec!["e.com"]]);
+        assert!(input.parse_batch(&mut stats, None, 2).unwrap().is_none());
+
+        let content = std::fs::read_to_string(&output_path).unwrap();
+        assert_eq!(content.lines().collect::<Vec<_>>(), vec![
+            "# schema_version: 3",
+            "domain;categories_manual;llm_category_1",
+            "a.com;Email;",
+            "b.com;;",
+            "c.com;Emploi;",
+            "d.com;;",
+            "e.com;;",
+        ]);
+
+        // A new header starts a new file
+        output.create_output_header(&input.headers, 1);
+        output.append(&sample_data(), &Infos::new("", "", "", 1)).unwrap();
+        assert_eq!(std::fs::read_to_string(&output_path).unwrap().lines().count(), 4);
+
+        let _ = std::fs::remove_file(input_path);
+        let _ = std::fs::remove_file(output_path);
+    }
+
+    #[test]
+    fn test_merge_rows_leaves_other_rows_byte_identical() {
+        let target = "# schema_version: 3\r\ndomain;llm_category_1;description\r\na.com;Email;\"keep; \"\"quoted\"\"\"\r\nb.com;*RED*Santé;old\r\nc.com;Autres;  spaced  \r\nb.com;Santé;duplicate\r\n";
+        let fresh = "# schema_version: 3\ndomain;llm_category_1;description\nb.com;Jeux;new\nd.com;Emploi;added\n";
+
+        let (merged, summary) = merge_rows(target.as_bytes(), fresh.as_bytes(), b';', |_| true).unwrap();
+        assert_eq!(summary, MergeSummary { updated: 2, appended: 1 });
+        assert_eq!(
+            String::from_utf8(merged).unwrap(),
+            "# schema_version: 3\r\ndomain;llm_category_1;description\r\na.com;Email;\"keep; \"\"quoted\"\"\"\r\nb.com;Jeux;new\r\nc.com;Autres;  spaced  \r\nb.com;Jeux;new\r\nd.com;Emploi;added\r\n"
+        );
+
+        // Domains left out by the filter keep their rows, even without a final line break
+        let target = "domain;llm_category_1\na.com;Email";
+        let (merged, summary) = merge_rows(target.as_bytes(), b"domain;llm_category_1\na.com;\n", b';', |domain| domain != "a.com").unwrap();
+        assert_eq!(summary, MergeSummary { updated: 0, appended: 0 });
+        assert_eq!(merged, target.as_bytes());
+
+        // Outputs of other columns are not merged
+        assert!(merge_rows(b"domain;llm_category_1\n", b"domain;llm_category_1;llm_category_2\n", b';', |_| true).is_err());
+    }
+}
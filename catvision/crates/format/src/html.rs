@@ -1,10 +1,11 @@
 use indexmap::IndexMap;
 use std::collections::HashMap;
-use std::error::Error;
 use std::path::PathBuf;
 use traits::Infos;
 use utils::CatVisionData;
+use utils::error::CatVisionError;
 use itertools::Itertools;
+use crate::schema::html_schema_version_meta;
 
 /// HTML output generator.
 #[derive(Debug)]
@@ -13,6 +14,103 @@ pub struct HTMLGenerator {
     pub filename: PathBuf,
     /// Mapping of column names to their indices.
     pub columns: HashMap<String, usize>,
+    /// Comparison with the previous run, rendered above the table when set.
+    pub comparison: Option<RunComparison>,
+}
+
+/// Domain whose first proposition changed since the previous run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropositionChange {
+    pub domain: String,
+    /// First proposition of the previous run, empty when it had none.
+    pub before: String,
+    /// First proposition of this run, empty when it has none.
+    pub after: String,
+}
+
+/// Domain whose confidence moved across the confidence threshold since the previous run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThresholdCrossing {
+    pub domain: String,
+    pub before: f64,
+    pub after: f64,
+}
+
+/// Differences between a run and the previous one, listed in the "Changed since last run" section of the report.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunComparison {
+    /// Domains classified by this run and missing from the previous one.
+    pub new_domains: Vec<String>,
+    /// Domains whose first proposition changed.
+    pub changed: Vec<PropositionChange>,
+    /// Confidence threshold of the crossings.
+    pub threshold: f64,
+    /// Domains that moved above or below `threshold`.
+    pub crossings: Vec<ThresholdCrossing>,
+}
+
+impl RunComparison {
+    /// Compares the first propositions of `data` with those of the previous run.
+    ///
+    /// # Arguments
+    ///
+    /// * `previous` - First proposition of each domain of the previous run, `None` when it had none.
+    /// * `data` - Results of this run.
+    pub fn new(previous: &HashMap<String, Option<String>>, data: &IndexMap<String, CatVisionData>) -> Self {
+        let mut comparison = RunComparison::default();
+        for (domain, categories) in data.iter().sorted_by_key(|(domain, _)| *domain) {
+            let after = first_proposition(categories);
+            match previous.get(domain) {
+                None => comparison.new_domains.push(domain.clone()),
+                Some(before) if before.as_deref().unwrap_or("") != after => comparison.changed.push(PropositionChange {
+                    domain: domain.clone(),
+                    before: before.clone().unwrap_or_default(),
+                    after: after.to_string(),
+                }),
+                Some(_) => {}
+            }
+        }
+        comparison
+    }
+
+    /// Adds the domains whose confidence moved across `threshold`, a confidence at the threshold is above it.
+    /// Domains without a confidence in both runs are left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `previous` - Confidence of each domain in the previous run.
+    /// * `current` - Confidence of each domain in this run.
+    /// * `threshold` - Confidence threshold.
+    pub fn with_confidences(mut self, previous: &HashMap<String, f64>, current: &HashMap<String, f64>, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self.crossings = current
+            .iter()
+            .filter_map(|(domain, after)| previous.get(domain).map(|before| (domain, *before, *after)))
+            .filter(|(_, before, after)| (*before >= threshold) != (*after >= threshold))
+            .map(|(domain, before, after)| ThresholdCrossing { domain: domain.clone(), before, after })
+            .sorted_by(|a, b| a.domain.cmp(&b.domain))
+            .collect();
+        self
+    }
+
+    /// Whether the runs have no difference to list.
+    pub fn is_empty(&self) -> bool {
+        self.new_domains.is_empty() && self.changed.is_empty() && self.crossings.is_empty()
+    }
+}
+
+/// First LLM proposition of a domain, empty when it has none.
+fn first_proposition(categories: &CatVisionData) -> &'static str {
+    categories.categories_llm.as_ref().and_then(|v| v.first()).copied().unwrap_or("")
+}
+
+/// Id of the table row of `domain`, the characters not allowed in a fragment are replaced with `_`.
+fn row_id(domain: &str) -> String {
+    let domain: String = domain
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+        .collect();
+    format!("row-{}", domain)
 }
 
 impl traits::Output for HTMLGenerator {
@@ -21,9 +119,15 @@ impl traits::Output for HTMLGenerator {
         Box::new(Self {
             filename: self.filename.clone(),
             columns: self.columns.clone(),
+            comparison: self.comparison.clone(),
         })
     }
 
+    /// Get a mutable reference to self as Any.
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
     /// Generate HTML output from structured data.
     ///
     /// # Arguments
@@ -38,12 +142,12 @@ impl traits::Output for HTMLGenerator {
         &mut self,
         data: &dyn std::any::Any,
         infos: &Infos,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<(), CatVisionError> {
         let data = data
             .downcast_ref::<IndexMap<String, CatVisionData>>()
-            .ok_or("Failed to downcast data to IndexMap<String, CatVisionData>")?;
+            .ok_or(CatVisionError::Downcast("IndexMap<String, CatVisionData>"))?;
 
-        generate_html_table(self.columns.clone(), data, infos, &self.filename)?;
+        generate_html_table(self.columns.clone(), data, infos, self.comparison.as_ref(), &self.filename)?;
         println!("HTML output written to {}", self.filename.display());
         Ok(())
     }
@@ -57,7 +161,7 @@ impl traits::Output for HTMLGenerator {
     /// # Errors
     ///
     /// Returns an error if parent directories cannot be created.
-    fn new(filename: &PathBuf) -> Result<Self, Box<dyn std::error::Error>> {
+    fn new(filename: &PathBuf) -> Result<Self, CatVisionError> {
         if let Some(parent) = filename.parent() {
             std::fs::create_dir_all(parent)?;
         }
@@ -65,6 +169,7 @@ impl traits::Output for HTMLGenerator {
         Ok(HTMLGenerator {
             filename: filename.to_path_buf(),
             columns: HashMap::new(),
+            comparison: None,
         })
     }
 
@@ -97,21 +202,74 @@ impl traits::Output for HTMLGenerator {
 
 /// Render a single table cell in HTML.
 ///
-/// Cells containing `*RED*` are rendered in red.
+/// Cells containing `*RED*` are rendered in red when comparison coloring is enabled.
 ///
 /// # Arguments
 ///
 /// * `html` - HTML string buffer to append to.
 /// * `value` - Cell content.
-fn render_cell(html: &mut String, value: &str) {
+/// * `compare` - Whether comparison coloring is enabled.
+fn render_cell(html: &mut String, value: &str, compare: bool) {
     if value.contains("*RED*") {
         let clean = value.replace("*RED*", "");
-        html.push_str(&format!("<td><span class='red'>{}</span></td>", clean));
+        if compare {
+            html.push_str(&format!("<td><span class='red'>{}</span></td>", clean));
+        } else {
+            html.push_str(&format!("<td>{}</td>", clean));
+        }
     } else {
         html.push_str(&format!("<td>{}</td>", value));
     }
 }
 
+/// Render the collapsible "Changed since last run" section, each domain linking to its row of the table.
+///
+/// # Arguments
+///
+/// * `html` - HTML string buffer to append to.
+/// * `comparison` - Differences with the previous run.
+fn render_comparison(html: &mut String, comparison: &RunComparison) {
+    let link = |domain: &str| format!("<a href=\"#{}\">{}</a>", row_id(domain), domain);
+
+    html.push_str("<details class=\"changes\" open><summary>Changed since last run</summary>");
+    if comparison.is_empty() {
+        html.push_str("<p>No change since the last run.</p>");
+    }
+    if !comparison.new_domains.is_empty() {
+        html.push_str(&format!("<h3>Newly classified domains ({})</h3><ul>", comparison.new_domains.len()));
+        for domain in &comparison.new_domains {
+            html.push_str(&format!("<li>{}</li>", link(domain)));
+        }
+        html.push_str("</ul>");
+    }
+    if !comparison.changed.is_empty() {
+        html.push_str(&format!("<h3>First proposition changed ({})</h3><ul>", comparison.changed.len()));
+        for change in &comparison.changed {
+            html.push_str(&format!("<li>{}: {} &rarr; {}</li>", link(&change.domain), change.before, change.after));
+        }
+        html.push_str("</ul>");
+    }
+    if !comparison.crossings.is_empty() {
+        html.push_str(&format!(
+            "<h3>Crossed the confidence threshold of {} ({})</h3><ul>",
+            comparison.threshold,
+            comparison.crossings.len()
+        ));
+        for crossing in &comparison.crossings {
+            let direction = if crossing.after >= comparison.threshold { "above" } else { "below" };
+            html.push_str(&format!(
+                "<li>{}: {} ({} &rarr; {})</li>",
+                link(&crossing.domain),
+                direction,
+                crossing.before,
+                crossing.after
+            ));
+        }
+        html.push_str("</ul>");
+    }
+    html.push_str("</details>");
+}
+
 /// Generate an HTML table from structured data.
 ///
 /// # Arguments
@@ -119,6 +277,7 @@ fn render_cell(html: &mut String, value: &str) {
 /// * `columns` - Mapping of column names to indices.
 /// * `data` - Data to render as an HTML table.
 /// * `infos` - Metadata including title, header, and footer.
+/// * `comparison` - Differences with the previous run, the section is omitted when `None`.
 /// * `output_path` - Path to the output HTML file.
 ///
 /// # Errors
@@ -128,10 +287,11 @@ pub fn generate_html_table(
     columns: HashMap<String, usize>,
     data: &IndexMap<String, CatVisionData>,
     infos: &Infos,
+    comparison: Option<&RunComparison>,
     output_path: &PathBuf,
-) -> Result<(), Box<dyn Error>> {
+) -> Result<(), CatVisionError> {
     if data.is_empty() {
-        return Err("Data is empty".into());
+        return Err(CatVisionError::Output("No domain to write in the HTML report".to_string()));
     }
 
     // Escape header/footer HTML
@@ -148,6 +308,7 @@ pub fn generate_html_table(
         "#,
     );
 
+    html.push_str(&html_schema_version_meta());
     html.push_str(&format!("<title>{}</title>", infos.title));
     html.push_str(
         r#"
@@ -158,6 +319,7 @@ pub fn generate_html_table(
             .red { color: red; }
             .stats { font-style: italic; color: #666; margin-bottom: 20px; white-space: pre-line; }
             .header, .footer { margin: 20px 0; white-space: pre-line; }
+            .changes { margin: 20px 0; padding: 8px; border: 1px solid #ddd; }
         </style>
         </head>
         <body>
@@ -166,6 +328,12 @@ pub fn generate_html_table(
 
     html.push_str(&format!("<h1>{}</h1>", infos.title));
     html.push_str(&format!("<div class=\"header\">{}</div>", header_html));
+    if let Some(comparison) = comparison {
+        render_comparison(&mut html, comparison);
+    }
+
+    // Without expected categories there is nothing to compare the LLM answer with
+    let compare = columns.contains_key("categories_manual");
 
     // Table start
     html.push_str("<table><thead><tr>");
@@ -176,14 +344,19 @@ pub fn generate_html_table(
 
     // Rows
     for (domain, categories) in data.iter().sorted_by_key(|(row, _)| *row) {
-        html.push_str("<tr>");
+        html.push_str(&format!("<tr id=\"{}\">", row_id(domain)));
         for (col_name, _) in columns.iter().sorted_by_key(|(_, idx)| *idx) {
             match col_name.as_str() {
-                "domain" => render_cell(&mut html, domain),
-                "appsite_name_by_olfeo" => render_cell(&mut html, categories.appsite_name_by_olfeo.as_deref().unwrap_or("")),
-                "appsite_name_by_gemini" => render_cell(&mut html, categories.appsite_name_by_gemini.as_deref().unwrap_or("")),
-                "categories_manual" => render_cell(&mut html, categories.categories_manual.as_deref().unwrap_or("")),
-                "category_by_olfeo" => render_cell(&mut html, categories.category_olfeo.as_deref().unwrap_or("")),
+                "domain" => render_cell(&mut html, domain, compare),
+                "appsite_name_by_olfeo" => render_cell(&mut html, categories.appsite_name_by_olfeo.as_deref().unwrap_or(""), compare),
+                "appsite_name_by_gemini" => render_cell(&mut html, categories.appsite_name_by_gemini.as_deref().unwrap_or(""), compare),
+                "categories_manual" => render_cell(&mut html, categories.categories_manual.as_deref().unwrap_or(""), compare),
+                "category_by_olfeo" => render_cell(&mut html, categories.category_olfeo.as_deref().unwrap_or(""), compare),
+                "url" => render_cell(&mut html, categories.url.as_deref().unwrap_or(""), compare),
+                "host" => render_cell(&mut html, categories.host.as_deref().unwrap_or(""), compare),
+                "description_fr_by_gemini" => render_cell(&mut html, categories.description_fr_by_gemini.as_deref().unwrap_or(""), compare),
+                "description_en_by_gemini" => render_cell(&mut html, categories.description_en_by_gemini.as_deref().unwrap_or(""), compare),
+                "description_updated_at" => render_cell(&mut html, categories.description_updated_at.as_deref().unwrap_or(""), compare),
                 other if other.starts_with("llm_category_") => {
                     let level = other.trim_start_matches("llm_category_").parse::<usize>().unwrap_or(0);
                     let cell = categories
@@ -192,9 +365,9 @@ pub fn generate_html_table(
                         .and_then(|v| v.get(level - 1))
                         .map(|s| *s)
                         .unwrap_or("");
-                    render_cell(&mut html, cell);
+                    render_cell(&mut html, cell, compare);
                 }
-                _ => render_cell(&mut html, ""),
+                _ => render_cell(&mut html, "", compare),
             }
         }
         html.push_str("</tr>");
@@ -210,3 +383,95 @@ pub fn generate_html_table(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_has_schema_version_meta_tag() {
+        let path = std::env::temp_dir().join(format!("catvision-schema-{}.html", std::process::id()));
+        let mut data = IndexMap::new();
+        data.insert(
+            "example.com".to_string(),
+            CatVisionData::new(None, None, Some(vec!["Business"]), None, None, None, None),
+        );
+        let columns = HashMap::from([("domain".to_string(), 0), ("llm_category_1".to_string(), 1)]);
+        generate_html_table(columns, &data, &Infos::new("Results", "", "", 1), None, &path).unwrap();
+
+        let html = std::fs::read_to_string(&path).unwrap();
+        assert!(html.contains("<meta name=\"schema_version\" content=\"3\">"));
+        assert!(html.find("schema_version").unwrap() < html.find("</head>").unwrap());
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    fn comparison_data() -> IndexMap<String, CatVisionData> {
+        let mut data = IndexMap::new();
+        for (domain, category) in [("lemonde.fr", "Médias / Actualités"), ("doctolib.fr", "Santé"), ("new-site.fr", "Business")] {
+            data.insert(domain.to_string(), CatVisionData::new(None, None, Some(vec![category]), None, None, None, None));
+        }
+        data
+    }
+
+    fn render(comparison: Option<&RunComparison>, name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("catvision-{}-{}.html", name, std::process::id()));
+        let columns = HashMap::from([("domain".to_string(), 0), ("llm_category_1".to_string(), 1)]);
+        generate_html_table(columns, &comparison_data(), &Infos::new("Results", "", "", 1), comparison, &path).unwrap();
+        let html = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(path);
+        html
+    }
+
+    /// Values of the `attribute="..."` attributes of `html`
+    fn attribute_values<'a>(html: &'a str, attribute: &str) -> Vec<&'a str> {
+        let pattern = format!("{}=\"", attribute);
+        html.match_indices(&pattern)
+            .map(|(start, _)| {
+                let value = &html[start + pattern.len()..];
+                &value[..value.find('"').unwrap()]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_report_without_comparison_has_no_changes_section() {
+        let html = render(None, "no-comparison");
+        assert!(!html.contains("Changed since last run"));
+        assert!(!html.contains("<details"));
+        assert_eq!(attribute_values(&html, "id").len(), 3);
+    }
+
+    #[test]
+    fn test_changes_section_links_to_existing_rows() {
+        let previous = HashMap::from([
+            ("lemonde.fr".to_string(), Some("Blogs / Forums".to_string())),
+            ("doctolib.fr".to_string(), Some("Santé".to_string())),
+        ]);
+        let comparison = RunComparison::new(&previous, &comparison_data()).with_confidences(
+            &HashMap::from([("doctolib.fr".to_string(), 0.9), ("lemonde.fr".to_string(), 0.8)]),
+            &HashMap::from([("doctolib.fr".to_string(), 0.4), ("lemonde.fr".to_string(), 0.85)]),
+            0.5,
+        );
+        assert_eq!(comparison.new_domains, vec!["new-site.fr"]);
+        assert_eq!(comparison.changed, vec![PropositionChange {
+            domain: "lemonde.fr".to_string(),
+            before: "Blogs / Forums".to_string(),
+            after: "Médias / Actualités".to_string(),
+        }]);
+        assert_eq!(comparison.crossings, vec![ThresholdCrossing { domain: "doctolib.fr".to_string(), before: 0.9, after: 0.4 }]);
+
+        let html = render(Some(&comparison), "comparison");
+        // The section comes before the table
+        assert!(html.find("Changed since last run").unwrap() < html.find("<table>").unwrap());
+        assert!(html.contains("Blogs / Forums &rarr; Médias / Actualités"));
+        assert!(html.contains("below (0.9 &rarr; 0.4)"));
+
+        let ids = attribute_values(&html, "id");
+        let anchors = attribute_values(&html, "href");
+        assert_eq!(anchors, vec!["#row-new-site.fr", "#row-lemonde.fr", "#row-doctolib.fr"]);
+        for anchor in anchors {
+            assert!(ids.contains(&anchor.trim_start_matches('#')), "{} has no row", anchor);
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use std::{collections::HashMap, path::PathBuf};
+use traits::Input;
+use statistics::Statistics;
+use utils::category::{main_domain_for, normalize};
+use utils::trim_domain_by_llm;
+use indexmap::IndexMap;
+use utils::CatVisionData;
+use utils::error::CatVisionError;
+use std::any::Any;
+
+/// JSON lines input handler, one `{"domain": ..., "category_olfeo": ..., "categories_manual": ...}` object per line.
+///
+/// Only `domain` is required, `category_olfeo`, `categories_manual` and `appsite_name_by_olfeo` are optional.
+/// Blank lines are skipped, malformed lines are counted in the statistics and skipped.
+#[derive(Debug)]
+pub struct MyJSONInput {
+    /// Path to the JSON lines file.
+    pub filename: PathBuf,
+    /// Mapping of the CSV header names of the fields found in the file to their column indices.
+    pub headers: HashMap<String, usize>,
+}
+
+impl Input for MyJSONInput {
+    /// Clone the input object.
+    fn clone_box(&self) -> Box<dyn Input> {
+        Box::new(Self {
+            filename: self.filename.clone(),
+            headers: self.headers.clone(),
+        })
+    }
+
+    /// Get a reference to self as Any.
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    /// Get a mutable reference to self as Any.
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    /// Parse the JSON lines file and return structured data, like the CSV input.
+    ///
+    /// # Arguments
+    ///
+    /// * `stats` - Mutable reference to statistics object to track processing stats.
+    /// * `dict` - Optional mapping used to enrich data with `appsite_name_by_gemini`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read. Malformed lines are not an error.
+    fn parse(
+        &mut self,
+        stats: &mut Statistics,
+        dict: Option<&HashMap<String, String>>,
+    ) -> Result<Box<dyn Any>, CatVisionError> {
+        let content = std::fs::read_to_string(&self.filename).map_err(|e| {
+            eprintln!("Error opening file {}: {}", self.filename.display(), e);
+            e
+        })?;
+
+        self.headers = HashMap::from([("domain".to_string(), 0)]);
+        let mut res: IndexMap<String, CatVisionData> = IndexMap::new();
+        let mut malformed = 0;
+
+        for (index, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record = match parse_record(line) {
+                Ok(record) => record,
+                Err(e) => {
+                    eprintln!("Warning: skipping line {} of {}: {}", index + 1, self.filename.display(), e);
+                    stats.malformed_input_lines += 1;
+                    malformed += 1;
+                    continue;
+                }
+            };
+
+            let mut new_data = CatVisionData::new(None, None, None, None, None, None, None);
+
+            if let Some(dict) = dict {
+                let (appsite_name_by_gemini, _) = trim_domain_by_llm(dict, &record.domain);
+                new_data.appsite_name_by_gemini = appsite_name_by_gemini;
+            }
+
+            if let Some(appsite_name) = record.appsite_name_by_olfeo {
+                self.add_header("appsite_name_by_olfeo");
+                new_data.appsite_name_by_olfeo = Some(appsite_name);
+            }
+
+            if let Some(expected_category) = &record.categories_manual {
+                self.add_header("categories_manual");
+                new_data.categories_manual = normalize(expected_category);
+            }
+
+            // Same meaning as the `category_by_olfeo` CSV column
+            if let Some(olfeo_category) = &record.category_olfeo {
+                self.add_header("category_by_olfeo");
+                new_data.category_olfeo = main_domain_for(olfeo_category);
+                if let (Some(category), Some(expected_category)) = (new_data.category_olfeo, new_data.categories_manual) {
+                    if expected_category.contains(category) {
+                        stats.increment_olfeo_match_count();
+                    }
+                }
+            }
+
+            res.insert(record.domain, new_data);
+        }
+
+        if dict.is_some() {
+            self.add_header("appsite_name_by_gemini");
+        }
+        if malformed > 0 {
+            eprintln!("Warning: {} malformed lines skipped in {}", malformed, self.filename.display());
+        }
+
+        Ok(Box::new(res))
+    }
+
+    /// Create a new `MyJSONInput` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - Path to the JSON lines file to parse.
+    fn new(filename: &PathBuf) -> Self {
+        MyJSONInput {
+            filename: filename.to_path_buf(),
+            headers: HashMap::new(),
+        }
+    }
+}
+
+impl MyJSONInput {
+    /// Add a column after the ones found so far, if it is not there yet.
+    fn add_header(&mut self, header: &str) {
+        if !self.headers.contains_key(header) {
+            self.headers.insert(header.to_string(), self.headers.len());
+        }
+    }
+}
+
+/// Fields of one line, blank strings are missing fields.
+#[derive(Debug, PartialEq)]
+struct JsonRecord {
+    domain: String,
+    category_olfeo: Option<String>,
+    categories_manual: Option<String>,
+    appsite_name_by_olfeo: Option<String>,
+}
+
+/// Parse one line of the file into its fields, trimmed.
+///
+/// # Errors
+///
+/// Returns an error if the line is not a JSON object with a non-blank `domain` string,
+/// or if an optional field is neither a string nor null.
+fn parse_record(line: &str) -> Result<JsonRecord, String> {
+    let value: serde_json::Value = serde_json::from_str(line).map_err(|e| format!("invalid JSON: {}", e))?;
+    let object = value.as_object().ok_or("not a JSON object")?;
+
+    let field = |name: &str| -> Result<Option<String>, String> {
+        match object.get(name) {
+            None | Some(serde_json::Value::Null) => Ok(None),
+            Some(serde_json::Value::String(s)) if s.trim().is_empty() => Ok(None),
+            Some(serde_json::Value::String(s)) => Ok(Some(s.trim().to_string())),
+            Some(_) => Err(format!("field '{}' is not a string", name)),
+        }
+    };
+
+    Ok(JsonRecord {
+        domain: field("domain")?.ok_or("missing 'domain' field")?,
+        category_olfeo: field("category_olfeo")?,
+        categories_manual: field("categories_manual")?,
+        appsite_name_by_olfeo: field("appsite_name_by_olfeo")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_record() {
+        let record = parse_record(r#"{"domain": " example.com ", "category_olfeo": null, "categories_manual": "", "extra": 1}"#).unwrap();
+        assert_eq!(record, JsonRecord {
+            domain: "example.com".to_string(),
+            category_olfeo: None,
+            categories_manual: None,
+            appsite_name_by_olfeo: None,
+        });
+
+        assert!(parse_record("{\"domain\": \"example.com\"").unwrap_err().starts_with("invalid JSON"));
+        assert_eq!(parse_record(r#"["example.com"]"#).unwrap_err(), "not a JSON object");
+        assert_eq!(parse_record(r#"{"category_olfeo": "Email"}"#).unwrap_err(), "missing 'domain' field");
+        assert_eq!(parse_record(r#"{"domain": "example.com", "categories_manual": 3}"#).unwrap_err(), "field 'categories_manual' is not a string");
+    }
+
+    #[test]
+    fn test_parse_jsonl_skips_malformed_lines() {
+        let path = std::env::temp_dir().join(format!("catvision-json-{}.jsonl", std::process::id()));
+        std::fs::write(
+            &path,
+            "{\"domain\": \"example.com\", \"categories_manual\": \"Email\"}\n\
+             not json\n\
+             \n\
+             {\"domain\": \"news.example.org\", \"appsite_name_by_olfeo\": \"Example News\", \"categories_manual\": \"Médias/Actualites\"}\n\
+             {\"category_olfeo\": \"Email\"}\n",
+        )
+        .unwrap();
+
+        let mut input = MyJSONInput::new(&path);
+        let mut stats = Statistics::new(1);
+        let data = input
+            .parse(&mut stats, None)
+            .unwrap()
+            .downcast::<IndexMap<String, CatVisionData>>()
+            .unwrap();
+
+        assert_eq!(data.keys().collect::<Vec<_>>(), vec!["example.com", "news.example.org"]);
+        assert_eq!(data["example.com"].categories_manual, Some("Email"));
+        assert_eq!(data["news.example.org"].appsite_name_by_olfeo.as_deref(), Some("Example News"));
+        // Near-miss expected categories are matched like the labels of the LLM
+        assert_eq!(data["news.example.org"].categories_manual, Some("Médias / Actualités"));
+        assert_eq!(stats.malformed_input_lines, 2);
+        assert_eq!(input.headers, HashMap::from([
+            ("domain".to_string(), 0),
+            ("categories_manual".to_string(), 1),
+            ("appsite_name_by_olfeo".to_string(), 2),
+        ]));
+
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -1,2 +1,5 @@
 pub mod csv;
-pub mod html;
\ No newline at end of file
+pub mod html;
+pub mod json;
+pub mod schema;
+pub mod txt;
\ No newline at end of file
@@ -0,0 +1,188 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use utils::error::CatVisionError;
+
+/// Version of the column set of the output files, bumped whenever a column is added, removed or renamed.
+pub const SCHEMA_VERSION: u32 = 3;
+
+/// Start of the comment line holding the schema version, first line of the CSV outputs.
+pub const CSV_SCHEMA_VERSION_PREFIX: &str = "# schema_version:";
+
+/// Column set of one output schema version.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SchemaChange {
+    /// Schema version.
+    pub version: u32,
+    /// Output columns, in order. `llm_category_<n>` stands for one column per LLM proposition.
+    pub columns: &'static [&'static str],
+    /// Migration notes from the previous version.
+    pub notes: &'static str,
+}
+
+/// Every output schema version, oldest first.
+pub const SCHEMA_CHANGELOG: &[SchemaChange] = &[
+    SchemaChange {
+        version: 1,
+        columns: &[
+            "domain",
+            "appsite_name_by_olfeo",
+            "appsite_name_by_gemini",
+            "categories_manual",
+            "category_by_olfeo",
+            "llm_category_<n>",
+            "prioritized_category",
+        ],
+        notes: "First versioned schema. Input columns are kept in their input order, `appsite_name_by_gemini` is only \
+                present with a dictionary and `prioritized_category` only in the HTML report.",
+    },
+    SchemaChange {
+        version: 2,
+        columns: &[
+            "domain",
+            "appsite_name_by_olfeo",
+            "appsite_name_by_gemini",
+            "categories_manual",
+            "category_by_olfeo",
+            "url",
+            "host",
+            "llm_category_<n>",
+            "prioritized_category",
+        ],
+        notes: "Adds the `url` and `host` columns, only present in URL mode where `domain` holds the \
+                `host/first_path_segment` key of the URL.",
+    },
+    SchemaChange {
+        version: 3,
+        columns: &[
+            "domain",
+            "appsite_name_by_olfeo",
+            "appsite_name_by_gemini",
+            "categories_manual",
+            "category_by_olfeo",
+            "url",
+            "host",
+            "description_fr_by_gemini",
+            "description_en_by_gemini",
+            "description_updated_at",
+            "llm_category_<n>",
+            "prioritized_category",
+        ],
+        notes: "Keeps the `description_fr_by_gemini` and `description_en_by_gemini` columns of the input, written \
+                empty before, and adds `description_updated_at`, the UTC time (RFC 3339) the descriptions were \
+                generated, read by `describe --refresh-older-than`. Only present when the input has them.",
+    },
+];
+
+/// Get the column set of a schema version.
+///
+/// # Arguments
+///
+/// * `version` - Schema version to look up.
+pub fn schema(version: u32) -> Option<&'static SchemaChange> {
+    SCHEMA_CHANGELOG.iter().find(|change| change.version == version)
+}
+
+/// Comment line written first in the CSV outputs, without its line terminator.
+pub fn csv_schema_version_line() -> String {
+    format!("{} {}", CSV_SCHEMA_VERSION_PREFIX, SCHEMA_VERSION)
+}
+
+/// Meta tag of the schema version, written in the head of the HTML report.
+pub fn html_schema_version_meta() -> String {
+    format!("<meta name=\"schema_version\" content=\"{}\">", SCHEMA_VERSION)
+}
+
+/// Read the schema version of a CSV file from its first line.
+///
+/// # Arguments
+///
+/// * `path` - Path to the CSV file.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read, or if its version line holds no valid version.
+///
+/// # Returns
+/// * `None` for a file without version line, an input that was not written by catvision
+pub fn read_csv_schema_version(path: &Path) -> Result<Option<u32>, CatVisionError> {
+    let mut first_line = String::new();
+    BufReader::new(File::open(path)?).read_line(&mut first_line)?;
+
+    let Some(version) = first_line.strip_prefix(CSV_SCHEMA_VERSION_PREFIX) else {
+        return Ok(None);
+    };
+    let version = version
+        .trim()
+        .parse::<u32>()
+        .map_err(|_| CatVisionError::Input(format!("Invalid schema version line in {}: {}", path.display(), first_line.trim())))?;
+    Ok(Some(version))
+}
+
+/// Check that a file written with schema `version` has the columns of the current schema.
+///
+/// # Arguments
+///
+/// * `path` - Path to the file, for the error message.
+/// * `version` - Schema version of the file.
+///
+/// # Errors
+///
+/// Returns an error explaining how to migrate if `version` is not [`SCHEMA_VERSION`].
+pub fn check_schema_version(path: &Path, version: u32) -> Result<(), String> {
+    if version < SCHEMA_VERSION {
+        return Err(format!(
+            "{} was written with output schema version {}, older than version {} written by this catvision: \
+             classify its original input again (`--command schema --schema-version {}` lists its columns)",
+            path.display(),
+            version,
+            SCHEMA_VERSION,
+            version
+        ));
+    }
+    if version > SCHEMA_VERSION {
+        return Err(format!(
+            "{} was written with output schema version {}, newer than version {} written by this catvision: \
+             update catvision to read it",
+            path.display(),
+            version,
+            SCHEMA_VERSION
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changelog_covers_every_version() {
+        let versions = SCHEMA_CHANGELOG.iter().map(|change| change.version).collect::<Vec<_>>();
+        assert_eq!(versions, (1..=SCHEMA_VERSION).collect::<Vec<_>>());
+        assert_eq!(schema(SCHEMA_VERSION).unwrap().columns[0], "domain");
+        assert!(schema(SCHEMA_VERSION + 1).is_none());
+    }
+
+    #[test]
+    fn test_read_and_check_csv_schema_version() {
+        let path = std::env::temp_dir().join(format!("catvision-schema-{}.csv", std::process::id()));
+
+        std::fs::write(&path, format!("{}\ndomain\nexample.com\n", csv_schema_version_line())).unwrap();
+        assert_eq!(read_csv_schema_version(&path).unwrap(), Some(SCHEMA_VERSION));
+        assert!(check_schema_version(&path, SCHEMA_VERSION).is_ok());
+
+        std::fs::write(&path, "domain\nexample.com\n").unwrap();
+        assert_eq!(read_csv_schema_version(&path).unwrap(), None);
+
+        std::fs::write(&path, "# schema_version: one\ndomain\n").unwrap();
+        assert!(read_csv_schema_version(&path).is_err());
+
+        let older = check_schema_version(&path, SCHEMA_VERSION - 1).unwrap_err();
+        assert!(older.contains("older than version"));
+        assert!(older.contains("--command schema --schema-version"));
+        assert!(check_schema_version(&path, SCHEMA_VERSION + 1).unwrap_err().contains("newer than version"));
+
+        let _ = std::fs::remove_file(path);
+    }
+}
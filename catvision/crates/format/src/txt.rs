@@ -0,0 +1,152 @@
+use std::{collections::HashMap, path::PathBuf};
+use traits::Input;
+use statistics::Statistics;
+use utils::trim_domain_by_llm;
+use indexmap::IndexMap;
+use utils::CatVisionData;
+use utils::error::CatVisionError;
+use std::any::Any;
+
+/// Plain text input handler, one domain per line.
+///
+/// Everything after a `#` is a comment, blank lines are skipped and duplicated
+/// domains are kept once, at their first position.
+#[derive(Debug)]
+pub struct TxtInput {
+    /// Path to the text file.
+    pub filename: PathBuf,
+    /// Mapping of header names to their column indices, only `domain` (and the dictionary column when enabled).
+    pub headers: HashMap<String, usize>,
+}
+
+impl Input for TxtInput {
+    /// Clone the input object.
+    fn clone_box(&self) -> Box<dyn Input> {
+        Box::new(Self {
+            filename: self.filename.clone(),
+            headers: self.headers.clone(),
+        })
+    }
+
+    /// Get a reference to self as Any.
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    /// Get a mutable reference to self as Any.
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    /// Parse the text file and return structured data, with every optional field set to `None`.
+    ///
+    /// # Arguments
+    ///
+    /// * `_stats` - Statistics object (unused, plain text input has no Olfeo columns).
+    /// * `dict` - Optional mapping used to enrich data with `appsite_name_by_gemini`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read.
+    fn parse(
+        &mut self,
+        _stats: &mut Statistics,
+        dict: Option<&HashMap<String, String>>,
+    ) -> Result<Box<dyn Any>, CatVisionError> {
+        let content = std::fs::read_to_string(&self.filename).map_err(|e| {
+            eprintln!("Error opening file {}: {}", self.filename.display(), e);
+            e
+        })?;
+
+        self.headers = HashMap::from([("domain".to_string(), 0)]);
+        if dict.is_some() {
+            self.headers.insert("appsite_name_by_gemini".to_string(), self.headers.len());
+        }
+
+        let mut res: IndexMap<String, CatVisionData> = IndexMap::new();
+
+        for domain in content.lines().filter_map(parse_line) {
+            if res.contains_key(domain) {
+                continue;
+            }
+
+            let mut new_data = CatVisionData::new(None, None, None, None, None, None, None);
+            if let Some(dict) = dict {
+                let (appsite_name_by_gemini, _) = trim_domain_by_llm(dict, domain);
+                new_data.appsite_name_by_gemini = appsite_name_by_gemini;
+            }
+
+            res.insert(domain.to_string(), new_data);
+        }
+
+        Ok(Box::new(res))
+    }
+
+    /// Create a new `TxtInput` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - Path to the text file to parse.
+    fn new(filename: &PathBuf) -> Self {
+        TxtInput {
+            filename: filename.to_path_buf(),
+            headers: HashMap::new(),
+        }
+    }
+}
+
+/// Domain of a line, `None` for blank and comment-only lines.
+fn parse_line(line: &str) -> Option<&str> {
+    let domain = match line.split_once('#') {
+        Some((before, _comment)) => before,
+        None => line,
+    }
+    .trim();
+
+    if domain.is_empty() {
+        None
+    } else {
+        Some(domain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_txt_with_comments_blanks_and_duplicates() {
+        let path = std::env::temp_dir().join(format!("catvision-txt-{}.txt", std::process::id()));
+        std::fs::write(
+            &path,
+            "# Domains to classify\n\
+             example.com\n\
+             \n\
+             \t news.example.org   # trailing comment\n\
+             example.com\n\
+             #commented.net\n\
+             \x20\x20\n\
+             shop.fr\r\n",
+        )
+        .unwrap();
+
+        let mut input = TxtInput::new(&path);
+        let mut stats = Statistics::new(1);
+        let data = input
+            .parse(&mut stats, None)
+            .unwrap()
+            .downcast::<IndexMap<String, CatVisionData>>()
+            .unwrap();
+
+        let domains: Vec<&str> = data.keys().map(|s| s.as_str()).collect();
+        assert_eq!(domains, vec!["example.com", "news.example.org", "shop.fr"]);
+        assert!(data.values().all(|d| d.category_olfeo.is_none()
+            && d.categories_manual.is_none()
+            && d.categories_llm.is_none()
+            && d.appsite_name_by_olfeo.is_none()
+            && d.appsite_name_by_gemini.is_none()));
+        assert_eq!(input.headers, HashMap::from([("domain".to_string(), 0)]));
+
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use config::Config;
+use utils::category::check_category_validity;
+use crate::core::LLMCommand;
+use crate::core::prompt::prompt_version;
+
+/// Number of files the cache is split into, a lookup only reads the shards of its domains.
+pub const CACHE_SHARDS: usize = 16;
+
+/// Line of a shard: the categories of a domain, for the model and prompt version that returned them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CacheEntry {
+    model: String,
+    prompt_version: String,
+    domain: String,
+    categories: Vec<String>,
+}
+
+/// Categories of the domains classified by previous runs, kept on disk so they are not paid for again.
+///
+/// Entries are keyed by `(model, prompt version, domain)`: a change of the model or of the prompt (rules, number of
+/// propositions, extra instructions) misses every entry. They are JSON lines appended to one of [`CACHE_SHARDS`]
+/// files, chosen by the hash of the domain; the last entry of a domain wins.
+///
+/// The tasks of a run share the cache, the writes to a shard are serialized and each batch of entries is appended
+/// with a single write. A line left incomplete by an interrupted run is skipped by the lookups.
+#[derive(Debug)]
+pub struct DomainCache {
+    dir: PathBuf,
+    model: String,
+    prompt_version: String,
+    shards: Vec<Mutex<()>>,
+}
+
+impl DomainCache {
+    /// Opens the cache of `dir`, the directory is created on the first write.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - Directory of the shards
+    /// * `model` - Model the entries are looked up for
+    /// * `prompt_version` - Version of the prompt, see [`prompt_version`]
+    pub fn open(dir: &Path, model: &str, prompt_version: &str) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            model: model.to_string(),
+            prompt_version: prompt_version.to_string(),
+            shards: (0..CACHE_SHARDS).map(|_| Mutex::new(())).collect(),
+        }
+    }
+
+    /// Cache of the categorization runs of `config`, looked up for its first model, None when `use_local_cache` is off
+    pub fn from_config(config: &Config) -> Option<Self> {
+        config.use_local_cache.then(|| {
+            Self::open(&config.local_cache_path, &config.model[0], &prompt_version(config, &LLMCommand::CategorizeDomains))
+        })
+    }
+
+    /// Directory of the shards
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn shard(domain: &str) -> usize {
+        Sha256::digest(domain.as_bytes())[0] as usize % CACHE_SHARDS
+    }
+
+    fn shard_path(&self, shard: usize) -> PathBuf {
+        self.dir.join(format!("shard-{:02}.jsonl", shard))
+    }
+
+    /// Looks up the categories of `domains`.
+    ///
+    /// Categories that are no longer part of the taxonomy make the entry a miss.
+    ///
+    /// # Returns
+    /// * The categories of the domains found in the cache
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a shard exists but cannot be read
+    pub fn lookup(&self, domains: &[String]) -> std::io::Result<HashMap<String, Vec<&'static str>>> {
+        let mut by_shard: HashMap<usize, Vec<&String>> = HashMap::new();
+        for domain in domains {
+            by_shard.entry(Self::shard(domain)).or_default().push(domain);
+        }
+
+        let mut hits = HashMap::new();
+        for (shard, shard_domains) in by_shard {
+            let entries = self.read_shard(shard)?;
+            for domain in shard_domains {
+                let Some(categories) = entries.get(domain.as_str()) else { continue };
+                let categories: Option<Vec<&'static str>> = categories.iter().map(|category| check_category_validity(category)).collect();
+                if let Some(categories) = categories.filter(|categories| !categories.is_empty()) {
+                    hits.insert(domain.clone(), categories);
+                }
+            }
+        }
+        Ok(hits)
+    }
+
+    /// Entries of the model and prompt version of the cache in `shard`, by domain
+    fn read_shard(&self, shard: usize) -> std::io::Result<HashMap<String, Vec<String>>> {
+        let _lock = self.shards[shard].lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        let file = match std::fs::File::open(self.shard_path(shard)) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut entries = HashMap::new();
+        for line in std::io::BufReader::new(file).lines() {
+            let Ok(entry) = serde_json::from_str::<CacheEntry>(&line?) else { continue };
+            if entry.model == self.model && entry.prompt_version == self.prompt_version {
+                entries.insert(entry.domain, entry.categories);
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Writes the categories of `categories` to the cache, replacing the entries of the same domains.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - Model that answered the categories, a fallback model is only hit by the lookups made for it
+    /// * `categories` - Categories by domain
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be created or a shard cannot be written
+    pub fn insert(&self, model: &str, categories: &HashMap<String, Vec<&'static str>>) -> std::io::Result<()> {
+        let mut by_shard: HashMap<usize, String> = HashMap::new();
+        for (domain, domain_categories) in categories {
+            let entry = CacheEntry {
+                model: model.to_string(),
+                prompt_version: self.prompt_version.clone(),
+                domain: domain.clone(),
+                categories: domain_categories.iter().map(|category| category.to_string()).collect(),
+            };
+            let lines = by_shard.entry(Self::shard(domain)).or_default();
+            lines.push_str(&serde_json::to_string(&entry)?);
+            lines.push('\n');
+        }
+        if by_shard.is_empty() {
+            return Ok(());
+        }
+
+        std::fs::create_dir_all(&self.dir)?;
+        for (shard, lines) in by_shard {
+            let _lock = self.shards[shard].lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+            let mut file = std::fs::OpenOptions::new().create(true).append(true).open(self.shard_path(shard))?;
+            file.write_all(lines.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("catvision-cache-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_lookup_is_keyed_by_model_and_prompt_version() {
+        let dir = cache_dir("keys");
+        let cache = DomainCache::open(&dir, "gemini-2.5-flash", "v1");
+        let domains = vec!["lemonde.fr".to_string(), "doctolib.fr".to_string()];
+        assert!(cache.lookup(&domains).unwrap().is_empty());
+
+        cache.insert("gemini-2.5-flash", &HashMap::from([
+            ("lemonde.fr".to_string(), vec!["Médias / Actualités"]),
+            ("doctolib.fr".to_string(), vec!["Santé"]),
+        ])).unwrap();
+        // The last entry of a domain wins
+        cache.insert("gemini-2.5-flash", &HashMap::from([("lemonde.fr".to_string(), vec!["Médias / Actualités", "Blogs / Forums"])])).unwrap();
+        // Answered by a fallback model, only hit by the lookups of that model
+        cache.insert("gemini-2.5-pro", &HashMap::from([("lemonde.fr".to_string(), vec!["Santé"])])).unwrap();
+
+        let hits = cache.lookup(&[domains.clone(), vec!["unknown.fr".to_string()]].concat()).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits["lemonde.fr"], vec!["Médias / Actualités", "Blogs / Forums"]);
+        assert_eq!(hits["doctolib.fr"], vec!["Santé"]);
+
+        // Another model only hits its own entries, another prompt misses every entry
+        let fallback_hits = DomainCache::open(&dir, "gemini-2.5-pro", "v1").lookup(&domains).unwrap();
+        assert_eq!(fallback_hits, HashMap::from([("lemonde.fr".to_string(), vec!["Santé"])]));
+        assert!(DomainCache::open(&dir, "gemini-2.5-flash", "v2").lookup(&domains).unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_lookup_skips_incomplete_lines_and_unknown_categories() {
+        let dir = cache_dir("corrupt");
+        let cache = DomainCache::open(&dir, "model", "v1");
+        cache.insert("model", &HashMap::from([("a.fr".to_string(), vec!["Santé"])])).unwrap();
+
+        let shard = cache.shard_path(DomainCache::shard("a.fr"));
+        let mut file = std::fs::OpenOptions::new().append(true).open(&shard).unwrap();
+        file.write_all(b"{\"model\":\"model\",\"prompt_version\":\"v1\",\"domain\":\"b.fr\",\"categories\":[\"Cat\n").unwrap();
+        let shard = cache.shard_path(DomainCache::shard("c.fr"));
+        std::fs::write(&shard, "{\"model\":\"model\",\"prompt_version\":\"v1\",\"domain\":\"c.fr\",\"categories\":[\"Retired category\"]}\n").unwrap();
+
+        let hits = cache.lookup(&["a.fr".to_string(), "b.fr".to_string(), "c.fr".to_string()]).unwrap();
+        assert_eq!(hits, HashMap::from([("a.fr".to_string(), vec!["Santé"])]));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_concurrent_inserts_keep_every_entry() {
+        let dir = cache_dir("concurrent");
+        let cache = DomainCache::open(&dir, "model", "v1");
+
+        std::thread::scope(|scope| {
+            for thread in 0..8 {
+                let cache = &cache;
+                scope.spawn(move || {
+                    for batch in 0..10 {
+                        let categories = (0..20)
+                            .map(|i| (format!("{}-{}-{}.fr", thread, batch, i), vec!["Santé"]))
+                            .collect::<HashMap<String, Vec<&'static str>>>();
+                        cache.insert("model", &categories).unwrap();
+                    }
+                });
+            }
+        });
+
+        let domains: Vec<String> = (0..8)
+            .flat_map(|thread| (0..10).flat_map(move |batch| (0..20).map(move |i| format!("{}-{}-{}.fr", thread, batch, i))))
+            .collect();
+        assert_eq!(cache.lookup(&domains).unwrap().len(), 1600);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
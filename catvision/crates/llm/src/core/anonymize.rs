@@ -0,0 +1,191 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::HashMap;
+use tldextract::{TldExtractor, TldOption};
+use utils::split_domain;
+
+/// Environment variable holding the HMAC key used to pseudonymize domains
+pub const ANONYMIZATION_KEY_ENV: &str = "CATVISION_ANONYMIZATION_KEY";
+
+/// Length of the token replacing the registrable label, grown on collision
+const TOKEN_LEN: usize = 6;
+const TOKEN_ALPHABET: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// Replaces the registrable label of domains with a stable HMAC-based pseudonym,
+/// keeping the subdomains and the public suffix, e.g. `mail.example.fr` -> `mail.x7kq93.fr`.
+///
+/// Pseudonyms are deterministic for a given key, so duplicated domains map to the same
+/// pseudonym, and results returned by the LLM can be mapped back to the real domains.
+pub struct Anonymizer {
+    key: Vec<u8>,
+    ext: TldExtractor,
+    to_pseudonym: HashMap<String, String>,
+    to_domain: HashMap<String, String>,
+}
+
+impl Anonymizer {
+    pub fn new(key: &[u8]) -> Self {
+        Self {
+            key: key.to_vec(),
+            ext: TldOption::default().cache_path(".tld_cache").build(),
+            to_pseudonym: HashMap::new(),
+            to_domain: HashMap::new(),
+        }
+    }
+
+    /// Creates an anonymizer keyed from the `CATVISION_ANONYMIZATION_KEY` environment variable
+    /// # Returns
+    /// * `Result<Anonymizer, String>` - An error if the variable is unset or empty
+    pub fn from_env() -> Result<Self, String> {
+        match std::env::var(ANONYMIZATION_KEY_ENV) {
+            Ok(key) if !key.is_empty() => Ok(Self::new(key.as_bytes())),
+            _ => Err(format!("Set {} environment variable to anonymize domains", ANONYMIZATION_KEY_ENV)),
+        }
+    }
+
+    /// Base36 token derived from HMAC-SHA256(key, registrable domain)
+    fn token(&self, registrable: &str, len: usize) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts keys of any size");
+        mac.update(registrable.as_bytes());
+        let digest = mac.finalize().into_bytes();
+
+        digest
+            .iter()
+            .cycle()
+            .take(len)
+            .map(|b| TOKEN_ALPHABET[*b as usize % TOKEN_ALPHABET.len()] as char)
+            .collect()
+    }
+
+    /// Returns the pseudonym of `domain`, registering it for [`Anonymizer::restore`]
+    pub fn pseudonymize(&mut self, domain: &str) -> String {
+        if let Some(pseudonym) = self.to_pseudonym.get(domain) {
+            return pseudonym.clone();
+        }
+
+        // Domains without a public suffix are replaced as a whole
+        let (subdomain, registrable, suffix) = match split_domain(&self.ext, domain) {
+            Some((subdomain, label, suffix)) => (subdomain, format!("{}.{}", label, suffix), Some(suffix)),
+            None => (None, domain.to_string(), None),
+        };
+
+        let mut len = TOKEN_LEN;
+        let pseudonym = loop {
+            let token = self.token(&registrable, len);
+            let pseudonym = match (&subdomain, &suffix) {
+                (Some(sub), Some(suffix)) => format!("{}.{}.{}", sub, token, suffix),
+                (None, Some(suffix)) => format!("{}.{}", token, suffix),
+                _ => token,
+            };
+            match self.to_domain.get(&pseudonym) {
+                Some(existing) if existing != domain => len += 2,
+                _ => break pseudonym,
+            }
+        };
+
+        self.to_pseudonym.insert(domain.to_string(), pseudonym.clone());
+        self.to_domain.insert(pseudonym.clone(), domain.to_string());
+        pseudonym
+    }
+
+    pub fn pseudonymize_all(&mut self, domains: &[String]) -> Vec<String> {
+        domains.iter().map(|d| self.pseudonymize(d)).collect()
+    }
+
+    /// Maps a pseudonym back to the real domain
+    pub fn restore(&self, pseudonym: &str) -> Option<&str> {
+        self.to_domain.get(pseudonym).map(String::as_str)
+    }
+
+    /// Maps pseudonyms back to the real domains, unknown ones are kept as is
+    pub fn restore_all(&self, pseudonyms: &[String]) -> Vec<String> {
+        pseudonyms
+            .iter()
+            .map(|p| self.restore(p).unwrap_or(p).to_string())
+            .collect()
+    }
+
+    /// Re-keys a result map from pseudonyms to the real domains
+    pub fn restore_keys<V>(&self, map: HashMap<String, V>) -> HashMap<String, V> {
+        map.into_iter()
+            .map(|(k, v)| (self.restore(&k).map(str::to_string).unwrap_or(k), v))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::prompt::{generate_categorization_full_prompt, generate_description_full_prompt};
+    use crate::providers::gemini::network::build_generate_request;
+
+    const RAW_DOMAINS: [&str; 4] = [
+        "mail.acme-secret-corp.fr",
+        "acme-secret-corp.fr",
+        "intranet.hidden-bank.co.uk",
+        "hidden-bank.co.uk",
+    ];
+
+    fn raw_domains() -> Vec<String> {
+        RAW_DOMAINS.iter().map(|d| d.to_string()).collect()
+    }
+
+    #[test]
+    fn test_pseudonym_structure_and_determinism() {
+        let mut anonymizer = Anonymizer::new(b"test-key");
+        let pseudonyms = anonymizer.pseudonymize_all(&raw_domains());
+
+        assert!(pseudonyms[0].starts_with("mail."));
+        assert!(pseudonyms[0].ends_with(".fr"));
+        assert!(pseudonyms[2].starts_with("intranet."));
+        assert!(pseudonyms[2].ends_with(".co.uk"));
+
+        // Same registrable domain, same token
+        assert_eq!(pseudonyms[0], format!("mail.{}", pseudonyms[1]));
+        assert_eq!(pseudonyms[2], format!("intranet.{}", pseudonyms[3]));
+
+        // Deterministic for a given key, different with another key
+        let mut same_key = Anonymizer::new(b"test-key");
+        assert_eq!(same_key.pseudonymize_all(&raw_domains()), pseudonyms);
+        let mut other_key = Anonymizer::new(b"other-key");
+        assert_ne!(other_key.pseudonymize(RAW_DOMAINS[1]), pseudonyms[1]);
+    }
+
+    #[test]
+    fn test_round_trip_mapping() {
+        let mut anonymizer = Anonymizer::new(b"test-key");
+        let pseudonyms = anonymizer.pseudonymize_all(&raw_domains());
+
+        assert_eq!(anonymizer.restore_all(&pseudonyms), raw_domains());
+
+        let results: HashMap<String, Vec<&'static str>> = pseudonyms
+            .iter()
+            .map(|p| (p.clone(), vec!["Business"]))
+            .collect();
+        let restored = anonymizer.restore_keys(results);
+        for domain in RAW_DOMAINS {
+            assert_eq!(restored[domain], vec!["Business"]);
+        }
+
+        assert_eq!(anonymizer.restore("unknown.com"), None);
+    }
+
+    #[test]
+    fn test_raw_domains_never_sent() {
+        let mut anonymizer = Anonymizer::new(b"test-key");
+        let pseudonyms = anonymizer.pseudonymize_all(&raw_domains());
+
+        for prompt in [
+            generate_categorization_full_prompt(&pseudonyms, 3),
+            generate_description_full_prompt(&pseudonyms),
+        ] {
+            let request = build_generate_request(&prompt, None, true, true, Some(1024), None);
+            let body = serde_json::to_string(&request).unwrap();
+
+            assert!(body.contains(&pseudonyms[0]));
+            for label in ["acme-secret-corp", "hidden-bank"] {
+                assert!(!body.contains(label), "raw domain label {} found in request body", label);
+            }
+        }
+    }
+}
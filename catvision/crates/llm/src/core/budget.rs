@@ -0,0 +1,151 @@
+use std::path::{Path, PathBuf};
+
+/// File receiving the domains left unprocessed when a run is stopped by the budget cap.
+pub const BUDGET_CHECKPOINT_FILE: &str = "budget_checkpoint.txt";
+
+/// Outcome of a budget check after a batch of chunks.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BudgetStatus {
+    /// Spent and projected costs are within the cap.
+    WithinBudget,
+    /// The projected end-of-run cost exceeds the cap by more than the warning factor.
+    ProjectionExceeded { spent_eur: f64, projected_eur: f64 },
+    /// The accumulated cost exceeds the cap.
+    Exceeded { spent_eur: f64 },
+}
+
+/// Tracks the accumulated cost of a run against `max_cost_eur`.
+#[derive(Debug, Clone)]
+pub struct BudgetTracker {
+    max_cost_eur: f64,
+    projection_factor: f64,
+    total_chunks: usize,
+}
+
+impl BudgetTracker {
+    /// Creates a new tracker.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_cost_eur` - Cost cap of the run, in euros
+    /// * `projection_factor` - Factor by which the projected cost may exceed the cap before the run is stopped early
+    /// * `total_chunks` - Number of chunks of the run
+    pub fn new(max_cost_eur: f64, projection_factor: f64, total_chunks: usize) -> Self {
+        Self { max_cost_eur, projection_factor, total_chunks }
+    }
+
+    pub fn max_cost_eur(&self) -> f64 {
+        self.max_cost_eur
+    }
+
+    /// Projected end-of-run cost, extrapolated from the average cost of the chunks done so far.
+    pub fn projected_cost(&self, spent_eur: f64, chunks_done: usize) -> f64 {
+        if chunks_done == 0 {
+            return spent_eur;
+        }
+        spent_eur / chunks_done as f64 * self.total_chunks.max(chunks_done) as f64
+    }
+
+    /// Checks the cost spent after `chunks_done` chunks.
+    ///
+    /// # Returns
+    ///
+    /// The budget status, anything but `WithinBudget` means no new chunk should be launched.
+    pub fn check(&self, spent_eur: f64, chunks_done: usize) -> BudgetStatus {
+        if spent_eur > self.max_cost_eur {
+            return BudgetStatus::Exceeded { spent_eur };
+        }
+
+        let projected_eur = self.projected_cost(spent_eur, chunks_done);
+        if projected_eur > self.max_cost_eur * self.projection_factor {
+            return BudgetStatus::ProjectionExceeded { spent_eur, projected_eur };
+        }
+
+        BudgetStatus::WithinBudget
+    }
+}
+
+/// Details of a run stopped by the budget cap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetStop {
+    /// Check that stopped the run.
+    pub status: BudgetStatus,
+    /// Cost cap of the run, in euros.
+    pub max_cost_eur: f64,
+    /// Domains that were not sent to the LLM.
+    pub remaining_domains: Vec<String>,
+    /// Checkpoint file listing the remaining domains, if it could be written.
+    pub checkpoint: Option<PathBuf>,
+}
+
+/// Writes the remaining domains to a checkpoint file, one domain per line.
+///
+/// # Arguments
+///
+/// * `domains` - Domains left unprocessed
+/// * `path` - Checkpoint file, overwritten if it exists
+pub fn write_checkpoint(domains: &[String], path: &Path) -> std::io::Result<()> {
+    let mut content = String::with_capacity(domains.len() * 16);
+    for domain in domains {
+        content.push_str(domain);
+        content.push('\n');
+    }
+    std::fs::write(path, content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Replays a run where every chunk costs `chunk_cost` and returns the first non `WithinBudget` status
+    fn run_with_mock_pricing(tracker: &BudgetTracker, chunk_cost: f64, total_chunks: usize) -> (usize, BudgetStatus) {
+        let mut spent = 0.0;
+        for chunks_done in 1..=total_chunks {
+            spent += chunk_cost;
+            let status = tracker.check(spent, chunks_done);
+            if status != BudgetStatus::WithinBudget {
+                return (chunks_done, status);
+            }
+        }
+        (total_chunks, BudgetStatus::WithinBudget)
+    }
+
+    #[test]
+    fn test_budget_hard_stop() {
+        // Projection check disabled, only the accumulated cost can stop the run
+        let tracker = BudgetTracker::new(1.0, f64::INFINITY, 10);
+        let (chunks_done, status) = run_with_mock_pricing(&tracker, 0.3, 10);
+        assert_eq!(chunks_done, 4);
+        match status {
+            BudgetStatus::Exceeded { spent_eur } => assert!((spent_eur - 1.2).abs() < 1e-9),
+            other => panic!("unexpected status {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_budget_projection_warning() {
+        // 10 chunks at 0.3€ project to 3€, over twice the 1€ cap after the very first chunk
+        let tracker = BudgetTracker::new(1.0, 2.0, 10);
+        let (chunks_done, status) = run_with_mock_pricing(&tracker, 0.3, 10);
+        assert_eq!(chunks_done, 1);
+        match status {
+            BudgetStatus::ProjectionExceeded { spent_eur, projected_eur } => {
+                assert!((spent_eur - 0.3).abs() < 1e-9);
+                assert!((projected_eur - 3.0).abs() < 1e-9);
+            }
+            other => panic!("unexpected status {:?}", other),
+        }
+
+        // A run projected within the factor completes
+        let tracker = BudgetTracker::new(1.0, 2.0, 10);
+        assert_eq!(run_with_mock_pricing(&tracker, 0.09, 10), (10, BudgetStatus::WithinBudget));
+    }
+
+    #[test]
+    fn test_write_checkpoint() {
+        let path = std::env::temp_dir().join(format!("catvision-checkpoint-{}.txt", std::process::id()));
+        write_checkpoint(&["a.com".to_string(), "b.org".to_string()], &path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "a.com\nb.org\n");
+        let _ = std::fs::remove_file(path);
+    }
+}
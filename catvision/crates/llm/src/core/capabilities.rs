@@ -0,0 +1,235 @@
+use config::Config;
+
+/// Request features supported by a model family
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelCapabilities {
+    /// Model name prefix identifying the family
+    pub family: &'static str,
+    /// Whether requests may carry a thinking budget
+    pub supports_thinking: bool,
+    /// Whether explicit context caching is available
+    pub supports_explicit_caching: bool,
+    /// Whether the google_search tool is available
+    pub supports_google_search: bool,
+    /// Maximum number of output tokens of a response
+    pub max_output_tokens: u32,
+}
+
+/// Capabilities of the known model families, more specific prefixes first
+const MODEL_CAPABILITIES: [ModelCapabilities; 7] = [
+    ModelCapabilities { family: "gemini-2.5-flash-lite", supports_thinking: false, supports_explicit_caching: true, supports_google_search: true, max_output_tokens: 65536 },
+    ModelCapabilities { family: "gemini-2.5-flash", supports_thinking: true, supports_explicit_caching: true, supports_google_search: true, max_output_tokens: 65536 },
+    ModelCapabilities { family: "gemini-2.5-pro", supports_thinking: true, supports_explicit_caching: true, supports_google_search: true, max_output_tokens: 65536 },
+    ModelCapabilities { family: "gemini-2.0-flash-lite", supports_thinking: false, supports_explicit_caching: false, supports_google_search: false, max_output_tokens: 8192 },
+    ModelCapabilities { family: "gemini-2.0-flash", supports_thinking: false, supports_explicit_caching: true, supports_google_search: true, max_output_tokens: 8192 },
+    ModelCapabilities { family: "gemini-1.5-flash", supports_thinking: false, supports_explicit_caching: true, supports_google_search: true, max_output_tokens: 8192 },
+    ModelCapabilities { family: "gemini-1.5-pro", supports_thinking: false, supports_explicit_caching: true, supports_google_search: true, max_output_tokens: 8192 },
+];
+
+/// Returns the capabilities of `model`, or None if its family is unknown
+///
+/// # Arguments
+///
+/// * `model` - Model name, versioned names such as `gemini-2.5-flash-preview-05-20` match their family
+pub fn model_capabilities(model: &str) -> Option<&'static ModelCapabilities> {
+    MODEL_CAPABILITIES.iter().find(|capabilities| model.starts_with(capabilities.family))
+}
+
+/// Configuration field a model may not support
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    Thinking,
+    ExplicitCaching,
+    GoogleSearch,
+}
+
+impl Capability {
+    /// Name of the configuration field enabling the capability
+    pub fn config_field(&self) -> &'static str {
+        match self {
+            Capability::Thinking => "thinking_budget",
+            Capability::ExplicitCaching => "use_gemini_explicit_caching",
+            Capability::GoogleSearch => "use_gemini_google_search",
+        }
+    }
+}
+
+/// Configuration field set for a model that does not support it
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapabilityMismatch {
+    pub model: String,
+    pub capability: Capability,
+}
+
+impl std::fmt::Display for CapabilityMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fix = match self.capability {
+            Capability::Thinking => "remove it",
+            Capability::ExplicitCaching | Capability::GoogleSearch => "set it to false",
+        };
+        write!(f, "{} does not support {}; {} or switch model", self.model, self.capability.config_field(), fix)
+    }
+}
+
+impl std::error::Error for CapabilityMismatch {}
+
+/// Lists the fields of `config` not supported by its models
+///
+/// Models of an unknown family are skipped with a warning.
+///
+/// # Arguments
+///
+/// * `config` - Configuration to check
+pub fn check_model_capabilities(config: &Config) -> Vec<CapabilityMismatch> {
+    let mut mismatches = Vec::new();
+
+    for model in &config.model {
+        let Some(capabilities) = model_capabilities(model) else {
+            eprintln!("Warning: unknown model {}, its capabilities are not checked", model);
+            continue;
+        };
+
+        let unsupported = [
+            (Capability::Thinking, config.thinking_budget.is_some() && !capabilities.supports_thinking),
+            (Capability::ExplicitCaching, config.use_gemini_explicit_caching && !capabilities.supports_explicit_caching),
+            (Capability::GoogleSearch, config.use_gemini_google_search && !capabilities.supports_google_search),
+        ];
+        for (capability, _) in unsupported.into_iter().filter(|(_, unsupported)| *unsupported) {
+            mismatches.push(CapabilityMismatch { model: model.clone(), capability });
+        }
+    }
+
+    mismatches
+}
+
+/// Validates `config` against the capabilities of its models
+///
+/// With `lenient_capabilities` set, the unsupported fields are dropped from the configuration,
+/// and so from every request, instead of failing.
+///
+/// # Arguments
+///
+/// * `config` - Configuration to validate, normalized in place in lenient mode
+///
+/// # Errors
+///
+/// Returns every mismatch found when `lenient_capabilities` is not set
+pub fn apply_model_capabilities(config: &mut Config) -> Result<Vec<CapabilityMismatch>, Vec<CapabilityMismatch>> {
+    let mismatches = check_model_capabilities(config);
+    if mismatches.is_empty() {
+        return Ok(mismatches);
+    }
+    if !config.lenient_capabilities {
+        return Err(mismatches);
+    }
+
+    for mismatch in &mismatches {
+        eprintln!("Warning: {}, dropping {} from the requests", mismatch, mismatch.capability.config_field());
+        match mismatch.capability {
+            Capability::Thinking => config.thinking_budget = None,
+            Capability::ExplicitCaching => config.use_gemini_explicit_caching = false,
+            Capability::GoogleSearch => config.use_gemini_google_search = false,
+        }
+    }
+
+    Ok(mismatches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_for(model: &str) -> Config {
+        Config {
+            model: vec![model.to_string()],
+            thinking_budget: None,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn test_model_capabilities_lookup() {
+        assert!(model_capabilities("gemini-2.5-flash").unwrap().supports_thinking);
+        assert!(model_capabilities("gemini-2.5-flash-preview-05-20").unwrap().supports_thinking);
+        assert_eq!(model_capabilities("gemini-2.5-flash-lite-preview").unwrap().family, "gemini-2.5-flash-lite");
+        assert_eq!(model_capabilities("gemini-2.0-flash-001").unwrap().max_output_tokens, 8192);
+        assert!(model_capabilities("Qwen2.5-Coder-32B-Instruct-AWQ").is_none());
+    }
+
+    #[test]
+    fn test_thinking_budget_on_non_thinking_models() {
+        for model in ["gemini-2.0-flash", "gemini-2.5-flash-lite", "gemini-1.5-pro"] {
+            let mut config = Config { thinking_budget: Some(1024), ..config_for(model) };
+            let mismatches = apply_model_capabilities(&mut config).unwrap_err();
+            assert_eq!(mismatches, vec![CapabilityMismatch { model: model.to_string(), capability: Capability::Thinking }]);
+        }
+
+        let config = Config { thinking_budget: Some(1024), ..config_for("gemini-2.0-flash") };
+        assert_eq!(
+            check_model_capabilities(&config)[0].to_string(),
+            "gemini-2.0-flash does not support thinking_budget; remove it or switch model"
+        );
+
+        let mut config = Config { thinking_budget: Some(1024), ..config_for("gemini-2.5-pro") };
+        assert_eq!(apply_model_capabilities(&mut config), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_explicit_caching_and_google_search_on_unsupported_model() {
+        let mut config = Config {
+            use_gemini_explicit_caching: true,
+            use_gemini_google_search: true,
+            ..config_for("gemini-2.0-flash-lite")
+        };
+        let mismatches = apply_model_capabilities(&mut config).unwrap_err();
+        assert_eq!(
+            mismatches.iter().map(|mismatch| mismatch.capability).collect::<Vec<_>>(),
+            vec![Capability::ExplicitCaching, Capability::GoogleSearch]
+        );
+        assert_eq!(
+            mismatches[1].to_string(),
+            "gemini-2.0-flash-lite does not support use_gemini_google_search; set it to false or switch model"
+        );
+        // Strict mode leaves the configuration untouched
+        assert!(config.use_gemini_explicit_caching);
+        assert!(config.use_gemini_google_search);
+    }
+
+    #[test]
+    fn test_lenient_capabilities_drop_unsupported_fields() {
+        let mut config = Config {
+            thinking_budget: Some(2048),
+            use_gemini_explicit_caching: true,
+            use_gemini_google_search: true,
+            lenient_capabilities: true,
+            ..config_for("gemini-2.0-flash-lite")
+        };
+        let mismatches = apply_model_capabilities(&mut config).unwrap();
+        assert_eq!(mismatches.len(), 3);
+        assert_eq!(config.thinking_budget, None);
+        assert!(!config.use_gemini_explicit_caching);
+        assert!(!config.use_gemini_google_search);
+
+        // Supported fields are kept
+        let mut config = Config {
+            thinking_budget: Some(2048),
+            use_gemini_google_search: true,
+            lenient_capabilities: true,
+            ..config_for("gemini-2.0-flash")
+        };
+        apply_model_capabilities(&mut config).unwrap();
+        assert_eq!(config.thinking_budget, None);
+        assert!(config.use_gemini_google_search);
+    }
+
+    #[test]
+    fn test_unknown_model_skips_validation() {
+        let mut config = Config {
+            thinking_budget: Some(1024),
+            use_gemini_explicit_caching: true,
+            ..config_for("Mistral-Small")
+        };
+        assert_eq!(apply_model_capabilities(&mut config), Ok(vec![]));
+        assert_eq!(config.thinking_budget, Some(1024));
+    }
+}
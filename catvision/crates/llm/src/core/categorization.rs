@@ -1,6 +1,7 @@
-use serde_json::Value;
+use crate::core::output::{parse_llm_output, OutputFallback};
+use config::Config;
 use std::collections::HashMap;
-use utils::category::{check_category_validity};
+use utils::category::{check_category_validity, match_category, CategoryMatch};
 
 #[derive(Debug)]
 pub enum DomainError {
@@ -9,32 +10,69 @@ pub enum DomainError {
     InvalidStrings,             // elements are not strings
     }
 
+/// Label returned by the LLM that was auto-corrected to a category of the taxonomy
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategoryCorrection {
+    /// Label returned by the LLM
+    pub original: String,
+    /// Category it was corrected to
+    pub corrected: &'static str,
+}
+
+/// Rules the categories returned by the LLM are held to, from the configuration of the run
+#[derive(Debug, Clone, Default)]
+pub struct CategoryRules {
+    /// Number of categories kept per domain, the first ones returned; all of them when None
+    pub max_propositions: Option<usize>,
+    /// Category replacing a label outside of the taxonomy, the domain is sent again when None
+    pub fallback: Option<&'static str>,
+}
+
+impl CategoryRules {
+    /// Rules of a run: `max_domain_propositions` categories per domain, `invalid_category_fallback` for unknown labels
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            max_propositions: Some(config.max_domain_propositions.max(1)),
+            fallback: config.invalid_category_fallback.as_deref().and_then(check_category_validity),
+        }
+    }
+}
+
+/// Categories of an LLM output: the domains with their categories, the domains in error, the corrections applied,
+/// the labels outside of the taxonomy, replaced by the fallback category or sending their domain again, and the
+/// fallback that found the JSON
+pub type CategorizationOutput = (
+    HashMap<String, Vec<&'static str>>,
+    HashMap<String, DomainError>,
+    Vec<CategoryCorrection>,
+    Vec<String>,
+    Option<OutputFallback>,
+);
+
 /// Parses the categorization output from the LLM and maps domains to their categories.
 /// # Arguments
 /// * `domains` - A slice of domain strings that were categorized.
-/// * `content` - The raw JSON string output from the LLM containing categorization results.
+/// * `content` - The raw output from the LLM containing categorization results, see `parse_llm_output`.
+/// * `rules` - Number of categories kept per domain and fallback category of the run.
+/// Near-miss labels are validated against the taxonomy with `match_category`: normalized and
+/// fuzzy matches are auto-corrected, any other label is replaced by the fallback category of `rules`,
+/// or marks the domain as needing a retry without one. Categories beyond `rules.max_propositions` are dropped.
 /// # Returns
-/// A Result containing a HashMap mapping each domain to a vector of category &str on success,
-/// or an error message on failure.
+/// A Result containing the [`CategorizationOutput`] on success, or an error message on failure.
 /// 
 pub fn parse_categorization_output(
     domains: Vec<String>,
     content: &str,
-) -> Result<
-        (HashMap<String, Vec<&'static str>>, HashMap<String, DomainError>),
-        Box<dyn std::error::Error>> 
-        {
-    // Avoid unnecessary trim/allocation
-    let content = content.trim_start_matches(|c: char| c.is_whitespace())
-                         .trim_end_matches(|c: char| c.is_whitespace());
-
-    
-    let json: Value = serde_json::from_str(content)?;
-    let obj = json.as_object().ok_or("Expected top-level JSON object")?;
+    rules: &CategoryRules,
+) -> Result<CategorizationOutput, Box<dyn std::error::Error>> {
+    let parsed = parse_llm_output(content, &domains)?;
+    let obj = &parsed.object;
 
     // Pre allocate the result map for better performance
     let mut result: HashMap<String, Vec<&'static str>> = HashMap::with_capacity(domains.len());
     let mut errors: HashMap<String, DomainError> = HashMap::new();
+    let mut corrections: Vec<CategoryCorrection> = Vec::new();
+    let mut rejected: Vec<String> = Vec::new();
 
     for domain in domains {
         let value = match obj.get(&domain)
@@ -72,15 +110,36 @@ pub fn parse_categorization_output(
 
         let mut categories_ref: Vec<&'static str> = Vec::with_capacity(categories.len());
 
+        let mut domain_corrections: Vec<CategoryCorrection> = Vec::new();
+
         let mut invalid_found = false;
 
         for category in &categories {
-            if let Some(valid_category) = check_category_validity(category) {
-                categories_ref.push(valid_category);
-            } else {
-                errors.insert(domain.clone(), DomainError::InvalidStrings);
-                invalid_found = true;
-                break;
+            match match_category(category) {
+                CategoryMatch::Exact(valid_category) => categories_ref.push(valid_category),
+                CategoryMatch::Normalized(valid_category) => {
+                    categories_ref.push(valid_category);
+                    domain_corrections.push(CategoryCorrection { original: category.clone(), corrected: valid_category });
+                }
+                CategoryMatch::Fuzzy(valid_category) => {
+                    eprintln!("Warning: category '{}' returned for {} auto-corrected to '{}'", category, domain, valid_category);
+                    categories_ref.push(valid_category);
+                    domain_corrections.push(CategoryCorrection { original: category.clone(), corrected: valid_category });
+                }
+                CategoryMatch::Invalid => {
+                    rejected.push(category.clone());
+                    match rules.fallback {
+                        Some(fallback) => {
+                            eprintln!("Warning: category '{}' returned for {} is not in the taxonomy, replaced by '{}'", category, domain, fallback);
+                            categories_ref.push(fallback);
+                        }
+                        None => {
+                            errors.insert(domain.clone(), DomainError::InvalidStrings);
+                            invalid_found = true;
+                            break;
+                        }
+                    }
+                }
             }
         }
 
@@ -88,8 +147,79 @@ pub fn parse_categorization_output(
             continue;
         }
 
-        result.insert(domain.to_string(), categories_ref);
+        // A fallback or a corrected label may repeat a category already returned
+        let mut unique: Vec<&'static str> = Vec::with_capacity(categories_ref.len());
+        for category in categories_ref {
+            if !unique.contains(&category) {
+                unique.push(category);
+            }
+        }
+        if let Some(max_propositions) = rules.max_propositions {
+            unique.truncate(max_propositions);
+        }
+        corrections.extend(domain_corrections);
+        result.insert(domain.to_string(), unique);
+    }
+
+    Ok((result, errors, corrections, rejected, parsed.fallback))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_categorization_output_corrections() {
+        let domains = vec!["a.com".to_string(), "b.com".to_string(), "c.com".to_string(), "d.com".to_string()];
+        let content = r#"{
+            "a.com": ["Réseaux sociaux", "Email"],
+            "b.com": ["Réseaux Sociaux", "Immobillier"],
+            "c.com": ["Emali"],
+            "d.com": ["Email", "Cuisine"]
+        }"#;
+
+        let (valid, errors, corrections, rejected, fallback) = parse_categorization_output(domains, content, &CategoryRules::default()).unwrap();
+        assert_eq!(rejected, vec!["Emali".to_string(), "Cuisine".to_string()]);
+        assert_eq!(fallback, None);
+
+        assert_eq!(valid["a.com"], vec!["Réseaux sociaux", "Email"]);
+        assert_eq!(valid["b.com"], vec!["Réseaux sociaux", "Immobilier"]);
+        assert_eq!(corrections, vec![
+            CategoryCorrection { original: "Réseaux Sociaux".to_string(), corrected: "Réseaux sociaux" },
+            CategoryCorrection { original: "Immobillier".to_string(), corrected: "Immobilier" },
+        ]);
+
+        // Ambiguous and unknown labels are left for a retry
+        assert!(matches!(errors.get("c.com"), Some(DomainError::InvalidStrings)));
+        assert!(matches!(errors.get("d.com"), Some(DomainError::InvalidStrings)));
+        assert_eq!(valid.len(), 2);
+    }
+
+    #[test]
+    fn test_too_many_categories_are_cut() {
+        let domains = vec!["a.com".to_string()];
+        let content = r#"{"a.com": ["Email", "Réseaux sociaux", "Immobilier", "Santé", "Autres"]}"#;
+        let rules = CategoryRules { max_propositions: Some(3), fallback: Some("Autres") };
+
+        let (valid, errors, _, rejected, _) = parse_categorization_output(domains, content, &rules).unwrap();
+        assert!(errors.is_empty());
+        assert!(rejected.is_empty());
+        assert_eq!(valid["a.com"], vec!["Email", "Réseaux sociaux", "Immobilier"]);
     }
 
-    Ok((result, errors))
-}
\ No newline at end of file
+    #[test]
+    fn test_misspelled_category_gets_the_fallback() {
+        let domains = vec!["a.com".to_string(), "b.com".to_string()];
+        // "Cuisine" is not in the taxonomy, "Emali" is within 2 edits of several categories
+        let content = r#"{"a.com": ["Cuisine", "Email"], "b.com": ["Emali", "Autres"]}"#;
+        let config = Config { max_domain_propositions: 2, ..Config::default() };
+
+        let (valid, errors, corrections, rejected, _) = parse_categorization_output(domains, content, &CategoryRules::from_config(&config)).unwrap();
+        assert!(errors.is_empty());
+        assert!(corrections.is_empty());
+        assert_eq!(rejected, vec!["Cuisine".to_string(), "Emali".to_string()]);
+        assert_eq!(valid["a.com"], vec!["Autres", "Email"]);
+        // The fallback is not repeated when the LLM returned it too
+        assert_eq!(valid["b.com"], vec!["Autres"]);
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use crate::core::output::{parse_llm_output, OutputFallback};
 
 #[derive(Debug)]
 pub enum DomainError {
@@ -10,55 +11,23 @@ pub enum DomainError {
 }
 
 
-/// Strips JSON code fences (```json ... ```) from the input string.
-/// # Arguments
-/// * `input` - The input string potentially containing JSON code fences.
-/// # Returns
-/// A &str slice with the code fences removed.
-/// 
-fn strip_json_fence(input: &str) -> &str {
-    let trimmed = input.trim();
-
-    // Fast path: no code fence
-    if !trimmed.starts_with("```") {
-        return trimmed;
-    }
-
-    // Remove opening fence line (``` or ```json)
-    let without_opening = match trimmed.find('\n') {
-        Some(idx) => &trimmed[idx + 1..],
-        None => return trimmed, // malformed fence, return as-is
-    };
-
-    // Remove closing fence if present
-    let without_closing = without_opening
-        .trim_end()
-        .strip_suffix("```")
-        .unwrap_or(without_opening);
-
-    without_closing.trim()
-}
-
 /// Parses the description output from the LLM and maps domains to their descriptions.
 /// # Arguments
 /// * `domains` - A slice of domain strings that were described.
-/// * `content` - The raw JSON string output from the LLM containing description results.
+/// * `content` - The raw output from the LLM containing description results, see `parse_llm_output`.
 /// # Returns
-/// A Result containing a HashMap mapping each domain to a HashMap of language keys and descriptions on success,
-/// or an error message on failure.
+/// A Result containing a HashMap mapping each domain to a HashMap of language keys and descriptions, the domains
+/// in error and the fallback that found the JSON on success, or an error message on failure.
 /// 
 pub fn parse_description_output<'a>(
     domains: Vec<String>,
     content: &str,
 ) -> Result<
-        (HashMap<String, HashMap<&'static str, String>>, HashMap<String, DomainError>),
+        (HashMap<String, HashMap<&'static str, String>>, HashMap<String, DomainError>, Option<OutputFallback>),
         Box<dyn std::error::Error>> 
         {
-    let content = content.trim();
-    let cleaned = strip_json_fence(content);
-
-    let json: serde_json::Value = serde_json::from_str(cleaned)?;
-    let obj = json.as_object().ok_or("Expected top-level JSON object")?;
+    let parsed = parse_llm_output(content, &domains)?;
+    let obj = &parsed.object;
 
     let mut result = HashMap::with_capacity(domains.len());
     let mut errors = HashMap::new();
@@ -104,5 +73,5 @@ pub fn parse_description_output<'a>(
         }
     }
 
-    Ok((result, errors))
+    Ok((result, errors, parsed.fallback))
 }
@@ -0,0 +1,288 @@
+use serde::Serialize;
+use config::{Config, ProviderKind};
+use crate::cache::DomainCache;
+use crate::core::LLMCommand;
+use crate::core::prompt::{generate_cached_prompt, generate_request_prompt};
+use crate::core::rate_limit::CHARS_PER_TOKEN;
+use crate::providers::gemini::billing::{CacheCostResult, CostResult};
+use crate::providers::gemini::caching::CachedUsageMetadata;
+use crate::providers::gemini::network::UsageMetadata;
+use crate::providers::openai::openai_cost_eur;
+
+/// Characters of a category in an answer, quotes and separator included
+const CATEGORY_CHARS: usize = 20;
+/// Characters of the French and English descriptions of a domain in an answer, one or two sentences each
+const DESCRIPTION_CHARS: usize = 300;
+/// Characters around each domain of an answer: quotes, colon, brackets and indentation
+const ANSWER_OVERHEAD_CHARS: usize = 12;
+
+/// Estimated tokens and cost of the requests of a run at one chunk size.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChunkSizeEstimate {
+    pub chunk_size: usize,
+    /// Requests of the run, one per chunk, retries excluded
+    pub requests: usize,
+    /// Tokens of the prompts sent, the cached content excluded
+    pub prompt_tokens: u64,
+    /// Tokens read from the cached content by the requests, not billed
+    pub cached_tokens: u64,
+    /// Tokens of the expected answers
+    pub output_tokens: u64,
+    /// Tokens the model may think for, at most the thinking budget of every request
+    pub thinking_tokens: u64,
+    /// Cost of the prompt tokens, in euros
+    pub prompt_cost_eur: f64,
+    /// Cost of the output and thinking tokens, in euros
+    pub output_cost_eur: f64,
+    /// Cost of the run at this chunk size, the cached content included, in euros
+    pub total_cost_eur: f64,
+}
+
+/// Estimate of a run without any request to the provider, see [`estimate_run`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunEstimate {
+    pub command: &'static str,
+    pub model: String,
+    /// Domains sent to the LLM, the ones of the local cache excluded
+    pub domains: usize,
+    /// Domains found in the local cache, not sent
+    pub local_cache_hits: usize,
+    /// Tokens of the cached content of the rules, None without explicit caching
+    pub cached_content_tokens: Option<u64>,
+    /// Cost of creating the cached content once, in euros
+    pub cached_content_cost_eur: f64,
+    pub chunk_sizes: Vec<ChunkSizeEstimate>,
+}
+
+impl RunEstimate {
+    /// Human readable estimate, one line per chunk size.
+    pub fn report(&self) -> String {
+        let mut report = format!(
+            "Dry run of {} on {} domains with {}, no request sent\n",
+            self.command, self.domains, self.model
+        );
+        if self.local_cache_hits > 0 {
+            report.push_str(&format!("Local cache: {} domains found, not sent\n", self.local_cache_hits));
+        }
+        if let Some(tokens) = self.cached_content_tokens {
+            report.push_str(&format!("Cached content: {} tokens, {:.4}€ to create\n", tokens, self.cached_content_cost_eur));
+        }
+        report.push_str(&format!(
+            "{:>10} | {:>8} | {:>13} | {:>13} | {:>15} | {:>11} | {:>11} | {:>10}\n",
+            "Chunk size", "Requests", "Prompt tokens", "Output tokens", "Thinking tokens", "Prompt cost", "Output cost", "Total cost"
+        ));
+        for estimate in &self.chunk_sizes {
+            report.push_str(&format!(
+                "{:>10} | {:>8} | {:>13} | {:>13} | {:>15} | {:>10.4}€ | {:>10.4}€ | {:>9.4}€\n",
+                estimate.chunk_size,
+                estimate.requests,
+                estimate.prompt_tokens,
+                estimate.output_tokens,
+                estimate.thinking_tokens,
+                estimate.prompt_cost_eur,
+                estimate.output_cost_eur,
+                estimate.total_cost_eur
+            ));
+        }
+        report.push_str(&format!(
+            "Tokens estimated at {} characters per token, thinking at most its budget, retries excluded",
+            CHARS_PER_TOKEN
+        ));
+        report
+    }
+}
+
+/// Tokens of `text`, with the estimate the rate limiter uses for the requests
+fn text_tokens(text: &str) -> u64 {
+    (text.len() / CHARS_PER_TOKEN) as u64
+}
+
+/// Tokens of the expected answer of `command` on `domains`
+fn answer_tokens(domains: &[String], config: &Config, command: &LLMCommand) -> u64 {
+    let answer_chars = match command {
+        LLMCommand::CategorizeDomains => config.max_domain_propositions * CATEGORY_CHARS,
+        LLMCommand::DescribeDomains => DESCRIPTION_CHARS,
+    };
+    let chars: usize = domains.iter().map(|domain| domain.len() + answer_chars + ANSWER_OVERHEAD_CHARS).sum();
+    (chars / CHARS_PER_TOKEN) as u64
+}
+
+/// Usage of a request as Gemini reports it, priced by its billing
+fn usage(prompt_tokens: u64, cached_tokens: u64, output_tokens: u64, thinking_tokens: u64) -> UsageMetadata {
+    UsageMetadata {
+        // Gemini counts the cached content in the prompt, then leaves it out of the billable tokens
+        prompt_token_count: (prompt_tokens + cached_tokens) as i64,
+        candidates_token_count: output_tokens as i64,
+        total_token_count: (prompt_tokens + cached_tokens + output_tokens + thinking_tokens) as i64,
+        traffic_type: None,
+        prompt_tokens_details: None,
+        candidates_tokens_details: None,
+        thoughts_token_count: Some(thinking_tokens as i64),
+        cached_content_token_count: Some(cached_tokens as i64),
+    }
+}
+
+/// Prompt and output costs of `tokens` with the pricing of the provider of `config`, in euros
+fn costs(config: &Config, prompt_tokens: u64, cached_tokens: u64, output_tokens: u64, thinking_tokens: u64) -> (f64, f64) {
+    match config.provider {
+        ProviderKind::Gemini => (
+            CostResult::new(&usage(prompt_tokens, cached_tokens, 0, 0)).compute_cost().eur,
+            CostResult::new(&usage(0, 0, output_tokens, thinking_tokens)).compute_cost().eur,
+        ),
+        ProviderKind::OpenAi => (
+            openai_cost_eur(prompt_tokens, config.openai_usd_per_1k_tokens),
+            openai_cost_eur(output_tokens + thinking_tokens, config.openai_usd_per_1k_tokens),
+        ),
+    }
+}
+
+/// Estimates the tokens and the cost of running `command` on `domains`, without sending any request nor creating any
+/// cached content. The prompts are built chunk by chunk as the run builds them, with the rules left out when the run
+/// uses a cached content, and the domains of the local cache are left out as the run leaves them out.
+///
+/// # Arguments
+///
+/// * `domains` - Domains of the run
+/// * `config` - Configuration of the run
+/// * `command` - Command of the run
+/// * `chunk_sizes` - Chunk sizes to compare, each at least 1
+///
+/// # Returns
+/// * The estimate, one entry per chunk size in the given order
+pub fn estimate_run(domains: &[String], config: &Config, command: &LLMCommand, chunk_sizes: &[usize]) -> RunEstimate {
+    let cache = match command {
+        LLMCommand::CategorizeDomains => DomainCache::from_config(config),
+        LLMCommand::DescribeDomains => None,
+    };
+    let hits = cache
+        .and_then(|cache| cache.lookup(domains).ok())
+        .unwrap_or_default();
+    let domains: Vec<String> = domains.iter().filter(|domain| !hits.contains_key(*domain)).cloned().collect();
+
+    let cached = config.provider == ProviderKind::Gemini && config.use_gemini_explicit_caching;
+    let cached_content_tokens = cached.then(|| text_tokens(&generate_cached_prompt(config.max_domain_propositions)));
+    let cached_content_cost_eur = cached_content_tokens.map_or(0.0, |tokens| {
+        CacheCostResult::new(CachedUsageMetadata { text_count: 1, total_token_count: tokens as i32 }).compute_cost().eur
+    });
+    // The OpenAI-compatible provider sends no thinking configuration
+    let thinking_budget = match config.provider {
+        ProviderKind::Gemini => config.thinking_budget.unwrap_or(0).max(0) as u64,
+        ProviderKind::OpenAi => 0,
+    };
+
+    let chunk_sizes = chunk_sizes
+        .iter()
+        .map(|&chunk_size| {
+            let mut estimate = ChunkSizeEstimate {
+                chunk_size,
+                requests: 0,
+                prompt_tokens: 0,
+                cached_tokens: 0,
+                output_tokens: 0,
+                thinking_tokens: 0,
+                prompt_cost_eur: 0.0,
+                output_cost_eur: 0.0,
+                total_cost_eur: 0.0,
+            };
+            for chunk in domains.chunks(chunk_size) {
+                let prompt = generate_request_prompt(&chunk.to_vec(), config, command, cached);
+                estimate.requests += 1;
+                estimate.prompt_tokens += text_tokens(&prompt);
+                estimate.cached_tokens += cached_content_tokens.unwrap_or(0);
+                estimate.output_tokens += answer_tokens(chunk, config, command);
+                estimate.thinking_tokens += thinking_budget;
+            }
+            (estimate.prompt_cost_eur, estimate.output_cost_eur) = costs(
+                config,
+                estimate.prompt_tokens,
+                estimate.cached_tokens,
+                estimate.output_tokens,
+                estimate.thinking_tokens,
+            );
+            estimate.total_cost_eur = estimate.prompt_cost_eur + estimate.output_cost_eur + cached_content_cost_eur;
+            estimate
+        })
+        .collect();
+
+    RunEstimate {
+        command: command.name(),
+        model: config.model[0].clone(),
+        domains: domains.len(),
+        local_cache_hits: hits.len(),
+        cached_content_tokens,
+        cached_content_cost_eur,
+        chunk_sizes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn domains(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("domain-{}.example.com", i)).collect()
+    }
+
+    #[test]
+    fn test_estimate_builds_the_prompts_of_every_chunk() {
+        let config = Config { model: vec!["gemini-2.5-flash".to_string()], thinking_budget: Some(1024), ..Config::default() };
+        let domains = domains(250);
+
+        let estimate = estimate_run(&domains, &config, &LLMCommand::CategorizeDomains, &[50, 200]);
+        assert_eq!(estimate.domains, 250);
+        assert_eq!(estimate.cached_content_tokens, None);
+        let (small, large) = (&estimate.chunk_sizes[0], &estimate.chunk_sizes[1]);
+        assert_eq!((small.requests, large.requests), (5, 2));
+        // The rules are sent with every chunk, smaller chunks send them more often
+        assert!(small.prompt_tokens > large.prompt_tokens);
+        let prompt_tokens: u64 = domains
+            .chunks(50)
+            .map(|chunk| text_tokens(&generate_request_prompt(&chunk.to_vec(), &config, &LLMCommand::CategorizeDomains, false)))
+            .sum();
+        assert_eq!(small.prompt_tokens, prompt_tokens);
+        // The answers do not depend on the chunking
+        assert_eq!(small.output_tokens, large.output_tokens);
+        assert_eq!(small.thinking_tokens, 5 * 1024);
+        assert!((small.total_cost_eur - small.prompt_cost_eur - small.output_cost_eur).abs() < 1e-12);
+        assert!(small.total_cost_eur > large.total_cost_eur);
+    }
+
+    #[test]
+    fn test_cached_content_leaves_the_rules_out_of_the_prompts() {
+        let config = Config { model: vec!["gemini-2.5-flash".to_string()], ..Config::default() };
+        let cached_config = Config { use_gemini_explicit_caching: true, ..config.clone() };
+        let domains = domains(100);
+
+        let full = estimate_run(&domains, &config, &LLMCommand::CategorizeDomains, &[50]);
+        let cached = estimate_run(&domains, &cached_config, &LLMCommand::CategorizeDomains, &[50]);
+        let tokens = cached.cached_content_tokens.unwrap();
+        assert!(tokens > 0);
+        assert!(cached.cached_content_cost_eur > 0.0);
+        assert_eq!(cached.chunk_sizes[0].cached_tokens, 2 * tokens);
+        assert!(cached.chunk_sizes[0].prompt_tokens < full.chunk_sizes[0].prompt_tokens);
+        // The cached tokens are not billed, the creation of the cached content is
+        assert!(cached.chunk_sizes[0].prompt_cost_eur < full.chunk_sizes[0].prompt_cost_eur);
+        let chunk = &cached.chunk_sizes[0];
+        assert!((chunk.total_cost_eur - chunk.prompt_cost_eur - chunk.output_cost_eur - cached.cached_content_cost_eur).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_openai_estimate_uses_the_configured_price() {
+        let config = Config {
+            model: vec!["gpt-4o-mini".to_string()],
+            provider: ProviderKind::OpenAi,
+            openai_usd_per_1k_tokens: 0.5,
+            use_gemini_explicit_caching: true,
+            ..Config::default()
+        };
+
+        let estimate = estimate_run(&domains(10), &config, &LLMCommand::DescribeDomains, &[10]);
+        let chunk = &estimate.chunk_sizes[0];
+        // No cached content nor thinking with the OpenAI-compatible provider
+        assert_eq!(estimate.cached_content_tokens, None);
+        assert_eq!(chunk.thinking_tokens, 0);
+        assert!((chunk.prompt_cost_eur - openai_cost_eur(chunk.prompt_tokens, 0.5)).abs() < 1e-12);
+        assert!((chunk.output_cost_eur - openai_cost_eur(chunk.output_tokens, 0.5)).abs() < 1e-12);
+        assert!(estimate.report().contains("Dry run of describe on 10 domains with gpt-4o-mini"));
+    }
+}
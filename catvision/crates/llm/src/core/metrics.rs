@@ -0,0 +1,183 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// Timing of a single chunk sent to the LLM, retries included.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkMetrics {
+    /// Worker that processed the chunk.
+    pub thread_id: usize,
+    /// Domains of the chunk, as first sent to the LLM.
+    pub domains: Vec<String>,
+    /// Wall clock time spent on the chunk, across all attempts.
+    pub latency: Duration,
+    /// Number of requests sent for the chunk.
+    pub attempts: usize,
+}
+
+/// Per-domain share of a chunk latency.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessingHint {
+    pub domain: String,
+    /// Latency of the whole chunk the domain belonged to.
+    pub chunk_latency: Duration,
+    /// Number of domains in that chunk.
+    pub chunk_size: usize,
+    /// Whether the chunk needed more than one attempt.
+    pub retried: bool,
+}
+
+impl ProcessingHint {
+    /// Chunk latency divided evenly between the domains of the chunk.
+    pub fn domain_latency(&self) -> Duration {
+        self.chunk_latency / self.chunk_size.max(1) as u32
+    }
+}
+
+impl ChunkMetrics {
+    pub fn retried(&self) -> bool {
+        self.attempts > 1
+    }
+
+    /// Expands the chunk into one processing hint per domain.
+    pub fn processing_hints(&self) -> Vec<ProcessingHint> {
+        self.domains
+            .iter()
+            .map(|domain| ProcessingHint {
+                domain: domain.clone(),
+                chunk_latency: self.latency,
+                chunk_size: self.domains.len(),
+                retried: self.retried(),
+            })
+            .collect()
+    }
+}
+
+/// Expands every chunk into per-domain processing hints, keeping chunk order.
+pub fn expand_processing_hints(metrics: &[ChunkMetrics]) -> Vec<ProcessingHint> {
+    metrics.iter().flat_map(|m| m.processing_hints()).collect()
+}
+
+/// Returns the `k` slowest chunks, slowest first.
+pub fn slowest_chunks(metrics: &[ChunkMetrics], k: usize) -> Vec<&ChunkMetrics> {
+    let mut sorted: Vec<&ChunkMetrics> = metrics.iter().collect();
+    sorted.sort_by_key(|chunk| std::cmp::Reverse(chunk.latency));
+    sorted.truncate(k);
+    sorted
+}
+
+/// Writes the processing hints to a `;`-delimited CSV file.
+/// # Arguments
+/// * `hints` - Per-domain processing hints
+/// * `path` - Output file path
+/// # Returns
+/// * `Result<(), std::io::Error>` - Ok(()) if the file was written
+pub fn write_processing_hints(hints: &[ProcessingHint], path: &Path) -> Result<(), std::io::Error> {
+    let mut wtr = BufWriter::new(File::create(path)?);
+
+    writeln!(wtr, "domain;domain_latency_ms;chunk_latency_ms;chunk_size;retried")?;
+    for hint in hints {
+        writeln!(
+            wtr,
+            "{};{};{};{};{}",
+            hint.domain,
+            hint.domain_latency().as_millis(),
+            hint.chunk_latency.as_millis(),
+            hint.chunk_size,
+            hint.retried
+        )?;
+    }
+    wtr.flush()
+}
+
+/// Formats the `k` slowest chunks with their domains for the run summary.
+pub fn slowest_chunks_report(metrics: &[ChunkMetrics], k: usize) -> String {
+    let mut report = String::new();
+    for (rank, chunk) in slowest_chunks(metrics, k).iter().enumerate() {
+        report.push_str(&format!(
+            "{}. {} ms ({} domains, {} attempts): {}\n",
+            rank + 1,
+            chunk.latency.as_millis(),
+            chunk.domains.len(),
+            chunk.attempts,
+            chunk.domains.join(", ")
+        ));
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(thread_id: usize, domains: &[&str], latency_ms: u64, attempts: usize) -> ChunkMetrics {
+        ChunkMetrics {
+            thread_id,
+            domains: domains.iter().map(|d| d.to_string()).collect(),
+            latency: Duration::from_millis(latency_ms),
+            attempts,
+        }
+    }
+
+    fn mocked_metrics() -> Vec<ChunkMetrics> {
+        vec![
+            chunk(0, &["a.com", "b.com"], 1_200, 1),
+            chunk(1, &["slow.com", "c.com", "d.com"], 9_000, 2),
+            chunk(2, &["e.com"], 300, 1),
+            chunk(3, &["f.com", "g.com"], 4_000, 1),
+        ]
+    }
+
+    #[test]
+    fn test_slowest_chunks_ordering() {
+        let metrics = mocked_metrics();
+
+        let top = slowest_chunks(&metrics, 2);
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].thread_id, 1);
+        assert_eq!(top[1].thread_id, 3);
+
+        // k larger than the number of chunks returns them all, slowest first
+        let all: Vec<usize> = slowest_chunks(&metrics, 10).iter().map(|m| m.thread_id).collect();
+        assert_eq!(all, vec![1, 3, 0, 2]);
+
+        let report = slowest_chunks_report(&metrics, 2);
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "1. 9000 ms (3 domains, 2 attempts): slow.com, c.com, d.com");
+        assert_eq!(lines[1], "2. 4000 ms (2 domains, 1 attempts): f.com, g.com");
+    }
+
+    #[test]
+    fn test_expand_processing_hints() {
+        let hints = expand_processing_hints(&mocked_metrics());
+        assert_eq!(hints.len(), 8);
+
+        let slow = hints.iter().find(|h| h.domain == "slow.com").unwrap();
+        assert_eq!(slow.chunk_size, 3);
+        assert_eq!(slow.chunk_latency, Duration::from_millis(9_000));
+        assert_eq!(slow.domain_latency(), Duration::from_millis(3_000));
+        assert!(slow.retried);
+
+        let single = hints.iter().find(|h| h.domain == "e.com").unwrap();
+        assert_eq!(single.domain_latency(), Duration::from_millis(300));
+        assert!(!single.retried);
+    }
+
+    #[test]
+    fn test_write_processing_hints() {
+        let path = std::env::temp_dir().join(format!("catvision-hints-{}.csv", std::process::id()));
+        let hints = expand_processing_hints(&mocked_metrics()[..2]);
+        write_processing_hints(&hints, &path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0], "domain;domain_latency_ms;chunk_latency_ms;chunk_size;retried");
+        assert_eq!(lines[1], "a.com;600;1200;2;false");
+        assert_eq!(lines[3], "slow.com;3000;9000;3;true");
+
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -1,76 +1,132 @@
+use std::collections::HashMap;
 use async_scoped::TokioScope;
 use tokio::runtime::{Runtime};
-use std::sync::atomic::Ordering;
-use crate::core::tools::write_domain_in_garbage_file;
-use crate::providers::gemini::generating::{async_gemini_fetch_chat_completion, GeminiResult, GeminiConfig, async_gemini_handle_cached_content};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::providers::{LLMProvider, ProviderCall};
+use crate::providers::http::HttpClient;
+use crate::providers::gemini::generating::GeminiProvider;
 use crate::providers::gemini::network::GeminiNetworkClient;
-use config::Config;
+use crate::providers::openai::OpenAiProvider;
+use crate::core::prompt::generate_request_prompt;
+use crate::core::result::LLMRunResult;
+use crate::core::categorization::CategoryRules;
+use crate::error::LlmError;
+use crate::core::metrics::ChunkMetrics;
+use crate::core::anonymize::Anonymizer;
+use crate::core::progress::ProgressReporter;
+use crate::core::budget::{BudgetStatus, BudgetStop, BudgetTracker, BUDGET_CHECKPOINT_FILE, write_checkpoint};
+use crate::cache::DomainCache;
+use crate::core::rate_limit::{RateLimitedClient, RateLimiter};
+use crate::core::responses::ResponseStore;
+use crate::core::retry::{FailedDomain, RetryPass, GARBAGE_FILE, retry_config, write_garbage_file};
+use crate::telemetry;
+use crate::metrics as run_metrics;
+use config::{Config, ProviderKind};
+use utils::error::CatVisionError;
+use tracing::Instrument;
 pub mod categorization;
 pub mod prompt;
 pub mod description;
-pub mod tools;
+pub mod output;
+pub mod metrics;
+pub mod anonymize;
+pub mod budget;
+pub mod capabilities;
+pub mod estimate;
+pub mod progress;
+pub mod rate_limit;
+pub mod responses;
+pub mod retry;
+pub mod result;
 
 pub enum LLMCommand {
     CategorizeDomains,
     DescribeDomains,
 }
 
-enum LLMResult {
-    Gemini(GeminiResult),
+impl LLMCommand {
+    /// Name of the command, part of the key of its stored responses
+    pub fn name(&self) -> &'static str {
+        match self {
+            LLMCommand::CategorizeDomains => "categorize",
+            LLMCommand::DescribeDomains => "describe",
+        }
+    }
 }
 
+/// Number of retries of a chunk before its remaining domains are given up on
+const MAX_CHUNK_RETRIES: usize = 3;
+
+/// Number of rate limited requests of a chunk retried after a backoff, on top of the chunk retries
+const MAX_RATE_LIMITED_RETRIES: usize = 10;
 
-type DynError = Box<dyn std::error::Error + Send + Sync>;
+/// Exit status of a run aborted by a second Ctrl-C, the status of a cancelled run in the CLI taxonomy
+pub const CANCELLED_EXIT_CODE: i32 = 7;
 
-async fn async_llm_process_command(
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn async_llm_process_command<P: LLMProvider>(
+    provider: &P,
     domains: &Vec<String>,
     config: &Config,
     cache_name: &Option<String>,
     id: usize,
-    client: &reqwest::Client,
-    command: &LLMCommand
-) -> Result<LLMResult, DynError> {
+    client: &dyn HttpClient,
+    limiter: &RateLimiter,
+    command: &LLMCommand,
+    store: &ResponseStore,
+) -> Result<LLMRunResult, LlmError> {
 
-    let mut gemini_result = GeminiResult::new();
+    // Every request of the chunk waits for the limiter shared by the run
+    let client = RateLimitedClient::new(client, limiter);
+    let mut chunk_result = LLMRunResult { category_rules: CategoryRules::from_config(config), ..LLMRunResult::new() };
     let mut retries_chunk = 0;
+    let mut rate_limited_retries = 0;
+    let chunk_domains = domains.clone();
     let mut domains = domains.clone();
-    
-    let gemini_config = GeminiConfig {
-        model: config.model[0].clone(),
-        prompt: String::new(), // Prompt will be generated in the fetch function
-        cache_name: cache_name.clone(),
-        use_url_context: config.use_gemini_url_context,
-        use_google_search: config.use_gemini_google_search,
-        thinking_budget: config.thinking_budget,
-        use_gemini_explicit_caching: config.use_gemini_explicit_caching,
-        use_gemini_custom_cache_duration: config.use_gemini_custom_cache_duration.clone(),
-        max_domain_propositions: config.max_domain_propositions,
-    };
+    let chunk_start = std::time::Instant::now();
+    let mut attempts = 0;
+    // The cached content holds the rules for the first model only, the fallback models get them in the prompt
+    let mut model_index = 0;
+    let no_cache = None;
 
     loop {
-        if retries_chunk == 3 {
-            eprintln!("Thread {} Failed to get LLM response after 3 attempts for chunk starting with domain: {}", id, domains[0]);
-            gemini_result.failed.fetch_add(domains.len(), Ordering::Relaxed);
-            write_domain_in_garbage_file(&domains, id);
+        if retries_chunk == MAX_CHUNK_RETRIES && model_index + 1 < config.model.len() {
+            model_index += 1;
+            eprintln!("Thread {} Model {} failed {} times, falling back to {} for {} domains", id, config.model[model_index - 1], MAX_CHUNK_RETRIES, config.model[model_index], domains.len());
+            retries_chunk = 0;
+        }
+
+        if retries_chunk == MAX_CHUNK_RETRIES {
+            eprintln!("Thread {} Failed to get LLM response after {} attempts for chunk starting with domain: {}", id, MAX_CHUNK_RETRIES, domains[0]);
+            chunk_result.failed.fetch_add(domains.len(), Ordering::Relaxed);
+            chunk_result.retry_queue.extend(FailedDomain::all(&domains, &LlmError::MaxRetries { attempts: retries_chunk }.to_string()));
+            telemetry::record_failure(&LlmError::MaxRetries { attempts: retries_chunk });
+            run_metrics::record_failure(&LlmError::MaxRetries { attempts: retries_chunk });
 
             // If no domains were processed successfully, return an error
-            if gemini_result.processed.load(Ordering::Relaxed) == 0 {
-                return Err("Max retries reached for LLM request".into());
+            if chunk_result.processed.load(Ordering::Relaxed) == 0 {
+                return Err(LlmError::MaxRetries { attempts: retries_chunk });
             }
 
             break;
         }
 
-        match async_gemini_fetch_chat_completion(
-            domains.clone(),
-            &gemini_config,
-            cache_name,
-  &mut gemini_result,
-            command,
-            client,
-        )
-            .await 
-            {
+        attempts += 1;
+        let model = &config.model[model_index];
+        let cache_name = if model_index == 0 { cache_name } else { &no_cache };
+        let prompt = generate_request_prompt(&domains, config, command, cache_name.is_some());
+        let before = chunk_result.usage();
+        let call = ProviderCall { client: &client, model, cache_name, store, result: &mut chunk_result };
+        let outcome = match command {
+            LLMCommand::CategorizeDomains => provider.classify(domains.clone(), prompt, config, call).await,
+            LLMCommand::DescribeDomains => provider.describe(domains.clone(), prompt, config, call).await,
+        };
+        chunk_result.attribute_to_model(model, before);
+        for domain in domains.iter().filter(|domain| chunk_result.categories.contains_key(*domain)) {
+            chunk_result.answering_model.insert(domain.clone(), model.clone());
+        }
+        match outcome {
             Ok(remaining) => {
                 if remaining.len() > 0 {
                     eprintln!("Thread {} Some domains were not processed, retrying: {:?}", id, remaining);
@@ -83,54 +139,292 @@ async fn async_llm_process_command(
                     break;
                 }
             },
-            Err(e) => {
+            Err(e @ LlmError::RateLimited(_)) if rate_limited_retries < MAX_RATE_LIMITED_RETRIES => {
+                // The limiter backs off before the next request, the chunk retries are kept for the LLM errors
+                eprintln!("Thread {} {}, sending the request again", id, e);
+                telemetry::record_retry(attempts, &e);
+                rate_limited_retries += 1;
+                chunk_result.retried.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+            Err(e) if e.is_retryable() => {
                 eprintln!("Thread {} Error during LLM request (attempt {}): {}", id, retries_chunk + 1, e);
+                telemetry::record_retry(attempts, &e);
                 retries_chunk += 1;
-                gemini_result.retried.fetch_add(1, Ordering::Relaxed);
+                chunk_result.retried.fetch_add(1, Ordering::Relaxed);
                 continue;
             }
+            Err(e) => {
+                // Retrying would fail the same way, give up on this chunk
+                eprintln!("Thread {} Fatal error during LLM request for chunk starting with domain {}: {}", id, domains[0], e);
+                telemetry::record_failure(&e);
+                run_metrics::record_failure(&e);
+                return Err(e);
+            }
+        };
+    }
+
+    chunk_result.throttled += client.throttled();
+    chunk_result.chunk_metrics.push(ChunkMetrics {
+        thread_id: id,
+        domains: chunk_domains,
+        latency: chunk_start.elapsed(),
+        attempts,
+    });
+
+    Ok(chunk_result)
+}
+
+/// Domains as written to local files, mapped back to the real ones when the run is anonymized
+fn local_domains(domains: &[String], anonymizer: Option<&Anonymizer>) -> Vec<String> {
+    match anonymizer {
+        Some(anonymizer) => anonymizer.restore_all(domains),
+        None => domains.to_vec(),
+    }
+}
+
+/// Stops the run on a budget check, writing the domains of the chunks not launched yet to the checkpoint file
+fn stop_on_budget(status: BudgetStatus, budget: &BudgetTracker, chunks: std::slice::Chunks<'_, String>, anonymizer: Option<&Anonymizer>) -> BudgetStop {
+    let remaining_domains = local_domains(&chunks.flatten().cloned().collect::<Vec<String>>(), anonymizer);
+
+    match &status {
+        BudgetStatus::Exceeded { spent_eur } => eprintln!(
+            "Budget cap of {}€ exceeded ({}€ spent), stopping with {} domains remaining",
+            budget.max_cost_eur(), spent_eur, remaining_domains.len()
+        ),
+        BudgetStatus::ProjectionExceeded { spent_eur, projected_eur } => eprintln!(
+            "Projected cost of {}€ exceeds the budget cap of {}€ ({}€ spent), stopping with {} domains remaining",
+            projected_eur, budget.max_cost_eur(), spent_eur, remaining_domains.len()
+        ),
+        BudgetStatus::WithinBudget => {}
+    }
+
+    let checkpoint = std::path::PathBuf::from(BUDGET_CHECKPOINT_FILE);
+    let checkpoint = match write_checkpoint(&remaining_domains, &checkpoint) {
+        Ok(()) => Some(checkpoint),
+        Err(e) => {
+            eprintln!("Failed to write budget checkpoint {}: {}", checkpoint.display(), e);
+            None
+        }
+    };
+
+    BudgetStop {
+        status,
+        max_cost_eur: budget.max_cost_eur(),
+        remaining_domains,
+        checkpoint,
+    }
+}
+
+/// Writes the categories of a chunk to the local cache under the model that answered each domain, and under the
+/// real domains when the run is anonymized
+fn cache_categories(cache: Option<&DomainCache>, result: &LLMRunResult, anonymizer: Option<&Anonymizer>) {
+    let Some(cache) = cache else { return };
+    let mut by_model: HashMap<&str, HashMap<String, Vec<&'static str>>> = HashMap::new();
+    for (domain, categories) in &result.categories {
+        let Some(model) = result.answering_model.get(domain) else { continue };
+        by_model.entry(model.as_str()).or_default().insert(domain.clone(), categories.clone());
+    }
+
+    for (model, categories) in by_model {
+        let categories = match anonymizer {
+            Some(anonymizer) => anonymizer.restore_keys(categories),
+            None => categories,
+        };
+        if let Err(e) = cache.insert(model, &categories) {
+            eprintln!("Failed to write the local cache {}: {}", cache.dir().display(), e);
+        }
+    }
+}
+
+/// Flags the run as cancelled on the first Ctrl-C: the chunks in flight finish and no new one is launched.
+/// A second Ctrl-C exits at once, without writing the outputs.
+fn listen_for_cancellation(cancelled: Arc<AtomicBool>) {
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_err() {
+            return;
+        }
+        eprintln!("Cancelling the run once the chunks in flight are done, press Ctrl-C again to exit now");
+        cancelled.store(true, Ordering::Relaxed);
+
+        if tokio::signal::ctrl_c().await.is_ok() {
+            eprintln!("Run aborted, no output written");
+            std::process::exit(CANCELLED_EXIT_CODE);
+        }
+    });
+}
+
+/// What the retry pass shares with the first pass of the run
+struct RetryContext<'a> {
+    config: &'a Config,
+    command: &'a LLMCommand,
+    store: &'a ResponseStore,
+    network_clients: &'a GeminiNetworkClient,
+    limiter: &'a RateLimiter,
+    cache: Option<&'a DomainCache>,
+    anonymizer: Option<&'a Anonymizer>,
+    cancelled: &'a AtomicBool,
+    budget: Option<&'a BudgetTracker>,
+    run_span: &'a tracing::Span,
+}
+
+/// Sends the domains given up on by the first pass once more, in smaller chunks with a higher thinking budget.
+/// The results are merged into `result`, with the counts of the pass in `result.retry_pass`.
+///
+/// # Returns
+/// * The domains still failing, with the reason of their last failure
+async fn retry_failed_domains<P: LLMProvider>(provider: &P, failed: Vec<FailedDomain>, retry: &RetryContext<'_>, result: &mut LLMRunResult) -> Vec<FailedDomain> {
+    let config = retry_config(retry.config);
+    let domains: Vec<String> = failed.iter().map(|failed| failed.domain.clone()).collect();
+    println!(
+        "Retrying {} failed domains on {} with chunk size {} and thinking budget {:?}",
+        domains.len(), config.model[0], config.chunk_size, config.thinking_budget
+    );
+
+    let mut retry_result = LLMRunResult::new();
+    let mut still_failing = Vec::new();
+    let mut chunks = domains.chunks(config.chunk_size);
+
+    while chunks.len() > 0 {
+        if retry.cancelled.load(Ordering::Relaxed) {
+            still_failing.extend(FailedDomain::all(&chunks.flatten().cloned().collect::<Vec<String>>(), "Run cancelled before the retry"));
+            break;
+        }
+        let spent_eur = result.cost.load(Ordering::Relaxed) + retry_result.cost.load(Ordering::Relaxed);
+        if retry.budget.is_some_and(|budget| spent_eur > budget.max_cost_eur()) {
+            still_failing.extend(FailedDomain::all(&chunks.flatten().cloned().collect::<Vec<String>>(), "Budget cap reached before the retry"));
+            break;
+        }
+
+        let before = retry_result.usage();
+        let cache_name = provider.prepare_cache(retry.config, &retry_result.cost).await;
+        retry_result.attribute_to_model(&retry.config.model[0], before);
+        let cache_name = match cache_name {
+            Ok(cache_name) => cache_name,
+            Err(e) => {
+                eprintln!("Error handling cached content: {}", e);
+                still_failing.extend(FailedDomain::all(&chunks.flatten().cloned().collect::<Vec<String>>(), &format!("Cached content error: {}", e)));
+                break;
+            }
         };
+
+        let (_, results) = TokioScope::scope_and_block(|scope| {
+            for id in 0..config.max_threads {
+                let Some(chunk) = chunks.next() else { break };
+                let client = &retry.network_clients.client[id];
+                let cache_name = &cache_name;
+                let config = &config;
+                let (limiter, command, store) = (retry.limiter, retry.command, retry.store);
+                let chunk_span = telemetry::chunk_span(retry.run_span, id, chunk.len());
+
+                scope.spawn(async move {
+                    async_llm_process_command(provider, &chunk.to_vec(), config, cache_name, id, client, limiter, command, store)
+                        .await
+                        .map_err(|e| (e, chunk.to_vec()))
+                }.instrument(chunk_span));
+            }
+        });
+
+        for outcome in results {
+            match outcome {
+                Ok(Ok(chunk_result)) => {
+                    run_metrics::record_chunk(&chunk_result);
+                    cache_categories(retry.cache, &chunk_result, retry.anonymizer);
+                    retry_result.merge(&chunk_result);
+                }
+                Ok(Err((e, chunk))) => {
+                    eprintln!("Retry of the chunk starting with domain {} failed: {}", chunk[0], e);
+                    still_failing.extend(FailedDomain::all(&chunk, &e.to_string()));
+                    retry_result.chunk_errors.push(e);
+                }
+                Err(join_error) => {
+                    eprintln!("Task panicked: {:?}", join_error);
+                    run_metrics::record_failure_kind(run_metrics::PANIC_FAILURE_KIND);
+                }
+            }
+        }
     }
 
-    //println!("Thread {} Final Gemini Result: {}", id, gemini_result);
+    // Domains of the retried chunks the LLM still left without a result
+    still_failing.append(&mut retry_result.retry_queue);
+
+    result.retry_pass = Some(RetryPass {
+        retried: domains.len(),
+        processed: retry_result.processed.load(Ordering::Relaxed),
+    });
+    println!("Retry pass processed {} of {} failed domains", retry_result.processed.load(Ordering::Relaxed), domains.len());
+    result.merge(&retry_result);
 
-    Ok(LLMResult::Gemini(GeminiResult {
-        processed: gemini_result.processed,
-        retried: gemini_result.retried,
-        failed: gemini_result.failed,
-        cost: gemini_result.cost,
-        cache_saving: gemini_result.cache_saving,
-        categories: gemini_result.categories,
-        descriptions: gemini_result.descriptions,
-    }))
+    still_failing
 }
 
-async fn llm_runtime(domains: Vec<String>, config: &Config, command: &LLMCommand) -> Result<GeminiResult, DynError> {
+async fn llm_runtime<P: LLMProvider>(provider: &P, domains: Vec<String>, config: &Config, command: &LLMCommand, store: &ResponseStore) -> Result<LLMRunResult, CatVisionError> {
+    // Run span entered by the caller, chunk spans are attached to it explicitly since they run on other threads
+    let run_span = tracing::Span::current();
+    let mut final_result = LLMRunResult::new();
+
+    // Domains classified by previous runs are not sent again, their categories are added back once the run is over
+    let cache = match command {
+        LLMCommand::CategorizeDomains => DomainCache::from_config(config),
+        LLMCommand::DescribeDomains => None,
+    };
+    let (domains, cached) = match &cache {
+        Some(cache) => {
+            let cached = cache.lookup(&domains).unwrap_or_else(|e| {
+                eprintln!("Failed to read the local cache {}, every domain is sent: {}", cache.dir().display(), e);
+                HashMap::new()
+            });
+            let misses: Vec<String> = domains.into_iter().filter(|domain| !cached.contains_key(domain)).collect();
+            final_result.cache_hits = cached.len();
+            final_result.cache_misses = misses.len();
+            println!("Local cache: {} domains found, {} sent to the LLM", cached.len(), misses.len());
+            (misses, cached)
+        }
+        None => (domains, HashMap::new()),
+    };
+    let cache = cache.as_ref();
+
+    // Pseudonymize domains before anything is sent to the LLM, results are mapped back once the run is over
+    let (domains, anonymizer) = if config.anonymize_domains {
+        let mut anonymizer = Anonymizer::from_env().map_err(CatVisionError::Config)?;
+        (anonymizer.pseudonymize_all(&domains), Some(anonymizer))
+    } else {
+        (domains, None)
+    };
+    let anonymizer = anonymizer.as_ref();
 
     let mut chunks = domains.chunks(config.chunk_size);
     let mut processed_domains = 0;
     let total_domains = domains.len();
+    let progress = ProgressReporter::new(config.progress, total_domains, config.chunk_size, config.max_threads);
 
-    let mut final_gemini_result = GeminiResult::new();
-
-    let gemini_config = GeminiConfig {
-        model: config.model[0].clone(),
-        prompt: String::new(), // Prompt will be generated in the fetch function
-        cache_name: None,
-        use_url_context: config.use_gemini_url_context,
-        use_google_search: config.use_gemini_google_search,
-        thinking_budget: config.thinking_budget,
-        use_gemini_explicit_caching: config.use_gemini_explicit_caching,
-        use_gemini_custom_cache_duration: config.use_gemini_custom_cache_duration.clone(),
-        max_domain_propositions: config.max_domain_propositions,
-    };
+    let budget = config.max_cost_eur.map(|max_cost_eur| {
+        BudgetTracker::new(max_cost_eur, config.budget_projection_factor, chunks.len())
+    });
+    let mut chunks_done = 0;
+    // Cost already added to the metrics, the caching cost is accumulated outside of the chunks
+    let mut reported_cost = 0.0;
+    run_metrics::record_budget_stop(false);
 
     let network_clients = GeminiNetworkClient::new(config.max_threads);
+    let limiter = RateLimiter::from_config(config);
+    let cancelled = Arc::new(AtomicBool::new(false));
+    listen_for_cancellation(Arc::clone(&cancelled));
 
     // We want to make sure all chunks are processed
     while chunks.len() > 0 {
+        if cancelled.load(Ordering::Relaxed) {
+            eprintln!("Run cancelled, {} domains not sent to the LLM", total_domains - processed_domains);
+            final_result.cancelled = true;
+            break;
+        }
+
         // Handle cached content creation or update for the next batch of chunks
-        let cache_name = match async_gemini_handle_cached_content(&gemini_config, &mut final_gemini_result.cost).await {
+        // The cached content is created for the first model, its cost goes to it
+        let before = final_result.usage();
+        let cache_name = provider.prepare_cache(config, &final_result.cost).await;
+        final_result.attribute_to_model(&config.model[0], before);
+        let cache_name = match cache_name {
             Ok(cache_name) => cache_name,
             Err(e) => {
                 eprintln!("Error handling cached content: {}", e);
@@ -139,8 +433,8 @@ async fn llm_runtime(domains: Vec<String>, config: &Config, command: &LLMCommand
                     println!("Skipping LLM runtime on {} with chunk size [{}-{}]/{} due to caching error",
                         config.model[0], processed_domains - chunk.len(), processed_domains, total_domains
                     );
-                    final_gemini_result.failed.fetch_add(chunk.len(), Ordering::Relaxed);
-                    write_domain_in_garbage_file(&chunk.to_vec(), 666); // Using 666 as an arbitrary ID for skipped chunks
+                    final_result.failed.fetch_add(chunk.len(), Ordering::Relaxed);
+                    final_result.retry_queue.extend(FailedDomain::all(chunk, &format!("Cached content error: {}", e)));
                 }
                 break;
             }
@@ -160,25 +454,40 @@ async fn llm_runtime(domains: Vec<String>, config: &Config, command: &LLMCommand
                     );
 
                     let cache_name = &cache_name;
+                    let limiter = &limiter;
+                    let progress = &progress;
+                    let chunk_span = telemetry::chunk_span(&run_span, id, chunk.len());
+                    let merge_parent = chunk_span.clone();
 
                     scope.spawn(async move {
+                        let chunk_start = std::time::Instant::now();
                         match async_llm_process_command(
+                            provider,
                             &chunk.to_vec(),
                             &config,
                             cache_name,
                             id,
                             client,
-                            command)
+                            limiter,
+                            command,
+                            store)
                             .await {
-                            Ok(LLMResult::Gemini(gemini_result)) => {
-                                Ok(gemini_result)
+                            Ok(chunk_result) => {
+                                progress.chunk_done(
+                                    chunk.len(),
+                                    chunk_result.failed.load(Ordering::Relaxed),
+                                    chunk_result.cost.load(Ordering::Relaxed),
+                                    chunk_start.elapsed(),
+                                );
+                                Ok((chunk_result, merge_parent))
                             },
                             Err(e) => {
                                 eprintln!("Thread {} LLM classification failed: {}", id, e);
-                                Err(e)
+                                progress.chunk_done(chunk.len(), chunk.len(), 0.0, chunk_start.elapsed());
+                                Err((e, chunk.to_vec()))
                             }
                         } 
-                    });
+                    }.instrument(chunk_span));
 
                 } else {
                     break;
@@ -189,19 +498,31 @@ async fn llm_runtime(domains: Vec<String>, config: &Config, command: &LLMCommand
         // Process the results
         for result in results {
             match result {
-                Ok(Ok(gemini_result)) => {
+                Ok(Ok((chunk_result, chunk_span))) => {
                     // Successfully got a result
-                    final_gemini_result.merge(&gemini_result); // or whatever you want to do
+                    let _span = tracing::info_span!(parent: &chunk_span, "merge").entered();
+                    run_metrics::record_chunk(&chunk_result);
+                    cache_categories(cache, &chunk_result, anonymizer);
+                    final_result.merge(&chunk_result);
                 }
-                Ok(Err(e)) => {
+                Ok(Err((e, chunk))) => {
                     eprintln!("Task returned error: {}", e);
+                    final_result.failed.fetch_add(chunk.len(), Ordering::Relaxed);
+                    final_result.retry_queue.extend(FailedDomain::all(&chunk, &e.to_string()));
+                    final_result.chunk_errors.push(e);
                 }
                 Err(join_error) => {
                     eprintln!("Task panicked: {:?}", join_error);
+                    run_metrics::record_failure_kind(run_metrics::PANIC_FAILURE_KIND);
                 }
             }
+            chunks_done += 1;
         }
 
+        let cost = final_result.cost.load(Ordering::Relaxed);
+        run_metrics::record_cost(cost - reported_cost);
+        reported_cost = cost;
+
         println!(
             "Completed LLM runtime on {} with chunk size [{}-{}]/{}",
             config.model[0],
@@ -210,22 +531,285 @@ async fn llm_runtime(domains: Vec<String>, config: &Config, command: &LLMCommand
             total_domains
         );
 
+        if let Some(budget) = &budget {
+            let status = budget.check(final_result.cost.load(Ordering::Relaxed), chunks_done);
+            if status != BudgetStatus::WithinBudget && chunks.len() > 0 {
+                final_result.budget_stop = Some(stop_on_budget(status, budget, chunks, anonymizer));
+                run_metrics::record_budget_stop(true);
+                break;
+            }
+        }
+    }
+
+    let retry_queue = std::mem::take(&mut final_result.retry_queue);
+    if !retry_queue.is_empty() {
+        let still_failing = if final_result.cancelled || final_result.budget_stop.is_some() {
+            eprintln!("Run stopped, the {} failed domains are not retried", retry_queue.len());
+            retry_queue
+        } else if final_result.chunk_errors.iter().any(|e| matches!(e, LlmError::Auth(_))) {
+            eprintln!("Authentication rejected, the {} failed domains are not retried", retry_queue.len());
+            retry_queue
+        } else {
+            let retry = RetryContext { config, command, store, network_clients: &network_clients, limiter: &limiter, cache, anonymizer, cancelled: &cancelled, budget: budget.as_ref(), run_span: &run_span };
+            retry_failed_domains(provider, retry_queue, &retry, &mut final_result).await
+        };
+        let cost = final_result.cost.load(Ordering::Relaxed);
+        run_metrics::record_cost(cost - reported_cost);
+
+        let domains = local_domains(&still_failing.iter().map(|failed| failed.domain.clone()).collect::<Vec<String>>(), anonymizer);
+        let still_failing: Vec<FailedDomain> = still_failing
+            .into_iter()
+            .zip(domains)
+            .map(|(failed, domain)| FailedDomain { domain, reason: failed.reason })
+            .collect();
+        if let Err(e) = write_garbage_file(&still_failing, std::path::Path::new(GARBAGE_FILE)) {
+            eprintln!("Failed to write {}: {}", GARBAGE_FILE, e);
+        }
+        final_result.permanently_failed = still_failing.into_iter().map(|failed| failed.domain).collect();
+        final_result.failed.store(final_result.permanently_failed.len(), Ordering::Relaxed);
     }
 
+    progress.finish(&final_result);
     println!(
         "LLM runtime completed on {} for total domains: {}",
         config.model[0],
         total_domains
     );
 
-    Ok(final_gemini_result)
+    if let Some(anonymizer) = anonymizer {
+        final_result.categories = anonymizer.restore_keys(std::mem::take(&mut final_result.categories));
+        final_result.descriptions = anonymizer.restore_keys(std::mem::take(&mut final_result.descriptions));
+        for chunk in final_result.chunk_metrics.iter_mut() {
+            chunk.domains = anonymizer.restore_all(&chunk.domains);
+        }
+    }
+    final_result.categories.extend(cached);
+
+    Ok(final_result)
 
 }
 
-pub fn sync_llm_runtime(domains: Vec<String>, config: &Config, command: LLMCommand) -> Result<GeminiResult, DynError> {
+/// Runs `command` on `domains` with the LLM, storing every response in `store`
+pub fn sync_llm_runtime(domains: Vec<String>, config: &Config, command: LLMCommand, store: &ResponseStore) -> Result<LLMRunResult, CatVisionError> {
     // Create a new Tokio runtime
     let rt = Runtime::new().expect("Failed to create Tokio runtime");
 
-    // Block on the async function
-    rt.block_on(llm_runtime(domains, config, &command))
+    // Block on the async function, with the provider of the configuration
+    match config.provider {
+        ProviderKind::Gemini => rt.block_on(llm_runtime(&GeminiProvider, domains, config, &command, store)),
+        ProviderKind::OpenAi => rt.block_on(llm_runtime(&OpenAiProvider::from_env(), domains, config, &command, store)),
+    }
+}
+
+/// Parses the stored responses of the chunk `domains`, following its retries while responses are found
+///
+/// # Returns
+/// * The domains left without a result, to query again
+fn reparse_chunk<P: LLMProvider>(provider: &P, domains: &[String], store: &ResponseStore, command: &LLMCommand, result: &mut LLMRunResult) -> Vec<String> {
+    let mut domains = domains.to_vec();
+
+    // The first request of the chunk and its retries, as made by the runtime
+    for _ in 0..=MAX_CHUNK_RETRIES {
+        let response: serde_json::Value = match store.load(command, &domains) {
+            Ok(Some(response)) => response,
+            Ok(None) => break,
+            Err(e) => {
+                eprintln!("{}", e);
+                break;
+            }
+        };
+
+        match provider.parse_stored(domains.clone(), response, result, command) {
+            Ok(remaining) if remaining.is_empty() => return remaining,
+            Ok(remaining) => domains = remaining,
+            Err(e) => {
+                eprintln!("Stored response for chunk starting with domain {} rejected: {}", domains[0], e);
+                break;
+            }
+        }
+    }
+
+    domains
+}
+
+/// Runs `command` on `domains` from the responses stored by a previous run, without any request to the LLM.
+/// The domains are chunked as by [`sync_llm_runtime`], so each chunk finds the responses of its requests.
+///
+/// # Returns
+/// * The parsed results, with no cost, and the domains without a usable stored response in `requery_domains`
+///
+/// # Errors
+///
+/// Returns an error if the run is anonymized and the anonymization key is missing
+pub fn sync_llm_reparse(domains: Vec<String>, config: &Config, command: LLMCommand, store: &ResponseStore) -> Result<LLMRunResult, CatVisionError> {
+    // The stored requests were made on pseudonyms, the same key gives the same ones
+    let (domains, anonymizer) = if config.anonymize_domains {
+        let mut anonymizer = Anonymizer::from_env().map_err(CatVisionError::Config)?;
+        (anonymizer.pseudonymize_all(&domains), Some(anonymizer))
+    } else {
+        (domains, None)
+    };
+
+    let mut result = LLMRunResult { category_rules: CategoryRules::from_config(config), ..LLMRunResult::new() };
+    for chunk in domains.chunks(config.chunk_size) {
+        // The responses were stored by the provider of the configuration
+        let requery = match config.provider {
+            ProviderKind::Gemini => reparse_chunk(&GeminiProvider, chunk, store, &command, &mut result),
+            ProviderKind::OpenAi => reparse_chunk(&OpenAiProvider::new(None), chunk, store, &command, &mut result),
+        };
+        if !requery.is_empty() {
+            eprintln!("No usable stored response for {} domains of the chunk starting with domain {}", requery.len(), chunk[0]);
+        }
+        result.requery_domains.extend(local_domains(&requery, anonymizer.as_ref()));
+    }
+
+    if let Some(anonymizer) = &anonymizer {
+        result.categories = anonymizer.restore_keys(std::mem::take(&mut result.categories));
+        result.descriptions = anonymizer.restore_keys(std::mem::take(&mut result.descriptions));
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Provider failing on `down`, answering `a.fr` only on `partial` and every domain on other models
+    struct FallbackProvider {
+        calls: Mutex<Calls>,
+    }
+
+    impl LLMProvider for FallbackProvider {
+        fn name(&self) -> &'static str {
+            "fallback"
+        }
+
+        async fn classify(&self, domains: Vec<String>, _prompt: String, _config: &Config, call: ProviderCall<'_>) -> crate::providers::ProviderResult {
+            self.calls.lock().unwrap().push((call.model.to_string(), domains.clone()));
+            call.result.cost.fetch_add(0.01, Ordering::Relaxed);
+            let (done, remaining): (Vec<String>, Vec<String>) = match call.model {
+                "down" => return Err(LlmError::Network("connection reset".to_string())),
+                "partial" => domains.into_iter().partition(|domain| domain == "a.fr"),
+                _ => (domains, Vec::new()),
+            };
+            call.result.processed.fetch_add(done.len(), Ordering::Relaxed);
+            for domain in done {
+                call.result.categories.insert(domain, vec!["Enseignement"]);
+            }
+            Ok(remaining)
+        }
+
+        async fn describe(&self, domains: Vec<String>, _prompt: String, _config: &Config, _call: ProviderCall<'_>) -> crate::providers::ProviderResult {
+            Ok(domains)
+        }
+
+        fn parse_stored(&self, domains: Vec<String>, _response: serde_json::Value, _result: &mut LLMRunResult, _command: &LLMCommand) -> crate::providers::ProviderResult {
+            Ok(domains)
+        }
+    }
+
+    /// Model and domains of each request
+    type Calls = Vec<(String, Vec<String>)>;
+
+    fn process(models: &[&str], domains: &[&str]) -> (Result<LLMRunResult, LlmError>, Calls) {
+        let provider = FallbackProvider { calls: Mutex::new(Vec::new()) };
+        let config = Config {
+            model: models.iter().map(|model| model.to_string()).collect(),
+            ..Config::default()
+        };
+        let domains: Vec<String> = domains.iter().map(|domain| domain.to_string()).collect();
+        let store = ResponseStore::new(&std::env::temp_dir().join("catvision-fallback-responses"));
+        let client = reqwest::Client::new();
+        let cache_name = Some("cachedContents/primary".to_string());
+
+        let result = Runtime::new().unwrap().block_on(
+            async_llm_process_command(&provider, &domains, &config, &cache_name, 0, &client, &RateLimiter::unlimited(), &LLMCommand::CategorizeDomains, &store)
+        );
+        (result, provider.calls.into_inner().unwrap())
+    }
+
+    #[test]
+    fn test_fallback_models_only_receive_missing_domains() {
+        let (result, calls) = process(&["down", "partial", "backup"], &["a.fr", "b.fr", "c.fr"]);
+        let result = result.unwrap();
+
+        let sent: Vec<(&str, usize)> = calls.iter().map(|(model, domains)| (model.as_str(), domains.len())).collect();
+        assert_eq!(sent, vec![
+            ("down", 3), ("down", 3), ("down", 3),
+            ("partial", 3), ("partial", 2), ("partial", 2),
+            ("backup", 2),
+        ]);
+        assert_eq!(calls.last().unwrap().1, vec!["b.fr".to_string(), "c.fr".to_string()]);
+
+        assert_eq!(result.processed.load(Ordering::Relaxed), 3);
+        assert_eq!(result.failed.load(Ordering::Relaxed), 0);
+        assert!(result.retry_queue.is_empty());
+        assert_eq!(result.processed_per_model.get("partial"), Some(&1));
+        assert_eq!(result.processed_per_model.get("backup"), Some(&2));
+        assert_eq!(result.processed_per_model.get("down"), None);
+        assert!((result.cost_per_model["down"] - 0.03).abs() < 1e-9);
+        assert!((result.cost_per_model["partial"] - 0.03).abs() < 1e-9);
+        assert!((result.cost_per_model["backup"] - 0.01).abs() < 1e-9);
+        assert_eq!(result.answering_model["a.fr"], "partial");
+        assert_eq!(result.answering_model["b.fr"], "backup");
+
+        // The categories are cached under the model that answered them
+        let cache_dir = std::env::temp_dir().join(format!("catvision-fallback-cache-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&cache_dir);
+        cache_categories(Some(&DomainCache::open(&cache_dir, "down", "v1")), &result, None);
+        let domains: Vec<String> = ["a.fr", "b.fr", "c.fr"].iter().map(|domain| domain.to_string()).collect();
+        let lookup = |model: &str| {
+            let mut hits: Vec<String> = DomainCache::open(&cache_dir, model, "v1").lookup(&domains).unwrap().into_keys().collect();
+            hits.sort();
+            hits
+        };
+        assert!(lookup("down").is_empty());
+        assert_eq!(lookup("partial"), vec!["a.fr"]);
+        assert_eq!(lookup("backup"), vec!["b.fr", "c.fr"]);
+        let _ = std::fs::remove_dir_all(cache_dir);
+    }
+
+    #[test]
+    fn test_chunk_fails_once_every_model_is_exhausted() {
+        let (result, calls) = process(&["down", "down"], &["a.fr", "b.fr"]);
+
+        assert!(matches!(result, Err(LlmError::MaxRetries { attempts: 3 })));
+        assert_eq!(calls.len(), 2 * MAX_CHUNK_RETRIES);
+    }
+
+    #[test]
+    fn test_local_cache_hits_are_not_sent_again() {
+        let cache_dir = std::env::temp_dir().join(format!("catvision-runtime-cache-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&cache_dir);
+        let config = Config {
+            model: vec!["backup".to_string()],
+            use_local_cache: true,
+            local_cache_path: cache_dir.clone(),
+            ..Config::default()
+        };
+        let store = ResponseStore::new(&std::env::temp_dir().join("catvision-cache-responses"));
+        let run = |domains: &[&str]| {
+            let provider = FallbackProvider { calls: Mutex::new(Vec::new()) };
+            let domains = domains.iter().map(|domain| domain.to_string()).collect();
+            let result = Runtime::new().unwrap()
+                .block_on(llm_runtime(&provider, domains, &config, &LLMCommand::CategorizeDomains, &store))
+                .unwrap();
+            (result, provider.calls.into_inner().unwrap())
+        };
+
+        let (result, calls) = run(&["a.fr", "b.fr"]);
+        assert_eq!(calls.len(), 1);
+        assert_eq!((result.cache_hits, result.cache_misses), (0, 2));
+
+        // Only the new domain is sent, the others come from the cache
+        let (result, calls) = run(&["a.fr", "b.fr", "c.fr"]);
+        assert_eq!(calls, vec![("backup".to_string(), vec!["c.fr".to_string()])]);
+        assert_eq!((result.cache_hits, result.cache_misses), (2, 1));
+        assert_eq!(result.categories.len(), 3);
+        assert_eq!(result.categories["a.fr"], vec!["Enseignement"]);
+
+        let _ = std::fs::remove_dir_all(cache_dir);
+    }
 }
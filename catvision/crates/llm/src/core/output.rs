@@ -0,0 +1,228 @@
+use serde_json::{Map, Value};
+
+/// Fallback that found the JSON of an LLM output which was not the expected bare or fenced JSON
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutputFallback {
+    /// A balanced `{...}` block in the middle of prose
+    BalancedBlock,
+    /// A fenced block which is not the whole output, whatever its language tag
+    FencedBlock,
+}
+
+impl OutputFallback {
+    /// Name of the fallback in the statistics
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputFallback::BalancedBlock => "balanced_block",
+            OutputFallback::FencedBlock => "fenced_block",
+        }
+    }
+}
+
+/// JSON object found in an LLM output
+#[derive(Debug)]
+pub struct ParsedOutput {
+    /// Top-level object of the output
+    pub object: Map<String, Value>,
+    /// Fallback that found it, None when the output was the JSON requested by the prompt
+    pub fallback: Option<OutputFallback>,
+}
+
+/// Strips JSON code fences (```json ... ```) from the input string.
+/// # Arguments
+/// * `input` - The input string potentially containing JSON code fences.
+/// # Returns
+/// A &str slice with the code fences removed.
+///
+fn strip_json_fence(input: &str) -> &str {
+    let trimmed = input.trim();
+
+    // Fast path: no code fence
+    if !trimmed.starts_with("```") {
+        return trimmed;
+    }
+
+    // Remove opening fence line (``` or ```json)
+    let without_opening = match trimmed.find('\n') {
+        Some(idx) => &trimmed[idx + 1..],
+        None => return trimmed, // malformed fence, return as-is
+    };
+
+    // Remove closing fence if present
+    let without_closing = without_opening
+        .trim_end()
+        .strip_suffix("```")
+        .unwrap_or(without_opening);
+
+    without_closing.trim()
+}
+
+/// Parses `candidate` as a JSON object
+fn parse_object(candidate: &str) -> Option<Map<String, Value>> {
+    match serde_json::from_str(candidate) {
+        Ok(Value::Object(object)) => Some(object),
+        _ => None,
+    }
+}
+
+/// Index of the brace closing the one at `open`, skipping the braces inside JSON strings
+fn matching_brace(bytes: &[u8], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (index, &byte) in bytes.iter().enumerate().skip(open) {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if byte == b'\\' {
+                escaped = true;
+            } else if byte == b'"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Top-level balanced `{...}` blocks of `content`, in order.
+/// An opening brace never closed (prose such as "use { to ...") is skipped and the scan resumes after it.
+fn balanced_blocks(content: &str) -> Vec<&str> {
+    let bytes = content.as_bytes();
+    let mut blocks = Vec::new();
+    let mut start = 0;
+
+    while let Some(offset) = content[start..].find('{') {
+        let open = start + offset;
+        match matching_brace(bytes, open) {
+            Some(close) => {
+                // Braces are ASCII, both ends are char boundaries
+                blocks.push(&content[open..=close]);
+                start = close + 1;
+            }
+            None => start = open + 1,
+        }
+    }
+    blocks
+}
+
+/// Contents of the fenced blocks of `content`, without their language tag
+fn fenced_blocks(content: &str) -> Vec<&str> {
+    content
+        .split("```")
+        .skip(1)
+        .step_by(2) // Odd parts are inside a fence
+        .map(|block| match block.split_once('\n') {
+            // The language tag, if any, is on the opening line
+            Some((tag, body)) if !tag.trim_start().starts_with('{') => body,
+            _ => block,
+        })
+        .collect()
+}
+
+/// Object of `candidates` containing the most requested `domains`, the first one on a tie
+fn best_object<'a>(candidates: impl Iterator<Item = &'a str>, domains: &[String]) -> Option<Map<String, Value>> {
+    let mut best: Option<(usize, Map<String, Value>)> = None;
+    for object in candidates.filter_map(parse_object) {
+        let found = domains.iter().filter(|domain| object.contains_key(domain.as_str())).count();
+        if best.as_ref().is_none_or(|(best_found, _)| found > *best_found) {
+            best = Some((found, object));
+        }
+    }
+    best.map(|(_, object)| object)
+}
+
+/// Finds the JSON object of an LLM output, even when the model ignored the fencing instruction.
+/// The output is tried, in order:
+/// 1. whole, trimmed, or inside the fence wrapping it, as requested by the prompt
+/// 2. the balanced `{...}` blocks found in it, for JSON wrapped in prose
+/// 3. the fenced blocks found in it, whatever their language tag
+///
+/// When a fallback finds several objects, the one containing the most requested domains wins.
+/// # Arguments
+/// * `content` - The raw text output of the LLM.
+/// * `domains` - Domains the output should hold a result for.
+/// # Returns
+/// The top-level object and the fallback that found it, or an error if no object parses.
+///
+pub fn parse_llm_output(content: &str, domains: &[String]) -> Result<ParsedOutput, Box<dyn std::error::Error>> {
+    if let Some(object) = parse_object(strip_json_fence(content)) {
+        return Ok(ParsedOutput { object, fallback: None });
+    }
+
+    if let Some(object) = best_object(balanced_blocks(content).into_iter(), domains) {
+        return Ok(ParsedOutput { object, fallback: Some(OutputFallback::BalancedBlock) });
+    }
+
+    if let Some(object) = best_object(fenced_blocks(content).into_iter(), domains) {
+        return Ok(ParsedOutput { object, fallback: Some(OutputFallback::FencedBlock) });
+    }
+
+    Err("No JSON object found in the LLM output".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn domains() -> Vec<String> {
+        vec!["a.com".to_string(), "b.com".to_string()]
+    }
+
+    #[test]
+    fn test_bare_and_fenced_json_need_no_fallback() {
+        let bare = parse_llm_output("  {\"a.com\": [\"Email\"]}\n", &domains()).unwrap();
+        assert_eq!(bare.fallback, None);
+        assert!(bare.object.contains_key("a.com"));
+
+        let fenced = parse_llm_output("```json\n{\"a.com\": [\"Email\"]}\n```", &domains()).unwrap();
+        assert_eq!(fenced.fallback, None);
+        assert!(fenced.object.contains_key("a.com"));
+    }
+
+    #[test]
+    fn test_prose_wrapped_json() {
+        let content = "Voici la classification demandée : {\"a.com\": [\"Email\"], \"b.com\": [\"Réseaux {sociaux}\"]} J'espère que cela aide.";
+        let parsed = parse_llm_output(content, &domains()).unwrap();
+        assert_eq!(parsed.fallback, Some(OutputFallback::BalancedBlock));
+        assert_eq!(parsed.object["b.com"][0], "Réseaux {sociaux}");
+    }
+
+    #[test]
+    fn test_multiple_fenced_blocks_take_the_most_domains() {
+        let content = "Exemple du format :\n```json\n{\"example.com\": [\"Email\"]}\n```\nRésultat :\n```\n{\"a.com\": [\"Email\"], \"b.com\": [\"Jeux\"]}\n```\nEt un rappel :\n```json\n{\"a.com\": [\"Email\"]}\n```";
+        let parsed = parse_llm_output(content, &domains()).unwrap();
+        assert!(parsed.fallback.is_some());
+        assert_eq!(parsed.object.len(), 2);
+        assert!(parsed.object.contains_key("a.com") && parsed.object.contains_key("b.com"));
+    }
+
+    #[test]
+    fn test_fenced_block_inside_prose_braces() {
+        // The prose braces enclose the object in a block that does not parse, the fence still holds it
+        let content = "Résultat {\n```python\n{\"a.com\": [\"Email\"]}\n```\n}";
+        let parsed = parse_llm_output(content, &domains()).unwrap();
+        assert_eq!(parsed.fallback, Some(OutputFallback::FencedBlock));
+        assert!(parsed.object.contains_key("a.com"));
+    }
+
+    #[test]
+    fn test_broken_output() {
+        assert!(parse_llm_output("this is not json", &domains()).is_err());
+        assert!(parse_llm_output("{\"a.com\": [\"Email\"", &domains()).is_err());
+        assert!(parse_llm_output("```json\n[\"a.com\"]\n```", &domains()).is_err());
+        assert!(parse_llm_output("", &domains()).is_err());
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use atomic_float::AtomicF64;
+use config::ProgressMode;
+use indicatif::{ProgressBar, ProgressStyle};
+use utils::seconds_to_pretty;
+use crate::core::result::LLMRunResult;
+
+/// Number of the last chunks the average time per chunk is computed on
+const CHUNK_WINDOW: usize = 20;
+
+/// Minimum time between two lines of the `log` progress mode
+const LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Counters of a run at one point, as reported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressSnapshot {
+    /// Domains of the finished chunks, processed or failed
+    pub done: usize,
+    /// Domains sent to the LLM by the run
+    pub total: usize,
+    /// Domains of the finished chunks left without a result
+    pub failed: usize,
+    /// Cost of the finished chunks, in euros
+    pub cost: f64,
+    /// Time left at the rolling average time per chunk, unknown before the first chunk
+    pub eta: Option<Duration>,
+}
+
+impl ProgressSnapshot {
+    /// Share of the finished domains left without a result.
+    pub fn failure_rate(&self) -> f64 {
+        if self.done == 0 {
+            return 0.0;
+        }
+        self.failed as f64 / self.done as f64
+    }
+
+    /// ETA, cost and failure rate, shown next to the progress bar.
+    pub fn message(&self) -> String {
+        let eta = self.eta.and_then(|eta| seconds_to_pretty(eta.as_secs())).unwrap_or_else(|| "--:--:--".to_string());
+        format!("ETA {} | {:.4}€ | {:.1}% failed", eta, self.cost, self.failure_rate() * 100.0)
+    }
+
+    /// Single line of the `log` progress mode.
+    pub fn line(&self) -> String {
+        let percent = if self.total == 0 { 100.0 } else { self.done as f64 / self.total as f64 * 100.0 };
+        format!("Progress: {}/{} domains ({:.1}%) | {}", self.done, self.total, percent, self.message())
+    }
+}
+
+/// Progress of the chunks of a run, updated by the chunk tasks as they finish and shared by reference between them.
+pub struct ProgressReporter {
+    mode: ProgressMode,
+    total: usize,
+    chunk_size: usize,
+    /// Number of chunks run at once, the remaining chunks are shared among them
+    parallelism: usize,
+    done: AtomicUsize,
+    failed: AtomicUsize,
+    cost: AtomicF64,
+    /// Seconds taken by the last `CHUNK_WINDOW` chunks
+    chunk_seconds: Mutex<VecDeque<f64>>,
+    start: Instant,
+    last_log: Mutex<Instant>,
+    bar: Option<ProgressBar>,
+}
+
+impl ProgressReporter {
+    /// Creates the reporter of a run.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - How the progress is reported
+    /// * `total` - Number of domains sent to the LLM
+    /// * `chunk_size` - Number of domains per chunk
+    /// * `parallelism` - Number of chunks run at once
+    pub fn new(mode: ProgressMode, total: usize, chunk_size: usize, parallelism: usize) -> Self {
+        let bar = (mode == ProgressMode::Bar).then(|| {
+            let bar = ProgressBar::new(total as u64);
+            bar.set_style(
+                ProgressStyle::with_template("{bar:40} {pos}/{len} domains | {msg}").expect("Invalid progress bar template"),
+            );
+            bar
+        });
+        let start = Instant::now();
+
+        Self {
+            mode,
+            total,
+            chunk_size: chunk_size.max(1),
+            parallelism: parallelism.max(1),
+            done: AtomicUsize::new(0),
+            failed: AtomicUsize::new(0),
+            cost: AtomicF64::new(0.0),
+            chunk_seconds: Mutex::new(VecDeque::with_capacity(CHUNK_WINDOW)),
+            start,
+            last_log: Mutex::new(start),
+            bar,
+        }
+    }
+
+    /// Records a finished chunk, successful or not, and reports the progress.
+    ///
+    /// # Arguments
+    ///
+    /// * `domains` - Number of domains of the chunk
+    /// * `failed` - Number of them left without a result, all of them when the chunk failed
+    /// * `cost` - Cost of the chunk, in euros
+    /// * `latency` - Time taken by the chunk, retries included
+    pub fn chunk_done(&self, domains: usize, failed: usize, cost: f64, latency: Duration) {
+        self.done.fetch_add(domains, Ordering::Relaxed);
+        self.failed.fetch_add(failed, Ordering::Relaxed);
+        self.cost.fetch_add(cost, Ordering::Relaxed);
+        {
+            let mut chunk_seconds = self.chunk_seconds.lock().unwrap();
+            if chunk_seconds.len() == CHUNK_WINDOW {
+                chunk_seconds.pop_front();
+            }
+            chunk_seconds.push_back(latency.as_secs_f64());
+        }
+
+        match self.mode {
+            ProgressMode::Bar => {
+                if let Some(bar) = &self.bar {
+                    let snapshot = self.snapshot();
+                    bar.set_position(snapshot.done as u64);
+                    bar.set_message(snapshot.message());
+                }
+            }
+            ProgressMode::Log => {
+                let mut last_log = self.last_log.lock().unwrap();
+                if last_log.elapsed() >= LOG_INTERVAL {
+                    *last_log = Instant::now();
+                    println!("{}", self.snapshot().line());
+                }
+            }
+            ProgressMode::Quiet => {}
+        }
+    }
+
+    /// Current counters, with the ETA at the rolling average time per chunk.
+    pub fn snapshot(&self) -> ProgressSnapshot {
+        let done = self.done.load(Ordering::Relaxed);
+        let chunk_seconds = self.chunk_seconds.lock().unwrap();
+        let eta = (!chunk_seconds.is_empty()).then(|| {
+            let average = chunk_seconds.iter().sum::<f64>() / chunk_seconds.len() as f64;
+            let remaining_chunks = self.total.saturating_sub(done).div_ceil(self.chunk_size);
+            Duration::from_secs_f64(average * remaining_chunks as f64 / self.parallelism as f64)
+        });
+
+        ProgressSnapshot {
+            done,
+            total: self.total,
+            failed: self.failed.load(Ordering::Relaxed),
+            cost: self.cost.load(Ordering::Relaxed),
+            eta,
+        }
+    }
+
+    /// Reports the final counters of the run, failed chunks and retry pass included, and clears the progress bar.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - Result of the whole run
+    pub fn finish(&self, result: &LLMRunResult) {
+        if let Some(bar) = &self.bar {
+            bar.finish_and_clear();
+        }
+        if self.mode == ProgressMode::Quiet {
+            return;
+        }
+
+        let processed = result.processed.load(Ordering::Relaxed);
+        let failed = result.failed.load(Ordering::Relaxed);
+        let failure_rate = if processed + failed == 0 { 0.0 } else { failed as f64 / (processed + failed) as f64 };
+        println!(
+            "LLM requests done in {}: {} of {} domains processed, {} failed ({:.1}%), {:.4}€",
+            seconds_to_pretty(self.start.elapsed().as_secs()).unwrap_or_default(),
+            processed,
+            self.total,
+            failed,
+            failure_rate * 100.0,
+            result.cost.load(Ordering::Relaxed)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eta_from_the_rolling_average_time_per_chunk() {
+        let progress = ProgressReporter::new(ProgressMode::Quiet, 100, 10, 2);
+        assert_eq!(progress.snapshot().eta, None);
+        assert!(progress.snapshot().message().starts_with("ETA --:--:--"));
+
+        progress.chunk_done(10, 0, 0.5, Duration::from_secs(4));
+        progress.chunk_done(10, 2, 0.25, Duration::from_secs(4));
+        let snapshot = progress.snapshot();
+        // 8 chunks left, two at a time
+        assert_eq!(snapshot.eta, Some(Duration::from_secs(16)));
+        assert_eq!(snapshot.done, 20);
+        assert_eq!(snapshot.failed, 2);
+        assert_eq!(snapshot.failure_rate(), 0.1);
+        assert_eq!(snapshot.line(), "Progress: 20/100 domains (20.0%) | ETA 00:00:16 | 0.7500€ | 10.0% failed");
+
+        // Only the last chunks count
+        for _ in 0..CHUNK_WINDOW {
+            progress.chunk_done(0, 0, 0.0, Duration::from_secs(1));
+        }
+        assert_eq!(progress.snapshot().eta, Some(Duration::from_secs(4)));
+    }
+
+    #[test]
+    fn test_counters_add_up_across_tasks() {
+        let progress = ProgressReporter::new(ProgressMode::Quiet, 800, 10, 8);
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..10 {
+                        progress.chunk_done(10, 1, 0.125, Duration::from_millis(10));
+                    }
+                });
+            }
+        });
+
+        let snapshot = progress.snapshot();
+        assert_eq!(snapshot.done, 800);
+        assert_eq!(snapshot.failed, 80);
+        assert_eq!(snapshot.cost, 10.0);
+        assert_eq!(snapshot.eta, Some(Duration::ZERO));
+    }
+}
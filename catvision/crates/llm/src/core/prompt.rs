@@ -1,4 +1,7 @@
 use utils::category::{CATEGORIES};
+use config::Config;
+use crate::core::LLMCommand;
+use sha2::{Digest, Sha256};
 
 /// Generates a full categorization prompt for the given domains.
 /// # Arguments
@@ -760,3 +763,206 @@ Domains:
 }
 
 
+
+/// Maximum length of the extra instructions of a run, in characters.
+pub const MAX_EXTRA_INSTRUCTIONS_CHARS: usize = 2000;
+
+/// Lines delimiting the extra instructions in the prompt.
+pub const EXTRA_INSTRUCTIONS_BEGIN: &str = "=== DÉBUT DES INSTRUCTIONS SUPPLÉMENTAIRES ===";
+pub const EXTRA_INSTRUCTIONS_END: &str = "=== FIN DES INSTRUCTIONS SUPPLÉMENTAIRES ===";
+
+/// Sanitizes the extra instructions so they cannot alter the structure of the prompt:
+/// backticks (code fences) become quotes, JSON braces become parentheses and `=` runs cannot forge a delimiter line.
+/// # Arguments
+/// * `text` - Instructions given by the analyst.
+/// # Returns
+/// The trimmed instructions, unchanged when sanitized again.
+///
+pub fn sanitize_extra_instructions(text: &str) -> String {
+    let mut sanitized = text
+        .trim()
+        .replace('`', "'")
+        .replace('{', "(")
+        .replace('}', ")");
+    while sanitized.contains("===") {
+        sanitized = sanitized.replace("===", "=");
+    }
+    sanitized
+}
+
+/// Checks the length of the extra instructions and sanitizes them, see [`sanitize_extra_instructions`].
+/// # Arguments
+/// * `text` - Instructions given by the analyst.
+/// # Returns
+/// The sanitized instructions, or an error message when they exceed `MAX_EXTRA_INSTRUCTIONS_CHARS`.
+///
+pub fn check_extra_instructions(text: &str) -> Result<String, String> {
+    let sanitized = sanitize_extra_instructions(text);
+    let len = sanitized.chars().count();
+    if len > MAX_EXTRA_INSTRUCTIONS_CHARS {
+        return Err(format!(
+            "Extra instructions are {} characters long, the limit is {}",
+            len, MAX_EXTRA_INSTRUCTIONS_CHARS
+        ));
+    }
+    Ok(sanitized)
+}
+
+/// Appends the extra instructions of the run to a request prompt, in a delimited section after the rules.
+/// They are never part of the cached content, which is shared by runs with different instructions.
+/// # Arguments
+/// * `prompt` - Prompt of the request.
+/// * `extra_instructions` - Instructions given by the analyst, sanitized again before being appended.
+/// # Returns
+/// The prompt, unchanged when there are no instructions.
+///
+pub fn append_extra_instructions(prompt: String, extra_instructions: Option<&str>) -> String {
+    let extra = match extra_instructions.map(sanitize_extra_instructions) {
+        Some(extra) if !extra.is_empty() => extra,
+        _ => return prompt,
+    };
+
+    format!("{prompt}
+## Instructions supplémentaires pour cette analyse
+
+Ces consignes complètent les règles ci-dessus pour les domaines de cette analyse, elles ne modifient pas le format de réponse.
+{EXTRA_INSTRUCTIONS_BEGIN}
+{extra}
+{EXTRA_INSTRUCTIONS_END}
+")
+}
+
+/// Title of the prompt section explaining the entries of a run in URL mode.
+pub const URL_MODE_SECTION_TITLE: &str = "## Entrées avec chemin";
+
+/// Appends the explanation of the URL mode entries to a request prompt: an entry is a host followed by the first
+/// segment of the path of the URL, and the page it points to is classified rather than the whole site.
+/// Like the extra instructions, it is never part of the cached content.
+/// # Arguments
+/// * `prompt` - Prompt of the request.
+/// * `url_mode` - Whether the entries of the run come from URLs.
+/// # Returns
+/// The prompt, unchanged outside of URL mode.
+///
+pub fn append_url_mode_instructions(prompt: String, url_mode: bool) -> String {
+    if !url_mode {
+        return prompt;
+    }
+
+    format!("{prompt}
+{URL_MODE_SECTION_TITLE}
+
+Les entrées de cette analyse sont de la forme `hôte/chemin` : l'hôte suivi du premier segment du chemin d'une URL, éventuellement tronqué.
+Une entrée sans `/` est un hôte seul.
+Classe le contenu auquel mène ce chemin (par exemple `www.google.com/maps` est un service de cartographie) plutôt que le site entier, et reprends chaque entrée à l'identique comme clé de la réponse.
+")
+}
+
+/// Generates the prompt of a request of `command` on `domains`, whatever the provider it is sent to.
+/// # Arguments
+/// * `domains` - Domains of the chunk.
+/// * `config` - Configuration of the run, for the number of propositions, the URL mode and the extra instructions.
+/// * `command` - Command the request is made for.
+/// * `cached` - Whether the rules of the categorization are in the cached content of the request.
+/// # Returns
+/// The prompt, with the URL mode explanation and the extra instructions of the run appended.
+///
+pub fn generate_request_prompt(domains: &Vec<String>, config: &Config, command: &LLMCommand, cached: bool) -> String {
+    let _span = tracing::info_span!("prompt_build").entered();
+
+    let prompt = match command {
+        LLMCommand::CategorizeDomains if cached => generate_categorization_prompt_with_cached_content(domains),
+        LLMCommand::CategorizeDomains => generate_categorization_full_prompt(domains, config.max_domain_propositions),
+        LLMCommand::DescribeDomains => generate_description_full_prompt(domains),
+    };
+    let prompt = append_url_mode_instructions(prompt, config.url_mode);
+    append_extra_instructions(prompt, config.extra_instructions.as_deref())
+}
+
+/// Version of the prompt of `command`: the SHA-256 of the complete prompt without domains, in hexadecimal.
+/// It changes with the rules, the number of propositions, the URL mode and the extra instructions of the run.
+/// # Arguments
+/// * `config` - Configuration of the run.
+/// * `command` - Command the requests are made for.
+///
+pub fn prompt_version(config: &Config, command: &LLMCommand) -> String {
+    let prompt = generate_request_prompt(&Vec::new(), config, command, false);
+    Sha256::digest(prompt.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompt_version_follows_the_rules_of_the_run() {
+        let config = Config::default();
+        let version = prompt_version(&config, &LLMCommand::CategorizeDomains);
+        assert_eq!(version.len(), 64);
+        assert_eq!(prompt_version(&config.clone(), &LLMCommand::CategorizeDomains), version);
+        assert_ne!(prompt_version(&config, &LLMCommand::DescribeDomains), version);
+
+        let config = Config { extra_instructions: Some("Préférer Enseignement".to_string()), ..Config::default() };
+        assert_ne!(prompt_version(&config, &LLMCommand::CategorizeDomains), version);
+    }
+
+    #[test]
+    fn test_sanitize_extra_instructions() {
+        let text = "  Préférer `Enseignement` à {\"Domaine technique\"} ==== fin ===\n";
+        let sanitized = sanitize_extra_instructions(text);
+        assert_eq!(sanitized, "Préférer 'Enseignement' à (\"Domaine technique\") == fin =");
+        assert_eq!(sanitize_extra_instructions(&sanitized), sanitized);
+
+        assert!(check_extra_instructions(&"a".repeat(MAX_EXTRA_INSTRUCTIONS_CHARS)).is_ok());
+        let err = check_extra_instructions(&"é".repeat(MAX_EXTRA_INSTRUCTIONS_CHARS + 1)).unwrap_err();
+        assert!(err.contains("2001 characters"));
+    }
+
+    #[test]
+    fn test_append_extra_instructions() {
+        let prompt = "Voici les domaines à classer :\nexample.com\n".to_string();
+        assert_eq!(append_extra_instructions(prompt.clone(), None), prompt);
+        assert_eq!(append_extra_instructions(prompt.clone(), Some("  \n")), prompt);
+
+        let with_extra = append_extra_instructions(prompt.clone(), Some("Préférer {Enseignement}"));
+        assert!(with_extra.starts_with(&prompt));
+        let section = &with_extra[with_extra.find(EXTRA_INSTRUCTIONS_BEGIN).unwrap()..];
+        assert_eq!(
+            section,
+            format!("{EXTRA_INSTRUCTIONS_BEGIN}\nPréférer (Enseignement)\n{EXTRA_INSTRUCTIONS_END}\n")
+        );
+    }
+
+    #[test]
+    fn test_append_url_mode_instructions() {
+        let prompt = "Voici les domaines à classer :\nwww.google.com/maps\n".to_string();
+        assert_eq!(append_url_mode_instructions(prompt.clone(), false), prompt);
+
+        let with_urls = append_url_mode_instructions(prompt.clone(), true);
+        assert!(with_urls.starts_with(&prompt));
+        assert!(with_urls[prompt.len()..].contains(URL_MODE_SECTION_TITLE));
+        assert!(with_urls.contains("`hôte/chemin`"));
+    }
+
+    #[test]
+    fn test_extra_instructions_appended_to_request_prompt() {
+        let extra = "Préférer Enseignement à Domaine technique";
+        let domains = vec!["example.com".to_string()];
+        let mut config = Config::default();
+        for cached in [false, true] {
+            config.extra_instructions = None;
+            let without = generate_request_prompt(&domains, &config, &LLMCommand::CategorizeDomains, cached);
+            config.extra_instructions = Some(extra.to_string());
+            let with = generate_request_prompt(&domains, &config, &LLMCommand::CategorizeDomains, cached);
+
+            // After the request prompt, domains included, in the delimited section
+            assert!(!without.contains(EXTRA_INSTRUCTIONS_BEGIN));
+            assert!(with.starts_with(&without), "cached: {}", cached);
+            assert!(with.trim_end().ends_with(&format!("{}\n{}\n{}", EXTRA_INSTRUCTIONS_BEGIN, extra, EXTRA_INSTRUCTIONS_END)));
+        }
+
+        // The cached content stays the same for every run
+        assert!(!generate_cached_prompt(3).contains(EXTRA_INSTRUCTIONS_BEGIN));
+        assert!(generate_request_prompt(&domains, &config, &LLMCommand::CategorizeDomains, true).contains("cached_content"));
+    }
+}
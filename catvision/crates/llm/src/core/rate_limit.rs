@@ -0,0 +1,256 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use rand::Rng;
+use config::Config;
+use crate::providers::http::{HttpClient, HttpFuture, HttpRequest};
+
+/// Delay of the first backoff after a rate limited request without `Retry-After`, doubled on every 429 in a row.
+pub const RATE_LIMIT_BASE_BACKOFF: Duration = Duration::from_secs(2);
+
+/// Longest backoff after a rate limited request without `Retry-After`.
+pub const RATE_LIMIT_MAX_BACKOFF: Duration = Duration::from_secs(64);
+
+/// Characters per token of the estimate of a request size, the Gemini tokenizer averages about four.
+pub(crate) const CHARS_PER_TOKEN: usize = 4;
+
+/// Token bucket refilled continuously, `capacity` tokens per minute.
+#[derive(Debug)]
+struct Bucket {
+    capacity: f64,
+    available: f64,
+}
+
+impl Bucket {
+    fn new(per_minute: u64) -> Self {
+        Self { capacity: per_minute as f64, available: per_minute as f64 }
+    }
+
+    fn refill(&mut self, elapsed: Duration) {
+        self.available = (self.available + elapsed.as_secs_f64() * self.capacity / 60.0).min(self.capacity);
+    }
+
+    /// Time before `amount` tokens are available, a request larger than the bucket waits for a full bucket
+    fn wait_for(&self, amount: f64) -> Duration {
+        let missing = amount.min(self.capacity) - self.available;
+        if missing <= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(missing * 60.0 / self.capacity)
+        }
+    }
+
+    fn take(&mut self, amount: f64) {
+        self.available -= amount.min(self.capacity);
+    }
+}
+
+#[derive(Debug)]
+struct LimiterState {
+    requests: Option<Bucket>,
+    tokens: Option<Bucket>,
+    last_refill: Instant,
+    /// Set after a 429, no request is sent before
+    backoff_until: Option<Instant>,
+    /// Number of 429 in a row, the exponent of the next backoff
+    consecutive_rate_limits: u32,
+}
+
+/// Rate limiter shared by the chunks of a run: requests wait for the per-minute request and token budgets of
+/// `Config`, and every request waits out the backoff of a 429 answered to any of them.
+#[derive(Debug)]
+pub struct RateLimiter {
+    state: Mutex<LimiterState>,
+}
+
+impl RateLimiter {
+    /// Creates a limiter, a budget left unset is not limited.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_requests_per_minute` - Number of requests sent per minute at most
+    /// * `max_tokens_per_minute` - Number of estimated prompt tokens sent per minute at most
+    pub fn new(max_requests_per_minute: Option<u64>, max_tokens_per_minute: Option<u64>) -> Self {
+        Self {
+            state: Mutex::new(LimiterState {
+                requests: max_requests_per_minute.map(Bucket::new),
+                tokens: max_tokens_per_minute.map(Bucket::new),
+                last_refill: Instant::now(),
+                backoff_until: None,
+                consecutive_rate_limits: 0,
+            }),
+        }
+    }
+
+    /// Limiter of the budgets of the configuration
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.max_requests_per_minute, config.max_tokens_per_minute)
+    }
+
+    /// Limiter without budgets, it only backs off after a 429
+    pub fn unlimited() -> Self {
+        Self::new(None, None)
+    }
+
+    /// Takes a request of `tokens` from the budgets if they allow it at `now`.
+    ///
+    /// # Returns
+    ///
+    /// Zero once the request is taken, the time to wait before trying again otherwise.
+    fn try_acquire(&self, tokens: u64, now: Instant) -> Duration {
+        let mut guard = self.state.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+        let state = &mut *guard;
+
+        let elapsed = now.saturating_duration_since(state.last_refill);
+        state.last_refill = state.last_refill.max(now);
+        for bucket in [&mut state.requests, &mut state.tokens].into_iter().flatten() {
+            bucket.refill(elapsed);
+        }
+
+        let backoff = state.backoff_until.map_or(Duration::ZERO, |until| until.saturating_duration_since(now));
+        let wait = backoff
+            .max(state.requests.as_ref().map_or(Duration::ZERO, |bucket| bucket.wait_for(1.0)))
+            .max(state.tokens.as_ref().map_or(Duration::ZERO, |bucket| bucket.wait_for(tokens as f64)));
+        if wait.is_zero() {
+            state.backoff_until = None;
+            if let Some(bucket) = &mut state.requests {
+                bucket.take(1.0);
+            }
+            if let Some(bucket) = &mut state.tokens {
+                bucket.take(tokens as f64);
+            }
+        }
+        wait
+    }
+
+    /// Waits until a request of `tokens` fits in the budgets and no backoff is going on.
+    ///
+    /// # Returns
+    ///
+    /// The time spent waiting.
+    pub async fn acquire(&self, tokens: u64) -> Duration {
+        let mut throttled = Duration::ZERO;
+        loop {
+            let wait = self.try_acquire(tokens, Instant::now());
+            if wait.is_zero() {
+                return throttled;
+            }
+            tokio::time::sleep(wait).await;
+            throttled += wait;
+        }
+    }
+
+    /// Backs off after a 429: the `Retry-After` delay of the response when it has one, an exponential backoff with
+    /// jitter from [`RATE_LIMIT_BASE_BACKOFF`] otherwise.
+    ///
+    /// # Returns
+    ///
+    /// The backoff before the next request.
+    pub fn rate_limited(&self, retry_after: Option<Duration>) -> Duration {
+        let mut state = self.state.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        let backoff = retry_after.unwrap_or_else(|| {
+            let exponential = RATE_LIMIT_BASE_BACKOFF.saturating_mul(2u32.saturating_pow(state.consecutive_rate_limits)).min(RATE_LIMIT_MAX_BACKOFF);
+            // Up to half the delay on top, the chunks rate limited together do not come back together
+            exponential.mul_f64(1.0 + rand::rng().random_range(0.0..0.5))
+        });
+        state.consecutive_rate_limits = state.consecutive_rate_limits.saturating_add(1);
+
+        let until = Instant::now() + backoff;
+        state.backoff_until = Some(state.backoff_until.map_or(until, |current| current.max(until)));
+        backoff
+    }
+
+    /// Resets the exponential backoff once a request goes through
+    pub fn succeeded(&self) {
+        self.state.lock().unwrap_or_else(std::sync::PoisonError::into_inner).consecutive_rate_limits = 0;
+    }
+}
+
+/// Estimated number of prompt tokens of a request, from the size of its body
+pub fn estimate_tokens(request: &HttpRequest) -> u64 {
+    request.body.as_ref().map_or(0, |body| (body.to_string().len() / CHARS_PER_TOKEN) as u64)
+}
+
+/// [`HttpClient`] of a chunk, sending its requests through the shared [`RateLimiter`] of the run
+pub struct RateLimitedClient<'a> {
+    client: &'a dyn HttpClient,
+    limiter: &'a RateLimiter,
+    /// Time the requests of the chunk waited for the limiter, in microseconds
+    throttled_micros: AtomicU64,
+}
+
+impl<'a> RateLimitedClient<'a> {
+    pub fn new(client: &'a dyn HttpClient, limiter: &'a RateLimiter) -> Self {
+        Self { client, limiter, throttled_micros: AtomicU64::new(0) }
+    }
+
+    /// Time the requests sent so far waited for the limiter
+    pub fn throttled(&self) -> Duration {
+        Duration::from_micros(self.throttled_micros.load(Ordering::Relaxed))
+    }
+}
+
+impl HttpClient for RateLimitedClient<'_> {
+    fn send(&self, request: HttpRequest) -> HttpFuture<'_> {
+        Box::pin(async move {
+            let throttled = self.limiter.acquire(estimate_tokens(&request)).await;
+            self.throttled_micros.fetch_add(throttled.as_micros() as u64, Ordering::Relaxed);
+
+            let response = self.client.send(request).await?;
+            if response.status == 429 {
+                let backoff = self.limiter.rate_limited(response.retry_after);
+                eprintln!("Rate limited, backing off for {:?}", backoff);
+            } else {
+                self.limiter.succeeded();
+            }
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_budget_spreads_requests_over_the_minute() {
+        let limiter = RateLimiter::new(Some(2), None);
+        let start = Instant::now();
+
+        assert_eq!(limiter.try_acquire(0, start), Duration::ZERO);
+        assert_eq!(limiter.try_acquire(0, start), Duration::ZERO);
+        // The bucket is empty, a request comes back every 30 seconds
+        assert_eq!(limiter.try_acquire(0, start), Duration::from_secs(30));
+        assert_eq!(limiter.try_acquire(0, start + Duration::from_secs(10)), Duration::from_secs(20));
+        assert_eq!(limiter.try_acquire(0, start + Duration::from_secs(30)), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_token_budget_waits_for_the_missing_tokens() {
+        let limiter = RateLimiter::new(None, Some(600));
+        let start = Instant::now();
+
+        assert_eq!(limiter.try_acquire(500, start), Duration::ZERO);
+        // 100 tokens left, the 300 missing take 30 seconds at 10 tokens per second
+        assert_eq!(limiter.try_acquire(400, start), Duration::from_secs(30));
+        // A request larger than the budget waits for a full bucket
+        assert_eq!(limiter.try_acquire(1000, start + Duration::from_secs(30)), Duration::from_secs(20));
+    }
+
+    #[test]
+    fn test_rate_limited_backs_off_exponentially_or_as_told() {
+        let limiter = RateLimiter::unlimited();
+
+        let first = limiter.rate_limited(None);
+        assert!(first >= RATE_LIMIT_BASE_BACKOFF && first < RATE_LIMIT_BASE_BACKOFF.mul_f64(1.5));
+        let second = limiter.rate_limited(None);
+        assert!(second >= RATE_LIMIT_BASE_BACKOFF * 2 && second < RATE_LIMIT_BASE_BACKOFF.mul_f64(3.0));
+        assert!(limiter.try_acquire(0, Instant::now()) > Duration::from_secs(3));
+
+        // Retry-After wins over the exponential backoff, and a success starts the backoff over
+        assert_eq!(limiter.rate_limited(Some(Duration::from_secs(7))), Duration::from_secs(7));
+        limiter.succeeded();
+        assert!(limiter.rate_limited(None) < RATE_LIMIT_BASE_BACKOFF.mul_f64(1.5));
+    }
+}
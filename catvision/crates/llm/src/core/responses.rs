@@ -0,0 +1,137 @@
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use crate::core::LLMCommand;
+use crate::error::LlmError;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// Name of the directory, inside the output directory, receiving the responses of the LLM.
+pub const RESPONSE_STORE_DIR: &str = "responses";
+
+/// File, inside the output directory, listing the domains a reparse left without a result, one per line.
+pub const REQUERY_FILE: &str = "requery_domains.txt";
+
+/// Responses of the LLM, one compact JSON file per request named after the hash of the chunk it was made for.
+/// Responses are stored as returned by the provider, a reparse needs the provider of the run that stored them.
+///
+/// Every response is stored as soon as it is received, before it is parsed, so improving the parsing only needs
+/// a reparse of the stored responses instead of new requests. A request made again for the same chunk replaces
+/// the previous response.
+#[derive(Debug, Clone)]
+pub struct ResponseStore {
+    dir: PathBuf,
+}
+
+impl ResponseStore {
+    /// Creates a store in `dir`, the directory is created on the first response.
+    pub fn new(dir: &Path) -> Self {
+        Self { dir: dir.to_path_buf() }
+    }
+
+    /// Directory of the stored responses
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Key of the request of `command` on `domains`: SHA-256 of the command and the domains, in hexadecimal.
+    /// The domains are the ones sent to the LLM, pseudonyms when the run is anonymized.
+    pub fn chunk_key(command: &LLMCommand, domains: &[String]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(command.name().as_bytes());
+        // Length-prefixed, so no domain boundary can be moved without changing the key
+        for domain in domains {
+            hasher.update((domain.len() as u64).to_le_bytes());
+            hasher.update(domain.as_bytes());
+        }
+        hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// Path of the response of `command` on `domains`
+    pub fn response_path(&self, command: &LLMCommand, domains: &[String]) -> PathBuf {
+        self.dir.join(format!("{}.json", Self::chunk_key(command, domains)))
+    }
+
+    /// Stores the response of the request of `command` on `domains`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be created or the response cannot be written
+    pub fn save<T: Serialize>(&self, command: &LLMCommand, domains: &[String], response: &T) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let body = serde_json::to_string(response)?;
+        std::fs::write(self.response_path(command, domains), body)
+    }
+
+    /// Loads the stored response of the request of `command` on `domains`.
+    ///
+    /// # Returns
+    /// * The response, None if no request was made for these domains
+    ///
+    /// # Errors
+    ///
+    /// Returns `LlmError::Parse` if the stored response cannot be read or parsed
+    pub fn load<T: DeserializeOwned>(&self, command: &LLMCommand, domains: &[String]) -> Result<Option<T>, LlmError> {
+        let path = self.response_path(command, domains);
+        let body = match std::fs::read_to_string(&path) {
+            Ok(body) => body,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(LlmError::Parse(format!("Cannot read stored response {}: {}", path.display(), e))),
+        };
+        serde_json::from_str(&body)
+            .map(Some)
+            .map_err(|e| LlmError::Parse(format!("Invalid stored response {}: {}", path.display(), e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::gemini::network::ApiResponse;
+
+    fn response(text: &str) -> ApiResponse {
+        serde_json::from_value(serde_json::json!({
+            "candidates": [{
+                "content": { "role": "model", "parts": [{ "text": text }] },
+                "finishReason": "STOP",
+            }],
+            "usageMetadata": { "promptTokenCount": 10, "candidatesTokenCount": 10, "totalTokenCount": 20 },
+            "modelVersion": "gemini-2.5-flash",
+            "createTime": "2025-01-01T00:00:00Z",
+            "responseId": "test",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_chunk_key_depends_on_command_and_domains() {
+        let domains = vec!["a.com".to_string(), "b.org".to_string()];
+        let key = ResponseStore::chunk_key(&LLMCommand::CategorizeDomains, &domains);
+        assert_eq!(key.len(), 64);
+        assert_eq!(key, ResponseStore::chunk_key(&LLMCommand::CategorizeDomains, &domains));
+        assert_ne!(key, ResponseStore::chunk_key(&LLMCommand::DescribeDomains, &domains));
+        assert_ne!(key, ResponseStore::chunk_key(&LLMCommand::CategorizeDomains, &domains[..1]));
+        // Domains are separated, not concatenated
+        assert_ne!(key, ResponseStore::chunk_key(&LLMCommand::CategorizeDomains, &["a.com\nb.org".to_string()]));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("catvision-responses-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = ResponseStore::new(&dir.join(RESPONSE_STORE_DIR));
+        let domains = vec!["a.com".to_string()];
+        let command = LLMCommand::CategorizeDomains;
+
+        assert!(store.load::<ApiResponse>(&command, &domains).unwrap().is_none());
+
+        store.save(&command, &domains, &response(r#"{"a.com": ["Email"]}"#)).unwrap();
+        let loaded: ApiResponse = store.load(&command, &domains).unwrap().unwrap();
+        assert_eq!(loaded.candidates[0].content.parts[0].text.as_deref(), Some(r#"{"a.com": ["Email"]}"#));
+        assert!(store.load::<ApiResponse>(&LLMCommand::DescribeDomains, &domains).unwrap().is_none());
+
+        std::fs::write(store.response_path(&command, &domains), "{truncated").unwrap();
+        assert!(matches!(store.load::<ApiResponse>(&command, &domains), Err(LlmError::Parse(_))));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use atomic_float::AtomicF64;
+use crate::core::LLMCommand;
+use crate::core::budget::BudgetStop;
+use crate::core::categorization::{parse_categorization_output, CategoryRules};
+use crate::core::description::parse_description_output;
+use crate::core::metrics::ChunkMetrics;
+use crate::core::retry::{FailedDomain, RetryPass};
+use crate::error::LlmError;
+
+/// Domains left without a result by a request, to send again, or the error failing the whole request
+pub type ProviderResult = Result<Vec<String>, LlmError>;
+
+/// Results of a run, whatever the provider: counts, cost, categories or descriptions, and what went wrong.
+/// Chunks fill one each, merged into the result of the run as they complete.
+#[derive(Debug)]
+pub struct LLMRunResult {
+    pub processed: AtomicUsize,
+    pub failed: AtomicUsize,
+    pub retried: AtomicUsize,
+    pub cost: AtomicF64,
+    pub cache_saving: AtomicF64,
+    pub categories: HashMap<String, Vec<&'static str>>,
+    pub descriptions: HashMap<String, HashMap<&'static str, String>>,
+    pub chunk_metrics: Vec<ChunkMetrics>,
+    /// Set when the run was stopped by the budget cap
+    pub budget_stop: Option<BudgetStop>,
+    /// Number of auto-corrected categories per (label returned by the LLM, corrected category) pair
+    pub category_corrections: HashMap<(String, &'static str), usize>,
+    /// Number of labels outside of the taxonomy per label, replaced by the fallback category or sending their domain again
+    pub invalid_categories: HashMap<String, usize>,
+    /// Rules the categories answered by the model are parsed with, set by the runtime from the configuration
+    pub category_rules: CategoryRules,
+    /// Number of responses parsed by each fallback of `parse_llm_output`, the prompt format was ignored
+    pub parse_fallbacks: HashMap<&'static str, usize>,
+    /// Errors of the chunks given up on, a chunk returning no result at all
+    pub chunk_errors: Vec<LlmError>,
+    /// Set when the run was cancelled (Ctrl-C) before every chunk was launched
+    pub cancelled: bool,
+    /// Domains a reparse left without a result, their stored response is missing or does not parse
+    pub requery_domains: Vec<String>,
+    /// Domains given up on by the chunks, sent again by the retry pass at the end of the run
+    pub retry_queue: Vec<FailedDomain>,
+    /// Domains still failing after the retry pass, listed in the garbage file
+    pub permanently_failed: Vec<String>,
+    /// Set when failed domains were sent again at the end of the run
+    pub retry_pass: Option<RetryPass>,
+    /// Cost of the requests sent to each model, the fallback models of `Config.model` included
+    pub cost_per_model: HashMap<String, f64>,
+    /// Number of domains each model ended up processing
+    pub processed_per_model: HashMap<String, usize>,
+    /// Model that answered each categorized domain, the fallback models of `Config.model` included
+    pub answering_model: HashMap<String, String>,
+    /// Time the requests waited for the rate limiter, summed over the chunks
+    pub throttled: Duration,
+    /// Number of domains found in the local cache, not sent to the LLM
+    pub cache_hits: usize,
+    /// Number of domains looked up in the local cache and sent to the LLM
+    pub cache_misses: usize,
+}
+
+impl Default for LLMRunResult {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LLMRunResult {
+    pub fn new() -> Self {
+        Self {
+            processed: AtomicUsize::new(0),
+            failed: AtomicUsize::new(0),
+            retried: AtomicUsize::new(0),
+            cost: AtomicF64::new(0.0),
+            cache_saving: AtomicF64::new(0.0),
+            categories: HashMap::with_capacity(10000),
+            descriptions: HashMap::with_capacity(10000),
+            chunk_metrics: Vec::new(),
+            budget_stop: None,
+            category_corrections: HashMap::new(),
+            invalid_categories: HashMap::new(),
+            category_rules: CategoryRules::default(),
+            parse_fallbacks: HashMap::new(),
+            chunk_errors: Vec::new(),
+            cancelled: false,
+            requery_domains: Vec::new(),
+            retry_queue: Vec::new(),
+            permanently_failed: Vec::new(),
+            retry_pass: None,
+            cost_per_model: HashMap::new(),
+            processed_per_model: HashMap::new(),
+            answering_model: HashMap::new(),
+            throttled: Duration::ZERO,
+            cache_hits: 0,
+            cache_misses: 0,
+        }
+    }
+
+    pub fn merge(&mut self, other: &LLMRunResult) {
+        self.processed.fetch_add(other.processed.load(Ordering::Relaxed), Ordering::Relaxed);
+        self.failed.fetch_add(other.failed.load(Ordering::Relaxed), Ordering::Relaxed);
+        self.retried.fetch_add(other.retried.load(Ordering::Relaxed), Ordering::Relaxed);
+        self.cost.fetch_add(other.cost.load(Ordering::Relaxed), Ordering::Relaxed);
+        self.cache_saving.fetch_add(other.cache_saving.load(Ordering::Relaxed), Ordering::Relaxed);
+
+        for (domain, categories) in &other.categories {
+            self.categories.entry(domain.clone())
+                .or_default()
+                .extend(categories.iter().cloned());
+        }
+        for (domain, descriptions) in &other.descriptions {
+            self.descriptions.entry(domain.clone())
+                .or_default()
+                .extend(descriptions.iter().map(|(k, v)| (*k, v.clone())));
+        }
+        self.chunk_metrics.extend(other.chunk_metrics.iter().cloned());
+        for (pair, count) in &other.category_corrections {
+            *self.category_corrections.entry(pair.clone()).or_insert(0) += count;
+        }
+        for (fallback, count) in &other.parse_fallbacks {
+            *self.parse_fallbacks.entry(fallback).or_insert(0) += count;
+        }
+        self.chunk_errors.extend(other.chunk_errors.iter().cloned());
+        self.requery_domains.extend(other.requery_domains.iter().cloned());
+        self.retry_queue.extend(other.retry_queue.iter().cloned());
+        self.permanently_failed.extend(other.permanently_failed.iter().cloned());
+        for (model, cost) in &other.cost_per_model {
+            *self.cost_per_model.entry(model.clone()).or_insert(0.0) += cost;
+        }
+        for (model, processed) in &other.processed_per_model {
+            *self.processed_per_model.entry(model.clone()).or_insert(0) += processed;
+        }
+        self.answering_model.extend(other.answering_model.iter().map(|(domain, model)| (domain.clone(), model.clone())));
+        self.throttled += other.throttled;
+        self.cache_hits += other.cache_hits;
+        self.cache_misses += other.cache_misses;
+        for (label, count) in &other.invalid_categories {
+            *self.invalid_categories.entry(label.clone()).or_insert(0) += count;
+        }
+    }
+
+    /// Attributes to `model` the cost and the domains added to the result since the snapshot of `before`
+    /// # Arguments
+    /// * `model` - Model the requests were sent to
+    /// * `before` - Cost and number of processed domains before the requests
+    pub fn attribute_to_model(&mut self, model: &str, before: (f64, usize)) {
+        let cost = self.cost.load(Ordering::Relaxed) - before.0;
+        let processed = self.processed.load(Ordering::Relaxed) - before.1;
+        if cost > 0.0 {
+            *self.cost_per_model.entry(model.to_string()).or_insert(0.0) += cost;
+        }
+        if processed > 0 {
+            *self.processed_per_model.entry(model.to_string()).or_insert(0) += processed;
+        }
+    }
+
+    /// Cost and number of processed domains, the snapshot given to [`LLMRunResult::attribute_to_model`]
+    pub fn usage(&self) -> (f64, usize) {
+        (self.cost.load(Ordering::Relaxed), self.processed.load(Ordering::Relaxed))
+    }
+
+    /// Parses the text answered by a model into the categories or descriptions of the result, whatever the provider
+    /// # Arguments
+    /// * `domains` - Domain names sent in the request
+    /// * `response` - Text of the model answer
+    /// * `command` - Command the request was made for
+    /// # Returns
+    /// * `Result<Vec<String>, LlmError>` - Domains that still need processing, or a parse error
+    pub fn add_model_output(&mut self, domains: Vec<String>, response: &str, command: &LLMCommand) -> ProviderResult {
+        println!("LLM Response: {}", response);
+
+        let (errors, fallback) = match command {
+            LLMCommand::CategorizeDomains => {
+                let (valid, errors, corrections, rejected, fallback) = parse_categorization_output(domains, response, &self.category_rules)
+                    .map_err(|e| LlmError::Parse(format!("Error parsing LLM output : {}", e)))?;
+                self.processed.fetch_add(valid.len(), Ordering::Relaxed);
+                self.categories.extend(valid);
+                for correction in corrections {
+                    *self.category_corrections.entry((correction.original, correction.corrected)).or_insert(0) += 1;
+                }
+                for label in rejected {
+                    *self.invalid_categories.entry(label).or_insert(0) += 1;
+                }
+                (errors.into_keys().collect::<Vec<String>>(), fallback)
+            },
+            LLMCommand::DescribeDomains => {
+                let (valid, errors, fallback) = parse_description_output(domains, response)
+                    .map_err(|e| LlmError::Parse(format!("Error parsing LLM output : {}", e)))?;
+                self.processed.fetch_add(valid.len(), Ordering::Relaxed);
+                self.descriptions.extend(valid);
+                (errors.into_keys().collect::<Vec<String>>(), fallback)
+            },
+        };
+        if let Some(fallback) = fallback {
+            eprintln!("LLM output was not the requested JSON, parsed from a {}", fallback.name().replace('_', " "));
+            *self.parse_fallbacks.entry(fallback.name()).or_insert(0) += 1;
+        }
+
+        // Return list of domains that failed
+        self.failed.fetch_add(errors.len(), Ordering::Relaxed);
+        Ok(errors)
+    }
+}
+
+impl Clone for LLMRunResult {
+    fn clone(&self) -> Self {
+        Self {
+            processed: AtomicUsize::new(self.processed.load(Ordering::Relaxed)),
+            failed: AtomicUsize::new(self.failed.load(Ordering::Relaxed)),
+            retried: AtomicUsize::new(self.retried.load(Ordering::Relaxed)),
+            cost: AtomicF64::new(self.cost.load(Ordering::Release)),
+            cache_saving: AtomicF64::new(self.cache_saving.load(Ordering::Release)),
+            categories: self.categories.clone(),
+            descriptions: self.descriptions.clone(),
+            chunk_metrics: self.chunk_metrics.clone(),
+            budget_stop: self.budget_stop.clone(),
+            category_corrections: self.category_corrections.clone(),
+            invalid_categories: self.invalid_categories.clone(),
+            category_rules: self.category_rules.clone(),
+            parse_fallbacks: self.parse_fallbacks.clone(),
+            chunk_errors: self.chunk_errors.clone(),
+            cancelled: self.cancelled,
+            requery_domains: self.requery_domains.clone(),
+            retry_queue: self.retry_queue.clone(),
+            permanently_failed: self.permanently_failed.clone(),
+            retry_pass: self.retry_pass.clone(),
+            cost_per_model: self.cost_per_model.clone(),
+            processed_per_model: self.processed_per_model.clone(),
+            answering_model: self.answering_model.clone(),
+            throttled: self.throttled,
+            cache_hits: self.cache_hits,
+            cache_misses: self.cache_misses,
+        }
+    }
+}
+
+impl std::fmt::Display for LLMRunResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "LLMRunResult = processed: {}, failed: {}, retried: {}, cost: {}, cache_saving: {}",
+            self.processed.load(Ordering::Relaxed),
+            self.failed.load(Ordering::Relaxed),
+            self.retried.load(Ordering::Relaxed),
+            self.cost.load(Ordering::Relaxed),
+            self.cache_saving.load(Ordering::Relaxed)
+        )?;
+        for (category, items) in &self.categories {
+            writeln!(f, "\nCategory: {}", category)?;
+            for item in items {
+                writeln!(f, " - {}", item)?;
+            }
+        }
+        Ok(())
+    }
+}
+
@@ -0,0 +1,124 @@
+use std::io::Write;
+use std::path::Path;
+use config::Config;
+
+/// File receiving the domains still failing after the retry pass, with the reason of their last failure.
+pub const GARBAGE_FILE: &str = "garbage.csv";
+
+/// Divisor of the chunk size of the retry pass, smaller chunks give the LLM less to get wrong at once.
+pub const RETRY_CHUNK_SIZE_DIVISOR: usize = 4;
+
+/// Thinking budget of the retry pass when the first pass thought little or not at all.
+pub const MIN_RETRY_THINKING_BUDGET: i64 = 1024;
+
+/// Domain given up on by a chunk, queued for the retry pass.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FailedDomain {
+    pub domain: String,
+    /// Error of the last attempt on the domain.
+    pub reason: String,
+}
+
+impl FailedDomain {
+    /// Queues every domain of `domains` with the same `reason`.
+    pub fn all(domains: &[String], reason: &str) -> Vec<FailedDomain> {
+        domains.iter().map(|domain| FailedDomain { domain: domain.clone(), reason: reason.to_string() }).collect()
+    }
+}
+
+/// Outcome of the retry pass of a run.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RetryPass {
+    /// Number of domains sent again.
+    pub retried: usize,
+    /// Number of them processed by the retry.
+    pub processed: usize,
+}
+
+/// Configuration of the retry pass: a quarter of the chunk size and twice the thinking budget.
+///
+/// A dynamic thinking budget (-1) is kept, and so is a model without thinking.
+///
+/// # Arguments
+///
+/// * `config` - Configuration of the first pass
+pub fn retry_config(config: &Config) -> Config {
+    let mut retry_config = config.clone();
+    retry_config.chunk_size = (config.chunk_size / RETRY_CHUNK_SIZE_DIVISOR).max(1);
+    retry_config.thinking_budget = match config.thinking_budget {
+        Some(budget) if budget >= 0 => Some((budget * 2).max(MIN_RETRY_THINKING_BUDGET)),
+        budget => budget,
+    };
+    retry_config
+}
+
+/// Quotes a CSV field if it holds the delimiter, a quote or a line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Appends the domains still failing to the garbage file, one `domain,reason` row each.
+/// The header is written when the file is created, the batches of a run share the file.
+///
+/// # Arguments
+///
+/// * `failed` - Domains given up on, with the reason of their last failure
+/// * `path` - Garbage file
+pub fn write_garbage_file(failed: &[FailedDomain], path: &Path) -> std::io::Result<()> {
+    let new_file = !path.is_file();
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+
+    let mut content = String::with_capacity(failed.len() * 32);
+    if new_file {
+        content.push_str("domain,reason\n");
+    }
+    for failed_domain in failed {
+        content.push_str(&format!("{},{}\n", csv_field(&failed_domain.domain), csv_field(&failed_domain.reason)));
+    }
+    file.write_all(content.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_config_shrinks_chunks_and_thinks_more() {
+        let mut config = Config { chunk_size: 100, thinking_budget: Some(2048), ..Default::default() };
+        let retry = retry_config(&config);
+        assert_eq!(retry.chunk_size, 25);
+        assert_eq!(retry.thinking_budget, Some(4096));
+
+        // Tiny chunks and no thinking at all
+        config.chunk_size = 3;
+        config.thinking_budget = Some(0);
+        let retry = retry_config(&config);
+        assert_eq!(retry.chunk_size, 1);
+        assert_eq!(retry.thinking_budget, Some(MIN_RETRY_THINKING_BUDGET));
+
+        // Dynamic thinking, or a model without thinking, is left alone
+        config.thinking_budget = Some(-1);
+        assert_eq!(retry_config(&config).thinking_budget, Some(-1));
+        config.thinking_budget = None;
+        assert_eq!(retry_config(&config).thinking_budget, None);
+    }
+
+    #[test]
+    fn test_write_garbage_file_appends_with_one_header() {
+        let path = std::env::temp_dir().join(format!("catvision-garbage-{}.csv", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        write_garbage_file(&FailedDomain::all(&["a.com".to_string()], "Max retries reached after 3 attempts"), &path).unwrap();
+        write_garbage_file(&[FailedDomain { domain: "b.com".to_string(), reason: "HTTP 500, \"oops\"".to_string() }], &path).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "domain,reason\na.com,Max retries reached after 3 attempts\nb.com,\"HTTP 500, \"\"oops\"\"\"\n"
+        );
+        let _ = std::fs::remove_file(path);
+    }
+}
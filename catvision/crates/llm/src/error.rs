@@ -0,0 +1,164 @@
+use std::fmt;
+use utils::error::CatVisionError;
+
+/// Errors returned by the LLM providers and runtime.
+///
+/// The runtime branches on the variant to decide whether a chunk is worth retrying,
+/// see [`LlmError::is_retryable`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum LlmError {
+    /// Request rejected by the provider rate limiter (HTTP 429).
+    RateLimited(String),
+    /// Missing or invalid credentials (HTTP 401/403).
+    Auth(String),
+    /// Response body or model output could not be parsed.
+    Parse(String),
+    /// Transport failure or unexpected HTTP status.
+    Network(String),
+    /// Spending limit or billing quota exhausted (HTTP 402).
+    Budget(String),
+    /// Chunk still failing after every allowed attempt.
+    MaxRetries { attempts: usize },
+    /// Response blocked by the provider safety filters.
+    SafetyBlocked(String),
+}
+
+impl LlmError {
+    /// Map a non-success HTTP status and its response body to an error.
+    pub fn from_status(status: u16, body: &str) -> Self {
+        let message = format!("HTTP {}: {}", status, body);
+        match status {
+            401 | 403 => LlmError::Auth(message),
+            402 => LlmError::Budget(message),
+            429 => LlmError::RateLimited(message),
+            _ => LlmError::Network(message),
+        }
+    }
+
+    /// Short label of the variant, used as the `kind` of failure metrics.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LlmError::RateLimited(_) => "rate_limited",
+            LlmError::Auth(_) => "auth",
+            LlmError::Parse(_) => "parse",
+            LlmError::Network(_) => "network",
+            LlmError::Budget(_) => "budget",
+            LlmError::MaxRetries { .. } => "max_retries",
+            LlmError::SafetyBlocked(_) => "safety_blocked",
+        }
+    }
+
+    /// Whether sending the same request again may succeed.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            LlmError::RateLimited(_) | LlmError::Parse(_) | LlmError::Network(_)
+        )
+    }
+}
+
+impl fmt::Display for LlmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LlmError::RateLimited(msg) => write!(f, "Rate limited: {}", msg),
+            LlmError::Auth(msg) => write!(f, "Authentication error: {}", msg),
+            LlmError::Parse(msg) => write!(f, "Parse error: {}", msg),
+            LlmError::Network(msg) => write!(f, "Network error: {}", msg),
+            LlmError::Budget(msg) => write!(f, "Budget exhausted: {}", msg),
+            LlmError::MaxRetries { attempts } => write!(f, "Max retries reached after {} attempts", attempts),
+            LlmError::SafetyBlocked(reason) => write!(f, "Response blocked by safety filters: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for LlmError {}
+
+/// Requests keep the HTTP status of [`LlmError::from_status`], so the CLI tells rejected credentials from the
+/// other failures
+impl From<LlmError> for CatVisionError {
+    fn from(e: LlmError) -> Self {
+        let request = |message: String| {
+            let status = message
+                .strip_prefix("HTTP ")
+                .and_then(|rest| rest.split_once(": "))
+                .and_then(|(status, body)| Some((status.parse::<u16>().ok()?, body.to_string())));
+            match status {
+                Some((status, body)) => CatVisionError::LlmRequest { status: Some(status), body },
+                None => CatVisionError::LlmRequest { status: None, body: message },
+            }
+        };
+        match e {
+            LlmError::Parse(message) => CatVisionError::LlmParse(message),
+            LlmError::RateLimited(message) | LlmError::Auth(message) | LlmError::Network(message) | LlmError::Budget(message) => request(message),
+            e @ (LlmError::MaxRetries { .. } | LlmError::SafetyBlocked(_)) => CatVisionError::LlmRequest { status: None, body: e.to_string() },
+        }
+    }
+}
+
+impl From<reqwest::Error> for LlmError {
+    fn from(e: reqwest::Error) -> Self {
+        LlmError::Network(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for LlmError {
+    fn from(e: serde_json::Error) -> Self {
+        LlmError::Parse(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_status() {
+        assert!(matches!(LlmError::from_status(429, "RESOURCE_EXHAUSTED"), LlmError::RateLimited(_)));
+        assert!(matches!(LlmError::from_status(401, ""), LlmError::Auth(_)));
+        assert!(matches!(LlmError::from_status(403, ""), LlmError::Auth(_)));
+        assert!(matches!(LlmError::from_status(402, ""), LlmError::Budget(_)));
+        assert!(matches!(LlmError::from_status(500, ""), LlmError::Network(_)));
+        assert!(matches!(LlmError::from_status(503, ""), LlmError::Network(_)));
+    }
+
+    #[test]
+    fn test_into_catvision_error_keeps_the_status() {
+        let err: CatVisionError = LlmError::from_status(403, "PERMISSION_DENIED").into();
+        assert!(err.is_auth());
+        assert!(matches!(err, CatVisionError::LlmRequest { status: Some(403), body } if body == "PERMISSION_DENIED"));
+
+        let err: CatVisionError = LlmError::Auth("No gcloud access token".to_string()).into();
+        assert!(matches!(err, CatVisionError::LlmRequest { status: None, .. }));
+        assert!(matches!(LlmError::Parse("{".to_string()).into(), CatVisionError::LlmParse(_)));
+    }
+
+    #[test]
+    fn test_from_serde_json_error() {
+        let err: LlmError = serde_json::from_str::<serde_json::Value>("{not json").unwrap_err().into();
+        assert!(matches!(err, LlmError::Parse(_)));
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        assert!(LlmError::RateLimited(String::new()).is_retryable());
+        assert!(LlmError::Parse(String::new()).is_retryable());
+        assert!(LlmError::Network(String::new()).is_retryable());
+        assert!(!LlmError::Auth(String::new()).is_retryable());
+        assert!(!LlmError::Budget(String::new()).is_retryable());
+        assert!(!LlmError::SafetyBlocked(String::new()).is_retryable());
+        assert!(!LlmError::MaxRetries { attempts: 3 }.is_retryable());
+    }
+
+    #[test]
+    fn test_kind() {
+        assert_eq!(LlmError::RateLimited(String::new()).kind(), "rate_limited");
+        assert_eq!(LlmError::MaxRetries { attempts: 3 }.kind(), "max_retries");
+        assert_eq!(LlmError::SafetyBlocked(String::new()).kind(), "safety_blocked");
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(LlmError::MaxRetries { attempts: 3 }.to_string(), "Max retries reached after 3 attempts");
+        assert_eq!(LlmError::SafetyBlocked("SAFETY".into()).to_string(), "Response blocked by safety filters: SAFETY");
+    }
+}
@@ -1,2 +1,6 @@
 pub mod providers;
-pub mod core;
\ No newline at end of file
+pub mod core;
+pub mod cache;
+pub mod error;
+pub mod telemetry;
+pub mod metrics;
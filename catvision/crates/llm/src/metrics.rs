@@ -0,0 +1,209 @@
+//! Prometheus metrics for runs embedded in a long-running service.
+//!
+//! The runtime updates them at the same points as the run statistics: on every merged chunk,
+//! on chunk failures and after each batch. The hooks cost nothing without the `metrics` feature.
+//! With it, the host service mounts [`metrics_handler`] to expose the text exposition format.
+
+use crate::error::LlmError;
+use crate::core::result::LLMRunResult;
+
+/// Failure kind recorded when a chunk task panicked
+pub const PANIC_FAILURE_KIND: &str = "panic";
+
+/// Records a chunk merged into the run result
+pub fn record_chunk(result: &LLMRunResult) {
+    #[cfg(feature = "metrics")]
+    exporter::metrics().record_chunk(result);
+    #[cfg(not(feature = "metrics"))]
+    let _ = result;
+}
+
+/// Records a chunk given up on after `error`
+pub fn record_failure(error: &LlmError) {
+    record_failure_kind(error.kind());
+}
+
+/// Records a chunk failure of the given kind, see [`LlmError::kind`]
+pub fn record_failure_kind(kind: &str) {
+    #[cfg(feature = "metrics")]
+    exporter::metrics().record_failure(kind);
+    #[cfg(not(feature = "metrics"))]
+    let _ = kind;
+}
+
+/// Adds `cost_eur` to the accumulated cost
+pub fn record_cost(cost_eur: f64) {
+    #[cfg(feature = "metrics")]
+    exporter::metrics().record_cost(cost_eur);
+    #[cfg(not(feature = "metrics"))]
+    let _ = cost_eur;
+}
+
+/// Flags whether the current run was stopped by the budget cap
+pub fn record_budget_stop(stopped: bool) {
+    #[cfg(feature = "metrics")]
+    exporter::metrics().record_budget_stop(stopped);
+    #[cfg(not(feature = "metrics"))]
+    let _ = stopped;
+}
+
+#[cfg(feature = "metrics")]
+pub use exporter::{metrics_handler, RunMetrics};
+
+#[cfg(feature = "metrics")]
+mod exporter {
+    use crate::core::result::LLMRunResult;
+    use prometheus::{Counter, Histogram, HistogramOpts, IntCounter, IntCounterVec, IntGauge, Opts, Registry, TextEncoder};
+    use std::sync::atomic::Ordering;
+    use std::sync::OnceLock;
+
+    /// Buckets of the chunk latency histogram, in seconds
+    const CHUNK_LATENCY_BUCKETS: [f64; 9] = [0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0];
+
+    static METRICS: OnceLock<RunMetrics> = OnceLock::new();
+
+    /// Metrics shared by every run of the process
+    pub fn metrics() -> &'static RunMetrics {
+        METRICS.get_or_init(|| RunMetrics::new().expect("Failed to register the Prometheus metrics"))
+    }
+
+    /// Renders the metrics of every run in the Prometheus text exposition format,
+    /// to be served by the host service (usually on `/metrics`)
+    pub fn metrics_handler() -> String {
+        metrics().render()
+    }
+
+    /// Counters, gauges and histograms of the classification runs, in their own registry
+    pub struct RunMetrics {
+        registry: Registry,
+        chunks_processed: IntCounter,
+        domains_classified: IntCounter,
+        failures: IntCounterVec,
+        cost_eur: Counter,
+        chunk_latency: Histogram,
+        budget_stopped: IntGauge,
+    }
+
+    impl RunMetrics {
+        pub fn new() -> Result<Self, prometheus::Error> {
+            let metrics = RunMetrics {
+                registry: Registry::new(),
+                chunks_processed: IntCounter::with_opts(Opts::new(
+                    "catvision_chunks_processed_total",
+                    "Chunks sent to the LLM and merged into the run result",
+                ))?,
+                domains_classified: IntCounter::with_opts(Opts::new(
+                    "catvision_domains_classified_total",
+                    "Domains processed successfully by the LLM",
+                ))?,
+                failures: IntCounterVec::new(
+                    Opts::new("catvision_failures_total", "Chunks given up on, by error kind"),
+                    &["kind"],
+                )?,
+                cost_eur: Counter::with_opts(Opts::new("catvision_cost_eur_total", "Accumulated LLM cost in euros"))?,
+                chunk_latency: Histogram::with_opts(
+                    HistogramOpts::new("catvision_chunk_latency_seconds", "Wall clock time spent on a chunk, retries included")
+                        .buckets(CHUNK_LATENCY_BUCKETS.to_vec()),
+                )?,
+                budget_stopped: IntGauge::with_opts(Opts::new(
+                    "catvision_budget_stopped",
+                    "1 while the last run is stopped by the budget cap, 0 otherwise",
+                ))?,
+            };
+
+            metrics.registry.register(Box::new(metrics.chunks_processed.clone()))?;
+            metrics.registry.register(Box::new(metrics.domains_classified.clone()))?;
+            metrics.registry.register(Box::new(metrics.failures.clone()))?;
+            metrics.registry.register(Box::new(metrics.cost_eur.clone()))?;
+            metrics.registry.register(Box::new(metrics.chunk_latency.clone()))?;
+            metrics.registry.register(Box::new(metrics.budget_stopped.clone()))?;
+
+            Ok(metrics)
+        }
+
+        pub fn record_chunk(&self, result: &LLMRunResult) {
+            self.chunks_processed.inc_by(result.chunk_metrics.len() as u64);
+            self.domains_classified.inc_by(result.processed.load(Ordering::Relaxed) as u64);
+            for chunk in &result.chunk_metrics {
+                self.chunk_latency.observe(chunk.latency.as_secs_f64());
+            }
+        }
+
+        pub fn record_failure(&self, kind: &str) {
+            self.failures.with_label_values(&[kind]).inc();
+        }
+
+        pub fn record_cost(&self, cost_eur: f64) {
+            // Counters only go up, a negative amount would panic
+            if cost_eur > 0.0 {
+                self.cost_eur.inc_by(cost_eur);
+            }
+        }
+
+        pub fn record_budget_stop(&self, stopped: bool) {
+            self.budget_stopped.set(stopped as i64);
+        }
+
+        /// Text exposition format of the registry
+        pub fn render(&self) -> String {
+            TextEncoder::new()
+                .encode_to_string(&self.registry.gather())
+                .unwrap_or_else(|e| {
+                    eprintln!("Failed to encode the Prometheus metrics: {}", e);
+                    String::new()
+                })
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::core::metrics::ChunkMetrics;
+        use crate::error::LlmError;
+        use std::time::Duration;
+
+        fn chunk_result(domains: &[&str], latency_ms: u64, cost_eur: f64) -> LLMRunResult {
+            let mut result = LLMRunResult::new();
+            result.processed.fetch_add(domains.len(), Ordering::Relaxed);
+            result.cost.fetch_add(cost_eur, Ordering::Relaxed);
+            result.chunk_metrics.push(ChunkMetrics {
+                thread_id: 0,
+                domains: domains.iter().map(|d| d.to_string()).collect(),
+                latency: Duration::from_millis(latency_ms),
+                attempts: 1,
+            });
+            result
+        }
+
+        #[test]
+        fn test_two_chunk_job_exposition() {
+            let metrics = RunMetrics::new().unwrap();
+            metrics.record_budget_stop(false);
+
+            // Same sequence as the runtime: merge each chunk, then account for the batch cost
+            let chunks = [chunk_result(&["a.com", "b.com"], 1_250, 0.25), chunk_result(&["c.com", "d.com", "e.com"], 9_000, 0.5)];
+            let mut final_result = LLMRunResult::new();
+            for chunk in &chunks {
+                metrics.record_chunk(chunk);
+                final_result.merge(chunk);
+            }
+            metrics.record_cost(final_result.cost.load(Ordering::Relaxed));
+            metrics.record_failure(LlmError::RateLimited(String::new()).kind());
+            metrics.record_failure(LlmError::Auth(String::new()).kind());
+            metrics.record_failure(LlmError::Auth(String::new()).kind());
+
+            let exposition = metrics.render();
+            assert!(exposition.contains("# TYPE catvision_chunks_processed_total counter"));
+            assert!(exposition.contains("catvision_chunks_processed_total 2\n"));
+            assert!(exposition.contains("catvision_domains_classified_total 5\n"));
+            assert!(exposition.contains("catvision_cost_eur_total 0.75\n"));
+            assert!(exposition.contains("catvision_failures_total{kind=\"rate_limited\"} 1\n"));
+            assert!(exposition.contains("catvision_failures_total{kind=\"auth\"} 2\n"));
+            assert!(exposition.contains("catvision_chunk_latency_seconds_bucket{le=\"2.5\"} 1\n"));
+            assert!(exposition.contains("catvision_chunk_latency_seconds_bucket{le=\"10\"} 2\n"));
+            assert!(exposition.contains("catvision_chunk_latency_seconds_sum 10.25\n"));
+            assert!(exposition.contains("catvision_chunk_latency_seconds_count 2\n"));
+            assert!(exposition.contains("catvision_budget_stopped 0\n"));
+        }
+    }
+}
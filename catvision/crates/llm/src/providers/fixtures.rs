@@ -0,0 +1,188 @@
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use crate::providers::http::{HttpClient, HttpFuture, HttpMethod, HttpRequest, HttpResponse};
+
+/// Environment variable sending the requests of the fixture tests to the real APIs, for maintainers with a key:
+/// the exchanges are recorded over the fixture files
+pub const RECORD_FIXTURES_ENV: &str = "CATVISION_RECORD_FIXTURES";
+
+/// Project written in place of the Google Cloud project in the fixtures
+pub const FIXTURE_PROJECT_ID: &str = "PROJECT_ID";
+
+/// Written in place of the prompts in the fixtures, they change with the rules and are tested on their own
+const PROMPT_PLACEHOLDER: &str = "<prompt>";
+
+/// Request as written in a fixture: the kind of credentials only, the body without prompts nor random seed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: HttpMethod,
+    pub url: String,
+    pub auth: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<serde_json::Value>,
+}
+
+/// Response as written in a fixture, a body that is not JSON is kept as a string
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    pub body: serde_json::Value,
+    /// `Retry-After` header of the response, in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exchange {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+/// [`HttpClient`] of the tests, answering the requests with the exchanges of a fixture file of `tests/fixtures`.
+///
+/// Requests must come in the order of the fixture, each one matching its recorded request. With
+/// [`RECORD_FIXTURES_ENV`] set, the requests are sent to the real APIs instead and the fixture file is rewritten
+/// with the new exchanges, scrubbed of the project and credentials, once the test passes.
+pub enum FixtureClient {
+    Replay {
+        name: String,
+        exchanges: Mutex<VecDeque<Exchange>>,
+    },
+    Record {
+        path: PathBuf,
+        project_id: String,
+        client: reqwest::Client,
+        exchanges: Mutex<Vec<Exchange>>,
+    },
+}
+
+impl FixtureClient {
+    /// Client replaying the fixture `name`, or recording it when [`RECORD_FIXTURES_ENV`] is set
+    pub fn open(name: &str) -> Self {
+        if std::env::var_os(RECORD_FIXTURES_ENV).is_some() {
+            return Self::record_fixture(name);
+        }
+
+        let path = fixture_path(name);
+        let content = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("Failed to read fixture {}: {}", path.display(), e));
+        let exchanges: Vec<Exchange> = serde_json::from_str(&content)
+            .unwrap_or_else(|e| panic!("Invalid fixture {}: {}", path.display(), e));
+        FixtureClient::Replay { name: name.to_string(), exchanges: Mutex::new(exchanges.into()) }
+    }
+
+    /// Client sending the requests to the real APIs and recording them into the fixture `name`
+    pub fn record_fixture(name: &str) -> Self {
+        FixtureClient::Record {
+            path: fixture_path(name),
+            project_id: utils::env::get_project_id().to_string(),
+            client: reqwest::Client::new(),
+            exchanges: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Google Cloud project of the requests: the real one when recording
+    pub fn project_id(&self) -> &str {
+        match self {
+            FixtureClient::Replay { .. } => FIXTURE_PROJECT_ID,
+            FixtureClient::Record { project_id, .. } => project_id,
+        }
+    }
+}
+
+impl HttpClient for FixtureClient {
+    fn send(&self, request: HttpRequest) -> HttpFuture<'_> {
+        match self {
+            FixtureClient::Replay { name, exchanges } => {
+                let recorded = scrub_request(&request, FIXTURE_PROJECT_ID);
+                let exchange = exchanges.lock().unwrap().pop_front()
+                    .unwrap_or_else(|| panic!("Fixture {} has no exchange left for {:?} {}", name, recorded.method, recorded.url));
+                assert_eq!(recorded, exchange.request, "Request does not match the next exchange of fixture {}", name);
+
+                let body = match exchange.response.body {
+                    serde_json::Value::String(body) => body,
+                    body => body.to_string(),
+                };
+                let retry_after = exchange.response.retry_after.map(std::time::Duration::from_secs);
+                Box::pin(async move { Ok(HttpResponse { status: exchange.response.status, body, retry_after }) })
+            }
+            FixtureClient::Record { project_id, client, exchanges, .. } => Box::pin(async move {
+                let recorded = scrub_request(&request, project_id);
+                let response = HttpClient::send(client, request).await?;
+
+                let body = response.body.replace(project_id.as_str(), FIXTURE_PROJECT_ID);
+                let body = serde_json::from_str(&body).unwrap_or(serde_json::Value::String(body));
+                exchanges.lock().unwrap().push(Exchange {
+                    request: recorded,
+                    response: RecordedResponse { status: response.status, body, retry_after: response.retry_after.map(|delay| delay.as_secs()) },
+                });
+                Ok(response)
+            }),
+        }
+    }
+}
+
+impl Drop for FixtureClient {
+    fn drop(&mut self) {
+        // A failing test already reports what went wrong, and its exchanges are not worth keeping
+        if std::thread::panicking() {
+            return;
+        }
+        match self {
+            FixtureClient::Replay { name, exchanges } => {
+                let left = exchanges.get_mut().unwrap().len();
+                assert_eq!(left, 0, "{} exchanges of fixture {} were not requested", left, name);
+            }
+            FixtureClient::Record { path, exchanges, .. } => {
+                let content = serde_json::to_string_pretty(exchanges.get_mut().unwrap()).unwrap();
+                std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+                std::fs::write(&path, content + "\n").unwrap();
+                println!("Recorded fixture {}", path.display());
+            }
+        }
+    }
+}
+
+/// Path of the fixture `name`, relative to `tests/fixtures`
+fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join(format!("{}.json", name))
+}
+
+/// Request as written in a fixture: the project and credentials are left out, so are the prompts and the fields
+/// changing from one request to the next (seed) or with the build (output-json feature)
+fn scrub_request(request: &HttpRequest, project_id: &str) -> RecordedRequest {
+    let body = request.body.as_ref().map(|body| {
+        let body = body.to_string().replace(project_id, FIXTURE_PROJECT_ID);
+        let mut body = serde_json::from_str(&body).unwrap();
+        scrub_body(&mut body);
+        body
+    });
+
+    RecordedRequest {
+        method: request.method,
+        url: request.url.replace(project_id, FIXTURE_PROJECT_ID),
+        auth: request.auth.kind().to_string(),
+        body,
+    }
+}
+
+fn scrub_body(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            fields.remove("seed");
+            fields.remove("responseMimeType");
+            fields.remove("responseSchema");
+            fields.remove("response_format");
+            for (key, field) in fields.iter_mut() {
+                match key.as_str() {
+                    "text" | "content" if field.is_string() => *field = PROMPT_PLACEHOLDER.into(),
+                    _ => scrub_body(field),
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(scrub_body),
+        _ => {}
+    }
+}
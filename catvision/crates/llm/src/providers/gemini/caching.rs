@@ -5,6 +5,7 @@ use std::error::Error;
 use std::process::Command;
 
 use crate::core::prompt::generate_cached_prompt;
+use crate::providers::http::{HttpAuth, HttpClient, HttpMethod, HttpRequest};
 use utils::env::{get_project_id};
 
 //
@@ -136,21 +137,23 @@ pub enum CachingResponse {
 #[allow(dead_code)]
 impl CachingRequest {
     pub async fn execute(&self) -> Result<CachingResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let client = Client::new();
+        let project_id = get_project_id();
         match self {
             CachingRequest::List => {
-                let cached_contents = list_cached_contents().await?;
+                let cached_contents = list_cached_contents(&client, project_id).await?;
                 Ok(CachingResponse::List(cached_contents))
             }
             CachingRequest::Create { model_id, nb_propositions, ttl } => {
-                let cache_response = async_gemini_create_cached_content(model_id, *nb_propositions, ttl.clone()).await?;
+                let cache_response = async_gemini_create_cached_content(&client, project_id, model_id, *nb_propositions, ttl.clone()).await?;
                 Ok(CachingResponse::Create(cache_response))
             }
             CachingRequest::DeleteAll => {
-                async_gemini_delete_all_cached_contents().await?;
+                async_gemini_delete_all_cached_contents(&client, project_id).await?;
                 Ok(CachingResponse::DeleteAll)
             }
             CachingRequest::UpdateTTL { cache_name, ttl } => {
-                let updated_cache = async_gemini_update_cached_content_ttl(cache_name, ttl.clone()).await?;
+                let updated_cache = async_gemini_update_cached_content_ttl(&client, cache_name, ttl.clone()).await?;
                 Ok(CachingResponse::UpdateTTL(updated_cache))
             }
         }
@@ -161,7 +164,7 @@ impl CachingRequest {
 const MODEL_ID: &str = "gemini-2.5-flash";
 const REGION: &str = "us-central1";
 
-fn get_gcloud_access_token() -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+pub(crate) fn get_gcloud_access_token() -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
     let output = Command::new("gcloud")
         .args(["auth", "print-access-token"])
         .output()?;
@@ -185,52 +188,45 @@ fn get_gcloud_access_token() -> Result<String, Box<dyn std::error::Error + Send
     Ok(token)
 }
 
- pub async fn list_cached_contents() -> 
+/// URL of the cached contents of `project_id`
+fn cached_contents_url(project_id: &str) -> String {
+    format!(
+        "https://{}-aiplatform.googleapis.com/v1/projects/{}/locations/{}/cachedContents",
+        REGION, project_id, REGION
+    )
+}
+
+/// URL of the cached content named `cache_name`
+fn cached_content_url(cache_name: &str) -> String {
+    format!("https://{}-aiplatform.googleapis.com/v1/{}", REGION, cache_name)
+}
+
+pub async fn list_cached_contents(client: &dyn HttpClient, project_id: &str) ->
  Result<CachedContentList, Box<dyn std::error::Error + Send + Sync>> {
 
-    let access_token = get_gcloud_access_token()?;
+    let request = HttpRequest::new(HttpMethod::Get, cached_contents_url(project_id))
+        .with_auth(HttpAuth::Gcloud);
 
-    let url = format!(
-        "https://{}-aiplatform.googleapis.com/v1/projects/{}/locations/{}/cachedContents",
-        REGION, get_project_id(), REGION
-    );
-
-    let client = Client::new();
-
-    let resp = match client
-        .get(&url)
-        .header("Authorization", format!("Bearer {}", access_token))
-        .header("Content-Type", "application/json")
-        .send()
-        .await {
-            Err(e) => {
-                return Err(format!("Network error while listing cached contents: {}", e).into());
-            },
-            Ok(resp) => resp,
-        };
-
-    let body = resp.text().await?;
-    // println!("List cached contents response body: {}", body);
-    let resp: CachedContentList = serde_json::from_str(&body)?;
+    let resp = match client.send(request).await {
+        Err(e) => {
+            return Err(format!("Network error while listing cached contents: {}", e).into());
+        },
+        Ok(resp) => resp,
+    };
+
+    // println!("List cached contents response body: {}", resp.body);
+    let resp: CachedContentList = resp.json()?;
     // println!("Parsed cached contents: {:?}", resp);
 
     Ok(resp)
 }
 
-pub async fn async_gemini_create_cached_content(model_id: &String, nb_propositions: usize, ttl: Option<String>) -> Result<CacheResponse, Box<dyn Error + Send + Sync>> {
-    let token = get_gcloud_access_token()?;
-    let real_model_path = format!("projects/{}/locations/{}/publishers/google/models/{}", get_project_id(), REGION, model_id);
-
-    let url = format!(
-        "https://{}-aiplatform.googleapis.com/v1/projects/{}/locations/{}/cachedContents",
-        REGION, get_project_id(), REGION
-    );
-
-    let client: Client = Client::new();
+pub async fn async_gemini_create_cached_content(client: &dyn HttpClient, project_id: &str, model_id: &String, nb_propositions: usize, ttl: Option<String>) -> Result<CacheResponse, Box<dyn Error + Send + Sync>> {
+    let real_model_path = format!("projects/{}/locations/{}/publishers/google/models/{}", project_id, REGION, model_id);
 
     let request = 
     CacheRequest {
-        model: real_model_path.into(),
+        model: real_model_path,
         display_name: "CACHE_DISPLAY_NAME".into(),
         contents: vec![
             CachedContent {
@@ -243,61 +239,45 @@ pub async fn async_gemini_create_cached_content(model_id: &String, nb_propositio
                 ],
             }
         ],
-        ttl: ttl,
+        ttl,
+    };
+    let request = HttpRequest::new(HttpMethod::Post, cached_contents_url(project_id))
+        .with_auth(HttpAuth::Gcloud)
+        .with_json(&request)?;
+
+    let resp = match client.send(request).await {
+        Err(e) => {
+            return Err(format!("Network error while creating cached content: {}", e).into());
+        },
+        Ok(resp) => resp,
     };
 
-    let resp = match client
-        .post(url)
-        .header("Authorization", format!("Bearer {}", token))
-        .header("Content-Type", "application/json")
-        .json(&request)
-        .send()
-        .await {
-            Err(e) => {
-                return Err(format!("Network error while creating cached content: {}", e).into());
-            },
-            Ok(resp) => resp,
-        };
-
-    let body = resp.text().await?;
-    //println!("Cache creation response body: {}", body);
-    let resp: CacheResponse = serde_json::from_str(&body)?;
+    //println!("Cache creation response body: {}", resp.body);
+    let resp: CacheResponse = resp.json()?;
 
     Ok(resp)
 }
 
 #[allow(dead_code)]
-pub async fn async_gemini_delete_all_cached_contents() -> Result<(), Box<dyn Error + Send + Sync>> {
-    let token = get_gcloud_access_token()?;
-
-    let cached_contents = list_cached_contents().await?;
-
-    let client: Client = Client::new();
+pub async fn async_gemini_delete_all_cached_contents(client: &dyn HttpClient, project_id: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let cached_contents = list_cached_contents(client, project_id).await?;
 
     if let Some(contents) = cached_contents.cached_contents {
         for content in contents {
-            let url = format!(
-                "https://{}-aiplatform.googleapis.com/v1/{}",
-                REGION, content.name
-            );
-
-            let resp = match client
-                .delete(&url)
-                .header("Authorization", format!("Bearer {}", token))
-                .header("Content-Type", "application/json")
-                .send()
-                .await {
-                    Err(e) => {
-                        return Err(format!("Network error while deleting cached content: {}", e).into());
-                    },
-                    Ok(resp) => resp,
-                };
-
-            if resp.status().is_success() {
+            let request = HttpRequest::new(HttpMethod::Delete, cached_content_url(&content.name))
+                .with_auth(HttpAuth::Gcloud);
+
+            let resp = match client.send(request).await {
+                Err(e) => {
+                    return Err(format!("Network error while deleting cached content: {}", e).into());
+                },
+                Ok(resp) => resp,
+            };
+
+            if resp.is_success() {
                 println!("Deleted cached content: {}", content.name);
             } else {
-                let body = resp.text().await?;
-                eprintln!("Failed to delete cached content {}: {}", content.name, body);
+                eprintln!("Failed to delete cached content {}: {}", content.name, resp.body);
             }
         }
     } else {
@@ -308,16 +288,7 @@ pub async fn async_gemini_delete_all_cached_contents() -> Result<(), Box<dyn Err
 }  
 
 #[allow(dead_code)]
-pub async fn async_gemini_update_cached_content_ttl(cache_name: &String, ttl: String) -> Result<CacheResponse, Box<dyn std::error::Error + Send + Sync>> {
-    let token = get_gcloud_access_token()?;
-
-    let url = format!(
-        "https://{}-aiplatform.googleapis.com/v1/{}",
-        REGION, cache_name
-    );
-
-    let client: Client = Client::new();
-
+pub async fn async_gemini_update_cached_content_ttl(client: &dyn HttpClient, cache_name: &str, ttl: String) -> Result<CacheResponse, Box<dyn std::error::Error + Send + Sync>> {
     let request = 
     CacheRequest {
         model: "".into(), // Le modèle n'est pas mis à jour
@@ -325,23 +296,19 @@ pub async fn async_gemini_update_cached_content_ttl(cache_name: &String, ttl: St
         contents: vec![], // Le contenu n'est pas mis à jour
         ttl: Some(ttl),
     };
+    let request = HttpRequest::new(HttpMethod::Patch, cached_content_url(cache_name))
+        .with_auth(HttpAuth::Gcloud)
+        .with_json(&request)?;
+
+    let resp = match client.send(request).await {
+        Err(e) => {
+            return Err(format!("Network error while updating cached content TTL: {}", e).into());
+        },
+        Ok(resp) => resp,
+    };
 
-    let resp = match client
-        .patch(url)
-        .header("Authorization", format!("Bearer {}", token))
-        .header("Content-Type", "application/json")
-        .json(&request)
-        .send()
-        .await {
-            Err(e) => {
-                return Err(format!("Network error while updating cached content TTL: {}", e).into());
-            },
-            Ok(resp) => resp,
-        };
-
-    let body = resp.text().await?;
-    //println!("Cache update response body: {}", body);
-    let resp: CacheResponse = serde_json::from_str(&body)?;
+    //println!("Cache update response body: {}", resp.body);
+    let resp: CacheResponse = resp.json()?;
 
     Ok(resp)
 }
@@ -349,93 +316,38 @@ pub async fn async_gemini_update_cached_content_ttl(cache_name: &String, ttl: St
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::providers::fixtures::FixtureClient;
 
     #[tokio::test]
-    async fn test_async_gemini_create_cached_content() {
-        let nb_propositions = 3;
-        let response = async_gemini_create_cached_content(&MODEL_ID.to_string(), nb_propositions, Some("120s".into())).await;
-        match response {
-            Ok(resp) => {
-                println!("Cache created successfully: {:?}", resp);
-                assert_eq!(resp.model, format!("projects/ultra-evening-480109-i2/locations/us-central1/publishers/google/models/{}", MODEL_ID));
-            },
-            Err(e) => {
-                eprintln!("Error creating cache: {}", e);
-                assert!(false);
-            }
-        }
-    }
+    async fn test_fixture_cached_contents_list_create_update() {
+        let client = FixtureClient::open("gemini/cached_contents_cycle");
+        let project_id = client.project_id().to_string();
 
-    #[tokio::test]
-    async fn test_list_cached_contents() {
-        let nb_propositions = 3;
-        let _ = async_gemini_create_cached_content(&MODEL_ID.to_string(), nb_propositions, Some("120s".into())).await;
-        let response = list_cached_contents().await;
-        match response {
-            Ok(info) => {
-                println!("Cached contents retrieved successfully: {:?}", info);
-                assert!(info.cached_contents.is_some());
-            },
-            Err(e) => {
-                eprintln!("Error retrieving cache info: {}", e);
-                assert!(false);
-            }
-        }
-    }
+        let listed = list_cached_contents(&client, &project_id).await.unwrap();
+        assert!(listed.cached_contents.is_none());
 
-    #[tokio::test]
-    async fn test_async_gemini_delete_all_cached_contents() {
-        let nb_propositions = 3;
-        let _ = async_gemini_create_cached_content(&MODEL_ID.to_string(), nb_propositions, Some("120s".into())).await;
-        let response = async_gemini_delete_all_cached_contents().await;
-        match response {
-            Ok(_) => {
-                println!("All cached contents deleted successfully.");
-                assert!(true);
-            },
-            Err(e) => {
-                eprintln!("Error deleting cached contents: {}", e);
-                assert!(false);
-            }
-        }
+        let created = async_gemini_create_cached_content(&client, &project_id, &MODEL_ID.to_string(), 3, Some("60s".into())).await.unwrap();
+        assert_eq!(created.model, format!("projects/{}/locations/us-central1/publishers/google/models/{}", project_id, MODEL_ID));
+        assert!(created.name.starts_with(&format!("projects/{}/locations/us-central1/cachedContents/", project_id)));
 
-        let response = list_cached_contents().await;
-        match response {
-            Ok(info) => {
-                println!("Cached contents after deletion: {:?}", info);
-                assert!(info.cached_contents.is_none() || info.cached_contents.unwrap().is_empty());
-            },
-            Err(e) => {
-                eprintln!("Error retrieving cache info after deletion: {}", e);
-                assert!(false);
-            }
-        }
+        let listed = list_cached_contents(&client, &project_id).await.unwrap();
+        let names: Vec<_> = listed.cached_contents.unwrap().into_iter().map(|item| item.name).collect();
+        assert_eq!(names, vec![created.name.clone()]);
+
+        let updated = async_gemini_update_cached_content_ttl(&client, &created.name, "60s".into()).await.unwrap();
+        assert_eq!(updated.name, created.name);
+        assert_ne!(updated.update_time, created.update_time);
     }
 
+    // No fixture records a deletion, the test sends real requests
     #[tokio::test]
-    async fn test_async_gemini_update_cached_content_ttl() {
+    #[ignore = "sends requests to the Gemini API of MY_GEMINI_PROJECT_ID"]
+    async fn test_async_gemini_delete_all_cached_contents() {
         let nb_propositions = 3;
-        let create_response = async_gemini_create_cached_content(&MODEL_ID.to_string(), nb_propositions, Some("60s".into())).await;
-        match create_response {
-            Ok(resp) => {
-                println!("Cache created successfully for TTL update test: {:?}", resp);
-                let cache_name = resp.name;
-                let update_response = async_gemini_update_cached_content_ttl(&cache_name, "300s".into()).await;
-                match update_response {
-                    Ok(updated_resp) => {
-                        println!("Cache TTL updated successfully: {:?}", updated_resp);
-                        assert_eq!(updated_resp.name, cache_name);
-                    },
-                    Err(e) => {
-                        eprintln!("Error updating cache TTL: {}", e);
-                        assert!(false);
-                    }
-                }
-            },
-            Err(e) => {
-                eprintln!("Error creating cache for TTL update test: {}", e);
-                assert!(false);
-            }
-        }
+        let _ = async_gemini_create_cached_content(&Client::new(), get_project_id(), &MODEL_ID.to_string(), nb_propositions, Some("120s".into())).await;
+        async_gemini_delete_all_cached_contents(&Client::new(), get_project_id()).await.unwrap();
+
+        let info = list_cached_contents(&Client::new(), get_project_id()).await.unwrap();
+        assert!(info.cached_contents.is_none_or(|contents| contents.is_empty()));
     }
 }
\ No newline at end of file
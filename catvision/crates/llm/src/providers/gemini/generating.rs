@@ -1,101 +1,21 @@
-use std::{collections::HashMap};
 use std::error::Error;
+use std::future::Future;
+use tracing::Instrument;
+use config::Config;
 
 use super::caching;
 use super::billing;
-use crate::core::prompt::{
-    generate_categorization_full_prompt,
-    generate_categorization_prompt_with_cached_content,
-    generate_description_full_prompt,
-};
-
-use crate::core::description::parse_description_output;
-use crate::core::categorization::parse_categorization_output;
-use crate::core::LLMCommand; 
-use crate::providers::gemini::network::{GeminiApiCall};
-use std::sync::atomic::AtomicUsize;
+use crate::core::LLMCommand;
+use crate::core::responses::ResponseStore;
+use crate::core::result::{LLMRunResult, ProviderResult};
+use crate::providers::{LLMProvider, ProviderCall};
+use crate::providers::gemini::network::{GeminiApiCall, ApiResponse};
+use crate::providers::http::HttpClient;
+use utils::env::get_project_id;
+use crate::error::LlmError;
 use std::sync::atomic::Ordering;
 use atomic_float::AtomicF64;
 
-#[derive(Debug)]
-pub struct GeminiResult {
-    pub processed: AtomicUsize,
-    pub failed: AtomicUsize,
-    pub retried: AtomicUsize,
-    pub cost: AtomicF64,
-    pub cache_saving: AtomicF64,
-    pub categories: HashMap<String, Vec<&'static str>>,
-    pub descriptions: HashMap<String, HashMap<&'static str, String>>,
-}
-
-impl GeminiResult {
-    pub fn new() -> Self {
-        Self {
-            processed: AtomicUsize::new(0),
-            failed: AtomicUsize::new(0),
-            retried: AtomicUsize::new(0),
-            cost: AtomicF64::new(0.0),
-            cache_saving: AtomicF64::new(0.0),
-            categories: HashMap::with_capacity(10000),
-            descriptions: HashMap::with_capacity(10000),
-        }
-    }
-
-    pub fn merge(&mut self, other: &GeminiResult) {
-        self.processed.fetch_add(other.processed.load(Ordering::Relaxed), Ordering::Relaxed);
-        self.failed.fetch_add(other.failed.load(Ordering::Relaxed), Ordering::Relaxed);
-        self.retried.fetch_add(other.retried.load(Ordering::Relaxed), Ordering::Relaxed);
-        self.cost.fetch_add(other.cost.load(Ordering::Relaxed), Ordering::Relaxed);
-        self.cache_saving.fetch_add(other.cache_saving.load(Ordering::Relaxed), Ordering::Relaxed);
-
-        for (domain, categories) in &other.categories {
-            self.categories.entry(domain.clone())
-                .or_insert_with(Vec::new)
-                .extend(categories.iter().cloned());
-        }
-        for (domain, descriptions) in &other.descriptions {
-            self.descriptions.entry(domain.clone())
-                .or_insert_with(HashMap::new)
-                .extend(descriptions.iter().map(|(k, v)| (*k, v.clone())));
-        }
-    }
-}
-
-impl Clone for GeminiResult {
-    fn clone(&self) -> Self {
-        Self {
-            processed: AtomicUsize::new(self.processed.load(Ordering::Relaxed)),
-            failed: AtomicUsize::new(self.failed.load(Ordering::Relaxed)),
-            retried: AtomicUsize::new(self.retried.load(Ordering::Relaxed)),
-            cost: AtomicF64::new(self.cost.load(Ordering::Release)),
-            cache_saving: AtomicF64::new(self.cache_saving.load(Ordering::Release)),
-            categories: self.categories.clone(),
-            descriptions: self.descriptions.clone(),
-        }
-    }
-}
-
-impl std::fmt::Display for GeminiResult {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(
-            f,
-            "GeminiResult = processed: {}, failed: {}, retried: {}, cost: {}, cache_saving: {}",
-            self.processed.load(Ordering::Relaxed),
-            self.failed.load(Ordering::Relaxed),
-            self.retried.load(Ordering::Relaxed),
-            self.cost.load(Ordering::Relaxed),
-            self.cache_saving.load(Ordering::Relaxed)
-        )?;
-        for (category, items) in &self.categories {
-            writeln!(f, "\nCategory: {}", category)?;
-            for item in items {
-                writeln!(f, " - {}", item)?;
-            }
-        }
-        Ok(())
-    }
-}
-
 use chrono::{DateTime, Utc, Duration};
 
 /// Checks if the cached content needs to be refreshed based on its expiration time
@@ -119,19 +39,22 @@ fn check_cache_expire_time(expire_time_str: &str, minutes: i64) -> Result<bool,
 
 /// Handles cached content for Gemini asynchronously
 /// # Arguments
-/// * `ctx` - Reference to the context containing configuration
-/// * `cost` - Mutable reference to accumulate cost
+/// * `client` - HTTP client of the cached contents API
+/// * `project_id` - Google Cloud project holding the cached contents
+/// * `config` - Reference to the Gemini configuration
+/// * `cost` - Reference to accumulate cost
 /// # Returns
 /// * `Result<Option<String>, Box<dyn Error + Send + Sync>>` - Ok(Some(cache_name)) if cached content is used or created, Ok(None) if not using caching, or an error
-pub async fn async_gemini_handle_cached_content(config: &GeminiConfig, cost: &mut AtomicF64) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+pub async fn async_gemini_handle_cached_content(client: &dyn HttpClient, project_id: &str, config: &GeminiConfig, cost: &AtomicF64) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
     if config.use_gemini_explicit_caching {
-        let cache_contents = caching::list_cached_contents().await?;
+        let cache_contents = caching::list_cached_contents(client, project_id).await?;
         let cache_content_name = match cache_contents.cached_contents {
             Some(cached_list) => {
                 let cache = cached_list.first().unwrap();
                 if let Some(expire_time) = &cache.expire_time {
                     if check_cache_expire_time(expire_time, 1)? {
                         let cache_content = caching::async_gemini_update_cached_content_ttl(
+                            client,
                             &cache.name,
                         config.use_gemini_custom_cache_duration.as_ref().unwrap().clone()
                     ).await?;
@@ -146,7 +69,7 @@ pub async fn async_gemini_handle_cached_content(config: &GeminiConfig, cost: &mu
                 cache.name.clone()
             },
             None => {
-                let cache_content = caching::async_gemini_create_cached_content(&config.model, config.max_domain_propositions,   config.use_gemini_custom_cache_duration.clone()).await?;
+                let cache_content = caching::async_gemini_create_cached_content(client, project_id, &config.model, config.max_domain_propositions,   config.use_gemini_custom_cache_duration.clone()).await?;
                 let cache_cost = billing::CacheCostResult::new(cache_content.usage.unwrap()).compute_cost();
                 cost.fetch_add(cache_cost.eur, Ordering::Relaxed);
                 println!("Created cached contents with name : {}.", cache_content.name);
@@ -165,107 +88,455 @@ pub struct GeminiConfig {
     pub cache_name: Option<String>,
     pub use_url_context: bool,
     pub use_google_search: bool,
-    pub thinking_budget: i64,
+    pub thinking_budget: Option<i64>,
     pub use_gemini_explicit_caching: bool,
     pub use_gemini_custom_cache_duration: Option<String>,
     pub max_domain_propositions: usize,
+    /// Analyst guidance appended to the prompt of every request, never to the cached content
+    pub extra_instructions: Option<String>,
+    /// Whether the domains are `host/path` keys of URLs, explained in the prompt of every request
+    pub url_mode: bool,
+}
+
+impl GeminiConfig {
+    /// Gemini configuration of a run
+    /// # Arguments
+    /// * `config` - Configuration of the run, the first model is used, the one of the cached content
+    /// * `cache_name` - Cached content of the requests, None to send the full prompt
+    pub fn from_config(config: &Config, cache_name: Option<String>) -> Self {
+        Self {
+            model: config.model[0].clone(),
+            prompt: String::new(), // Prompt is generated by the runtime, whatever the provider
+            cache_name,
+            use_url_context: config.use_gemini_url_context,
+            use_google_search: config.use_gemini_google_search,
+            thinking_budget: config.thinking_budget,
+            use_gemini_explicit_caching: config.use_gemini_explicit_caching,
+            use_gemini_custom_cache_duration: config.use_gemini_custom_cache_duration.clone(),
+            max_domain_propositions: config.max_domain_propositions,
+            extra_instructions: config.extra_instructions.clone(),
+            url_mode: config.url_mode,
+        }
+    }
+}
+
+/// Finish reasons reported by Gemini when a candidate is blocked by safety filters.
+const SAFETY_FINISH_REASONS: [&str; 4] = ["SAFETY", "PROHIBITED_CONTENT", "BLOCKLIST", "SPII"];
+
+/// Gemini generateContent API, see [`LLMProvider`]
+pub struct GeminiProvider;
+
+impl LLMProvider for GeminiProvider {
+    fn name(&self) -> &'static str {
+        "gemini"
+    }
+
+    async fn prepare_cache(&self, config: &Config, cost: &AtomicF64) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+        // The project is only required with explicit caching
+        if !config.use_gemini_explicit_caching {
+            return Ok(None);
+        }
+        async_gemini_handle_cached_content(&reqwest::Client::new(), get_project_id(), &GeminiConfig::from_config(config, None), cost).await
+    }
+
+    async fn classify(&self, domains: Vec<String>, prompt: String, config: &Config, call: ProviderCall<'_>) -> ProviderResult {
+        let gemini_config = GeminiConfig { model: call.model.to_string(), ..GeminiConfig::from_config(config, call.cache_name.clone()) };
+        async_gemini_fetch_chat_completion(domains, prompt, &gemini_config, &LLMCommand::CategorizeDomains, call).await
+    }
+
+    async fn describe(&self, domains: Vec<String>, prompt: String, config: &Config, call: ProviderCall<'_>) -> ProviderResult {
+        let gemini_config = GeminiConfig { model: call.model.to_string(), ..GeminiConfig::from_config(config, call.cache_name.clone()) };
+        async_gemini_fetch_chat_completion(domains, prompt, &gemini_config, &LLMCommand::DescribeDomains, call).await
+    }
+
+    fn parse_stored(&self, domains: Vec<String>, response: serde_json::Value, result: &mut LLMRunResult, command: &LLMCommand) -> ProviderResult {
+        let response: ApiResponse = serde_json::from_value(response)
+            .map_err(|e| LlmError::Parse(format!("Stored response is not a Gemini response: {}", e)))?;
+        gemini_parse_response(domains, &response, result, command)
+    }
+}
+
+/// Number of items of each array of the answer, at least and at most: the categories of a domain, up to
+/// `max_domain_propositions`, or its French and English descriptions
+fn response_item_bounds(config: &GeminiConfig, command: &LLMCommand) -> (usize, usize) {
+    match command {
+        LLMCommand::CategorizeDomains => (1, config.max_domain_propositions.max(1)),
+        LLMCommand::DescribeDomains => (2, 2),
+    }
 }
 
 /// Fetches chat completion from Gemini asynchronously
 /// # Arguments
 /// * `domains` - Slice of domain names to process
-/// * `config` - Reference to the Gemini configuration
-/// * `cache_name` - Optional cache name for using cached content
-/// * `my_result` - Mutable reference to accumulate Gemini results
+/// * `prompt` - Prompt of the request, see [`crate::core::prompt::generate_request_prompt`]
+/// * `config` - Reference to the Gemini configuration, with the cache name of the request
+/// * `command` - Command the request is made for
+/// * `call` - HTTP client, response store and result of the chunk
 /// # Returns
-/// * `Result<(), Box<dyn Error>>` - Ok(()) if successful, or an error
+/// * `Result<Vec<String>, LlmError>` - Domains that still need processing, or an error
 pub async fn async_gemini_fetch_chat_completion(
     domains: Vec<String>,
+    prompt: String,
     config: &GeminiConfig,
-    cache_name : &Option<String>,
-    my_result: &mut GeminiResult,
     command: &LLMCommand,
-    client: &reqwest::Client,
-) -> Result<Vec<String>, Box<dyn Error>> {
-
-    let user_prompt = match command {
-        LLMCommand::CategorizeDomains => {
-            if cache_name.is_some() {
-                generate_categorization_prompt_with_cached_content(&domains)
-            } else {
-                generate_categorization_full_prompt(&domains, config.max_domain_propositions)  
-            }
-        },
-        LLMCommand::DescribeDomains => {
-            generate_description_full_prompt(&domains)   
-        },
-    };
+    call: ProviderCall<'_>,
+) -> Result<Vec<String>, LlmError> {
+    let client = call.client;
+    gemini_fetch_with(domains, prompt, config, call.result, command, call.store, |request| async move {
+        request.process_request(client).await
+    })
+    .await
+}
 
+/// Same as [`async_gemini_fetch_chat_completion`], with the HTTP call made by `send`
+/// # Arguments
+/// * `send` - Sends the generate request and returns the raw Gemini response
+async fn gemini_fetch_with<F, Fut>(
+    domains: Vec<String>,
+    prompt: String,
+    config: &GeminiConfig,
+    my_result: &mut LLMRunResult,
+    command: &LLMCommand,
+    store: &ResponseStore,
+    send: F,
+) -> Result<Vec<String>, LlmError>
+where
+    F: FnOnce(GeminiApiCall) -> Fut,
+    Fut: Future<Output = Result<ApiResponse, LlmError>>,
+{
     let generating_api_call = GeminiApiCall::Generate{
         model: config.model.clone(),
-        prompt: user_prompt,
-        cache_name: cache_name.clone(),
+        prompt,
+        cache_name: config.cache_name.clone(),
         use_url_context: config.use_url_context,
         use_google_search: config.use_google_search,
         thinking_budget: config.thinking_budget,
+        item_bounds: Some(response_item_bounds(config, command)),
     };
 
-    let result = generating_api_call.process_request(client).await?;
+    let result = send(generating_api_call)
+        .instrument(tracing::info_span!("http_call"))
+        .await?;
+
+    // Stored before parsing, a response the parser rejects can be reparsed once the parser is fixed
+    if let Err(e) = store.save(command, &domains, &result) {
+        eprintln!("Failed to store the LLM response in {}: {}", store.dir().display(), e);
+    }
+
+    gemini_handle_response(domains, &result, my_result, command)
+}
+
+/// Accounts the cost of a Gemini response and parses its content into `my_result`
+/// # Arguments
+/// * `domains` - Domain names sent in the request
+/// * `result` - Response returned by the Gemini API
+/// * `my_result` - Mutable reference to accumulate Gemini results
+/// * `command` - Command the request was made for
+/// # Returns
+/// * `Result<Vec<String>, LlmError>` - Domains that still need processing, or an error
+fn gemini_handle_response(
+    domains: Vec<String>,
+    result: &ApiResponse,
+    my_result: &mut LLMRunResult,
+    command: &LLMCommand,
+) -> Result<Vec<String>, LlmError> {
+    let _span = tracing::info_span!("parse").entered();
 
     let cost = billing::CostResult::new(&result.usage_metadata).compute_cost();
     my_result.cost.fetch_add(cost.eur, Ordering::Relaxed);
     my_result.cache_saving.fetch_add(cost.cache_saving, Ordering::Relaxed);
 
-    if let Some(candidate) = result.candidates.first() {
-        match candidate.content.parts.first() {
-            None => {
-                return Err("No content in the response message.".into());
+    gemini_parse_response(domains, result, my_result, command)
+}
+
+/// Parses the content of a Gemini response into `my_result`, without accounting its cost
+/// # Arguments
+/// * `domains` - Domain names sent in the request
+/// * `result` - Response returned by the Gemini API, or loaded from the response store
+/// * `my_result` - Mutable reference to accumulate Gemini results
+/// * `command` - Command the request was made for
+/// # Returns
+/// * `Result<Vec<String>, LlmError>` - Domains that still need processing, or an error
+pub fn gemini_parse_response(
+    domains: Vec<String>,
+    result: &ApiResponse,
+    my_result: &mut LLMRunResult,
+    command: &LLMCommand,
+) -> Result<Vec<String>, LlmError> {
+    let candidate = match result.candidates.first() {
+        Some(candidate) => candidate,
+        None => return Err(LlmError::Parse("No choices in the response.".to_string())),
+    };
+
+    if SAFETY_FINISH_REASONS.contains(&candidate.finish_reason.as_str()) {
+        return Err(LlmError::SafetyBlocked(candidate.finish_reason.clone()));
+    }
+
+    let content = match candidate.content.parts.first() {
+        Some(content) => content,
+        None => return Err(LlmError::Parse("No content in the response message.".to_string())),
+    };
+
+    let response = content.text.as_deref().unwrap_or("");
+    my_result.add_model_output(domains, response, command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::prompt::generate_request_prompt;
+    use crate::providers::fixtures::FixtureClient;
+
+    fn response_with(finish_reason: &str, text: &str) -> ApiResponse {
+        serde_json::from_value(serde_json::json!({
+            "candidates": [{
+                "content": { "role": "model", "parts": [{ "text": text }] },
+                "finishReason": finish_reason,
+            }],
+            "usageMetadata": {
+                "promptTokenCount": 10,
+                "candidatesTokenCount": 10,
+                "totalTokenCount": 20,
             },
-            Some(content) => {
-                let response = content.text.as_deref().unwrap_or("");
-                println!("LLM Response: {}", response);
-                match command {
-                    LLMCommand::CategorizeDomains => {
-                        match parse_categorization_output(domains.clone(), response) {
-                            Ok((valid, errors)) => {
-                                my_result.processed.fetch_add(valid.len(), Ordering::Relaxed);
-                                my_result.categories.extend(valid);
-                                
-                                if errors.len() > 0 {
-                                    my_result.failed.fetch_add(errors.len(), Ordering::Relaxed);
-                                    Ok(errors.keys().cloned().collect()) // Return list of domains that failed
-                                } else {
-                                    Ok(vec![])
-                                }
-                            },
-                            Err(e) => {
-                                return Err(format!("Error parsing LLM output : {}", e).into());
-                            }
-                        }
-                    },
-                    LLMCommand::DescribeDomains => {
-                        match parse_description_output(domains.clone(), response) {
-                            Ok((valid, errors)) => {
-                                my_result.processed.fetch_add(valid.len(), Ordering::Relaxed);
-                                my_result.descriptions.extend(valid);
-                                
-                                if errors.len() > 0 {
-                                    my_result.failed.fetch_add(errors.len(), Ordering::Relaxed);
-                                    Ok(errors.keys().cloned().collect()) // Return list of domains that failed
-                                } else {
-                                    Ok(vec![])
-                                }
-                            },
-                            Err(e) => {
-                                return Err(format!("Error parsing LLM output : {}", e).into());
-                            }
-                        }
+            "modelVersion": "gemini-2.5-flash",
+            "createTime": "2025-01-01T00:00:00Z",
+            "responseId": "test",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_handle_response_safety_blocked() {
+        let mut result = LLMRunResult::new();
+        let response = response_with("SAFETY", "");
+        let err = gemini_handle_response(vec!["example.com".into()], &response, &mut result, &LLMCommand::CategorizeDomains).unwrap_err();
+        assert_eq!(err, LlmError::SafetyBlocked("SAFETY".to_string()));
+        assert_eq!(result.processed.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_handle_response_parse_error() {
+        let mut result = LLMRunResult::new();
+        let response = response_with("STOP", "this is not json");
+        let err = gemini_handle_response(vec!["example.com".into()], &response, &mut result, &LLMCommand::DescribeDomains).unwrap_err();
+        assert!(matches!(err, LlmError::Parse(_)));
+    }
+
+    #[test]
+    fn test_handle_response_no_candidates() {
+        let mut result = LLMRunResult::new();
+        let mut response = response_with("STOP", "{}");
+        response.candidates.clear();
+        let err = gemini_handle_response(vec!["example.com".into()], &response, &mut result, &LLMCommand::CategorizeDomains).unwrap_err();
+        assert!(matches!(err, LlmError::Parse(_)));
+    }
+
+    #[test]
+    fn test_handle_response_returns_failed_domains() {
+        let mut result = LLMRunResult::new();
+        let response = response_with("STOP", r#"{"example.com": ["Acheter", "Buy"]}"#);
+        let remaining = gemini_handle_response(
+            vec!["example.com".into(), "missing.com".into()],
+            &response,
+            &mut result,
+            &LLMCommand::DescribeDomains,
+        ).unwrap();
+        assert_eq!(remaining, vec!["missing.com".to_string()]);
+        assert_eq!(result.processed.load(Ordering::Relaxed), 1);
+        assert_eq!(result.failed.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_handle_response_counts_parse_fallbacks() {
+        let mut result = LLMRunResult::new();
+        let response = response_with("STOP", r#"Voici le résultat : {"example.com": ["Email"]} Bonne journée."#);
+        let remaining = gemini_handle_response(vec!["example.com".into()], &response, &mut result, &LLMCommand::CategorizeDomains).unwrap();
+        assert!(remaining.is_empty());
+        assert_eq!(result.categories["example.com"], vec!["Email"]);
+        assert_eq!(result.parse_fallbacks.get("balanced_block"), Some(&1));
+
+        // Bare JSON is the requested format, it is not counted
+        let response = response_with("STOP", r#"{"example.com": ["Email"]}"#);
+        gemini_handle_response(vec!["example.com".into()], &response, &mut result, &LLMCommand::CategorizeDomains).unwrap();
+        assert_eq!(result.parse_fallbacks.values().sum::<usize>(), 1);
+    }
+
+    /// Configuration the fixtures of `tests/fixtures/gemini` were recorded with
+    fn fixture_config() -> Config {
+        Config { model: vec!["gemini-2.5-flash".to_string()], thinking_budget: Some(0), ..Config::default() }
+    }
+
+    /// Sends the categorization request of `domains` to the fixture `name`
+    fn classify_fixture(name: &str, domains: &[&str], result: &mut LLMRunResult) -> ProviderResult {
+        let client = FixtureClient::open(name);
+        let config = fixture_config();
+        let domains: Vec<String> = domains.iter().map(|domain| domain.to_string()).collect();
+        let prompt = generate_request_prompt(&domains, &config, &LLMCommand::CategorizeDomains, false);
+        let store_dir = std::env::temp_dir().join(format!("catvision-{}-{}", name.replace('/', "-"), std::process::id()));
+        let store = ResponseStore::new(&store_dir);
+
+        let outcome = tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let call = ProviderCall { client: &client, model: &config.model[0], cache_name: &None, store: &store, result };
+            GeminiProvider.classify(domains, prompt, &config, call).await
+        });
+        let _ = std::fs::remove_dir_all(store_dir);
+        outcome
+    }
+
+    #[test]
+    fn test_fixture_successful_chunk() {
+        let mut result = LLMRunResult::new();
+        let remaining = classify_fixture("gemini/generate_success", &["lemonde.fr", "doctolib.fr"], &mut result).unwrap();
+        assert!(remaining.is_empty());
+        assert_eq!(result.categories["lemonde.fr"], vec!["Médias / Actualités", "Blogs / Forums"]);
+        assert_eq!(result.categories["doctolib.fr"], vec!["Santé", "Emploi"]);
+        assert_eq!(result.processed.load(Ordering::Relaxed), 2);
+        assert!(result.cost.load(Ordering::Relaxed) > 0.0);
+    }
+
+    #[test]
+    fn test_fixture_partial_response() {
+        let mut result = LLMRunResult::new();
+        let remaining = classify_fixture("gemini/generate_partial", &["lemonde.fr", "doctolib.fr"], &mut result).unwrap();
+        assert_eq!(remaining, vec!["doctolib.fr".to_string()]);
+        assert_eq!(result.processed.load(Ordering::Relaxed), 1);
+        assert_eq!(result.failed.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_fixture_safety_blocked() {
+        // Blocked candidates come without content
+        let mut result = LLMRunResult::new();
+        let err = classify_fixture("gemini/generate_safety_blocked", &["lemonde.fr"], &mut result).unwrap_err();
+        assert_eq!(err, LlmError::SafetyBlocked("SAFETY".to_string()));
+        assert_eq!(result.processed.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_fixture_malformed_json() {
+        let mut result = LLMRunResult::new();
+        let err = classify_fixture("gemini/generate_malformed_json", &["lemonde.fr", "doctolib.fr"], &mut result).unwrap_err();
+        assert!(matches!(err, LlmError::Parse(_)));
+        assert!(result.categories.is_empty());
+    }
+
+    #[test]
+    fn test_fixture_rate_limited_chunk_is_retried() {
+        let client = FixtureClient::open("gemini/generate_rate_limited");
+        let config = fixture_config();
+        let domains = vec!["lemonde.fr".to_string(), "doctolib.fr".to_string()];
+        let store_dir = std::env::temp_dir().join(format!("catvision-gemini-rate-limited-{}", std::process::id()));
+        let store = ResponseStore::new(&store_dir);
+        let limiter = crate::core::rate_limit::RateLimiter::unlimited();
+
+        // The 429 carries a Retry-After of a second, the chunk backs off then sends the same request again
+        // without using up a chunk retry
+        let result = tokio::runtime::Runtime::new().unwrap().block_on(crate::core::async_llm_process_command(
+            &GeminiProvider, &domains, &config, &None, 0, &client, &limiter, &LLMCommand::CategorizeDomains, &store,
+        ))
+        .unwrap();
+        assert_eq!(result.retried.load(Ordering::Relaxed), 1);
+        assert_eq!(result.processed.load(Ordering::Relaxed), 2);
+        assert_eq!(result.chunk_metrics[0].attempts, 2);
+        assert!(result.throttled >= std::time::Duration::from_millis(900));
+
+        let _ = std::fs::remove_dir_all(store_dir);
+    }
+
+    #[test]
+    fn test_fixture_cached_contents_create_then_refresh() {
+        let client = FixtureClient::open("gemini/cached_contents_cycle");
+        let run_config = Config {
+            use_gemini_explicit_caching: true,
+            use_gemini_custom_cache_duration: Some("60s".to_string()),
+            ..fixture_config()
+        };
+        let config = GeminiConfig::from_config(&run_config, None);
+        let cost = AtomicF64::new(0.0);
+        let rt = tokio::runtime::Runtime::new().unwrap();
+
+        // No cached content yet, it is created with the rules
+        let created = rt.block_on(async_gemini_handle_cached_content(&client, client.project_id(), &config, &cost)).unwrap().unwrap();
+        assert!(created.starts_with(&format!("projects/{}/locations/us-central1/cachedContents/", client.project_id())));
+        let creation_cost = cost.load(Ordering::Relaxed);
+        assert!(creation_cost > 0.0);
+
+        // It expires within the minute, its TTL is extended and the refresh is billed
+        let refreshed = rt.block_on(async_gemini_handle_cached_content(&client, client.project_id(), &config, &cost)).unwrap();
+        assert_eq!(refreshed, Some(created));
+        assert!(cost.load(Ordering::Relaxed) > creation_cost);
+    }
+
+    #[cfg(feature = "otel")]
+    #[test]
+    fn test_otel_span_hierarchy_for_two_chunks() {
+        use opentelemetry::trace::TracerProvider as _;
+        use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider};
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder().with_simple_exporter(exporter.clone()).build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        let run_config = Config { model: vec!["gemini-2.5-flash".to_string()], thinking_budget: Some(0), ..Config::default() };
+        let config = GeminiConfig::from_config(&run_config, None);
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let store_dir = std::env::temp_dir().join(format!("catvision-otel-responses-{}", std::process::id()));
+        let store = ResponseStore::new(&store_dir);
+
+        // Mocked run: one domain per chunk, the second chunk is rate limited once
+        tracing::subscriber::with_default(subscriber, || {
+            let run = crate::telemetry::run_span("domains.csv", &config.model, 1);
+            let mut merged = LLMRunResult::new();
+
+            for (id, domain) in ["a.com", "b.com"].into_iter().enumerate() {
+                let chunk = crate::telemetry::chunk_span(&run, id, 1);
+                let mut result = LLMRunResult::new();
+                let response = response_with("STOP", &format!(r#"{{"{}": ["Acheter", "Buy"]}}"#, domain));
+
+                let remaining = rt.block_on(async {
+                    if id == 1 {
+                        crate::telemetry::record_retry(1, &LlmError::RateLimited("quota".to_string()));
                     }
-                }
+                    let domains = vec![domain.to_string()];
+                    let prompt = crate::core::prompt::generate_request_prompt(&domains, &run_config, &LLMCommand::DescribeDomains, false);
+                    gemini_fetch_with(domains, prompt, &config, &mut result, &LLMCommand::DescribeDomains, &store, |_| async move {
+                        Ok(response)
+                    })
+                    .await
+                }.instrument(chunk.clone()))
+                .unwrap();
+                assert!(remaining.is_empty());
+
+                let _merge = tracing::info_span!(parent: &chunk, "merge").entered();
+                merged.merge(&result);
             }
+            assert_eq!(merged.processed.load(Ordering::Relaxed), 2);
+        });
+
+        let spans = exporter.get_finished_spans().unwrap();
+        let runs: Vec<_> = spans.iter().filter(|s| s.name == "catvision.run").collect();
+        assert_eq!(runs.len(), 1);
+        let chunks: Vec<_> = spans.iter().filter(|s| s.name == "catvision.chunk").collect();
+        assert_eq!(chunks.len(), 2);
+
+        for chunk in &chunks {
+            assert_eq!(chunk.parent_span_id, runs[0].span_context.span_id());
+            assert_eq!(chunk.span_context.trace_id(), runs[0].span_context.trace_id());
+
+            let mut stages: Vec<&str> = spans
+                .iter()
+                .filter(|s| s.parent_span_id == chunk.span_context.span_id())
+                .map(|s| s.name.as_ref())
+                .collect();
+            stages.sort();
+            assert_eq!(stages, ["http_call", "merge", "parse", "prompt_build"]);
         }
-    } else {
-        return Err("No choices in the response.".into());
-    } 
-}
 
+        let retried = chunks.iter().filter(|c| c.events.iter().any(|e| e.name == "retry")).count();
+        assert_eq!(retried, 1);
+
+        let _ = std::fs::remove_dir_all(store_dir);
+    }
+}
@@ -2,10 +2,17 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap};
 use rand::prelude::*;
-use utils::env::get_api_key;
 use super::caching::CachingRequest;
+use crate::error::LlmError;
+use crate::providers::http::{HttpAuth, HttpClient, HttpMethod, HttpRequest};
 use std::time::Duration;
 
+/// Environment variable overriding the base URL of the Gemini generation endpoint, e.g. a local mock provider in tests
+pub const GEMINI_ENDPOINT_ENV: &str = "CATVISION_GEMINI_ENDPOINT";
+
+/// Base URL of the Gemini generation endpoint
+const GEMINI_ENDPOINT: &str = "https://aiplatform.googleapis.com";
+
 #[allow(dead_code)]
 /// Enum representing different Gemini API calls
 pub enum GeminiApiCall {
@@ -15,7 +22,9 @@ pub enum GeminiApiCall {
         cache_name: Option<String>,
         use_url_context: bool,
         use_google_search: bool,
-        thinking_budget: i64
+        thinking_budget: Option<i64>,
+        /// Number of items of each array of the response schema, at least and at most
+        item_bounds: Option<(usize, usize)>,
     },
     Caching(CachingRequest),
 }
@@ -44,6 +53,104 @@ impl GeminiNetworkClient {
 
 
 
+/// Builds the body of a Gemini generateContent request
+///
+/// Arguments:
+///
+/// * `prompt` - User prompt
+/// * `cache_name` - Optional cache name
+/// * `use_url_context` - Whether to use URL context tool
+/// * `use_google_search` - Whether to use Google search tool
+/// * `thinking_budget` - Thinking budget for the request, the thinking configuration is left out when None
+/// * `item_bounds` - Number of items of each array of the response schema, at least and at most, unbounded when None
+///
+pub fn build_generate_request(
+    prompt: &str,
+    cache_name: Option<String>,
+    use_url_context: bool,
+    use_google_search: bool,
+    thinking_budget: Option<i64>,
+    item_bounds: Option<(usize, usize)>) -> GeminiRequest {
+
+    let mut tools = vec![];
+
+    if use_url_context {
+        tools.push(
+            Tool {
+                url_context: Some(UrlContextTool {}),
+                google_search: None,
+            }
+        );
+    }
+    if use_google_search {
+        tools.push(
+            Tool {
+                url_context: None,
+                google_search: Some(GoogleSearchTool {}),
+            }
+        );
+    }
+
+    // Construct the Gemini API request
+    GeminiRequest {
+        contents: vec![
+            Content {
+                role: Some(String::from("user")),
+                parts: vec![
+                    Part {
+                        text: Some(prompt.to_string()),
+                        inline_data: None,
+                        file_data: None,
+                        video_metadata: None,
+                    }
+                ]
+            }
+        ],
+
+        cached_content: cache_name,
+        system_instruction: None,
+        tools: Some(tools),
+        safety_settings: None,
+        generation_config: Some(GenerationConfig {
+            temperature: Some(1.0),
+            top_p: None,
+            top_k: None,
+            candidate_count: None,
+            max_output_tokens: None,
+            presence_penalty: None,
+            frequency_penalty: None,
+            stop_sequences: None,
+            response_mime_type: cfg!(feature = "output-json")
+                .then(|| String::from("application/json")),
+            response_schema: if cfg!(feature = "output-json") {
+                Some(ResponseSchema {
+                schema_type: String::from("object"),
+                additional_properties: AdditionalProperties {
+                    value_type: String::from("array"),
+                    items: Items {
+                        item_type: String::from("string"),
+                    },
+                    min_items: item_bounds.map(|(min, _)| min),
+                    max_items: item_bounds.map(|(_, max)| max),
+                },
+            })
+            } else {
+                None
+            },
+
+            seed: Some(generate_seed() as i32),
+            response_logprobs: None,
+            logprobs: None,
+            audio_timestamp: None,
+            thinking_config: thinking_budget.map(|thinking_budget| ThinkingConfig {
+                thinking_budget: Some(thinking_budget),
+            }),
+            disable_nvcc: None,
+        }),
+        labels: None,
+    }
+}
+
 /// Generates a random seed for the LLM request
 pub fn generate_seed() -> i32 {
     let mut rng = rand::rng();
@@ -51,14 +158,14 @@ pub fn generate_seed() -> i32 {
 }
 
 impl GeminiApiCall {
-    pub async fn process_request(&self, client: &Client) -> Result<ApiResponse, Box<dyn std::error::Error>> {
+    pub async fn process_request(&self, client: &dyn HttpClient) -> Result<ApiResponse, LlmError> {
     
         match self {
-            GeminiApiCall::Generate{model, prompt, cache_name, use_url_context, use_google_search, thinking_budget} => {
-                GeminiApiCall::generate_chat_completion(&self, client, model, prompt, cache_name.clone(), *use_url_context, *use_google_search, *thinking_budget).await
+            GeminiApiCall::Generate{model, prompt, cache_name, use_url_context, use_google_search, thinking_budget, item_bounds} => {
+                GeminiApiCall::generate_chat_completion(&self, client, model, prompt, cache_name.clone(), *use_url_context, *use_google_search, *thinking_budget, *item_bounds).await
             }
             GeminiApiCall::Caching(_caching_request) => {
-                Err("Caching API call not implemented".into())
+                Err(LlmError::Network("Caching API call not implemented".to_string()))
             }
         }
     }
@@ -67,137 +174,59 @@ impl GeminiApiCall {
     /// 
     /// Arguments:
     ///
-    /// * `client` - Reqwest HTTP client
+    /// * `client` - HTTP client, adding the API key to the request
     /// * `model` - Model name to use
     /// * `prompt` - User prompt
     /// * `cache_name` - Optional cache name
     /// * `use_url_context` - Whether to use URL context tool
     /// * `use_google_search` - Whether to use Google search tool
-    /// * `thinking_budget` - Thinking budget for the request
+    /// * `thinking_budget` - Optional thinking budget for the request
+    /// * `item_bounds` - Number of items of each array of the response schema, at least and at most
     /// 
     /// Returns:
     /// * `ApiResponse` - Parsed response from the Gemini API
     ///
     async fn generate_chat_completion(&self,
-        client: &Client,
+        client: &dyn HttpClient,
         model: &str,
         prompt: &str,
         cache_name: Option<String>,
         use_url_context: bool,
         use_google_search: bool,
-        thinking_budget: i64) 
-        -> Result<ApiResponse, Box<dyn std::error::Error>> {
+        thinking_budget: Option<i64>,
+        item_bounds: Option<(usize, usize)>)
+        -> Result<ApiResponse, LlmError> {
 
-        static API_BASE:  &str = "aiplatform.googleapis.com";
+        let api_base = std::env::var(GEMINI_ENDPOINT_ENV).unwrap_or_else(|_| GEMINI_ENDPOINT.to_string());
 
         let url = format!(
-            "https://{}/v1/publishers/google/models/{}:generateContent?key={}",
-            API_BASE,                    // e.g. "us-central1-aiplatform.googleapis.com"
+            "{}/v1/publishers/google/models/{}:generateContent",
+            api_base.trim_end_matches('/'), // e.g. "https://us-central1-aiplatform.googleapis.com"
             model,                           // e.g. "gemini-2.5-flash"
-            get_api_key()                    // Your API key
         );
     
-        let mut tools = vec![];
-
-        if use_url_context {
-            tools.push(
-                Tool {
-                    url_context: Some(UrlContextTool {}),
-                    google_search: None,
-                }
-            );
-        }
-        if use_google_search {
-            tools.push(
-                Tool {
-                    url_context: None,
-                    google_search: Some(GoogleSearchTool {}),
-                }
-            );
-        }
-
-        // Construct the Gemini API request
-        let request = GeminiRequest {
-            contents: vec![
-                Content {
-                    role: Some(String::from("user")),
-                    parts: vec![
-                        Part {
-                            text: Some(prompt.to_string()),
-                            inline_data: None,
-                            file_data: None,
-                            video_metadata: None,
-                        }
-                    ]
-                }
-            ],
-
-            cached_content: cache_name.clone(),
-            system_instruction: None,
-            tools: Some(tools),
-            safety_settings: None,
-            generation_config: Some(GenerationConfig {
-                temperature: Some(1.0),
-                top_p: None,
-                top_k: None,
-                candidate_count: None,
-                max_output_tokens: None,
-                presence_penalty: None,
-                frequency_penalty: None,
-                stop_sequences: None,
-                response_mime_type: cfg!(feature = "output-json")
-                    .then(|| String::from("application/json")),
-                response_schema: if cfg!(feature = "output-json") {
-                    Some(ResponseSchema {
-                    schema_type: String::from("object"),
-                    additional_properties: AdditionalProperties {
-                        value_type: String::from("array"),
-                        items: Items {
-                            item_type: String::from("string"),
-                        },
-                    },
-                })
-                } else {
-                    None
-                },
-
-                seed: Some(generate_seed() as i32),
-                response_logprobs: None,
-                logprobs: None,
-                audio_timestamp: None,
-                thinking_config: Some(ThinkingConfig {
-                    thinking_budget: Some(thinking_budget as i64),
-                }),
-                disable_nvcc: None,
-            }),
-            labels: None,
+        let request = build_generate_request(prompt, cache_name, use_url_context, use_google_search, thinking_budget, item_bounds);
+        let request = HttpRequest::new(HttpMethod::Post, url)
+            .with_auth(HttpAuth::GeminiApiKey)
+            .with_json(&request)?;
+
+        let resp = match client.send(request).await {
+            Err(e) => {
+                eprintln!("Error sending Gemini API request: {}", e);
+                return Err(e);
+            },
+            Ok(resp) => resp,
         };
 
-        let resp = match client
-            .post(&url)
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await {
-                Err(e) => {
-                    eprintln!("Error sending Gemini API request: {}", e);
-                    return Err(Box::new(e));
-                },
-                Ok(resp) => resp,
-            };
-
-        let status = resp.status();
-        let body = resp.text().await?;
-
         // If the API returned an error status, print the body
-        if !status.is_success() {
-            eprintln!("Gemini API request failed. Status: {}", status);
-            eprintln!("Response body: {}", body);
-            return Err(format!("Gemini API error: {}", status).into());
+        if !resp.is_success() {
+            eprintln!("Gemini API request failed. Status: {}", resp.status);
+            eprintln!("Response body: {}", resp.body);
+            return Err(LlmError::from_status(resp.status, &resp.body));
         }
 
-        //println!("Gemini API response body: {}", body);
-        let result: ApiResponse = serde_json::from_str(&body)?;
+        //println!("Gemini API response body: {}", resp.body);
+        let result: ApiResponse = serde_json::from_str(&resp.body)?;
 
         return Ok(result);
     }
@@ -227,9 +256,11 @@ pub struct GeminiRequest {
     pub labels: Option<HashMap<String, String>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Content {
     pub role: Option<String>,
+    /// Left out of the candidates blocked by the safety filters
+    #[serde(default)]
     pub parts: Vec<Part>,
 }
 
@@ -390,6 +421,7 @@ pub struct GenerationConfig {
     #[serde(rename = "audioTimestamp")]
     pub audio_timestamp: Option<bool>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "thinkingConfig")]
     pub thinking_config: Option<ThinkingConfig>,
 
@@ -413,6 +445,14 @@ pub struct AdditionalProperties {
     pub value_type: String,
 
     pub items: Items,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "minItems")]
+    pub min_items: Option<usize>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "maxItems")]
+    pub max_items: Option<usize>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -450,6 +490,8 @@ pub struct ApiResponse {
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Candidate {
+    /// Left out of the candidates blocked by the safety filters
+    #[serde(default)]
     pub content: Content,
 
     #[serde(rename = "finishReason")]
@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use config::Config;
+use utils::category::check_category_validity;
+use utils::urls::key_host;
+use crate::core::LLMCommand;
+use crate::core::metrics::ChunkMetrics;
+use crate::core::result::LLMRunResult;
+
+/// Model name selecting the heuristic provider, `"model": ["heuristic"]` in the configuration
+pub const HEURISTIC_MODEL: &str = "heuristic";
+
+/// Confidence given to every heuristic classification, it is not measured
+pub const HEURISTIC_CONFIDENCE: f64 = 0.5;
+
+/// Category of the domains no rule matched
+const FALLBACK_CATEGORY: &str = "Autres";
+
+/// Second level labels that are part of the public suffix under a ccTLD, e.g. `co.uk` or `gouv.fr`
+const SECOND_LEVEL_SUFFIXES: &[&str] = &["co", "com", "net", "org", "gouv", "gov", "gv", "ac", "edu", "admin"];
+
+/// Categories hinted by the public suffix, matched on the last labels of the domain
+const SUFFIX_HINTS: &[(&str, &str)] = &[
+    ("gouv.fr", "Gouvernement / Administration"),
+    ("gov", "Gouvernement / Administration"),
+    ("gov.uk", "Gouvernement / Administration"),
+    ("gv.at", "Gouvernement / Administration"),
+    ("admin.ch", "Gouvernement / Administration"),
+    ("mil", "Gouvernement / Administration"),
+    ("edu", "Enseignement"),
+    ("ac.uk", "Enseignement"),
+    ("bank", "Banques / Services financiers / Investissement"),
+    ("shop", "E-Commerce / Enchères"),
+    ("store", "E-Commerce / Enchères"),
+    ("news", "Médias / Actualités"),
+    ("xxx", "Pornographie / Nudité / Images à caractère sexuel"),
+    ("porn", "Pornographie / Nudité / Images à caractère sexuel"),
+    ("adult", "Pornographie / Nudité / Images à caractère sexuel"),
+];
+
+/// Keywords of each category, matched against the labels of the domain without its public suffix.
+/// Keywords of 3 characters or less must be a whole label, longer ones may appear anywhere in a label.
+const KEYWORDS: &[(&str, &[&str])] = &[
+    ("Pornographie / Nudité / Images à caractère sexuel", &["porn", "xxx", "sex", "xvideo", "xhamster", "hentai", "adult"]),
+    ("Banques / Services financiers / Investissement", &["bank", "banque", "credit", "bourse", "finance", "invest", "paypal", "caisse", "epargne", "bnpparibas", "boursorama"]),
+    ("E-Commerce / Enchères", &["shop", "store", "boutique", "amazon", "ebay", "cdiscount", "fnac", "aliexpress", "achat", "vente"]),
+    ("Email", &["mail", "smtp", "imap", "pop3", "outlook"]),
+    ("Hebergement web / FAI", &["cdn", "akamai", "cloudfront", "cloudflare", "fastly", "hosting", "ovh", "static"]),
+    ("Médias / Actualités", &["news", "actu", "journal", "presse", "lemonde", "lefigaro", "liberation", "bbc", "cnn", "francetvinfo"]),
+    ("Moteur de recherche", &["google", "bing", "qwant", "duckduckgo", "ecosia", "yahoo", "search"]),
+    ("Réseaux sociaux", &["facebook", "twitter", "instagram", "linkedin", "tiktok", "snapchat", "pinterest"]),
+    ("Chat / Communication", &["whatsapp", "telegram", "discord", "slack", "skype", "zoom", "teams"]),
+    ("Streaming / Télévision / Radio", &["youtube", "netflix", "twitch", "dailymotion", "spotify", "deezer", "stream", "radio", "tv"]),
+    ("Blogs / Forums", &["blog", "forum", "reddit", "wordpress"]),
+    ("Jeux d'argent", &["casino", "poker", "betclic", "winamax", "pmu", "fdj", "bet"]),
+    ("Santé", &["sante", "health", "doctolib", "ameli", "pharma", "hopital", "clinique"]),
+    ("Emploi", &["emploi", "recrut", "indeed", "career", "jobs", "job"]),
+    ("Enseignement", &["univ", "ecole", "school", "college", "lycee", "campus"]),
+    ("Gouvernement / Administration", &["gouv", "impots", "prefecture", "mairie", "service-public"]),
+    ("Voyage / Tourisme / Sortie", &["travel", "voyage", "booking", "airbnb", "hotel", "tripadvisor", "sncf", "airfrance"]),
+    ("Intelligence artificielle", &["openai", "chatgpt", "anthropic", "mistral", "huggingface"]),
+    ("Traduction", &["translate", "deepl", "traduc"]),
+    ("VPNs / Filtres / Proxies / Redirection", &["vpn", "proxy"]),
+    ("Hébergement de fichiers", &["dropbox", "wetransfer", "mega"]),
+    ("Publicité", &["doubleclick", "adservice", "advert", "adsystem", "ads"]),
+    ("Itinéraires / Cartographie", &["maps", "waze", "mappy", "viamichelin"]),
+    ("Petites annonces", &["leboncoin", "annonce"]),
+    ("Immobilier", &["immo", "seloger", "realestate"]),
+    ("Voitures / Mécaniques", &["auto", "moto", "garage"]),
+];
+
+/// Categories of a domain guessed by the heuristic provider
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeuristicClassification {
+    /// Categories, most specific first, never empty
+    pub categories: Vec<&'static str>,
+    /// Fixed pseudo-confidence, see [`HEURISTIC_CONFIDENCE`]
+    pub confidence: f64,
+}
+
+/// Whether `model` selects the heuristic provider instead of an LLM
+pub fn is_heuristic_model(model: &str) -> bool {
+    model == HEURISTIC_MODEL
+}
+
+/// Splits `domain` into its labels and the number of trailing labels forming its public suffix
+fn split_suffix(domain: &str) -> (Vec<&str>, usize) {
+    let labels = domain.split('.').filter(|label| !label.is_empty()).collect::<Vec<&str>>();
+    let suffix_len = match labels.as_slice() {
+        [.., second, tld] if labels.len() > 2 && tld.len() == 2 && SECOND_LEVEL_SUFFIXES.contains(second) => 2,
+        [_, _, ..] => 1,
+        _ => 0,
+    };
+    (labels, suffix_len)
+}
+
+/// Category inherited from the closest dictionary entry among `domain` and its parent domains
+fn inherited_category(dictionary: &HashMap<String, String>, labels: &[&str], suffix_len: usize) -> Option<&'static str> {
+    (0..labels.len().saturating_sub(suffix_len))
+        .map(|start| labels[start..].join("."))
+        .find_map(|parent| dictionary.get(&parent).and_then(|category| check_category_validity(category)))
+}
+
+/// Whether `keyword` matches one of `labels`, see [`KEYWORDS`]
+fn keyword_matches(keyword: &str, labels: &[&str]) -> bool {
+    if keyword.len() <= 3 {
+        labels.iter().any(|label| label.split('-').any(|part| part == keyword))
+    } else {
+        labels.iter().any(|label| label.contains(keyword))
+    }
+}
+
+/// Classifies `domain` offline, from the dictionary, its public suffix and keywords of its labels
+///
+/// # Arguments
+///
+/// * `domain` - Domain to classify, or `host/path` key in URL mode where only the host is used
+/// * `dictionary` - Optional dictionary, the category of the closest listed parent domain comes first
+pub fn heuristic_classify(domain: &str, dictionary: Option<&HashMap<String, String>>) -> HeuristicClassification {
+    let domain = key_host(domain.trim()).trim_end_matches('.').to_lowercase();
+    let (labels, suffix_len) = split_suffix(&domain);
+    let mut categories = Vec::new();
+
+    if let Some(category) = dictionary.and_then(|dictionary| inherited_category(dictionary, &labels, suffix_len)) {
+        categories.push(category);
+    }
+
+    for (suffix, category) in SUFFIX_HINTS {
+        if domain.ends_with(&format!(".{}", suffix)) && !categories.contains(category) {
+            categories.push(category);
+        }
+    }
+
+    let names = &labels[..labels.len() - suffix_len];
+    for (category, keywords) in KEYWORDS {
+        if !categories.contains(category) && keywords.iter().any(|keyword| keyword_matches(keyword, names)) {
+            categories.push(category);
+        }
+    }
+
+    if categories.is_empty() {
+        categories.push(FALLBACK_CATEGORY);
+    }
+
+    HeuristicClassification { categories, confidence: HEURISTIC_CONFIDENCE }
+}
+
+/// French and English descriptions of a domain from its heuristic categories
+fn heuristic_describe(classification: &HeuristicClassification) -> HashMap<&'static str, String> {
+    let categories = classification.categories.join(", ");
+    HashMap::from([
+        ("description_fr", format!("Domaine classé par heuristique locale : {}", categories)),
+        ("description_en", format!("Domain classified by local heuristic: {}", categories)),
+    ])
+}
+
+/// Runs `command` on `domains` with the heuristic provider: no request, no cost and no latency.
+/// Anonymization does not apply, the domains never leave the machine.
+///
+/// # Arguments
+///
+/// * `domains` - Domain names to process
+/// * `config` - Configuration, giving the chunk size and the number of categories kept per domain
+/// * `command` - Command to run
+/// * `dictionary` - Optional dictionary the categories are inherited from
+pub fn heuristic_runtime(
+    domains: Vec<String>,
+    config: &Config,
+    command: &LLMCommand,
+    dictionary: Option<&HashMap<String, String>>,
+) -> LLMRunResult {
+    let mut result = LLMRunResult::new();
+
+    // Chunked as by the LLM runtime, so the chunk reports read the same
+    for (id, chunk) in domains.chunks(config.chunk_size.max(1)).enumerate() {
+        for domain in chunk {
+            let mut classification = heuristic_classify(domain, dictionary);
+            match command {
+                LLMCommand::CategorizeDomains => {
+                    classification.categories.truncate(config.max_domain_propositions.max(1));
+                    result.categories.insert(domain.clone(), classification.categories);
+                },
+                LLMCommand::DescribeDomains => {
+                    result.descriptions.insert(domain.clone(), heuristic_describe(&classification));
+                },
+            }
+        }
+        result.processed.fetch_add(chunk.len(), Ordering::Relaxed);
+        result.chunk_metrics.push(ChunkMetrics {
+            thread_id: id,
+            domains: chunk.to_vec(),
+            latency: Duration::ZERO,
+            attempts: 1,
+        });
+    }
+
+    println!("Heuristic runtime completed for total domains: {}", domains.len());
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn categories(domain: &str) -> Vec<&'static str> {
+        heuristic_classify(domain, None).categories
+    }
+
+    #[test]
+    fn test_well_known_domains() {
+        let expected = [
+            ("www.bnpparibas.com", "Banques / Services financiers / Investissement"),
+            ("www.credit-agricole.fr", "Banques / Services financiers / Investissement"),
+            ("www.paypal.com", "Banques / Services financiers / Investissement"),
+            ("www.amazon.fr", "E-Commerce / Enchères"),
+            ("shop.example.com", "E-Commerce / Enchères"),
+            ("mail.google.com", "Email"),
+            ("outlook.live.com", "Email"),
+            ("d1234.cloudfront.net", "Hebergement web / FAI"),
+            ("cdn.example.org", "Hebergement web / FAI"),
+            ("www.pornhub.com", "Pornographie / Nudité / Images à caractère sexuel"),
+            ("www.lemonde.fr", "Médias / Actualités"),
+            ("www.bbc.co.uk", "Médias / Actualités"),
+            ("www.google.com", "Moteur de recherche"),
+            ("www.facebook.com", "Réseaux sociaux"),
+            ("www.youtube.com", "Streaming / Télévision / Radio"),
+            ("www.impots.gouv.fr", "Gouvernement / Administration"),
+            ("www.ox.ac.uk", "Enseignement"),
+            ("chatgpt.com", "Intelligence artificielle"),
+            ("www.deepl.com", "Traduction"),
+            ("www.leboncoin.fr", "Petites annonces"),
+        ];
+
+        for (domain, category) in expected {
+            assert_eq!(categories(domain)[0], category, "first category of {}", domain);
+        }
+    }
+
+    #[test]
+    fn test_tables_use_valid_categories() {
+        let categories = SUFFIX_HINTS.iter().map(|(_, category)| category)
+            .chain(KEYWORDS.iter().map(|(category, _)| category))
+            .chain(std::iter::once(&FALLBACK_CATEGORY));
+        for category in categories {
+            assert!(check_category_validity(category).is_some(), "{}", category);
+        }
+    }
+
+    #[test]
+    fn test_categories_are_valid_and_confidence_is_fixed() {
+        for domain in ["webmail.free.fr", "www.example.com", "a.b.c.d.example.bank", "localhost", ""] {
+            let classification = heuristic_classify(domain, None);
+            assert!(!classification.categories.is_empty());
+            assert_eq!(classification.confidence, HEURISTIC_CONFIDENCE);
+            for category in classification.categories {
+                assert_eq!(check_category_validity(category), Some(category), "{} for {}", category, domain);
+            }
+        }
+    }
+
+    #[test]
+    fn test_short_keywords_match_whole_labels_only() {
+        assert_eq!(categories("www.alphabet.com"), vec!["Autres"]);
+        assert_eq!(categories("bet.example.com"), vec!["Jeux d'argent"]);
+        assert_eq!(categories("www.my-tv.fr"), vec!["Streaming / Télévision / Radio"]);
+    }
+
+    #[test]
+    fn test_public_suffix_is_not_matched_as_keyword() {
+        // "tv" and "co" are suffixes here, not names
+        assert_eq!(categories("www.example.tv"), vec!["Autres"]);
+        assert_eq!(split_suffix("www.example.co.uk"), (vec!["www", "example", "co", "uk"], 2));
+        assert_eq!(split_suffix("example.com"), (vec!["example", "com"], 1));
+        assert_eq!(split_suffix("localhost"), (vec!["localhost"], 0));
+    }
+
+    #[test]
+    fn test_dictionary_category_is_inherited_by_subdomains() {
+        let dictionary = HashMap::from([
+            ("example.com".to_string(), "Santé".to_string()),
+            ("shop.example.com".to_string(), "Not a category".to_string()),
+        ]);
+
+        let classification = heuristic_classify("a.shop.example.com", Some(&dictionary));
+        assert_eq!(classification.categories, vec!["Santé", "E-Commerce / Enchères"]);
+        // The public suffix alone is never looked up
+        let dictionary = HashMap::from([("com".to_string(), "Santé".to_string())]);
+        assert_eq!(heuristic_classify("www.example.com", Some(&dictionary)).categories, vec!["Autres"]);
+    }
+
+    #[test]
+    fn test_url_keys_are_classified_by_host() {
+        assert_eq!(categories("mail.google.com/inbox"), categories("mail.google.com"));
+        // The path is not matched as keyword
+        assert_eq!(categories("www.example.com/bet"), vec!["Autres"]);
+
+        let dictionary = HashMap::from([("example.com".to_string(), "Santé".to_string())]);
+        assert_eq!(heuristic_classify("www.example.com/shop", Some(&dictionary)).categories, vec!["Santé"]);
+    }
+
+    #[test]
+    fn test_runtime_fills_the_result_without_cost() {
+        let config = Config { chunk_size: 2, max_domain_propositions: 1, ..Config::default() };
+        let domains = vec!["mail.google.com".to_string(), "www.lemonde.fr".to_string(), "www.example.com".to_string()];
+
+        let result = heuristic_runtime(domains.clone(), &config, &LLMCommand::CategorizeDomains, None);
+        assert_eq!(result.processed.load(Ordering::Relaxed), 3);
+        assert_eq!(result.failed.load(Ordering::Relaxed), 0);
+        assert_eq!(result.cost.load(Ordering::Relaxed), 0.0);
+        assert_eq!(result.categories["mail.google.com"], vec!["Email"]);
+        assert_eq!(result.chunk_metrics.len(), 2);
+        assert!(result.chunk_metrics.iter().all(|chunk| chunk.latency == Duration::ZERO));
+
+        let result = heuristic_runtime(domains, &config, &LLMCommand::DescribeDomains, None);
+        assert_eq!(result.descriptions.len(), 3);
+        assert!(result.descriptions["www.lemonde.fr"]["description_en"].contains("Médias / Actualités"));
+    }
+}
@@ -0,0 +1,163 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use utils::env::get_api_key;
+use crate::error::LlmError;
+use crate::providers::gemini::caching::get_gcloud_access_token;
+
+/// Future of a request sent by an [`HttpClient`]
+pub type HttpFuture<'a> = Pin<Box<dyn Future<Output = Result<HttpResponse, LlmError>> + Send + 'a>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HttpMethod {
+    Get,
+    Post,
+    Patch,
+    Delete,
+}
+
+/// Credentials of a request, added by the client when it is sent: requests never hold a secret, neither do the
+/// fixtures recording them
+#[derive(Debug, Clone, PartialEq)]
+pub enum HttpAuth {
+    None,
+    /// Gemini API key, sent as the `key` query parameter
+    GeminiApiKey,
+    /// Access token of `gcloud auth print-access-token`, sent as a bearer token
+    Gcloud,
+    /// Bearer token
+    Bearer(String),
+}
+
+impl HttpAuth {
+    /// Kind of credentials, without the secret
+    pub fn kind(&self) -> &'static str {
+        match self {
+            HttpAuth::None => "none",
+            HttpAuth::GeminiApiKey => "gemini_api_key",
+            HttpAuth::Gcloud => "gcloud",
+            HttpAuth::Bearer(_) => "bearer",
+        }
+    }
+}
+
+/// JSON request of a provider
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: HttpMethod,
+    pub url: String,
+    pub auth: HttpAuth,
+    pub body: Option<serde_json::Value>,
+}
+
+impl HttpRequest {
+    /// Request without credentials nor body
+    pub fn new(method: HttpMethod, url: impl Into<String>) -> Self {
+        Self { method, url: url.into(), auth: HttpAuth::None, body: None }
+    }
+
+    pub fn with_auth(mut self, auth: HttpAuth) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Sets `body` as the JSON body of the request
+    pub fn with_json<T: Serialize>(mut self, body: &T) -> Result<Self, LlmError> {
+        self.body = Some(serde_json::to_value(body)?);
+        Ok(self)
+    }
+}
+
+/// Status and body of a response, whatever the status
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: String,
+    /// Delay of the `Retry-After` header, sent with the 429 responses
+    pub retry_after: Option<Duration>,
+}
+
+impl HttpResponse {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Parses the body of a successful response, the error matching the status otherwise
+    pub fn json<T: serde::de::DeserializeOwned>(&self) -> Result<T, LlmError> {
+        if !self.is_success() {
+            return Err(LlmError::from_status(self.status, &self.body));
+        }
+        Ok(serde_json::from_str(&self.body)?)
+    }
+}
+
+/// Sends the requests of the providers: `reqwest` for the runs, recorded exchanges in the tests
+pub trait HttpClient: Sync {
+    fn send(&self, request: HttpRequest) -> HttpFuture<'_>;
+}
+
+impl HttpClient for reqwest::Client {
+    fn send(&self, request: HttpRequest) -> HttpFuture<'_> {
+        Box::pin(async move {
+            let mut url = request.url;
+            if request.auth == HttpAuth::GeminiApiKey {
+                let separator = if url.contains('?') { '&' } else { '?' };
+                url = format!("{}{}key={}", url, separator, get_api_key());
+            }
+
+            let mut builder = match request.method {
+                HttpMethod::Get => self.get(&url),
+                HttpMethod::Post => self.post(&url),
+                HttpMethod::Patch => self.patch(&url),
+                HttpMethod::Delete => self.delete(&url),
+            }
+            .header("Content-Type", "application/json");
+
+            match request.auth {
+                HttpAuth::None | HttpAuth::GeminiApiKey => {}
+                HttpAuth::Gcloud => {
+                    let token = get_gcloud_access_token().map_err(|e| LlmError::Auth(e.to_string()))?;
+                    builder = builder.bearer_auth(token);
+                }
+                HttpAuth::Bearer(token) => builder = builder.bearer_auth(token),
+            }
+            if let Some(body) = &request.body {
+                builder = builder.json(body);
+            }
+
+            let resp = builder.send().await?;
+            let status = resp.status().as_u16();
+            let retry_after = resp.headers().get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(parse_retry_after);
+            let body = resp.text().await?;
+            Ok(HttpResponse { status, body, retry_after })
+        })
+    }
+}
+
+/// Delay of a `Retry-After` header, given in seconds or as the HTTP date to retry at
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some((date.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after(" 0 "), Some(Duration::ZERO));
+        // A date already past means no wait at all
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+}
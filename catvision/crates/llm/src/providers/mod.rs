@@ -1 +1,64 @@
-pub mod gemini;
\ No newline at end of file
+pub mod gemini;
+pub mod heuristic;
+pub mod http;
+pub mod openai;
+#[cfg(test)]
+pub mod fixtures;
+
+use std::future::Future;
+use atomic_float::AtomicF64;
+use config::Config;
+use crate::core::LLMCommand;
+use crate::core::responses::ResponseStore;
+use crate::core::result::LLMRunResult;
+use crate::providers::http::HttpClient;
+
+pub use crate::core::result::ProviderResult;
+
+/// What a request shares with the run: its HTTP client, its model, its cached content, the response store and the result of its chunk
+pub struct ProviderCall<'a> {
+    pub client: &'a dyn HttpClient,
+    /// Model of the attempt, one of `Config.model`: the first one, then the fallbacks once it keeps failing
+    pub model: &'a str,
+    /// Cached content returned by [`LLMProvider::prepare_cache`], the rules are then left out of the prompt
+    pub cache_name: &'a Option<String>,
+    /// Store receiving the response before it is parsed
+    pub store: &'a ResponseStore,
+    /// Result of the chunk, receiving the cost and the parsed domains
+    pub result: &'a mut LLMRunResult,
+}
+
+/// API the LLM requests of a run are sent to, selected by the `provider` field of the configuration.
+///
+/// The runtime builds the prompts, chunks the domains, retries and merges the results; a provider only sends one
+/// request, stores its raw response, accounts its cost and parses its text with [`LLMRunResult::add_model_output`].
+pub trait LLMProvider: Sync {
+    /// Name of the provider, as written in the configuration
+    fn name(&self) -> &'static str;
+
+    /// Creates or refreshes the cached content of the run, before each batch of chunks
+    ///
+    /// # Returns
+    /// * The name of the cached content, None when the provider or the configuration does not use caching
+    fn prepare_cache(&self, _config: &Config, _cost: &AtomicF64) -> impl Future<Output = Result<Option<String>, Box<dyn std::error::Error + Send + Sync>>> {
+        async { Ok(None) }
+    }
+
+    /// Sends the categorization request of `domains`
+    ///
+    /// # Returns
+    /// * `Result<Vec<String>, LlmError>` - Domains the response left without categories, or an error
+    fn classify(&self, domains: Vec<String>, prompt: String, config: &Config, call: ProviderCall<'_>) -> impl Future<Output = ProviderResult> + Send;
+
+    /// Sends the description request of `domains`
+    ///
+    /// # Returns
+    /// * `Result<Vec<String>, LlmError>` - Domains the response left without descriptions, or an error
+    fn describe(&self, domains: Vec<String>, prompt: String, config: &Config, call: ProviderCall<'_>) -> impl Future<Output = ProviderResult> + Send;
+
+    /// Parses a response stored by a previous run into `result`, without accounting its cost
+    ///
+    /// # Returns
+    /// * `Result<Vec<String>, LlmError>` - Domains left without a result, or an error if the response is not one of this provider
+    fn parse_stored(&self, domains: Vec<String>, response: serde_json::Value, result: &mut LLMRunResult, command: &LLMCommand) -> ProviderResult;
+}
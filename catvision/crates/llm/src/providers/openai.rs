@@ -0,0 +1,398 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::Ordering;
+use tracing::Instrument;
+use config::Config;
+use crate::core::LLMCommand;
+use crate::core::result::{LLMRunResult, ProviderResult};
+use crate::error::LlmError;
+use crate::providers::{LLMProvider, ProviderCall};
+use crate::providers::http::{HttpAuth, HttpClient, HttpMethod, HttpRequest};
+use crate::providers::gemini::network::generate_seed;
+
+/// Environment variable holding the API key of the OpenAI-compatible server, no key is sent when unset
+pub const OPENAI_API_KEY_ENV: &str = "OPENAI_API_KEY";
+
+/// Finish reason reported when the answer was blocked by the content filter of the server
+const CONTENT_FILTER_FINISH_REASON: &str = "content_filter";
+
+/// Exchange rate applied to the dollar price of the model, the one of the Gemini billing
+const EUR_RATE: f64 = 0.92;
+
+/// OpenAI `/v1/chat/completions` API, also served by vLLM, Ollama and other compatible servers at `openai_base_url`.
+/// There is no cached content, every request carries the full prompt.
+pub struct OpenAiProvider {
+    api_key: Option<String>,
+}
+
+impl OpenAiProvider {
+    /// Provider sending `api_key` as a bearer token, none for servers without authentication
+    pub fn new(api_key: Option<String>) -> Self {
+        Self { api_key }
+    }
+
+    /// Provider with the API key of [`OPENAI_API_KEY_ENV`]
+    pub fn from_env() -> Self {
+        Self::new(std::env::var(OPENAI_API_KEY_ENV).ok().filter(|key| !key.is_empty()))
+    }
+
+    /// Sends the request, stores the raw response, accounts its cost and parses it into the result of the chunk
+    async fn generate(&self, domains: Vec<String>, prompt: String, config: &Config, command: &LLMCommand, call: ProviderCall<'_>) -> ProviderResult {
+        let request = ChatCompletionRequest::new(call.model, prompt);
+
+        let response = self
+            .send(call.client, &config.openai_base_url, &request)
+            .instrument(tracing::info_span!("http_call"))
+            .await?;
+
+        // Stored before parsing, a response the parser rejects can be reparsed once the parser is fixed
+        if let Err(e) = call.store.save(command, &domains, &response) {
+            eprintln!("Failed to store the LLM response in {}: {}", call.store.dir().display(), e);
+        }
+
+        openai_handle_response(domains, &response, config.openai_usd_per_1k_tokens, call.result, command)
+    }
+
+    /// Posts `request` to the chat completions endpoint of `base_url`
+    /// # Returns
+    /// * `Result<ChatCompletionResponse, LlmError>` - Parsed response, or the error matching the HTTP status
+    async fn send(&self, client: &dyn HttpClient, base_url: &str, request: &ChatCompletionRequest) -> Result<ChatCompletionResponse, LlmError> {
+        let url = format!("{}/v1/chat/completions", base_url.trim_end_matches('/'));
+
+        let auth = match &self.api_key {
+            Some(api_key) => HttpAuth::Bearer(api_key.clone()),
+            None => HttpAuth::None,
+        };
+        let http_request = HttpRequest::new(HttpMethod::Post, url.as_str())
+            .with_auth(auth)
+            .with_json(request)?;
+
+        let resp = match client.send(http_request).await {
+            Err(e) => {
+                eprintln!("Error sending chat completion request to {}: {}", url, e);
+                return Err(e);
+            },
+            Ok(resp) => resp,
+        };
+
+        if !resp.is_success() {
+            eprintln!("Chat completion request failed. Status: {}", resp.status);
+            eprintln!("Response body: {}", resp.body);
+        }
+
+        resp.json()
+    }
+}
+
+impl LLMProvider for OpenAiProvider {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    async fn classify(&self, domains: Vec<String>, prompt: String, config: &Config, call: ProviderCall<'_>) -> ProviderResult {
+        self.generate(domains, prompt, config, &LLMCommand::CategorizeDomains, call).await
+    }
+
+    async fn describe(&self, domains: Vec<String>, prompt: String, config: &Config, call: ProviderCall<'_>) -> ProviderResult {
+        self.generate(domains, prompt, config, &LLMCommand::DescribeDomains, call).await
+    }
+
+    fn parse_stored(&self, domains: Vec<String>, response: serde_json::Value, result: &mut LLMRunResult, command: &LLMCommand) -> ProviderResult {
+        let response: ChatCompletionResponse = serde_json::from_value(response)
+            .map_err(|e| LlmError::Parse(format!("Stored response is not a chat completion: {}", e)))?;
+        openai_parse_response(domains, &response, result, command)
+    }
+}
+
+/// Cost in euros of `tokens` prompt and completion tokens, at `usd_per_1k_tokens`
+pub fn openai_cost_eur(tokens: u64, usd_per_1k_tokens: f64) -> f64 {
+    tokens as f64 / 1000.0 * usd_per_1k_tokens * EUR_RATE
+}
+
+/// Accounts the cost of a chat completion and parses its content into `my_result`
+/// # Arguments
+/// * `domains` - Domain names sent in the request
+/// * `response` - Response returned by the server
+/// * `usd_per_1k_tokens` - Price of the model, zero for a self-hosted server
+/// * `my_result` - Mutable reference to accumulate the results of the chunk
+/// * `command` - Command the request was made for
+/// # Returns
+/// * `Result<Vec<String>, LlmError>` - Domains that still need processing, or an error
+fn openai_handle_response(
+    domains: Vec<String>,
+    response: &ChatCompletionResponse,
+    usd_per_1k_tokens: f64,
+    my_result: &mut LLMRunResult,
+    command: &LLMCommand,
+) -> ProviderResult {
+    let _span = tracing::info_span!("parse").entered();
+
+    if let Some(usage) = &response.usage {
+        my_result.cost.fetch_add(openai_cost_eur(usage.total_tokens, usd_per_1k_tokens), Ordering::Relaxed);
+    }
+
+    openai_parse_response(domains, response, my_result, command)
+}
+
+/// Parses the content of a chat completion into `my_result`, without accounting its cost
+/// # Arguments
+/// * `domains` - Domain names sent in the request
+/// * `response` - Response returned by the server, or loaded from the response store
+/// * `my_result` - Mutable reference to accumulate the results of the chunk
+/// * `command` - Command the request was made for
+/// # Returns
+/// * `Result<Vec<String>, LlmError>` - Domains that still need processing, or an error
+pub fn openai_parse_response(
+    domains: Vec<String>,
+    response: &ChatCompletionResponse,
+    my_result: &mut LLMRunResult,
+    command: &LLMCommand,
+) -> ProviderResult {
+    let choice = match response.choices.first() {
+        Some(choice) => choice,
+        None => return Err(LlmError::Parse("No choices in the response.".to_string())),
+    };
+
+    if choice.finish_reason.as_deref() == Some(CONTENT_FILTER_FINISH_REASON) {
+        return Err(LlmError::SafetyBlocked(CONTENT_FILTER_FINISH_REASON.to_string()));
+    }
+
+    let content = match &choice.message.content {
+        Some(content) => content,
+        None => return Err(LlmError::Parse("No content in the response message.".to_string())),
+    };
+
+    my_result.add_model_output(domains, content, command)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatCompletionRequest {
+    pub model: String,
+    pub messages: Vec<ChatMessage>,
+    pub temperature: f64,
+    pub seed: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+}
+
+impl ChatCompletionRequest {
+    /// Request sending `prompt` as the only user message, with the temperature of the Gemini requests
+    pub fn new(model: &str, prompt: String) -> Self {
+        Self {
+            model: model.to_string(),
+            messages: vec![ChatMessage {
+                role: String::from("user"),
+                content: Some(prompt),
+            }],
+            temperature: 1.0,
+            seed: generate_seed(),
+            response_format: cfg!(feature = "output-json").then(|| ResponseFormat {
+                format_type: String::from("json_object"),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: String,
+    /// Text of the message, null in an answer made only of tool calls
+    pub content: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResponseFormat {
+    #[serde(rename = "type")]
+    pub format_type: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatCompletionResponse {
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    pub choices: Vec<ChatChoice>,
+    /// Token counts, left out by some servers
+    #[serde(default)]
+    pub usage: Option<ChatUsage>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatChoice {
+    pub message: ChatMessage,
+    #[serde(default)]
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatUsage {
+    #[serde(default)]
+    pub prompt_tokens: u64,
+    #[serde(default)]
+    pub completion_tokens: u64,
+    #[serde(default)]
+    pub total_tokens: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::responses::ResponseStore;
+    use reqwest::Client;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    /// Raw request received by the mock server: request line, lowercase headers and body
+    struct ReceivedRequest {
+        request_line: String,
+        headers: Vec<(String, String)>,
+        body: serde_json::Value,
+    }
+
+    /// Serves `responses` in order, one connection each, and sends back the requests received
+    fn mock_server(responses: Vec<(u16, String)>) -> (String, mpsc::Receiver<ReceivedRequest>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            for (status, body) in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut headers = Vec::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let Some((name, value)) = line.trim_end().split_once(':') else { break };
+                    headers.push((name.to_lowercase(), value.trim().to_string()));
+                }
+                let length = headers.iter().find(|(name, _)| name == "content-length").map_or(0, |(_, value)| value.parse().unwrap());
+                let mut request_body = vec![0; length];
+                reader.read_exact(&mut request_body).unwrap();
+
+                let response = format!(
+                    "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status, body.len(), body
+                );
+                reader.get_mut().write_all(response.as_bytes()).unwrap();
+                tx.send(ReceivedRequest {
+                    request_line: request_line.trim_end().to_string(),
+                    headers,
+                    body: serde_json::from_slice(&request_body).unwrap(),
+                }).unwrap();
+            }
+        });
+
+        (base_url, rx)
+    }
+
+    fn completion(content: &str, finish_reason: &str) -> String {
+        serde_json::json!({
+            "id": "chatcmpl-test",
+            "object": "chat.completion",
+            "model": "llama3",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": content },
+                "finish_reason": finish_reason,
+            }],
+            "usage": { "prompt_tokens": 800, "completion_tokens": 200, "total_tokens": 1000 },
+        })
+        .to_string()
+    }
+
+    /// Sends the categorization request of `domains` to `base_url`
+    fn classify(provider: &OpenAiProvider, base_url: &str, domains: &[&str], store: &ResponseStore, result: &mut LLMRunResult) -> ProviderResult {
+        let config = Config {
+            model: vec!["llama3".to_string()],
+            openai_base_url: format!("{}/", base_url),
+            openai_usd_per_1k_tokens: 0.5,
+            ..Config::default()
+        };
+        let domains: Vec<String> = domains.iter().map(|domain| domain.to_string()).collect();
+        let prompt = crate::core::prompt::generate_request_prompt(&domains, &config, &LLMCommand::CategorizeDomains, false);
+        let client = Client::new();
+
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let call = ProviderCall { client: &client, model: &config.model[0], cache_name: &None, store, result };
+            provider.classify(domains, prompt, &config, call).await
+        })
+    }
+
+    #[test]
+    fn test_chat_completion_request_and_response() {
+        let (base_url, requests) = mock_server(vec![(200, completion(r#"{"example.com": ["Enseignement"]}"#, "stop"))]);
+        let store_dir = std::env::temp_dir().join(format!("catvision-openai-responses-{}", std::process::id()));
+        let store = ResponseStore::new(&store_dir);
+        let provider = OpenAiProvider::new(Some("test-key".to_string()));
+        let mut result = LLMRunResult::new();
+
+        let remaining = classify(&provider, &base_url, &["example.com", "missing.com"], &store, &mut result).unwrap();
+        assert_eq!(remaining, vec!["missing.com".to_string()]);
+        assert_eq!(result.categories["example.com"], vec!["Enseignement"]);
+        assert_eq!(result.processed.load(Ordering::Relaxed), 1);
+        assert!((result.cost.load(Ordering::Relaxed) - 0.5 * EUR_RATE).abs() < 1e-9);
+
+        // One user message with the whole prompt, to the chat completions path of the base URL
+        let request = requests.recv().unwrap();
+        assert_eq!(request.request_line, "POST /v1/chat/completions HTTP/1.1");
+        assert!(request.headers.contains(&("authorization".to_string(), "Bearer test-key".to_string())));
+        assert_eq!(request.body["model"], "llama3");
+        assert_eq!(request.body["messages"].as_array().unwrap().len(), 1);
+        assert_eq!(request.body["messages"][0]["role"], "user");
+        let content = request.body["messages"][0]["content"].as_str().unwrap();
+        assert!(content.contains("example.com; missing.com"));
+        assert_eq!(request.body["temperature"], 1.0);
+        assert!(request.body["seed"].is_i64());
+
+        // The raw response is stored, a reparse finds the same categories without any request
+        let domains = vec!["example.com".to_string(), "missing.com".to_string()];
+        let stored: serde_json::Value = store.load(&LLMCommand::CategorizeDomains, &domains).unwrap().unwrap();
+        let mut reparsed = LLMRunResult::new();
+        let remaining = provider.parse_stored(domains, stored, &mut reparsed, &LLMCommand::CategorizeDomains).unwrap();
+        assert_eq!(remaining, vec!["missing.com".to_string()]);
+        assert_eq!(reparsed.categories, result.categories);
+        assert_eq!(reparsed.cost.load(Ordering::Relaxed), 0.0);
+
+        let _ = std::fs::remove_dir_all(store_dir);
+    }
+
+    #[test]
+    fn test_chat_completion_errors() {
+        let (base_url, requests) = mock_server(vec![
+            (200, completion("", "content_filter")),
+            (429, r#"{"error": {"message": "Rate limit reached"}}"#.to_string()),
+            (200, r#"{"choices": []}"#.to_string()),
+        ]);
+        let store_dir = std::env::temp_dir().join(format!("catvision-openai-errors-{}", std::process::id()));
+        let store = ResponseStore::new(&store_dir);
+        // A local server without authentication
+        let provider = OpenAiProvider::new(None);
+        let mut result = LLMRunResult::new();
+
+        let err = classify(&provider, &base_url, &["example.com"], &store, &mut result).unwrap_err();
+        assert_eq!(err, LlmError::SafetyBlocked("content_filter".to_string()));
+        assert!(requests.recv().unwrap().headers.iter().all(|(name, _)| name != "authorization"));
+
+        let err = classify(&provider, &base_url, &["example.com"], &store, &mut result).unwrap_err();
+        assert!(matches!(err, LlmError::RateLimited(_)));
+
+        let err = classify(&provider, &base_url, &["example.com"], &store, &mut result).unwrap_err();
+        assert!(matches!(err, LlmError::Parse(_)));
+        assert_eq!(result.processed.load(Ordering::Relaxed), 0);
+
+        let _ = std::fs::remove_dir_all(store_dir);
+    }
+
+    #[test]
+    fn test_parse_stored_rejects_other_providers() {
+        let gemini = serde_json::json!({ "candidates": [], "usageMetadata": {} });
+        let mut result = LLMRunResult::new();
+        let err = OpenAiProvider::new(None).parse_stored(vec!["example.com".into()], gemini, &mut result, &LLMCommand::CategorizeDomains).unwrap_err();
+        assert!(matches!(err, LlmError::Parse(_)));
+    }
+}
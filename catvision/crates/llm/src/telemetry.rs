@@ -0,0 +1,82 @@
+//! Tracing spans for the run and chunk lifecycle.
+//!
+//! Spans are plain `tracing` spans and cost nothing without a subscriber. With the `otel`
+//! feature, [`init_telemetry`] bridges them to OpenTelemetry and exports them over OTLP.
+
+use tracing::Span;
+
+/// Environment variable read for the OTLP endpoint when none is configured
+pub const OTLP_ENDPOINT_ENV: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+
+/// Root span of a classification or description run
+pub fn run_span(input_file: &str, model: &str, chunk_size: usize) -> Span {
+    tracing::info_span!("catvision.run", input_file, model, chunk_size)
+}
+
+/// Span covering every attempt made for one chunk
+pub fn chunk_span(parent: &Span, thread_id: usize, chunk_size: usize) -> Span {
+    tracing::info_span!(parent: parent, "catvision.chunk", thread_id, chunk_size)
+}
+
+/// Records a retried attempt as an event on the current chunk span
+pub fn record_retry(attempt: usize, error: &dyn std::fmt::Display) {
+    tracing::warn!(attempt, error = %error, "retry");
+}
+
+/// Records a chunk failure as an event on the current chunk span
+pub fn record_failure(error: &dyn std::fmt::Display) {
+    tracing::error!(error = %error, "failure");
+}
+
+#[cfg(feature = "otel")]
+pub use otel::{init_telemetry, TelemetryGuard};
+
+#[cfg(feature = "otel")]
+mod otel {
+    use super::OTLP_ENDPOINT_ENV;
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use opentelemetry_sdk::Resource;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::util::SubscriberInitExt;
+
+    /// Flushes and shuts down the exporter when dropped, keep it alive for the whole run
+    pub struct TelemetryGuard {
+        provider: SdkTracerProvider,
+    }
+
+    impl Drop for TelemetryGuard {
+        fn drop(&mut self) {
+            if let Err(e) = self.provider.shutdown() {
+                eprintln!("Failed to flush OpenTelemetry spans: {}", e);
+            }
+        }
+    }
+
+    /// Installs a global subscriber exporting spans to the OTLP/HTTP `endpoint`,
+    /// falling back to `OTEL_EXPORTER_OTLP_ENDPOINT`.
+    /// # Returns
+    /// * `Ok(None)` if no endpoint is configured, tracing stays disabled
+    pub fn init_telemetry(endpoint: Option<&str>) -> Result<Option<TelemetryGuard>, Box<dyn std::error::Error>> {
+        let endpoint = match endpoint.map(str::to_string).or_else(|| std::env::var(OTLP_ENDPOINT_ENV).ok()) {
+            Some(endpoint) if !endpoint.is_empty() => endpoint,
+            _ => return Ok(None),
+        };
+
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .with_endpoint(endpoint)
+            .build()?;
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(Resource::builder().with_service_name("catvision").build())
+            .build();
+
+        tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("catvision")))
+            .try_init()?;
+
+        Ok(Some(TelemetryGuard { provider }))
+    }
+}
@@ -1,5 +1,5 @@
-use std::{fmt::Display, sync::atomic::{AtomicUsize, Ordering}};
-use utils::seconds_to_pretty;
+use std::{collections::{BTreeMap, HashMap}, fmt::Display, sync::atomic::{AtomicUsize, Ordering}};
+use utils::duration_to_pretty;
 use atomic_float::AtomicF64;
 
 
@@ -13,6 +13,12 @@ pub struct Statistics {
     olfeo_match_count : usize,
     /// Number of LLM matches found at each level
     llm_level_match_count : Vec<usize>,
+    /// Number of LLM propositions agreeing with the mapped Olfeo category at each level
+    olfeo_agreement_count : Vec<usize>,
+    /// Number of domains compared with their mapped Olfeo category
+    olfeo_compared_count : usize,
+    /// Number of domains whose Olfeo category has no mapping to the LLM taxonomy
+    olfeo_unknown_mapping_count : usize,
     /// Number of prioritized domains processed
     priorized_done_count : usize,
     /// Number of prioritized matches found
@@ -29,10 +35,71 @@ pub struct Statistics {
     failed : usize,
     /// LLM chunk size used
     chunk_size: usize,
-    /// LLM thinking budget used
-    thinking_budget: i64,
+    /// LLM thinking budget used, None when thinking was not configured
+    thinking_budget: Option<i64>,
     /// Elapsed time for processing
     pub elapsed_time: std::time::Duration,
+    /// Whether domains were pseudonymized before being sent to the LLM
+    pub anonymized: bool,
+    /// Set when the run was stopped by the budget cap
+    pub budget_stop: Option<BudgetStopSummary>,
+    /// Whether the input provides expected categories, level and total matches are "n/a" otherwise
+    pub expected_categories: bool,
+    /// Whether the input provides Olfeo categories, the Olfeo match is "n/a" otherwise
+    pub olfeo_categories: bool,
+    /// Number of LLM labels auto-corrected to a category, per (original, corrected) pair
+    pub category_corrections: BTreeMap<(String, String), usize>,
+    /// Number of LLM labels outside of the taxonomy, replaced by the fallback category or sending their domain again
+    pub invalid_category_count: usize,
+    /// Number of LLM labels outside of the taxonomy per label, to spot the categories the prompt misses
+    pub rejected_categories: BTreeMap<String, usize>,
+    /// Number of LLM outputs parsed by a fallback because the requested JSON format was ignored, per fallback
+    pub parse_fallbacks: BTreeMap<String, usize>,
+    /// Dictionary or taxonomy files modified while the run was going on, the run is tainted when not empty
+    pub modified_inputs: Vec<String>,
+    /// Number of input lines skipped because they could not be parsed
+    pub malformed_input_lines: usize,
+    /// Number of input values left unclassified because they are not valid URLs, in URL mode
+    pub unclassifiable_inputs: usize,
+    /// Set when domains failing the first pass were sent again at the end of the run
+    pub retry_pass: Option<RetryPassSummary>,
+    /// Cost of the requests sent to each model, the fallbacks of the first one included
+    pub cost_per_model: HashMap<String, f64>,
+    /// Number of domains each model ended up processing
+    pub processed_per_model: HashMap<String, usize>,
+    /// Time the LLM requests waited for the rate limiter, summed over the threads
+    pub throttled_time: std::time::Duration,
+    /// Set when the domains were looked up in the local cache before being sent to the LLM
+    pub local_cache: Option<LocalCacheSummary>,
+    /// Number of domains of a describe run whose input already had their descriptions, not sent to the LLM
+    pub already_described: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Budget cap that stopped a run
+pub struct BudgetStopSummary {
+    /// Cost cap of the run, in euros
+    pub max_cost_eur: f64,
+    /// Number of domains that were not sent to the LLM
+    pub remaining_domains: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+/// Retry pass of the domains failing the first pass
+pub struct RetryPassSummary {
+    /// Number of domains sent again
+    pub retried: usize,
+    /// Number of them processed by the retry
+    pub processed: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+/// Lookups of the domains of a run in the local cache
+pub struct LocalCacheSummary {
+    /// Number of domains found in the cache
+    pub hits: usize,
+    /// Number of domains sent to the LLM
+    pub misses: usize,
 }
 
 /// Methods for the Statistics struct
@@ -51,6 +118,9 @@ impl Statistics {
             domaine_count: 0,
             olfeo_match_count: 0,
             llm_level_match_count: vec![0; levels],
+            olfeo_agreement_count: vec![0; levels],
+            olfeo_compared_count: 0,
+            olfeo_unknown_mapping_count: 0,
             priorized_done_count: 0,
             prioritized_match_count: 0,
             prioritized_done_success: 0,
@@ -59,11 +129,33 @@ impl Statistics {
             retried: 0,
             failed: 0,
             chunk_size: 0,
-            thinking_budget: 0,
+            thinking_budget: None,
             elapsed_time: std::time::Duration::new(0, 0),
+            anonymized: false,
+            budget_stop: None,
+            expected_categories: true,
+            olfeo_categories: true,
+            category_corrections: BTreeMap::new(),
+            invalid_category_count: 0,
+            rejected_categories: BTreeMap::new(),
+            parse_fallbacks: BTreeMap::new(),
+            modified_inputs: Vec::new(),
+            malformed_input_lines: 0,
+            unclassifiable_inputs: 0,
+            retry_pass: None,
+            cost_per_model: HashMap::new(),
+            processed_per_model: HashMap::new(),
+            throttled_time: std::time::Duration::ZERO,
+            local_cache: None,
+            already_described: 0,
         }
     }
 
+    /// Whether a dictionary or taxonomy file was modified during the run
+    pub fn tainted(&self) -> bool {
+        !self.modified_inputs.is_empty()
+    }
+
     pub fn increment_domain_count (&mut self) {
         self.domaine_count += 1;
     }
@@ -131,13 +223,66 @@ impl Statistics {
         }
     }
 
+    /// Records the agreement of the LLM propositions of a domain with its Olfeo category
+    ///
+    /// # Arguments
+    ///
+    /// * `mapped_olfeo_category` - Olfeo category mapped to the LLM taxonomy, `None` when it has no mapping
+    /// * `llm_categories` - LLM propositions, one per level
+    pub fn record_olfeo_agreement(&mut self, mapped_olfeo_category: Option<&str>, llm_categories: &[&str]) {
+        let mapped_olfeo_category = match mapped_olfeo_category {
+            Some(category) => category,
+            None => {
+                self.olfeo_unknown_mapping_count += 1;
+                return;
+            }
+        };
+
+        self.olfeo_compared_count += 1;
+        for (level, category) in llm_categories.iter().enumerate().take(self.olfeo_agreement_count.len()) {
+            if *category == mapped_olfeo_category {
+                self.olfeo_agreement_count[level] += 1;
+            }
+        }
+    }
+
+    pub fn process_olfeo_agreement_percentage(&self, level: usize) -> f64 {
+        if self.olfeo_compared_count == 0 || level >= self.olfeo_agreement_count.len() {
+            0.0
+        } else {
+            (self.olfeo_agreement_count[level] as f64 / self.olfeo_compared_count as f64) * 100.0
+        }
+    }
+
+    fn process_total_llm_matches_percentage(&self) -> f64 {
+        let total_matches: usize = self.llm_level_match_count.iter().sum();
+        (total_matches as f64 / self.domaine_count as f64) * 100.0
+    }
+
+    /// Formats an accuracy percentage, "n/a" when the input columns needed to compute it are missing
+    fn format_accuracy(available: bool, percentage: f64) -> String {
+        if available {
+            format!("{:.2} %", percentage)
+        } else {
+            "n/a".to_string()
+        }
+    }
+
+    /// Formats the thinking budget, "none" when no thinking configuration was sent
+    fn format_thinking_budget(thinking_budget: Option<i64>) -> String {
+        match thinking_budget {
+            Some(budget) => budget.to_string(),
+            None => "none".to_string(),
+        }
+    }
+
     pub fn update_llm_statistics(&mut self,
-        processed: AtomicUsize,
-        cost: AtomicF64,
-        retried: AtomicUsize,
-        failed: AtomicUsize,
+        processed: &AtomicUsize,
+        cost: &AtomicF64,
+        retried: &AtomicUsize,
+        failed: &AtomicUsize,
         chunk_size: usize,
-        thinking_budget: i64
+        thinking_budget: Option<i64>
     ){
         self.processed += processed.load(Ordering::Relaxed);
         self.cost += cost.load(Ordering::Relaxed);
@@ -147,6 +292,19 @@ impl Statistics {
         self.thinking_budget = thinking_budget;
     }
 
+    /// Number of domains processed by the first pass, before the retry of the failed ones
+    pub fn first_pass_processed(&self) -> usize {
+        self.processed.saturating_sub(self.retry_pass.as_ref().map_or(0, |retry| retry.processed))
+    }
+
+    /// Models that were sent requests or processed domains, sorted by name
+    fn models(&self) -> Vec<&String> {
+        let mut models: Vec<&String> = self.cost_per_model.keys().chain(self.processed_per_model.keys()).collect();
+        models.sort();
+        models.dedup();
+        models
+    }
+
     /// Generates a summary of the statistics
     ///
     /// # Arguments
@@ -158,19 +316,84 @@ impl Statistics {
     /// Panics if memory allocation fails.
     pub fn generate_output_summary(&self) -> String {
         let mut summary = String::new();
+        if self.tainted() {
+            summary.push_str("WARNING: TAINTED RUN, inputs were modified during the run, results were computed from the copies taken at startup:\n");
+            for path in &self.modified_inputs {
+                summary.push_str(&format!("\t {}\n", path));
+            }
+        }
         summary.push_str(&format!("Statistics with {} domains\n", self.domaine_count));
-        summary.push_str(&format!("\t Olfeo match percentage: {:.2} %\n", self.process_olfeo_matches_percentage()));
+        let olfeo_available = self.expected_categories && self.olfeo_categories;
+        summary.push_str(&format!("\t Olfeo match percentage: {}\n", Self::format_accuracy(olfeo_available, self.process_olfeo_matches_percentage())));
         for level in 0..self.llm_level_match_count.len() {
-            summary.push_str(&format!("\t Level {} match percentage: {:.2} %\n", level + 1, self.process_llm_level_matches_percentage(level)));
+            summary.push_str(&format!("\t Level {} match percentage: {}\n", level + 1, Self::format_accuracy(self.expected_categories, self.process_llm_level_matches_percentage(level))));
+        }
+        summary.push_str(&format!("\t Total LLM match percentage: {}\n", Self::format_accuracy(self.expected_categories, self.process_total_llm_matches_percentage())));
+        for level in 0..self.olfeo_agreement_count.len() {
+            summary.push_str(&format!("\t Olfeo agreement level {}: {} ({} domains compared)\n", level + 1, Self::format_accuracy(self.olfeo_categories, self.process_olfeo_agreement_percentage(level)), self.olfeo_compared_count));
         }
-        let total_percentage: usize = self.llm_level_match_count.iter().sum();
-        summary.push_str(&format!("\t Total LLM match percentage: {:.2} %\n", (total_percentage as f64 / self.domaine_count as f64) * 100.0));
+        summary.push_str(&format!("\t Olfeo unknown mappings: {}\n", self.olfeo_unknown_mapping_count));
         summary.push_str(&format!("\t LLM cost: {:.6}\n", self.cost));
         summary.push_str(&format!("\t LLM retried: {}\n", self.retried));
         summary.push_str(&format!("\t LLM failed: {}\n", self.failed));
+        if let Some(cache) = &self.local_cache {
+            summary.push_str(&format!("\t Local cache: {} hits, {} misses\n", cache.hits, cache.misses));
+        }
+        if self.already_described > 0 {
+            summary.push_str(&format!("\t Already described: {}\n", self.already_described));
+        }
+        if !self.throttled_time.is_zero() {
+            summary.push_str(&format!("\t LLM throttled time: {}\n", duration_to_pretty(self.throttled_time).unwrap_or_else(|| format!("{:?}", self.throttled_time))));
+        }
+        if let Some(retry) = &self.retry_pass {
+            summary.push_str(&format!("\t LLM first pass processed: {}\n", self.first_pass_processed()));
+            summary.push_str(&format!("\t LLM retry pass processed: {} of {} failed domains\n", retry.processed, retry.retried));
+        }
+        if !self.cost_per_model.is_empty() || !self.processed_per_model.is_empty() {
+            summary.push_str("\t LLM models:\n");
+            for model in self.models() {
+                summary.push_str(&format!("\t\t {}: {} domains, cost {:.6}\n",
+                    model,
+                    self.processed_per_model.get(model).copied().unwrap_or(0),
+                    self.cost_per_model.get(model).copied().unwrap_or(0.0)
+                ));
+            }
+        }
         summary.push_str(&format!("\t LLM chunk size: {}\n", self.chunk_size));
-        summary.push_str(&format!("\t LLM thinking budget: {}\n", self.thinking_budget));
-        summary.push_str(&format!("\t Elapsed time : {}\n", seconds_to_pretty(self.elapsed_time.as_secs()).unwrap_or_else(|| format!("{:?}", self.elapsed_time))));
+        summary.push_str(&format!("\t LLM thinking budget: {}\n", Self::format_thinking_budget(self.thinking_budget)));
+        if self.anonymized {
+            summary.push_str("\t Anonymized run: domains were pseudonymized, classification quality may be lower\n");
+        }
+        if let Some(stop) = &self.budget_stop {
+            summary.push_str(&format!("\t Budget stop: cap of {:.6} exceeded, {} domains remaining\n", stop.max_cost_eur, stop.remaining_domains));
+        }
+        if !self.category_corrections.is_empty() {
+            let total: usize = self.category_corrections.values().sum();
+            summary.push_str(&format!("\t Category corrections: {}\n", total));
+            for ((original, corrected), count) in &self.category_corrections {
+                summary.push_str(&format!("\t\t '{}' -> '{}': {}\n", original, corrected, count));
+            }
+        }
+        if self.invalid_category_count > 0 {
+            summary.push_str(&format!("\t Categories outside of the taxonomy: {}\n", self.invalid_category_count));
+            for (label, count) in &self.rejected_categories {
+                summary.push_str(&format!("\t\t '{}': {}\n", label, count));
+            }
+        }
+        if !self.parse_fallbacks.is_empty() {
+            let total: usize = self.parse_fallbacks.values().sum();
+            summary.push_str(&format!("\t LLM output format ignored: {}\n", total));
+            for (fallback, count) in &self.parse_fallbacks {
+                summary.push_str(&format!("\t\t parsed from a {}: {}\n", fallback.replace('_', " "), count));
+            }
+        }
+        if self.malformed_input_lines > 0 {
+            summary.push_str(&format!("\t Malformed input lines skipped: {}\n", self.malformed_input_lines));
+        }
+        if self.unclassifiable_inputs > 0 {
+            summary.push_str(&format!("\t Unclassifiable inputs (invalid URLs): {}\n", self.unclassifiable_inputs));
+        }
+        summary.push_str(&format!("\t Elapsed time : {}\n", duration_to_pretty(self.elapsed_time).unwrap_or_else(|| format!("{:?}", self.elapsed_time))));
         summary.push_str("\t Estimated cost for 4000000 domains: ");
         let estimated_cost = if self.domaine_count > 0 {
             (4000000.0 / self.domaine_count as f64) * self.cost
@@ -185,7 +408,7 @@ impl Statistics {
         } else {
             std::time::Duration::new(0, 0)
         };
-        summary.push_str(&format!("{}\n", seconds_to_pretty(estimated_time.as_secs()).unwrap_or_else(|| format!("{:?}", estimated_time))));
+        summary.push_str(&format!("{}\n", duration_to_pretty(estimated_time).unwrap_or_else(|| format!("{:?}", estimated_time))));
 
 
 
@@ -193,24 +416,98 @@ impl Statistics {
     }
 }
 
+impl Statistics {
+    /// Statistics of the run as a JSON document, written next to the outputs
+    pub fn to_json(&self) -> String {
+        let category_corrections: Vec<serde_json::Value> = self
+            .category_corrections
+            .iter()
+            .map(|((original, corrected), count)| serde_json::json!({
+                "original": original,
+                "corrected": corrected,
+                "count": count,
+            }))
+            .collect();
+
+        let models: Vec<serde_json::Value> = self
+            .models()
+            .into_iter()
+            .map(|model| serde_json::json!({
+                "model": model,
+                "processed": self.processed_per_model.get(model).copied().unwrap_or(0),
+                "cost": self.cost_per_model.get(model).copied().unwrap_or(0.0),
+            }))
+            .collect();
+
+        let json = serde_json::json!({
+            "domains": self.domaine_count,
+            "processed": self.processed,
+            "cost": self.cost,
+            "retried": self.retried,
+            "failed": self.failed,
+            "chunk_size": self.chunk_size,
+            "thinking_budget": self.thinking_budget,
+            "elapsed_seconds": self.elapsed_time.as_secs_f64(),
+            "throttled_seconds": self.throttled_time.as_secs_f64(),
+            "anonymized": self.anonymized,
+            "budget_stop": self.budget_stop.as_ref().map(|stop| serde_json::json!({
+                "max_cost_eur": stop.max_cost_eur,
+                "remaining_domains": stop.remaining_domains,
+            })),
+            "category_corrections": category_corrections,
+            "invalid_category_count": self.invalid_category_count,
+            "rejected_categories": self.rejected_categories,
+            "parse_fallbacks": self.parse_fallbacks,
+            "tainted": self.tainted(),
+            "modified_inputs": self.modified_inputs,
+            "malformed_input_lines": self.malformed_input_lines,
+            "unclassifiable_inputs": self.unclassifiable_inputs,
+            "first_pass_processed": self.first_pass_processed(),
+            "retry_pass": self.retry_pass.as_ref().map(|retry| serde_json::json!({
+                "retried": retry.retried,
+                "processed": retry.processed,
+            })),
+            "models": models,
+            "local_cache": self.local_cache.as_ref().map(|cache| serde_json::json!({
+                "hits": cache.hits,
+                "misses": cache.misses,
+            })),
+            "already_described": self.already_described,
+        });
+
+        serde_json::to_string_pretty(&json).unwrap_or_default()
+    }
+}
+
 impl Display for Statistics {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut total_percentage = 0;
         write!(f, "Statistics with {} domains ", self.domaine_count)?;
-        write!(f, "\n\t Olfeo match percentage: {:.2} %", self.process_olfeo_matches_percentage())?;
+        let olfeo_available = self.expected_categories && self.olfeo_categories;
+        write!(f, "\n\t Olfeo match percentage: {}", Self::format_accuracy(olfeo_available, self.process_olfeo_matches_percentage()))?;
         for level in 0..self.llm_level_match_count.len() {
-            total_percentage += self.llm_level_match_count[level];
-            write!(f, "\n\t Level {} match percentage: {:.2} %", level + 1, self.process_llm_level_matches_percentage(level))?;
+            write!(f, "\n\t Level {} match percentage: {}", level + 1, Self::format_accuracy(self.expected_categories, self.process_llm_level_matches_percentage(level)))?;
         }
-        write!(f, "\n\t Total LLM match percentage: {:.2} %", (total_percentage as f64 / self.domaine_count as f64) * 100.0)?;
+        write!(f, "\n\t Total LLM match percentage: {}", Self::format_accuracy(self.expected_categories, self.process_total_llm_matches_percentage()))?;
+        for level in 0..self.olfeo_agreement_count.len() {
+            write!(f, "\n\t Olfeo agreement level {}: {}", level + 1, Self::format_accuracy(self.olfeo_categories, self.process_olfeo_agreement_percentage(level)))?;
+        }
+        write!(f, "\n\t Olfeo unknown mappings: {}", self.olfeo_unknown_mapping_count)?;
         write!(f, "\n\t Prioritized match percentage: {:.2} %", self.process_prioritized_matches_percentage())?;
         write!(f, "\n\t Priorized done percentage: {:.2} %", self.process_priorized_done_percentage())?;
         write!(f, "\n\t Prioritized done success percentage: {:.2} %", self.process_prioritized_done_success_percentage())?;
         write!(f, "\n\t LLM cost: {:.2}", self.cost)?;
         write!(f, "\n\t LLM retried: {}", self.retried)?;
         write!(f, "\n\t LLM failed: {}", self.failed)?;
+        write!(f, "\n\t LLM throttled time: {:?}", self.throttled_time)?;
+        write!(f, "\n\t Local cache: {:?}", self.local_cache)?;
+        write!(f, "\n\t Retry pass: {:?}", self.retry_pass)?;
         write!(f, "\n\t LLM chunk size: {}", self.chunk_size)?;
-        write!(f, "\n\t LLM thinking budget: {}", self.thinking_budget)?;
+        write!(f, "\n\t LLM thinking budget: {}", Self::format_thinking_budget(self.thinking_budget))?;
+        write!(f, "\n\t Anonymized: {}", self.anonymized)?;
+        write!(f, "\n\t Budget stop: {:?}", self.budget_stop)?;
+        write!(f, "\n\t Category corrections: {:?}", self.category_corrections)?;
+        write!(f, "\n\t Parse fallbacks: {:?}", self.parse_fallbacks)?;
+        write!(f, "\n\t Modified inputs: {:?}", self.modified_inputs)?;
         write!(f, "\n\t Elapsed time: {:?}", self.elapsed_time)?;
 
         Ok(())
@@ -283,4 +580,174 @@ mod tests {
         assert!(summary.contains("Estimated cost for 4000000 domains: 0.000000"));
         assert!(summary.contains("Estimated time for 4000000 domains: 00:00:00"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_statistics_output_summary_sub_second() {
+        let mut stats = Statistics::new(1);
+        stats.elapsed_time = std::time::Duration::from_millis(850);
+        let summary = stats.generate_output_summary();
+        assert!(summary.contains("Elapsed time : 00:00:00.850"));
+    }
+
+    #[test]
+    fn test_statistics_output_summary_anonymized() {
+        let mut stats = Statistics::new(1);
+        assert!(!stats.generate_output_summary().contains("Anonymized run"));
+        stats.anonymized = true;
+        assert!(stats.generate_output_summary().contains("Anonymized run"));
+    }
+
+    #[test]
+    fn test_statistics_output_summary_budget_stop() {
+        let mut stats = Statistics::new(1);
+        assert!(!stats.generate_output_summary().contains("Budget stop"));
+        stats.budget_stop = Some(BudgetStopSummary { max_cost_eur: 1.5, remaining_domains: 42 });
+        assert!(stats.generate_output_summary().contains("Budget stop: cap of 1.500000 exceeded, 42 domains remaining"));
+    }
+
+    #[test]
+    fn test_statistics_output_summary_without_expected_categories() {
+        let mut stats = Statistics::new(2);
+        stats.increment_domain_count();
+        stats.expected_categories = false;
+        stats.olfeo_categories = false;
+        let summary = stats.generate_output_summary();
+        assert!(summary.contains("Olfeo match percentage: n/a"));
+        assert!(summary.contains("Level 1 match percentage: n/a"));
+        assert!(summary.contains("Level 2 match percentage: n/a"));
+        assert!(summary.contains("Total LLM match percentage: n/a"));
+        assert!(!summary.contains("0.00 %"));
+        assert!(stats.to_string().contains("Total LLM match percentage: n/a"));
+
+        // Olfeo categories alone cannot be compared without expected categories
+        stats.olfeo_categories = true;
+        assert!(stats.generate_output_summary().contains("Olfeo match percentage: n/a"));
+        stats.expected_categories = true;
+        assert!(stats.generate_output_summary().contains("Olfeo match percentage: 0.00 %"));
+    }
+
+    #[test]
+    fn test_statistics_output_summary_category_corrections() {
+        let mut stats = Statistics::new(1);
+        assert!(!stats.generate_output_summary().contains("Category corrections"));
+        stats.category_corrections.insert(("Réseaux Sociaux".to_string(), "Réseaux sociaux".to_string()), 3);
+        stats.category_corrections.insert(("Immobillier".to_string(), "Immobilier".to_string()), 1);
+        let summary = stats.generate_output_summary();
+        assert!(summary.contains("Category corrections: 4\n\t\t 'Immobillier' -> 'Immobilier': 1\n\t\t 'Réseaux Sociaux' -> 'Réseaux sociaux': 3\n"));
+    }
+
+    #[test]
+    fn test_statistics_invalid_category_count() {
+        let mut stats = Statistics::new(1);
+        assert!(!stats.generate_output_summary().contains("outside of the taxonomy"));
+        stats.invalid_category_count = 3;
+        stats.rejected_categories.insert("Cuisine".to_string(), 2);
+        stats.rejected_categories.insert("Emali".to_string(), 1);
+        assert!(stats.generate_output_summary().contains("\t Categories outside of the taxonomy: 3\n\t\t 'Cuisine': 2\n\t\t 'Emali': 1\n"));
+
+        let json: serde_json::Value = serde_json::from_str(&stats.to_json()).unwrap();
+        assert_eq!(json["invalid_category_count"], 3);
+        assert_eq!(json["rejected_categories"], serde_json::json!({ "Cuisine": 2, "Emali": 1 }));
+    }
+
+    #[test]
+    fn test_statistics_parse_fallbacks() {
+        let mut stats = Statistics::new(1);
+        assert!(!stats.generate_output_summary().contains("LLM output format ignored"));
+        stats.parse_fallbacks.insert("balanced_block".to_string(), 2);
+        stats.parse_fallbacks.insert("fenced_block".to_string(), 1);
+        let summary = stats.generate_output_summary();
+        assert!(summary.contains("LLM output format ignored: 3\n\t\t parsed from a balanced block: 2\n\t\t parsed from a fenced block: 1\n"));
+
+        let json: serde_json::Value = serde_json::from_str(&stats.to_json()).unwrap();
+        assert_eq!(json["parse_fallbacks"], serde_json::json!({ "balanced_block": 2, "fenced_block": 1 }));
+    }
+
+    #[test]
+    fn test_statistics_malformed_input_lines() {
+        let mut stats = Statistics::new(1);
+        assert!(!stats.generate_output_summary().contains("Malformed input lines"));
+        stats.malformed_input_lines = 2;
+        assert!(stats.generate_output_summary().contains("\t Malformed input lines skipped: 2\n"));
+
+        let json: serde_json::Value = serde_json::from_str(&stats.to_json()).unwrap();
+        assert_eq!(json["malformed_input_lines"], 2);
+    }
+
+    #[test]
+    fn test_statistics_unclassifiable_inputs() {
+        let mut stats = Statistics::new(1);
+        assert!(!stats.generate_output_summary().contains("Unclassifiable inputs"));
+        stats.unclassifiable_inputs = 3;
+        assert!(stats.generate_output_summary().contains("\t Unclassifiable inputs (invalid URLs): 3\n"));
+
+        let json: serde_json::Value = serde_json::from_str(&stats.to_json()).unwrap();
+        assert_eq!(json["unclassifiable_inputs"], 3);
+    }
+
+    #[test]
+    fn test_statistics_retry_pass() {
+        let mut stats = Statistics::new(1);
+        stats.processed = 10;
+        assert!(!stats.generate_output_summary().contains("retry pass"));
+        assert_eq!(stats.first_pass_processed(), 10);
+
+        stats.retry_pass = Some(RetryPassSummary { retried: 4, processed: 3 });
+        let summary = stats.generate_output_summary();
+        assert!(summary.contains("\t LLM first pass processed: 7\n\t LLM retry pass processed: 3 of 4 failed domains\n"));
+
+        let json: serde_json::Value = serde_json::from_str(&stats.to_json()).unwrap();
+        assert_eq!(json["first_pass_processed"], 7);
+        assert_eq!(json["retry_pass"], serde_json::json!({ "retried": 4, "processed": 3 }));
+    }
+
+    #[test]
+    fn test_statistics_models() {
+        let mut stats = Statistics::new(1);
+        assert!(!stats.generate_output_summary().contains("LLM models"));
+
+        stats.cost_per_model.insert("gemini-2.5-pro".to_string(), 0.25);
+        stats.processed_per_model.insert("gemini-2.5-pro".to_string(), 2);
+        stats.cost_per_model.insert("gemini-2.5-flash".to_string(), 0.5);
+        stats.processed_per_model.insert("gemini-2.5-flash".to_string(), 40);
+        stats.cost_per_model.insert("gemini-2.0-flash".to_string(), 0.125);
+        let summary = stats.generate_output_summary();
+        assert!(summary.contains("\t LLM models:\n\t\t gemini-2.0-flash: 0 domains, cost 0.125000\n\t\t gemini-2.5-flash: 40 domains, cost 0.500000\n\t\t gemini-2.5-pro: 2 domains, cost 0.250000\n"));
+
+        let json: serde_json::Value = serde_json::from_str(&stats.to_json()).unwrap();
+        assert_eq!(json["models"][1], serde_json::json!({ "model": "gemini-2.5-flash", "processed": 40, "cost": 0.5 }));
+    }
+
+    #[test]
+    fn test_statistics_throttled_time() {
+        let mut stats = Statistics::new(1);
+        assert!(!stats.generate_output_summary().contains("LLM throttled time"));
+
+        stats.throttled_time = std::time::Duration::from_millis(75_500);
+        assert!(stats.generate_output_summary().contains("\t LLM throttled time: 00:01:15.500\n"));
+        let json: serde_json::Value = serde_json::from_str(&stats.to_json()).unwrap();
+        assert_eq!(json["throttled_seconds"], 75.5);
+    }
+
+    #[test]
+    fn test_statistics_local_cache() {
+        let mut stats = Statistics::new(1);
+        assert!(!stats.generate_output_summary().contains("Local cache"));
+
+        stats.local_cache = Some(LocalCacheSummary { hits: 30, misses: 12 });
+        assert!(stats.generate_output_summary().contains("\t Local cache: 30 hits, 12 misses\n"));
+        let json: serde_json::Value = serde_json::from_str(&stats.to_json()).unwrap();
+        assert_eq!(json["local_cache"], serde_json::json!({ "hits": 30, "misses": 12 }));
+    }
+
+    #[test]
+    fn test_statistics_already_described() {
+        let mut stats = Statistics::new(1);
+        assert!(!stats.generate_output_summary().contains("Already described"));
+
+        stats.already_described = 4;
+        assert!(stats.generate_output_summary().contains("\t Already described: 4\n"));
+        let json: serde_json::Value = serde_json::from_str(&stats.to_json()).unwrap();
+        assert_eq!(json["already_described"], 4);
+    }
+}
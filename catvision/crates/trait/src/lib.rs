@@ -1,6 +1,7 @@
 use statistics::Statistics;
 use std::path::PathBuf;
 use std::any::Any;
+use utils::error::CatVisionError;
 
 /// Information for the HTML or other output formats
 pub struct Infos {
@@ -26,13 +27,21 @@ pub trait Output: Send + Sync {
     /// Clones the boxed Output trait object
     fn clone_box(&self) -> Box<dyn Output>;
     /// Writes data to the output formats
-    fn write(&mut self, data: &dyn std::any::Any, infos: &Infos) -> Result<(), Box<dyn std::error::Error>>;
+    fn write(&mut self, data: &dyn std::any::Any, infos: &Infos) -> Result<(), CatVisionError>;
     /// Creates the output header based on input headers and levels count
     fn create_output_header(&mut self, input_headers: &std::collections::HashMap<String, usize>, levels_count: usize);
+    /// Appends a batch of data to the output, the first batch after `create_output_header` starts a new file.
+    /// Formats that need the whole data at once (e.g., HTML) do not support it.
+    fn append(&mut self, _data: &dyn std::any::Any, _infos: &Infos) -> Result<(), CatVisionError> {
+        Err(CatVisionError::Output("This output format cannot be written in batches".to_string()))
+    }
     /// Creates a new instance of the output format handler
-    fn new(filename: &PathBuf) -> Result<Self, Box<dyn std::error::Error>>
+    fn new(filename: &PathBuf) -> Result<Self, CatVisionError>
     where
         Self: Sized; // Need the Sized bound for constructors because they return Self
+
+    /// Returns a mutable reference to the underlying Any type for downcasting
+    fn as_any_mut(&mut self) -> &mut dyn Any;
 }
 
 impl Clone for Box<dyn Output> {
@@ -46,7 +55,7 @@ pub trait Input: Send + Sync {
     /// Clones the boxed Input trait object
     fn clone_box(&self) -> Box<dyn Input>;
     /// Parses the input data and returns it as a boxed Any type
-    fn parse(&mut self, stats: &mut Statistics, dict: Option<&std::collections::HashMap<String, String>>) -> Result<Box<dyn std::any::Any>, Box<dyn std::error::Error>>;
+    fn parse(&mut self, stats: &mut Statistics, dict: Option<&std::collections::HashMap<String, String>>) -> Result<Box<dyn std::any::Any>, CatVisionError>;
    
     /// Creates a new instance of the input format handler
     fn new(filename: &PathBuf) -> Self
@@ -59,6 +68,13 @@ pub trait Input: Send + Sync {
     fn as_any_mut(&mut self) -> &mut dyn Any;
 }
 
+/// Trait for input formats able to read the data in batches instead of the whole file at once
+pub trait StreamingInput: Input {
+    /// Parses the next batch of at most `batch_size` records, `None` once the input is exhausted.
+    /// The first call opens the input and reads its header.
+    fn parse_batch(&mut self, stats: &mut Statistics, dict: Option<&std::collections::HashMap<String, String>>, batch_size: usize) -> Result<Option<Box<dyn std::any::Any>>, CatVisionError>;
+}
+
 impl Clone for Box<dyn Input> {
     fn clone(&self) -> Box<dyn Input> {
         self.clone_box()
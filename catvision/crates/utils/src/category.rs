@@ -56,6 +56,121 @@ pub fn check_category_validity(category: &str) -> Option<&'static str> {
     }
 }
 
+/// Maximum edit distance for a near-miss label to be auto-corrected
+pub const MAX_CORRECTION_DISTANCE: usize = 2;
+
+/// How a label returned by the LLM matches the taxonomy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CategoryMatch {
+    /// The label is a category
+    Exact(&'static str),
+    /// The label matches a category once case, diacritics and whitespace are folded
+    Normalized(&'static str),
+    /// The label is within `MAX_CORRECTION_DISTANCE` edits of a single category, or the first parts of a single one
+    Fuzzy(&'static str),
+    /// No category or several categories are close to the label
+    Invalid,
+}
+
+/// Categories folded with `normalize_category`, in the `CATEGORIES_VEC` order
+static NORMALIZED_CATEGORIES: Lazy<Vec<String>> = Lazy::new(|| {
+    CATEGORIES_VEC.iter().map(|cat| normalize_category(cat)).collect()
+});
+
+/// Folds case, diacritics and whitespace so near-identical labels compare equal.
+/// The slashes between the parts of a category are spaced the same way whatever the label.
+/// Example: "  Réseaux   Sociaux " → "reseaux sociaux", "Intérêts/Loisirs" → "interets / loisirs"
+pub fn normalize_category(category: &str) -> String {
+    let mut normalized = String::with_capacity(category.len());
+    for word in category.replace('/', " / ").split_whitespace() {
+        if !normalized.is_empty() {
+            normalized.push(' ');
+        }
+        for c in word.chars().flat_map(char::to_lowercase) {
+            match c {
+                'à' | 'â' | 'ä' | 'á' | 'ã' | 'å' => normalized.push('a'),
+                'ç' => normalized.push('c'),
+                'é' | 'è' | 'ê' | 'ë' => normalized.push('e'),
+                'î' | 'ï' | 'í' | 'ì' => normalized.push('i'),
+                'ô' | 'ö' | 'ó' | 'ò' | 'õ' => normalized.push('o'),
+                'ù' | 'û' | 'ü' | 'ú' => normalized.push('u'),
+                'ÿ' => normalized.push('y'),
+                'œ' => normalized.push_str("oe"),
+                'æ' => normalized.push_str("ae"),
+                '’' => normalized.push('\''),
+                c => normalized.push(c),
+            }
+        }
+    }
+    normalized
+}
+
+/// Levenshtein distance between two strings, counted in characters
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == *cb { 0 } else { 1 };
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+/// Matches a label returned by the LLM against the taxonomy.
+///
+/// Tiers are tried in order: exact match, normalized match, a unique category within `MAX_CORRECTION_DISTANCE`
+/// edits of the normalized label, then a unique category whose first parts are the label. Ambiguous near-misses are
+/// invalid.
+pub fn match_category(category: &str) -> CategoryMatch {
+    if let Some(cat) = check_category_validity(category) {
+        return CategoryMatch::Exact(cat);
+    }
+
+    let normalized = normalize_category(category);
+    if let Some(index) = NORMALIZED_CATEGORIES.iter().position(|cat| *cat == normalized) {
+        return CategoryMatch::Normalized(CATEGORIES_VEC[index]);
+    }
+
+    let mut candidates = NORMALIZED_CATEGORIES
+        .iter()
+        .enumerate()
+        .filter(|(_, cat)| levenshtein(&normalized, cat) <= MAX_CORRECTION_DISTANCE);
+
+    match (candidates.next(), candidates.next()) {
+        (Some((index, _)), None) => return CategoryMatch::Fuzzy(CATEGORIES_VEC[index]),
+        (Some(_), Some(_)) => return CategoryMatch::Invalid,
+        _ => {}
+    }
+
+    // A category cut after some of its parts ("Banques / Services financiers"), when only one starts with them
+    if normalized.contains(" / ") {
+        let prefix = format!("{} / ", normalized);
+        let mut candidates = NORMALIZED_CATEGORIES.iter().enumerate().filter(|(_, cat)| cat.starts_with(&prefix));
+        if let (Some((index, _)), None) = (candidates.next(), candidates.next()) {
+            return CategoryMatch::Fuzzy(CATEGORIES_VEC[index]);
+        }
+    }
+    CategoryMatch::Invalid
+}
+
+/// Category of the taxonomy a label stands for, once case, diacritics and whitespace are folded and within
+/// `MAX_CORRECTION_DISTANCE` edits of a single category, see `match_category`. `None` for any other label.
+pub fn normalize(label: &str) -> Option<&'static str> {
+    match match_category(label) {
+        CategoryMatch::Exact(category) | CategoryMatch::Normalized(category) | CategoryMatch::Fuzzy(category) => {
+            Some(category)
+        }
+        CategoryMatch::Invalid => None,
+    }
+}
+
 pub const CATEGORIES: &str = r#"
     [
         "Armes / Explosifs",
@@ -350,4 +465,70 @@ static SUB_TO_MAIN: Lazy<HashMap<String, String>> = Lazy::new(|| {
 /// Example: "Forum, Wiki" → Some("Blogs / Forums")
 pub fn main_domain_for(sub: &str) -> Option<&'static str> {
     SUB_TO_MAIN.get(sub).map(|s| s.as_str())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_category_exact() {
+        assert_eq!(match_category("Réseaux sociaux"), CategoryMatch::Exact("Réseaux sociaux"));
+        assert_eq!(match_category("Email"), CategoryMatch::Exact("Email"));
+    }
+
+    #[test]
+    fn test_match_category_normalized() {
+        assert_eq!(normalize_category("  Réseaux   Sociaux "), "reseaux sociaux");
+        assert_eq!(match_category("Réseaux Sociaux"), CategoryMatch::Normalized("Réseaux sociaux"));
+        assert_eq!(match_category("reseaux sociaux"), CategoryMatch::Normalized("Réseaux sociaux"));
+        assert_eq!(match_category("HEBERGEMENT  DE FICHIERS"), CategoryMatch::Normalized("Hébergement de fichiers"));
+    }
+
+    #[test]
+    fn test_match_category_fuzzy() {
+        assert_eq!(levenshtein("reseaux socieux", "reseaux sociaux"), 1);
+        assert_eq!(match_category("Réseaux socieux"), CategoryMatch::Fuzzy("Réseaux sociaux"));
+        assert_eq!(match_category("Immobillier"), CategoryMatch::Fuzzy("Immobilier"));
+        assert_eq!(match_category("Voyage / Tourism / Sorties"), CategoryMatch::Fuzzy("Voyage / Tourisme / Sortie"));
+    }
+
+    #[test]
+    fn test_match_category_invalid() {
+        assert_eq!(match_category("Cuisine"), CategoryMatch::Invalid);
+        assert_eq!(match_category("Immobilier de luxe"), CategoryMatch::Invalid);
+        assert_eq!(match_category(""), CategoryMatch::Invalid);
+    }
+
+    #[test]
+    fn test_normalize_real_world_labels() {
+        let labels = [
+            ("Réseaux Sociaux", Some("Réseaux sociaux")),
+            ("reseaux sociaux", Some("Réseaux sociaux")),
+            ("Banques/Services financiers/Investissement", Some("Banques / Services financiers / Investissement")),
+            ("Banques / Services Financiers / Investissements", Some("Banques / Services financiers / Investissement")),
+            ("E-commerce/Enchères", Some("E-Commerce / Enchères")),
+            ("Hébergement Web / FAI", Some("Hebergement web / FAI")),
+            ("  Santé ", Some("Santé")),
+            ("EMAIL", Some("Email")),
+            ("Jeux d’argent", Some("Jeux d'argent")),
+            ("Medias / Actualites", Some("Médias / Actualités")),
+            ("VPN / Filtres / Proxies / Redirection", Some("VPNs / Filtres / Proxies / Redirection")),
+            ("Téléphonie Mobile", Some("Téléphonie mobile")),
+            ("Banques/Services financiers", Some("Banques / Services financiers / Investissement")),
+            ("Voyage / Tourisme", Some("Voyage / Tourisme / Sortie")),
+            ("Voyage", None),
+            ("Cuisine", None),
+        ];
+        for (label, expected) in labels {
+            assert_eq!(normalize(label), expected, "{}", label);
+        }
+    }
+
+    #[test]
+    fn test_match_category_ambiguous_distance() {
+        // Two edits away from both "Email" and "Emploi", must not be auto-corrected
+        assert_eq!(levenshtein("emali", "email"), 2);
+        assert_eq!(levenshtein("emali", "emploi"), 2);
+        assert_eq!(match_category("Emali"), CategoryMatch::Invalid);
+    }
+}
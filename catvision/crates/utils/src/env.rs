@@ -1,8 +1,13 @@
 use once_cell::sync::Lazy;
+use std::path::PathBuf;
 
-static API_KEY: Lazy<String> = Lazy::new(|| {
+/// Name of the file holding the Gemini API key in the configuration directory
+pub const API_KEY_FILE_NAME: &str = "api_key";
+
+static API_KEY: Lazy<Option<String>> = Lazy::new(|| {
     std::env::var("MY_GEMINI_API_KEY")
-        .expect("Set MY_GEMINI_API_KEY environment variable")
+        .ok()
+        .or_else(read_api_key_file)
 });
 
 static PROJECT_ID: Lazy<String> = Lazy::new(|| {
@@ -11,9 +16,34 @@ static PROJECT_ID: Lazy<String> = Lazy::new(|| {
 });
 
 pub fn get_api_key() -> &'static str {
-    &API_KEY
+    API_KEY
+        .as_deref()
+        .expect("Set MY_GEMINI_API_KEY environment variable or write the key to $XDG_CONFIG_HOME/catvision/api_key")
+}
+
+/// Whether a Gemini API key is set, in the environment or the configuration directory
+pub fn has_api_key() -> bool {
+    API_KEY.is_some()
 }
 
 pub fn get_project_id() -> &'static str {
     &PROJECT_ID
-}
\ No newline at end of file
+}
+
+/// Returns the CatVision configuration directory following the XDG base directory spec:
+/// `$XDG_CONFIG_HOME/catvision`, or `$HOME/.config/catvision` when `XDG_CONFIG_HOME` is unset or empty.
+pub fn xdg_config_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("catvision"))
+}
+
+/// Reads the API key from the configuration directory, ignoring surrounding whitespace
+fn read_api_key_file() -> Option<String> {
+    let path = xdg_config_dir()?.join(API_KEY_FILE_NAME);
+    let key = std::fs::read_to_string(path).ok()?;
+    let key = key.trim();
+    (!key.is_empty()).then(|| key.to_string())
+}
@@ -0,0 +1,120 @@
+use std::fmt;
+
+/// Errors of the catvision crates, so the CLI tells a missing input from a rejected request or an unwritable output.
+///
+/// The CLI maps the variants to its exit codes, see `RunError::from_error` in the cli crate.
+#[derive(Debug)]
+pub enum CatVisionError {
+    /// File missing, unreadable or not writable.
+    Io(std::io::Error),
+    /// CSV file that cannot be read or written.
+    Csv(csv::Error),
+    /// Invalid configuration or command line setting.
+    Config(String),
+    /// Input without the expected content: missing column, unsupported schema version.
+    Input(String),
+    /// Data the output cannot write, or an output setting the run does not support.
+    Output(String),
+    /// Request rejected by the LLM provider, with its HTTP status when known.
+    LlmRequest { status: Option<u16>, body: String },
+    /// LLM answer that cannot be parsed.
+    LlmParse(String),
+    /// Data of another type than the one an input or output handles.
+    Downcast(&'static str),
+}
+
+impl CatVisionError {
+    /// Short label of the variant, for logs and metrics.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CatVisionError::Io(_) => "io",
+            CatVisionError::Csv(_) => "csv",
+            CatVisionError::Config(_) => "config",
+            CatVisionError::Input(_) => "input",
+            CatVisionError::Output(_) => "output",
+            CatVisionError::LlmRequest { .. } => "llm_request",
+            CatVisionError::LlmParse(_) => "llm_parse",
+            CatVisionError::Downcast(_) => "downcast",
+        }
+    }
+
+    /// Whether the error is a request the provider refused for its credentials (HTTP 401/403).
+    pub fn is_auth(&self) -> bool {
+        matches!(self, CatVisionError::LlmRequest { status: Some(401 | 403), .. })
+    }
+}
+
+impl fmt::Display for CatVisionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CatVisionError::Io(e) => write!(f, "I/O error: {}", e),
+            CatVisionError::Csv(e) => write!(f, "CSV error: {}", e),
+            CatVisionError::Config(msg) => write!(f, "Configuration error: {}", msg),
+            CatVisionError::Input(msg) => write!(f, "Invalid input: {}", msg),
+            CatVisionError::Output(msg) => write!(f, "Output error: {}", msg),
+            CatVisionError::LlmRequest { status: Some(status), body } => write!(f, "LLM request failed with HTTP {}: {}", status, body),
+            CatVisionError::LlmRequest { status: None, body } => write!(f, "LLM request failed: {}", body),
+            CatVisionError::LlmParse(msg) => write!(f, "Cannot parse the LLM answer: {}", msg),
+            CatVisionError::Downcast(expected) => write!(f, "Unexpected data, expected {}", expected),
+        }
+    }
+}
+
+impl std::error::Error for CatVisionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CatVisionError::Io(e) => Some(e),
+            CatVisionError::Csv(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for CatVisionError {
+    fn from(e: std::io::Error) -> Self {
+        CatVisionError::Io(e)
+    }
+}
+
+/// I/O errors of the underlying file stay `Io`, so a missing file is reported as such
+impl From<csv::Error> for CatVisionError {
+    fn from(e: csv::Error) -> Self {
+        if e.is_io_error() {
+            match e.into_kind() {
+                csv::ErrorKind::Io(e) => CatVisionError::Io(e),
+                _ => unreachable!("is_io_error checked the kind"),
+            }
+        } else {
+            CatVisionError::Csv(e)
+        }
+    }
+}
+
+impl<W> From<csv::IntoInnerError<W>> for CatVisionError {
+    fn from(e: csv::IntoInnerError<W>) -> Self {
+        CatVisionError::Io(e.into_error())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_io_errors_stay_io() {
+        let err: CatVisionError = csv::Reader::from_path("/nonexistent/catvision.csv").unwrap_err().into();
+        assert!(matches!(&err, CatVisionError::Io(e) if e.kind() == std::io::ErrorKind::NotFound));
+
+        let mut rdr = csv::ReaderBuilder::new().has_headers(false).from_reader("a,b\nc".as_bytes());
+        let err: CatVisionError = rdr.records().try_for_each(|record| record.map(|_| ())).unwrap_err().into();
+        assert_eq!(err.kind(), "csv");
+    }
+
+    #[test]
+    fn test_is_auth() {
+        assert!(CatVisionError::LlmRequest { status: Some(401), body: String::new() }.is_auth());
+        assert!(CatVisionError::LlmRequest { status: Some(403), body: String::new() }.is_auth());
+        assert!(!CatVisionError::LlmRequest { status: Some(429), body: String::new() }.is_auth());
+        assert!(!CatVisionError::LlmRequest { status: None, body: String::new() }.is_auth());
+    }
+}
@@ -3,6 +3,8 @@ use std::collections::HashMap;
 
 pub mod env;
 pub mod category;
+pub mod urls;
+pub mod error;
 
 #[derive(Debug, Clone)]
 /// Structure to hold various categories data for a domain
@@ -14,6 +16,12 @@ pub struct CatVisionData {
     pub appsite_name_by_gemini: Option<String>,
     pub description_fr_by_gemini: Option<String>,
     pub description_en_by_gemini: Option<String>,
+    /// UTC time the descriptions were generated, RFC 3339
+    pub description_updated_at: Option<String>,
+    /// Original URL of the input, in URL mode
+    pub url: Option<String>,
+    /// Host derived from the URL, in URL mode
+    pub host: Option<String>,
 }
 
 impl CatVisionData {
@@ -35,6 +43,9 @@ impl CatVisionData {
             appsite_name_by_gemini,
             description_fr_by_gemini,
             description_en_by_gemini,
+            description_updated_at: None,
+            url: None,
+            host: None,
         }
     }
 }
@@ -58,6 +69,31 @@ pub fn seconds_to_pretty(total_seconds: u64) -> Option<String> {
     }
 }
 
+/// Converts a duration to a human-readable string format with millisecond precision
+/// # Arguments
+/// * `duration` - Duration to format
+/// # Returns
+/// * `Option<String>` - Formatted duration string, e.g. `00:00:00.850`
+pub fn duration_to_pretty(duration: std::time::Duration) -> Option<String> {
+    let pretty = seconds_to_pretty(duration.as_secs())?;
+    Some(format!("{}.{:03}", pretty, duration.subsec_millis()))
+}
+
+/// Splits a domain into its subdomain, registrable label and public suffix
+/// # Arguments
+/// * `ext` - TLD extractor used to find the public suffix
+/// * `domain` - Domain to split, e.g. `mail.example.co.uk`
+/// # Returns
+/// * `Option<(Option<String>, String, String)>` - e.g. `(Some("mail"), "example", "co.uk")`, or None if the domain has no registrable part
+pub fn split_domain(ext: &TldExtractor, domain: &str) -> Option<(Option<String>, String, String)> {
+    match ext.extract(domain) {
+        Ok(TldResult { domain: Some(label), suffix: Some(suffix), subdomain }) => {
+            Some((subdomain.filter(|s| !s.is_empty()), label, suffix))
+        }
+        _ => None,
+    }
+}
+
 pub fn trim_domain_by_llm(dict: &HashMap<String, String>, domain: &str) -> (Option<String>, Option<String>) {
     //Trim subdomains of the given domain with the Olfeo method.
     // The basic idea of the Olfeo method is the following. As input take a domain,
@@ -129,3 +165,22 @@ pub fn trim_domain_by_llm(dict: &HashMap<String, String>, domain: &str) -> (Opti
 
     (Some(root_domain), llm_classification)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_seconds_to_pretty() {
+        assert_eq!(seconds_to_pretty(0).unwrap(), "00:00:00");
+        assert_eq!(seconds_to_pretty(3_723).unwrap(), "01:02:03");
+        assert_eq!(seconds_to_pretty(90_061).unwrap(), "1 days 01:01:01");
+    }
+
+    #[test]
+    fn test_duration_to_pretty_sub_second() {
+        assert_eq!(duration_to_pretty(Duration::from_millis(850)).unwrap(), "00:00:00.850");
+        assert_eq!(duration_to_pretty(Duration::from_millis(3_723_005)).unwrap(), "01:02:03.005");
+    }
+}
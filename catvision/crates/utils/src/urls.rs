@@ -0,0 +1,105 @@
+use url::Url;
+
+/// Maximum number of characters of the path segment kept in the key of a URL
+pub const MAX_PATH_SEGMENT_CHARS: usize = 64;
+
+/// Key a URL is classified and deduplicated on, in URL mode
+#[derive(Debug, Clone, PartialEq)]
+pub struct UrlKey {
+    /// Host of the URL, lowercase, without port
+    pub host: String,
+    /// `host/first_path_segment`, or the host alone for a URL without path. Sent to the LLM as the domain
+    pub key: String,
+}
+
+/// Parses an input value as a URL and derives its key: the host and the first path segment, truncated to
+/// [`MAX_PATH_SEGMENT_CHARS`] characters. The scheme, port, query string and fragment are dropped;
+/// values without scheme are read as `http://` URLs.
+/// # Arguments
+/// * `input` - Input value, e.g. `https://www.google.com:443/maps/place?q=paris#map`
+/// # Returns
+/// * `Result<UrlKey, String>` - e.g. `www.google.com/maps`, or why the value is not a URL with a host
+pub fn url_key(input: &str) -> Result<UrlKey, String> {
+    let input = input.trim();
+    let has_scheme = input.contains("://");
+    let url = if has_scheme {
+        Url::parse(input)
+    } else {
+        Url::parse(&format!("http://{}", input))
+    }
+    .map_err(|e| format!("invalid URL '{}': {}", input, e))?;
+
+    // Without scheme, `user@host` is an email address rather than credentials
+    if !has_scheme && !url.username().is_empty() {
+        return Err(format!("'{}' is not a URL", input));
+    }
+
+    let host = match url.host_str() {
+        Some(host) if !host.is_empty() => host.trim_end_matches('.').to_lowercase(),
+        _ => return Err(format!("URL '{}' has no host", input)),
+    };
+
+    let segment = url
+        .path_segments()
+        .and_then(|mut segments| segments.next())
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| segment.chars().take(MAX_PATH_SEGMENT_CHARS).collect::<String>());
+
+    let key = match segment {
+        Some(segment) => format!("{}/{}", host, segment),
+        None => host.clone(),
+    };
+    Ok(UrlKey { host, key })
+}
+
+/// Host part of a key built by [`url_key`], the whole value for a plain domain
+pub fn key_host(key: &str) -> &str {
+    key.split('/').next().unwrap_or(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(input: &str) -> String {
+        url_key(input).unwrap().key
+    }
+
+    #[test]
+    fn test_url_key() {
+        assert_eq!(key("https://www.google.com/maps/place/Paris"), "www.google.com/maps");
+        assert_eq!(key("google.com/maps"), "google.com/maps");
+        assert_eq!(key("https://google.com"), "google.com");
+        assert_eq!(key("https://google.com/"), "google.com");
+        assert_eq!(url_key("HTTP://Google.COM/Maps").unwrap(), UrlKey { host: "google.com".to_string(), key: "google.com/Maps".to_string() });
+
+        let long_segment = "a".repeat(MAX_PATH_SEGMENT_CHARS + 10);
+        assert_eq!(key(&format!("example.com/{}/b", long_segment)), format!("example.com/{}", &long_segment[..MAX_PATH_SEGMENT_CHARS]));
+    }
+
+    #[test]
+    fn test_url_key_drops_port_query_and_fragment() {
+        assert_eq!(key("http://example.com:8080/shop/cart"), "example.com/shop");
+        assert_eq!(key("example.com:8080"), "example.com");
+        assert_eq!(key("https://example.com/search?q=rust&lang=fr"), "example.com/search");
+        assert_eq!(key("https://example.com?q=rust"), "example.com");
+        assert_eq!(key("https://example.com/docs#install"), "example.com/docs");
+        assert_eq!(key("https://example.com/#/app/home"), "example.com");
+    }
+
+    #[test]
+    fn test_invalid_urls() {
+        assert!(url_key("").is_err());
+        assert!(url_key("http://").is_err());
+        assert!(url_key("not a url").is_err());
+        assert!(url_key("someone@example.com").unwrap_err().contains("is not a URL"));
+        assert!(url_key("file:///etc/hosts").unwrap_err().contains("has no host"));
+        assert!(url_key("http://exa mple.com/path").is_err());
+    }
+
+    #[test]
+    fn test_key_host() {
+        assert_eq!(key_host("google.com/maps"), "google.com");
+        assert_eq!(key_host("google.com"), "google.com");
+    }
+}
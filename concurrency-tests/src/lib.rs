@@ -0,0 +1,36 @@
+//! Loom models of the lock-free code of `spsc` and `atomics_and_locks`.
+//!
+//! Built with `--cfg loom`, both crates take their atomics, `UnsafeCell`, locks and threads from loom, so the tests
+//! of this crate explore the interleavings of the real code and flag any access to a cell that is not ordered by
+//! the atomics. Without the flag there is nothing to run:
+//!
+//! ```text
+//! RUSTFLAGS="--cfg loom" cargo test --release
+//! ```
+//!
+//! Exhaustive exploration does not finish for models with spin loops, so [`model`] bounds the number of preemptions
+//! of an execution to [`PREEMPTION_BOUND`]. Most ordering bugs show up with two, and at three the whole suite runs in
+//! seconds, far below the few minutes CI allows it. `LOOM_MAX_PREEMPTIONS` overrides the bound, e.g. for a deeper run
+//! before a release, and `LOOM_CHECKPOINT_FILE` / `LOOM_LOG` help replaying a failure, see the loom documentation.
+//!
+//! A side waiting for its peer retries with `loom::thread::yield_now`: loom then schedules the peer, and the model
+//! reaches the executions where the data handed over is actually read.
+//!
+//! The parking of `RingBuffer::push_blocking` / `pop_blocking` is not modeled, it stays on `std::thread` handles under
+//! loom; the stress tests of `spsc` cover it.
+
+/// Number of preemptions of an execution explored by [`model`] when `LOOM_MAX_PREEMPTIONS` is not set.
+pub const PREEMPTION_BOUND: usize = 3;
+
+/// Runs `f` under every interleaving loom explores within the preemption bound, see [`PREEMPTION_BOUND`].
+#[cfg(loom)]
+pub fn model<F>(f: F)
+where
+    F: Fn() + Sync + Send + 'static,
+{
+    let mut builder = loom::model::Builder::new();
+    if builder.preemption_bound.is_none() {
+        builder.preemption_bound = Some(PREEMPTION_BOUND);
+    }
+    builder.check(f);
+}
@@ -0,0 +1,113 @@
+//! One-shot `Channel`: a send or a sender drop racing every way of receiving, and the message left in the channel.
+//! Messages are loom `Arc`s, so loom also fails a model that leaks the message or drops it twice.
+#![cfg(loom)]
+
+use atomics_and_locks::channels::{Channel, RecvError};
+use concurrency_tests::model;
+use loom::sync::Arc;
+use loom::thread;
+
+/// Channel with a `'static` lifetime for the handles moved to the model threads, reclaimed by [`reclaim`].
+fn channel() -> *mut Channel<Arc<u32>> {
+    Box::into_raw(Box::new(Channel::new()))
+}
+
+/// Drops the channel of [`channel`] and the message left in it, once its handles are gone.
+fn reclaim(channel: *mut Channel<Arc<u32>>) {
+    drop(unsafe { Box::from_raw(channel) });
+}
+
+/// Regression: `try_receive` swapped `ready` on every poll, loom ordered all those writes before the send and the
+/// receiver polled forever.
+#[test]
+fn send_racing_try_receive() {
+    model(|| {
+        let channel = channel();
+        let (sender, mut receiver) = unsafe { &mut *channel }.split();
+
+        let handle = thread::spawn(move || sender.send(Arc::new(1)));
+
+        let message = loop {
+            match receiver.try_receive() {
+                Ok(Some(message)) => break message,
+                Ok(None) => thread::yield_now(),
+                Err(e) => panic!("unexpected {:?}", e),
+            }
+        };
+        handle.join().unwrap();
+
+        assert_eq!(*message, 1);
+        reclaim(channel);
+    });
+}
+
+#[test]
+fn sender_drop_racing_try_receive() {
+    model(|| {
+        let channel = channel();
+        let (sender, mut receiver) = unsafe { &mut *channel }.split();
+
+        let handle = thread::spawn(move || drop(sender));
+
+        let result = loop {
+            match receiver.try_receive() {
+                Ok(None) => thread::yield_now(),
+                result => break result,
+            }
+        };
+        handle.join().unwrap();
+
+        assert!(matches!(result, Err(RecvError::Disconnected)));
+        reclaim(channel);
+    });
+}
+
+/// The receiver registers itself before checking `ready`, so a send between the check and the park still unparks it.
+/// The receiver is the spawned thread: loom fails a `join` woken by the unpark of a receiver that did not park.
+#[test]
+fn send_racing_receive_blocking() {
+    model(|| {
+        let channel = channel();
+        let (sender, receiver) = unsafe { &mut *channel }.split();
+
+        let handle = thread::spawn(move || receiver.receive_blocking().map(|message| *message));
+
+        sender.send(Arc::new(1));
+        assert_eq!(handle.join().unwrap(), Ok(1));
+        reclaim(channel);
+    });
+}
+
+#[test]
+fn sender_drop_racing_receive_blocking() {
+    model(|| {
+        let channel = channel();
+        let (sender, receiver) = unsafe { &mut *channel }.split();
+
+        let handle = thread::spawn(move || receiver.receive_blocking().map(|message| *message));
+
+        drop(sender);
+        assert_eq!(handle.join().unwrap(), Err(RecvError::Disconnected));
+        reclaim(channel);
+    });
+}
+
+/// A message sent but never received is dropped with the channel, exactly once.
+#[test]
+fn unreceived_message_is_dropped_with_the_channel() {
+    model(|| {
+        let channel = channel();
+        let (sender, receiver) = unsafe { &mut *channel }.split();
+        let message = Arc::new(1);
+
+        let sent = message.clone();
+        let handle = thread::spawn(move || sender.send(sent));
+        // The receiver looks at the channel and goes away without receiving
+        receiver.is_ready();
+        handle.join().unwrap();
+
+        assert_eq!(Arc::strong_count(&message), 2);
+        reclaim(channel);
+        assert_eq!(Arc::strong_count(&message), 1);
+    });
+}
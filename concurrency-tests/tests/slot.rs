@@ -0,0 +1,84 @@
+//! `Slot` publish/take state machine: Empty -> Writing -> Full by the producer racing Full -> Writing -> Empty by the
+//! consumer. Values are loom `Arc`s, so loom also fails a model that leaks a value or drops it twice.
+#![cfg(loom)]
+
+use concurrency_tests::model;
+use loom::sync::Arc;
+use loom::thread;
+use spsc::Slot;
+
+/// Every value is either taken once, handed back once as overwritten, or left in the slot.
+#[test]
+fn publish_take_race() {
+    model(|| {
+        let slot: &'static mut Slot<Box<u32>> = Box::leak(Box::new(Slot::new()));
+        let (producer, consumer) = slot.split();
+
+        let handle = thread::spawn(move || {
+            let mut overwritten = Vec::new();
+            overwritten.extend(producer.publish(Box::new(1)));
+            overwritten.extend(producer.publish(Box::new(2)));
+            overwritten
+        });
+
+        let mut taken = Vec::new();
+        taken.extend(consumer.take());
+        taken.extend(consumer.take());
+
+        let overwritten = handle.join().unwrap();
+        let left = consumer.take();
+
+        // Values are taken in publication order
+        assert!(taken.windows(2).all(|pair| pair[0] < pair[1]));
+        let mut seen: Vec<u32> = taken
+            .into_iter()
+            .chain(overwritten)
+            .chain(left)
+            .map(|value| *value)
+            .collect();
+        seen.sort();
+        assert_eq!(seen, vec![1, 2]);
+    });
+}
+
+/// Full -> Writing -> Full: an overwrite racing a take hands the unread value to exactly one side.
+#[test]
+fn overwrite_take_race() {
+    model(|| {
+        let slot: &'static mut Slot<Box<u32>> = Box::leak(Box::new(Slot::new()));
+        let (producer, consumer) = slot.split();
+        producer.publish(Box::new(1));
+
+        let handle = thread::spawn(move || producer.publish(Box::new(2)).map(|value| *value));
+        let taken = consumer.take().map(|value| *value);
+        let overwritten = handle.join().unwrap();
+
+        match (taken, overwritten) {
+            // The take won: 2 is published afterwards
+            (Some(1), None) => assert_eq!(consumer.take().map(|value| *value), Some(2)),
+            // The overwrite won, or the take found the producer writing
+            (Some(2), Some(1)) | (None, Some(1)) => {}
+            other => panic!("unexpected outcome {:?}", other),
+        }
+    });
+}
+
+/// The value left in the slot after a race is dropped with the slot, exactly once.
+#[test]
+fn value_left_is_dropped_with_the_slot() {
+    model(|| {
+        let slot = Box::into_raw(Box::new(Slot::new()));
+        let (producer, consumer) = unsafe { &mut *slot }.split();
+        let value = Arc::new(1);
+
+        let published = value.clone();
+        let handle = thread::spawn(move || drop(producer.publish(published)));
+        let taken = consumer.take();
+        handle.join().unwrap();
+
+        assert_eq!(Arc::strong_count(&value), 2);
+        drop(taken);
+        drop(unsafe { Box::from_raw(slot) });
+        assert_eq!(Arc::strong_count(&value), 1);
+    });
+}
@@ -0,0 +1,108 @@
+//! Lock-free `RingBuffer`: the producer writes the slot at head while the consumer reads the one at tail, loom checks
+//! that every read of a slot is ordered after its write by the index handoff.
+//!
+//! The sides retry with `yield_now` instead of giving up on a full or empty buffer, so loom schedules the peer and
+//! every model reaches the reads of pushed items.
+#![cfg(loom)]
+
+use concurrency_tests::model;
+use loom::thread;
+use spsc::spsc_lock_free::RingBuffer;
+
+/// Ring buffer with a `'static` lifetime for the handles moved to the model threads.
+fn leaked<const N: usize>() -> &'static mut RingBuffer<u32, N> {
+    Box::leak(Box::new(RingBuffer::new()))
+}
+
+/// Single item pushes and pops through a one item buffer, so every item reuses the slot freed by the previous pop.
+#[test]
+fn push_pop_pair() {
+    model(|| {
+        let (producer, consumer) = leaked::<2>().split();
+
+        let handle = thread::spawn(move || {
+            for item in 1..=2 {
+                while producer.try_push(item).is_err() {
+                    thread::yield_now();
+                }
+            }
+        });
+
+        let mut popped = Vec::new();
+        while popped.len() < 2 {
+            match consumer.try_pop() {
+                Some(item) => popped.push(item),
+                None => thread::yield_now(),
+            }
+        }
+        handle.join().unwrap();
+
+        assert_eq!(popped, [1, 2]);
+        assert_eq!(consumer.try_pop(), None);
+    });
+}
+
+/// Slices copied in and out with one index update each, with both copies straddling the end of the storage.
+#[test]
+fn push_slice_pop_slice_across_the_wrap() {
+    model(|| {
+        let (producer, consumer) = leaked::<4>().split();
+        // Move both indices to 2 so the next slices wrap
+        producer.push_slice(&[0, 0]);
+        consumer.pop_slice(&mut [0; 2]);
+
+        let handle = thread::spawn(move || assert_eq!(producer.push_slice(&[1, 2, 3]), 3));
+
+        let mut out = [0; 3];
+        let mut popped = 0;
+        while popped < 3 {
+            match consumer.pop_slice(&mut out[popped..]) {
+                0 => thread::yield_now(),
+                count => popped += count,
+            }
+        }
+        handle.join().unwrap();
+
+        assert_eq!(out, [1, 2, 3]);
+    });
+}
+
+/// Regression: `pop_batch` loaded head Relaxed, so the items it read were not ordered after their writes by the
+/// producer. Loom reported a causality violation on the slot as soon as the pop saw the new head.
+#[test]
+fn pop_batch_reads_items_after_their_push() {
+    model(|| {
+        let (producer, consumer) = leaked::<4>().split();
+
+        let handle = thread::spawn(move || producer.push(1).unwrap());
+
+        let mut out = [0; 2];
+        while consumer.pop_batch(&mut out) == 0 {
+            thread::yield_now();
+        }
+        handle.join().unwrap();
+
+        assert_eq!(out, [1, 0]);
+    });
+}
+
+/// `peek` reads the slot at tail in place, it must be ordered after the push like a pop.
+#[test]
+fn peek_sees_the_pushed_item() {
+    model(|| {
+        let (producer, mut consumer) = leaked::<2>().split();
+
+        let handle = thread::spawn(move || producer.push(7).unwrap());
+
+        let peeked = loop {
+            match consumer.peek() {
+                Some(&item) => break item,
+                None => thread::yield_now(),
+            }
+        };
+        handle.join().unwrap();
+
+        assert_eq!(peeked, 7);
+        assert_eq!(consumer.try_pop(), Some(7));
+    });
+}
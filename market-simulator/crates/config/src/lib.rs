@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::env;
 use std::fs;
 
@@ -21,6 +22,66 @@ pub struct SnapshotConfig {
     pub max_depth: usize,
 }
 
+/// Encoding of the session journal records.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalFormat {
+    /// Length-prefixed binary records.
+    #[default]
+    Binary,
+    /// One JSON object per line.
+    Jsonl,
+}
+
+/// What the journal taps do when the writer thread falls behind.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalBackpressure {
+    /// Block the engine until the writer catches up, nothing is lost.
+    #[default]
+    Block,
+    /// Drop the record and count it, the engines never wait on the journal.
+    Drop,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct JournalConfig {
+    /// Directory receiving the journal segments.
+    pub dir: String,
+    #[serde(default)]
+    pub format: JournalFormat,
+    #[serde(default)]
+    pub backpressure: JournalBackpressure,
+    /// Number of records buffered between the taps and the writer thread.
+    pub capacity: usize,
+    /// Records written between two fsync calls.
+    pub fsync_every: usize,
+    /// Size after which the current segment is closed and a new one is opened.
+    pub max_file_bytes: u64,
+    pub core: usize,
+}
+
+/// Market data served by the FIX engine (MarketDataRequest, 35=V).
+#[derive(Clone, Deserialize)]
+pub struct MarketDataConfig {
+    /// Price levels per side kept in the read model of each book, deeper requests are capped to it.
+    pub depth: usize,
+    /// Maximum incremental refreshes per second and subscription, 0 disables throttling.
+    #[serde(default)]
+    pub max_updates_per_sec: u32,
+}
+
+/// Limits of the framing checking the FIX messages of every session.
+#[derive(Clone, Deserialize)]
+pub struct FixFramingConfig {
+    /// Maximum size of a message, header and checksum included.
+    pub max_message_size: usize,
+    /// Milliseconds a client has to send a whole message once its first byte arrived.
+    pub read_timeout_ms: u64,
+    /// Maximum of bytes received but not parsed yet, per connection.
+    pub max_buffered_bytes: usize,
+}
+
 #[derive(Clone, Deserialize)]
 pub struct PlayerServiceConfig {
     pub database_url_env: String,
@@ -41,6 +102,33 @@ pub struct MarketConfig {
     pub snapshot_multicast: MulticastConfig,
     pub core_mapping: EngineCoreMapping,
     pub snapshot: SnapshotConfig,
+    /// Session journal, disabled when absent.
+    #[serde(default)]
+    pub journal: Option<JournalConfig>,
+    /// FIX market data, MarketDataRequests are rejected when absent.
+    #[serde(default)]
+    pub market_data: Option<MarketDataConfig>,
+    /// FIX framing limits, the defaults of the FIX crate when absent.
+    #[serde(default)]
+    pub fix_framing: Option<FixFramingConfig>,
+    /// Maximum number of orders resting at a single price level of each book, unlimited when absent.
+    #[serde(default)]
+    pub max_orders_per_level: Option<usize>,
+    /// Lot size per symbol, order quantities of the symbol must be a multiple of it. Symbols absent accept any quantity.
+    #[serde(default)]
+    pub lot_sizes: HashMap<String, f64>,
+    /// Rest limit orders whose minimum quantity (FIX MinQty) is not immediately executable instead of rejecting them.
+    #[serde(default)]
+    pub rest_unmet_min_quantity: bool,
+    /// Number of resting orders each book is pre-sized for, matching allocates nothing below it. Grows on demand when absent.
+    #[serde(default)]
+    pub order_capacity: Option<usize>,
+    /// Gross exposure each participant may reach across all the symbols of the market, unlimited when absent.
+    #[serde(default)]
+    pub max_gross_exposure: Option<f64>,
+    /// Stop the whole market when a worker thread panics.
+    #[serde(default)]
+    pub shutdown_on_panic: bool,
 }
 
 impl MarketConfig {
@@ -53,6 +141,15 @@ impl MarketConfig {
         })
     }
 
+    /// Lot size of `symbol`, looked up with the same normalization as `normalized_stocks`.
+    pub fn lot_size(&self, symbol: &str) -> Option<f64> {
+        let symbol = symbol.trim().to_uppercase();
+        self.lot_sizes
+            .iter()
+            .find(|(configured, _)| configured.trim().to_uppercase() == symbol)
+            .map(|(_, lot_size)| *lot_size)
+    }
+
     pub fn normalized_stocks(&self) -> Vec<String> {
         let mut stocks = Vec::new();
         for symbol in &self.stocks {
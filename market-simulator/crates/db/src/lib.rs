@@ -745,6 +745,12 @@ pub async fn persist_order_update(
     let reason = match order_result.status {
         OrderStatus::CancelRejected => Some("cancel_rejected"),
         OrderStatus::Cancelled => Some("cancelled"),
+        OrderStatus::RejectedLevelFull => Some("level_full"),
+        OrderStatus::RejectedInvalidQuantity => Some("invalid_quantity"),
+        OrderStatus::RejectedInvalidPrice => Some("invalid_price"),
+        OrderStatus::RejectedInvalidLot => Some("invalid_lot"),
+        OrderStatus::RejectedMinQuantity => Some("min_quantity"),
+        OrderStatus::RejectedExposureLimit => Some("exposure_limit"),
         _ => None,
     };
     let order_id_text = order_id_text_from_internal(order_result.internal_order_id);
@@ -1009,7 +1015,13 @@ async fn sync_pending_orders(
                 .await?;
             return Ok(());
         }
-        OrderStatus::CancelRejected => return Ok(()),
+        OrderStatus::CancelRejected
+        | OrderStatus::RejectedLevelFull
+        | OrderStatus::RejectedInvalidQuantity
+        | OrderStatus::RejectedInvalidPrice
+        | OrderStatus::RejectedInvalidLot
+        | OrderStatus::RejectedMinQuantity
+        | OrderStatus::RejectedExposureLimit => return Ok(()),
         _ => {}
     }
 
@@ -1125,6 +1137,12 @@ fn classify_result_type(order_event: &OrderEvent, order_result: &OrderResult) ->
             }
         }
         OrderStatus::Unmatched => "UNMATCHED",
+        OrderStatus::RejectedLevelFull
+        | OrderStatus::RejectedInvalidQuantity
+        | OrderStatus::RejectedInvalidPrice
+        | OrderStatus::RejectedInvalidLot
+        | OrderStatus::RejectedMinQuantity
+        | OrderStatus::RejectedExposureLimit => "REJECTED",
     }
 }
 
@@ -1134,6 +1152,12 @@ fn parse_order_status(value: Option<String>) -> OrderStatus {
         Some("PartiallyFilled") => OrderStatus::PartiallyFilled,
         Some("Cancelled") => OrderStatus::Cancelled,
         Some("CancelRejected") => OrderStatus::CancelRejected,
+        Some("RejectedLevelFull") => OrderStatus::RejectedLevelFull,
+        Some("RejectedInvalidQuantity") => OrderStatus::RejectedInvalidQuantity,
+        Some("RejectedInvalidPrice") => OrderStatus::RejectedInvalidPrice,
+        Some("RejectedInvalidLot") => OrderStatus::RejectedInvalidLot,
+        Some("RejectedMinQuantity") => OrderStatus::RejectedMinQuantity,
+        Some("RejectedExposureLimit") => OrderStatus::RejectedExposureLimit,
         _ => OrderStatus::New,
     }
 }
@@ -1149,6 +1173,8 @@ fn parse_order_type(value: Option<String>) -> OrderType {
     match value.as_deref() {
         Some("MarketOrder") => OrderType::MarketOrder,
         Some("CancelOrder") => OrderType::CancelOrder,
+        Some("ImmediateOrCancel") => OrderType::ImmediateOrCancel,
+        Some("FillOrKill") => OrderType::FillOrKill,
         _ => OrderType::LimitOrder,
     }
 }
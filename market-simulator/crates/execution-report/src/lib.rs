@@ -1,23 +1,36 @@
 use fix::engine::FixRawMsg;
-use types::{macros::EntityId, ExecReportData, ExecutionReportMessage, OrderEvent, OrderResult};
+use types::{ExecReportData, ExecutionReportMessage, OrderEvent, OrderResult, macros::EntityId};
 
 use crossbeam_channel;
 use fix::tags::{
     exec_type_code_set, msg_types, ord_status_code_set, side_code_set,
     tags::{self},
 };
-use spsc::spsc_lock_free::Producer;
-use std::sync::atomic::{AtomicBool, Ordering};
+use spsc::spsc_lock_free::{Consumer, Producer};
 use std::sync::Arc;
-use std::time::Instant;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 use types::FixedPointArithmetic;
 use utils::{field_str, market_name, number_to_bytes};
 
+/// Results popped from one result queue before moving on to the next one, so a busy symbol cannot starve the others.
+const RESULT_QUEUE_BATCH: usize = 64;
+
+/// Result queue of one order book engine, see `ExecutionReportEngine::add_result_queue`.
+struct ResultQueue<const N: usize> {
+    consumer: Consumer<'static, (OrderEvent, OrderResult), N>,
+    /// Set once the end-of-stream sentinel of the order book engine has been popped.
+    closed: bool,
+}
+
 pub struct ExecutionReportEngine<const N: usize> {
     fifo_in: crossbeam_channel::Receiver<(OrderEvent, OrderResult)>,
     fifo_out: Producer<'static, (EntityId, ExecutionReportMessage<N>), N>,
     shutdown: Arc<AtomicBool>,
     metrics: Option<Arc<types::MarketMetrics>>,
+    result_queues: Vec<ResultQueue<N>>,
+    /// Results copied out of a result queue by one `pop_slice`, reused across polls.
+    result_buffer: Vec<(OrderEvent, OrderResult)>,
 }
 
 impl<const N: usize> ExecutionReportEngine<N> {
@@ -31,6 +44,11 @@ impl<const N: usize> ExecutionReportEngine<N> {
             fifo_out,
             shutdown,
             metrics: None,
+            result_queues: Vec::new(),
+            result_buffer: vec![
+                (OrderEvent::default(), OrderResult::default());
+                RESULT_QUEUE_BATCH
+            ],
         }
     }
 
@@ -38,31 +56,41 @@ impl<const N: usize> ExecutionReportEngine<N> {
         self.metrics = Some(metrics);
     }
 
-    pub fn run(&self) -> Result<(), Box<dyn std::error::Error>> {
+    /// Also reads the results pushed by an order book engine into `consumer`, see `OrderBookEngine::set_result_queue`.
+    /// Call it once per order book engine: every result queue has a single producer.
+    pub fn add_result_queue(&mut self, consumer: Consumer<'static, (OrderEvent, OrderResult), N>) {
+        self.result_queues.push(ResultQueue {
+            consumer,
+            closed: false,
+        });
+    }
+
+    /// Runs until the shutdown flag is set, or until the input channel is disconnected and every result queue has
+    /// delivered its end-of-stream sentinel.
+    pub fn run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut fifo_in_open = true;
+
         while !self.shutdown.load(Ordering::Relaxed) {
-            match self
-                .fifo_in
-                .recv_timeout(std::time::Duration::from_millis(1))
-            {
-                Ok(exec_report) => {
-                    let received_at = Instant::now();
-                    self.process_execution_report(&exec_report);
-                    if let Some(metrics) = &self.metrics {
-                        metrics
-                            .execution_report_events
-                            .fetch_add(1, Ordering::Relaxed);
-                        let elapsed_ms = received_at.elapsed().as_millis() as u64;
-                        if let Ok(mut samples) =
-                            metrics.execution_report_event_to_fanout_latency_ms.lock()
-                        {
-                            samples.push(elapsed_ms);
-                        }
+            let handled = self.poll_result_queues();
+
+            if fifo_in_open {
+                // Only wait on the channel when the result queues were idle
+                let timeout = if handled {
+                    Duration::ZERO
+                } else {
+                    Duration::from_millis(1)
+                };
+                match self.fifo_in.recv_timeout(timeout) {
+                    Ok(exec_report) => self.handle_execution_report(&exec_report),
+                    Err(crossbeam_channel::RecvTimeoutError::Timeout) => {}
+                    Err(crossbeam_channel::RecvTimeoutError::Disconnected) => {
+                        fifo_in_open = false;
                     }
                 }
-                Err(crossbeam_channel::RecvTimeoutError::Timeout) => {}
-                Err(crossbeam_channel::RecvTimeoutError::Disconnected) => {
-                    break;
-                }
+            } else if self.result_queues.iter().all(|queue| queue.closed) {
+                break;
+            } else if !handled {
+                std::hint::spin_loop();
             }
         }
 
@@ -93,6 +121,45 @@ impl<const N: usize> ExecutionReportEngine<N> {
         Ok(())
     }
 
+    /// Handles up to `RESULT_QUEUE_BATCH` results of each open result queue, returns whether any was popped.
+    /// The results of a queue are copied out with a single `pop_slice`, one tail update per queue and poll.
+    fn poll_result_queues(&mut self) -> bool {
+        let mut handled = false;
+        for index in 0..self.result_queues.len() {
+            if self.result_queues[index].closed {
+                continue;
+            }
+            let popped = self.result_queues[index]
+                .consumer
+                .pop_slice(&mut self.result_buffer);
+            for position in 0..popped {
+                handled = true;
+                let exec_report = self.result_buffer[position];
+                if exec_report.0.is_end_of_stream() {
+                    // Nothing is pushed after the sentinel
+                    self.result_queues[index].closed = true;
+                    break;
+                }
+                self.handle_execution_report(&exec_report);
+            }
+        }
+        handled
+    }
+
+    fn handle_execution_report(&self, exec_report: &(OrderEvent, OrderResult)) {
+        let received_at = Instant::now();
+        self.process_execution_report(exec_report);
+        if let Some(metrics) = &self.metrics {
+            metrics
+                .execution_report_events
+                .fetch_add(1, Ordering::Relaxed);
+            let elapsed_ms = received_at.elapsed().as_millis() as u64;
+            if let Ok(mut samples) = metrics.execution_report_event_to_fanout_latency_ms.lock() {
+                samples.push(elapsed_ms);
+            }
+        }
+    }
+
     fn build_cancel_report(&self, exec_report: &(OrderEvent, OrderResult)) -> FixRawMsg<N> {
         let mut report = FixRawMsg::<N>::default();
         let mut cursor = 0;
@@ -446,7 +513,7 @@ impl<const N: usize> ExecutionReportEngine<N> {
         );
         self.build_field(
             tags::TARGET_COMP_ID,
-            &order.sender_id.as_ref(),
+            &Self::maker_session(order, trade).as_ref(),
             &mut report,
             &mut cursor,
         );
@@ -525,6 +592,26 @@ impl<const N: usize> ExecutionReportEngine<N> {
         }
     }
 
+    /// Text (58) of the report of an order rejected by the order book.
+    fn reject_text(status: types::OrderStatus) -> &'static [u8] {
+        match status {
+            types::OrderStatus::RejectedInvalidQuantity => b"Invalid quantity",
+            types::OrderStatus::RejectedInvalidPrice => b"Invalid price",
+            types::OrderStatus::RejectedInvalidLot => b"Quantity is not a multiple of the lot size",
+            types::OrderStatus::RejectedMinQuantity => b"Minimum quantity not executable",
+            types::OrderStatus::RejectedExposureLimit => b"Gross exposure limit exceeded",
+            _ => b"Price level full",
+        }
+    }
+
+    fn exec_data_for_reject(order_event: &OrderEvent) -> ExecReportData {
+        ExecReportData {
+            ord_status: 8u8,
+            leaves_qty: 0.0,
+            ..Self::exec_data_for_new(order_event)
+        }
+    }
+
     fn exec_data_for_trade_report(
         order_event: &OrderEvent,
         order_result: &OrderResult,
@@ -545,6 +632,16 @@ impl<const N: usize> ExecutionReportEngine<N> {
         }
     }
 
+    /// Session the trade report of the resting order of `trade` is routed to. A resting order without a
+    /// SenderCompID, only built by tests and tools, has its reports routed to the session of the aggressor `order`.
+    fn maker_session(order: &OrderEvent, trade: &types::Trade) -> EntityId {
+        if trade.sender_id == EntityId::default() {
+            order.sender_id
+        } else {
+            trade.sender_id
+        }
+    }
+
     fn exec_data_for_maker_trade(
         order_event: &OrderEvent,
         trade: &types::Trade,
@@ -611,7 +708,9 @@ impl<const N: usize> ExecutionReportEngine<N> {
     }
 
     fn process_execution_report(&self, exec_report: &(OrderEvent, OrderResult)) {
-        let mut reports: Vec<(FixRawMsg<N>, ExecReportData)> = vec![];
+        // Reports of the aggressor go to its session, the trade reports of the resting orders to theirs
+        let taker = exec_report.0.sender_id;
+        let mut reports: Vec<(EntityId, FixRawMsg<N>, ExecReportData)> = vec![];
 
         match exec_report.1.status {
             types::OrderStatus::Unmatched => {
@@ -620,18 +719,33 @@ impl<const N: usize> ExecutionReportEngine<N> {
             }
             types::OrderStatus::Cancelled | types::OrderStatus::CancelRejected => {
                 reports.push((
+                    taker,
                     self.build_cancel_report(exec_report),
                     Self::exec_data_for_cancel(&exec_report.0, &exec_report.1),
                 ));
             }
+            types::OrderStatus::RejectedLevelFull
+            | types::OrderStatus::RejectedInvalidQuantity
+            | types::OrderStatus::RejectedInvalidPrice
+            | types::OrderStatus::RejectedInvalidLot
+            | types::OrderStatus::RejectedMinQuantity
+            | types::OrderStatus::RejectedExposureLimit => {
+                reports.push((
+                    taker,
+                    self.build_new_execution_report(exec_report),
+                    Self::exec_data_for_reject(&exec_report.0),
+                ));
+            }
             _ => {
                 reports.push((
+                    taker,
                     self.build_new_execution_report(exec_report),
                     Self::exec_data_for_new(&exec_report.0),
                 ));
 
                 if exec_report.1.trades.len() > 0 {
                     reports.push((
+                        taker,
                         self.build_execution_report(exec_report),
                         Self::exec_data_for_trade_report(&exec_report.0, &exec_report.1),
                     ));
@@ -644,6 +758,7 @@ impl<const N: usize> ExecutionReportEngine<N> {
                     for trade in exec_report.1.trades.iter() {
                         if trade.cl_ord_id != exec_report.0.cl_ord_id {
                             reports.push((
+                                Self::maker_session(&exec_report.0, trade),
                                 self.build_execution_report_for_trade(
                                     &exec_report.0,
                                     trade,
@@ -657,8 +772,7 @@ impl<const N: usize> ExecutionReportEngine<N> {
             }
         }
 
-        let key = exec_report.0.sender_id;
-        for (report, exec_report_data) in reports.into_iter() {
+        for (key, report, exec_report_data) in reports.into_iter() {
             let msg = ExecutionReportMessage::new(report.len, report.data, exec_report_data);
             loop {
                 if let Err((_, _msg)) = self.fifo_out.push((key, msg.clone())) {
@@ -688,18 +802,15 @@ impl<const N: usize> ExecutionReportEngine<N> {
         );
 
         // Set NEW status for all new execution reports, since this is the first report being sent for a new order.
-        self.build_field(
-            tags::ORD_STATUS,
-            ord_status_code_set::NEW,
-            &mut report,
-            &mut cursor,
-        ); // OrdStatus=New
-        self.build_field(
-            tags::EXEC_TYPE,
-            exec_type_code_set::NEW,
-            &mut report,
-            &mut cursor,
-        ); // ExecType=New
+        // Orders rejected by the order book get this single report, with a Rejected status instead.
+        let rejected = order_result.status.is_rejected();
+        let (ord_status, exec_type) = if rejected {
+            (ord_status_code_set::REJECTED, exec_type_code_set::REJECTED)
+        } else {
+            (ord_status_code_set::NEW, exec_type_code_set::NEW)
+        };
+        self.build_field(tags::ORD_STATUS, ord_status, &mut report, &mut cursor); // OrdStatus=New or Rejected
+        self.build_field(tags::EXEC_TYPE, exec_type, &mut report, &mut cursor); // ExecType=New or Rejected
 
         self.build_field(
             tags::ORDER_ID,
@@ -735,9 +846,14 @@ impl<const N: usize> ExecutionReportEngine<N> {
             &mut report,
             &mut cursor,
         );
+        let leaves_qty = if rejected {
+            FixedPointArithmetic::ZERO
+        } else {
+            order.quantity
+        };
         self.build_field(
             tags::LEAVES_QTY,
-            &order.quantity.to_fix_bytes(),
+            &leaves_qty.to_fix_bytes(),
             &mut report,
             &mut cursor,
         );
@@ -747,6 +863,14 @@ impl<const N: usize> ExecutionReportEngine<N> {
             &mut report,
             &mut cursor,
         );
+        if rejected {
+            self.build_field(
+                tags::TEXT,
+                Self::reject_text(order_result.status),
+                &mut report,
+                &mut cursor,
+            );
+        }
 
         // Switch sender and target for the execution report since it's going back to the client
         self.build_field(
@@ -845,7 +969,7 @@ mod tests {
         let (fifo_out_tx, fifo_out_rx) = rb_out.split();
 
         let shutdown_signal = Arc::new(AtomicBool::new(false));
-        let engine =
+        let mut engine =
             ExecutionReportEngine::new(fifo_in_rx, fifo_out_tx, Arc::clone(&shutdown_signal));
 
         std::thread::scope(|s| {
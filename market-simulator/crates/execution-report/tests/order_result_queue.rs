@@ -0,0 +1,115 @@
+//! Order book and FIX halves of a market, linked by an order result queue in shared memory.
+
+use execution_report::ExecutionReportEngine;
+use fix::builder::FixMessageBuilder;
+use fix::engine::{FixEngine, FixRawMsg, kill_fix_inbound_engine};
+use order_book::book::OrderBook;
+use order_book::engine::{OrderBookEngine, RESULT_QUEUE_MAX_WAIT};
+use spsc::spsc_lock_free::RingBuffer;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use types::macros::EntityId;
+use types::{ExecutionReportMessage, FixedPointArithmetic, OrderEvent, OrderResult, Side};
+
+const N: usize = 1024;
+
+#[test]
+fn test_client_receives_report_through_order_result_queue() {
+    let queue_name = format!("test_{}_order_result_queue", std::process::id());
+
+    // Order book half creates the queue, the FIX half attaches to it through the file
+    let ob_side = memory::open_shared_queue::<N, (OrderEvent, OrderResult)>(&queue_name, true);
+    let fix_side = memory::open_shared_queue::<N, (OrderEvent, OrderResult)>(&queue_name, false);
+    let (result_tx, _) = ob_side.queue.split();
+    let (_, result_rx) = fix_side.queue.split();
+
+    let (net_to_fix_tx, net_to_fix_rx) = crossbeam_channel::bounded::<FixRawMsg<N>>(N);
+    let mut fix_to_ob = RingBuffer::<OrderEvent, N>::new();
+    let er_to_fix = Box::leak(Box::new(RingBuffer::<
+        (EntityId, ExecutionReportMessage<N>),
+        N,
+    >::new()));
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let metrics = Arc::new(types::MarketMetrics::new());
+
+    std::thread::scope(|scope| {
+        let (fix_to_ob_tx, fix_to_ob_rx) = fix_to_ob.split();
+        let (er_to_fix_tx, er_to_fix_rx) = er_to_fix.split();
+
+        // Order book half
+        let (_control_tx, control_rx) = crossbeam_channel::unbounded();
+        let mut order_book_engine = OrderBookEngine::new(
+            fix_to_ob_rx,
+            None,
+            None,
+            None,
+            control_rx,
+            OrderBook::new("EURUSD"),
+            None,
+            Arc::clone(&shutdown),
+        );
+        order_book_engine.set_result_queue(result_tx, RESULT_QUEUE_MAX_WAIT);
+
+        // FIX half, results are only read from the shared queue
+        let (_, er_rx) = crossbeam_channel::unbounded::<(OrderEvent, OrderResult)>();
+        let mut execution_report_engine =
+            ExecutionReportEngine::new(er_rx, er_to_fix_tx, Arc::clone(&shutdown));
+        execution_report_engine.add_result_queue(result_rx);
+        let (mut inbound_engine, mut outbound_engine) = FixEngine::new(
+            Arc::new(net_to_fix_rx),
+            fix_to_ob_tx,
+            er_to_fix_rx,
+            Arc::clone(&shutdown),
+            Arc::clone(&metrics),
+        )
+        .split();
+
+        let handles = [
+            scope.spawn(move || order_book_engine.run().unwrap()),
+            scope.spawn(move || execution_report_engine.run().unwrap()),
+            scope.spawn(move || inbound_engine.run().unwrap()),
+            scope.spawn(move || outbound_engine.run().unwrap()),
+        ];
+
+        let (client_tx, mut client_rx) =
+            tokio::sync::mpsc::channel::<ExecutionReportMessage<N>>(16);
+        let order = FixMessageBuilder::new(b"FIX.4.2", b"CLIENT", b"MARKET")
+            .msg_seq_num(1)
+            .sending_time(utils::UtcTimestamp::from_fix_bytes(b"20240219-12:30:00.000").unwrap())
+            .cl_ord_id(b"ORD-1")
+            .side(Side::Buy)
+            .order_qty(FixedPointArithmetic::from_number(10))
+            .price(FixedPointArithmetic::from_f64(1.2345))
+            .symbol(b"EURUSD")
+            .build();
+        net_to_fix_tx
+            .send(FixRawMsg::new(&order, Some(client_tx)))
+            .unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let report = loop {
+            if let Ok(report) = client_rx.try_recv() {
+                break report;
+            }
+            assert!(Instant::now() < deadline, "no execution report received");
+            std::thread::sleep(Duration::from_millis(1));
+        };
+
+        let fix = String::from_utf8_lossy(&report.fix_data[..report.fix_len as usize]).to_string();
+        assert!(fix.contains("35=8\x01"), "not an execution report: {fix}");
+        assert_eq!(report.exec_report_data.cl_ord_id, "ORD-1");
+        assert_eq!(
+            metrics.order_result_queue_dropped.load(Ordering::Relaxed),
+            0
+        );
+
+        shutdown.store(true, Ordering::Release);
+        kill_fix_inbound_engine(&net_to_fix_tx);
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    });
+
+    let _ = std::fs::remove_file(format!("/dev/shm/{queue_name}"));
+}
@@ -0,0 +1,273 @@
+//! Whole market behind a TCP listener: FIX sessions served by `fix::session`, the FIX engine, the order book engine and
+//! the execution report engine, linked by in-memory queues the way `main` links them.
+
+use execution_report::ExecutionReportEngine;
+use fix::builder::FixMessageBuilder;
+use fix::engine::{FixEngine, FixRawMsg, kill_fix_inbound_engine};
+use fix::framing::FramingLimits;
+use fix::parser::FixParser;
+use fix::session::serve_session;
+use fix::tags::{msg_types, ord_status_code_set, tags};
+use order_book::Depth;
+use order_book::book::OrderBook;
+use order_book::engine::OrderBookEngine;
+use spsc::spsc_lock_free::RingBuffer;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::OwnedReadHalf;
+use tokio::net::{TcpListener, TcpStream};
+use types::macros::EntityId;
+use types::{ExecutionReportMessage, FixedPointArithmetic, OrderEvent, OrderResult, Side};
+
+const N: usize = 1024;
+/// Time any single message is waited for before the test fails.
+const RECV_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Fields of a received message by tag.
+fn fields(message: &[u8]) -> HashMap<u32, String> {
+    FixParser::new(message)
+        .get_fields()
+        .fields
+        .iter()
+        .map(|field| (field.tag, String::from_utf8_lossy(field.value).to_string()))
+        .collect()
+}
+
+/// Length of the first complete message of `buffer`, up to the SOH closing its CheckSum field.
+/// Execution reports are written with their BodyLength field last, so they are split on the trailer instead.
+fn message_len(buffer: &[u8]) -> Option<usize> {
+    let trailer = buffer.windows(4).position(|window| window == b"\x0110=")?;
+    let end = buffer[trailer + 1..]
+        .iter()
+        .position(|&byte| byte == b'\x01')?;
+    Some(trailer + 1 + end + 1)
+}
+
+/// TCP client of one FIX session.
+struct Client {
+    sender: &'static [u8],
+    reader: OwnedReadHalf,
+    writer: tokio::net::tcp::OwnedWriteHalf,
+    buffer: Vec<u8>,
+    seq_num: u64,
+}
+
+impl Client {
+    /// Connects to `addr` and logs on as `sender`.
+    async fn logon(addr: std::net::SocketAddr, sender: &'static [u8]) -> Self {
+        let (reader, writer) = TcpStream::connect(addr).await.unwrap().into_split();
+        let mut client = Self {
+            sender,
+            reader,
+            writer,
+            buffer: Vec::new(),
+            seq_num: 0,
+        };
+
+        let logon = client
+            .builder()
+            .msg_type(msg_types::LOGON)
+            .raw_tag(98, b"0")
+            .raw_tag(108, b"30")
+            .build();
+        client.writer.write_all(&logon).await.unwrap();
+
+        let ack = client.recv().await;
+        assert_eq!(ack[&tags::MSG_TYPE].as_bytes(), msg_types::LOGON);
+        assert_eq!(ack[&tags::TARGET_COMP_ID].as_bytes(), sender);
+        client
+    }
+
+    fn builder(&mut self) -> FixMessageBuilder {
+        self.seq_num += 1;
+        FixMessageBuilder::new(b"FIX.4.2", self.sender, b"MARKET")
+            .msg_seq_num(self.seq_num)
+            .sending_time(utils::UtcTimestamp::now())
+    }
+
+    async fn send_order(&mut self, cl_ord_id: &[u8], side: Side, qty: u32, price: u32) {
+        let order = self
+            .builder()
+            .cl_ord_id(cl_ord_id)
+            .side(side)
+            .order_qty(FixedPointArithmetic::from_number(qty))
+            .price(FixedPointArithmetic::from_number(price))
+            .symbol(b"EURUSD")
+            .build();
+        self.writer.write_all(&order).await.unwrap();
+    }
+
+    /// Next message of the session, failing the test after `RECV_TIMEOUT`.
+    async fn recv(&mut self) -> HashMap<u32, String> {
+        let deadline = tokio::time::Instant::now() + RECV_TIMEOUT;
+        let mut chunk = [0u8; N];
+        loop {
+            if let Some(len) = message_len(&self.buffer) {
+                let message: Vec<u8> = self.buffer.drain(..len).collect();
+                return fields(&message);
+            }
+            let read = tokio::time::timeout_at(deadline, self.reader.read(&mut chunk))
+                .await
+                .expect("no message received")
+                .unwrap();
+            assert!(read > 0, "connection closed by the server");
+            self.buffer.extend_from_slice(&chunk[..read]);
+        }
+    }
+
+    /// Skips the messages of the session until the execution report of `cl_ord_id` with `ord_status`.
+    async fn expect_report(&mut self, cl_ord_id: &str, ord_status: &[u8]) -> HashMap<u32, String> {
+        loop {
+            let report = self.recv().await;
+            assert_eq!(
+                report[&tags::MSG_TYPE].as_bytes(),
+                msg_types::EXECUTION_REPORT
+            );
+            assert_eq!(report[&tags::TARGET_COMP_ID].as_bytes(), self.sender);
+            if report[&tags::CL_ORD_ID] == cl_ord_id
+                && report[&tags::ORD_STATUS].as_bytes() == ord_status
+            {
+                return report;
+            }
+        }
+    }
+}
+
+#[test]
+fn test_crossing_orders_of_two_tcp_sessions() {
+    let start = Instant::now();
+    let (net_to_fix_tx, net_to_fix_rx) = crossbeam_channel::bounded::<FixRawMsg<N>>(N);
+    let mut fix_to_ob = RingBuffer::<OrderEvent, N>::new();
+    let er_to_fix = Box::leak(Box::new(RingBuffer::<
+        (EntityId, ExecutionReportMessage<N>),
+        N,
+    >::new()));
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let metrics = Arc::new(types::MarketMetrics::new());
+
+    let depth = std::thread::scope(|scope| {
+        let (fix_to_ob_tx, fix_to_ob_rx) = fix_to_ob.split();
+        let (er_to_fix_tx, er_to_fix_rx) = er_to_fix.split();
+
+        let (ob_to_er_tx, ob_to_er_rx) =
+            crossbeam_channel::unbounded::<(OrderEvent, OrderResult)>();
+        let (_control_tx, control_rx) = crossbeam_channel::unbounded();
+        let mut order_book_engine = OrderBookEngine::new(
+            fix_to_ob_rx,
+            Some(Arc::new(ob_to_er_tx)),
+            None,
+            None,
+            control_rx,
+            OrderBook::new("EURUSD"),
+            None,
+            Arc::clone(&shutdown),
+        );
+        let mut execution_report_engine =
+            ExecutionReportEngine::new(ob_to_er_rx, er_to_fix_tx, Arc::clone(&shutdown));
+        let (mut inbound_engine, mut outbound_engine) = FixEngine::new(
+            Arc::new(net_to_fix_rx),
+            fix_to_ob_tx,
+            er_to_fix_rx,
+            Arc::clone(&shutdown),
+            Arc::clone(&metrics),
+        )
+        .split();
+
+        let order_book_handle = scope.spawn(move || {
+            order_book_engine.run().unwrap();
+            order_book_engine
+        });
+        let handles = [
+            scope.spawn(move || execution_report_engine.run().unwrap()),
+            scope.spawn(move || inbound_engine.run().unwrap()),
+            scope.spawn(move || outbound_engine.run().unwrap()),
+        ];
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_all()
+            .build()
+            .unwrap();
+        // The engines are stopped whatever the outcome of the sessions, a failed assertion would hang the scope
+        let sessions = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            runtime.block_on(async {
+                let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+                let addr = listener.local_addr().unwrap();
+                let server_tx = net_to_fix_tx.clone();
+                tokio::spawn(async move {
+                    while let Ok((stream, _)) = listener.accept().await {
+                        tokio::spawn(serve_session(
+                            stream,
+                            FramingLimits::default(),
+                            server_tx.clone(),
+                        ));
+                    }
+                });
+
+                let mut maker = Client::logon(addr, b"MAKER").await;
+                let mut taker = Client::logon(addr, b"TAKER").await;
+
+                maker.send_order(b"SELL-1", Side::Sell, 10, 100).await;
+                let resting = maker
+                    .expect_report("SELL-1", ord_status_code_set::NEW)
+                    .await;
+                assert_eq!(resting[&tags::SIDE], "2");
+
+                // Crosses the resting sell, partially filling it
+                taker.send_order(b"BUY-1", Side::Buy, 6, 101).await;
+                taker.expect_report("BUY-1", ord_status_code_set::NEW).await;
+                let fill = taker
+                    .expect_report("BUY-1", ord_status_code_set::FILL)
+                    .await;
+                assert_eq!(fill[&tags::SIDE], "1");
+                assert_eq!(fill[&tags::LEAVES_QTY].parse::<f64>().unwrap(), 0.0);
+                // The maker is told of the fill of its resting order on its own session
+                let partial_fill = maker
+                    .expect_report("SELL-1", ord_status_code_set::PARTIAL_FILL)
+                    .await;
+                assert_eq!(partial_fill[&tags::SIDE], "2");
+                assert_eq!(partial_fill[&tags::LEAVES_QTY].parse::<f64>().unwrap(), 4.0);
+
+                // Rests below the remaining ask
+                taker.send_order(b"BUY-2", Side::Buy, 5, 99).await;
+                taker.expect_report("BUY-2", ord_status_code_set::NEW).await;
+            })
+        }));
+        // Drops the listener and the sessions, closing their response queues
+        runtime.shutdown_timeout(Duration::from_secs(1));
+
+        shutdown.store(true, Ordering::Release);
+        kill_fix_inbound_engine(&net_to_fix_tx);
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        let order_book_engine = order_book_handle.join().unwrap();
+        if let Err(panic) = sessions {
+            std::panic::resume_unwind(panic);
+        }
+        order_book_engine.core().depth(5)
+    });
+
+    assert_eq!(
+        depth,
+        Depth {
+            bids: vec![(
+                FixedPointArithmetic::from_number(99),
+                FixedPointArithmetic::from_number(5)
+            )],
+            asks: vec![(
+                FixedPointArithmetic::from_number(100),
+                FixedPointArithmetic::from_number(4)
+            )],
+        }
+    );
+    assert_eq!(metrics.fix_response_dropped.load(Ordering::Relaxed), 0);
+    assert!(
+        start.elapsed() < Duration::from_secs(5),
+        "took {:?}",
+        start.elapsed()
+    );
+}
@@ -1,10 +1,12 @@
 use crossbeam_channel::Sender;
-use order_book::OrderBookControl;
+use order_book::analytics::SideAnalytics;
+use order_book::{ExecutionAnalytics, OrderBookControl, SharedPositionTracker};
 use sqlx::PgPool;
-use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, PoisonError};
 use std::time::Duration;
 use tonic::{Request, Response, Status, transport::Server};
+use types::EntityId;
 
 // Include the generated protobuf/gRPC bindings.
 pub mod proto {
@@ -13,7 +15,8 @@ pub mod proto {
 
 use proto::{
     DumpOrderBookRequest, DumpOrderBookResponse, GetLastTradesRequest, GetLastTradesResponse,
-    PendingOrder, ResetRequest, ResetResponse, Trade,
+    GetPositionsRequest, GetPositionsResponse, GetStatsRequest, GetStatsResponse, PendingOrder,
+    ResetRequest, ResetResponse, SideStats, SymbolPosition, SymbolStats, Trade,
     market_control_server::{MarketControl, MarketControlServer},
 };
 
@@ -24,13 +27,20 @@ pub struct MarketControlService {
     ob_control_txs: Vec<Sender<OrderBookControl>>,
     /// Database pool used to reset the persisted state.
     db_pool: Arc<PgPool>,
+    /// Positions of the participants, shared with the order-book engines.
+    positions: SharedPositionTracker,
 }
 
 impl MarketControlService {
-    pub fn new(ob_control_txs: Vec<Sender<OrderBookControl>>, db_pool: Arc<PgPool>) -> Self {
+    pub fn new(
+        ob_control_txs: Vec<Sender<OrderBookControl>>,
+        db_pool: Arc<PgPool>,
+        positions: SharedPositionTracker,
+    ) -> Self {
         Self {
             ob_control_txs,
             db_pool,
+            positions,
         }
     }
 }
@@ -186,6 +196,116 @@ impl MarketControl for MarketControlService {
             }
         }
     }
+
+    /// Returns the execution quality analytics of each order book since its last reset.
+    async fn get_stats(
+        &self,
+        _request: Request<GetStatsRequest>,
+    ) -> Result<Response<GetStatsResponse>, Status> {
+        tracing::info!("gRPC GetStats called");
+
+        let ob_control_txs = self.ob_control_txs.clone();
+        let stats_result = tokio::task::spawn_blocking(move || {
+            let mut reply_receivers = Vec::with_capacity(ob_control_txs.len());
+
+            for ob_control_tx in ob_control_txs {
+                let (reply_tx, reply_rx) = crossbeam_channel::bounded(1);
+                ob_control_tx
+                    .send(OrderBookControl::Stats { reply: reply_tx })
+                    .map_err(|e| format!("Failed to send stats request to order book: {e}"))?;
+                reply_receivers.push(reply_rx);
+            }
+
+            reply_receivers
+                .into_iter()
+                .map(|reply_rx| {
+                    reply_rx
+                        .recv_timeout(Duration::from_secs(5))
+                        .map(|(symbol, analytics)| symbol_stats(symbol, &analytics))
+                        .map_err(|_| "Order book stats timed out after 5 seconds".to_string())
+                })
+                .collect::<Result<Vec<_>, String>>()
+        })
+        .await;
+
+        let msg = match stats_result {
+            Ok(Ok(stats)) => {
+                return Ok(Response::new(GetStatsResponse {
+                    success: true,
+                    message: "Order book stats retrieved successfully".to_string(),
+                    stats,
+                }));
+            }
+            Ok(Err(msg)) => msg,
+            Err(e) => format!("Stats task panicked: {e}"),
+        };
+        tracing::error!("{msg}");
+        Ok(Response::new(GetStatsResponse {
+            success: false,
+            message: msg,
+            stats: Vec::new(),
+        }))
+    }
+
+    /// Returns the positions of a participant in each symbol and its gross exposure across all of them.
+    async fn get_positions(
+        &self,
+        request: Request<GetPositionsRequest>,
+    ) -> Result<Response<GetPositionsResponse>, Status> {
+        let sender_id = request.into_inner().sender_id;
+        tracing::info!("gRPC GetPositions called for '{sender_id}'");
+
+        let sender = EntityId::from_ascii(sender_id.trim());
+        let positions = self
+            .positions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let symbol_positions: Vec<SymbolPosition> = positions
+            .positions(sender)
+            .into_iter()
+            .map(|(symbol, position)| SymbolPosition {
+                symbol: symbol.to_string(),
+                net_quantity: position.net_quantity.to_f64(),
+                realized_notional: position.realized_notional.to_f64(),
+                open_quantity: position.open_quantity.to_f64(),
+                open_notional: position.open_notional.to_f64(),
+            })
+            .collect();
+
+        Ok(Response::new(GetPositionsResponse {
+            success: true,
+            message: if symbol_positions.is_empty() {
+                format!("No position for '{sender_id}'")
+            } else {
+                format!("Positions of '{sender_id}' retrieved successfully")
+            },
+            positions: symbol_positions,
+            gross_exposure: positions.gross_exposure(sender).to_f64(),
+            max_gross_exposure: positions.max_gross_exposure().map(|limit| limit.to_f64()),
+        }))
+    }
+}
+
+fn side_stats(analytics: &SideAnalytics) -> SideStats {
+    SideStats {
+        trades: analytics.trades,
+        improved_trades: analytics.improved_trades,
+        avg_effective_spread: analytics
+            .average_effective_spread()
+            .map(|spread| spread.to_f64()),
+        price_improvement_pct: analytics.price_improvement_pct(),
+    }
+}
+
+fn symbol_stats(symbol: String, analytics: &ExecutionAnalytics) -> SymbolStats {
+    SymbolStats {
+        symbol,
+        all: Some(side_stats(&analytics.total())),
+        buy: Some(side_stats(&analytics.buy)),
+        sell: Some(side_stats(&analytics.sell)),
+        empty_opposite_side: analytics.empty_opposite_side,
+        no_midpoint: analytics.no_midpoint,
+    }
 }
 
 /// Start the gRPC server on the given address (e.g. `"[::1]:50051"`).
@@ -0,0 +1,7 @@
+pub mod reader;
+pub mod record;
+pub mod writer;
+
+pub use self::reader::read_journal;
+pub use self::record::{JournalEntry, JournalRecord};
+pub use self::writer::{JournalTap, start_journal};
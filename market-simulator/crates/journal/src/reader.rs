@@ -0,0 +1,62 @@
+use crate::record::JournalRecord;
+use crate::writer::segment_number;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+/// Reads a journal back in write order.
+/// # Arguments
+/// * `path` - A single segment file, or a journal directory whose segments are read in sequence order
+pub fn read_journal(path: &Path) -> io::Result<Vec<JournalRecord>> {
+    let mut records = Vec::new();
+    for segment in journal_segments(path)? {
+        read_segment(&segment, &mut records)?;
+    }
+    Ok(records)
+}
+
+fn journal_segments(path: &Path) -> io::Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut segments: Vec<(usize, PathBuf)> = Vec::new();
+    for entry in std::fs::read_dir(path)? {
+        let path = entry?.path();
+        if let Some(segment) = segment_number(&path) {
+            segments.push((segment, path));
+        }
+    }
+    segments.sort();
+    Ok(segments.into_iter().map(|(_, path)| path).collect())
+}
+
+fn read_segment(path: &Path, records: &mut Vec<JournalRecord>) -> io::Result<()> {
+    let file = std::fs::File::open(path)?;
+    let mut reader = BufReader::new(file);
+
+    if path.extension().is_some_and(|ext| ext == "jsonl") {
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let value: serde_json::Value = serde_json::from_str(&line)?;
+            records.push(JournalRecord::from_json(&value)?);
+        }
+        return Ok(());
+    }
+
+    let mut len = [0u8; 4];
+    let mut payload = Vec::new();
+    loop {
+        match reader.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        }
+        payload.resize(u32::from_be_bytes(len) as usize, 0);
+        reader.read_exact(&mut payload)?;
+        records.push(JournalRecord::decode(&payload)?);
+    }
+    Ok(())
+}
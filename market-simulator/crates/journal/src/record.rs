@@ -0,0 +1,644 @@
+use serde_json::{Value, json};
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+use types::macros::{EntityId, OrderId, SymbolId};
+use types::{
+    FixedPointArithmetic, OrderEvent, OrderResult, OrderStatus, OrderType, Side, Trade, Trades,
+};
+
+// Binary record format
+//
+// Every record is written as a 4-byte big-endian payload length followed by the payload.
+// Payload format:
+// - 1 byte: record kind (see the KIND_* constants)
+// - 8 bytes: timestamp in nanoseconds since epoch (u64)
+// - kind specific body:
+//   - FIX inbound: the raw FIX bytes
+//   - order event: encoded order event (see `put_order_event`)
+//   - order result: encoded order event followed by the encoded order result
+//   - reset: 4 bytes symbol
+//   - dropped result: same body as an order result
+// All integers are big-endian, identifiers are written as their fixed-size byte arrays.
+
+const KIND_FIX_INBOUND: u8 = 0;
+const KIND_ORDER_EVENT: u8 = 1;
+const KIND_ORDER_RESULT: u8 = 2;
+const KIND_RESET: u8 = 3;
+const KIND_DROPPED_RESULT: u8 = 4;
+
+/// A single entry of the session journal, captured at one of the tap points.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JournalEntry {
+    /// Raw FIX message as received by the inbound FIX engine.
+    FixInbound(Vec<u8>),
+    /// Order event as processed by an order book engine.
+    OrderEvent(OrderEvent),
+    /// Result of an order event, as fanned out by an order book engine.
+    OrderResult(OrderEvent, OrderResult),
+    /// Order book of the symbol reset through the control channel.
+    Reset(SymbolId),
+    /// Result an order book engine dropped because its result queue stayed full, the client never got its report.
+    DroppedResult(OrderEvent, OrderResult),
+}
+
+/// Timestamped journal entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalRecord {
+    pub timestamp_ns: u64,
+    pub entry: JournalEntry,
+}
+
+impl JournalRecord {
+    pub fn new(timestamp_ns: u64, entry: JournalEntry) -> Self {
+        Self {
+            timestamp_ns,
+            entry,
+        }
+    }
+
+    /// Record stamped with the current time.
+    pub fn now(entry: JournalEntry) -> Self {
+        let timestamp_ns = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos() as u64;
+        Self::new(timestamp_ns, entry)
+    }
+
+    /// Appends the length-prefixed binary encoding of the record to `buf`.
+    pub fn encode(&self, buf: &mut Vec<u8>) {
+        let start = buf.len();
+        buf.extend_from_slice(&[0u8; 4]);
+
+        match &self.entry {
+            JournalEntry::FixInbound(bytes) => {
+                buf.push(KIND_FIX_INBOUND);
+                buf.extend_from_slice(&self.timestamp_ns.to_be_bytes());
+                buf.extend_from_slice(bytes);
+            }
+            JournalEntry::OrderEvent(event) => {
+                buf.push(KIND_ORDER_EVENT);
+                buf.extend_from_slice(&self.timestamp_ns.to_be_bytes());
+                put_order_event(buf, event);
+            }
+            JournalEntry::OrderResult(event, result) => {
+                buf.push(KIND_ORDER_RESULT);
+                buf.extend_from_slice(&self.timestamp_ns.to_be_bytes());
+                put_order_event(buf, event);
+                put_order_result(buf, result);
+            }
+            JournalEntry::Reset(symbol) => {
+                buf.push(KIND_RESET);
+                buf.extend_from_slice(&self.timestamp_ns.to_be_bytes());
+                buf.extend_from_slice(&symbol.0);
+            }
+            JournalEntry::DroppedResult(event, result) => {
+                buf.push(KIND_DROPPED_RESULT);
+                buf.extend_from_slice(&self.timestamp_ns.to_be_bytes());
+                put_order_event(buf, event);
+                put_order_result(buf, result);
+            }
+        }
+
+        let len = (buf.len() - start - 4) as u32;
+        buf[start..start + 4].copy_from_slice(&len.to_be_bytes());
+    }
+
+    /// Decodes one record payload, without its length prefix.
+    pub fn decode(payload: &[u8]) -> io::Result<Self> {
+        let mut cursor = Cursor::new(payload);
+        let kind = cursor.u8()?;
+        let timestamp_ns = cursor.u64()?;
+
+        let entry = match kind {
+            KIND_FIX_INBOUND => JournalEntry::FixInbound(cursor.rest().to_vec()),
+            KIND_ORDER_EVENT => JournalEntry::OrderEvent(get_order_event(&mut cursor)?),
+            KIND_ORDER_RESULT => {
+                let event = get_order_event(&mut cursor)?;
+                JournalEntry::OrderResult(event, get_order_result(&mut cursor)?)
+            }
+            KIND_RESET => JournalEntry::Reset(SymbolId(cursor.array()?)),
+            KIND_DROPPED_RESULT => {
+                let event = get_order_event(&mut cursor)?;
+                JournalEntry::DroppedResult(event, get_order_result(&mut cursor)?)
+            }
+            other => return Err(invalid(format!("unknown journal record kind {other}"))),
+        };
+
+        Ok(Self::new(timestamp_ns, entry))
+    }
+
+    /// JSON encoding of the record, written as one line of a JSONL journal.
+    pub fn to_json(&self) -> Value {
+        match &self.entry {
+            JournalEntry::FixInbound(bytes) => json!({
+                "timestamp_ns": self.timestamp_ns,
+                "kind": "fix_inbound",
+                "data": String::from_utf8_lossy(bytes),
+            }),
+            JournalEntry::OrderEvent(event) => json!({
+                "timestamp_ns": self.timestamp_ns,
+                "kind": "order_event",
+                "event": order_event_to_json(event),
+            }),
+            JournalEntry::OrderResult(event, result) => json!({
+                "timestamp_ns": self.timestamp_ns,
+                "kind": "order_result",
+                "event": order_event_to_json(event),
+                "result": order_result_to_json(result),
+            }),
+            JournalEntry::Reset(symbol) => json!({
+                "timestamp_ns": self.timestamp_ns,
+                "kind": "reset",
+                "symbol": symbol.to_string(),
+            }),
+            JournalEntry::DroppedResult(event, result) => json!({
+                "timestamp_ns": self.timestamp_ns,
+                "kind": "dropped_result",
+                "event": order_event_to_json(event),
+                "result": order_result_to_json(result),
+            }),
+        }
+    }
+
+    pub fn from_json(value: &Value) -> io::Result<Self> {
+        let timestamp_ns = json_u64(value, "timestamp_ns")?;
+        let entry = match json_str(value, "kind")? {
+            "fix_inbound" => JournalEntry::FixInbound(json_str(value, "data")?.as_bytes().to_vec()),
+            "order_event" => {
+                JournalEntry::OrderEvent(order_event_from_json(json_field(value, "event")?)?)
+            }
+            "order_result" => JournalEntry::OrderResult(
+                order_event_from_json(json_field(value, "event")?)?,
+                order_result_from_json(json_field(value, "result")?)?,
+            ),
+            "reset" => JournalEntry::Reset(SymbolId::from_ascii(json_str(value, "symbol")?)),
+            "dropped_result" => JournalEntry::DroppedResult(
+                order_event_from_json(json_field(value, "event")?)?,
+                order_result_from_json(json_field(value, "result")?)?,
+            ),
+            other => return Err(invalid(format!("unknown journal record kind '{other}'"))),
+        };
+
+        Ok(Self::new(timestamp_ns, entry))
+    }
+}
+
+impl std::fmt::Display for JournalRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.entry {
+            JournalEntry::FixInbound(bytes) => write!(
+                f,
+                "[{}] FIX inbound: {}",
+                self.timestamp_ns,
+                String::from_utf8_lossy(bytes).replace('\x01', " | ")
+            ),
+            JournalEntry::OrderEvent(event) => write!(f, "[{}] {}", self.timestamp_ns, event),
+            JournalEntry::OrderResult(event, result) => {
+                write!(f, "[{}] {}{}", self.timestamp_ns, event, result)
+            }
+            JournalEntry::Reset(symbol) => {
+                write!(f, "[{}] Order book reset: {}", self.timestamp_ns, symbol)
+            }
+            JournalEntry::DroppedResult(event, result) => {
+                write!(
+                    f,
+                    "[{}] Dropped result: {}{}",
+                    self.timestamp_ns, event, result
+                )
+            }
+        }
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// ---------------- Binary encoding ----------------
+
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.offset + len > self.bytes.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "truncated journal record",
+            ));
+        }
+        let bytes = &self.bytes[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_be_bytes(self.array()?))
+    }
+
+    fn fixed(&mut self) -> io::Result<FixedPointArithmetic> {
+        Ok(FixedPointArithmetic::from_raw(i64::from_be_bytes(
+            self.array()?,
+        )))
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let bytes = &self.bytes[self.offset..];
+        self.offset = self.bytes.len();
+        bytes
+    }
+}
+
+fn put_fixed(buf: &mut Vec<u8>, value: FixedPointArithmetic) {
+    buf.extend_from_slice(&value.raw().to_be_bytes());
+}
+
+// Order event: price (8), quantity (8), side (1), order type (1), symbol (4), cl_ord_id (20),
+// orig_cl_ord_id presence (1) + orig_cl_ord_id (20), sender_id (20), target_id (20), timestamp (8), min quantity (8)
+fn put_order_event(buf: &mut Vec<u8>, event: &OrderEvent) {
+    put_fixed(buf, event.price);
+    put_fixed(buf, event.quantity);
+    buf.push(side_code(event.side));
+    buf.push(order_type_code(event.order_type));
+    buf.extend_from_slice(&event.symbol.0);
+    buf.extend_from_slice(&event.cl_ord_id.0);
+    buf.push(event.orig_cl_ord_id.is_some() as u8);
+    buf.extend_from_slice(&event.orig_cl_ord_id.unwrap_or_default().0);
+    buf.extend_from_slice(&event.sender_id.0);
+    buf.extend_from_slice(&event.target_id.0);
+    buf.extend_from_slice(&event.timestamp_ms.to_be_bytes());
+    put_fixed(buf, event.min_quantity);
+}
+
+fn get_order_event(cursor: &mut Cursor) -> io::Result<OrderEvent> {
+    let price = cursor.fixed()?;
+    let quantity = cursor.fixed()?;
+    let side = side_from_code(cursor.u8()?)?;
+    let order_type = order_type_from_code(cursor.u8()?)?;
+    let symbol = SymbolId(cursor.array()?);
+    let cl_ord_id = OrderId(cursor.array()?);
+    let has_orig = cursor.u8()? != 0;
+    let orig_cl_ord_id = OrderId(cursor.array()?);
+
+    Ok(OrderEvent {
+        price,
+        quantity,
+        side,
+        order_type,
+        symbol,
+        cl_ord_id,
+        orig_cl_ord_id: has_orig.then_some(orig_cl_ord_id),
+        sender_id: EntityId(cursor.array()?),
+        target_id: EntityId(cursor.array()?),
+        timestamp_ms: cursor.u64()?,
+        min_quantity: cursor.fixed()?,
+    })
+}
+
+// Order result: internal order id (8), status (1), timestamp (8), trade count (1), trades.
+// Trade: price (8), quantity (8), id (8), cl_ord_id (20), sender_id (20), order_qty (8), leaves_qty (8), timestamp (8)
+fn put_order_result(buf: &mut Vec<u8>, result: &OrderResult) {
+    buf.extend_from_slice(&result.internal_order_id.to_be_bytes());
+    buf.push(status_code(result.status));
+    buf.extend_from_slice(&result.timestamp_ms.to_be_bytes());
+    buf.push(result.trades.len() as u8);
+    for trade in result.trades.iter() {
+        put_fixed(buf, trade.price);
+        put_fixed(buf, trade.quantity);
+        buf.extend_from_slice(&trade.id.to_be_bytes());
+        buf.extend_from_slice(&trade.cl_ord_id.0);
+        buf.extend_from_slice(&trade.sender_id.0);
+        put_fixed(buf, trade.order_qty);
+        put_fixed(buf, trade.leaves_qty);
+        buf.extend_from_slice(&trade.timestamp.to_be_bytes());
+    }
+}
+
+fn get_order_result(cursor: &mut Cursor) -> io::Result<OrderResult> {
+    let internal_order_id = cursor.u64()?;
+    let status = status_from_code(cursor.u8()?)?;
+    let timestamp_ms = cursor.u64()?;
+    let count = cursor.u8()?;
+
+    let mut trades = Trades::new();
+    for _ in 0..count {
+        let trade = Trade {
+            price: cursor.fixed()?,
+            quantity: cursor.fixed()?,
+            id: cursor.u64()?,
+            cl_ord_id: OrderId(cursor.array()?),
+            sender_id: EntityId(cursor.array()?),
+            order_qty: cursor.fixed()?,
+            leaves_qty: cursor.fixed()?,
+            timestamp: cursor.u64()?,
+        };
+        trades
+            .add_trade(trade)
+            .map_err(|e| invalid(e.to_string()))?;
+    }
+
+    Ok(OrderResult {
+        internal_order_id,
+        trades,
+        status,
+        timestamp_ms,
+        arrival_mid: None, // Derived from the book, recomputed on replay
+    })
+}
+
+fn side_code(side: Side) -> u8 {
+    match side {
+        Side::Buy => 0,
+        Side::Sell => 1,
+    }
+}
+
+fn side_from_code(code: u8) -> io::Result<Side> {
+    match code {
+        0 => Ok(Side::Buy),
+        1 => Ok(Side::Sell),
+        other => Err(invalid(format!("invalid side code {other}"))),
+    }
+}
+
+fn order_type_code(order_type: OrderType) -> u8 {
+    match order_type {
+        OrderType::LimitOrder => 0,
+        OrderType::MarketOrder => 1,
+        OrderType::CancelOrder => 2,
+        OrderType::ImmediateOrCancel => 3,
+        OrderType::FillOrKill => 4,
+    }
+}
+
+fn order_type_from_code(code: u8) -> io::Result<OrderType> {
+    match code {
+        0 => Ok(OrderType::LimitOrder),
+        1 => Ok(OrderType::MarketOrder),
+        2 => Ok(OrderType::CancelOrder),
+        3 => Ok(OrderType::ImmediateOrCancel),
+        4 => Ok(OrderType::FillOrKill),
+        other => Err(invalid(format!("invalid order type code {other}"))),
+    }
+}
+
+const STATUSES: [OrderStatus; 12] = [
+    OrderStatus::New,
+    OrderStatus::PartiallyFilled,
+    OrderStatus::Filled,
+    OrderStatus::Cancelled,
+    OrderStatus::CancelRejected,
+    OrderStatus::Unmatched,
+    OrderStatus::RejectedLevelFull,
+    OrderStatus::RejectedInvalidQuantity,
+    OrderStatus::RejectedInvalidPrice,
+    OrderStatus::RejectedInvalidLot,
+    OrderStatus::RejectedMinQuantity,
+    OrderStatus::RejectedExposureLimit,
+];
+
+fn status_code(status: OrderStatus) -> u8 {
+    STATUSES.iter().position(|s| *s == status).unwrap() as u8
+}
+
+fn status_from_code(code: u8) -> io::Result<OrderStatus> {
+    STATUSES
+        .get(code as usize)
+        .copied()
+        .ok_or_else(|| invalid(format!("invalid order status code {code}")))
+}
+
+// ---------------- JSON encoding ----------------
+// Prices and quantities are written as raw fixed-point integers so the journal is lossless.
+
+fn order_event_to_json(event: &OrderEvent) -> Value {
+    json!({
+        "price": event.price.raw(),
+        "quantity": event.quantity.raw(),
+        "side": side_code(event.side),
+        "order_type": order_type_code(event.order_type),
+        "symbol": event.symbol.to_string(),
+        "cl_ord_id": event.cl_ord_id.to_string(),
+        "orig_cl_ord_id": event.orig_cl_ord_id.map(|id| id.to_string()),
+        "sender_id": event.sender_id.to_string(),
+        "target_id": event.target_id.to_string(),
+        "timestamp_ms": event.timestamp_ms,
+        "min_quantity": event.min_quantity.raw(),
+    })
+}
+
+fn order_event_from_json(value: &Value) -> io::Result<OrderEvent> {
+    let orig_cl_ord_id = match json_field(value, "orig_cl_ord_id")? {
+        Value::Null => None,
+        Value::String(id) => Some(OrderId::from_ascii(id)),
+        _ => return Err(invalid("invalid 'orig_cl_ord_id' field".to_string())),
+    };
+
+    Ok(OrderEvent {
+        price: FixedPointArithmetic::from_raw(json_i64(value, "price")?),
+        quantity: FixedPointArithmetic::from_raw(json_i64(value, "quantity")?),
+        side: side_from_code(json_u64(value, "side")? as u8)?,
+        order_type: order_type_from_code(json_u64(value, "order_type")? as u8)?,
+        symbol: SymbolId::from_ascii(json_str(value, "symbol")?),
+        cl_ord_id: OrderId::from_ascii(json_str(value, "cl_ord_id")?),
+        orig_cl_ord_id,
+        sender_id: EntityId::from_ascii(json_str(value, "sender_id")?),
+        target_id: EntityId::from_ascii(json_str(value, "target_id")?),
+        timestamp_ms: json_u64(value, "timestamp_ms")?,
+        min_quantity: FixedPointArithmetic::from_raw(json_i64(value, "min_quantity")?),
+    })
+}
+
+fn order_result_to_json(result: &OrderResult) -> Value {
+    let trades: Vec<Value> = result
+        .trades
+        .iter()
+        .map(|trade| {
+            json!({
+                "price": trade.price.raw(),
+                "quantity": trade.quantity.raw(),
+                "id": trade.id,
+                "cl_ord_id": trade.cl_ord_id.to_string(),
+                "sender_id": trade.sender_id.to_string(),
+                "order_qty": trade.order_qty.raw(),
+                "leaves_qty": trade.leaves_qty.raw(),
+                "timestamp": trade.timestamp,
+            })
+        })
+        .collect();
+
+    json!({
+        "internal_order_id": result.internal_order_id,
+        "status": status_code(result.status),
+        "timestamp_ms": result.timestamp_ms,
+        "trades": trades,
+    })
+}
+
+fn order_result_from_json(value: &Value) -> io::Result<OrderResult> {
+    let mut trades = Trades::new();
+    let json_trades = json_field(value, "trades")?
+        .as_array()
+        .ok_or_else(|| invalid("invalid 'trades' field".to_string()))?;
+    for trade in json_trades {
+        trades
+            .add_trade(Trade {
+                price: FixedPointArithmetic::from_raw(json_i64(trade, "price")?),
+                quantity: FixedPointArithmetic::from_raw(json_i64(trade, "quantity")?),
+                id: json_u64(trade, "id")?,
+                cl_ord_id: OrderId::from_ascii(json_str(trade, "cl_ord_id")?),
+                sender_id: EntityId::from_ascii(json_str(trade, "sender_id")?),
+                order_qty: FixedPointArithmetic::from_raw(json_i64(trade, "order_qty")?),
+                leaves_qty: FixedPointArithmetic::from_raw(json_i64(trade, "leaves_qty")?),
+                timestamp: json_u64(trade, "timestamp")?,
+            })
+            .map_err(|e| invalid(e.to_string()))?;
+    }
+
+    Ok(OrderResult {
+        internal_order_id: json_u64(value, "internal_order_id")?,
+        trades,
+        status: status_from_code(json_u64(value, "status")? as u8)?,
+        timestamp_ms: json_u64(value, "timestamp_ms")?,
+        arrival_mid: None,
+    })
+}
+
+fn json_field<'a>(value: &'a Value, key: &str) -> io::Result<&'a Value> {
+    value
+        .get(key)
+        .ok_or_else(|| invalid(format!("missing '{key}' field")))
+}
+
+fn json_str<'a>(value: &'a Value, key: &str) -> io::Result<&'a str> {
+    json_field(value, key)?
+        .as_str()
+        .ok_or_else(|| invalid(format!("invalid '{key}' field")))
+}
+
+fn json_u64(value: &Value, key: &str) -> io::Result<u64> {
+    json_field(value, key)?
+        .as_u64()
+        .ok_or_else(|| invalid(format!("invalid '{key}' field")))
+}
+
+fn json_i64(value: &Value, key: &str) -> io::Result<i64> {
+    json_field(value, key)?
+        .as_i64()
+        .ok_or_else(|| invalid(format!("invalid '{key}' field")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order_event() -> OrderEvent {
+        OrderEvent {
+            price: FixedPointArithmetic::from_f64(101.25),
+            quantity: FixedPointArithmetic::from_f64(3.0),
+            side: Side::Sell,
+            order_type: OrderType::CancelOrder,
+            cl_ord_id: OrderId::from_ascii("cancel1"),
+            orig_cl_ord_id: Some(OrderId::from_ascii("ask1")),
+            sender_id: EntityId::from_ascii("trader1"),
+            target_id: EntityId::from_ascii("exchange"),
+            symbol: SymbolId::from_ascii("TEST"),
+            timestamp_ms: 1627846267000,
+            min_quantity: FixedPointArithmetic::from_f64(2.0),
+        }
+    }
+
+    fn order_result() -> OrderResult {
+        let mut trades = Trades::new();
+        trades
+            .add_trade(Trade {
+                price: FixedPointArithmetic::from_f64(101.0),
+                quantity: FixedPointArithmetic::from_f64(1.5),
+                id: 7,
+                cl_ord_id: OrderId::from_ascii("bid1"),
+                sender_id: EntityId::from_ascii("trader2"),
+                order_qty: FixedPointArithmetic::from_f64(3.0),
+                leaves_qty: FixedPointArithmetic::from_f64(1.5),
+                timestamp: 1627846267001,
+            })
+            .unwrap();
+        OrderResult {
+            internal_order_id: 42,
+            trades,
+            status: OrderStatus::PartiallyFilled,
+            timestamp_ms: 1627846267002,
+            arrival_mid: None,
+        }
+    }
+
+    fn records() -> Vec<JournalRecord> {
+        vec![
+            JournalRecord::new(
+                1,
+                JournalEntry::FixInbound(b"8=FIX.4.4\x0135=D\x01".to_vec()),
+            ),
+            JournalRecord::new(2, JournalEntry::OrderEvent(order_event())),
+            JournalRecord::new(3, JournalEntry::OrderResult(order_event(), order_result())),
+            JournalRecord::new(4, JournalEntry::Reset(SymbolId::from_ascii("TEST"))),
+            JournalRecord::new(
+                5,
+                JournalEntry::DroppedResult(order_event(), order_result()),
+            ),
+        ]
+    }
+
+    fn assert_same_record(decoded: &JournalRecord, record: &JournalRecord) {
+        assert_eq!(decoded, record);
+        // OrderEvent equality only compares prices, compare every field through Debug
+        assert_eq!(format!("{decoded:?}"), format!("{record:?}"));
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        for record in records() {
+            let mut buf = Vec::new();
+            record.encode(&mut buf);
+
+            let len = u32::from_be_bytes(buf[..4].try_into().unwrap()) as usize;
+            assert_eq!(len, buf.len() - 4);
+            assert_same_record(&JournalRecord::decode(&buf[4..]).unwrap(), &record);
+
+            // Truncated fixed-size payloads are rejected instead of decoded as garbage
+            if !matches!(record.entry, JournalEntry::FixInbound(_)) {
+                assert!(JournalRecord::decode(&buf[4..buf.len() - 1]).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        for record in records() {
+            let line = record.to_json().to_string();
+            assert!(!line.contains('\n'));
+            let value: Value = serde_json::from_str(&line).unwrap();
+            assert_same_record(&JournalRecord::from_json(&value).unwrap(), &record);
+        }
+    }
+
+    #[test]
+    fn test_decode_rejects_unknown_kind() {
+        let mut payload = vec![99u8];
+        payload.extend_from_slice(&0u64.to_be_bytes());
+        assert!(JournalRecord::decode(&payload).is_err());
+    }
+}
@@ -0,0 +1,316 @@
+use crate::record::{JournalEntry, JournalRecord};
+use config::{JournalBackpressure, JournalConfig, JournalFormat};
+use crossbeam_channel::{Receiver, Sender, TrySendError};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use types::macros::SymbolId;
+use types::{OrderEvent, OrderResult};
+use utils::market_name;
+
+/// Prefix of the journal segment file names, followed by a 5-digit sequence number.
+pub const SEGMENT_PREFIX: &str = "journal-";
+
+/// Extension of the segment files written in `format`.
+pub fn segment_extension(format: JournalFormat) -> &'static str {
+    match format {
+        JournalFormat::Binary => "bin",
+        JournalFormat::Jsonl => "jsonl",
+    }
+}
+
+/// Cloneable handle given to the engines, feeding the journal writer thread.
+/// The writer thread exits once every tap has been dropped.
+#[derive(Clone)]
+pub struct JournalTap {
+    tx: Sender<JournalRecord>,
+    backpressure: JournalBackpressure,
+    dropped: Arc<AtomicU64>,
+}
+
+impl JournalTap {
+    pub fn record_fix_inbound(&self, bytes: &[u8]) {
+        self.record(JournalEntry::FixInbound(bytes.to_vec()));
+    }
+
+    pub fn record_order_event(&self, event: &OrderEvent) {
+        self.record(JournalEntry::OrderEvent(*event));
+    }
+
+    pub fn record_order_result(&self, event: &OrderEvent, result: &OrderResult) {
+        self.record(JournalEntry::OrderResult(*event, *result));
+    }
+
+    pub fn record_reset(&self, symbol: SymbolId) {
+        self.record(JournalEntry::Reset(symbol));
+    }
+
+    pub fn record_dropped_result(&self, event: &OrderEvent, result: &OrderResult) {
+        self.record(JournalEntry::DroppedResult(*event, *result));
+    }
+
+    /// Number of records dropped because the writer thread was behind.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    fn record(&self, entry: JournalEntry) {
+        let record = JournalRecord::now(entry);
+        match self.backpressure {
+            JournalBackpressure::Block => {
+                // Only fails once the writer thread is gone, there is nobody left to write the record
+                let _ = self.tx.send(record);
+            }
+            JournalBackpressure::Drop => {
+                if let Err(TrySendError::Full(_)) = self.tx.try_send(record) {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+}
+
+/// Rotating journal file writer, owned by the journal thread.
+pub struct JournalWriter {
+    dir: PathBuf,
+    format: JournalFormat,
+    max_file_bytes: u64,
+    fsync_every: usize,
+    segment: usize,
+    file: BufWriter<File>,
+    written: u64,
+    unsynced: usize,
+    buf: Vec<u8>,
+}
+
+impl JournalWriter {
+    /// Opens a new segment in `config.dir`, after the segments left by previous sessions.
+    pub fn new(config: &JournalConfig) -> io::Result<Self> {
+        let dir = PathBuf::from(&config.dir);
+        std::fs::create_dir_all(&dir)?;
+
+        let segment = next_segment(&dir)?;
+        let file = open_segment(&dir, segment, config.format)?;
+
+        Ok(Self {
+            dir,
+            format: config.format,
+            max_file_bytes: config.max_file_bytes,
+            fsync_every: config.fsync_every.max(1),
+            segment,
+            file,
+            written: 0,
+            unsynced: 0,
+            buf: Vec::with_capacity(256),
+        })
+    }
+
+    pub fn write(&mut self, record: &JournalRecord) -> io::Result<()> {
+        self.buf.clear();
+        match self.format {
+            JournalFormat::Binary => record.encode(&mut self.buf),
+            JournalFormat::Jsonl => {
+                serde_json::to_writer(&mut self.buf, &record.to_json())?;
+                self.buf.push(b'\n');
+            }
+        }
+
+        // Never split a record across segments, an oversized record gets a segment of its own
+        if self.written > 0 && self.written + self.buf.len() as u64 > self.max_file_bytes {
+            self.rotate()?;
+        }
+
+        self.file.write_all(&self.buf)?;
+        self.written += self.buf.len() as u64;
+        self.unsynced += 1;
+
+        if self.unsynced >= self.fsync_every {
+            self.sync()?;
+        }
+        Ok(())
+    }
+
+    /// Flushes and fsyncs the current segment.
+    pub fn sync(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        self.file.get_ref().sync_data()?;
+        self.unsynced = 0;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.sync()?;
+        self.segment += 1;
+        self.file = open_segment(&self.dir, self.segment, self.format)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+/// Spawns the journal writer thread.
+/// # Returns
+/// The tap to hand over to the engines, and the writer thread handle.
+pub fn start_journal(
+    config: &JournalConfig,
+) -> io::Result<(JournalTap, std::thread::JoinHandle<()>)> {
+    let mut writer = JournalWriter::new(config)?;
+    let (tx, rx) = crossbeam_channel::bounded::<JournalRecord>(config.capacity.max(1));
+    let core_id = config.core;
+
+    let tap = JournalTap {
+        tx,
+        backpressure: config.backpressure,
+        dropped: Arc::new(AtomicU64::new(0)),
+    };
+    let dropped = Arc::clone(&tap.dropped);
+
+    let handle = std::thread::spawn(move || {
+        core_affinity::set_for_current(core_affinity::CoreId { id: core_id });
+        if let Err(e) = run_writer(&mut writer, rx) {
+            tracing::error!("[{}] Journal writer error: {e:#}", market_name());
+        }
+
+        let dropped = dropped.load(Ordering::Relaxed);
+        if dropped > 0 {
+            tracing::warn!(
+                "[{}] Journal dropped {} records while the writer was behind",
+                market_name(),
+                dropped
+            );
+        }
+        tracing::info!("[{}] Journal writer stopped", market_name());
+    });
+
+    Ok((tap, handle))
+}
+
+fn run_writer(writer: &mut JournalWriter, rx: Receiver<JournalRecord>) -> io::Result<()> {
+    // Ends once every tap has been dropped and the channel is drained
+    for record in rx.iter() {
+        writer.write(&record)?;
+    }
+    writer.sync()
+}
+
+fn segment_path(dir: &Path, segment: usize, format: JournalFormat) -> PathBuf {
+    dir.join(format!(
+        "{SEGMENT_PREFIX}{segment:05}.{}",
+        segment_extension(format)
+    ))
+}
+
+fn open_segment(dir: &Path, segment: usize, format: JournalFormat) -> io::Result<BufWriter<File>> {
+    let file = File::options()
+        .create_new(true)
+        .write(true)
+        .open(segment_path(dir, segment, format))?;
+    Ok(BufWriter::new(file))
+}
+
+/// Sequence number following the highest segment already present in `dir`.
+fn next_segment(dir: &Path) -> io::Result<usize> {
+    let mut next = 0;
+    for entry in std::fs::read_dir(dir)? {
+        if let Some(segment) = segment_number(&entry?.path()) {
+            next = next.max(segment + 1);
+        }
+    }
+    Ok(next)
+}
+
+/// Sequence number of a journal segment file, `None` for any other file.
+pub fn segment_number(path: &Path) -> Option<usize> {
+    path.file_stem()?
+        .to_str()?
+        .strip_prefix(SEGMENT_PREFIX)?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reader::read_journal;
+
+    fn config(name: &str, format: JournalFormat) -> JournalConfig {
+        let dir = std::env::temp_dir().join(format!("journal-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        JournalConfig {
+            dir: dir.to_string_lossy().to_string(),
+            format,
+            backpressure: JournalBackpressure::Block,
+            capacity: 16,
+            fsync_every: 3,
+            max_file_bytes: 256,
+            core: 0,
+        }
+    }
+
+    fn write_session(config: &JournalConfig, count: usize) {
+        let (tap, handle) = start_journal(config).unwrap();
+        for i in 0..count {
+            tap.record_fix_inbound(format!("8=FIX.4.4\x0134={i}\x01").as_bytes());
+            tap.record_order_event(&OrderEvent {
+                timestamp_ms: i as u64,
+                ..Default::default()
+            });
+        }
+        drop(tap);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_rotating_journal_round_trip() {
+        for format in [JournalFormat::Binary, JournalFormat::Jsonl] {
+            let config = config(segment_extension(format), format);
+            write_session(&config, 20);
+
+            let dir = Path::new(&config.dir);
+            let segments = std::fs::read_dir(dir).unwrap().count();
+            assert!(segments > 1, "expected rotation, got {segments} segment(s)");
+
+            let records = read_journal(dir).unwrap();
+            assert_eq!(records.len(), 40);
+            for (i, pair) in records.chunks(2).enumerate() {
+                assert_eq!(
+                    pair[0].entry,
+                    JournalEntry::FixInbound(format!("8=FIX.4.4\x0134={i}\x01").into_bytes())
+                );
+                match &pair[1].entry {
+                    JournalEntry::OrderEvent(event) => assert_eq!(event.timestamp_ms, i as u64),
+                    other => panic!("unexpected entry {other:?}"),
+                }
+            }
+            assert!(
+                records
+                    .windows(2)
+                    .all(|w| w[0].timestamp_ns <= w[1].timestamp_ns)
+            );
+
+            // A new session appends segments instead of overwriting the previous ones
+            write_session(&config, 1);
+            assert_eq!(read_journal(dir).unwrap().len(), 42);
+
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
+
+    #[test]
+    fn test_drop_policy_counts_dropped_records() {
+        let (tx, _rx) = crossbeam_channel::bounded(2);
+        let tap = JournalTap {
+            tx,
+            backpressure: JournalBackpressure::Drop,
+            dropped: Arc::new(AtomicU64::new(0)),
+        };
+
+        // Nothing drains the channel, everything past its capacity is dropped without blocking
+        for _ in 0..5 {
+            tap.record_reset(SymbolId::from_ascii("TEST"));
+        }
+        assert_eq!(tap.dropped(), 3);
+        assert_eq!(tap.clone().dropped(), 3);
+    }
+}
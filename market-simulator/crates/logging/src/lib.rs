@@ -1,15 +1,29 @@
 use once_cell::sync::OnceCell;
+use std::backtrace::Backtrace;
+use std::panic::PanicHookInfo;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Once};
 use tracing_subscriber::EnvFilter;
 use tracing_subscriber::fmt::format::FmtSpan;
 
+#[cfg(not(test))]
+use std::sync::Mutex;
 #[cfg(not(test))]
 use tracing_appender::non_blocking::WorkerGuard;
 
+static TRACING: OnceCell<()> = OnceCell::new();
+
+/// Guard of the non-blocking writer, dropping it flushes the pending log lines.
 #[cfg(not(test))]
-static TRACING: OnceCell<WorkerGuard> = OnceCell::new();
+static GUARD: Mutex<Option<WorkerGuard>> = Mutex::new(None);
 
-#[cfg(test)]
-static TRACING: OnceCell<()> = OnceCell::new();
+static PANIC_HOOK: Once = Once::new();
+
+/// Shutdown flag raised when a thread panics, see `shutdown_on_panic`.
+static PANIC_SHUTDOWN: OnceCell<Arc<AtomicBool>> = OnceCell::new();
+
+/// Set once a panic raised the shutdown flag.
+static PANIC_SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
 
 pub fn init_tracing(module: &str) {
     TRACING.get_or_init(|| {
@@ -33,10 +47,121 @@ pub fn init_tracing(module: &str) {
             .with_thread_names(true)
             .init();
 
-        #[cfg(test)]
-        ();
-
         #[cfg(not(test))]
-        guard
+        {
+            *GUARD.lock().unwrap() = Some(guard);
+        }
+    });
+
+    install_panic_hook();
+}
+
+/// Routes panics through `tracing::error!` with the payload, the thread name and a backtrace,
+/// then hands them over to the previous hook. Installing the hook more than once is a no-op.
+///
+/// The log writer is flushed when the panic brings the process down: with `panic = "abort"`,
+/// when the main thread panics, or when `shutdown_on_panic` is enabled.
+pub fn install_panic_hook() {
+    PANIC_HOOK.call_once(|| {
+        let previous_hook = std::panic::take_hook();
+
+        std::panic::set_hook(Box::new(move |info| {
+            let thread = std::thread::current();
+            let thread_name = thread.name().unwrap_or("<unnamed>");
+            let location = info
+                .location()
+                .map(|location| location.to_string())
+                .unwrap_or_else(|| "unknown location".to_string());
+            let backtrace = Backtrace::force_capture();
+
+            tracing::error!(
+                "Thread '{}' panicked at {}: {}\n{}",
+                thread_name,
+                location,
+                panic_payload(info),
+                backtrace
+            );
+
+            let shutdown = match PANIC_SHUTDOWN.get() {
+                Some(shutdown) => {
+                    PANIC_SHUTDOWN_REQUESTED.store(true, Ordering::Release);
+                    shutdown.store(true, Ordering::Release);
+                    true
+                }
+                None => false,
+            };
+
+            if cfg!(panic = "abort") || thread_name == "main" {
+                flush();
+            } else if shutdown {
+                tracing::error!("Shutdown requested after thread '{}' panicked", thread_name);
+            }
+
+            previous_hook(info);
+        }));
     });
 }
+
+/// Raises `shutdown` whenever a thread panics, turning panics in worker threads into a
+/// process-wide shutdown signal. Only the first registered flag is used.
+pub fn shutdown_on_panic(shutdown: Arc<AtomicBool>) {
+    let _ = PANIC_SHUTDOWN.set(shutdown);
+    install_panic_hook();
+}
+
+/// Whether a panic raised the flag registered with `shutdown_on_panic`.
+pub fn panic_shutdown_requested() -> bool {
+    PANIC_SHUTDOWN_REQUESTED.load(Ordering::Acquire)
+}
+
+/// Flushes the pending log lines and stops the background writer.
+/// Lines logged afterwards are lost, only call it right before the process exits.
+pub fn flush() {
+    #[cfg(not(test))]
+    {
+        // try_lock: the panic may have happened while the guard was being stored
+        let guard = match GUARD.try_lock() {
+            Ok(mut guard) => guard.take(),
+            Err(_) => None,
+        };
+        drop(guard);
+    }
+}
+
+fn panic_payload<'a>(info: &'a PanicHookInfo<'_>) -> &'a str {
+    if let Some(message) = info.payload().downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = info.payload().downcast_ref::<String>() {
+        message
+    } else {
+        "Box<dyn Any>"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_panic_is_written_to_log_file() {
+        let _ = std::fs::remove_file("./logs/logging_panic_hook.log");
+        init_tracing("logging_panic_hook");
+
+        let shutdown = Arc::new(AtomicBool::new(false));
+        shutdown_on_panic(Arc::clone(&shutdown));
+
+        let handle = std::thread::Builder::new()
+            .name("panicking-worker".to_string())
+            .spawn(|| panic!("worker exploded with code {}", 1984))
+            .unwrap();
+        assert!(handle.join().is_err());
+
+        assert!(shutdown.load(Ordering::Acquire));
+        assert!(panic_shutdown_requested());
+
+        let log = std::fs::read_to_string("./logs/logging_panic_hook.log").unwrap();
+        assert!(log.contains("Thread 'panicking-worker' panicked at"));
+        assert!(log.contains("worker exploded with code 1984"));
+        assert!(log.contains("Shutdown requested after thread 'panicking-worker' panicked"));
+    }
+}
@@ -128,7 +128,15 @@ impl<'a, const N: usize> MarketDataFeedEngine<'a, N> {
             return Some(vec![MarketEvent::Delete(header, delete_order)]);
         }
 
-        if order_result.status == types::OrderStatus::Unmatched {
+        if order_result.status == types::OrderStatus::Unmatched || order_result.status.is_rejected()
+        {
+            return None;
+        }
+
+        // An immediate-or-cancel order that traded nothing never reached the book
+        if order_event.order_type == types::OrderType::ImmediateOrCancel
+            && order_result.trades.len() == 0
+        {
             return None;
         }
 
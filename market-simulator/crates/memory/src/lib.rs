@@ -1,16 +1,88 @@
 use memmap2::MmapMut;
 use spsc::spsc_lock_free::RingBuffer;
 use std::fs::OpenOptions;
+use types::LAYOUT_VERSION;
+
+/// Identifies a shared queue file, "MSQUEUE" followed by a format byte.
+const QUEUE_MAGIC: u64 = u64::from_be_bytes(*b"MSQUEUE1");
+
+/// Bytes reserved for the header at the start of the file, the ring buffer starts right after.
+/// A multiple of the cache line so the ring buffer keeps its alignment.
+const HEADER_SPACE: usize = 128;
+
+/// Header written at the start of a shared queue file by the process creating it.
+/// A process attaching to the queue checks it first, so binaries built with another memory layout of the queued
+/// types, another element type or another capacity refuse to attach instead of reading garbage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub struct SharedQueueHeader {
+    pub magic: u64,
+    pub layout_version: u32,
+    pub element_size: u32,
+    pub element_align: u32,
+    pub capacity: u32,
+}
+
+const _: () = assert!(std::mem::size_of::<SharedQueueHeader>() <= HEADER_SPACE);
+
+impl SharedQueueHeader {
+    /// Header of a queue of `N` elements of type `T`, built with the layout version of this binary.
+    pub fn new<const N: usize, T>() -> Self {
+        Self {
+            magic: QUEUE_MAGIC,
+            layout_version: LAYOUT_VERSION,
+            element_size: std::mem::size_of::<T>() as u32,
+            element_align: std::mem::align_of::<T>() as u32,
+            capacity: N as u32,
+        }
+    }
+
+    /// Checks the header found in a queue file against the one this binary would write.
+    /// Returns a description of the first mismatch.
+    pub fn check(&self, expected: &SharedQueueHeader) -> Result<(), String> {
+        if self.magic != expected.magic {
+            return Err("not a shared queue, or not initialized yet".to_string());
+        }
+        if self.layout_version != expected.layout_version {
+            return Err(format!(
+                "layout version {} but this binary uses layout version {}, rebuild both processes from the same \
+                 sources",
+                self.layout_version, expected.layout_version
+            ));
+        }
+        if (self.element_size, self.element_align)
+            != (expected.element_size, expected.element_align)
+        {
+            return Err(format!(
+                "elements of {} bytes aligned on {} but this binary expects {} bytes aligned on {}",
+                self.element_size,
+                self.element_align,
+                expected.element_size,
+                expected.element_align
+            ));
+        }
+        if self.capacity != expected.capacity {
+            return Err(format!(
+                "capacity {} but this binary expects {}",
+                self.capacity, expected.capacity
+            ));
+        }
+        Ok(())
+    }
+}
 
 pub struct SharedQueue<const N: usize, T: 'static> {
     pub queue: &'static mut RingBuffer<T, N>,
 }
 
+/// Opens the shared queue `name` in `/dev/shm`, creating it when `create` is set.
+/// Panics when an existing queue was created by a binary with another header, see [`SharedQueueHeader`].
 pub fn open_shared_queue<const N: usize, T: 'static>(
     name: &str,
     create: bool,
 ) -> SharedQueue<N, T> {
-    let size = std::mem::size_of::<RingBuffer<T, N>>();
+    assert!(std::mem::align_of::<RingBuffer<T, N>>() <= HEADER_SPACE);
+    let size = HEADER_SPACE + std::mem::size_of::<RingBuffer<T, N>>();
     let path = format!("/dev/shm/{name}");
 
     let file = if create {
@@ -45,19 +117,71 @@ pub fn open_shared_queue<const N: usize, T: 'static>(
         });
     }
     let mmap = unsafe { MmapMut::map_mut(&file).unwrap() };
+    if mmap.len() < size {
+        panic!(
+            "Shared queue '{path}' is {} bytes, expected {size}: refusing to attach",
+            mmap.len()
+        );
+    }
 
     // Leak the mapping so the backing memory lives for the entire process.
     // These queues are created once at startup and are never freed, so the
     // leak is intentional and the OS will reclaim the memory on exit.
     let mmap: &'static mut MmapMut = Box::leak(Box::new(mmap));
 
-    let queue_ptr = mmap.as_mut_ptr() as *mut RingBuffer<T, N>;
+    let header_ptr = mmap.as_mut_ptr() as *mut SharedQueueHeader;
+    let queue_ptr = unsafe { mmap.as_mut_ptr().add(HEADER_SPACE) } as *mut RingBuffer<T, N>;
+    let expected = SharedQueueHeader::new::<N, T>();
 
     if create {
-        unsafe { RingBuffer::init(queue_ptr) };
+        unsafe {
+            RingBuffer::init(queue_ptr);
+            header_ptr.write(expected);
+        }
+    } else if let Err(e) = unsafe { header_ptr.read() }.check(&expected) {
+        panic!("Shared queue '{path}' {e}: refusing to attach");
     }
 
     let queue: &'static mut RingBuffer<T, N> = unsafe { &mut *queue_ptr };
 
     SharedQueue { queue }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_check() {
+        let expected = SharedQueueHeader::new::<1024, u64>();
+        assert_eq!(expected.check(&expected), Ok(()));
+
+        let older = SharedQueueHeader {
+            layout_version: LAYOUT_VERSION + 1,
+            ..expected
+        };
+        assert!(
+            older
+                .check(&expected)
+                .unwrap_err()
+                .contains("layout version")
+        );
+
+        let other_type = SharedQueueHeader::new::<1024, u32>();
+        assert!(other_type.check(&expected).unwrap_err().contains("4 bytes"));
+
+        let other_capacity = SharedQueueHeader::new::<512, u64>();
+        assert!(
+            other_capacity
+                .check(&expected)
+                .unwrap_err()
+                .contains("capacity 512")
+        );
+
+        let zeroed = SharedQueueHeader {
+            magic: 0,
+            ..expected
+        };
+        assert!(zeroed.check(&expected).is_err());
+    }
+}
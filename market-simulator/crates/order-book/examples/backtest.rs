@@ -0,0 +1,187 @@
+//! Deterministic backtest of a naive market maker, driving a `MatchingCore` directly without any engine thread.
+//!
+//! Each step the strategy cancels its quotes and quotes one tick around the mid, then a pseudo-random taker flow trades
+//! against the book. The clock is simulated, so two runs print exactly the same results.
+//!
+//! Run it with `cargo run -p order-book --example backtest --release`.
+
+use order_book::MatchingCore;
+use order_book::book::OrderBook;
+use types::macros::{EntityId, OrderId, SymbolId};
+use types::{FixedPointArithmetic, OrderEvent, Side};
+
+const SYMBOL: &str = "AAPL";
+const STEPS: u64 = 10_000;
+/// Simulated time between two steps.
+const STEP_MS: u64 = 100;
+const TICK: f64 = 0.01;
+const QUOTE_QTY: f64 = 5.0;
+const STRATEGY: EntityId = EntityId::from_ascii("STRATEGY");
+const FLOW: EntityId = EntityId::from_ascii("FLOW");
+
+/// Linear congruential generator, enough for a reproducible order flow without any dependency.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        self.0 >> 33
+    }
+}
+
+/// Order ID from a prefix and a number, e.g. `B42`.
+fn order_id(prefix: u8, n: u64) -> OrderId {
+    let mut id = [0u8; 20];
+    id[0] = prefix;
+    id[1..9].copy_from_slice(&n.to_be_bytes());
+    OrderId(id)
+}
+
+fn limit(
+    side: Side,
+    price: f64,
+    quantity: f64,
+    cl_ord_id: OrderId,
+    sender: EntityId,
+) -> OrderEvent {
+    OrderEvent::builder()
+        .side(side)
+        .limit()
+        .price_f64(price)
+        .qty(quantity)
+        .cl_ord_id(cl_ord_id)
+        .sender_id(sender)
+        .symbol(SymbolId::from_ascii(SYMBOL))
+        .build()
+}
+
+/// Position and cash of the strategy, updated from the trades against its quotes.
+#[derive(Default)]
+struct Pnl {
+    position: f64,
+    cash: f64,
+    fills: u64,
+}
+
+impl Pnl {
+    fn record(&mut self, side: Side, price: FixedPointArithmetic, quantity: FixedPointArithmetic) {
+        let (price, quantity) = (price.to_f64(), quantity.to_f64());
+        match side {
+            Side::Buy => {
+                self.position += quantity;
+                self.cash -= price * quantity;
+            }
+            Side::Sell => {
+                self.position -= quantity;
+                self.cash += price * quantity;
+            }
+        }
+        self.fills += 1;
+    }
+}
+
+fn main() {
+    let mut core = MatchingCore::new(OrderBook::new(SYMBOL).with_order_capacity(1_024))
+        .with_start_time_ms(1_700_000_000_000);
+    let mut rng = Lcg(42);
+    let mut pnl = Pnl::default();
+    let mut reports = Vec::with_capacity(4);
+
+    // Background liquidity, so the book has a mid from the start
+    for level in 1..=10 {
+        let offset = level as f64 * TICK * 5.0;
+        core.process(limit(
+            Side::Buy,
+            100.0 - offset,
+            50.0,
+            order_id(b'S', level),
+            FLOW,
+        ));
+        core.process(limit(
+            Side::Sell,
+            100.0 + offset,
+            50.0,
+            order_id(b'S', 100 + level),
+            FLOW,
+        ));
+    }
+
+    let mut quotes: Option<(OrderId, OrderId)> = None;
+    for step in 0..STEPS {
+        core.advance_time(STEP_MS);
+
+        if let Some((bid, ask)) = quotes.take() {
+            core.cancel(bid);
+            core.cancel(ask);
+        }
+        if let Some(mid) = core.order_book().mid_price() {
+            let mid = mid.to_f64();
+            let (bid, ask) = (order_id(b'B', step), order_id(b'A', step));
+            core.process(limit(Side::Buy, mid - TICK, QUOTE_QTY, bid, STRATEGY));
+            core.process(limit(Side::Sell, mid + TICK, QUOTE_QTY, ask, STRATEGY));
+            quotes = Some((bid, ask));
+        }
+
+        // Taker flow, the occasional large order walks the book and moves the mid
+        let roll = rng.next();
+        let side = if roll.is_multiple_of(2) {
+            Side::Buy
+        } else {
+            Side::Sell
+        };
+        let quantity = 1.0 + (roll % 7) as f64 + if roll.is_multiple_of(50) { 60.0 } else { 0.0 };
+        let price = match side {
+            Side::Buy => 101.0,
+            Side::Sell => 99.0,
+        };
+        reports.clear();
+        core.submit_into(
+            limit(side, price, quantity, order_id(b'T', step), FLOW),
+            &mut reports,
+        );
+
+        // Replenish the liquidity taken, half a point inside the aggressor's limit
+        let refill = order_id(b'R', step);
+        let (refill_side, refill_price) = match side {
+            Side::Buy => (Side::Sell, price - 0.5),
+            Side::Sell => (Side::Buy, price + 0.5),
+        };
+        core.process(limit(refill_side, refill_price, quantity, refill, FLOW));
+
+        for (_, result) in &reports {
+            for trade in result.trades.iter() {
+                if let Some((bid, ask)) = quotes {
+                    if trade.cl_ord_id == bid {
+                        pnl.record(Side::Buy, trade.price, trade.quantity);
+                    } else if trade.cl_ord_id == ask {
+                        pnl.record(Side::Sell, trade.price, trade.quantity);
+                    }
+                }
+            }
+        }
+    }
+
+    core.check_invariants().expect("order book invariants");
+
+    let mark = core
+        .order_book()
+        .mid_price()
+        .map_or(0.0, FixedPointArithmetic::to_f64);
+    println!("Steps:      {}", STEPS);
+    println!("Fills:      {}", pnl.fills);
+    println!("Position:   {:.2}", pnl.position);
+    println!("PnL @ mid:  {:.2}", pnl.cash + pnl.position * mark);
+    println!("Clock (ms): {}", core.now_ms().unwrap_or_default());
+    let depth = core.depth(3);
+    for (name, levels) in [("Top bids:", &depth.bids), ("Top asks:", &depth.asks)] {
+        let levels: Vec<String> = levels
+            .iter()
+            .map(|(price, quantity)| format!("{} x {}", quantity, price))
+            .collect();
+        println!("{:<11} {}", name, levels.join(", "));
+    }
+    println!("{}", core.analytics());
+}
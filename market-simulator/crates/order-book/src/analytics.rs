@@ -0,0 +1,201 @@
+use types::{FixedPointArithmetic, Side, Trade};
+
+/// Execution quality of the trades of one aggressor side.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SideAnalytics {
+    /// Number of trades measured against an arrival midpoint.
+    pub trades: u64,
+    /// Number of trades executed at a better price than the arrival midpoint.
+    pub improved_trades: u64,
+    /// Sum of the effective spreads of the trades, in raw fixed-point units so it cannot overflow.
+    pub effective_spread_sum: i128,
+}
+
+impl SideAnalytics {
+    /// Records one trade of an aggressive order of `side`.
+    /// The effective spread is `2 * (price - mid)` for a buy and `2 * (mid - price)` for a sell, the trade improves on
+    /// the midpoint when it is negative.
+    /// Arguments:
+    /// - `side`: The side of the aggressive order.
+    /// - `mid`: The midpoint of the book when the order arrived.
+    /// - `price`: The execution price of the trade.
+    fn record(&mut self, side: Side, mid: FixedPointArithmetic, price: FixedPointArithmetic) {
+        let signed_diff = match side {
+            Side::Buy => price.raw() as i128 - mid.raw() as i128,
+            Side::Sell => mid.raw() as i128 - price.raw() as i128,
+        };
+        self.trades += 1;
+        self.effective_spread_sum += 2 * signed_diff;
+        if signed_diff < 0 {
+            self.improved_trades += 1;
+        }
+    }
+
+    /// Average effective spread of the trades, `None` when no trade was measured.
+    pub fn average_effective_spread(&self) -> Option<FixedPointArithmetic> {
+        (self.trades > 0).then(|| {
+            FixedPointArithmetic::from_raw((self.effective_spread_sum / self.trades as i128) as i64)
+        })
+    }
+
+    /// Percentage of the trades executed at a better price than the arrival midpoint, `None` when no trade was measured.
+    pub fn price_improvement_pct(&self) -> Option<f64> {
+        (self.trades > 0).then(|| self.improved_trades as f64 * 100.0 / self.trades as f64)
+    }
+
+    fn merge(&self, other: &SideAnalytics) -> SideAnalytics {
+        SideAnalytics {
+            trades: self.trades + other.trades,
+            improved_trades: self.improved_trades + other.improved_trades,
+            effective_spread_sum: self.effective_spread_sum + other.effective_spread_sum,
+        }
+    }
+}
+
+impl std::fmt::Display for SideAnalytics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (
+            self.average_effective_spread(),
+            self.price_improvement_pct(),
+        ) {
+            (Some(spread), Some(improvement)) => write!(
+                f,
+                "{} trades, avg effective spread {}, price improvement {:.2}%",
+                self.trades, spread, improvement
+            ),
+            _ => write!(f, "no trades"),
+        }
+    }
+}
+
+/// Execution quality of the trades of an order book, measured against the midpoint of the book when each aggressive
+/// order arrived, before it matched.
+/// Market orders arriving into an empty opposite side cannot trade and are only counted, as are orders trading while
+/// their own side is empty, which have no midpoint to be measured against.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExecutionAnalytics {
+    /// Trades of buy aggressive orders.
+    pub buy: SideAnalytics,
+    /// Trades of sell aggressive orders.
+    pub sell: SideAnalytics,
+    /// Number of market orders arriving while the opposite side of the book was empty.
+    pub empty_opposite_side: u64,
+    /// Number of orders that traded while their own side of the book was empty, their trades are not measured.
+    pub no_midpoint: u64,
+}
+
+impl ExecutionAnalytics {
+    /// Records the trades of an order.
+    /// Arguments:
+    /// - `side`: The side of the order.
+    /// - `arrival_mid`: The midpoint of the book when the order arrived, `None` if one side was empty.
+    /// - `opposite_empty`: Whether the order is a market order that arrived into an empty opposite side.
+    /// - `trades`: The trades generated by the order.
+    pub fn record<'a>(
+        &mut self,
+        side: Side,
+        arrival_mid: Option<FixedPointArithmetic>,
+        opposite_empty: bool,
+        trades: impl IntoIterator<Item = &'a Trade>,
+    ) {
+        if opposite_empty {
+            self.empty_opposite_side += 1;
+            return;
+        }
+
+        let mut trades = trades.into_iter().peekable();
+        let Some(mid) = arrival_mid else {
+            if trades.peek().is_some() {
+                self.no_midpoint += 1;
+            }
+            return;
+        };
+
+        let side_analytics = match side {
+            Side::Buy => &mut self.buy,
+            Side::Sell => &mut self.sell,
+        };
+        for trade in trades {
+            side_analytics.record(side, mid, trade.price);
+        }
+    }
+
+    /// Analytics of the trades of both sides.
+    pub fn total(&self) -> SideAnalytics {
+        self.buy.merge(&self.sell)
+    }
+}
+
+impl std::fmt::Display for ExecutionAnalytics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "all:  {}", self.total())?;
+        writeln!(f, "buy:  {}", self.buy)?;
+        writeln!(f, "sell: {}", self.sell)?;
+        write!(
+            f,
+            "excluded: {} order(s) into an empty opposite side, {} order(s) without a midpoint",
+            self.empty_opposite_side, self.no_midpoint
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(price: f64) -> Trade {
+        Trade {
+            price: FixedPointArithmetic::from_f64(price),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_effective_spread_and_price_improvement() {
+        let mut analytics = ExecutionAnalytics::default();
+        let mid = Some(FixedPointArithmetic::from_f64(100.0));
+
+        // Buys: 1.0 above the mid, then 0.5 below it
+        analytics.record(Side::Buy, mid, false, &[trade(101.0), trade(99.5)]);
+        // Sells: 0.5 below the mid
+        analytics.record(Side::Sell, mid, false, &[trade(99.5)]);
+
+        assert_eq!(analytics.buy.trades, 2);
+        assert_eq!(analytics.buy.improved_trades, 1);
+        assert_eq!(
+            analytics.buy.average_effective_spread(),
+            Some(FixedPointArithmetic::from_f64(0.5))
+        );
+        assert_eq!(analytics.buy.price_improvement_pct(), Some(50.0));
+
+        assert_eq!(analytics.sell.trades, 1);
+        assert_eq!(
+            analytics.sell.average_effective_spread(),
+            Some(FixedPointArithmetic::from_f64(1.0))
+        );
+        assert_eq!(analytics.sell.price_improvement_pct(), Some(0.0));
+
+        let total = analytics.total();
+        assert_eq!(total.trades, 3);
+        assert_eq!(
+            total.average_effective_spread(),
+            Some(FixedPointArithmetic::from_raw(66_666_666)) // 2 / 3, truncated
+        );
+    }
+
+    #[test]
+    fn test_excluded_orders() {
+        let mut analytics = ExecutionAnalytics::default();
+
+        analytics.record(Side::Buy, None, true, &[]);
+        analytics.record(Side::Sell, None, false, &[trade(100.0)]);
+        // Resting without trading, nothing to measure
+        analytics.record(Side::Sell, None, false, &[]);
+
+        assert_eq!(analytics.empty_opposite_side, 1);
+        assert_eq!(analytics.no_midpoint, 1);
+        assert_eq!(analytics.total(), SideAnalytics::default());
+        assert_eq!(analytics.total().average_effective_spread(), None);
+        assert_eq!(analytics.total().price_improvement_pct(), None);
+    }
+}
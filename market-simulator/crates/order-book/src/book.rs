@@ -1,4 +1,8 @@
+use crate::analytics::ExecutionAnalytics;
+use crate::matching::Depth;
 use std::collections::{BTreeMap, HashMap};
+use std::ops::Bound::{Excluded, Unbounded};
+use std::time::{SystemTime, UNIX_EPOCH};
 use types::{
     FixedPointArithmetic, OrderEvent, OrderResult, OrderStatus, OrderType, Side, Trade, Trades,
     macros::OrderId,
@@ -8,6 +12,14 @@ use utils::market_name;
 
 type NodeId = usize;
 
+/// Side an order of `side` trades against.
+fn opposite(side: Side) -> Side {
+    match side {
+        Side::Buy => Side::Sell,
+        Side::Sell => Side::Buy,
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 struct OrderRef {
     side: Side,
@@ -32,11 +44,26 @@ struct Node {
     next: Option<NodeId>,
 }
 
-#[derive(Debug, Clone, Default)]
+/// FIFO queue of the orders resting at one price.
+/// The order count and the cumulative quantity are maintained on every insert, fill and removal,
+/// so neither the depth nor the level cap has to walk the queue.
+#[derive(Debug, Clone)]
 pub struct PriceLevel {
     head: Option<NodeId>,
     tail: Option<NodeId>,
     len: usize,
+    quantity: FixedPointArithmetic,
+}
+
+impl Default for PriceLevel {
+    fn default() -> Self {
+        Self {
+            head: None,
+            tail: None,
+            len: 0,
+            quantity: FixedPointArithmetic::ZERO,
+        }
+    }
 }
 
 impl PriceLevel {
@@ -47,13 +74,19 @@ impl PriceLevel {
     pub fn is_empty(&self) -> bool {
         self.len == 0
     }
+
+    /// Total resting quantity of the level.
+    pub fn quantity(&self) -> FixedPointArithmetic {
+        self.quantity
+    }
 }
 
-/// Represents the order book, maintaining separate heaps for bids and asks.
-/// Bids are stored in a max-heap (higher prices have priority), while asks are stored in a min-heap (lower prices have priority).
+/// Represents the order book, maintaining separate price ladders for bids and asks.
+/// Orders match in price-time priority: the best price first (highest bid, lowest ask), and within a price level
+/// in arrival order, since each level is a FIFO queue appended on insert. The timestamps of the orders play no part.
 /// The order book processes incoming orders, matches them against existing orders, and updates the order book accordingly.
-/// - `bids`: A binary heap containing buy orders, sorted by price in descending order.
-/// - `asks`: A binary heap containing sell orders, sorted by price in ascending order (using `Reverse` to achieve min-heap behavior).
+/// - `bids`: Buy orders by price, matched from the highest price.
+/// - `asks`: Sell orders by price, matched from the lowest price.
 /// - `trade_id_counter`: A counter used to generate unique trade IDs for matched orders.
 #[derive(Debug)]
 pub struct OrderBook {
@@ -71,21 +104,116 @@ pub struct OrderBook {
     order_map: HashMap<OrderId, OrderRef>,
     /// The symbol for this order book.
     pub(crate) symbol: String,
+    /// Price of the last trade executed in this order book, if any.
+    pub(crate) last_trade_price: Option<FixedPointArithmetic>,
+    /// Maximum number of orders resting at a single price level, unlimited when `None`.
+    max_orders_per_level: Option<usize>,
+    /// Quantity every order quantity must be a multiple of, any quantity is accepted when `None`.
+    lot_size: Option<FixedPointArithmetic>,
+    /// Whether a limit order whose minimum quantity is not immediately executable rests instead of being rejected.
+    rest_unmet_min_quantity: bool,
+    /// Number of resting orders the book is pre-sized for, matching allocates nothing until it is exceeded.
+    order_capacity: usize,
+    /// Fixed clock stamping results and trades in milliseconds since epoch, the system clock when `None`.
+    clock_ms: Option<u64>,
+    /// Time the order being processed is stamped with, set by `process_order` before the order matches.
+    processing_time_ms: u64,
+    /// Execution quality of the trades, measured against the midpoint of the book when each order arrived.
+    analytics: ExecutionAnalytics,
+    /// Latest trades in execution order, the oldest are dropped once more than `trade_history_capacity` are kept.
+    trade_history: Vec<Trade>,
+    /// Number of trades kept in the tape, none are recorded when 0.
+    trade_history_capacity: usize,
 }
 
+/// Number of price levels per side printed by `Display for OrderBook` when no precision is given.
+pub const DEFAULT_DISPLAY_DEPTH: usize = 10;
+
+/// Number of trades kept in the tape of a book when no capacity is given.
+pub const DEFAULT_TRADE_HISTORY: usize = 256;
+
+/// Prints a price ladder of the top `DEFAULT_DISPLAY_DEPTH` levels per side, asks above bids.
+/// The depth can be set with the precision flag (`{:.5}`) or [`OrderBook::format_depth`], and `{:#}` prints every level.
 impl std::fmt::Display for OrderBook {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Ask")?;
-        for ask in self.asks.iter() {
-            write!(f, "\n{}", ask.0)?;
+        let depth = if f.alternate() {
+            usize::MAX
+        } else {
+            f.precision().unwrap_or(DEFAULT_DISPLAY_DEPTH)
+        };
+
+        let or_dash = |value: Option<FixedPointArithmetic>| match value {
+            Some(value) => value.to_string(),
+            None => "-".to_string(),
+        };
+        writeln!(
+            f,
+            "{} | bid {} | ask {} | spread {} | last {}",
+            self.symbol,
+            or_dash(self.best_price(Side::Buy)),
+            or_dash(self.best_price(Side::Sell)),
+            or_dash(self.get_spread()),
+            or_dash(self.last_trade_price),
+        )?;
+
+        // Rows are (cumulative bid qty, price, cumulative ask qty), deepest ask first
+        let mut rows: Vec<[String; 3]> = Vec::new();
+        let cumulate = |levels: Vec<(FixedPointArithmetic, FixedPointArithmetic)>| {
+            let mut total = FixedPointArithmetic::ZERO;
+            levels
+                .into_iter()
+                .map(|(price, quantity)| {
+                    total += quantity;
+                    (price, total)
+                })
+                .collect::<Vec<_>>()
+        };
+        for (price, total) in cumulate(self.aggregated_depth(Side::Sell, depth))
+            .into_iter()
+            .rev()
+        {
+            rows.push([String::new(), price.to_string(), total.to_string()]);
+        }
+        for (price, total) in cumulate(self.aggregated_depth(Side::Buy, depth)) {
+            rows.push([total.to_string(), price.to_string(), String::new()]);
         }
-        write!(f, "\nBid")?;
-        for bid in self.bids.iter().rev() {
-            write!(f, "\n{}", bid.0)?;
+
+        let header = [
+            "Bid Qty".to_string(),
+            "Price".to_string(),
+            "Ask Qty".to_string(),
+        ];
+        let widths: Vec<usize> = (0..3)
+            .map(|col| {
+                rows.iter()
+                    .chain(std::iter::once(&header))
+                    .map(|row| row[col].len())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let mut lines = std::iter::once(&header).chain(rows.iter()).peekable();
+        while let Some(row) = lines.next() {
+            let line = format!(
+                "{:>w0$} | {:>w1$} | {:>w2$}",
+                row[0],
+                row[1],
+                row[2],
+                w0 = widths[0],
+                w1 = widths[1],
+                w2 = widths[2]
+            );
+            // Bid rows have no ask column, drop its separator
+            write!(f, "{}", line.trim_end_matches([' ', '|']))?;
+            if lines.peek().is_some() {
+                writeln!(f)?;
+            }
         }
         Ok(())
     }
 }
+
 impl OrderBook {
     pub fn new(symbol: &str) -> Self {
         OrderBook {
@@ -98,9 +226,96 @@ impl OrderBook {
             free_nodes: Vec::new(),
             order_map: HashMap::new(),  // Initialize the order map
             symbol: symbol.to_string(), // Set the symbol for this order book
+            last_trade_price: None,
+            max_orders_per_level: None,
+            lot_size: None,
+            rest_unmet_min_quantity: false,
+            order_capacity: 0,
+            clock_ms: None,
+            processing_time_ms: 0,
+            analytics: ExecutionAnalytics::default(),
+            // Twice the capacity, so the oldest trades are dropped in batches rather than shifted on every trade
+            trade_history: Vec::with_capacity(2 * DEFAULT_TRADE_HISTORY),
+            trade_history_capacity: DEFAULT_TRADE_HISTORY,
         }
     }
 
+    /// Caps the number of orders resting at a single price level.
+    /// Limit orders joining a full level are rejected with `OrderStatus::RejectedLevelFull`, `None` removes the cap.
+    pub fn with_max_orders_per_level(mut self, max_orders_per_level: Option<usize>) -> Self {
+        self.max_orders_per_level = max_orders_per_level;
+        self
+    }
+
+    /// Maximum number of orders resting at a single price level, `None` when unlimited.
+    pub fn max_orders_per_level(&self) -> Option<usize> {
+        self.max_orders_per_level
+    }
+
+    /// Sets the lot size of the book, orders whose quantity is not a multiple of it are rejected with
+    /// `OrderStatus::RejectedInvalidLot`. `None`, or a lot size that is not positive, accepts any quantity.
+    pub fn with_lot_size(mut self, lot_size: Option<FixedPointArithmetic>) -> Self {
+        self.lot_size = lot_size.filter(|lot_size| lot_size.raw() > 0);
+        self
+    }
+
+    /// Lot size of the book, `None` when any quantity is accepted.
+    pub fn lot_size(&self) -> Option<FixedPointArithmetic> {
+        self.lot_size
+    }
+
+    /// Makes limit orders whose minimum quantity is not immediately executable rest in the book without matching,
+    /// instead of being rejected with `OrderStatus::RejectedMinQuantity`. Market orders are always rejected.
+    pub fn with_rest_unmet_min_quantity(mut self, rest_unmet_min_quantity: bool) -> Self {
+        self.rest_unmet_min_quantity = rest_unmet_min_quantity;
+        self
+    }
+
+    /// Whether limit orders whose minimum quantity is not immediately executable rest instead of being rejected.
+    pub fn rest_unmet_min_quantity(&self) -> bool {
+        self.rest_unmet_min_quantity
+    }
+
+    /// Reserves room for `order_capacity` resting orders in the order arena, its free list and the order map, so
+    /// processing orders allocates nothing as long as fewer orders rest in the book. Price levels are not reserved:
+    /// a `BTreeMap` only allocates when the number of levels of a side outgrows its nodes.
+    pub fn with_order_capacity(mut self, order_capacity: usize) -> Self {
+        self.order_capacity = order_capacity;
+        self.nodes.reserve(order_capacity);
+        self.free_nodes.reserve(order_capacity);
+        self.order_map.reserve(order_capacity);
+        self
+    }
+
+    /// Number of resting orders the book was pre-sized for.
+    pub fn order_capacity(&self) -> usize {
+        self.order_capacity
+    }
+
+    /// Sets the number of trades kept in the tape returned by `recent_trades`, 0 records none.
+    /// The tape is pre-sized, recording trades allocates nothing.
+    pub fn with_trade_history(mut self, trade_history_capacity: usize) -> Self {
+        self.trade_history_capacity = trade_history_capacity;
+        self.trade_history = Vec::with_capacity(2 * trade_history_capacity);
+        self
+    }
+
+    /// Number of trades kept in the tape.
+    pub fn trade_history_capacity(&self) -> usize {
+        self.trade_history_capacity
+    }
+
+    /// Replaces the system clock with a fixed time, used to reproduce journaled results.
+    /// Results and trades of the next orders are stamped with `clock_ms`, `None` restores the system clock.
+    pub fn set_clock_ms(&mut self, clock_ms: Option<u64>) {
+        self.clock_ms = clock_ms;
+    }
+
+    /// Fixed clock of the book in milliseconds since epoch, `None` when it runs on the system clock.
+    pub fn clock_ms(&self) -> Option<u64> {
+        self.clock_ms
+    }
+
     fn generate_internal_order_id(&mut self) -> u64 {
         let id = self.internal_id_counter;
         self.internal_id_counter += 1;
@@ -166,6 +381,26 @@ impl OrderBook {
         self.levels(side).get(&price).and_then(|level| level.head)
     }
 
+    /// Price of the level of `side` right after `price` in matching order, lower for bids and higher for asks.
+    fn next_level_price(
+        &self,
+        side: Side,
+        price: FixedPointArithmetic,
+    ) -> Option<FixedPointArithmetic> {
+        match side {
+            Side::Buy => self
+                .bids
+                .range(..price)
+                .next_back()
+                .map(|(&price, _)| price),
+            Side::Sell => self
+                .asks
+                .range((Excluded(price), Unbounded))
+                .next()
+                .map(|(&price, _)| price),
+        }
+    }
+
     fn collect_level_orders(&self, level: &PriceLevel) -> Vec<OrderEvent> {
         let mut orders = Vec::with_capacity(level.len);
         let mut current = level.head;
@@ -179,6 +414,63 @@ impl OrderBook {
         orders
     }
 
+    /// Fills `quantity` of a resting order, keeping the cumulative quantity of its level in sync.
+    fn fill_resting_order(
+        &mut self,
+        side: Side,
+        price: FixedPointArithmetic,
+        node_id: NodeId,
+        quantity: FixedPointArithmetic,
+    ) {
+        self.node_mut(node_id).order.quantity -= quantity;
+        self.levels_mut(side)
+            .get_mut(&price)
+            .expect("price level missing for node")
+            .quantity -= quantity;
+    }
+
+    /// Recounts every level and checks it against its maintained order count and quantity.
+    /// Walks the queues in place, so it allocates nothing unless it finds a level out of sync.
+    /// Returns:
+    /// - `Err` describing the first level whose order count or quantity is out of sync.
+    pub fn check_invariants(&self) -> Result<(), String> {
+        for (side, levels) in [(Side::Buy, &self.bids), (Side::Sell, &self.asks)] {
+            for (price, level) in levels {
+                let mut len = 0;
+                let mut quantity = FixedPointArithmetic::ZERO;
+                let mut current = level.head;
+                while let Some(node_id) = current {
+                    let node = self.node(node_id);
+                    len += 1;
+                    quantity += node.order.quantity;
+                    current = node.next;
+                }
+                if len != level.len {
+                    return Err(format!(
+                        "order count out of sync at {:?} {}: {} queued, {} counted",
+                        side, price, len, level.len
+                    ));
+                }
+                if quantity != level.quantity {
+                    return Err(format!(
+                        "level quantity out of sync at {:?} {}: {} queued, {} counted",
+                        side, price, quantity, level.quantity
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Panics if a level is out of sync, see `check_invariants`.
+    /// Only compiled in debug builds, where it runs after each processed order.
+    #[cfg(debug_assertions)]
+    fn assert_level_invariants(&self) {
+        if let Err(violation) = self.check_invariants() {
+            panic!("{}", violation);
+        }
+    }
+
     #[cfg(test)]
     fn price_level_orders(&self, side: Side, price: FixedPointArithmetic) -> Vec<OrderEvent> {
         self.levels(side)
@@ -202,6 +494,7 @@ impl OrderBook {
             }
             level.tail = Some(node_id);
             level.len += 1;
+            level.quantity += order.quantity;
             prev_tail
         };
 
@@ -244,6 +537,7 @@ impl OrderBook {
                 level.tail = prev;
             }
             level.len -= 1;
+            level.quantity -= order.quantity;
             level.is_empty()
         };
 
@@ -280,11 +574,299 @@ impl OrderBook {
     /// - An `OrderResult` containing the details of the processed order, including any trade ID and status.
     //#[instrument(level = "debug", skip(self, order), fields(order_id = order.order_id, side = ?order.side, price = order.price, quantity = order.quantity))]
     pub fn process_order(&mut self, order: OrderEvent) -> (OrderEvent, OrderResult) {
+        let event_time_ms = self.event_time_ms();
+        // Trades are stamped and taped as they are matched
+        self.processing_time_ms = event_time_ms;
+        // The midpoint is taken before the order matches, so its trades are measured against the book it arrived into
+        let opposite_side = opposite(order.side);
+        let (arrival_mid, opposite_empty) = match order.order_type {
+            OrderType::CancelOrder => (None, false),
+            // A limit order into an empty opposite side only rests, it is not aggressive
+            OrderType::LimitOrder | OrderType::ImmediateOrCancel | OrderType::FillOrKill => {
+                (self.mid_price(), false)
+            }
+            OrderType::MarketOrder => (self.mid_price(), self.best_price(opposite_side).is_none()),
+        };
+        // Invalid orders are rejected before they can match or rest
+        let processed = match self.invalid_order_status(&order) {
+            Some(status) => self.reject_order(order, status),
+            None => match order.order_type {
+                OrderType::LimitOrder => self.process_limit_order(order),
+                OrderType::MarketOrder => self.process_market_order(order),
+                OrderType::CancelOrder => self.process_cancel_order(order),
+                OrderType::ImmediateOrCancel => self.process_immediate_or_cancel_order(order),
+                OrderType::FillOrKill => self.process_fill_or_kill_order(order),
+            },
+        };
+        #[cfg(debug_assertions)]
+        self.assert_level_invariants();
+        // The result and its trades share the time the order was processed at
+        let (order, mut result) = processed;
+        result.timestamp_ms = event_time_ms;
+        result.arrival_mid = arrival_mid;
+        if order.order_type != OrderType::CancelOrder {
+            self.analytics.record(
+                order.side,
+                arrival_mid,
+                opposite_empty,
+                result.trades.iter(),
+            );
+        }
+        (order, result)
+    }
+
+    /// Appends a trade to the tape and makes its price the last trade price.
+    /// The oldest half of the storage is dropped once it is full.
+    fn record_trade(&mut self, trade: Trade) {
+        self.last_trade_price = Some(trade.price);
+        if self.trade_history_capacity == 0 {
+            return;
+        }
+        if self.trade_history.len() == 2 * self.trade_history_capacity {
+            self.trade_history.drain(..self.trade_history_capacity);
+        }
+        self.trade_history.push(trade);
+    }
+
+    /// Time an order processed now is stamped with: the simulated clock when set, the wall clock otherwise.
+    fn event_time_ms(&self) -> u64 {
+        self.clock_ms.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as u64
+        })
+    }
+
+    /// Status rejecting `order` before any matching, or `None` if it can be processed.
+    /// A zero or negative quantity could never fill and a zero or negative limit price would sit at the top of the
+    /// bids forever. A minimum quantity above the order quantity could never be met. Cancels only carry the order to
+    /// cancel and are not checked.
+    fn invalid_order_status(&self, order: &OrderEvent) -> Option<OrderStatus> {
         match order.order_type {
-            OrderType::LimitOrder => self.process_limit_order(order),
-            OrderType::MarketOrder => self.process_market_order(order),
-            OrderType::CancelOrder => self.process_cancel_order(order),
+            OrderType::CancelOrder => None,
+            _ if order.quantity <= FixedPointArithmetic::ZERO => {
+                Some(OrderStatus::RejectedInvalidQuantity)
+            }
+            _ if order.min_quantity < FixedPointArithmetic::ZERO
+                || order.min_quantity > order.quantity =>
+            {
+                Some(OrderStatus::RejectedInvalidQuantity)
+            }
+            OrderType::LimitOrder | OrderType::ImmediateOrCancel | OrderType::FillOrKill
+                if order.price <= FixedPointArithmetic::ZERO =>
+            {
+                Some(OrderStatus::RejectedInvalidPrice)
+            }
+            _ if self
+                .lot_size
+                .is_some_and(|lot_size| order.quantity.raw() % lot_size.raw() != 0) =>
+            {
+                Some(OrderStatus::RejectedInvalidLot)
+            }
+            _ => None,
+        }
+    }
+
+    /// Rejects `order` with `status`: it gets an internal ID but neither matches nor rests in the book.
+    pub(crate) fn reject_order(
+        &mut self,
+        order: OrderEvent,
+        status: OrderStatus,
+    ) -> (OrderEvent, OrderResult) {
+        tracing::warn!(
+            "[{}][{}][{}] Rejected order with ID: {}, side: {:?}, price: {}, quantity: {}: {}",
+            market_name(),
+            order.symbol,
+            order.cl_ord_id,
+            order.cl_ord_id,
+            order.side,
+            order.price,
+            order.quantity,
+            status
+        );
+        (
+            order,
+            OrderResult {
+                internal_order_id: self.generate_internal_order_id(),
+                trades: Trades::default(),
+                status,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Whether the level of `side` at `price` already holds the maximum number of orders.
+    fn is_level_full(&self, side: Side, price: FixedPointArithmetic) -> bool {
+        match self.max_orders_per_level {
+            Some(max) => self
+                .levels(side)
+                .get(&price)
+                .is_some_and(|level| level.len >= max),
+            None => false,
+        }
+    }
+
+    /// Position of a resting order in the queue of its price level.
+    /// Arguments:
+    /// - `order_id`: The client order ID of the resting order.
+    /// Returns:
+    /// - `Some((ahead_orders, ahead_qty))`, the number of orders queued before it at the same price and their remaining quantity, or `None` if the order is not resting in the book.
+    pub fn queue_position(&self, order_id: OrderId) -> Option<(usize, FixedPointArithmetic)> {
+        let order_ref = self.order_map.get(&order_id)?;
+        let mut ahead_orders = 0;
+        let mut ahead_qty = FixedPointArithmetic::ZERO;
+        let mut current = self.node(order_ref.node_id).prev;
+
+        while let Some(node_id) = current {
+            let node = self.node(node_id);
+            ahead_orders += 1;
+            ahead_qty += node.order.quantity;
+            current = node.prev;
+        }
+
+        Some((ahead_orders, ahead_qty))
+    }
+
+    /// Cancels a resting order, removing it from the queue of its price level. The level is dropped once empty.
+    /// Arguments:
+    /// - `order_id`: The client order ID of the resting order.
+    /// Returns:
+    /// - The removed order with its remaining quantity, or `None` if the order is not resting in the book.
+    pub fn cancel_order(&mut self, order_id: &OrderId) -> Option<OrderEvent> {
+        let cancelled_order = self.remove_order(order_id);
+
+        #[cfg(debug_assertions)]
+        self.assert_level_invariants();
+
+        cancelled_order
+    }
+
+    /// Replaces the price and quantity of a resting order.
+    /// A quantity decrease at the same price amends the order in place, it keeps its time priority. A price change or
+    /// a quantity increase removes the order and processes the replacement as a fresh limit order: it may match, and
+    /// whatever is left rests at the back of its level. A replacement that would be rejected leaves the order as it was.
+    /// Arguments:
+    /// - `order_id`: The client order ID of the resting order.
+    /// - `new_price`: The limit price of the replacement.
+    /// - `new_quantity`: The quantity of the replacement, what is left to execute.
+    /// Returns:
+    /// - The `OrderResult` of the replacement, with the trades of a reinserted order, `CancelRejected` if the order is not resting in the book.
+    pub fn replace_order(
+        &mut self,
+        order_id: &OrderId,
+        new_price: FixedPointArithmetic,
+        new_quantity: FixedPointArithmetic,
+    ) -> OrderResult {
+        let Some(order_ref) = self.order_map.get(order_id).copied() else {
+            tracing::error!(
+                "[{}][{}] Failed to replace order with ID: {}, order not found in order book",
+                market_name(),
+                self.symbol,
+                order_id
+            );
+            return OrderResult {
+                internal_order_id: self.generate_internal_order_id(),
+                trades: Trades::default(),
+                status: OrderStatus::CancelRejected,
+                timestamp_ms: self.event_time_ms(),
+                ..Default::default()
+            };
+        };
+
+        let resting_quantity = self.node(order_ref.node_id).order.quantity;
+        let mut replacement = self.node(order_ref.node_id).order;
+        replacement.order_type = OrderType::LimitOrder;
+        replacement.price = new_price;
+        replacement.quantity = new_quantity;
+        let in_place = new_price == order_ref.price && new_quantity <= resting_quantity;
+        if in_place {
+            // The minimum of a resting order is capped at what it has left, as `fill_quantity` does
+            replacement.min_quantity = replacement.min_quantity.min(new_quantity);
+        }
+
+        if let Some(status) = self.replacement_rejection(order_ref, &replacement, in_place) {
+            let (_, mut result) = self.reject_order(replacement, status);
+            result.timestamp_ms = self.event_time_ms();
+            return result;
+        }
+
+        if in_place {
+            let reduction = resting_quantity - new_quantity;
+            self.node_mut(order_ref.node_id).order.quantity = new_quantity;
+            self.levels_mut(order_ref.side)
+                .get_mut(&order_ref.price)
+                .expect("price level missing for node")
+                .quantity -= reduction;
+
+            tracing::debug!(
+                "[{}][{}][{}] Amended order with ID: {} in place, quantity: {} -> {}",
+                market_name(),
+                self.symbol,
+                order_id,
+                order_id,
+                resting_quantity,
+                new_quantity
+            );
+
+            #[cfg(debug_assertions)]
+            self.assert_level_invariants();
+
+            return OrderResult {
+                internal_order_id: self.generate_internal_order_id(),
+                trades: Trades::default(),
+                status: OrderStatus::New,
+                timestamp_ms: self.event_time_ms(),
+                ..Default::default()
+            };
+        }
+
+        // Loses its time priority, as a cancel followed by a new order would
+        self.remove_order(order_id);
+        let (_, result) = self.process_order(replacement);
+        result
+    }
+
+    /// Status rejecting the replacement of the resting order `order_ref` by `replacement`, checked before the resting
+    /// order is removed: the checks of a new order, the level cap of a new price and an unmet minimum quantity.
+    /// An order amended `in_place` does not match again, its minimum quantity is not checked against the book.
+    fn replacement_rejection(
+        &self,
+        order_ref: OrderRef,
+        replacement: &OrderEvent,
+        in_place: bool,
+    ) -> Option<OrderStatus> {
+        if let Some(status) = self.invalid_order_status(replacement) {
+            return Some(status);
+        }
+        // At the same price the order frees its own slot of the level
+        if replacement.price != order_ref.price
+            && self.is_level_full(replacement.side, replacement.price)
+        {
+            return Some(OrderStatus::RejectedLevelFull);
+        }
+        if !in_place && self.unmet_min_quantity(replacement) && !self.rest_unmet_min_quantity {
+            return Some(OrderStatus::RejectedMinQuantity);
+        }
+        None
+    }
+
+    /// Removes a resting order from the order map and from the queue of its price level.
+    fn remove_order(&mut self, order_id: &OrderId) -> Option<OrderEvent> {
+        let order_ref = self.order_map.remove(order_id)?;
+        let removed = self.unlink_node(order_ref.side, order_ref.price, order_ref.node_id);
+        if removed.is_none() {
+            tracing::error!(
+                "[{}][{}] Failed to remove order with ID: {}, side: {:?}, price: {}, node_id: {}, order not found in queue",
+                market_name(),
+                self.symbol,
+                order_id,
+                order_ref.side,
+                order_ref.price,
+                order_ref.node_id
+            );
         }
+        removed
     }
 
     /// Processes a limit order by matching it against existing orders in the order book based on its side (buy or sell). For buy limit orders, it matches against the best available asks, and for sell limit orders, it matches against the best available bids. If the order is not fully filled after matching, it is added to the appropriate side of the order book (bids for buy orders and asks for sell orders) for future matching.
@@ -293,34 +875,95 @@ impl OrderBook {
     /// Returns:
     /// - An `OrderResult` containing the details of the processed order, including any trade ID and status. The trade ID is generated if the order was partially or fully filled, and the status is determined based on the remaining quantity of the order.
     fn process_limit_order(&mut self, order: OrderEvent) -> (OrderEvent, OrderResult) {
-        match order.side {
-            Side::Buy => {
-                let (order, result) = self.process_buy_limit_order(order);
-                (order, result)
-            }
-            Side::Sell => {
-                let (order, result) = self.process_sell_limit_order(order);
-                (order, result)
-            }
+        // A resting level at the order price means the order cannot cross the book, it would only join that level
+        if self.is_level_full(order.side, order.price) {
+            return self.reject_order(order, OrderStatus::RejectedLevelFull);
         }
+
+        self.match_and_rest(order)
     }
 
-    /// Processes a market order by treating it as a limit order with an infinitely high price for buy orders or an infinitely low price for sell orders. This ensures that market orders will match with the best available prices in the order book. The function then calls the appropriate processing function for limit orders to handle the matching and execution of the market order.
+    /// Processes a market order by treating it as a limit order with an infinitely high price for buy orders or an infinitely low price for sell orders. This ensures that market orders will match with the best available prices in the order book. A market order never rests: the quantity the opposite side cannot fill is cancelled, as for an immediate-or-cancel order. A minimum quantity that cannot be met right away rejects it with `OrderStatus::RejectedMinQuantity`.
     /// Arguments:
     /// - `order`: The incoming market order to be processed, containing details such as price, quantity, side, order ID, and broker ID.
     /// Returns:
-    /// - An `OrderResult` containing the details of the processed order, including any trade ID and status. The trade ID is generated if the order was partially or fully filled, and the status is determined based on the remaining quantity of the order.
-    fn process_market_order(&mut self, order: OrderEvent) -> (OrderEvent, OrderResult) {
-        match order.side {
-            Side::Buy => {
-                let (order, result) = self.process_buy_market_order(order);
-                (order, result)
-            }
-            Side::Sell => {
-                let (order, result) = self.process_sell_market_order(order);
-                (order, result)
-            }
+    /// - An `OrderResult` with the trades of the order, `Filled`, `PartiallyFilled`, or `Unmatched` when the opposite side is empty.
+    fn process_market_order(&mut self, mut order: OrderEvent) -> (OrderEvent, OrderResult) {
+        order.price = match order.side {
+            Side::Buy => FixedPointArithmetic::from_f64(f64::INFINITY), // Market orders are treated as having an infinitely high price to ensure they match with the best available asks
+            Side::Sell => FixedPointArithmetic::from_f64(f64::NEG_INFINITY), // Market orders are treated as having an infinitely low price to ensure they match with the best available bids
+        };
+        if self.unmet_min_quantity(&order) {
+            return self.reject_order(order, OrderStatus::RejectedMinQuantity);
+        }
+
+        let (remaining_quantity, trades) = self.match_order(&order);
+        if remaining_quantity > FixedPointArithmetic::ZERO {
+            tracing::debug!(
+                "[{}][{}][{}] Market order with ID: {} ran out of liquidity, unfilled quantity cancelled: {}",
+                market_name(),
+                order.symbol,
+                order.cl_ord_id,
+                order.cl_ord_id,
+                remaining_quantity
+            );
+        }
+        let (order, mut order_result) = self.generate_order_result(order, trades);
+        order_result.status = if remaining_quantity == FixedPointArithmetic::ZERO {
+            OrderStatus::Filled
+        } else if remaining_quantity < order.quantity {
+            OrderStatus::PartiallyFilled
+        } else {
+            OrderStatus::Unmatched
+        };
+        (order, order_result)
+    }
+
+    /// Processes an immediate-or-cancel order: it matches like a limit order up to its price, but the quantity left
+    /// unfilled is cancelled instead of resting in the book. A minimum quantity that cannot be met right away rejects
+    /// it with `OrderStatus::RejectedMinQuantity`.
+    /// Arguments:
+    /// - `order`: The incoming immediate-or-cancel order.
+    /// Returns:
+    /// - An `OrderResult` with the trades of the order, `Filled`, `PartiallyFilled`, or `Cancelled` when none traded.
+    fn process_immediate_or_cancel_order(
+        &mut self,
+        order: OrderEvent,
+    ) -> (OrderEvent, OrderResult) {
+        if self.unmet_min_quantity(&order) {
+            return self.reject_order(order, OrderStatus::RejectedMinQuantity);
+        }
+
+        let (remaining_quantity, trades) = self.match_order(&order);
+        let (order, mut order_result) = self.generate_order_result(order, trades);
+        order_result.status = if remaining_quantity == FixedPointArithmetic::ZERO {
+            OrderStatus::Filled
+        } else if remaining_quantity < order.quantity {
+            OrderStatus::PartiallyFilled
+        } else {
+            OrderStatus::Cancelled
+        };
+        (order, order_result)
+    }
+
+    /// Processes a fill-or-kill order: the opposite side is scanned first, and the order only matches if its whole
+    /// quantity is executable up to its price. Otherwise it is killed, no trade occurs and the book is left unchanged.
+    /// Arguments:
+    /// - `order`: The incoming fill-or-kill order.
+    /// Returns:
+    /// - An `OrderResult` with the trades of the order and `Filled`, or `Unmatched` without trades when it is killed.
+    fn process_fill_or_kill_order(&mut self, order: OrderEvent) -> (OrderEvent, OrderResult) {
+        if self.executable_quantity(&order) < order.quantity {
+            let (order, mut order_result) = self.generate_order_result(order, Trades::default());
+            order_result.status = OrderStatus::Unmatched;
+            return (order, order_result);
         }
+
+        let (remaining_quantity, trades) = self.match_order(&order);
+        debug_assert_eq!(remaining_quantity, FixedPointArithmetic::ZERO);
+        let (order, mut order_result) = self.generate_order_result(order, trades);
+        order_result.status = OrderStatus::Filled;
+        (order, order_result)
     }
 
     /// Processes a cancel order by looking up the original order using the `orig_cl_ord_id` and removing it from the order book if it exists. The function checks for the validity of the cancel order, including the presence of the original client order ID and the existence of the original order in the order book. If the cancellation is successful, it returns an `OrderResult` with a status of `Cancelled`. If the cancellation fails (e.g., due to missing original client order ID or order not found), it returns an `OrderResult` with a status of `CancelRejected`.
@@ -350,56 +993,30 @@ impl OrderBook {
             );
         };
 
-        if let Some(order_ref) = self.order_map.get(&orig_cl_ord_id).copied() {
-            if let Some(cancelled_order) =
-                self.unlink_node(order_ref.side, order_ref.price, order_ref.node_id)
-            {
-                let mut cancel_ack = order;
-                cancel_ack.side = cancelled_order.side;
-                cancel_ack.price = cancelled_order.price;
-                cancel_ack.quantity = cancelled_order.quantity;
-
-                tracing::debug!(
-                    "[{}][{}][{}] Cancelled order with ID: {}, side: {:?}, price: {}, node_id: {}",
-                    market_name(),
-                    order.symbol,
-                    order.cl_ord_id,
-                    orig_cl_ord_id,
-                    order_ref.side,
-                    order_ref.price,
-                    order_ref.node_id
-                );
-
-                if self.order_map.remove(&orig_cl_ord_id).is_none() {
-                    tracing::error!(
-                        "[{}][{}][{}] Failed to remove order with ID: {} from order map after cancellation, order not found",
-                        market_name(),
-                        order.symbol,
-                        order.cl_ord_id,
-                        orig_cl_ord_id
-                    );
-                }
-
-                return (
-                    cancel_ack,
-                    OrderResult {
-                        internal_order_id: self.generate_internal_order_id(),
-                        trades: Trades::default(),
-                        status: OrderStatus::Cancelled,
-                        ..Default::default()
-                    },
-                );
-            }
+        if let Some(cancelled_order) = self.remove_order(&orig_cl_ord_id) {
+            let mut cancel_ack = order;
+            cancel_ack.side = cancelled_order.side;
+            cancel_ack.price = cancelled_order.price;
+            cancel_ack.quantity = cancelled_order.quantity;
 
-            tracing::error!(
-                "[{}][{}][{}] Failed to cancel order with ID: {}, side: {:?}, price: {}, node_id: {}, order not found in queue",
+            tracing::debug!(
+                "[{}][{}][{}] Cancelled order with ID: {}, side: {:?}, price: {}",
                 market_name(),
                 order.symbol,
                 order.cl_ord_id,
                 orig_cl_ord_id,
-                order_ref.side,
-                order_ref.price,
-                order_ref.node_id
+                cancelled_order.side,
+                cancelled_order.price
+            );
+
+            return (
+                cancel_ack,
+                OrderResult {
+                    internal_order_id: self.generate_internal_order_id(),
+                    trades: Trades::default(),
+                    status: OrderStatus::Cancelled,
+                    ..Default::default()
+                },
             );
         }
 
@@ -444,40 +1061,109 @@ impl OrderBook {
         (order, order_result)
     }
 
-    /// Processes a sell limit order by matching it against the best available bids in the order book. If the order is not fully filled, it is added to the asks heap.
-    /// Arguments:
-    /// - `order`: The incoming sell limit order to be processed.
-    /// Returns:
-    /// - An `OrderResult` containing the details of the processed order, including any trade ID and status.
-    fn process_sell_limit_order(&mut self, order: OrderEvent) -> (OrderEvent, OrderResult) {
+    /// Whether an order of `order.side` limited to `order.price` can trade with a resting order at `price`.
+    fn crosses(order: &OrderEvent, price: FixedPointArithmetic) -> bool {
+        match order.side {
+            Side::Buy => price <= order.price,
+            Side::Sell => price >= order.price,
+        }
+    }
+
+    /// Quantity an aggressor with `remaining` left trades with the resting `maker`, or `None` when the fill would be
+    /// smaller than the minimum quantity of the maker, which is then skipped.
+    /// The minimum is capped at what the maker has left, so a partially filled order can always be completed.
+    fn fill_quantity(
+        remaining: FixedPointArithmetic,
+        maker: &OrderEvent,
+    ) -> Option<FixedPointArithmetic> {
+        let quantity = remaining.min(maker.quantity);
+        (quantity >= maker.min_quantity.min(maker.quantity)).then_some(quantity)
+    }
+
+    /// Quantity of `order` that would execute right away, walking the book with the same rules as `match_order`.
+    fn executable_quantity(&self, order: &OrderEvent) -> FixedPointArithmetic {
+        let maker_side = opposite(order.side);
+        let mut remaining = order.quantity;
+        let mut level_price = self.best_price(maker_side);
+
+        while let Some(price) = level_price {
+            if !Self::crosses(order, price) {
+                break;
+            }
+            let mut current = self.head_node_id(maker_side, price);
+            while let Some(node_id) = current {
+                let node = self.node(node_id);
+                if let Some(quantity) = Self::fill_quantity(remaining, &node.order) {
+                    remaining -= quantity;
+                    if remaining == FixedPointArithmetic::ZERO {
+                        return order.quantity;
+                    }
+                }
+                current = node.next;
+            }
+            level_price = self.next_level_price(maker_side, price);
+        }
+
+        order.quantity - remaining
+    }
+
+    /// Whether `order` has a minimum quantity and less than that is executable right away.
+    fn unmet_min_quantity(&self, order: &OrderEvent) -> bool {
+        order.min_quantity > FixedPointArithmetic::ZERO
+            && self.executable_quantity(order) < order.min_quantity
+    }
+
+    /// Matches `order` against the opposite side of the book, best price first and in time priority within a level.
+    /// Returns the quantity left to execute and the trades.
+    ///
+    /// Minimum quantities (FIX MinQty) follow these rules:
+    /// - A resting order with a minimum only trades in fills of at least that minimum. An aggressor whose fill would
+    ///   be smaller skips it and moves on to the next order of the level, the skipped order keeps its place in the
+    ///   queue. Price priority is kept: levels are still walked best price first and never beyond the aggressor
+    ///   limit, only the time priority of the skipped order is given up, by the constraint its owner set.
+    /// - The aggressor may then trade at a worse level than a skipped order or rest at a price crossing it, so the
+    ///   book can stay locked or crossed until an order large enough for the minimum arrives.
+    fn match_order(&mut self, order: &OrderEvent) -> (FixedPointArithmetic, Trades<4>) {
+        let maker_side = opposite(order.side);
         let mut remaining_quantity = order.quantity;
         let mut trades = Trades::default();
-        while let Some(best_bid_price) = self.best_price(Side::Buy) {
-            if best_bid_price < order.price {
+        let mut level_price = self.best_price(maker_side);
+
+        while let Some(price) = level_price {
+            if remaining_quantity == FixedPointArithmetic::ZERO || !Self::crosses(order, price) {
                 break;
             }
+            // Looked up before the fills, which may remove the current level
+            let next_level_price = self.next_level_price(maker_side, price);
+            let mut current = self.head_node_id(maker_side, price);
 
-            while remaining_quantity > FixedPointArithmetic::ZERO {
-                let best_bid_id = match self.head_node_id(Side::Buy, best_bid_price) {
-                    Some(node_id) => node_id,
-                    None => break,
-                };
+            while let Some(maker_id) = current {
+                if remaining_quantity == FixedPointArithmetic::ZERO {
+                    break;
+                }
+                let maker = self.node(maker_id).order;
+                current = self.node(maker_id).next;
 
-                let maker_qty_before = self.node(best_bid_id).order.quantity;
-                let trade_quantity = remaining_quantity.min(maker_qty_before);
-                self.node_mut(best_bid_id).order.quantity -= trade_quantity;
+                let Some(trade_quantity) = Self::fill_quantity(remaining_quantity, &maker) else {
+                    continue;
+                };
+                self.fill_resting_order(maker_side, price, maker_id, trade_quantity);
                 remaining_quantity -= trade_quantity;
 
-                let best_bid = self.node(best_bid_id).order;
-                if let Err(_) = trades.add_trade(Trade {
-                    price: best_bid.price,
-                    cl_ord_id: best_bid.cl_ord_id,
+                let maker_after = self.node(maker_id).order;
+                let trade = Trade {
+                    price: maker_after.price,
+                    cl_ord_id: maker_after.cl_ord_id,
+                    sender_id: maker_after.sender_id,
                     quantity: trade_quantity,
                     id: self.generate_trade_id(),
-                    order_qty: maker_qty_before,
-                    leaves_qty: best_bid.quantity,
-                    ..Default::default()
-                }) {
+                    order_qty: maker.quantity,
+                    leaves_qty: maker_after.quantity,
+                    timestamp: self.processing_time_ms,
+                };
+                // Every fill reaches the tape, even those beyond the trades an OrderResult holds
+                self.record_trade(trade);
+                if let Err(_) = trades.add_trade(trade) {
                     tracing::error!(
                         "[{}][{}][{}] Maximum number of trades reached for this order, some trades may not be recorded in the OrderResult",
                         market_name(),
@@ -486,82 +1172,38 @@ impl OrderBook {
                     );
                 }
 
-                if best_bid.quantity == FixedPointArithmetic::ZERO {
-                    self.unlink_node(Side::Buy, best_bid_price, best_bid_id);
-                    self.order_map.remove(&best_bid.cl_ord_id);
-                }
-
-                if remaining_quantity == FixedPointArithmetic::ZERO {
-                    return self.generate_order_result(order, trades);
+                if maker_after.quantity == FixedPointArithmetic::ZERO {
+                    self.unlink_node(maker_side, price, maker_id);
+                    self.order_map.remove(&maker_after.cl_ord_id);
                 }
             }
+            level_price = next_level_price;
         }
 
-        let order_result = self.generate_order_result(order, trades);
-
-        if remaining_quantity > FixedPointArithmetic::ZERO {
-            let mut resting_order = order;
-            resting_order.quantity = remaining_quantity;
-            self.add_resting_order(resting_order);
-        }
-
-        order_result
+        (remaining_quantity, trades)
     }
 
-    /// Processes a buy limit order by matching it against the best available asks in the order book. If the order is not fully filled, it is added to the bids heap.
+    /// Processes a limit order against the opposite side of the book. If the order is not fully filled, the remaining
+    /// quantity rests on its side of the book.
+    /// An order with a minimum quantity only matches if at least that quantity is executable right away, otherwise it
+    /// is rejected with `OrderStatus::RejectedMinQuantity`, or rests without matching when `rest_unmet_min_quantity`
+    /// is set. Its minimum then applies to the fills of the resting order, see `match_order`.
     /// Arguments:
-    /// - `order`: The incoming buy limit order to be processed.
+    /// - `order`: The incoming order to be processed.
     /// Returns:
     /// - An `OrderResult` containing the details of the processed order, including any trade ID and status.
-    fn process_buy_limit_order(&mut self, order: OrderEvent) -> (OrderEvent, OrderResult) {
-        let mut remaining_quantity = order.quantity;
-        let mut trades = Trades::default();
-        while let Some(best_ask_price) = self.best_price(Side::Sell) {
-            if best_ask_price > order.price {
-                break;
-            }
-
-            while remaining_quantity > FixedPointArithmetic::ZERO {
-                let best_ask_id = match self.head_node_id(Side::Sell, best_ask_price) {
-                    Some(node_id) => node_id,
-                    None => break,
-                };
-
-                let maker_qty_before = self.node(best_ask_id).order.quantity;
-                let trade_quantity = remaining_quantity.min(maker_qty_before);
-                self.node_mut(best_ask_id).order.quantity -= trade_quantity;
-                remaining_quantity -= trade_quantity;
-
-                let best_ask = self.node(best_ask_id).order;
-                if let Err(_) = trades.add_trade(Trade {
-                    price: best_ask.price,
-                    cl_ord_id: best_ask.cl_ord_id,
-                    quantity: trade_quantity,
-                    id: self.generate_trade_id(),
-                    order_qty: maker_qty_before,
-                    leaves_qty: best_ask.quantity,
-                    ..Default::default()
-                }) {
-                    tracing::error!(
-                        "[{}][{}][{}] Maximum number of trades reached for this order, some trades may not be recorded in the OrderResult",
-                        market_name(),
-                        order.symbol,
-                        order.cl_ord_id
-                    );
-                }
-
-                if best_ask.quantity == FixedPointArithmetic::ZERO {
-                    self.unlink_node(Side::Sell, best_ask_price, best_ask_id);
-                    self.order_map.remove(&best_ask.cl_ord_id);
-                }
-
-                if remaining_quantity == FixedPointArithmetic::ZERO {
-                    return self.generate_order_result(order, trades);
-                }
+    fn match_and_rest(&mut self, order: OrderEvent) -> (OrderEvent, OrderResult) {
+        if self.unmet_min_quantity(&order) {
+            if !self.rest_unmet_min_quantity {
+                return self.reject_order(order, OrderStatus::RejectedMinQuantity);
             }
+            let order_result = self.generate_order_result(order, Trades::default());
+            self.add_resting_order(order);
+            return order_result;
         }
 
-        let (order, order_result) = self.generate_order_result(order, trades);
+        let (remaining_quantity, trades) = self.match_order(&order);
+        let order_result = self.generate_order_result(order, trades);
 
         if remaining_quantity > FixedPointArithmetic::ZERO {
             let mut resting_order = order;
@@ -569,17 +1211,7 @@ impl OrderBook {
             self.add_resting_order(resting_order);
         }
 
-        (order, order_result)
-    }
-
-    fn process_buy_market_order(&mut self, mut order: OrderEvent) -> (OrderEvent, OrderResult) {
-        order.price = FixedPointArithmetic::from_f64(f64::INFINITY); // Market orders are treated as having an infinitely high price to ensure they match with the best available asks
-        self.process_buy_limit_order(order)
-    }
-
-    fn process_sell_market_order(&mut self, mut order: OrderEvent) -> (OrderEvent, OrderResult) {
-        order.price = FixedPointArithmetic::from_f64(f64::NEG_INFINITY); // Market orders are treated as having an infinitely low price to ensure they match with the best available bids
-        self.process_sell_limit_order(order)
+        order_result
     }
 
     /// Gets the best bid from the order book, which is the highest-priced buy order. Since bids are stored in a max-heap, we can directly access the top element.
@@ -612,6 +1244,102 @@ impl OrderBook {
         }
     }
 
+    /// Calculates the midpoint of the order book, halfway between the best bid and the best ask. If either the best bid or best ask is not available, it returns `None`.
+    /// Returns:
+    /// - An `Option<FixedPointArithmetic>` containing the midpoint if both sides are quoted, or `None` if either is empty.
+    pub fn mid_price(&self) -> Option<FixedPointArithmetic> {
+        match (self.best_price(Side::Buy), self.best_price(Side::Sell)) {
+            (Some(best_bid), Some(best_ask)) => Some(FixedPointArithmetic::from_raw(
+                ((best_bid.raw() as i128 + best_ask.raw() as i128) / 2) as i64,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Execution quality of the trades processed since the book was created.
+    /// Returns:
+    /// - The effective spread and price improvement analytics, per aggressor side.
+    pub fn analytics(&self) -> &ExecutionAnalytics {
+        &self.analytics
+    }
+
+    /// Aggregates the order book by price level for a given side, best price first.
+    /// Arguments:
+    /// - `side`: The side of the order book to aggregate.
+    /// - `depth`: The maximum number of price levels to return.
+    /// Returns:
+    /// - A `Vec<(FixedPointArithmetic, FixedPointArithmetic)>` of (price, total resting quantity) pairs.
+    pub fn aggregated_depth(
+        &self,
+        side: Side,
+        depth: usize,
+    ) -> Vec<(FixedPointArithmetic, FixedPointArithmetic)> {
+        let mut levels = Vec::new();
+        self.aggregated_depth_into(side, depth, &mut levels);
+        levels
+    }
+
+    /// Same as `aggregated_depth`, written into `levels` so the caller can reuse its buffer.
+    /// `levels` is cleared first and does not reallocate as long as its capacity covers `depth` levels.
+    pub fn aggregated_depth_into(
+        &self,
+        side: Side,
+        depth: usize,
+        levels: &mut Vec<(FixedPointArithmetic, FixedPointArithmetic)>,
+    ) {
+        levels.clear();
+        let level_quantity =
+            |(price, level): (&FixedPointArithmetic, &PriceLevel)| (*price, level.quantity);
+        match side {
+            Side::Buy => levels.extend(self.bids.iter().rev().take(depth).map(level_quantity)),
+            Side::Sell => levels.extend(self.asks.iter().take(depth).map(level_quantity)),
+        }
+    }
+
+    /// Aggregated top `levels` price levels of both sides, the L2 view of the book market-data consumers expect.
+    /// Unlike `dump_order_book`, the orders resting at one price are summed into a single level.
+    pub fn depth_snapshot(&self, levels: usize) -> Depth {
+        Depth {
+            bids: self.aggregated_depth(Side::Buy, levels),
+            asks: self.aggregated_depth(Side::Sell, levels),
+        }
+    }
+
+    /// Price of the last trade executed in this order book, if any.
+    pub fn last_trade_price(&self) -> Option<FixedPointArithmetic> {
+        self.last_trade_price
+    }
+
+    /// Up to the `n` latest trades of the book, oldest first, bounded by the capacity of the tape.
+    pub fn recent_trades(&self, n: usize) -> &[Trade] {
+        let kept = self.trade_history.len().min(self.trade_history_capacity);
+        &self.trade_history[self.trade_history.len() - n.min(kept)..]
+    }
+
+    /// Every trade of the order `result` was returned for, including those beyond the trades an `OrderResult` holds.
+    /// They are read from the tape, so call it before the next order trades. Falls back to the trades of `result`
+    /// when the tape no longer holds all of them.
+    pub fn fills<'a>(&'a self, result: &'a OrderResult) -> &'a [Trade] {
+        let held = &result.trades.trades[..result.trades.len()];
+        let Some(first) = held.first() else {
+            return held;
+        };
+        // Trade ids increase along the tape, the trades of the latest order are at its end
+        let tape = self.recent_trades(self.trade_history_capacity);
+        let start = tape.partition_point(|trade| trade.id < first.id);
+        match tape.get(start) {
+            Some(trade) if trade.id == first.id && tape.len() - start >= held.len() => {
+                &tape[start..]
+            }
+            _ => held,
+        }
+    }
+
+    /// Formats the order book as a price ladder limited to `depth` levels per side, see `Display for OrderBook`.
+    pub fn format_depth(&self, depth: usize) -> String {
+        format!("{:.*}", depth, self)
+    }
+
     /// Dumps the current state of the order book for a given side (buy or sell) as a vector of orders. This can be useful for debugging or visualization purposes.
     /// Arguments:
     /// - `side`: The side of the order book to dump (either `Side::Buy` for bids or `Side::Sell` for asks).
@@ -637,11 +1365,11 @@ impl OrderBook {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
     use types::macros::{EntityId, OrderId, SymbolId};
 
-    const SYMBOL_STR: &str = "TEST";
+    pub(crate) const SYMBOL_STR: &str = "TEST";
     const SYMBOL_ID: SymbolId = SymbolId::from_ascii(SYMBOL_STR);
     const SENDER: EntityId = EntityId::from_ascii("SENDER0000000000000");
     const TARGET: EntityId = EntityId::from_ascii("TARGET0000000000000");
@@ -658,36 +1386,27 @@ mod tests {
     #[test]
     fn test_cancel_order() {
         let mut order_book = OrderBook::new(SYMBOL_STR);
-        let order = OrderEvent {
-            price: FixedPointArithmetic::from_f64(100.0), // 100.0 with 8 decimal places
-            quantity: FixedPointArithmetic::from_f64(10.0), // 10.0 with 8 decimal places
-            side: Side::Buy,
-            order_type: OrderType::LimitOrder,
-            orig_cl_ord_id: None,
-            cl_ord_id: CL_ORD_ID,
-            sender_id: SENDER,
-            target_id: TARGET,
-            symbol: SYMBOL_ID,    // Set the symbol for the order
-            ..Default::default() // Set the timestamp to the current time in milliseconds since epoch
-        };
+        let order = OrderEvent::builder()
+            .price_f64(100.0)
+            .qty(10.0)
+            .cl_ord_id(CL_ORD_ID)
+            .sender_id(SENDER)
+            .target_id(TARGET)
+            .symbol(SYMBOL_ID)
+            .build();
 
         let (order, result) = order_book.process_order(order);
         assert_eq!(result.status, OrderStatus::New);
         assert!(order.price == FixedPointArithmetic::from_f64(100.0));
         assert!(order.quantity == FixedPointArithmetic::from_f64(10.0));
 
-        let cancel_order = OrderEvent {
-            price: FixedPointArithmetic::ZERO, // Price is not relevant for cancel orders
-            quantity: FixedPointArithmetic::ZERO, // Quantity is not relevant for cancel orders
-            side: Side::Buy, // Side is not relevant for cancel orders, but we can set it to match the original order
-            order_type: OrderType::CancelOrder,
-            cl_ord_id: CL_ORD_ID, // Use the same ClOrdID to identify which order to cancel
-            orig_cl_ord_id: Some(CL_ORD_ID),
-            sender_id: SENDER,
-            target_id: TARGET,
-            symbol: SYMBOL_ID,    // Set the symbol for the cancel order
-            ..Default::default() // Set the timestamp to the current time in milliseconds since epoch
-        };
+        let cancel_order = OrderEvent::builder()
+            .cancel(CL_ORD_ID)
+            .cl_ord_id(CL_ORD_ID)
+            .sender_id(SENDER)
+            .target_id(TARGET)
+            .symbol(SYMBOL_ID)
+            .build();
 
         let (cancel_order, cancel_result) = order_book.process_order(cancel_order);
         assert_eq!(cancel_result.status, OrderStatus::Cancelled);
@@ -698,36 +1417,29 @@ mod tests {
         assert!(order_book.order_map.is_empty()); // There should be no asks in the order book
 
         // Testing cancellation of a sell order
-        let order = OrderEvent {
-            price: FixedPointArithmetic::from_f64(100.0), // 100.0 with 8 decimal places
-            quantity: FixedPointArithmetic::from_f64(10.0), // 10.0 with 8 decimal places
-            side: Side::Sell,
-            order_type: OrderType::LimitOrder,
-            cl_ord_id: CL_ORD_ID,
-            orig_cl_ord_id: None,
-            sender_id: SENDER,
-            target_id: TARGET,
-            symbol: SYMBOL_ID,    // Set the symbol for the order
-            ..Default::default() // Set the timestamp to the current time in milliseconds since epoch
-        };
+        let order = OrderEvent::builder()
+            .sell()
+            .price_f64(100.0)
+            .qty(10.0)
+            .cl_ord_id(CL_ORD_ID)
+            .sender_id(SENDER)
+            .target_id(TARGET)
+            .symbol(SYMBOL_ID)
+            .build();
 
         let (order, result) = order_book.process_order(order);
         assert_eq!(result.status, OrderStatus::New);
         assert!(order.price == FixedPointArithmetic::from_f64(100.0));
         assert!(order.quantity == FixedPointArithmetic::from_f64(10.0));
 
-        let cancel_order = OrderEvent {
-            price: FixedPointArithmetic::ZERO, // Price is not relevant for cancel orders
-            quantity: FixedPointArithmetic::ZERO, // Quantity is not relevant for cancel orders
-            side: Side::Sell, // Side is not relevant for cancel orders, but we can set it to match the original order
-            order_type: OrderType::CancelOrder,
-            cl_ord_id: CL_ORD_ID, // Use the same ClOrdID to identify which order to cancel
-            orig_cl_ord_id: Some(CL_ORD_ID),
-            sender_id: SENDER,
-            target_id: TARGET,
-            symbol: SYMBOL_ID,    // Set the symbol for the cancel order
-            ..Default::default() // Set the timestamp to the current time in milliseconds since epoch
-        };
+        let cancel_order = OrderEvent::builder()
+            .sell()
+            .cancel(CL_ORD_ID)
+            .cl_ord_id(CL_ORD_ID)
+            .sender_id(SENDER)
+            .target_id(TARGET)
+            .symbol(SYMBOL_ID)
+            .build();
 
         let (cancel_order, cancel_result) = order_book.process_order(cancel_order);
         assert_eq!(cancel_result.status, OrderStatus::Cancelled);
@@ -740,18 +1452,14 @@ mod tests {
     #[test]
     fn test_single_limit_order() {
         let mut order_book = OrderBook::new(SYMBOL_STR);
-        let order = OrderEvent {
-            price: FixedPointArithmetic::from_f64(100.0), // 100.0 with 8 decimal places
-            quantity: FixedPointArithmetic::from_f64(10.0), // 10.0 with 8 decimal places
-            side: Side::Buy,
-            order_type: OrderType::LimitOrder,
-            cl_ord_id: CL_ORD_ID,
-            orig_cl_ord_id: None,
-            sender_id: SENDER,
-            target_id: TARGET,
-            symbol: SYMBOL_ID,    // Set the symbol for the order
-            ..Default::default() // Set the timestamp to the current time in milliseconds since epoch
-        };
+        let order = OrderEvent::builder()
+            .price_f64(100.0)
+            .qty(10.0)
+            .cl_ord_id(CL_ORD_ID)
+            .sender_id(SENDER)
+            .target_id(TARGET)
+            .symbol(SYMBOL_ID)
+            .build();
 
         let (order, result) = order_book.process_order(order);
 
@@ -774,30 +1482,23 @@ mod tests {
     #[test]
     fn test_trade_with_same_price() {
         let mut order_book = OrderBook::new(SYMBOL_STR);
-        let order1 = OrderEvent {
-            price: FixedPointArithmetic::from_f64(100.0), // 100.0 with 8 decimal places
-            quantity: FixedPointArithmetic::from_f64(10.0), // 10.0 with 8 decimal places
-            side: Side::Buy,
-            order_type: OrderType::LimitOrder,
-            cl_ord_id: CL_ORD_ID,
-            orig_cl_ord_id: None,
-            sender_id: SENDER,
-            target_id: TARGET,
-            symbol: SYMBOL_ID,    // Set the symbol for the order
-            ..Default::default() // Set the timestamp to the current time in milliseconds since epoch
-        };
-        let order2 = OrderEvent {
-            price: FixedPointArithmetic::from_f64(100.0),
-            quantity: FixedPointArithmetic::from_f64(5.0),
-            side: Side::Sell,
-            order_type: OrderType::LimitOrder,
-            cl_ord_id: CL_ORD_ID,
-            orig_cl_ord_id: None,
-            sender_id: SENDER,
-            target_id: TARGET,
-            symbol: SYMBOL_ID,    // Set the symbol for the order
-            ..Default::default() // Set the timestamp to the current time in milliseconds since epoch
-        };
+        let order1 = OrderEvent::builder()
+            .price_f64(100.0)
+            .qty(10.0)
+            .cl_ord_id(CL_ORD_ID)
+            .sender_id(SENDER)
+            .target_id(TARGET)
+            .symbol(SYMBOL_ID)
+            .build();
+        let order2 = OrderEvent::builder()
+            .sell()
+            .price_f64(100.0)
+            .qty(5.0)
+            .cl_ord_id(CL_ORD_ID)
+            .sender_id(SENDER)
+            .target_id(TARGET)
+            .symbol(SYMBOL_ID)
+            .build();
 
         let (order1, result1) = order_book.process_order(order1);
         let (order2, result2) = order_book.process_order(order2);
@@ -834,42 +1535,32 @@ mod tests {
         logging::init_tracing("order_book");
 
         let mut order_book = OrderBook::new(SYMBOL_STR);
-        let order1 = OrderEvent {
-            price: FixedPointArithmetic::from_f64(100.0), // 100.0 with 8 decimal places
-            quantity: FixedPointArithmetic::from_f64(10.0), // 10.0 with 8 decimal places
-            side: Side::Buy,
-            order_type: OrderType::LimitOrder,
-            cl_ord_id: CL_ORD_ID,
-            orig_cl_ord_id: None,
-            sender_id: SENDER,
-            target_id: TARGET,
-            symbol: SYMBOL_ID,    // Set the symbol for the order
-            ..Default::default() // Set the timestamp to the current time in milliseconds since epoch
-        };
-        let order2 = OrderEvent {
-            price: FixedPointArithmetic::from_f64(99.0),
-            quantity: FixedPointArithmetic::from_f64(5.0),
-            side: Side::Sell,
-            order_type: OrderType::LimitOrder,
-            cl_ord_id: CL_ORD_ID,
-            orig_cl_ord_id: None,
-            sender_id: SENDER,
-            target_id: TARGET,
-            symbol: SYMBOL_ID,    // Set the symbol for the order
-            ..Default::default() // Set the timestamp to the current time in milliseconds since epoch
-        };
-        let order3 = OrderEvent {
-            price: FixedPointArithmetic::from_f64(98.0),
-            quantity: FixedPointArithmetic::from_f64(10.0),
-            side: Side::Sell,
-            order_type: OrderType::LimitOrder,
-            cl_ord_id: CL_ORD_ID,
-            orig_cl_ord_id: None,
-            sender_id: SENDER,
-            target_id: TARGET,
-            symbol: SYMBOL_ID,    // Set the symbol for the order
-            ..Default::default() // Set the timestamp to the current time in milliseconds since epoch
-        };
+        let order1 = OrderEvent::builder()
+            .price_f64(100.0)
+            .qty(10.0)
+            .cl_ord_id(CL_ORD_ID)
+            .sender_id(SENDER)
+            .target_id(TARGET)
+            .symbol(SYMBOL_ID)
+            .build();
+        let order2 = OrderEvent::builder()
+            .sell()
+            .price_f64(99.0)
+            .qty(5.0)
+            .cl_ord_id(CL_ORD_ID)
+            .sender_id(SENDER)
+            .target_id(TARGET)
+            .symbol(SYMBOL_ID)
+            .build();
+        let order3 = OrderEvent::builder()
+            .sell()
+            .price_f64(98.0)
+            .qty(10.0)
+            .cl_ord_id(CL_ORD_ID)
+            .sender_id(SENDER)
+            .target_id(TARGET)
+            .symbol(SYMBOL_ID)
+            .build();
 
         let (order1, result1) = order_book.process_order(order1);
         let (order2, result2) = order_book.process_order(order2);
@@ -964,54 +1655,41 @@ mod tests {
 
         let mut order_book = OrderBook::new(SYMBOL_STR);
 
-        let order1 = OrderEvent {
-            price: FixedPointArithmetic::from_f64(99.0),
-            quantity: FixedPointArithmetic::from_f64(3.0),
-            side: Side::Sell,
-            order_type: OrderType::LimitOrder,
-            cl_ord_id: CL_ORD_ID,
-            orig_cl_ord_id: None,
-            sender_id: SENDER,
-            target_id: TARGET,
-            symbol: SYMBOL_ID,
-            ..Default::default() // Set the timestamp to the current time in milliseconds since epoch
-        };
-        let order2 = OrderEvent {
-            price: FixedPointArithmetic::from_f64(98.0),
-            quantity: FixedPointArithmetic::from_f64(5.0),
-            side: Side::Sell,
-            order_type: OrderType::LimitOrder,
-            cl_ord_id: CL_ORD_ID,
-            orig_cl_ord_id: None,
-            sender_id: SENDER,
-            target_id: TARGET,
-            symbol: SYMBOL_ID,
-            ..Default::default() // Set the timestamp to the current time in milliseconds since epoch
-        };
-        let order3 = OrderEvent {
-            price: FixedPointArithmetic::from_f64(97.0),
-            quantity: FixedPointArithmetic::from_f64(3.0),
-            side: Side::Sell,
-            order_type: OrderType::LimitOrder,
-            cl_ord_id: CL_ORD_ID,
-            orig_cl_ord_id: None,
-            sender_id: SENDER,
-            target_id: TARGET,
-            symbol: SYMBOL_ID,
-            ..Default::default() // Set the timestamp to the current time in milliseconds since epoch
-        };
-        let order4 = OrderEvent {
-            price: FixedPointArithmetic::from_f64(100.0),
-            quantity: FixedPointArithmetic::from_f64(10.0),
-            side: Side::Buy,
-            order_type: OrderType::LimitOrder,
-            cl_ord_id: CL_ORD_ID,
-            orig_cl_ord_id: None,
-            sender_id: SENDER,
-            target_id: TARGET,
-            symbol: SYMBOL_ID,
-            ..Default::default() // Set the timestamp to the current time in milliseconds since epoch
-        };
+        let order1 = OrderEvent::builder()
+            .sell()
+            .price_f64(99.0)
+            .qty(3.0)
+            .cl_ord_id(CL_ORD_ID)
+            .sender_id(SENDER)
+            .target_id(TARGET)
+            .symbol(SYMBOL_ID)
+            .build();
+        let order2 = OrderEvent::builder()
+            .sell()
+            .price_f64(98.0)
+            .qty(5.0)
+            .cl_ord_id(CL_ORD_ID)
+            .sender_id(SENDER)
+            .target_id(TARGET)
+            .symbol(SYMBOL_ID)
+            .build();
+        let order3 = OrderEvent::builder()
+            .sell()
+            .price_f64(97.0)
+            .qty(3.0)
+            .cl_ord_id(CL_ORD_ID)
+            .sender_id(SENDER)
+            .target_id(TARGET)
+            .symbol(SYMBOL_ID)
+            .build();
+        let order4 = OrderEvent::builder()
+            .price_f64(100.0)
+            .qty(10.0)
+            .cl_ord_id(CL_ORD_ID)
+            .sender_id(SENDER)
+            .target_id(TARGET)
+            .symbol(SYMBOL_ID)
+            .build();
 
         let (order1, result1) = order_book.process_order(order1);
         let (order2, result2) = order_book.process_order(order2);
@@ -1103,54 +1781,42 @@ mod tests {
 
         let mut order_book = OrderBook::new(SYMBOL_STR);
 
-        let order1 = OrderEvent {
-            price: FixedPointArithmetic::from_f64(99.0),
-            quantity: FixedPointArithmetic::from_f64(5.0),
-            side: Side::Sell,
-            order_type: OrderType::LimitOrder,
-            cl_ord_id: CL_ORD_ID,
-            orig_cl_ord_id: None,
-            sender_id: SENDER,
-            target_id: TARGET,
-            symbol: SYMBOL_ID,
-            ..Default::default() // Set the timestamp to the current time in milliseconds since epoch
-        };
-        let order2 = OrderEvent {
-            price: FixedPointArithmetic::from_f64(98.0),
-            quantity: FixedPointArithmetic::from_f64(5.0),
-            side: Side::Sell,
-            order_type: OrderType::LimitOrder,
-            cl_ord_id: CL_ORD_ID,
-            orig_cl_ord_id: None,
-            sender_id: SENDER,
-            target_id: TARGET,
-            symbol: SYMBOL_ID,
-            ..Default::default() // Set the timestamp to the current time in milliseconds since epoch
-        };
-        let order3 = OrderEvent {
-            price: FixedPointArithmetic::from_f64(98.0),
-            quantity: FixedPointArithmetic::from_f64(10.0),
-            side: Side::Sell,
-            order_type: OrderType::LimitOrder,
-            cl_ord_id: CL_ORD_ID,
-            orig_cl_ord_id: None,
-            sender_id: SENDER,
-            target_id: TARGET,
-            symbol: SYMBOL_ID,
-            ..Default::default() // Set the timestamp to the current time in milliseconds since epoch
-        };
-        let order4 = OrderEvent {
-            price: FixedPointArithmetic::from_f64(0.0), // Price is ignored for market orders
-            quantity: FixedPointArithmetic::from_f64(12.0),
-            side: Side::Buy,
-            order_type: OrderType::MarketOrder,
-            cl_ord_id: CL_ORD_ID,
-            orig_cl_ord_id: None,
-            sender_id: SENDER,
-            target_id: TARGET,
-            symbol: SYMBOL_ID,
-            ..Default::default() // Set the timestamp to the current time in milliseconds since epoch
-        };
+        let order1 = OrderEvent::builder()
+            .sell()
+            .price_f64(99.0)
+            .qty(5.0)
+            .cl_ord_id(CL_ORD_ID)
+            .sender_id(SENDER)
+            .target_id(TARGET)
+            .symbol(SYMBOL_ID)
+            .build();
+        let order2 = OrderEvent::builder()
+            .sell()
+            .price_f64(98.0)
+            .qty(5.0)
+            .cl_ord_id(CL_ORD_ID)
+            .sender_id(SENDER)
+            .target_id(TARGET)
+            .symbol(SYMBOL_ID)
+            .build();
+        let order3 = OrderEvent::builder()
+            .sell()
+            .price_f64(98.0)
+            .qty(10.0)
+            .cl_ord_id(CL_ORD_ID)
+            .sender_id(SENDER)
+            .target_id(TARGET)
+            .symbol(SYMBOL_ID)
+            .build();
+        let order4 = OrderEvent::builder()
+            .market()
+            .price_f64(0.0)
+            .qty(12.0)
+            .cl_ord_id(CL_ORD_ID)
+            .sender_id(SENDER)
+            .target_id(TARGET)
+            .symbol(SYMBOL_ID)
+            .build();
 
         let (order1, result1) = order_book.process_order(order1);
         let (order2, result2) = order_book.process_order(order2);
@@ -1195,7 +1861,7 @@ mod tests {
             result4.trades[1].price,
             FixedPointArithmetic::from_f64(98.0)
         ); // 7 units * 98.0 price
-        assert_eq!(result4.status, OrderStatus::New);
+        assert_eq!(result4.status, OrderStatus::Filled);
 
         // Check the remaining orders in the order book after processing the market order
         assert_eq!(order_book.asks.len(), 2); // Two asks should remain in the order book
@@ -1230,30 +1896,23 @@ mod tests {
     fn test_spread_calculation() {
         logging::init_tracing("order_book");
         let mut order_book = OrderBook::new(SYMBOL_STR);
-        let order1 = OrderEvent {
-            price: FixedPointArithmetic::from_f64(100.0),
-            quantity: FixedPointArithmetic::from_f64(10.0),
-            side: Side::Buy,
-            order_type: OrderType::LimitOrder,
-            cl_ord_id: CL_ORD_ID,
-            orig_cl_ord_id: None,
-            sender_id: SENDER,
-            target_id: TARGET,
-            symbol: SYMBOL_ID,
-            ..Default::default() // Set the timestamp to the current time in milliseconds since epoch
-        };
-        let order2 = OrderEvent {
-            price: FixedPointArithmetic::from_f64(102.0),
-            quantity: FixedPointArithmetic::from_f64(5.0),
-            side: Side::Sell,
-            order_type: OrderType::LimitOrder,
-            cl_ord_id: CL_ORD_ID,
-            orig_cl_ord_id: None,
-            sender_id: SENDER,
-            target_id: TARGET,
-            symbol: SYMBOL_ID,
-            ..Default::default() // Set the timestamp to the current time in milliseconds since epoch
-        };
+        let order1 = OrderEvent::builder()
+            .price_f64(100.0)
+            .qty(10.0)
+            .cl_ord_id(CL_ORD_ID)
+            .sender_id(SENDER)
+            .target_id(TARGET)
+            .symbol(SYMBOL_ID)
+            .build();
+        let order2 = OrderEvent::builder()
+            .sell()
+            .price_f64(102.0)
+            .qty(5.0)
+            .cl_ord_id(CL_ORD_ID)
+            .sender_id(SENDER)
+            .target_id(TARGET)
+            .symbol(SYMBOL_ID)
+            .build();
 
         order_book.process_order(order1);
         order_book.process_order(order2);
@@ -1269,30 +1928,23 @@ mod tests {
     fn test_dump_order_book() {
         logging::init_tracing("order_book");
         let mut order_book = OrderBook::new(SYMBOL_STR);
-        let order1 = OrderEvent {
-            price: FixedPointArithmetic::from_f64(100.0),
-            quantity: FixedPointArithmetic::from_f64(10.0),
-            side: Side::Buy,
-            order_type: OrderType::LimitOrder,
-            cl_ord_id: CL_ORD_ID,
-            orig_cl_ord_id: None,
-            sender_id: SENDER,
-            target_id: TARGET,
-            symbol: SYMBOL_ID,
-            ..Default::default() // Set the timestamp to the current time in milliseconds since epoch
-        };
-        let order2 = OrderEvent {
-            price: FixedPointArithmetic::from_f64(102.0),
-            quantity: FixedPointArithmetic::from_f64(5.0),
-            side: Side::Sell,
-            order_type: OrderType::LimitOrder,
-            cl_ord_id: CL_ORD_ID,
-            orig_cl_ord_id: None,
-            sender_id: SENDER,
-            target_id: TARGET,
-            symbol: SYMBOL_ID,
-            ..Default::default() // Set the timestamp to the current time in milliseconds since epoch
-        };
+        let order1 = OrderEvent::builder()
+            .price_f64(100.0)
+            .qty(10.0)
+            .cl_ord_id(CL_ORD_ID)
+            .sender_id(SENDER)
+            .target_id(TARGET)
+            .symbol(SYMBOL_ID)
+            .build();
+        let order2 = OrderEvent::builder()
+            .sell()
+            .price_f64(102.0)
+            .qty(5.0)
+            .cl_ord_id(CL_ORD_ID)
+            .sender_id(SENDER)
+            .target_id(TARGET)
+            .symbol(SYMBOL_ID)
+            .build();
 
         order_book.process_order(order1);
         order_book.process_order(order2);
@@ -1315,4 +1967,992 @@ mod tests {
         assert_eq!(asks[0].target_id, TARGET); // The ask should have the correct target ID
         assert_eq!(asks[0].order_type, OrderType::LimitOrder); // The ask should have the correct order type
     }
+
+    /// Limit order of the test sender on the test symbol, shared by the unit tests of the crate.
+    pub(crate) fn limit_order(
+        side: Side,
+        price: f64,
+        quantity: f64,
+        cl_ord_id: &str,
+    ) -> OrderEvent {
+        OrderEvent::builder()
+            .side(side)
+            .price_f64(price)
+            .qty(quantity)
+            .cl_ord_id(OrderId::from_ascii(cl_ord_id))
+            .sender_id(SENDER)
+            .target_id(TARGET)
+            .symbol(SYMBOL_ID)
+            .build()
+    }
+
+    #[test]
+    fn test_display_ladder() {
+        let mut order_book = OrderBook::new(SYMBOL_STR);
+        assert_eq!(
+            order_book.to_string(),
+            "TEST | bid - | ask - | spread - | last -\nBid Qty | Price | Ask Qty"
+        );
+
+        order_book.process_order(limit_order(Side::Sell, 101.0, 5.0, "A1"));
+        order_book.process_order(limit_order(Side::Sell, 101.0, 2.0, "A2"));
+        order_book.process_order(limit_order(Side::Sell, 102.0, 3.0, "A3"));
+        order_book.process_order(limit_order(Side::Sell, 103.0, 4.0, "A4"));
+        order_book.process_order(limit_order(Side::Buy, 99.0, 4.0, "B1"));
+        order_book.process_order(limit_order(Side::Buy, 98.0, 6.0, "B2"));
+        order_book.process_order(limit_order(Side::Buy, 97.0, 1.0, "B3"));
+        // Trades 1 against A1, leaving 6 at 101
+        order_book.process_order(limit_order(Side::Buy, 101.0, 1.0, "B4"));
+
+        assert_eq!(
+            order_book.aggregated_depth(Side::Sell, 10),
+            vec![
+                (
+                    FixedPointArithmetic::from_f64(101.0),
+                    FixedPointArithmetic::from_f64(6.0)
+                ),
+                (
+                    FixedPointArithmetic::from_f64(102.0),
+                    FixedPointArithmetic::from_f64(3.0)
+                ),
+                (
+                    FixedPointArithmetic::from_f64(103.0),
+                    FixedPointArithmetic::from_f64(4.0)
+                ),
+            ]
+        );
+
+        let expected_depth_2 = "\
+TEST | bid 99.00000000 | ask 101.00000000 | spread 2.00000000 | last 101.00000000
+    Bid Qty |        Price |    Ask Qty
+            | 102.00000000 | 9.00000000
+            | 101.00000000 | 6.00000000
+ 4.00000000 |  99.00000000
+10.00000000 |  98.00000000";
+        assert_eq!(order_book.format_depth(2), expected_depth_2);
+        assert_eq!(format!("{:.2}", order_book), expected_depth_2);
+
+        let expected_full = "\
+TEST | bid 99.00000000 | ask 101.00000000 | spread 2.00000000 | last 101.00000000
+    Bid Qty |        Price |     Ask Qty
+            | 103.00000000 | 13.00000000
+            | 102.00000000 |  9.00000000
+            | 101.00000000 |  6.00000000
+ 4.00000000 |  99.00000000
+10.00000000 |  98.00000000
+11.00000000 |  97.00000000";
+        assert_eq!(format!("{:#}", order_book), expected_full);
+        // Default depth of 10 covers the whole book here
+        assert_eq!(order_book.to_string(), expected_full);
+    }
+
+    #[test]
+    fn test_depth_snapshot_aggregates_levels() {
+        let mut order_book = OrderBook::new(SYMBOL_STR);
+        order_book.process_order(limit_order(Side::Buy, 98.0, 6.0, "B1"));
+        order_book.process_order(limit_order(Side::Buy, 99.0, 4.0, "B2"));
+        order_book.process_order(limit_order(Side::Buy, 98.0, 2.0, "B3"));
+        order_book.process_order(limit_order(Side::Buy, 99.0, 1.0, "B4"));
+        order_book.process_order(limit_order(Side::Buy, 97.0, 3.0, "B5"));
+        order_book.process_order(limit_order(Side::Sell, 102.0, 3.0, "A1"));
+        order_book.process_order(limit_order(Side::Sell, 101.0, 5.0, "A2"));
+        order_book.process_order(limit_order(Side::Sell, 102.0, 1.5, "A3"));
+        order_book.process_order(limit_order(Side::Sell, 101.0, 2.0, "A4"));
+
+        let level = |price: f64, quantity: f64| {
+            (
+                FixedPointArithmetic::from_f64(price),
+                FixedPointArithmetic::from_f64(quantity),
+            )
+        };
+        // Best price first on each side, the third bid level is cut
+        assert_eq!(
+            order_book.depth_snapshot(2),
+            Depth {
+                bids: vec![level(99.0, 5.0), level(98.0, 8.0)],
+                asks: vec![level(101.0, 7.0), level(102.0, 4.5)],
+            }
+        );
+        assert_eq!(order_book.depth_snapshot(10).bids.len(), 3);
+        assert_eq!(order_book.dump_order_book(Side::Buy, 10).len(), 5);
+        assert_eq!(order_book.depth_snapshot(0), Depth::default());
+    }
+
+    #[test]
+    fn test_trade_tape_records_the_latest_trades() {
+        let mut order_book = OrderBook::new(SYMBOL_STR).with_trade_history(2);
+        order_book.set_clock_ms(Some(1_000));
+        order_book.process_order(limit_order(Side::Sell, 101.0, 2.0, "A1"));
+        order_book.process_order(limit_order(Side::Sell, 102.0, 3.0, "A2"));
+        assert_eq!(order_book.last_trade_price(), None);
+        assert!(order_book.recent_trades(5).is_empty());
+
+        // Sweeps both asks, two trades
+        order_book.process_order(limit_order(Side::Buy, 102.0, 4.0, "B1"));
+        order_book.set_clock_ms(Some(2_000));
+        order_book.process_order(limit_order(Side::Buy, 102.0, 1.0, "B2"));
+
+        assert_eq!(
+            order_book.last_trade_price(),
+            Some(FixedPointArithmetic::from_f64(102.0))
+        );
+        // The tape keeps two trades, the first fill at 101 is dropped
+        let trades = order_book.recent_trades(5);
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[0].price, FixedPointArithmetic::from_f64(102.0));
+        assert_eq!(trades[0].quantity, FixedPointArithmetic::from_f64(2.0));
+        assert_eq!(trades[0].timestamp, 1_000);
+        assert_eq!(trades[1].quantity, FixedPointArithmetic::from_f64(1.0));
+        assert_eq!(trades[1].timestamp, 2_000);
+        assert_eq!(order_book.recent_trades(1), &trades[1..]);
+    }
+
+    #[test]
+    fn test_trade_tape_records_fills_beyond_the_order_result() {
+        let mut order_book = OrderBook::new(SYMBOL_STR);
+        for (i, price) in [101.0, 102.0, 103.0, 104.0, 105.0].into_iter().enumerate() {
+            order_book.process_order(limit_order(Side::Sell, price, 1.0, &format!("A{i}")));
+        }
+
+        // Sweeps five asks, the result only holds the first four trades
+        let (_, result) = order_book.process_order(limit_order(Side::Buy, 105.0, 5.0, "B1"));
+        assert_eq!(result.trades.len(), 4);
+
+        let trades = order_book.recent_trades(10);
+        assert_eq!(trades.len(), 5);
+        assert_eq!(trades[4].cl_ord_id, OrderId::from_ascii("A4"));
+        assert_eq!(trades[4].price, FixedPointArithmetic::from_f64(105.0));
+        assert_eq!(&trades[..4], &result.trades.trades[..4]);
+        assert_eq!(
+            order_book.last_trade_price(),
+            Some(FixedPointArithmetic::from_f64(105.0))
+        );
+    }
+
+    #[test]
+    fn test_aggregated_depth_into_reuses_buffer() {
+        let mut order_book = OrderBook::new(SYMBOL_STR);
+        order_book.process_order(limit_order(Side::Buy, 99.0, 4.0, "B1"));
+        order_book.process_order(limit_order(Side::Buy, 99.0, 1.0, "B2"));
+        order_book.process_order(limit_order(Side::Buy, 98.0, 6.0, "B3"));
+        order_book.process_order(limit_order(Side::Buy, 97.0, 1.0, "B4"));
+        order_book.process_order(limit_order(Side::Sell, 101.0, 2.0, "A1"));
+
+        let mut levels = Vec::with_capacity(2);
+        let capacity = levels.capacity();
+
+        order_book.aggregated_depth_into(Side::Buy, 2, &mut levels);
+        assert_eq!(levels, order_book.aggregated_depth(Side::Buy, 2));
+        assert_eq!(
+            levels[0],
+            (
+                FixedPointArithmetic::from_f64(99.0),
+                FixedPointArithmetic::from_f64(5.0)
+            )
+        );
+
+        order_book.aggregated_depth_into(Side::Sell, 2, &mut levels);
+        assert_eq!(levels, order_book.aggregated_depth(Side::Sell, 2));
+        assert_eq!(levels.capacity(), capacity);
+        assert_eq!(order_book.last_trade_price(), None);
+    }
+
+    #[test]
+    fn test_queue_position_after_partial_fills_ahead() {
+        let mut order_book = OrderBook::new(SYMBOL_STR);
+        order_book.process_order(limit_order(Side::Sell, 101.0, 5.0, "A1"));
+        order_book.process_order(limit_order(Side::Sell, 101.0, 3.0, "A2"));
+        order_book.process_order(limit_order(Side::Sell, 101.0, 4.0, "A3"));
+        order_book.process_order(limit_order(Side::Sell, 102.0, 1.0, "A4"));
+
+        let position = |order_book: &OrderBook, cl_ord_id: &str| {
+            order_book
+                .queue_position(OrderId::from_ascii(cl_ord_id))
+                .map(|(orders, quantity)| (orders, quantity.to_f64()))
+        };
+        assert_eq!(position(&order_book, "A1"), Some((0, 0.0)));
+        assert_eq!(position(&order_book, "A3"), Some((2, 8.0)));
+        assert_eq!(position(&order_book, "A4"), Some((0, 0.0)));
+
+        // Partially fills A1, the orders behind it keep their rank with less quantity ahead
+        order_book.process_order(limit_order(Side::Buy, 101.0, 2.0, "B1"));
+        assert_eq!(position(&order_book, "A2"), Some((1, 3.0)));
+        assert_eq!(position(&order_book, "A3"), Some((2, 6.0)));
+
+        // Fills A1 and part of A2
+        order_book.process_order(limit_order(Side::Buy, 101.0, 4.0, "B2"));
+        assert_eq!(position(&order_book, "A1"), None);
+        assert_eq!(position(&order_book, "A2"), Some((0, 0.0)));
+        assert_eq!(position(&order_book, "A3"), Some((1, 2.0)));
+
+        let level = &order_book.asks[&FixedPointArithmetic::from_f64(101.0)];
+        assert_eq!(level.len(), 2);
+        assert_eq!(level.quantity(), FixedPointArithmetic::from_f64(6.0));
+
+        // Cancelling the order ahead moves A3 to the front
+        order_book.process_order(
+            OrderEvent::builder()
+                .sell()
+                .cancel(OrderId::from_ascii("A2"))
+                .cl_ord_id(OrderId::from_ascii("C1"))
+                .sender_id(SENDER)
+                .target_id(TARGET)
+                .symbol(SYMBOL_ID)
+                .build(),
+        );
+        assert_eq!(position(&order_book, "A3"), Some((0, 0.0)));
+        let level = &order_book.asks[&FixedPointArithmetic::from_f64(101.0)];
+        assert_eq!(level.len(), 1);
+        assert_eq!(level.quantity(), FixedPointArithmetic::from_f64(4.0));
+    }
+
+    #[test]
+    fn test_time_priority_within_price_level() {
+        let mut order_book = OrderBook::new(SYMBOL_STR);
+        // The later order carries an older timestamp, the arrival order wins
+        order_book.process_order(
+            OrderEvent::builder()
+                .sell()
+                .price_f64(101.0)
+                .qty(5.0)
+                .cl_ord_id(OrderId::from_ascii("A1"))
+                .timestamp_ms(2_000)
+                .symbol(SYMBOL_ID)
+                .build(),
+        );
+        order_book.process_order(
+            OrderEvent::builder()
+                .sell()
+                .price_f64(101.0)
+                .qty(5.0)
+                .cl_ord_id(OrderId::from_ascii("A2"))
+                .timestamp_ms(1_000)
+                .symbol(SYMBOL_ID)
+                .build(),
+        );
+
+        let (_, result) = order_book.process_order(limit_order(Side::Buy, 101.0, 7.0, "B1"));
+        let fills: Vec<(OrderId, f64)> = result
+            .trades
+            .iter()
+            .map(|trade| (trade.cl_ord_id, trade.quantity.to_f64()))
+            .collect();
+        assert_eq!(
+            fills,
+            vec![
+                (OrderId::from_ascii("A1"), 5.0),
+                (OrderId::from_ascii("A2"), 2.0)
+            ]
+        );
+        assert_eq!(
+            order_book
+                .queue_position(OrderId::from_ascii("A2"))
+                .map(|(orders, _)| orders),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn test_cancel_order_by_id() {
+        let mut order_book = OrderBook::new(SYMBOL_STR);
+        order_book.process_order(limit_order(Side::Sell, 101.0, 5.0, "A1"));
+        order_book.process_order(limit_order(Side::Sell, 101.0, 3.0, "A2"));
+        order_book.process_order(limit_order(Side::Sell, 101.0, 2.0, "A3"));
+        order_book.process_order(limit_order(Side::Sell, 102.0, 4.0, "A4"));
+        order_book.process_order(limit_order(Side::Buy, 99.0, 1.0, "B1"));
+
+        // Cancels the middle order of the 101.0 level
+        let cancelled = order_book
+            .cancel_order(&OrderId::from_ascii("A2"))
+            .expect("A2 is resting in the book");
+        assert_eq!(cancelled.cl_ord_id, OrderId::from_ascii("A2"));
+        assert_eq!(cancelled.side, Side::Sell);
+        assert_eq!(cancelled.price, FixedPointArithmetic::from_f64(101.0));
+        assert_eq!(cancelled.quantity, FixedPointArithmetic::from_f64(3.0));
+
+        assert_eq!(order_book.queue_position(OrderId::from_ascii("A2")), None);
+        assert_eq!(
+            order_book.queue_position(OrderId::from_ascii("A3")),
+            Some((1, FixedPointArithmetic::from_f64(5.0)))
+        );
+        let level = &order_book.asks[&FixedPointArithmetic::from_f64(101.0)];
+        assert_eq!(level.len(), 2);
+        assert_eq!(level.quantity(), FixedPointArithmetic::from_f64(7.0));
+
+        // Cancelling it again, or an unknown order, finds nothing
+        assert_eq!(order_book.cancel_order(&OrderId::from_ascii("A2")), None);
+        assert_eq!(order_book.cancel_order(&OrderId::from_ascii("Z9")), None);
+
+        // The aggressor matches the orders around the cancelled one, then the next level
+        let (_, result) = order_book.process_order(limit_order(Side::Buy, 102.0, 8.0, "B2"));
+        let matched: Vec<_> = result.trades.iter().map(|trade| trade.cl_ord_id).collect();
+        assert_eq!(
+            matched,
+            vec![
+                OrderId::from_ascii("A1"),
+                OrderId::from_ascii("A3"),
+                OrderId::from_ascii("A4")
+            ]
+        );
+        assert_eq!(
+            result.trades.quantity_sum(),
+            FixedPointArithmetic::from_f64(8.0)
+        );
+        assert!(
+            !order_book
+                .asks
+                .contains_key(&FixedPointArithmetic::from_f64(101.0))
+        );
+
+        // Cancelling the last order of a level drops the level
+        assert!(
+            order_book
+                .cancel_order(&OrderId::from_ascii("B1"))
+                .is_some()
+        );
+        assert!(order_book.bids.is_empty());
+    }
+
+    #[test]
+    fn test_replace_order_decrease_in_place() {
+        let mut order_book = OrderBook::new(SYMBOL_STR);
+        order_book.process_order(limit_order(Side::Buy, 100.0, 5.0, "B1"));
+        order_book.process_order(limit_order(Side::Buy, 100.0, 3.0, "B2"));
+
+        // A smaller quantity at the same price keeps the order at the front of its level
+        let result = order_book.replace_order(
+            &OrderId::from_ascii("B1"),
+            FixedPointArithmetic::from_f64(100.0),
+            FixedPointArithmetic::from_f64(2.0),
+        );
+        assert_eq!(result.status, OrderStatus::New);
+        assert_eq!(result.trades.len(), 0);
+        assert_eq!(
+            order_book.queue_position(OrderId::from_ascii("B1")),
+            Some((0, FixedPointArithmetic::ZERO))
+        );
+        let level = &order_book.bids[&FixedPointArithmetic::from_f64(100.0)];
+        assert_eq!(level.len(), 2);
+        assert_eq!(level.quantity(), FixedPointArithmetic::from_f64(5.0));
+
+        // The amended order is still matched first, for its new quantity
+        let (_, result) = order_book.process_order(limit_order(Side::Sell, 100.0, 4.0, "A1"));
+        let fills: Vec<_> = result
+            .trades
+            .iter()
+            .map(|trade| (trade.cl_ord_id, trade.quantity))
+            .collect();
+        assert_eq!(
+            fills,
+            vec![
+                (
+                    OrderId::from_ascii("B1"),
+                    FixedPointArithmetic::from_f64(2.0)
+                ),
+                (
+                    OrderId::from_ascii("B2"),
+                    FixedPointArithmetic::from_f64(2.0)
+                )
+            ]
+        );
+
+        // An unknown order, or an invalid quantity, is rejected and leaves the book as it was
+        let result = order_book.replace_order(
+            &OrderId::from_ascii("Z9"),
+            FixedPointArithmetic::from_f64(100.0),
+            FixedPointArithmetic::from_f64(1.0),
+        );
+        assert_eq!(result.status, OrderStatus::CancelRejected);
+        let result = order_book.replace_order(
+            &OrderId::from_ascii("B2"),
+            FixedPointArithmetic::from_f64(100.0),
+            FixedPointArithmetic::ZERO,
+        );
+        assert_eq!(result.status, OrderStatus::RejectedInvalidQuantity);
+        assert_eq!(
+            order_book.bids[&FixedPointArithmetic::from_f64(100.0)].quantity(),
+            FixedPointArithmetic::from_f64(1.0)
+        );
+    }
+
+    #[test]
+    fn test_replace_order_decrease_in_place_keeps_min_quantity_orders() {
+        let mut order_book = OrderBook::new(SYMBOL_STR);
+        order_book.process_order(limit_order(Side::Sell, 100.0, 3.0, "A1"));
+        // Matches the 3 offered, its remainder of 5 rests with a minimum of 3 nothing can execute against
+        let mut buy = limit_order(Side::Buy, 100.0, 8.0, "B1");
+        buy.min_quantity = qty(3.0);
+        let (_, result) = order_book.process_order(buy);
+        assert_eq!(result.trades.len(), 1);
+
+        let result = order_book.replace_order(&OrderId::from_ascii("B1"), qty(100.0), qty(4.0));
+        assert_eq!(result.status, OrderStatus::New);
+        // Below the minimum of the order, which is capped at what it has left
+        let result = order_book.replace_order(&OrderId::from_ascii("B1"), qty(100.0), qty(2.0));
+        assert_eq!(result.status, OrderStatus::New);
+        assert_eq!(
+            order_book.aggregated_depth(Side::Buy, 1),
+            vec![(qty(100.0), qty(2.0))]
+        );
+
+        // Moved to a new price the order matches again, its unmet minimum is still rejected
+        let result = order_book.replace_order(&OrderId::from_ascii("B1"), qty(101.0), qty(5.0));
+        assert_eq!(result.status, OrderStatus::RejectedMinQuantity);
+        assert_eq!(
+            order_book.aggregated_depth(Side::Buy, 1),
+            vec![(qty(100.0), qty(2.0))]
+        );
+    }
+
+    #[test]
+    fn test_replace_order_price_change_reinserts() {
+        let mut order_book = OrderBook::new(SYMBOL_STR);
+        order_book.process_order(limit_order(Side::Buy, 100.0, 5.0, "B1"));
+        order_book.process_order(limit_order(Side::Buy, 100.0, 3.0, "B2"));
+        order_book.process_order(limit_order(Side::Sell, 102.0, 4.0, "A1"));
+
+        // A larger quantity at the same price sends the order to the back of its level
+        let result = order_book.replace_order(
+            &OrderId::from_ascii("B1"),
+            FixedPointArithmetic::from_f64(100.0),
+            FixedPointArithmetic::from_f64(6.0),
+        );
+        assert_eq!(result.status, OrderStatus::New);
+        assert_eq!(
+            order_book.queue_position(OrderId::from_ascii("B1")),
+            Some((1, FixedPointArithmetic::from_f64(3.0)))
+        );
+        assert_eq!(
+            order_book.bids[&FixedPointArithmetic::from_f64(100.0)].quantity(),
+            FixedPointArithmetic::from_f64(9.0)
+        );
+
+        // A new price crossing the book runs the matching path, the rest of the order rests at the new price
+        let result = order_book.replace_order(
+            &OrderId::from_ascii("B2"),
+            FixedPointArithmetic::from_f64(102.0),
+            FixedPointArithmetic::from_f64(5.0),
+        );
+        assert_eq!(result.trades.len(), 1);
+        assert_eq!(result.trades[0].cl_ord_id, OrderId::from_ascii("A1"));
+        assert_eq!(
+            result.trades.quantity_sum(),
+            FixedPointArithmetic::from_f64(4.0)
+        );
+        assert!(order_book.asks.is_empty());
+        assert_eq!(
+            order_book
+                .get_best_bid()
+                .map(|order| (order.cl_ord_id, order.quantity)),
+            Some((
+                OrderId::from_ascii("B2"),
+                FixedPointArithmetic::from_f64(1.0)
+            ))
+        );
+        assert_eq!(
+            order_book.bids[&FixedPointArithmetic::from_f64(100.0)].len(),
+            1
+        );
+        assert!(order_book.check_invariants().is_ok());
+    }
+
+    #[test]
+    fn test_max_orders_per_level() {
+        let mut order_book = OrderBook::new(SYMBOL_STR).with_max_orders_per_level(Some(2));
+        assert_eq!(order_book.max_orders_per_level(), Some(2));
+
+        let (_, result) = order_book.process_order(limit_order(Side::Buy, 99.0, 1.0, "B1"));
+        assert_eq!(result.status, OrderStatus::New);
+        let (_, result) = order_book.process_order(limit_order(Side::Buy, 99.0, 2.0, "B2"));
+        assert_eq!(result.status, OrderStatus::New);
+
+        let (order, result) = order_book.process_order(limit_order(Side::Buy, 99.0, 3.0, "B3"));
+        assert_eq!(result.status, OrderStatus::RejectedLevelFull);
+        assert_eq!(result.trades.len(), 0);
+        assert_eq!(order.quantity, FixedPointArithmetic::from_f64(3.0));
+        assert_eq!(order_book.queue_position(OrderId::from_ascii("B3")), None);
+        assert_eq!(
+            order_book.aggregated_depth(Side::Buy, 1),
+            vec![(
+                FixedPointArithmetic::from_f64(99.0),
+                FixedPointArithmetic::from_f64(3.0)
+            )]
+        );
+
+        // Other levels are not affected
+        let (_, result) = order_book.process_order(limit_order(Side::Buy, 98.0, 3.0, "B4"));
+        assert_eq!(result.status, OrderStatus::New);
+
+        // A sell fully filling B1 frees a slot in the level
+        order_book.process_order(limit_order(Side::Sell, 99.0, 1.0, "A1"));
+        let (_, result) = order_book.process_order(limit_order(Side::Buy, 99.0, 3.0, "B5"));
+        assert_eq!(result.status, OrderStatus::New);
+        assert_eq!(
+            order_book.queue_position(OrderId::from_ascii("B5")),
+            Some((1, FixedPointArithmetic::from_f64(2.0)))
+        );
+    }
+
+    fn qty(quantity: f64) -> FixedPointArithmetic {
+        FixedPointArithmetic::from_f64(quantity)
+    }
+
+    #[test]
+    fn test_lot_size() {
+        let mut order_book = OrderBook::new(SYMBOL_STR).with_lot_size(Some(qty(100.0)));
+        assert_eq!(order_book.lot_size(), Some(qty(100.0)));
+
+        let (_, result) = order_book.process_order(limit_order(Side::Buy, 99.0, 150.0, "B1"));
+        assert_eq!(result.status, OrderStatus::RejectedInvalidLot);
+        assert!(result.status.is_rejected());
+        assert_eq!(order_book.queue_position(OrderId::from_ascii("B1")), None);
+
+        let (_, result) = order_book.process_order(limit_order(Side::Buy, 99.0, 0.5, "B2"));
+        assert_eq!(result.status, OrderStatus::RejectedInvalidLot);
+
+        let (_, result) = order_book.process_order(limit_order(Side::Buy, 99.0, 200.0, "B3"));
+        assert_eq!(result.status, OrderStatus::New);
+
+        // Odd-lot market orders are rejected before they can match
+        let market = OrderEvent {
+            order_type: OrderType::MarketOrder,
+            ..limit_order(Side::Sell, 0.0, 50.0, "M1")
+        };
+        let (_, result) = order_book.process_order(market);
+        assert_eq!(result.status, OrderStatus::RejectedInvalidLot);
+        assert_eq!(result.trades.len(), 0);
+        assert_eq!(
+            order_book.aggregated_depth(Side::Buy, 1),
+            vec![(qty(99.0), qty(200.0))]
+        );
+    }
+
+    #[test]
+    fn test_zero_lot_size_accepts_any_quantity() {
+        let mut order_book =
+            OrderBook::new(SYMBOL_STR).with_lot_size(Some(FixedPointArithmetic::ZERO));
+        assert_eq!(order_book.lot_size(), None);
+
+        let (_, result) = order_book.process_order(limit_order(Side::Buy, 99.0, 150.0, "B1"));
+        assert_eq!(result.status, OrderStatus::New);
+
+        // A lot size below the fixed-point resolution rounds to zero
+        let order_book = OrderBook::new(SYMBOL_STR).with_lot_size(Some(qty(1e-12)));
+        assert_eq!(order_book.lot_size(), None);
+    }
+
+    #[test]
+    fn test_min_quantity_aggressor() {
+        let mut order_book = OrderBook::new(SYMBOL_STR);
+        order_book.process_order(limit_order(Side::Sell, 100.0, 3.0, "A1"));
+        order_book.process_order(limit_order(Side::Sell, 101.0, 3.0, "A2"));
+        order_book.process_order(limit_order(Side::Sell, 103.0, 10.0, "A3"));
+
+        // Only 6 are executable up to 101
+        let mut buy = limit_order(Side::Buy, 101.0, 8.0, "B1");
+        buy.min_quantity = qty(7.0);
+        let (_, result) = order_book.process_order(buy);
+        assert_eq!(result.status, OrderStatus::RejectedMinQuantity);
+        assert_eq!(result.trades.len(), 0);
+        assert_eq!(order_book.queue_position(OrderId::from_ascii("B1")), None);
+        assert_eq!(
+            order_book.aggregated_depth(Side::Sell, 2),
+            vec![(qty(100.0), qty(3.0)), (qty(101.0), qty(3.0))]
+        );
+
+        // 5 are executable, the order matches then rests with the rest
+        let mut buy = limit_order(Side::Buy, 101.0, 8.0, "B2");
+        buy.min_quantity = qty(5.0);
+        let (_, result) = order_book.process_order(buy);
+        assert_eq!(result.status, OrderStatus::New);
+        assert_eq!(result.trades.len(), 2);
+        assert_eq!(result.trades[0].quantity, qty(3.0));
+        assert_eq!(result.trades[1].quantity, qty(3.0));
+        assert_eq!(
+            order_book.aggregated_depth(Side::Buy, 1),
+            vec![(qty(101.0), qty(2.0))]
+        );
+
+        // A minimum above the order quantity can never be met
+        let mut buy = limit_order(Side::Buy, 103.0, 2.0, "B3");
+        buy.min_quantity = qty(3.0);
+        let (_, result) = order_book.process_order(buy);
+        assert_eq!(result.status, OrderStatus::RejectedInvalidQuantity);
+    }
+
+    #[test]
+    fn test_market_order_beyond_the_liquidity_never_rests() {
+        let mut order_book = OrderBook::new(SYMBOL_STR);
+        order_book.process_order(limit_order(Side::Sell, 100.0, 3.0, "A1"));
+        order_book.process_order(limit_order(Side::Sell, 101.0, 2.0, "A2"));
+        order_book.process_order(limit_order(Side::Buy, 98.0, 1.0, "B1"));
+
+        let market_order = |side: Side, quantity: f64, cl_ord_id: &str| OrderEvent {
+            order_type: OrderType::MarketOrder,
+            ..limit_order(side, 0.0, quantity, cl_ord_id)
+        };
+
+        // Sweeps every ask, the other 5 are cancelled instead of resting at an infinite price
+        let (_, result) = order_book.process_order(market_order(Side::Buy, 10.0, "M1"));
+        assert_eq!(result.status, OrderStatus::PartiallyFilled);
+        assert_eq!(result.trades.quantity_sum(), qty(5.0));
+        assert_eq!(order_book.queue_position(OrderId::from_ascii("M1")), None);
+        assert!(order_book.aggregated_depth(Side::Sell, 10).is_empty());
+        assert_eq!(
+            order_book.aggregated_depth(Side::Buy, 10),
+            vec![(qty(98.0), qty(1.0))]
+        );
+        assert_eq!(order_book.best_price(Side::Buy), Some(qty(98.0)));
+
+        // Nothing left to buy from
+        let (_, result) = order_book.process_order(market_order(Side::Buy, 1.0, "M2"));
+        assert_eq!(result.status, OrderStatus::Unmatched);
+        assert_eq!(result.trades.len(), 0);
+        assert_eq!(order_book.aggregated_depth(Side::Buy, 10).len(), 1);
+
+        let (_, result) = order_book.process_order(market_order(Side::Sell, 1.0, "M3"));
+        assert_eq!(result.status, OrderStatus::Filled);
+        assert!(order_book.aggregated_depth(Side::Buy, 10).is_empty());
+        assert!(order_book.aggregated_depth(Side::Sell, 10).is_empty());
+        order_book.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_immediate_or_cancel_drops_the_unfilled_quantity() {
+        let mut order_book = OrderBook::new(SYMBOL_STR);
+        order_book.process_order(limit_order(Side::Sell, 100.0, 3.0, "A1"));
+        order_book.process_order(limit_order(Side::Sell, 101.0, 2.0, "A2"));
+        order_book.process_order(limit_order(Side::Sell, 103.0, 4.0, "A3"));
+
+        // 5 are available up to 101, the other 3 are dropped
+        let ioc = OrderEvent {
+            order_type: OrderType::ImmediateOrCancel,
+            ..limit_order(Side::Buy, 101.0, 8.0, "B1")
+        };
+        let (_, result) = order_book.process_order(ioc);
+        assert_eq!(result.status, OrderStatus::PartiallyFilled);
+        assert_eq!(result.trades.len(), 2);
+        assert_eq!(result.trades.quantity_sum(), qty(5.0));
+        assert_eq!(result.trades[1].price, qty(101.0));
+        assert_eq!(order_book.queue_position(OrderId::from_ascii("B1")), None);
+        assert!(order_book.aggregated_depth(Side::Buy, 1).is_empty());
+        assert_eq!(
+            order_book.aggregated_depth(Side::Sell, 1),
+            vec![(qty(103.0), qty(4.0))]
+        );
+
+        // Fully available
+        let ioc = OrderEvent {
+            order_type: OrderType::ImmediateOrCancel,
+            ..limit_order(Side::Buy, 103.0, 4.0, "B2")
+        };
+        let (_, result) = order_book.process_order(ioc);
+        assert_eq!(result.status, OrderStatus::Filled);
+        assert_eq!(result.trades.quantity_sum(), qty(4.0));
+        assert!(order_book.aggregated_depth(Side::Sell, 1).is_empty());
+
+        // Nothing to trade with: cancelled, the book is left as it was
+        order_book.process_order(limit_order(Side::Buy, 99.0, 1.0, "B3"));
+        let ioc = OrderEvent {
+            order_type: OrderType::ImmediateOrCancel,
+            ..limit_order(Side::Sell, 100.0, 2.0, "S1")
+        };
+        let (_, result) = order_book.process_order(ioc);
+        assert_eq!(result.status, OrderStatus::Cancelled);
+        assert_eq!(result.trades.len(), 0);
+        assert!(order_book.aggregated_depth(Side::Sell, 1).is_empty());
+        assert_eq!(
+            order_book.aggregated_depth(Side::Buy, 1),
+            vec![(qty(99.0), qty(1.0))]
+        );
+
+        // Limit price checked like a limit order
+        let ioc = OrderEvent {
+            order_type: OrderType::ImmediateOrCancel,
+            ..limit_order(Side::Sell, 0.0, 1.0, "S2")
+        };
+        let (_, result) = order_book.process_order(ioc);
+        assert_eq!(result.status, OrderStatus::RejectedInvalidPrice);
+    }
+
+    #[test]
+    fn test_fill_or_kill_fills_the_whole_quantity() {
+        let mut order_book = OrderBook::new(SYMBOL_STR);
+        order_book.process_order(limit_order(Side::Sell, 100.0, 3.0, "A1"));
+        order_book.process_order(limit_order(Side::Sell, 101.0, 2.0, "A2"));
+        order_book.process_order(limit_order(Side::Sell, 103.0, 4.0, "A3"));
+
+        let fok = OrderEvent {
+            order_type: OrderType::FillOrKill,
+            ..limit_order(Side::Buy, 101.0, 5.0, "B1")
+        };
+        let (_, result) = order_book.process_order(fok);
+        assert_eq!(result.status, OrderStatus::Filled);
+        assert_eq!(result.trades.len(), 2);
+        assert_eq!(result.trades.quantity_sum(), qty(5.0));
+        assert_eq!(result.trades[1].price, qty(101.0));
+        assert_eq!(order_book.queue_position(OrderId::from_ascii("B1")), None);
+        assert!(order_book.aggregated_depth(Side::Buy, 1).is_empty());
+        assert_eq!(
+            order_book.aggregated_depth(Side::Sell, 3),
+            vec![(qty(103.0), qty(4.0))]
+        );
+    }
+
+    #[test]
+    fn test_fill_or_kill_killed_leaves_the_book_unchanged() {
+        let mut order_book = OrderBook::new(SYMBOL_STR);
+        order_book.process_order(limit_order(Side::Sell, 100.0, 3.0, "A1"));
+        order_book.process_order(limit_order(Side::Sell, 101.0, 2.0, "A2"));
+        order_book.process_order(limit_order(Side::Sell, 103.0, 4.0, "A3"));
+        let asks = order_book.aggregated_depth(Side::Sell, 3);
+
+        // 5 are available up to 101, 9 in the whole book
+        for (price, quantity, id) in [(101.0, 6.0, "B1"), (110.0, 10.0, "B2")] {
+            let fok = OrderEvent {
+                order_type: OrderType::FillOrKill,
+                ..limit_order(Side::Buy, price, quantity, id)
+            };
+            let (_, result) = order_book.process_order(fok);
+            assert_eq!(result.status, OrderStatus::Unmatched);
+            assert_eq!(result.trades.len(), 0);
+            assert_eq!(order_book.queue_position(OrderId::from_ascii(id)), None);
+            assert!(order_book.aggregated_depth(Side::Buy, 1).is_empty());
+            assert_eq!(order_book.aggregated_depth(Side::Sell, 3), asks);
+        }
+        assert_eq!(order_book.last_trade_price(), None);
+
+        // Limit price checked like a limit order
+        let fok = OrderEvent {
+            order_type: OrderType::FillOrKill,
+            ..limit_order(Side::Buy, 0.0, 1.0, "B3")
+        };
+        let (_, result) = order_book.process_order(fok);
+        assert_eq!(result.status, OrderStatus::RejectedInvalidPrice);
+    }
+
+    #[test]
+    fn test_unmet_min_quantity_rests_when_configured() {
+        let mut order_book = OrderBook::new(SYMBOL_STR).with_rest_unmet_min_quantity(true);
+        assert!(order_book.rest_unmet_min_quantity());
+        order_book.process_order(limit_order(Side::Sell, 100.0, 2.0, "A1"));
+
+        let mut buy = limit_order(Side::Buy, 99.0, 5.0, "B1");
+        buy.min_quantity = qty(5.0);
+        let (_, result) = order_book.process_order(buy);
+        assert_eq!(result.status, OrderStatus::New);
+        assert_eq!(result.trades.len(), 0);
+        assert_eq!(
+            order_book.queue_position(OrderId::from_ascii("B1")),
+            Some((0, FixedPointArithmetic::ZERO))
+        );
+
+        // Market orders have no price to rest at
+        let mut market = OrderEvent {
+            order_type: OrderType::MarketOrder,
+            ..limit_order(Side::Buy, 0.0, 5.0, "M1")
+        };
+        market.min_quantity = qty(5.0);
+        let (_, result) = order_book.process_order(market);
+        assert_eq!(result.status, OrderStatus::RejectedMinQuantity);
+        assert_eq!(
+            order_book.aggregated_depth(Side::Sell, 1),
+            vec![(qty(100.0), qty(2.0))]
+        );
+    }
+
+    #[test]
+    fn test_resting_min_quantity_is_skipped() {
+        // B1 has nothing to match on entry, it rests with its minimum
+        let mut order_book = OrderBook::new(SYMBOL_STR).with_rest_unmet_min_quantity(true);
+        let mut bid = limit_order(Side::Buy, 100.0, 10.0, "B1");
+        bid.min_quantity = qty(5.0);
+        order_book.process_order(bid);
+        order_book.process_order(limit_order(Side::Buy, 100.0, 2.0, "B2"));
+        order_book.process_order(limit_order(Side::Buy, 99.0, 4.0, "B3"));
+
+        // Too small for B1: it trades with B2 behind it, then at the next level, B1 keeps its place
+        let (_, result) = order_book.process_order(limit_order(Side::Sell, 99.0, 3.0, "A1"));
+        assert_eq!(result.trades.len(), 2);
+        assert_eq!(result.trades[0].cl_ord_id, OrderId::from_ascii("B2"));
+        assert_eq!(result.trades[0].quantity, qty(2.0));
+        assert_eq!(result.trades[1].cl_ord_id, OrderId::from_ascii("B3"));
+        assert_eq!(result.trades[1].price, qty(99.0));
+        assert_eq!(result.trades[1].quantity, qty(1.0));
+        assert_eq!(
+            order_book.queue_position(OrderId::from_ascii("B1")),
+            Some((0, FixedPointArithmetic::ZERO))
+        );
+
+        // A sell too small for B1 rests at its price, the book stays locked
+        let (_, result) = order_book.process_order(limit_order(Side::Sell, 100.0, 4.0, "A2"));
+        assert_eq!(result.trades.len(), 0);
+        assert_eq!(order_book.get_spread(), Some(qty(0.0)));
+
+        // Large enough for the minimum of B1
+        let (_, result) = order_book.process_order(limit_order(Side::Sell, 100.0, 6.0, "A3"));
+        assert_eq!(result.trades.len(), 1);
+        assert_eq!(result.trades[0].cl_ord_id, OrderId::from_ascii("B1"));
+        assert_eq!(result.trades[0].quantity, qty(6.0));
+        assert_eq!(result.trades[0].leaves_qty, qty(4.0));
+
+        // The 4 left are below the minimum, the minimum is capped so they can still be completed
+        let (_, result) = order_book.process_order(limit_order(Side::Sell, 100.0, 4.0, "A4"));
+        assert_eq!(result.trades.len(), 1);
+        assert_eq!(result.trades[0].cl_ord_id, OrderId::from_ascii("B1"));
+        assert_eq!(result.trades[0].leaves_qty, FixedPointArithmetic::ZERO);
+        assert_eq!(order_book.queue_position(OrderId::from_ascii("B1")), None);
+    }
+
+    #[test]
+    fn test_zero_quantity_orders_never_rest() {
+        let mut order_book = OrderBook::new(SYMBOL_STR);
+        order_book.process_order(limit_order(Side::Sell, 99.0, 5.0, "A1"));
+
+        for (quantity, cl_ord_id) in [(0.0, "B1"), (-2.0, "B2")] {
+            // Crosses the resting ask, and would rest on the bids if not rejected
+            let (_, result) =
+                order_book.process_order(limit_order(Side::Buy, 99.0, quantity, cl_ord_id));
+            assert_eq!(result.status, OrderStatus::RejectedInvalidQuantity);
+            assert!(result.status.is_rejected());
+            assert_eq!(result.trades.len(), 0);
+            assert_eq!(
+                order_book.queue_position(OrderId::from_ascii(cl_ord_id)),
+                None
+            );
+        }
+        let (_, result) = order_book.process_order(limit_order(Side::Sell, 101.0, 0.0, "A2"));
+        assert_eq!(result.status, OrderStatus::RejectedInvalidQuantity);
+
+        let market_buy = OrderEvent::builder()
+            .buy()
+            .market()
+            .qty(0.0)
+            .cl_ord_id(OrderId::from_ascii("M1"))
+            .sender_id(SENDER)
+            .target_id(TARGET)
+            .symbol(SYMBOL_ID)
+            .build();
+        let (_, result) = order_book.process_order(market_buy);
+        assert_eq!(result.status, OrderStatus::RejectedInvalidQuantity);
+
+        assert!(order_book.aggregated_depth(Side::Buy, 10).is_empty());
+        assert_eq!(
+            order_book.aggregated_depth(Side::Sell, 10),
+            vec![(
+                FixedPointArithmetic::from_f64(99.0),
+                FixedPointArithmetic::from_f64(5.0)
+            )]
+        );
+    }
+
+    #[test]
+    fn test_non_positive_limit_price_is_rejected() {
+        let mut order_book = OrderBook::new(SYMBOL_STR);
+
+        for (price, cl_ord_id) in [(0.0, "B1"), (-1.0, "B2")] {
+            let (order, result) =
+                order_book.process_order(limit_order(Side::Buy, price, 1.0, cl_ord_id));
+            assert_eq!(result.status, OrderStatus::RejectedInvalidPrice);
+            assert_eq!(result.trades.len(), 0);
+            assert_eq!(order.price, FixedPointArithmetic::from_f64(price));
+            assert_eq!(
+                order_book.queue_position(OrderId::from_ascii(cl_ord_id)),
+                None
+            );
+        }
+        let (_, result) = order_book.process_order(limit_order(Side::Sell, 0.0, 1.0, "A1"));
+        assert_eq!(result.status, OrderStatus::RejectedInvalidPrice);
+
+        // No bid was left at the top of the book for a later sell to match against
+        assert!(order_book.aggregated_depth(Side::Buy, 10).is_empty());
+        let (_, result) = order_book.process_order(limit_order(Side::Sell, 1.0, 1.0, "A2"));
+        assert_eq!(result.status, OrderStatus::New);
+        assert_eq!(result.trades.len(), 0);
+    }
+
+    #[test]
+    fn test_execution_analytics_against_arrival_midpoint() {
+        let mut order_book = OrderBook::new(SYMBOL_STR);
+        order_book.process_order(limit_order(Side::Buy, 99.0, 1.0, "B1"));
+        order_book.process_order(limit_order(Side::Sell, 101.0, 1.0, "A1"));
+        order_book.process_order(limit_order(Side::Sell, 102.0, 1.0, "A2"));
+        assert_eq!(
+            order_book.mid_price(),
+            Some(FixedPointArithmetic::from_f64(100.0))
+        );
+        // Resting orders are not measured
+        assert_eq!(*order_book.analytics(), ExecutionAnalytics::default());
+
+        // Mid 100: a buy market order walks the asks at 101 and 102, effective spreads 2 and 4
+        let market_buy = OrderEvent::builder()
+            .buy()
+            .market()
+            .qty(2.0)
+            .cl_ord_id(OrderId::from_ascii("M1"))
+            .sender_id(SENDER)
+            .target_id(TARGET)
+            .symbol(SYMBOL_ID)
+            .build();
+        let (_, result) = order_book.process_order(market_buy);
+        assert_eq!(result.trades.len(), 2);
+        assert_eq!(
+            result.arrival_mid,
+            Some(FixedPointArithmetic::from_f64(100.0))
+        );
+
+        // The asks are empty: a sell has no midpoint
+        let (_, result) = order_book.process_order(limit_order(Side::Sell, 98.0, 0.5, "S1"));
+        assert_eq!(result.trades.len(), 1);
+        assert_eq!(result.arrival_mid, None);
+
+        // Mid 99.75: a sell at 99 has an effective spread of 1.5
+        order_book.process_order(limit_order(Side::Sell, 100.5, 1.0, "A3"));
+        let (_, result) = order_book.process_order(limit_order(Side::Sell, 99.0, 0.5, "S2"));
+        assert_eq!(result.trades[0].price, FixedPointArithmetic::from_f64(99.0));
+        assert_eq!(
+            result.arrival_mid,
+            Some(FixedPointArithmetic::from_f64(99.75))
+        );
+
+        // Cancels are not measured
+        order_book.process_order(
+            OrderEvent::builder()
+                .cancel(OrderId::from_ascii("A3"))
+                .cl_ord_id(OrderId::from_ascii("C1"))
+                .sender_id(SENDER)
+                .target_id(TARGET)
+                .symbol(SYMBOL_ID)
+                .build(),
+        );
+
+        let analytics = order_book.analytics();
+        assert_eq!(analytics.buy.trades, 2);
+        assert_eq!(
+            analytics.buy.average_effective_spread(),
+            Some(FixedPointArithmetic::from_f64(3.0))
+        );
+        assert_eq!(analytics.buy.price_improvement_pct(), Some(0.0));
+        assert_eq!(analytics.sell.trades, 1);
+        assert_eq!(
+            analytics.sell.average_effective_spread(),
+            Some(FixedPointArithmetic::from_f64(1.5))
+        );
+        assert_eq!(analytics.total().trades, 3);
+        assert_eq!(
+            analytics.total().average_effective_spread(),
+            Some(FixedPointArithmetic::from_f64(2.5))
+        );
+        assert_eq!(analytics.empty_opposite_side, 0);
+        assert_eq!(analytics.no_midpoint, 1);
+
+        // A buy market order into empty asks cannot trade
+        let mut order_book = OrderBook::new(SYMBOL_STR);
+        order_book.process_order(limit_order(Side::Buy, 99.0, 1.0, "B1"));
+        let (_, result) = order_book.process_order(market_buy);
+        assert_eq!(result.trades.len(), 0);
+        assert_eq!(result.arrival_mid, None);
+        assert_eq!(order_book.analytics().empty_opposite_side, 1);
+        assert_eq!(order_book.analytics().total().trades, 0);
+    }
 }
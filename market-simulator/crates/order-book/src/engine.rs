@@ -1,29 +1,45 @@
+use crate::analytics::ExecutionAnalytics;
 use crate::book::OrderBook;
+use crate::matching::MatchingCore;
+use crate::positions::SharedPositionTracker;
+use crate::read_model::{BookSnapshot, BookSnapshotReader, ReadModel, ReadModelConfig};
 use spsc::spsc_lock_free::{Consumer, Producer};
 use std::sync::{
-    Arc,
+    Arc, PoisonError,
     atomic::{AtomicBool, Ordering},
 };
 use std::time::{Duration, Instant};
+use types::macros::SymbolId;
 use types::{OrderEvent, OrderResult};
 
 use arc_swap::ArcSwap;
+use journal::JournalTap;
 use snapshot::types::Snapshot;
-use types::macros::EntityId;
 
 use utils::market_name;
 
+/// Pushes the end-of-stream sentinel, the engine drains the input queue and exits once it pops it.
+/// Waits for room when the queue is full, the engine keeps popping until it reaches the sentinel.
 pub fn kill_order_book_engine<const N: usize>(fix_to_ob_tx: &Producer<OrderEvent, N>) {
-    let order_event = OrderEvent {
-        sender_id: EntityId::from_ascii(""), // An empty sender_id is used as a signal to the order book engine to shut down
-        ..Default::default()                 // Fill the rest of the fields with default values
-    };
-
-    fix_to_ob_tx.push(order_event).unwrap();
+    let mut sentinel = OrderEvent::end_of_stream();
+    while let Err(rejected) = fix_to_ob_tx.push(sentinel) {
+        sentinel = rejected;
+        std::thread::yield_now();
+    }
 }
 
+/// How long an engine waits for room in a full result queue before dropping the result.
+/// Long enough to ride out a burst on the consumer side, short enough that a stalled consumer never stalls matching.
+pub const RESULT_QUEUE_MAX_WAIT: Duration = Duration::from_millis(10);
+
 pub enum OrderBookControl {
-    Reset { ack: crossbeam_channel::Sender<()> },
+    Reset {
+        ack: crossbeam_channel::Sender<()>,
+    },
+    /// Replies with the symbol of the book and the execution analytics accumulated since its last reset.
+    Stats {
+        reply: crossbeam_channel::Sender<(String, ExecutionAnalytics)>,
+    },
 }
 
 pub struct OrderBookSubscriber {
@@ -31,6 +47,13 @@ pub struct OrderBookSubscriber {
     pub market_data: Option<Arc<crossbeam_channel::Sender<(OrderEvent, OrderResult)>>>,
     pub database_persistence: Option<Arc<crossbeam_channel::Sender<(OrderEvent, OrderResult)>>>,
 }
+
+/// Shared-memory queue carrying the results of an engine to the execution report side, see `OrderBookEngine::set_result_queue`.
+struct ResultQueue<'a, const N: usize> {
+    producer: Producer<'a, (OrderEvent, OrderResult), N>,
+    max_wait: Duration,
+    dropped: u64,
+}
 /// Order book engine that processes incoming order events, updates the order book state, generates execution reports, and produces incremental snapshots of the order book for consumption by other components.
 /// The engine runs in a loop, processing control messages and incoming orders, and updates the snapshot after each order is processed.
 /// It also checks for a shutdown signal to gracefully exit when requested.
@@ -41,14 +64,22 @@ pub struct OrderBookEngine<'a, const N: usize> {
     subscribers: OrderBookSubscriber,
     /// Receiver for control messages to manage the order book engine, such as resetting the order book.
     control_rx: crossbeam_channel::Receiver<OrderBookControl>,
-    /// The order book instance that maintains the state of buy and sell orders.
-    order_book: OrderBook,
+    /// Matching core holding the order book that maintains the state of buy and sell orders.
+    core: MatchingCore,
     /// Optional ArcSwap holding the latest snapshot of the order book, allowing for efficient updates and reads without blocking. If this is None, snapshot generation is disabled.
     snapshot_ptr: Option<Arc<ArcSwap<Snapshot>>>,
     /// Atomic boolean flag to signal shutdown of the order book engine. When set to true, the engine will stop processing new orders and exit gracefully after processing any remaining orders in the input queue.
     shutdown: Arc<AtomicBool>,
     /// Optional shared metrics sink for engine-stage telemetry.
     metrics: Option<Arc<types::MarketMetrics>>,
+    /// Optional session journal, recording every order event, result and reset handled by the engine.
+    journal: Option<JournalTap>,
+    /// Optional double-buffered read model, publishing `BookSnapshot`s for readers on other threads.
+    read_model: Option<ReadModel>,
+    /// Optional shared-memory queue receiving every result, alongside the subscribers.
+    result_queue: Option<ResultQueue<'a, N>>,
+    /// Optional position tracker shared with the engines of the other symbols, checking the exposure of each order.
+    positions: Option<SharedPositionTracker>,
 }
 
 impl<'a, const N: usize> OrderBookEngine<'a, N> {
@@ -74,10 +105,14 @@ impl<'a, const N: usize> OrderBookEngine<'a, N> {
                 database_persistence: database_persistence_producer,
             },
             control_rx,
-            order_book,
+            core: MatchingCore::new(order_book),
             snapshot_ptr,
             shutdown,
             metrics: None,
+            journal: None,
+            read_model: None,
+            result_queue: None,
+            positions: None,
         }
     }
 
@@ -85,11 +120,84 @@ impl<'a, const N: usize> OrderBookEngine<'a, N> {
         self.metrics = Some(metrics);
     }
 
+    /// Journals every order handled from now on, set it before `import_order_book` so the imported orders are journaled too.
+    pub fn set_journal(&mut self, journal: JournalTap) {
+        self.journal = Some(journal);
+    }
+
+    /// Pushes every result, and the end-of-stream sentinel, into `producer` from now on.
+    /// When the queue is full the engine waits up to `max_wait` for room, then drops the result: it is journaled as
+    /// dropped and counted in `MarketMetrics::order_result_queue_dropped`, so a stalled consumer never stalls matching.
+    /// Arguments:
+    /// - `producer`: Producer side of the result queue, usually a `memory::SharedQueue` read by the FIX process.
+    /// - `max_wait`: Longest wait for room in the queue, see `RESULT_QUEUE_MAX_WAIT`.
+    pub fn set_result_queue(
+        &mut self,
+        producer: Producer<'a, (OrderEvent, OrderResult), N>,
+        max_wait: Duration,
+    ) {
+        self.result_queue = Some(ResultQueue {
+            producer,
+            max_wait,
+            dropped: 0,
+        });
+    }
+
+    /// Checks every order against the gross exposure limit of `positions` and records its result there from now on.
+    /// Set it before `import_order_book` so the imported resting orders are tracked too, they are not checked.
+    pub fn set_position_tracker(&mut self, positions: SharedPositionTracker) {
+        self.positions = Some(positions);
+    }
+
+    /// Number of results dropped because the result queue stayed full.
+    pub fn dropped_results(&self) -> u64 {
+        self.result_queue.as_ref().map_or(0, |queue| queue.dropped)
+    }
+
+    /// Publishes `BookSnapshot`s according to `config` from now on, the current state is published right away.
+    /// Returns a reader to hand over to the threads consuming the snapshots.
+    pub fn enable_read_model(&mut self, config: ReadModelConfig) -> BookSnapshotReader {
+        let mut read_model = ReadModel::new(config);
+        read_model.publish(self.core.order_book());
+        let reader = read_model.reader();
+        self.read_model = Some(read_model);
+        reader
+    }
+
+    /// Latest published snapshot, `None` unless the read model is enabled.
+    pub fn snapshot(&self) -> Option<Arc<BookSnapshot>> {
+        self.read_model.as_ref().map(ReadModel::snapshot)
+    }
+
+    pub fn order_book(&self) -> &OrderBook {
+        self.core.order_book()
+    }
+
+    /// Matching core run by the engine, the same API a backtest drives without the engine thread.
+    pub fn core(&self) -> &MatchingCore {
+        &self.core
+    }
+
     /// Imports a batch of order events into the order book engine, processing each order and updating the snapshot after each order is processed.
     /// This function is used to restored order book state from a database or other persistent storage after a reset or during initialization.
     pub fn import_order_book(&mut self, orders: Vec<OrderEvent>) {
         for order in orders {
-            let (order_event, order_result) = self.order_book.process_order(order);
+            if let Some(journal) = &self.journal {
+                journal.record_order_event(&order);
+            }
+            let (order_event, order_result) = self.core.process(order);
+            if let Some(positions) = &self.positions {
+                positions
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .record(
+                        &(order_event, order_result),
+                        self.core.order_book().fills(&order_result),
+                    );
+            }
+            if let Some(read_model) = &mut self.read_model {
+                read_model.on_event(self.core.order_book(), &order_result);
+            }
             // Update the snapshot with the latest state of the order book after processing the order
             if self.snapshot_ptr.is_some() {
                 self.incremental_update(order_event, order_result);
@@ -186,7 +294,7 @@ impl<'a, const N: usize> OrderBookEngine<'a, N> {
                 let mut next = Snapshot {
                     timestamp_ms: current.timestamp_ms,
                     symbol: if current.symbol.is_empty() {
-                        self.order_book.symbol.clone()
+                        self.core.symbol().to_string()
                     } else {
                         current.symbol.clone()
                     },
@@ -294,64 +402,170 @@ impl<'a, const N: usize> OrderBookEngine<'a, N> {
         if let Some(sender) = &self.subscribers.database_persistence {
             let _ = sender.send(execution_report);
         }
+
+        self.push_result(execution_report);
+    }
+
+    /// Pushes a result into the result queue, waiting up to its `max_wait` for room before dropping it.
+    fn push_result(&mut self, execution_report: (OrderEvent, OrderResult)) {
+        let Some(queue) = &mut self.result_queue else {
+            return;
+        };
+
+        let mut item = execution_report;
+        let mut deadline = None;
+        loop {
+            match queue.producer.push(item) {
+                Ok(()) => return,
+                Err(rejected) => item = rejected,
+            }
+            let deadline = *deadline.get_or_insert_with(|| Instant::now() + queue.max_wait);
+            if Instant::now() >= deadline {
+                break;
+            }
+            std::hint::spin_loop();
+        }
+
+        queue.dropped += 1;
+        if let Some(journal) = &self.journal {
+            journal.record_dropped_result(&item.0, &item.1);
+        }
+        if let Some(metrics) = &self.metrics {
+            metrics
+                .order_result_queue_dropped
+                .fetch_add(1, Ordering::Relaxed);
+        }
+        tracing::warn!(
+            "[{}][{}] Result queue full for {:?}, dropping the result of order {}",
+            market_name(),
+            self.core.symbol(),
+            queue.max_wait,
+            item.0.cl_ord_id
+        );
     }
 
+    /// Processes a single order event and fans the result out to the subscribers.
+    fn process_event(&mut self, event: OrderEvent) {
+        let received_at = Instant::now();
+        if let Some(journal) = &self.journal {
+            journal.record_order_event(&event);
+        }
+        // Process incoming order events from the input queue, the tracker stays locked from the exposure check to the
+        // update so the books of two symbols cannot both accept orders breaching the limit of the same participant
+        let (event, result) = match &self.positions {
+            Some(positions) => positions
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .process(&mut self.core, event),
+            None => self.core.process(event),
+        };
+        if let Some(journal) = &self.journal {
+            journal.record_order_result(&event, &result);
+        }
+        if let Some(read_model) = &mut self.read_model {
+            read_model.on_event(self.core.order_book(), &result);
+        }
+        // For now, I send a copy of the order event and result to each subscriber, but ideally I would like to avoid copying the order event and result in the hot path of processing orders.
+        // TODO : How can I avoid making a copy of the order in the hot path?
+        self.fan_out_execution_report(event, result);
+
+        if let Some(metrics) = &self.metrics {
+            metrics.order_book_events.fetch_add(1, Ordering::Relaxed);
+            let elapsed_ms = received_at.elapsed().as_millis() as u64;
+            if let Ok(mut samples) = metrics.order_book_event_to_fanout_latency_ms.lock() {
+                samples.push(elapsed_ms);
+            }
+        }
+
+        // Update the snapshot with the latest state of the order book after processing the order
+        // TODO : Just send execution reports to the snapshot engine and let it update the snapshot instead of doing it in the hot path of processing orders in the order book engine. Already done in database persistance.
+        if self.snapshot_ptr.is_some() {
+            self.incremental_update(event, result);
+        }
+    }
+
+    /// Runs the engine until the shutdown flag is set or the end-of-stream sentinel is received.
+    /// In both cases the input queue is drained before exiting, and an end-of-stream report is sent to every subscriber.
     pub fn run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         loop {
             // Process control messages first
             while let Ok(control) = self.control_rx.try_recv() {
                 match control {
                     OrderBookControl::Reset { ack } => {
-                        self.order_book = OrderBook::new(self.order_book.symbol.as_str()); // Reset the order book by creating a new instance
+                        // Reset the order book, keeping its level cap and order rules
+                        self.core.reset();
+                        if let Some(positions) = &self.positions {
+                            positions
+                                .lock()
+                                .unwrap_or_else(PoisonError::into_inner)
+                                .clear_open_orders(SymbolId::from_ascii(self.core.symbol()));
+                        }
+                        if let Some(journal) = &self.journal {
+                            journal.record_reset(SymbolId::from_ascii(self.core.symbol()));
+                        }
+                        if let Some(read_model) = &mut self.read_model {
+                            read_model.publish(self.core.order_book());
+                        }
                         tracing::info!(
                             "[{}][{}] Order book reset completed",
                             market_name(),
-                            self.order_book.symbol
+                            self.core.symbol()
                         );
                         let _ = ack.send(());
                     }
+                    OrderBookControl::Stats { reply } => {
+                        let _ =
+                            reply.send((self.core.symbol().to_string(), *self.core.analytics()));
+                    }
                 }
             }
 
-            if let Some(event) = self.fifo_in.pop_timeout(Duration::from_millis(500)) {
-                let received_at = Instant::now();
-                // Process incoming order events from the input queue
-                let (event, result) = self.order_book.process_order(event);
-                // For now, I send a copy of the order event and result to each subscriber, but ideally I would like to avoid copying the order event and result in the hot path of processing orders.
-                // TODO : How can I avoid making a copy of the order in the hot path?
-                self.fan_out_execution_report(event, result);
-
-                if let Some(metrics) = &self.metrics {
-                    metrics.order_book_events.fetch_add(1, Ordering::Relaxed);
-                    let elapsed_ms = received_at.elapsed().as_millis() as u64;
-                    if let Ok(mut samples) = metrics.order_book_event_to_fanout_latency_ms.lock() {
-                        samples.push(elapsed_ms);
-                    }
-                }
+            let mut end_of_stream = false;
 
-                // Update the snapshot with the latest state of the order book after processing the order
-                // TODO : Just send execution reports to the snapshot engine and let it update the snapshot instead of doing it in the hot path of processing orders in the order book engine. Already done in database persistance.
-                if self.snapshot_ptr.is_some() {
-                    self.incremental_update(event, result);
+            if let Some(event) = self.fifo_in.pop_timeout(Duration::from_millis(500)) {
+                if event.is_end_of_stream() {
+                    end_of_stream = true;
+                } else {
+                    self.process_event(event);
                 }
             }
 
-            if self.shutdown.load(Ordering::Relaxed) && self.fifo_in.is_empty() {
+            if end_of_stream {
+                // The producer is done, drain whatever is left without waiting for more
+                while let Some(event) = self.fifo_in.try_pop() {
+                    if !event.is_end_of_stream() {
+                        self.process_event(event);
+                    }
+                }
+                tracing::info!(
+                    "[{}][{}] End of stream received, stopping order book engine",
+                    market_name(),
+                    self.core.symbol()
+                );
+            } else if self.shutdown.load(Ordering::Relaxed) && self.fifo_in.is_empty() {
                 tracing::info!(
                     "[{}][{}] Shutdown signal received, stopping order book engine",
                     market_name(),
-                    self.order_book.symbol
+                    self.core.symbol()
                 );
-                // Propagate shutdown signal to snapshot generation engine by setting the shutdown flag, which both engines check to know when to exit gracefully.
-                self.fan_out_execution_report(OrderEvent::default(), OrderResult::default()); // Send a final execution report with default values to unblock any subscribers that may be waiting for execution reports, such as the snapshot generation engine, allowing them to check the shutdown flag and exit gracefully.
-                break;
+            } else {
+                continue;
             }
+
+            // Readers see the final state of the book, whatever the publishing triggers
+            if let Some(read_model) = &mut self.read_model {
+                read_model.publish(self.core.order_book());
+            }
+
+            // Propagate end of stream to subscribers, such as the snapshot generation engine, so they are unblocked and can exit gracefully.
+            self.fan_out_execution_report(OrderEvent::end_of_stream(), OrderResult::default());
+            break;
         }
 
         tracing::info!(
             "[{}][{}] Order book engine shutting down gracefully",
             market_name(),
-            self.order_book.symbol
+            self.core.symbol()
         );
         Ok(())
     }
@@ -362,7 +576,7 @@ mod tests {
     use super::*;
     use snapshot::types::OrderBookSnapshot;
     use types::macros::{EntityId, OrderId, SymbolId};
-    use types::{FixedPointArithmetic, OrderEvent, OrderStatus, OrderType, Side, Trade, Trades};
+    use types::{FixedPointArithmetic, OrderEvent, OrderStatus, OrderType, Trade, Trades};
 
     const SYMBOL_STR: &str = "TEST";
     const SYMBOL_ID: SymbolId = SymbolId::from_ascii(SYMBOL_STR);
@@ -375,8 +589,6 @@ mod tests {
     #[test]
     fn test_engine() {
         let mut inbound_queue = spsc::spsc_lock_free::RingBuffer::<OrderEvent, 1024>::new();
-        let mut outbound_queue =
-            spsc::spsc_lock_free::RingBuffer::<(OrderEvent, OrderResult), 1024>::new();
 
         thread::scope(|s| {
             let shutdown = Arc::new(AtomicBool::new(false));
@@ -384,12 +596,12 @@ mod tests {
             let snapshot_ptr = Arc::new(ArcSwap::from_pointee(Snapshot::default()));
 
             let (inbound_producer, inbound_consumer) = inbound_queue.split();
-            let (outbound_producer, outbound_consumer) = outbound_queue.split();
+            let (outbound_producer, outbound_consumer) = crossbeam_channel::unbounded();
 
             let (_control_tx, control_rx) = crossbeam_channel::unbounded();
             let mut engine = OrderBookEngine::new(
                 inbound_consumer,
-                Some(outbound_producer),
+                Some(Arc::new(outbound_producer)),
                 None,
                 None,
                 control_rx,
@@ -405,23 +617,21 @@ mod tests {
             });
 
             // Send some orders to the engine
-            let order = OrderEvent {
-                price: FixedPointArithmetic::from_f64(100.0),
-                quantity: FixedPointArithmetic::from_f64(10.0),
-                side: Side::Buy,
-                order_type: OrderType::LimitOrder,
-                cl_ord_id: CL_ORD_ID,
-                orig_cl_ord_id: None,
-                sender_id: SENDER,
-                target_id: TARGET,
-                symbol: SYMBOL_ID,
-                ..Default::default() // Set the timestamp to the current time in milliseconds since epoch
-            };
+            let order = OrderEvent::builder()
+                .price_f64(100.0)
+                .qty(10.0)
+                .cl_ord_id(CL_ORD_ID)
+                .sender_id(SENDER)
+                .target_id(TARGET)
+                .symbol(SYMBOL_ID)
+                .build();
 
             inbound_producer.push(order).unwrap();
             // Give some time for the engine to process the order
             std::thread::sleep(std::time::Duration::from_millis(100));
-            let (order_event, order_result) = outbound_consumer.pop().unwrap();
+            let (order_event, order_result) = outbound_consumer
+                .recv_timeout(Duration::from_secs(1))
+                .unwrap();
 
             assert!(order_event.price == FixedPointArithmetic::from_f64(100.0));
             assert!(order_result.trades.len() == 0);
@@ -429,23 +639,22 @@ mod tests {
             assert!(order_result.status == OrderStatus::New);
 
             // Send a matching sell order to the engine
-            let order2 = OrderEvent {
-                price: FixedPointArithmetic::from_f64(100.0),
-                quantity: FixedPointArithmetic::from_f64(10.0),
-                side: Side::Sell,
-                order_type: OrderType::LimitOrder,
-                cl_ord_id: CL_ORD_ID,
-                orig_cl_ord_id: None,
-                sender_id: SENDER,
-                target_id: TARGET,
-                symbol: SYMBOL_ID,
-                ..Default::default() // Set the timestamp to the current time in milliseconds since epoch
-            };
+            let order2 = OrderEvent::builder()
+                .sell()
+                .price_f64(100.0)
+                .qty(10.0)
+                .cl_ord_id(CL_ORD_ID)
+                .sender_id(SENDER)
+                .target_id(TARGET)
+                .symbol(SYMBOL_ID)
+                .build();
 
             inbound_producer.push(order2).unwrap();
             // Give some time for the engine to process the order
             std::thread::sleep(std::time::Duration::from_millis(100));
-            let (order_event2, order_result2) = outbound_consumer.pop().unwrap();
+            let (order_event2, order_result2) = outbound_consumer
+                .recv_timeout(Duration::from_secs(1))
+                .unwrap();
 
             assert!(order_event2.price == FixedPointArithmetic::from_f64(100.0));
             assert!(order_result2.trades.len() == 1); // One trade should be executed for the matching orders
@@ -474,22 +683,17 @@ mod tests {
         let shutdown = Arc::new(AtomicBool::new(false));
 
         let mut initial_book = OrderBookSnapshot::default();
-        let initial_ask = OrderEvent {
-            price: FixedPointArithmetic::from_f64(101.0),
-            quantity: FixedPointArithmetic::from_f64(7.0),
-            side: Side::Sell,
-            order_type: OrderType::LimitOrder,
-            symbol: SYMBOL_ID,
-            ..Default::default()
-        };
-        let initial_bid = OrderEvent {
-            price: FixedPointArithmetic::from_f64(99.0),
-            quantity: FixedPointArithmetic::from_f64(5.0),
-            side: Side::Buy,
-            order_type: OrderType::LimitOrder,
-            symbol: SYMBOL_ID,
-            ..Default::default()
-        };
+        let initial_ask = OrderEvent::builder()
+            .sell()
+            .price_f64(101.0)
+            .qty(7.0)
+            .symbol(SYMBOL_ID)
+            .build();
+        let initial_bid = OrderEvent::builder()
+            .price_f64(99.0)
+            .qty(5.0)
+            .symbol(SYMBOL_ID)
+            .build();
         initial_book.add_ask(initial_ask).unwrap();
         initial_book.add_bid(initial_bid).unwrap();
 
@@ -511,14 +715,11 @@ mod tests {
             Arc::clone(&shutdown),
         );
 
-        let taker_buy = OrderEvent {
-            price: FixedPointArithmetic::from_f64(102.0),
-            quantity: FixedPointArithmetic::from_f64(10.0),
-            side: Side::Buy,
-            order_type: OrderType::LimitOrder,
-            symbol: SYMBOL_ID,
-            ..Default::default()
-        };
+        let taker_buy = OrderEvent::builder()
+            .price_f64(102.0)
+            .qty(10.0)
+            .symbol(SYMBOL_ID)
+            .build();
 
         let mut trades = Trades::new();
         trades
@@ -567,14 +768,12 @@ mod tests {
             FixedPointArithmetic::from_f64(5.0)
         );
 
-        let cancel_order = OrderEvent {
-            price: FixedPointArithmetic::from_f64(102.0),
-            quantity: FixedPointArithmetic::from_f64(6.0),
-            side: Side::Buy,
-            order_type: OrderType::CancelOrder,
-            symbol: SYMBOL_ID,
-            ..Default::default()
-        };
+        let cancel_order = OrderEvent::builder()
+            .order_type(OrderType::CancelOrder)
+            .price_f64(102.0)
+            .qty(6.0)
+            .symbol(SYMBOL_ID)
+            .build();
 
         engine.incremental_update(
             cancel_order,
@@ -621,22 +820,17 @@ mod tests {
         let shutdown = Arc::new(AtomicBool::new(false));
 
         let mut initial_book = OrderBookSnapshot::default();
-        let initial_bid = OrderEvent {
-            price: FixedPointArithmetic::from_f64(100.0),
-            quantity: FixedPointArithmetic::from_f64(8.0),
-            side: Side::Buy,
-            order_type: OrderType::LimitOrder,
-            symbol: SYMBOL_ID,
-            ..Default::default()
-        };
-        let initial_ask = OrderEvent {
-            price: FixedPointArithmetic::from_f64(103.0),
-            quantity: FixedPointArithmetic::from_f64(4.0),
-            side: Side::Sell,
-            order_type: OrderType::LimitOrder,
-            symbol: SYMBOL_ID,
-            ..Default::default()
-        };
+        let initial_bid = OrderEvent::builder()
+            .price_f64(100.0)
+            .qty(8.0)
+            .symbol(SYMBOL_ID)
+            .build();
+        let initial_ask = OrderEvent::builder()
+            .sell()
+            .price_f64(103.0)
+            .qty(4.0)
+            .symbol(SYMBOL_ID)
+            .build();
         initial_book.add_bid(initial_bid).unwrap();
         initial_book.add_ask(initial_ask).unwrap();
 
@@ -658,14 +852,12 @@ mod tests {
             Arc::clone(&shutdown),
         );
 
-        let taker_sell = OrderEvent {
-            price: FixedPointArithmetic::from_f64(99.0),
-            quantity: FixedPointArithmetic::from_f64(10.0),
-            side: Side::Sell,
-            order_type: OrderType::LimitOrder,
-            symbol: SYMBOL_ID,
-            ..Default::default()
-        };
+        let taker_sell = OrderEvent::builder()
+            .sell()
+            .price_f64(99.0)
+            .qty(10.0)
+            .symbol(SYMBOL_ID)
+            .build();
 
         let mut trades = Trades::new();
         trades
@@ -714,14 +906,13 @@ mod tests {
             FixedPointArithmetic::from_f64(4.0)
         );
 
-        let cancel_order = OrderEvent {
-            price: FixedPointArithmetic::from_f64(99.0),
-            quantity: FixedPointArithmetic::from_f64(7.0),
-            side: Side::Sell,
-            order_type: OrderType::CancelOrder,
-            symbol: SYMBOL_ID,
-            ..Default::default()
-        };
+        let cancel_order = OrderEvent::builder()
+            .sell()
+            .order_type(OrderType::CancelOrder)
+            .price_f64(99.0)
+            .qty(7.0)
+            .symbol(SYMBOL_ID)
+            .build();
 
         engine.incremental_update(
             cancel_order,
@@ -770,14 +961,13 @@ mod tests {
         let mut initial_book = OrderBookSnapshot::default();
         for raw_price in (101..=110).rev() {
             initial_book
-                .add_bid(OrderEvent {
-                    price: FixedPointArithmetic::from_f64(raw_price as f64),
-                    quantity: FixedPointArithmetic::from_f64(1.0),
-                    side: Side::Buy,
-                    order_type: OrderType::LimitOrder,
-                    symbol: SYMBOL_ID,
-                    ..Default::default()
-                })
+                .add_bid(
+                    OrderEvent::builder()
+                        .price_f64(raw_price as f64)
+                        .qty(1.0)
+                        .symbol(SYMBOL_ID)
+                        .build(),
+                )
                 .unwrap();
         }
 
@@ -800,14 +990,11 @@ mod tests {
         );
 
         engine.incremental_update(
-            OrderEvent {
-                price: FixedPointArithmetic::from_f64(111.0),
-                quantity: FixedPointArithmetic::from_f64(2.0),
-                side: Side::Buy,
-                order_type: OrderType::LimitOrder,
-                symbol: SYMBOL_ID,
-                ..Default::default()
-            },
+            OrderEvent::builder()
+                .price_f64(111.0)
+                .qty(2.0)
+                .symbol(SYMBOL_ID)
+                .build(),
             OrderResult {
                 status: OrderStatus::New,
                 timestamp_ms: 21,
@@ -831,14 +1018,11 @@ mod tests {
         );
 
         engine.incremental_update(
-            OrderEvent {
-                price: FixedPointArithmetic::from_f64(90.0),
-                quantity: FixedPointArithmetic::from_f64(3.0),
-                side: Side::Buy,
-                order_type: OrderType::LimitOrder,
-                symbol: SYMBOL_ID,
-                ..Default::default()
-            },
+            OrderEvent::builder()
+                .price_f64(90.0)
+                .qty(3.0)
+                .symbol(SYMBOL_ID)
+                .build(),
             OrderResult {
                 status: OrderStatus::New,
                 timestamp_ms: 22,
@@ -857,4 +1041,226 @@ mod tests {
             FixedPointArithmetic::from_f64(102.0)
         );
     }
+
+    #[test]
+    fn test_run_exits_on_end_of_stream() {
+        const N: usize = 64;
+        const BATCH: usize = 10;
+
+        let mut inbound_queue = spsc::spsc_lock_free::RingBuffer::<OrderEvent, N>::new();
+        let (inbound_producer, inbound_consumer) = inbound_queue.split();
+
+        let (report_tx, report_rx) = crossbeam_channel::unbounded();
+        let (_control_tx, control_rx) = crossbeam_channel::unbounded();
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let mut engine = OrderBookEngine::new(
+            inbound_consumer,
+            Some(Arc::new(report_tx)),
+            None,
+            None,
+            control_rx,
+            OrderBook::new(SYMBOL_STR),
+            None,
+            Arc::clone(&shutdown),
+        );
+
+        for i in 0..BATCH {
+            let order = OrderEvent::builder()
+                .price_f64(100.0 - i as f64)
+                .qty(1.0)
+                .cl_ord_id(CL_ORD_ID)
+                .sender_id(SENDER)
+                .target_id(TARGET)
+                .symbol(SYMBOL_ID)
+                .build();
+            inbound_producer.push(order).unwrap();
+        }
+        kill_order_book_engine(&inbound_producer);
+
+        // Returns on its own, shutdown is never set
+        engine.run().unwrap();
+        assert!(!shutdown.load(Ordering::Relaxed));
+
+        let reports: Vec<(OrderEvent, OrderResult)> = report_rx.try_iter().collect();
+        assert_eq!(reports.len(), BATCH + 1);
+        for (i, (event, result)) in reports[..BATCH].iter().enumerate() {
+            assert_eq!(
+                event.price,
+                FixedPointArithmetic::from_f64(100.0 - i as f64)
+            );
+            assert!(result.status == OrderStatus::New);
+        }
+        assert!(reports[BATCH].0.is_end_of_stream());
+    }
+
+    #[test]
+    fn test_seeded_book_fills_crossing_orders() {
+        const N: usize = 64;
+
+        // Five bids from 95 to 99 and five asks from 101 to 105, SEED6 is the best ask
+        let mut seed = String::from("symbol,side,price,quantity,sender\n");
+        for i in 0..5 {
+            seed.push_str(&format!("{SYMBOL_STR},buy,{},1,MM1\n", 95 + i));
+        }
+        for i in 0..5 {
+            seed.push_str(&format!("{SYMBOL_STR},sell,{},2,MM2\n", 101 + i));
+        }
+        let path = std::env::temp_dir().join(format!("engine-seed-{}.csv", std::process::id()));
+        std::fs::write(&path, seed).unwrap();
+        let mut seed = crate::seed::read_book_seed(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let seed_orders = seed.remove(&SYMBOL_ID).unwrap();
+        assert_eq!(seed_orders.len(), 10);
+
+        let mut inbound_queue = spsc::spsc_lock_free::RingBuffer::<OrderEvent, N>::new();
+        let (inbound_producer, inbound_consumer) = inbound_queue.split();
+        let (report_tx, report_rx) = crossbeam_channel::unbounded();
+        let (_control_tx, control_rx) = crossbeam_channel::unbounded();
+
+        let mut engine = OrderBookEngine::new(
+            inbound_consumer,
+            Some(Arc::new(report_tx)),
+            None,
+            None,
+            control_rx,
+            OrderBook::new(SYMBOL_STR),
+            None,
+            Arc::new(AtomicBool::new(false)),
+        );
+        engine.import_order_book(seed_orders);
+        assert_eq!(engine.order_book().bids.len(), 5);
+        assert_eq!(engine.order_book().asks.len(), 5);
+        // Seeding does not report
+        assert!(report_rx.try_recv().is_err());
+
+        // Sweeps the asks at 101 and 102, then half of 103
+        inbound_producer
+            .push(
+                OrderEvent::builder()
+                    .price_f64(103.0)
+                    .qty(5.0)
+                    .cl_ord_id(CL_ORD_ID)
+                    .sender_id(SENDER)
+                    .target_id(TARGET)
+                    .symbol(SYMBOL_ID)
+                    .build(),
+            )
+            .unwrap();
+        kill_order_book_engine(&inbound_producer);
+        engine.run().unwrap();
+
+        let (event, result) = report_rx.try_recv().unwrap();
+        assert_eq!(event.cl_ord_id, CL_ORD_ID);
+        let fills: Vec<(OrderId, FixedPointArithmetic, FixedPointArithmetic)> = result
+            .trades
+            .iter()
+            .map(|trade| (trade.cl_ord_id, trade.price, trade.quantity))
+            .collect();
+        assert_eq!(
+            fills,
+            vec![
+                (
+                    OrderId::from_ascii("SEED6"),
+                    FixedPointArithmetic::from_f64(101.0),
+                    FixedPointArithmetic::from_f64(2.0)
+                ),
+                (
+                    OrderId::from_ascii("SEED7"),
+                    FixedPointArithmetic::from_f64(102.0),
+                    FixedPointArithmetic::from_f64(2.0)
+                ),
+                (
+                    OrderId::from_ascii("SEED8"),
+                    FixedPointArithmetic::from_f64(103.0),
+                    FixedPointArithmetic::from_f64(1.0)
+                ),
+            ]
+        );
+        assert_eq!(engine.order_book().asks.len(), 3);
+        assert_eq!(
+            engine.order_book().aggregated_depth(types::Side::Sell, 1),
+            vec![(
+                FixedPointArithmetic::from_f64(103.0),
+                FixedPointArithmetic::from_f64(1.0)
+            )]
+        );
+    }
+
+    #[test]
+    fn test_full_result_queue_drops_after_bounded_wait() {
+        const N: usize = 8;
+        // One slot of the ring buffer is kept free
+        const CAPACITY: usize = N - 1;
+        const BATCH: usize = CAPACITY + 3;
+
+        let mut inbound_queue = spsc::spsc_lock_free::RingBuffer::<OrderEvent, N>::new();
+        let (_inbound_producer, inbound_consumer) = inbound_queue.split();
+        let mut result_queue =
+            spsc::spsc_lock_free::RingBuffer::<(OrderEvent, OrderResult), N>::new();
+        let (result_producer, result_consumer) = result_queue.split();
+        let (_control_tx, control_rx) = crossbeam_channel::unbounded();
+        let metrics = Arc::new(types::MarketMetrics::new());
+
+        let mut engine = OrderBookEngine::new(
+            inbound_consumer,
+            None,
+            None,
+            None,
+            control_rx,
+            OrderBook::new(SYMBOL_STR),
+            None,
+            Arc::new(AtomicBool::new(false)),
+        );
+        engine.set_metrics(Arc::clone(&metrics));
+        engine.set_result_queue(result_producer, Duration::from_millis(1));
+
+        // Nobody consumes the results: the engine keeps matching once the queue is full
+        let started_at = Instant::now();
+        for i in 0..BATCH {
+            engine.process_event(
+                OrderEvent::builder()
+                    .price_f64(100.0 - i as f64)
+                    .qty(1.0)
+                    .cl_ord_id(CL_ORD_ID)
+                    .sender_id(SENDER)
+                    .target_id(TARGET)
+                    .symbol(SYMBOL_ID)
+                    .build(),
+            );
+        }
+        assert!(started_at.elapsed() < Duration::from_secs(1));
+        assert_eq!(engine.order_book().bids.len(), BATCH);
+        assert_eq!(engine.dropped_results(), (BATCH - CAPACITY) as u64);
+        assert_eq!(
+            metrics.order_result_queue_dropped.load(Ordering::Relaxed),
+            BATCH - CAPACITY
+        );
+
+        // The queued results are the first ones, in order
+        for i in 0..CAPACITY {
+            let (event, result) = result_consumer.try_pop().unwrap();
+            assert_eq!(
+                event.price,
+                FixedPointArithmetic::from_f64(100.0 - i as f64)
+            );
+            assert!(result.status == OrderStatus::New);
+        }
+        assert!(result_consumer.try_pop().is_none());
+
+        // Once drained, results flow again
+        engine.process_event(
+            OrderEvent::builder()
+                .price_f64(50.0)
+                .qty(1.0)
+                .cl_ord_id(CL_ORD_ID)
+                .sender_id(SENDER)
+                .target_id(TARGET)
+                .symbol(SYMBOL_ID)
+                .build(),
+        );
+        let (event, _) = result_consumer.try_pop().unwrap();
+        assert_eq!(event.price, FixedPointArithmetic::from_f64(50.0));
+        assert_eq!(engine.dropped_results(), (BATCH - CAPACITY) as u64);
+    }
 }
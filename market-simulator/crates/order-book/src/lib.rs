@@ -1,7 +1,16 @@
 pub mod aggregator;
+pub mod analytics;
 pub mod book;
 pub mod engine;
+pub mod matching;
+pub mod positions;
+pub mod read_model;
+pub mod replay;
+pub mod seed;
 pub mod snapshot;
 
 pub use self::aggregator::OrderBookAggregator;
+pub use self::analytics::ExecutionAnalytics;
 pub use self::engine::OrderBookControl;
+pub use self::matching::{Depth, ExecutionReport, MatchingCore};
+pub use self::positions::{PositionTracker, SharedPositionTracker};
@@ -0,0 +1,214 @@
+use crate::analytics::ExecutionAnalytics;
+use crate::book::OrderBook;
+use types::macros::{OrderId, SymbolId};
+use types::{FixedPointArithmetic, OrderEvent, OrderResult, OrderStatus, Side};
+
+/// Order event as processed by the book, with its result.
+/// The same pair the engine fans out to its subscribers.
+pub type ExecutionReport = (OrderEvent, OrderResult);
+
+/// Aggregated top of the book, (price, total resting quantity) pairs best price first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Depth {
+    pub bids: Vec<(FixedPointArithmetic, FixedPointArithmetic)>,
+    pub asks: Vec<(FixedPointArithmetic, FixedPointArithmetic)>,
+}
+
+/// Matching logic of one symbol without any thread or queue: the order book, its reset, its clock and its checks.
+/// `OrderBookEngine` runs one behind its input queue, a backtest drives one directly from its own loop.
+///
+/// Orders are checked by the book before they match (quantity, price, lot size, minimum quantity), rejected ones get
+/// a rejection status in their report. Results are stamped with the clock of the core: the system clock by default,
+/// a simulated one moved only by `advance_time` once `with_start_time_ms` is set, so a backtest is deterministic.
+#[derive(Debug)]
+pub struct MatchingCore {
+    book: OrderBook,
+}
+
+impl MatchingCore {
+    /// Wraps `book`, keeping its clock and order rules.
+    pub fn new(book: OrderBook) -> Self {
+        Self { book }
+    }
+
+    /// Runs the core on a simulated clock starting at `start_ms`, see `advance_time`.
+    pub fn with_start_time_ms(mut self, start_ms: u64) -> Self {
+        self.book.set_clock_ms(Some(start_ms));
+        self
+    }
+
+    /// Current time of the simulated clock in milliseconds, `None` on the system clock.
+    pub fn now_ms(&self) -> Option<u64> {
+        self.book.clock_ms()
+    }
+
+    /// Moves the simulated clock forward by `ms`, the next results are stamped with the new time.
+    /// On the system clock, the simulated clock starts at zero.
+    pub fn advance_time(&mut self, ms: u64) {
+        let now_ms = self.book.clock_ms().unwrap_or(0);
+        self.book.set_clock_ms(Some(now_ms + ms));
+    }
+
+    /// Processes one order event: a limit or market order matches and may rest, a cancel removes its order.
+    /// Returns:
+    /// - The order as processed and its result, with its trades against the resting orders.
+    pub fn process(&mut self, event: OrderEvent) -> ExecutionReport {
+        self.book.process_order(event)
+    }
+
+    /// Processes one order event, see `process`.
+    /// Returns:
+    /// - The execution reports of the event, use `submit_into` to reuse a buffer in a loop.
+    pub fn submit(&mut self, event: OrderEvent) -> Vec<ExecutionReport> {
+        let mut reports = Vec::with_capacity(1);
+        self.submit_into(event, &mut reports);
+        reports
+    }
+
+    /// Same as `submit`, the reports are appended to `reports` so the caller can reuse its buffer.
+    pub fn submit_into(&mut self, event: OrderEvent, reports: &mut Vec<ExecutionReport>) {
+        reports.push(self.process(event));
+    }
+
+    /// Rejects `event` with `status` without matching it, for the checks made outside the book such as the exposure
+    /// limit of `PositionTracker`. The result is stamped with the clock of the core like the processed ones.
+    pub fn reject(&mut self, event: OrderEvent, status: OrderStatus) -> ExecutionReport {
+        let (event, mut result) = self.book.reject_order(event, status);
+        if let Some(now_ms) = self.book.clock_ms() {
+            result.timestamp_ms = now_ms;
+        }
+        (event, result)
+    }
+
+    /// Cancels the resting order `order_id`.
+    /// Returns:
+    /// - The cancel with the side, price and quantity of the cancelled order, or a `CancelRejected` result when no
+    ///   such order rests in the book.
+    pub fn cancel(&mut self, order_id: OrderId) -> ExecutionReport {
+        let cancel = OrderEvent::builder()
+            .cancel(order_id)
+            .cl_ord_id(order_id)
+            .symbol(SymbolId::from_ascii(self.book.symbol.as_str()))
+            .build();
+        self.process(cancel)
+    }
+
+    /// Empties the book, keeping its symbol, order rules, tape capacity and clock.
+    pub fn reset(&mut self) {
+        let mut book = OrderBook::new(self.book.symbol.as_str())
+            .with_max_orders_per_level(self.book.max_orders_per_level())
+            .with_lot_size(self.book.lot_size())
+            .with_rest_unmet_min_quantity(self.book.rest_unmet_min_quantity())
+            .with_order_capacity(self.book.order_capacity())
+            .with_trade_history(self.book.trade_history_capacity());
+        book.set_clock_ms(self.book.clock_ms());
+        self.book = book;
+    }
+
+    /// Top `levels` price levels of each side.
+    pub fn depth(&self, levels: usize) -> Depth {
+        self.book.depth_snapshot(levels)
+    }
+
+    /// Same as `depth`, written into `depth` so the caller can reuse its buffers.
+    pub fn depth_into(&self, levels: usize, depth: &mut Depth) {
+        self.book
+            .aggregated_depth_into(Side::Buy, levels, &mut depth.bids);
+        self.book
+            .aggregated_depth_into(Side::Sell, levels, &mut depth.asks);
+    }
+
+    /// Execution quality of the trades since the core was created or last reset.
+    pub fn analytics(&self) -> &ExecutionAnalytics {
+        self.book.analytics()
+    }
+
+    /// Checks every price level against its maintained order count and quantity, see `OrderBook::check_invariants`.
+    /// Debug builds already check after each order, a backtest in release calls it when it wants to.
+    pub fn check_invariants(&self) -> Result<(), String> {
+        self.book.check_invariants()
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.book.symbol
+    }
+
+    pub fn order_book(&self) -> &OrderBook {
+        &self.book
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::book::tests::{SYMBOL_STR, limit_order};
+    use types::OrderStatus;
+
+    #[test]
+    fn test_simulated_clock_stamps_results() {
+        let mut core = MatchingCore::new(OrderBook::new(SYMBOL_STR)).with_start_time_ms(1_000);
+        assert_eq!(core.now_ms(), Some(1_000));
+
+        let reports = core.submit(limit_order(Side::Sell, 101.0, 5.0, "ASK1"));
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].1.timestamp_ms, 1_000);
+
+        core.advance_time(250);
+        let (_, result) = core.process(limit_order(Side::Buy, 101.0, 2.0, "BID1"));
+        assert_eq!(result.timestamp_ms, 1_250);
+        assert_eq!(result.trades.len(), 1);
+        assert_eq!(result.trades.iter().next().unwrap().timestamp, 1_250);
+    }
+
+    #[test]
+    fn test_cancel_depth_and_reset() {
+        let mut core = MatchingCore::new(
+            OrderBook::new(SYMBOL_STR).with_lot_size(Some(FixedPointArithmetic::from_f64(1.0))),
+        )
+        .with_start_time_ms(0);
+        let mut reports = Vec::new();
+        core.submit_into(limit_order(Side::Buy, 99.0, 3.0, "BID1"), &mut reports);
+        core.submit_into(limit_order(Side::Buy, 98.0, 4.0, "BID2"), &mut reports);
+        core.submit_into(limit_order(Side::Sell, 101.0, 1.0, "ASK1"), &mut reports);
+        // Checked by the book before matching
+        core.submit_into(limit_order(Side::Sell, 102.0, 1.5, "ASK2"), &mut reports);
+        assert_eq!(reports.len(), 4);
+        assert_eq!(reports[3].1.status, OrderStatus::RejectedInvalidLot);
+
+        let depth = core.depth(1);
+        assert_eq!(
+            depth.bids,
+            vec![(
+                FixedPointArithmetic::from_f64(99.0),
+                FixedPointArithmetic::from_f64(3.0)
+            )]
+        );
+        assert_eq!(
+            depth.asks,
+            vec![(
+                FixedPointArithmetic::from_f64(101.0),
+                FixedPointArithmetic::from_f64(1.0)
+            )]
+        );
+
+        let (cancel, result) = core.cancel(OrderId::from_ascii("BID1"));
+        assert_eq!(result.status, OrderStatus::Cancelled);
+        assert_eq!(cancel.quantity, FixedPointArithmetic::from_f64(3.0));
+        assert_eq!(
+            core.cancel(OrderId::from_ascii("BID1")).1.status,
+            OrderStatus::CancelRejected
+        );
+        assert_eq!(core.depth(5).bids.len(), 1);
+        assert!(core.check_invariants().is_ok());
+
+        core.advance_time(10);
+        core.reset();
+        assert_eq!(core.depth(5), Depth::default());
+        assert_eq!(core.now_ms(), Some(10));
+        assert_eq!(
+            core.order_book().lot_size(),
+            Some(FixedPointArithmetic::from_f64(1.0))
+        );
+        assert_eq!(core.symbol(), SYMBOL_STR);
+    }
+}
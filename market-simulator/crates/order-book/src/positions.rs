@@ -0,0 +1,550 @@
+use crate::matching::{ExecutionReport, MatchingCore};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use types::macros::{EntityId, OrderId, SymbolId};
+use types::{FixedPointArithmetic, Notional, OrderEvent, OrderStatus, OrderType, Side, Trade};
+
+/// Position tracker shared by the order book engines of a market, each engine locks it around every order.
+pub type SharedPositionTracker = Arc<Mutex<PositionTracker>>;
+
+/// Position of a participant in one symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    /// Net filled quantity, positive when long and negative when short.
+    pub net_quantity: FixedPointArithmetic,
+    /// Notional of the fills, buys and sells alike.
+    pub realized_notional: Notional,
+    /// Remaining quantity of the orders of the participant resting in the book.
+    pub open_quantity: FixedPointArithmetic,
+    /// Notional of the orders resting in the book, their remaining quantity at their price.
+    pub open_notional: Notional,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self {
+            net_quantity: FixedPointArithmetic::ZERO,
+            realized_notional: Notional::ZERO,
+            open_quantity: FixedPointArithmetic::ZERO,
+            open_notional: Notional::ZERO,
+        }
+    }
+}
+
+impl Position {
+    /// Gross exposure of the position: the net quantity valued at `mark_price`, plus the resting orders.
+    pub fn gross_exposure(&self, mark_price: FixedPointArithmetic) -> Notional {
+        let net_quantity = FixedPointArithmetic::from_raw(self.net_quantity.raw().abs());
+        mark_price.mul_qty(net_quantity) + self.open_notional
+    }
+
+    fn fill(&mut self, side: Side, price: FixedPointArithmetic, quantity: FixedPointArithmetic) {
+        match side {
+            Side::Buy => self.net_quantity += quantity,
+            Side::Sell => self.net_quantity -= quantity,
+        }
+        self.realized_notional += price.mul_qty(quantity);
+    }
+}
+
+/// Order resting in a book, as far as the tracker is concerned.
+#[derive(Debug, Clone, Copy)]
+struct OpenOrder {
+    sender_id: EntityId,
+    side: Side,
+    /// Price its remaining quantity is valued at.
+    price: FixedPointArithmetic,
+    leaves_quantity: FixedPointArithmetic,
+}
+
+/// Positions and exposure of each participant across all the symbols of a market, updated from the execution reports
+/// of every book.
+///
+/// The gross exposure of a participant sums, over its symbols, the absolute net position valued at the last traded
+/// price of the symbol and the notional of its resting orders. Resting orders are followed through their partial
+/// fills and cancels, the fills against them are attributed to their sender. With a gross limit set, `process` rejects
+/// orders whose notional would take the exposure of their sender past it, even orders reducing a position.
+#[derive(Debug, Default)]
+pub struct PositionTracker {
+    positions: HashMap<EntityId, HashMap<SymbolId, Position>>,
+    open_orders: HashMap<(SymbolId, OrderId), OpenOrder>,
+    last_prices: HashMap<SymbolId, FixedPointArithmetic>,
+    max_gross_exposure: Option<FixedPointArithmetic>,
+}
+
+impl PositionTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the gross exposure each participant may reach, unlimited when `None`.
+    pub fn with_max_gross_exposure(
+        mut self,
+        max_gross_exposure: Option<FixedPointArithmetic>,
+    ) -> Self {
+        self.max_gross_exposure = max_gross_exposure;
+        self
+    }
+
+    pub fn max_gross_exposure(&self) -> Option<FixedPointArithmetic> {
+        self.max_gross_exposure
+    }
+
+    /// Wraps the tracker to share it between the engines of a market.
+    pub fn shared(self) -> SharedPositionTracker {
+        Arc::new(Mutex::new(self))
+    }
+
+    /// Position of `sender_id` in `symbol`, `None` if it never had an order resting or filled there.
+    pub fn position(&self, sender_id: EntityId, symbol: SymbolId) -> Option<Position> {
+        self.positions.get(&sender_id)?.get(&symbol).copied()
+    }
+
+    /// Positions of `sender_id`, sorted by symbol.
+    pub fn positions(&self, sender_id: EntityId) -> Vec<(SymbolId, Position)> {
+        let mut positions: Vec<_> = self
+            .positions
+            .get(&sender_id)
+            .map(|positions| {
+                positions
+                    .iter()
+                    .map(|(symbol, position)| (*symbol, *position))
+                    .collect()
+            })
+            .unwrap_or_default();
+        positions.sort_by_key(|(symbol, _)| symbol.0);
+        positions
+    }
+
+    /// Gross exposure of `sender_id` across all symbols, see `PositionTracker`.
+    pub fn gross_exposure(&self, sender_id: EntityId) -> Notional {
+        let Some(positions) = self.positions.get(&sender_id) else {
+            return Notional::ZERO;
+        };
+        positions
+            .iter()
+            .map(|(symbol, position)| position.gross_exposure(self.last_price(*symbol)))
+            .sum()
+    }
+
+    /// Last traded price of `symbol`, zero before its first trade.
+    pub fn last_price(&self, symbol: SymbolId) -> FixedPointArithmetic {
+        self.last_prices
+            .get(&symbol)
+            .copied()
+            .unwrap_or(FixedPointArithmetic::ZERO)
+    }
+
+    /// Status rejecting `order` before it reaches the book, or `None` if it can be processed.
+    /// Arguments:
+    /// - `order`: The incoming order.
+    /// - `price`: The price its quantity is valued at, its limit price or an estimate for a market order.
+    /// Returns:
+    /// - `OrderStatus::RejectedExposureLimit` when the order would take the gross exposure of its sender past the
+    ///   limit. Cancels are never rejected.
+    pub fn check_order(
+        &self,
+        order: &OrderEvent,
+        price: FixedPointArithmetic,
+    ) -> Option<OrderStatus> {
+        let max_gross_exposure = self.max_gross_exposure?;
+        if order.order_type == OrderType::CancelOrder {
+            return None;
+        }
+
+        let exposure = self.gross_exposure(order.sender_id) + price.mul_qty(order.quantity);
+        let limit = max_gross_exposure.mul_qty(FixedPointArithmetic::from_number(1));
+        (exposure > limit).then_some(OrderStatus::RejectedExposureLimit)
+    }
+
+    /// Checks `event` against the exposure limit, then processes it with `core` or rejects it, and records the
+    /// report. Market orders are valued at the best opposite price, or the last traded price when that side is empty.
+    /// Returns:
+    /// - The execution report of the event, the same `core.process` would return when the order is accepted.
+    pub fn process(&mut self, core: &mut MatchingCore, event: OrderEvent) -> ExecutionReport {
+        let price = match event.order_type {
+            OrderType::MarketOrder => {
+                let book = core.order_book();
+                let best_opposite = match event.side {
+                    Side::Buy => book.get_best_ask(),
+                    Side::Sell => book.get_best_bid(),
+                };
+                best_opposite.map_or(self.last_price(event.symbol), |order| order.price)
+            }
+            _ => event.price,
+        };
+
+        let report = match self.check_order(&event, price) {
+            Some(status) => core.reject(event, status),
+            None => core.process(event),
+        };
+        self.record(&report, core.order_book().fills(&report.1));
+        report
+    }
+
+    /// Updates the positions with an execution report: the fills of the order and of the resting orders it matched,
+    /// its remaining quantity resting in the book, or the order removed by a cancel.
+    /// `fills` are every trade of the order, see `OrderBook::fills`, the result only holds the first few.
+    pub fn record(&mut self, report: &ExecutionReport, fills: &[Trade]) {
+        let (event, result) = report;
+        if event.order_type == OrderType::CancelOrder {
+            if result.status == OrderStatus::Cancelled
+                && let Some(orig_cl_ord_id) = event.orig_cl_ord_id
+            {
+                self.remove_open_order(event.symbol, orig_cl_ord_id);
+            }
+            return;
+        }
+        if result.status.is_rejected() {
+            return;
+        }
+
+        let mut traded_quantity = FixedPointArithmetic::ZERO;
+        for trade in fills {
+            if trade.quantity == FixedPointArithmetic::ZERO {
+                continue;
+            }
+            traded_quantity += trade.quantity;
+            self.last_prices.insert(event.symbol, trade.price);
+            self.position_mut(event.sender_id, event.symbol).fill(
+                event.side,
+                trade.price,
+                trade.quantity,
+            );
+
+            let key = (event.symbol, trade.cl_ord_id);
+            let Some(open_order) = self.open_orders.get_mut(&key) else {
+                continue;
+            };
+            open_order.leaves_quantity -= trade.quantity;
+            let open_order = *open_order;
+            if trade.leaves_qty == FixedPointArithmetic::ZERO {
+                self.open_orders.remove(&key);
+            }
+            let position = self.position_mut(open_order.sender_id, event.symbol);
+            position.fill(open_order.side, trade.price, trade.quantity);
+            position.open_quantity -= trade.quantity;
+            position.open_notional -= open_order.price.mul_qty(trade.quantity);
+        }
+
+        // The rest of a limit order rests in the book. The rest of a market or immediate-or-cancel order is
+        // cancelled, a killed fill-or-kill order traded nothing
+        if event.quantity > traded_quantity
+            && !matches!(
+                event.order_type,
+                OrderType::MarketOrder | OrderType::ImmediateOrCancel | OrderType::FillOrKill
+            )
+        {
+            let leaves_quantity = event.quantity - traded_quantity;
+            self.open_orders.insert(
+                (event.symbol, event.cl_ord_id),
+                OpenOrder {
+                    sender_id: event.sender_id,
+                    side: event.side,
+                    price: event.price,
+                    leaves_quantity,
+                },
+            );
+            let position = self.position_mut(event.sender_id, event.symbol);
+            position.open_quantity += leaves_quantity;
+            position.open_notional += event.price.mul_qty(leaves_quantity);
+        }
+    }
+
+    /// Forgets the resting orders of `symbol`, for a book that was emptied. Positions are kept.
+    pub fn clear_open_orders(&mut self, symbol: SymbolId) {
+        self.open_orders
+            .retain(|(order_symbol, _), _| *order_symbol != symbol);
+        for positions in self.positions.values_mut() {
+            if let Some(position) = positions.get_mut(&symbol) {
+                position.open_quantity = FixedPointArithmetic::ZERO;
+                position.open_notional = Notional::ZERO;
+            }
+        }
+    }
+
+    fn remove_open_order(&mut self, symbol: SymbolId, cl_ord_id: OrderId) {
+        let Some(open_order) = self.open_orders.remove(&(symbol, cl_ord_id)) else {
+            return;
+        };
+        let position = self.position_mut(open_order.sender_id, symbol);
+        position.open_quantity -= open_order.leaves_quantity;
+        position.open_notional -= open_order.price.mul_qty(open_order.leaves_quantity);
+    }
+
+    fn position_mut(&mut self, sender_id: EntityId, symbol: SymbolId) -> &mut Position {
+        self.positions
+            .entry(sender_id)
+            .or_default()
+            .entry(symbol)
+            .or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::book::OrderBook;
+
+    const AAPL: SymbolId = SymbolId::from_ascii("AAPL");
+    const MSFT: SymbolId = SymbolId::from_ascii("MSFT");
+    const ALICE: EntityId = EntityId::from_ascii("ALICE");
+    const BOB: EntityId = EntityId::from_ascii("BOB");
+    const CAROL: EntityId = EntityId::from_ascii("CAROL");
+
+    fn order(
+        sender_id: EntityId,
+        symbol: SymbolId,
+        side: Side,
+        price: f64,
+        quantity: f64,
+        cl_ord_id: &str,
+    ) -> OrderEvent {
+        let builder = OrderEvent::builder()
+            .price_f64(price)
+            .qty(quantity)
+            .cl_ord_id(OrderId::from_ascii(cl_ord_id))
+            .sender_id(sender_id)
+            .symbol(symbol);
+        match side {
+            Side::Buy => builder.build(),
+            Side::Sell => builder.sell().build(),
+        }
+    }
+
+    fn cancel(symbol: SymbolId, cl_ord_id: &str) -> OrderEvent {
+        OrderEvent::builder()
+            .cancel(OrderId::from_ascii(cl_ord_id))
+            .cl_ord_id(OrderId::from_ascii("CXL"))
+            .symbol(symbol)
+            .build()
+    }
+
+    fn qty(quantity: f64) -> FixedPointArithmetic {
+        FixedPointArithmetic::from_f64(quantity)
+    }
+
+    fn notional(value: f64) -> Notional {
+        qty(value).mul_qty(FixedPointArithmetic::from_number(1))
+    }
+
+    #[test]
+    fn test_positions_follow_partial_fills_and_cancels() {
+        let mut tracker = PositionTracker::new();
+        let mut core = MatchingCore::new(OrderBook::new("AAPL"));
+
+        tracker.process(&mut core, order(ALICE, AAPL, Side::Sell, 100.0, 10.0, "A1"));
+        let alice = tracker.position(ALICE, AAPL).unwrap();
+        assert_eq!(alice.open_quantity, qty(10.0));
+        assert_eq!(alice.open_notional, notional(1_000.0));
+        assert_eq!(tracker.gross_exposure(ALICE), notional(1_000.0));
+
+        // Bob partially fills Alice's order
+        tracker.process(&mut core, order(BOB, AAPL, Side::Buy, 100.0, 4.0, "B1"));
+        let alice = tracker.position(ALICE, AAPL).unwrap();
+        assert_eq!(alice.net_quantity, qty(-4.0));
+        assert_eq!(alice.realized_notional, notional(400.0));
+        assert_eq!(alice.open_quantity, qty(6.0));
+        let bob = tracker.position(BOB, AAPL).unwrap();
+        assert_eq!(bob.net_quantity, qty(4.0));
+        assert_eq!(bob.realized_notional, notional(400.0));
+        assert_eq!(bob.open_quantity, FixedPointArithmetic::ZERO);
+        assert_eq!(tracker.last_price(AAPL), qty(100.0));
+        // Short 4 at 100 and 6 resting at 100
+        assert_eq!(tracker.gross_exposure(ALICE), notional(1_000.0));
+
+        let (_, result) = tracker.process(&mut core, cancel(AAPL, "A1"));
+        assert_eq!(result.status, OrderStatus::Cancelled);
+        let alice = tracker.position(ALICE, AAPL).unwrap();
+        assert_eq!(alice.open_quantity, FixedPointArithmetic::ZERO);
+        assert_eq!(alice.open_notional, Notional::ZERO);
+        assert_eq!(alice.net_quantity, qty(-4.0));
+        assert_eq!(tracker.gross_exposure(ALICE), notional(400.0));
+
+        // Bob's bid rests, then a reset of the book forgets it but keeps his position
+        tracker.process(&mut core, order(BOB, AAPL, Side::Buy, 99.0, 2.0, "B2"));
+        assert_eq!(tracker.gross_exposure(BOB), notional(598.0));
+        core.reset();
+        tracker.clear_open_orders(AAPL);
+        assert_eq!(tracker.gross_exposure(BOB), notional(400.0));
+        assert_eq!(
+            tracker.positions(BOB),
+            vec![(AAPL, tracker.position(BOB, AAPL).unwrap())]
+        );
+    }
+
+    #[test]
+    fn test_immediate_or_cancel_leaves_no_open_order() {
+        let mut tracker = PositionTracker::new();
+        let mut core = MatchingCore::new(OrderBook::new("AAPL"));
+        tracker.process(&mut core, order(ALICE, AAPL, Side::Sell, 100.0, 4.0, "A1"));
+
+        let ioc = OrderEvent::builder()
+            .immediate_or_cancel()
+            .price_f64(100.0)
+            .qty(10.0)
+            .cl_ord_id(OrderId::from_ascii("B1"))
+            .sender_id(BOB)
+            .symbol(AAPL)
+            .build();
+        let (_, result) = tracker.process(&mut core, ioc);
+        assert_eq!(result.status, OrderStatus::PartiallyFilled);
+        let bob = tracker.position(BOB, AAPL).unwrap();
+        assert_eq!(bob.net_quantity, qty(4.0));
+        // The 6 not filled are cancelled, they add no exposure
+        assert_eq!(bob.open_quantity, FixedPointArithmetic::ZERO);
+        assert_eq!(bob.open_notional, Notional::ZERO);
+        assert_eq!(tracker.gross_exposure(BOB), notional(400.0));
+    }
+
+    #[test]
+    fn test_killed_fill_or_kill_leaves_no_open_order() {
+        let mut tracker = PositionTracker::new();
+        let mut core = MatchingCore::new(OrderBook::new("AAPL"));
+        tracker.process(&mut core, order(ALICE, AAPL, Side::Sell, 100.0, 4.0, "A1"));
+
+        let fok = OrderEvent::builder()
+            .fill_or_kill()
+            .price_f64(100.0)
+            .qty(10.0)
+            .cl_ord_id(OrderId::from_ascii("B1"))
+            .sender_id(BOB)
+            .symbol(AAPL)
+            .build();
+        let (_, result) = tracker.process(&mut core, fok);
+        assert_eq!(result.status, OrderStatus::Unmatched);
+        // Nothing traded and nothing rests
+        assert!(
+            tracker
+                .position(BOB, AAPL)
+                .is_none_or(|bob| bob.open_quantity == FixedPointArithmetic::ZERO
+                    && bob.net_quantity == FixedPointArithmetic::ZERO)
+        );
+        assert_eq!(tracker.gross_exposure(BOB), Notional::ZERO);
+    }
+
+    #[test]
+    fn test_market_order_beyond_the_asks_leaves_no_open_order() {
+        let mut tracker = PositionTracker::new();
+        let mut core = MatchingCore::new(OrderBook::new("AAPL"));
+        tracker.process(&mut core, order(ALICE, AAPL, Side::Sell, 100.0, 4.0, "A1"));
+
+        let market = OrderEvent::builder()
+            .market()
+            .qty(10.0)
+            .cl_ord_id(OrderId::from_ascii("B1"))
+            .sender_id(BOB)
+            .symbol(AAPL)
+            .build();
+        let (_, result) = tracker.process(&mut core, market);
+        assert_eq!(result.status, OrderStatus::PartiallyFilled);
+        let bob = tracker.position(BOB, AAPL).unwrap();
+        assert_eq!(bob.net_quantity, qty(4.0));
+        // The 6 beyond the asks are cancelled by the book, they add no exposure
+        assert_eq!(bob.open_quantity, FixedPointArithmetic::ZERO);
+        assert_eq!(bob.open_notional, Notional::ZERO);
+        assert_eq!(tracker.gross_exposure(BOB), notional(400.0));
+    }
+
+    #[test]
+    fn test_order_crossing_five_resting_orders() {
+        let mut tracker = PositionTracker::new();
+        let mut core = MatchingCore::new(OrderBook::new("AAPL"));
+        for i in 0..5 {
+            let cl_ord_id = format!("A{i}");
+            tracker.process(
+                &mut core,
+                order(ALICE, AAPL, Side::Sell, 100.0, 2.0, &cl_ord_id),
+            );
+        }
+
+        // Bob fills all five asks, the result only holds four trades
+        let (_, result) =
+            tracker.process(&mut core, order(BOB, AAPL, Side::Buy, 100.0, 10.0, "B1"));
+        assert_eq!(result.trades.len(), 4);
+
+        let bob = tracker.position(BOB, AAPL).unwrap();
+        assert_eq!(bob.net_quantity, qty(10.0));
+        assert_eq!(bob.open_quantity, FixedPointArithmetic::ZERO);
+        assert_eq!(bob.open_notional, Notional::ZERO);
+        assert_eq!(tracker.gross_exposure(BOB), notional(1_000.0));
+        let alice = tracker.position(ALICE, AAPL).unwrap();
+        assert_eq!(alice.net_quantity, qty(-10.0));
+        assert_eq!(alice.open_quantity, FixedPointArithmetic::ZERO);
+        assert_eq!(alice.open_notional, Notional::ZERO);
+        assert_eq!(tracker.gross_exposure(ALICE), notional(1_000.0));
+    }
+
+    #[test]
+    fn test_exposure_limit_across_symbols() {
+        let mut tracker = PositionTracker::new()
+            .with_max_gross_exposure(Some(FixedPointArithmetic::from_f64(2_500.0)));
+        let mut aapl = MatchingCore::new(OrderBook::new("AAPL"));
+        let mut msft = MatchingCore::new(OrderBook::new("MSFT"));
+
+        // Liquidity from Bob on AAPL and Carol on MSFT, 2 000 each
+        tracker.process(&mut aapl, order(BOB, AAPL, Side::Sell, 100.0, 20.0, "B1"));
+        tracker.process(&mut msft, order(CAROL, MSFT, Side::Sell, 50.0, 40.0, "C1"));
+
+        // 1 000 on AAPL, then 500 on MSFT and 500 resting on MSFT: 2 000
+        let (_, result) =
+            tracker.process(&mut aapl, order(ALICE, AAPL, Side::Buy, 100.0, 10.0, "A1"));
+        assert_eq!(result.trades.quantity_sum(), qty(10.0));
+        let (_, result) =
+            tracker.process(&mut msft, order(ALICE, MSFT, Side::Buy, 50.0, 10.0, "A2"));
+        assert_eq!(result.trades.quantity_sum(), qty(10.0));
+        tracker.process(&mut msft, order(ALICE, MSFT, Side::Buy, 25.0, 20.0, "A3"));
+        assert_eq!(tracker.gross_exposure(ALICE), notional(2_000.0));
+
+        // 600 more on AAPL would reach 2 600
+        let (_, result) =
+            tracker.process(&mut aapl, order(ALICE, AAPL, Side::Buy, 100.0, 6.0, "A4"));
+        assert_eq!(result.status, OrderStatus::RejectedExposureLimit);
+        assert_eq!(result.trades.len(), 0);
+        assert_eq!(
+            tracker.position(ALICE, AAPL).unwrap().net_quantity,
+            qty(10.0)
+        );
+        assert_eq!(
+            aapl.order_book().get_best_ask().unwrap().quantity,
+            qty(10.0)
+        );
+
+        // A market order is valued at the best ask, exactly up to the limit
+        let market = OrderEvent::builder()
+            .market()
+            .qty(5.0)
+            .cl_ord_id(OrderId::from_ascii("A5"))
+            .sender_id(ALICE)
+            .symbol(AAPL)
+            .build();
+        let (_, result) = tracker.process(&mut aapl, market);
+        assert_eq!(result.trades.quantity_sum(), qty(5.0));
+        assert_eq!(tracker.gross_exposure(ALICE), notional(2_500.0));
+
+        // Cancelling the resting MSFT bid frees 500 for the next order
+        let (_, result) =
+            tracker.process(&mut msft, order(ALICE, MSFT, Side::Buy, 50.0, 1.0, "A6"));
+        assert_eq!(result.status, OrderStatus::RejectedExposureLimit);
+        let (_, result) = tracker.process(&mut msft, cancel(MSFT, "A3"));
+        assert_eq!(result.status, OrderStatus::Cancelled);
+        let (_, result) =
+            tracker.process(&mut msft, order(ALICE, MSFT, Side::Buy, 50.0, 10.0, "A7"));
+        assert_eq!(result.trades.quantity_sum(), qty(10.0));
+        assert_eq!(tracker.gross_exposure(ALICE), notional(2_500.0));
+
+        // Short positions count as much as long ones, filled or resting
+        assert_eq!(tracker.gross_exposure(BOB), notional(2_000.0));
+        assert_eq!(
+            tracker.position(BOB, AAPL).unwrap().net_quantity,
+            qty(-15.0)
+        );
+        assert_eq!(tracker.gross_exposure(CAROL), notional(2_000.0));
+        let positions = tracker.positions(ALICE);
+        assert_eq!(positions.len(), 2);
+        assert_eq!(positions[0].0, AAPL);
+        assert_eq!(positions[0].1.net_quantity, qty(15.0));
+        assert_eq!(positions[1].0, MSFT);
+        assert_eq!(positions[1].1.net_quantity, qty(20.0));
+    }
+}
@@ -0,0 +1,285 @@
+use crate::book::OrderBook;
+use arc_swap::ArcSwap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use types::{FixedPointArithmetic, OrderResult, Side, Trade};
+
+/// Publishing policy of the read model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadModelConfig {
+    /// Number of price levels per side kept in each snapshot.
+    pub depth: usize,
+    /// A snapshot is published every `every_events` processed events, 0 disables the event trigger.
+    pub every_events: u64,
+    /// A snapshot is published once `every_ms` milliseconds have passed since the last one, 0 disables the time trigger.
+    pub every_ms: u64,
+}
+
+impl Default for ReadModelConfig {
+    fn default() -> Self {
+        Self {
+            depth: 10,
+            every_events: 64,
+            every_ms: 100,
+        }
+    }
+}
+
+/// Immutable view of the order book published by the engine thread.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BookSnapshot {
+    /// Publication sequence number, strictly increasing for a given engine.
+    pub sequence: u64,
+    /// Timestamp of the last event included in the snapshot, in milliseconds since epoch.
+    pub timestamp_ms: u64,
+    /// Top bid levels as (price, total resting quantity), best price first.
+    pub bids: Vec<(FixedPointArithmetic, FixedPointArithmetic)>,
+    /// Top ask levels as (price, total resting quantity), best price first.
+    pub asks: Vec<(FixedPointArithmetic, FixedPointArithmetic)>,
+    /// Last trade executed before the snapshot, if any.
+    pub last_trade: Option<Trade>,
+    /// Number of events processed by the engine.
+    pub events: u64,
+    /// Number of trades executed by the engine.
+    pub trades: u64,
+}
+
+impl BookSnapshot {
+    pub fn best_bid(&self) -> Option<FixedPointArithmetic> {
+        self.bids.first().map(|(price, _)| *price)
+    }
+
+    pub fn best_ask(&self) -> Option<FixedPointArithmetic> {
+        self.asks.first().map(|(price, _)| *price)
+    }
+
+    /// Whether the snapshot describes an uncrossed book: best bid below best ask and both sides sorted best price first.
+    pub fn is_consistent(&self) -> bool {
+        let uncrossed = match (self.best_bid(), self.best_ask()) {
+            (Some(bid), Some(ask)) => bid < ask,
+            _ => true,
+        };
+        uncrossed
+            && self.bids.windows(2).all(|w| w[0].0 > w[1].0)
+            && self.asks.windows(2).all(|w| w[0].0 < w[1].0)
+    }
+}
+
+/// Cloneable handle reading the latest snapshot from any thread, without blocking the engine.
+#[derive(Debug, Clone)]
+pub struct BookSnapshotReader {
+    snapshot_ptr: Arc<ArcSwap<BookSnapshot>>,
+}
+
+impl BookSnapshotReader {
+    pub fn snapshot(&self) -> Arc<BookSnapshot> {
+        self.snapshot_ptr.load_full()
+    }
+}
+
+/// Double-buffered read model owned by the engine thread.
+/// Counters and the last trade are updated in a scratch snapshot on every event, the scratch buffers are
+/// reused and only cloned into a new `Arc` when a snapshot is published.
+pub(crate) struct ReadModel {
+    config: ReadModelConfig,
+    snapshot_ptr: Arc<ArcSwap<BookSnapshot>>,
+    scratch: BookSnapshot,
+    pending_events: u64,
+    last_publish: Instant,
+}
+
+impl ReadModel {
+    pub(crate) fn new(config: ReadModelConfig) -> Self {
+        let scratch = BookSnapshot {
+            bids: Vec::with_capacity(config.depth),
+            asks: Vec::with_capacity(config.depth),
+            ..Default::default()
+        };
+
+        Self {
+            config,
+            snapshot_ptr: Arc::new(ArcSwap::from_pointee(BookSnapshot::default())),
+            scratch,
+            pending_events: 0,
+            last_publish: Instant::now(),
+        }
+    }
+
+    pub(crate) fn reader(&self) -> BookSnapshotReader {
+        BookSnapshotReader {
+            snapshot_ptr: Arc::clone(&self.snapshot_ptr),
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> Arc<BookSnapshot> {
+        self.snapshot_ptr.load_full()
+    }
+
+    /// Accounts for a processed event, publishing a snapshot when one of the triggers is due.
+    pub(crate) fn on_event(&mut self, order_book: &OrderBook, result: &OrderResult) {
+        self.scratch.events += 1;
+        self.scratch.timestamp_ms = result.timestamp_ms;
+        for trade in result.trades.iter() {
+            if trade.quantity == FixedPointArithmetic::ZERO {
+                continue;
+            }
+            self.scratch.trades += 1;
+            self.scratch.last_trade = Some(*trade);
+        }
+
+        self.pending_events += 1;
+        let events_due =
+            self.config.every_events > 0 && self.pending_events >= self.config.every_events;
+        let time_due = self.config.every_ms > 0
+            && self.last_publish.elapsed() >= Duration::from_millis(self.config.every_ms);
+
+        if events_due || time_due {
+            self.publish(order_book);
+        }
+    }
+
+    /// Publishes the current state of `order_book`.
+    pub(crate) fn publish(&mut self, order_book: &OrderBook) {
+        order_book.aggregated_depth_into(Side::Buy, self.config.depth, &mut self.scratch.bids);
+        order_book.aggregated_depth_into(Side::Sell, self.config.depth, &mut self.scratch.asks);
+        self.scratch.sequence += 1;
+
+        self.snapshot_ptr.store(Arc::new(self.scratch.clone()));
+
+        self.pending_events = 0;
+        self.last_publish = Instant::now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{OrderBookEngine, kill_order_book_engine};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use types::OrderEvent;
+    use types::macros::{EntityId, OrderId, SymbolId};
+
+    const SYMBOL_STR: &str = "TEST";
+
+    /// Deterministic stream of limit orders around 100, overlapping enough to trade often.
+    fn scripted_orders(count: usize) -> impl Iterator<Item = OrderEvent> {
+        let mut seed: u64 = 0x9E37_79B9_7F4A_7C15;
+        (0..count).map(move |i| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let side = if (seed >> 33) % 2 == 0 {
+                Side::Buy
+            } else {
+                Side::Sell
+            };
+            let offset = ((seed >> 40) % 6) as f64;
+            let price = match side {
+                Side::Buy => 96.0 + offset,
+                Side::Sell => 99.0 + offset,
+            };
+            OrderEvent::builder()
+                .side(side)
+                .price_f64(price)
+                .qty(1.0 + ((seed >> 50) % 5) as f64)
+                .cl_ord_id(OrderId::from_ascii(&format!("O{i}")))
+                .sender_id(EntityId::from_ascii("SENDER"))
+                .target_id(EntityId::from_ascii("TARGET"))
+                .symbol(SymbolId::from_ascii(SYMBOL_STR))
+                .build()
+        })
+    }
+
+    #[test]
+    fn test_snapshots_consistent_under_concurrent_reads() {
+        const N: usize = 1024;
+        const ORDERS: usize = 20_000;
+
+        let mut inbound_queue = spsc::spsc_lock_free::RingBuffer::<OrderEvent, N>::new();
+        let (inbound_producer, inbound_consumer) = inbound_queue.split();
+        let (_control_tx, control_rx) = crossbeam_channel::unbounded();
+
+        let mut engine = OrderBookEngine::new(
+            inbound_consumer,
+            None,
+            None,
+            None,
+            control_rx,
+            OrderBook::new(SYMBOL_STR),
+            None,
+            Arc::new(AtomicBool::new(false)),
+        );
+        let reader = engine.enable_read_model(ReadModelConfig {
+            depth: 5,
+            every_events: 16,
+            every_ms: 1,
+        });
+        assert_eq!(engine.snapshot().unwrap().sequence, 1);
+
+        let done = AtomicBool::new(false);
+
+        std::thread::scope(|s| {
+            let readers: Vec<_> = (0..2)
+                .map(|_| {
+                    let reader = reader.clone();
+                    let done = &done;
+                    s.spawn(move || {
+                        let mut last_sequence = 0;
+                        let mut last_events = 0;
+                        let mut reads = 0u64;
+                        while !done.load(Ordering::Acquire) {
+                            let snapshot = reader.snapshot();
+                            assert!(snapshot.is_consistent(), "crossed snapshot {snapshot:?}");
+                            assert!(snapshot.sequence >= last_sequence);
+                            assert!(snapshot.events >= last_events);
+                            assert!(snapshot.bids.len() <= 5 && snapshot.asks.len() <= 5);
+                            last_sequence = snapshot.sequence;
+                            last_events = snapshot.events;
+                            reads += 1;
+                        }
+                        reads
+                    })
+                })
+                .collect();
+
+            let engine_handle = s.spawn(move || {
+                engine.run().unwrap();
+                engine
+            });
+
+            for order in scripted_orders(ORDERS) {
+                let mut order = order;
+                while let Err(rejected) = inbound_producer.push(order) {
+                    order = rejected;
+                    std::hint::spin_loop();
+                }
+            }
+            kill_order_book_engine(&inbound_producer);
+
+            let engine = engine_handle.join().unwrap();
+            done.store(true, Ordering::Release);
+            for handle in readers {
+                assert!(handle.join().unwrap() > 0);
+            }
+
+            // The final publication covers the whole run and matches the book left by the engine
+            let last = reader.snapshot();
+            assert_eq!(engine.snapshot().unwrap(), last);
+            assert_eq!(last.events, ORDERS as u64);
+            assert!(last.trades > 0);
+            assert!(last.sequence > 1);
+            assert_eq!(
+                last.last_trade.map(|trade| trade.price),
+                engine.order_book().last_trade_price()
+            );
+            assert_eq!(
+                last.bids,
+                engine.order_book().aggregated_depth(Side::Buy, 5)
+            );
+            assert_eq!(
+                last.asks,
+                engine.order_book().aggregated_depth(Side::Sell, 5)
+            );
+        });
+    }
+}
@@ -0,0 +1,383 @@
+use crate::book::OrderBook;
+use journal::{JournalEntry, JournalRecord};
+use std::collections::HashMap;
+use types::macros::SymbolId;
+use types::{OrderEvent, OrderResult};
+
+/// Rebuilds the order book of `symbol` from a session journal.
+/// Order events of the symbol are processed in journal order, and resets start over from an empty book.
+/// Results recorded in the journal are ignored, they are what the replay reproduces.
+/// `max_orders_per_level` must match the cap the session ran with, or rejected orders would rest in the replayed book.
+/// The book has no lot size and rejects unmet minimum quantities, like a session run without those settings.
+pub fn replay_journal<'r>(
+    symbol: &str,
+    records: impl IntoIterator<Item = &'r JournalRecord>,
+    max_orders_per_level: Option<usize>,
+) -> OrderBook {
+    let symbol_id = SymbolId::from_ascii(symbol);
+    let new_book = || OrderBook::new(symbol).with_max_orders_per_level(max_orders_per_level);
+    let mut order_book = new_book();
+
+    for record in records {
+        match &record.entry {
+            JournalEntry::OrderEvent(event) if event.symbol == symbol_id => {
+                order_book.process_order(*event);
+            }
+            JournalEntry::Reset(reset) if *reset == symbol_id => {
+                order_book = new_book();
+            }
+            _ => {}
+        }
+    }
+
+    order_book
+}
+
+/// Outcome of a journal verification without divergence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerifySummary {
+    /// Order events whose result matched the journaled one.
+    pub verified: usize,
+    /// Order events replayed without a journaled result to compare with, e.g. orders imported from a snapshot.
+    pub unverified: usize,
+}
+
+/// First reprocessed result differing from the journal.
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    /// Index of the journal record holding the order event.
+    pub event_index: usize,
+    /// Index of the journal record holding the expected result.
+    pub result_index: usize,
+    /// The reprocessed order event.
+    pub event: OrderEvent,
+    /// Diverging field, such as `status` or `trades[1].quantity`.
+    pub field: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl std::fmt::Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{}][{}] Divergence at record {} (result record {}): {} expected {}, got {}",
+            self.event.symbol,
+            self.event.cl_ord_id,
+            self.event_index,
+            self.result_index,
+            self.field,
+            self.expected,
+            self.actual
+        )
+    }
+}
+
+impl std::error::Error for Divergence {}
+
+/// Reprocesses every order event of a session journal through fresh order books and compares each result, trades
+/// included, with the journaled one.
+/// The system clock is replaced by the journaled result timestamps, so timestamps are compared too.
+/// Arguments:
+/// - `records`: The journal records, in journal order.
+/// - `max_orders_per_level`: The price level cap the session ran with.
+/// Returns:
+/// - A `VerifySummary` counting the verified events, or the first `Divergence` found.
+pub fn verify_journal(
+    records: &[JournalRecord],
+    max_orders_per_level: Option<usize>,
+) -> Result<VerifySummary, Box<Divergence>> {
+    let result_indexes = pair_results(records);
+    let new_book = |symbol: SymbolId| {
+        OrderBook::new(&symbol.to_string()).with_max_orders_per_level(max_orders_per_level)
+    };
+    let mut books: HashMap<SymbolId, OrderBook> = HashMap::new();
+    let mut summary = VerifySummary {
+        verified: 0,
+        unverified: 0,
+    };
+
+    for (index, record) in records.iter().enumerate() {
+        match &record.entry {
+            JournalEntry::OrderEvent(event) => {
+                let book = books
+                    .entry(event.symbol)
+                    .or_insert_with(|| new_book(event.symbol));
+                let Some(result_index) = result_indexes[index] else {
+                    book.set_clock_ms(None);
+                    book.process_order(*event);
+                    summary.unverified += 1;
+                    continue;
+                };
+                let JournalEntry::OrderResult(expected_event, expected_result) =
+                    &records[result_index].entry
+                else {
+                    unreachable!("events are only paired with results");
+                };
+
+                book.set_clock_ms(Some(expected_result.timestamp_ms));
+                let (actual_event, actual_result) = book.process_order(*event);
+                if let Some((field, expected, actual)) = first_difference(
+                    (expected_event, expected_result),
+                    (&actual_event, &actual_result),
+                ) {
+                    return Err(Box::new(Divergence {
+                        event_index: index,
+                        result_index,
+                        event: *event,
+                        field,
+                        expected,
+                        actual,
+                    }));
+                }
+                summary.verified += 1;
+            }
+            JournalEntry::Reset(symbol) => {
+                books.insert(*symbol, new_book(*symbol));
+            }
+            JournalEntry::FixInbound(_)
+            | JournalEntry::OrderResult(..)
+            | JournalEntry::DroppedResult(..) => {}
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Index of the journaled result of each order event, `None` for other records and events without a result.
+/// An engine records the result of an event before handling the next one, so the result of an event is the first
+/// result of its symbol journaled before the next event or reset of that symbol.
+fn pair_results(records: &[JournalRecord]) -> Vec<Option<usize>> {
+    let mut result_indexes = vec![None; records.len()];
+    let mut pending: HashMap<SymbolId, usize> = HashMap::new();
+
+    for (index, record) in records.iter().enumerate() {
+        match &record.entry {
+            JournalEntry::OrderEvent(event) => {
+                pending.insert(event.symbol, index);
+            }
+            JournalEntry::OrderResult(event, _) => {
+                if let Some(event_index) = pending.remove(&event.symbol) {
+                    result_indexes[event_index] = Some(index);
+                }
+            }
+            JournalEntry::Reset(symbol) => {
+                pending.remove(symbol);
+            }
+            JournalEntry::FixInbound(_) | JournalEntry::DroppedResult(..) => {}
+        }
+    }
+
+    result_indexes
+}
+
+type FieldDifference = (String, String, String);
+
+fn differs<T: PartialEq + std::fmt::Display>(
+    field: &str,
+    expected: T,
+    actual: T,
+) -> Option<FieldDifference> {
+    (expected != actual).then(|| (field.to_string(), expected.to_string(), actual.to_string()))
+}
+
+/// First field differing between the expected and the actual processed event and result, as
+/// `(field, expected, actual)`.
+fn first_difference(
+    (expected_event, expected): (&OrderEvent, &OrderResult),
+    (actual_event, actual): (&OrderEvent, &OrderResult),
+) -> Option<FieldDifference> {
+    differs("side", expected_event.side, actual_event.side)
+        .or_else(|| differs("price", expected_event.price, actual_event.price))
+        .or_else(|| differs("quantity", expected_event.quantity, actual_event.quantity))
+        .or_else(|| {
+            differs(
+                "internal_order_id",
+                expected.internal_order_id,
+                actual.internal_order_id,
+            )
+        })
+        .or_else(|| differs("status", expected.status, actual.status))
+        .or_else(|| differs("timestamp_ms", expected.timestamp_ms, actual.timestamp_ms))
+        .or_else(|| differs("trades", expected.trades.len(), actual.trades.len()))
+        .or_else(|| {
+            expected
+                .trades
+                .iter()
+                .zip(actual.trades.iter())
+                .enumerate()
+                .find_map(|(i, (expected, actual))| {
+                    let field = |name: &str| format!("trades[{i}].{name}");
+                    differs(&field("id"), expected.id, actual.id)
+                        .or_else(|| {
+                            differs(&field("cl_ord_id"), expected.cl_ord_id, actual.cl_ord_id)
+                        })
+                        .or_else(|| differs(&field("price"), expected.price, actual.price))
+                        .or_else(|| differs(&field("quantity"), expected.quantity, actual.quantity))
+                        .or_else(|| {
+                            differs(&field("order_qty"), expected.order_qty, actual.order_qty)
+                        })
+                        .or_else(|| {
+                            differs(&field("leaves_qty"), expected.leaves_qty, actual.leaves_qty)
+                        })
+                        .or_else(|| {
+                            differs(&field("timestamp"), expected.timestamp, actual.timestamp)
+                        })
+                })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{OrderBookEngine, kill_order_book_engine};
+    use config::{JournalBackpressure, JournalConfig, JournalFormat};
+    use std::sync::{Arc, atomic::AtomicBool};
+    use types::macros::{EntityId, OrderId};
+    use types::{FixedPointArithmetic, OrderEvent, OrderType, Side};
+
+    const SYMBOL_STR: &str = "TEST";
+    const SYMBOL_ID: SymbolId = SymbolId::from_ascii(SYMBOL_STR);
+
+    fn order(side: Side, order_type: OrderType, price: f64, quantity: f64, id: &str) -> OrderEvent {
+        OrderEvent::builder()
+            .side(side)
+            .order_type(order_type)
+            .price_f64(price)
+            .qty(quantity)
+            .cl_ord_id(OrderId::from_ascii(id))
+            .sender_id(EntityId::from_ascii("SENDER"))
+            .target_id(EntityId::from_ascii("TARGET"))
+            .symbol(SYMBOL_ID)
+            .build()
+    }
+
+    /// Scripted session: resting orders on both sides, a partial fill, a market sweep and a cancel.
+    fn scripted_session() -> Vec<OrderEvent> {
+        let mut cancel = order(Side::Buy, OrderType::CancelOrder, 98.0, 4.0, "c1");
+        cancel.orig_cl_ord_id = Some(OrderId::from_ascii("b3"));
+
+        vec![
+            order(Side::Buy, OrderType::LimitOrder, 100.0, 5.0, "b1"),
+            order(Side::Buy, OrderType::LimitOrder, 99.0, 3.0, "b2"),
+            order(Side::Buy, OrderType::LimitOrder, 98.0, 4.0, "b3"),
+            order(Side::Sell, OrderType::LimitOrder, 102.0, 2.0, "a1"),
+            order(Side::Sell, OrderType::LimitOrder, 103.0, 6.0, "a2"),
+            order(Side::Sell, OrderType::LimitOrder, 100.0, 2.0, "a3"),
+            order(Side::Buy, OrderType::MarketOrder, 1.0, 4.0, "m1"),
+            cancel,
+            order(Side::Sell, OrderType::LimitOrder, 101.0, 1.5, "a4"),
+        ]
+    }
+
+    /// Runs the scripted session through an engine journaling into `dir`, returns the journal and the final book.
+    fn journal_session(dir: &std::path::Path) -> (Vec<JournalRecord>, String) {
+        const N: usize = 64;
+
+        let _ = std::fs::remove_dir_all(dir);
+        let journal_config = JournalConfig {
+            dir: dir.to_string_lossy().to_string(),
+            format: JournalFormat::Binary,
+            backpressure: JournalBackpressure::Block,
+            capacity: 4,
+            fsync_every: 2,
+            max_file_bytes: 1024,
+            core: 0,
+        };
+        let (tap, journal_handle) = journal::start_journal(&journal_config).unwrap();
+
+        let mut inbound_queue = spsc::spsc_lock_free::RingBuffer::<OrderEvent, N>::new();
+        let (inbound_producer, inbound_consumer) = inbound_queue.split();
+        let (_control_tx, control_rx) = crossbeam_channel::unbounded();
+
+        let mut engine = OrderBookEngine::new(
+            inbound_consumer,
+            None,
+            None,
+            None,
+            control_rx,
+            OrderBook::new(SYMBOL_STR),
+            None,
+            Arc::new(AtomicBool::new(false)),
+        );
+        engine.set_journal(tap);
+
+        for event in &scripted_session() {
+            inbound_producer.push(*event).unwrap();
+        }
+        kill_order_book_engine(&inbound_producer);
+        engine.run().unwrap();
+
+        let book = format!("{:#}", engine.order_book());
+        // Dropping the engine drops the last tap, the writer drains and exits
+        drop(engine);
+        journal_handle.join().unwrap();
+
+        let records = journal::read_journal(dir).unwrap();
+        let _ = std::fs::remove_dir_all(dir);
+        (records, book)
+    }
+
+    #[test]
+    fn test_journal_replays_to_same_book() {
+        let dir = std::env::temp_dir().join(format!("order-book-replay-{}", std::process::id()));
+        let (records, expected) = journal_session(&dir);
+
+        let session = scripted_session();
+        let events = records
+            .iter()
+            .filter(|r| matches!(r.entry, JournalEntry::OrderEvent(_)))
+            .count();
+        let results = records
+            .iter()
+            .filter(|r| matches!(r.entry, JournalEntry::OrderResult(..)))
+            .count();
+        assert_eq!(events, session.len());
+        assert_eq!(results, session.len());
+
+        let replayed = replay_journal(SYMBOL_STR, &records, None);
+        assert_eq!(format!("{:#}", replayed), expected);
+        assert_ne!(expected, format!("{:#}", OrderBook::new(SYMBOL_STR)));
+    }
+
+    #[test]
+    fn test_verify_journal_reports_first_divergence() {
+        let dir = std::env::temp_dir().join(format!("order-book-verify-{}", std::process::id()));
+        let (mut records, _) = journal_session(&dir);
+
+        let summary = verify_journal(&records, None).unwrap();
+        assert_eq!(
+            summary,
+            VerifySummary {
+                verified: scripted_session().len(),
+                unverified: 0,
+            }
+        );
+
+        // Tamper with the second fill of the market order sweeping the asks
+        let m1 = OrderId::from_ascii("m1");
+        let event_index = records
+            .iter()
+            .position(|r| matches!(&r.entry, JournalEntry::OrderEvent(e) if e.cl_ord_id == m1))
+            .unwrap();
+        let result_index = records
+            .iter()
+            .position(|r| matches!(&r.entry, JournalEntry::OrderResult(e, _) if e.cl_ord_id == m1))
+            .unwrap();
+        let JournalEntry::OrderResult(_, result) = &mut records[result_index].entry else {
+            unreachable!();
+        };
+        assert_eq!(result.trades.len(), 2);
+        let journaled_quantity = result.trades[1].quantity;
+        let tampered_quantity = FixedPointArithmetic::from_f64(1.0);
+        result.trades.trades[1].quantity = tampered_quantity;
+
+        let divergence = verify_journal(&records, None).unwrap_err();
+        assert_eq!(divergence.event_index, event_index);
+        assert_eq!(divergence.result_index, result_index);
+        assert_eq!(divergence.event.cl_ord_id, m1);
+        assert_eq!(divergence.field, "trades[1].quantity");
+        assert_eq!(divergence.expected, tampered_quantity.to_string());
+        assert_eq!(divergence.actual, journaled_quantity.to_string());
+    }
+}
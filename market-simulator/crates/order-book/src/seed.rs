@@ -0,0 +1,389 @@
+use crate::book::OrderBook;
+use std::collections::HashMap;
+use std::path::Path;
+use types::macros::{EntityId, OrderId, SymbolId};
+use types::{FixedPointArithmetic, OrderEvent, Side};
+
+/// Columns of a CSV seed file, in any order. The first non-comment line is the header.
+pub const SEED_COLUMNS: [&str; 5] = ["symbol", "side", "price", "quantity", "sender"];
+
+/// Prefix of the client order ids given to seeded orders, followed by their 1-based index in the file.
+pub const SEED_CL_ORD_ID_PREFIX: &str = "SEED";
+
+/// Error reading a book seed file.
+#[derive(Debug)]
+pub enum SeedError {
+    /// The seed file cannot be read.
+    Io(std::io::Error),
+    /// An order of the seed file is invalid.
+    /// `entry` is the line number in a CSV file, the 1-based index in the array of a JSON file.
+    Invalid { entry: usize, message: String },
+}
+
+impl std::fmt::Display for SeedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SeedError::Io(e) => write!(f, "cannot read book seed: {e}"),
+            SeedError::Invalid { entry, message } => {
+                write!(f, "invalid book seed entry {entry}: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SeedError {}
+
+impl From<std::io::Error> for SeedError {
+    fn from(e: std::io::Error) -> Self {
+        SeedError::Io(e)
+    }
+}
+
+/// Fields of one resting order of a seed file, before validation.
+struct SeedEntry<'s> {
+    symbol: &'s str,
+    side: &'s str,
+    price: f64,
+    quantity: f64,
+    sender: &'s str,
+}
+
+/// Reads the resting orders of a seed file, grouped by symbol in file order.
+/// Files ending in `.json` hold an array of `{symbol, side, price, quantity, sender}` objects, any other file is a
+/// comma-separated file with a header naming the `SEED_COLUMNS`, blank lines and `#` comments are skipped.
+/// Every order becomes a limit order with the client order id `SEED<index>`, so the same file always seeds the same book.
+/// Call it once the market name is set, it is the target of the seeded orders.
+/// Arguments:
+/// - `path`: The seed file.
+/// Returns:
+/// - The limit orders to process, per symbol, or the first invalid entry.
+pub fn read_book_seed(path: &Path) -> Result<HashMap<SymbolId, Vec<OrderEvent>>, SeedError> {
+    let content = std::fs::read_to_string(path)?;
+    let is_json = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+    let orders = if is_json {
+        parse_json_seed(&content)?
+    } else {
+        parse_csv_seed(&content)?
+    };
+
+    let mut by_symbol: HashMap<SymbolId, Vec<OrderEvent>> = HashMap::new();
+    for order in orders {
+        by_symbol.entry(order.symbol).or_default().push(order);
+    }
+    Ok(by_symbol)
+}
+
+/// Parses a CSV seed, see `read_book_seed`.
+pub fn parse_csv_seed(content: &str) -> Result<Vec<OrderEvent>, SeedError> {
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+    let Some((header_line, header)) = lines.next() else {
+        return Ok(Vec::new());
+    };
+    let header: Vec<String> = header
+        .split(',')
+        .map(|column| column.trim().to_ascii_lowercase())
+        .collect();
+    let mut positions = [0; SEED_COLUMNS.len()];
+    for (position, column) in positions.iter_mut().zip(SEED_COLUMNS) {
+        *position = header
+            .iter()
+            .position(|name| name == column)
+            .ok_or_else(|| invalid(header_line, format!("missing column `{column}`")))?;
+    }
+
+    let mut orders = Vec::new();
+    for (line_number, line) in lines {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() != header.len() {
+            return Err(invalid(
+                line_number,
+                format!("{} fields, the header has {}", fields.len(), header.len()),
+            ));
+        }
+        let number = |position: usize, column: &str| {
+            fields[position].parse::<f64>().map_err(|_| {
+                invalid(
+                    line_number,
+                    format!("invalid {column} `{}`", fields[position]),
+                )
+            })
+        };
+        let entry = SeedEntry {
+            symbol: fields[positions[0]],
+            side: fields[positions[1]],
+            price: number(positions[2], "price")?,
+            quantity: number(positions[3], "quantity")?,
+            sender: fields[positions[4]],
+        };
+        orders.push(seed_order(line_number, orders.len() + 1, &entry)?);
+    }
+    Ok(orders)
+}
+
+/// Parses a JSON seed, see `read_book_seed`.
+pub fn parse_json_seed(content: &str) -> Result<Vec<OrderEvent>, SeedError> {
+    let value: serde_json::Value =
+        serde_json::from_str(content).map_err(|e| invalid(0, e.to_string()))?;
+    let entries = value
+        .as_array()
+        .ok_or_else(|| invalid(0, "expected an array of orders".to_string()))?;
+
+    let mut orders = Vec::with_capacity(entries.len());
+    for (index, entry) in entries.iter().enumerate() {
+        let entry_number = index + 1;
+        let text = |field: &str| {
+            entry[field]
+                .as_str()
+                .ok_or_else(|| invalid(entry_number, format!("missing or non-string `{field}`")))
+        };
+        let number = |field: &str| {
+            entry[field]
+                .as_f64()
+                .ok_or_else(|| invalid(entry_number, format!("missing or non-numeric `{field}`")))
+        };
+        let entry = SeedEntry {
+            symbol: text("symbol")?,
+            side: text("side")?,
+            price: number("price")?,
+            quantity: number("quantity")?,
+            sender: text("sender")?,
+        };
+        orders.push(seed_order(entry_number, entry_number, &entry)?);
+    }
+    Ok(orders)
+}
+
+/// Builds the limit order of a seed entry, rejecting what could not rest in the book.
+/// Arguments:
+/// - `entry_number`: Line or index of the entry, for the error.
+/// - `index`: 1-based index of the order in the file, for its client order id.
+/// - `entry`: The parsed fields.
+fn seed_order(
+    entry_number: usize,
+    index: usize,
+    entry: &SeedEntry,
+) -> Result<OrderEvent, SeedError> {
+    let symbol = entry.symbol.trim().to_uppercase();
+    if symbol.is_empty() || symbol.len() > 4 {
+        return Err(invalid(
+            entry_number,
+            format!("invalid symbol `{}`", entry.symbol),
+        ));
+    }
+    let side = match entry.side.to_ascii_lowercase().as_str() {
+        "buy" | "b" | "bid" => Side::Buy,
+        "sell" | "s" | "ask" => Side::Sell,
+        _ => {
+            return Err(invalid(
+                entry_number,
+                format!("invalid side `{}`", entry.side),
+            ));
+        }
+    };
+    let price = FixedPointArithmetic::try_from_f64(entry.price)
+        .filter(|price| *price > FixedPointArithmetic::ZERO)
+        .ok_or_else(|| {
+            invalid(
+                entry_number,
+                format!("price {} is not positive", entry.price),
+            )
+        })?;
+    let quantity = FixedPointArithmetic::try_from_f64(entry.quantity)
+        .filter(|quantity| *quantity > FixedPointArithmetic::ZERO)
+        .ok_or_else(|| {
+            invalid(
+                entry_number,
+                format!("quantity {} is not positive", entry.quantity),
+            )
+        })?;
+    // An empty sender marks the end-of-stream sentinel
+    if entry.sender.trim().is_empty() {
+        return Err(invalid(entry_number, "empty sender".to_string()));
+    }
+
+    Ok(OrderEvent::builder()
+        .side(side)
+        .limit()
+        .price(price)
+        .quantity(quantity)
+        .symbol(SymbolId::from_ascii(&symbol))
+        .cl_ord_id(OrderId::from_ascii(&format!(
+            "{SEED_CL_ORD_ID_PREFIX}{index}"
+        )))
+        .sender_id(EntityId::from_ascii(entry.sender.trim()))
+        // Seeded orders are addressed to the market, like the FIX orders routed to it
+        .target_id(EntityId::from_ascii(utils::market_name()))
+        .build())
+}
+
+fn invalid(entry: usize, message: String) -> SeedError {
+    SeedError::Invalid { entry, message }
+}
+
+/// One-line summary of the levels of a freshly seeded book, logged at startup.
+pub fn seed_summary(order_book: &OrderBook, seeded: usize) -> String {
+    let bids = order_book.aggregated_depth(Side::Buy, usize::MAX);
+    let asks = order_book.aggregated_depth(Side::Sell, usize::MAX);
+    let best = |levels: &[(FixedPointArithmetic, FixedPointArithmetic)]| {
+        levels.first().map_or("-".to_string(), |(price, quantity)| {
+            format!("{quantity}@{price}")
+        })
+    };
+    format!(
+        "{seeded} orders seeded: {} bid levels (best {}), {} ask levels (best {})",
+        bids.len(),
+        best(&bids),
+        asks.len(),
+        best(&asks)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::OrderType;
+
+    const SEED_CSV: &str = "\
+# Demo book
+symbol,side,price,quantity,sender
+test,buy,99.5,10,MM1
+
+TEST,sell,100.5,5,MM2
+";
+
+    #[test]
+    fn test_parse_csv_seed() {
+        let orders = parse_csv_seed(SEED_CSV).unwrap();
+        assert_eq!(orders.len(), 2);
+
+        assert_eq!(orders[0].symbol, SymbolId::from_ascii("TEST"));
+        assert_eq!(orders[0].side, Side::Buy);
+        assert_eq!(orders[0].order_type, OrderType::LimitOrder);
+        assert_eq!(orders[0].price, FixedPointArithmetic::from_f64(99.5));
+        assert_eq!(orders[0].quantity, FixedPointArithmetic::from_f64(10.0));
+        assert_eq!(orders[0].sender_id, EntityId::from_ascii("MM1"));
+        assert_eq!(orders[0].cl_ord_id, OrderId::from_ascii("SEED1"));
+
+        assert_eq!(orders[1].side, Side::Sell);
+        assert_eq!(orders[1].cl_ord_id, OrderId::from_ascii("SEED2"));
+        assert!(orders.iter().all(|order| order.check_valid().is_ok()));
+    }
+
+    #[test]
+    fn test_csv_columns_in_any_order() {
+        let orders =
+            parse_csv_seed("sender,quantity,price,side,symbol\nMM1,3,101,s,AAPL\n").unwrap();
+        assert_eq!(orders[0].symbol, SymbolId::from_ascii("AAPL"));
+        assert_eq!(orders[0].side, Side::Sell);
+        assert_eq!(orders[0].price, FixedPointArithmetic::from_f64(101.0));
+        assert_eq!(orders[0].quantity, FixedPointArithmetic::from_f64(3.0));
+    }
+
+    #[test]
+    fn test_invalid_csv_entries_name_their_line() {
+        let entry = |content: &str| match parse_csv_seed(content) {
+            Err(SeedError::Invalid { entry, message }) => (entry, message),
+            other => panic!("expected an invalid entry, got {other:?}"),
+        };
+
+        assert_eq!(entry("symbol,side,price,quantity\n").0, 1);
+        assert_eq!(
+            entry("symbol,side,price,quantity,sender\nTEST,hold,1,1,MM1\n").0,
+            2
+        );
+        let (line, message) =
+            entry("symbol,side,price,quantity,sender\nTEST,buy,1,1,MM1\nTEST,buy,0,1,MM1\n");
+        assert_eq!(line, 3);
+        assert!(message.contains("price"));
+        assert!(
+            entry("symbol,side,price,quantity,sender\nTEST,buy,1,-2,MM1\n")
+                .1
+                .contains("quantity")
+        );
+        assert!(
+            entry("symbol,side,price,quantity,sender\nTEST,buy,abc,1,MM1\n")
+                .1
+                .contains("price")
+        );
+        assert!(
+            entry("symbol,side,price,quantity,sender\nTEST,buy,1,1,\n")
+                .1
+                .contains("sender")
+        );
+        assert!(
+            entry("symbol,side,price,quantity,sender\nTEST,buy,1\n")
+                .1
+                .contains("fields")
+        );
+    }
+
+    #[test]
+    fn test_parse_json_seed() {
+        let orders = parse_json_seed(
+            r#"[
+                { "symbol": "TEST", "side": "sell", "price": 100.5, "quantity": 5, "sender": "MM2" },
+                { "symbol": "TEST", "side": "buy", "price": 99.5, "quantity": 10, "sender": "MM1" }
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[0].side, Side::Sell);
+        assert_eq!(orders[0].cl_ord_id, OrderId::from_ascii("SEED1"));
+        assert_eq!(orders[1].price, FixedPointArithmetic::from_f64(99.5));
+
+        assert!(matches!(
+            parse_json_seed(r#"[{ "symbol": "TEST", "side": "buy", "price": "x" }]"#),
+            Err(SeedError::Invalid { entry: 1, .. })
+        ));
+        assert!(parse_json_seed(r#"{ "symbol": "TEST" }"#).is_err());
+    }
+
+    #[test]
+    fn test_read_book_seed_groups_by_symbol() {
+        let path = std::env::temp_dir().join(format!("book-seed-{}.csv", std::process::id()));
+        std::fs::write(
+            &path,
+            "symbol,side,price,quantity,sender\nAAPL,buy,10,1,MM1\nMSFT,sell,20,1,MM1\nAAPL,sell,11,2,MM2\n",
+        )
+        .unwrap();
+
+        let seed = read_book_seed(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(seed.len(), 2);
+        let aapl = &seed[&SymbolId::from_ascii("AAPL")];
+        assert_eq!(aapl.len(), 2);
+        assert_eq!(aapl[1].cl_ord_id, OrderId::from_ascii("SEED3"));
+        assert_eq!(seed[&SymbolId::from_ascii("MSFT")].len(), 1);
+
+        assert!(matches!(read_book_seed(&path), Err(SeedError::Io(_))));
+    }
+
+    #[test]
+    fn test_seed_summary() {
+        let mut order_book = OrderBook::new("TEST");
+        for order in parse_csv_seed(SEED_CSV).unwrap() {
+            order_book.process_order(order);
+        }
+        assert_eq!(
+            seed_summary(&order_book, 2),
+            format!(
+                "2 orders seeded: 1 bid levels (best {}@{}), 1 ask levels (best {}@{})",
+                FixedPointArithmetic::from_f64(10.0),
+                FixedPointArithmetic::from_f64(99.5),
+                FixedPointArithmetic::from_f64(5.0),
+                FixedPointArithmetic::from_f64(100.5)
+            )
+        );
+        assert_eq!(
+            seed_summary(&OrderBook::new("TEST"), 0),
+            "0 orders seeded: 0 bid levels (best -), 0 ask levels (best -)"
+        );
+    }
+}
@@ -88,24 +88,16 @@ mod tests {
     use super::*;
     use snapshot::types::OrderBookSnapshot;
     use std::sync::atomic::Ordering;
-    use types::{FixedPointArithmetic, OrderEvent, OrderType, Side};
+    use types::{FixedPointArithmetic, OrderEvent};
 
     #[test]
     fn test_send_snapshot() {
-        let order1 = OrderEvent {
-            price: FixedPointArithmetic::from_f64(100.0),
-            quantity: FixedPointArithmetic::from_f64(10.0),
-            side: Side::Buy,
-            order_type: OrderType::LimitOrder,
-            ..Default::default() // Set the timestamp to the current time in milliseconds since epoch
-        };
-        let order2 = OrderEvent {
-            price: FixedPointArithmetic::from_f64(102.0),
-            quantity: FixedPointArithmetic::from_f64(5.0),
-            side: Side::Sell,
-            order_type: OrderType::LimitOrder,
-            ..Default::default() // Set the timestamp to the current time in milliseconds since epoch
-        };
+        let order1 = OrderEvent::builder().price_f64(100.0).qty(10.0).build();
+        let order2 = OrderEvent::builder()
+            .sell()
+            .price_f64(102.0)
+            .qty(5.0)
+            .build();
 
         let mut snapshot = Snapshot {
             timestamp_ms: 1627846267000,
@@ -0,0 +1,91 @@
+//! Regression guard of the matching hot path: once the book is warmed up, `process_order` must not allocate.
+//! A dedicated test binary, so the counting global allocator only sees this test.
+
+mod common;
+
+use common::{SYMBOL, orders};
+use order_book::book::OrderBook;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Global allocator counting the allocations of the current thread while counting is on.
+struct CountingAllocator;
+
+thread_local! {
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn record_allocation() {
+    if COUNTING.get() {
+        ALLOCATIONS.set(ALLOCATIONS.get() + 1);
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record_allocation();
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        record_allocation();
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record_allocation();
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Number of allocations made by `f` on the current thread.
+fn allocations_in(f: impl FnOnce()) -> usize {
+    ALLOCATIONS.set(0);
+    COUNTING.set(true);
+    f();
+    COUNTING.set(false);
+    ALLOCATIONS.get()
+}
+
+const WARM_UP_ORDERS: usize = 2_000;
+const ORDERS: usize = 10_000;
+
+#[test]
+fn test_process_order_does_not_allocate_after_warm_up() {
+    let mut book = OrderBook::new(SYMBOL).with_order_capacity(WARM_UP_ORDERS + ORDERS);
+    book.set_clock_ms(Some(1_700_000_000_000));
+
+    for order in orders(WARM_UP_ORDERS, 0) {
+        book.process_order(order);
+    }
+
+    let orders = orders(ORDERS, WARM_UP_ORDERS);
+    let mut trades = 0;
+    let mut rejected = 0;
+    let allocations = allocations_in(|| {
+        for order in orders {
+            let (_, result) = book.process_order(order);
+            trades += result.trades.len();
+            if result.status.is_rejected() {
+                rejected += 1;
+            }
+        }
+    });
+
+    // The workload exercises matching, resting, cancels and rejections
+    assert!(trades > ORDERS / 2, "only {} trades", trades);
+    assert!(rejected > 0);
+    assert_eq!(
+        allocations, 0,
+        "process_order allocated {} times",
+        allocations
+    );
+}
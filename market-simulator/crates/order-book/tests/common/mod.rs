@@ -0,0 +1,59 @@
+//! Order flow shared by the integration tests of the order book.
+
+use types::OrderEvent;
+use types::macros::{EntityId, OrderId, SymbolId};
+
+/// Symbol of the generated orders.
+pub const SYMBOL: &str = "AAPL";
+/// Distinct prices per side, few enough for each side of the book to fit in a single `BTreeMap` node.
+pub const PRICE_LEVELS: u64 = 8;
+
+/// Order ID from a number, built without formatting so generating the orders allocates nothing either.
+pub fn order_id(n: usize) -> OrderId {
+    let mut id = [0u8; 20];
+    id[..8].copy_from_slice(&(n as u64).to_be_bytes());
+    OrderId(id)
+}
+
+/// Deterministic mix of crossing limit orders, market orders, cancels of earlier orders and rejected orders.
+/// Their IDs start at `first_id`, which also seeds the mix, so a second batch continues the first one.
+pub fn orders(count: usize, first_id: usize) -> Vec<OrderEvent> {
+    let mut seed = 0x2545_f491_4f6c_dd1d_u64 ^ first_id as u64;
+    let mut next = || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    };
+
+    (first_id..first_id + count)
+        .map(|n| {
+            let roll = next();
+            let builder = OrderEvent::builder()
+                .cl_ord_id(order_id(n))
+                .sender_id(EntityId::from_ascii("SENDER"))
+                .target_id(EntityId::from_ascii("TARGET"))
+                .symbol(SymbolId::from_ascii(SYMBOL))
+                .qty(1.0 + (roll % 10) as f64);
+            let builder = if roll % 2 == 0 {
+                builder.buy()
+            } else {
+                builder.sell()
+            };
+            // Buys at 96..104 and sells at 97..105 overlap, so most orders trade
+            let offset = (roll >> 8) % PRICE_LEVELS;
+            let price = match roll % 2 {
+                0 => 96.0 + offset as f64,
+                _ => 97.0 + offset as f64,
+            };
+            match (roll >> 16) % 20 {
+                0 => builder.market().build(),
+                1 => builder
+                    .cancel(order_id(n.saturating_sub(1 + (roll >> 24) as usize % 50)))
+                    .build(),
+                2 => builder.limit().price_f64(price).qty(0.0).build(),
+                _ => builder.limit().price_f64(price).build(),
+            }
+        })
+        .collect()
+}
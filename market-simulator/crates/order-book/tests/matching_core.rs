@@ -0,0 +1,118 @@
+//! The engine is a thin wrapper of `MatchingCore`: the same flow, run through the engine thread and driven directly,
+//! must give the same execution reports and leave the same book behind.
+
+mod common;
+
+use common::{SYMBOL, orders};
+use order_book::book::OrderBook;
+use order_book::engine::{OrderBookEngine, kill_order_book_engine};
+use order_book::{ExecutionReport, MatchingCore};
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::thread;
+use types::OrderEvent;
+
+const CLOCK_MS: u64 = 1_700_000_000_000;
+const ORDERS: usize = 500;
+
+fn book() -> OrderBook {
+    OrderBook::new(SYMBOL).with_max_orders_per_level(Some(16))
+}
+
+/// Runs `orders` through an engine thread, collecting the reports of its execution report subscriber.
+fn run_engine(orders: &[OrderEvent]) -> (Vec<ExecutionReport>, String) {
+    let mut inbound_queue = spsc::spsc_lock_free::RingBuffer::<OrderEvent, 1024>::new();
+    let (inbound_producer, inbound_consumer) = inbound_queue.split();
+    let (report_tx, report_rx) = crossbeam_channel::unbounded();
+    let (_control_tx, control_rx) = crossbeam_channel::unbounded();
+
+    let mut order_book = book();
+    order_book.set_clock_ms(Some(CLOCK_MS));
+    let mut engine = OrderBookEngine::new(
+        inbound_consumer,
+        Some(Arc::new(report_tx)),
+        None,
+        None,
+        control_rx,
+        order_book,
+        None,
+        Arc::new(AtomicBool::new(false)),
+    );
+
+    let book = thread::scope(|s| {
+        let handle = s.spawn(move || {
+            engine.run().unwrap();
+            format!("{:#}", engine.order_book())
+        });
+        for order in orders {
+            while inbound_producer.push(*order).is_err() {
+                thread::yield_now();
+            }
+        }
+        kill_order_book_engine(&inbound_producer);
+        handle.join().unwrap()
+    });
+
+    let reports = report_rx
+        .try_iter()
+        .filter(|(event, _)| !event.is_end_of_stream())
+        .collect();
+    (reports, book)
+}
+
+/// Runs `orders` through a `MatchingCore` on the calling thread.
+fn run_core(orders: &[OrderEvent]) -> (Vec<ExecutionReport>, String) {
+    let mut core = MatchingCore::new(book()).with_start_time_ms(CLOCK_MS);
+    let mut reports = Vec::with_capacity(orders.len());
+    for order in orders {
+        core.submit_into(*order, &mut reports);
+    }
+    assert_eq!(core.check_invariants(), Ok(()));
+    (reports, format!("{:#}", core.order_book()))
+}
+
+#[test]
+fn test_engine_and_matching_core_give_identical_results() {
+    let orders = orders(ORDERS, 0);
+    let (engine_reports, engine_book) = run_engine(&orders);
+    let (core_reports, core_book) = run_core(&orders);
+
+    assert_eq!(engine_reports.len(), ORDERS);
+    assert_eq!(core_reports.len(), ORDERS);
+    for (n, (engine, core)) in engine_reports.iter().zip(&core_reports).enumerate() {
+        assert_eq!(engine.1, core.1, "result of order {} differs", n);
+        assert_eq!(
+            format!("{:?}", engine.0),
+            format!("{:?}", core.0),
+            "order {} differs",
+            n
+        );
+    }
+    assert_eq!(engine_book, core_book);
+
+    // The flow exercises matching, resting, cancels and rejections
+    assert!(
+        core_reports
+            .iter()
+            .any(|(_, result)| result.trades.len() > 0)
+    );
+    assert!(
+        core_reports
+            .iter()
+            .any(|(_, result)| result.status.is_rejected())
+    );
+    assert!(
+        core_reports
+            .iter()
+            .any(|(_, result)| result.status == types::OrderStatus::Cancelled)
+    );
+}
+
+#[test]
+fn test_matching_core_is_deterministic() {
+    let orders = orders(ORDERS, 0);
+    let (first, first_book) = run_core(&orders);
+    let (second, second_book) = run_core(&orders);
+    assert!(first.iter().zip(&second).all(|(a, b)| a.1 == b.1));
+    assert_eq!(first_book, second_book);
+}
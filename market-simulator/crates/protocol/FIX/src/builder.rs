@@ -0,0 +1,257 @@
+use crate::parser::{EQUALS, SOH};
+use crate::tags::{msg_types, side_code_set, tags};
+use types::{FixedPointArithmetic, Side};
+use utils::{UtcTimestamp, field_str, number_to_bytes};
+
+/// Byte-level FIX message encoder.
+///
+/// The builder writes the standard header (BeginString, BodyLength, MsgType,
+/// SenderCompID, TargetCompID) and trailer (CheckSum) itself. BodyLength is
+/// computed over every byte between the BodyLength field and the CheckSum field,
+/// and CheckSum is the sum of all preceding bytes modulo 256, zero-padded to 3 digits.
+/// Body fields are written in the order the setters are called.
+#[derive(Debug, Clone)]
+pub struct FixMessageBuilder {
+    begin_string: Vec<u8>,
+    msg_type: Vec<u8>,
+    header: Vec<u8>,
+    body: Vec<u8>,
+}
+
+impl FixMessageBuilder {
+    /// Create a NewOrderSingle builder, use [`FixMessageBuilder::msg_type`] for other message types.
+    pub fn new(version: &[u8], sender: &[u8], target: &[u8]) -> Self {
+        let mut header = Vec::with_capacity(64);
+        write_field(&mut header, tags::SENDER_COMP_ID, sender);
+        write_field(&mut header, tags::TARGET_COMP_ID, target);
+
+        Self {
+            begin_string: version.to_vec(),
+            msg_type: msg_types::NEW_ORDER_SINGLE.to_vec(),
+            header,
+            body: Vec::with_capacity(256),
+        }
+    }
+
+    pub fn msg_type(mut self, msg_type: &[u8]) -> Self {
+        self.msg_type = msg_type.to_vec();
+        self
+    }
+
+    pub fn msg_seq_num(mut self, seq_num: u64) -> Self {
+        write_field(&mut self.header, tags::MSG_SEQ_NUM, &number_to_bytes(seq_num));
+        self
+    }
+
+    pub fn sending_time(mut self, timestamp: UtcTimestamp) -> Self {
+        write_field(
+            &mut self.header,
+            tags::SENDING_TIME,
+            field_str(&timestamp.to_fix_bytes()),
+        );
+        self
+    }
+
+    pub fn cl_ord_id(self, cl_ord_id: &[u8]) -> Self {
+        self.raw_tag(tags::CL_ORD_ID, cl_ord_id)
+    }
+
+    pub fn orig_cl_ord_id(self, orig_cl_ord_id: &[u8]) -> Self {
+        self.raw_tag(tags::ORIG_CL_ORD_ID, orig_cl_ord_id)
+    }
+
+    pub fn symbol(self, symbol: &[u8]) -> Self {
+        self.raw_tag(tags::SYMBOL, symbol)
+    }
+
+    pub fn side(self, side: Side) -> Self {
+        let code = match side {
+            Side::Buy => side_code_set::BUY,
+            Side::Sell => side_code_set::SELL,
+        };
+        self.raw_tag(tags::SIDE, code)
+    }
+
+    pub fn order_qty(self, qty: FixedPointArithmetic) -> Self {
+        self.raw_tag(tags::ORDER_QTY, field_str(&qty.to_fix_bytes()))
+    }
+
+    pub fn price(self, price: FixedPointArithmetic) -> Self {
+        self.raw_tag(tags::PRICE, field_str(&price.to_fix_bytes()))
+    }
+
+    /// Set OrdType (40), see [`crate::tags::ord_type_code_set`].
+    pub fn ord_type(self, ord_type: &[u8]) -> Self {
+        self.raw_tag(tags::ORD_TYPE, ord_type)
+    }
+
+    /// Set TimeInForce (59), see [`crate::tags::time_in_force_code_set`].
+    pub fn time_in_force(self, time_in_force: &[u8]) -> Self {
+        self.raw_tag(tags::TIME_IN_FORCE, time_in_force)
+    }
+
+    pub fn transact_time(self, timestamp: UtcTimestamp) -> Self {
+        self.raw_tag(tags::TRANSACT_TIME, field_str(&timestamp.to_fix_bytes()))
+    }
+
+    /// Append an arbitrary `tag=value` field to the message body.
+    /// Tags written by the builder itself (8, 9, 10, 35) are ignored.
+    pub fn raw_tag(mut self, tag: u32, value: &[u8]) -> Self {
+        if matches!(
+            tag,
+            tags::BEGIN_STRING | tags::BODY_LENGTH | tags::CHECKSUM | tags::MSG_TYPE
+        ) {
+            return self;
+        }
+        write_field(&mut self.body, tag, value);
+        self
+    }
+
+    /// Append several `(tag, value)` pairs at once, preserving their order.
+    pub fn raw_tags<'v, I>(self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = (u32, &'v [u8])>,
+    {
+        fields
+            .into_iter()
+            .fold(self, |builder, (tag, value)| builder.raw_tag(tag, value))
+    }
+
+    /// Assemble the final message: `8=..|9=..|35=..|<header>|<body>|10=NNN|`.
+    pub fn build(&self) -> Vec<u8> {
+        let body_len = tag_len(tags::MSG_TYPE, &self.msg_type) + self.header.len() + self.body.len();
+        let mut msg = Vec::with_capacity(body_len + self.begin_string.len() + 24);
+
+        write_field(&mut msg, tags::BEGIN_STRING, &self.begin_string);
+        write_field(&mut msg, tags::BODY_LENGTH, &number_to_bytes(body_len as u64));
+        write_field(&mut msg, tags::MSG_TYPE, &self.msg_type);
+        msg.extend_from_slice(&self.header);
+        msg.extend_from_slice(&self.body);
+
+        let checksum = checksum(&msg);
+        write_field(&mut msg, tags::CHECKSUM, &checksum_to_bytes(checksum));
+
+        msg
+    }
+}
+
+#[inline(always)]
+fn write_field(buf: &mut Vec<u8>, tag: u32, value: &[u8]) {
+    buf.extend_from_slice(&number_to_bytes(tag));
+    buf.push(EQUALS);
+    buf.extend_from_slice(value);
+    buf.push(SOH);
+}
+
+/// Encoded length of a `tag=value|` field.
+#[inline(always)]
+fn tag_len(tag: u32, value: &[u8]) -> usize {
+    number_to_bytes(tag).len() + value.len() + 2
+}
+
+/// Compute the FIX checksum (sum of bytes modulo 256) over `bytes`.
+#[inline(always)]
+pub fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |acc, &b| acc.wrapping_add(b))
+}
+
+/// Format a checksum as the 3-digit, zero-padded ASCII value expected in tag 10.
+#[inline(always)]
+pub fn checksum_to_bytes(checksum: u8) -> [u8; 3] {
+    [
+        b'0' + checksum / 100,
+        b'0' + (checksum / 10) % 10,
+        b'0' + checksum % 10,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::FixParser;
+    use crate::tags::{ord_type_code_set, time_in_force_code_set};
+
+    fn timestamp() -> UtcTimestamp {
+        UtcTimestamp::from_fix_bytes(b"20240219-12:30:00.123").unwrap()
+    }
+
+    #[test]
+    fn test_build_new_order_single_round_trip() {
+        let msg = FixMessageBuilder::new(b"FIX.4.4", b"SENDER", b"TARGET")
+            .msg_seq_num(1)
+            .sending_time(timestamp())
+            .cl_ord_id(b"12345")
+            .symbol(b"EURUSD")
+            .side(Side::Buy)
+            .order_qty(FixedPointArithmetic::from_number(100))
+            .price(FixedPointArithmetic::from_f64(1.23456))
+            .ord_type(ord_type_code_set::LIMIT)
+            .time_in_force(time_in_force_code_set::DAY)
+            .transact_time(timestamp())
+            .build();
+
+        let expected: [(u32, &[u8]); 15] = [
+            (tags::BEGIN_STRING, b"FIX.4.4"),
+            (tags::BODY_LENGTH, b"144"),
+            (tags::MSG_TYPE, b"D"),
+            (tags::SENDER_COMP_ID, b"SENDER"),
+            (tags::TARGET_COMP_ID, b"TARGET"),
+            (tags::MSG_SEQ_NUM, b"1"),
+            (tags::SENDING_TIME, b"20240219-12:30:00.123"),
+            (tags::CL_ORD_ID, b"12345"),
+            (tags::SYMBOL, b"EURUSD"),
+            (tags::SIDE, b"1"),
+            (tags::ORDER_QTY, b"100.00000000"),
+            (tags::PRICE, b"1.23456000"),
+            (tags::ORD_TYPE, b"2"),
+            (tags::TIME_IN_FORCE, b"0"),
+            (tags::TRANSACT_TIME, b"20240219-12:30:00.123"),
+        ];
+
+        let mut parser = FixParser::new(&msg);
+        for (tag, value) in expected {
+            let field = parser.next_field_scalar().unwrap();
+            assert_eq!(field.tag, tag);
+            assert_eq!(field.value, value);
+        }
+
+        // Body length covers everything between the BodyLength field and the CheckSum field
+        let body_start = msg.windows(4).position(|w| w == b"\x0135=").unwrap() + 1;
+        let checksum_start = msg.len() - b"10=000\x01".len();
+        assert_eq!(checksum_start - body_start, 144);
+
+        let field = parser.next_field_scalar().unwrap();
+        assert_eq!(field.tag, tags::CHECKSUM);
+        assert_eq!(field.value, checksum_to_bytes(checksum(&msg[..checksum_start])));
+        assert!(parser.next_field_scalar().is_none());
+
+        // SIMD parser agrees with the scalar one
+        let fields = FixParser::new(&msg).get_fields().fields;
+        assert_eq!(fields.len(), 16);
+        assert_eq!(fields[11].tag, tags::PRICE);
+        assert_eq!(fields[11].value, b"1.23456000");
+    }
+
+    #[test]
+    fn test_raw_tag_escape_hatch() {
+        let msg = FixMessageBuilder::new(b"FIX.4.2", b"S", b"T")
+            .msg_type(msg_types::HEARTBEAT)
+            .raw_tag(tags::BODY_LENGTH, b"9999")
+            .raw_tag(tags::MSG_TYPE, b"Z")
+            .raw_tag(tags::CHECKSUM, b"000")
+            .raw_tags([(tags::TEST_REQ_ID, &b"PING"[..]), (9999, &b"x"[..])])
+            .build();
+
+        assert!(msg.starts_with(b"8=FIX.4.2\x019=31\x0135=0\x0149=S\x0156=T\x01112=PING\x019999=x\x0110="));
+        let fields = FixParser::new(&msg).get_fields().fields;
+        assert_eq!(fields.len(), 8);
+    }
+
+    #[test]
+    fn test_checksum_to_bytes() {
+        assert_eq!(&checksum_to_bytes(0), b"000");
+        assert_eq!(&checksum_to_bytes(7), b"007");
+        assert_eq!(&checksum_to_bytes(42), b"042");
+        assert_eq!(&checksum_to_bytes(255), b"255");
+    }
+}
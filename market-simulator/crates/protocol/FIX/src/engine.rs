@@ -1,18 +1,23 @@
 use std::collections::HashMap;
 use std::sync::atomic::AtomicBool;
 
+use crate::market_data::{
+    MarketDataConfig, MarketDataReply, MarketDataRequest, MarketDataService,
+    is_market_data_request, to_session_message,
+};
 use crate::tags::{msg_types, side_code_set, tags};
 use crossbeam::queue::ArrayQueue;
+use journal::JournalTap;
 use serde::Serialize;
 use spsc::spsc_lock_free::{Consumer, Producer};
 use std::cell::UnsafeCell;
-use std::sync::Arc;
 use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 use tokio::sync::mpsc;
 use types::{
     ExecutionReportMessage, FixedPointArithmetic, OrderEvent, Side,
-    macros::{EntityId, OrderId},
+    macros::{EntityId, OrderId, SymbolId},
 };
 use utils::market_name;
 
@@ -108,6 +113,7 @@ pub struct FixEngine<'a, const N: usize> {
     shutdown: Arc<AtomicBool>,
     pending: Arc<FixPendingConnection<N>>, // Shared state for pending response queues, used
     metrics: Arc<types::MarketMetrics>,
+    market_data: Option<MarketDataService>,
 }
 
 /// The data struct which will be shared between the inbound and outbound engines, containing the pending response queues for each order event, and a shutdown flag to signal when the engine should stop. This allows the inbound and outbound engines to communicate with each other without needing to share the entire engine struct, which can help reduce contention and improve performance.
@@ -115,6 +121,8 @@ struct FixShared<const N: usize> {
     shutdown: Arc<AtomicBool>,
     pending: Arc<FixPendingConnection<N>>,
     metrics: Arc<types::MarketMetrics>,
+    // Locked by the inbound engine on MarketDataRequests, and by the outbound engine (try_lock) when idle
+    market_data: Option<Mutex<MarketDataService>>,
 }
 
 #[derive(Clone)]
@@ -167,6 +175,53 @@ impl<const N: usize> FixShared<N> {
         self.pending.locked.store(false, Ordering::Release);
         removed
     }
+
+    /// Sends market data messages through the pending route of their session.
+    /// Sessions whose channel is closed are unsubscribed. Returns the number of messages sent.
+    fn send_market_data(
+        &self,
+        service: &mut MarketDataService,
+        replies: Vec<MarketDataReply>,
+    ) -> usize {
+        let mut sent = 0;
+        for reply in replies {
+            let Some(route) = self.get_pending(&reply.session) else {
+                continue;
+            };
+
+            let Some(message) = to_session_message::<N>(&reply.message) else {
+                tracing::error!(
+                    "[{}] Market data message of {} bytes exceeds the {} bytes buffer, dropping it",
+                    market_name(),
+                    reply.message.len(),
+                    N
+                );
+                continue;
+            };
+
+            match route.resp_queue.try_send(message) {
+                Ok(_) => sent += 1,
+                Err(tokio::sync::mpsc::error::TrySendError::Closed(_)) => {
+                    self.remove_pending(&reply.session);
+                    service.remove_session(&reply.session);
+                    tracing::warn!(
+                        "[{}] Client response channel closed, removing market data subscriptions",
+                        market_name()
+                    );
+                }
+                Err(tokio::sync::mpsc::error::TrySendError::Full(_)) => {
+                    self.metrics
+                        .fix_response_dropped
+                        .fetch_add(1, Ordering::Relaxed);
+                    tracing::error!(
+                        "[{}] Client response channel full, dropping market data message",
+                        market_name()
+                    );
+                }
+            }
+        }
+        sent
+    }
 }
 
 pub struct FixInboundEngine<'a, const N: usize> {
@@ -174,9 +229,15 @@ pub struct FixInboundEngine<'a, const N: usize> {
     request_out: Producer<'a, OrderEvent, N>,
     counter: usize,
     shared: Arc<FixShared<N>>,
+    journal: Option<JournalTap>,
 }
 
 impl<'a, const N: usize> FixInboundEngine<'a, N> {
+    /// Journals every inbound FIX message, before it is parsed.
+    pub fn set_journal(&mut self, journal: JournalTap) {
+        self.journal = Some(journal);
+    }
+
     // Blocking wait for new inbound FIX messages. Shutdown is signaled via a
     // sentinel message (len == 0) or by disconnecting the input channel.
     pub fn run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
@@ -194,6 +255,10 @@ impl<'a, const N: usize> FixInboundEngine<'a, N> {
                 break;
             }
 
+            if let Some(journal) = &self.journal {
+                journal.record_fix_inbound(&msg.data[..msg.len as usize]);
+            }
+
             self.shared
                 .metrics
                 .fix_requests
@@ -201,6 +266,14 @@ impl<'a, const N: usize> FixInboundEngine<'a, N> {
             let resp_queue = msg.resp_queue.take(); // Take ownership of the response queue if provided, so we can use it later when sending responses back to the client
             let received_at = Instant::now();
 
+            // MarketDataRequests are answered here, they never reach the order book
+            if self.shared.market_data.is_some()
+                && is_market_data_request(&msg.data[..msg.len as usize])
+            {
+                self.handle_market_data_request(&msg, resp_queue, received_at);
+                continue;
+            }
+
             let order_event = match self.build_order(msg) {
                 Ok(event) => event,
                 Err(e) => {
@@ -270,6 +343,51 @@ impl<'a, const N: usize> FixInboundEngine<'a, N> {
         Ok(())
     }
 
+    fn handle_market_data_request(
+        &mut self,
+        msg: &FixRawMsg<N>,
+        resp_queue: Option<mpsc::Sender<ExecutionReportMessage<N>>>,
+        received_at: Instant,
+    ) {
+        let mut parser = crate::parser::FixParser::new(&msg.data[..msg.len as usize]);
+        let request = match MarketDataRequest::parse(&parser.get_fields().fields) {
+            Ok(request) => request,
+            Err(e) => {
+                tracing::error!(
+                    "[{}] Failed to parse MarketDataRequest: {}, skipping",
+                    market_name(),
+                    e
+                );
+                return;
+            }
+        };
+
+        self.counter += 1;
+        if let Some(resp_queue) = resp_queue {
+            self.shared.update_pending(
+                request.sender_id,
+                FixPendingRoute {
+                    resp_queue,
+                    received_at,
+                },
+            );
+        }
+
+        let Some(market_data) = &self.shared.market_data else {
+            return;
+        };
+        let mut service = market_data.lock().unwrap();
+        let replies = service.handle_request(&request, received_at);
+        let sent = self.shared.send_market_data(&mut service, replies);
+        tracing::debug!(
+            "[{}] MarketDataRequest {:?} from {}: {} message(s) sent",
+            market_name(),
+            request.request_type,
+            request.sender_id,
+            sent
+        );
+    }
+
     fn build_order(&self, msg: FixRawMsg<N>) -> Result<OrderEvent, &'static str> {
         let mut order_event = OrderEvent::default();
 
@@ -326,6 +444,13 @@ impl<'a, const N: usize> FixInboundEngine<'a, N> {
                         return Err("Invalid quantity format"); // Invalid quantity format
                     }
                 }
+                tags::MIN_QTY => {
+                    if let Some(min_qty) = FixedPointArithmetic::from_fix_bytes(field.value) {
+                        order_event.min_quantity = min_qty;
+                    } else {
+                        return Err("Invalid minimum quantity format"); // Invalid minimum quantity format
+                    }
+                }
                 tags::SENDING_TIME => {
                     if let Some(timestamp) = utils::UtcTimestamp::from_fix_bytes(field.value) {
                         order_event.timestamp_ms = timestamp.to_unix_ms();
@@ -403,6 +528,7 @@ impl<'a, const N: usize> FixOutboundEngine<'a, N> {
                 if self.shared.shutdown.load(Ordering::Acquire) {
                     break;
                 }
+                self.poll_market_data();
                 std::hint::spin_loop();
                 continue;
             }
@@ -429,6 +555,21 @@ impl<'a, const N: usize> FixOutboundEngine<'a, N> {
         );
         Ok(())
     }
+
+    /// Sends the incremental refreshes due, skipped while the inbound engine holds the subscriptions.
+    fn poll_market_data(&mut self) {
+        let Some(market_data) = &self.shared.market_data else {
+            return;
+        };
+        let Ok(mut service) = market_data.try_lock() else {
+            return;
+        };
+
+        let replies = service.poll(Instant::now());
+        if !replies.is_empty() {
+            self.counter += self.shared.send_market_data(&mut service, replies);
+        }
+    }
 }
 
 struct FixPendingConnection<const N: usize> {
@@ -457,14 +598,29 @@ impl<'a, const N: usize> FixEngine<'a, N> {
                 pending: UnsafeCell::new(HashMap::new()),
             }),
             metrics,
+            market_data: None,
         }
     }
 
+    /// Answers MarketDataRequests (35=V) with a snapshot, then incremental refreshes.
+    /// Without it, MarketDataRequests are rejected as unsupported messages.
+    /// Arguments:
+    /// - `config`: Market data settings.
+    /// - `books`: Read model of each order book served, see `OrderBookEngine::enable_read_model`.
+    pub fn enable_market_data(
+        &mut self,
+        config: MarketDataConfig,
+        books: HashMap<SymbolId, order_book::read_model::BookSnapshotReader>,
+    ) {
+        self.market_data = Some(MarketDataService::new(config, books));
+    }
+
     pub fn split(self) -> (FixInboundEngine<'a, N>, FixOutboundEngine<'a, N>) {
         let shared = Arc::new(FixShared {
             shutdown: Arc::clone(&self.shutdown),
             pending: Arc::clone(&self.pending),
             metrics: Arc::clone(&self.metrics),
+            market_data: self.market_data.map(Mutex::new),
         });
 
         let inbound = FixInboundEngine {
@@ -472,6 +628,7 @@ impl<'a, const N: usize> FixEngine<'a, N> {
             request_out: self.request_out,
             shared: Arc::clone(&shared),
             counter: 0,
+            journal: None,
         };
 
         let outbound = FixOutboundEngine {
@@ -487,6 +644,7 @@ impl<'a, const N: usize> FixEngine<'a, N> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::builder::FixMessageBuilder;
     use spsc::spsc_lock_free::RingBuffer;
     use utils::field_str;
 
@@ -524,17 +682,17 @@ mod tests {
                 let _ = outbound_engine.run();
             });
 
-            let fix_message = b"8=FIX.4.4\x019=0000\x0135=D\x0149=SENDER\x0156=TARGET\x0134=1\x0152=20240219-12:30:00.000\x0111=12345\x0154=1\x0138=1000000\x0144=1.23456\x0155=EURUSD\x0110=123\x01";
+            let fix_message = FixMessageBuilder::new(b"FIX.4.4", b"SENDER", b"TARGET")
+                .msg_seq_num(1)
+                .sending_time(utils::UtcTimestamp::from_fix_bytes(b"20240219-12:30:00.000").unwrap())
+                .cl_ord_id(b"12345")
+                .side(Side::Buy)
+                .order_qty(FixedPointArithmetic::from_number(1000000))
+                .price(FixedPointArithmetic::from_f64(1.23456))
+                .symbol(b"EURUSD")
+                .build();
 
-            let raw_msg = FixRawMsg {
-                len: fix_message.len() as u16,
-                data: {
-                    let mut data = [0u8; 1024];
-                    data[..fix_message.len()].copy_from_slice(fix_message);
-                    data
-                },
-                resp_queue: None, // Not using the response queue in this test, but could be set here if needed for future tests
-            };
+            let raw_msg = FixRawMsg::<1024>::new(&fix_message, None); // Not using the response queue in this test, but could be set here if needed for future tests
 
             net_to_fix_tx.send(raw_msg).expect("Failed to push message");
 
@@ -568,6 +726,73 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_fix_engine_skips_non_positive_quantity_and_price() {
+        let (net_to_fix_tx, net_to_fix_rx) = crossbeam_channel::bounded::<FixRawMsg<1024>>(1024);
+        let mut fix_to_ob = RingBuffer::<OrderEvent, 1024>::new();
+        let mut er_to_fix = RingBuffer::<(EntityId, ExecutionReportMessage<1024>), 1024>::new();
+
+        std::thread::scope(|scope| {
+            let shutdown = Arc::new(AtomicBool::new(false));
+            let metrics = Arc::new(types::MarketMetrics::new());
+            let (fix_to_ob_tx, fix_to_ob_rx) = fix_to_ob.split();
+            let (er_to_fix_tx, er_to_fix_rx) = er_to_fix.split();
+
+            let (mut inbound_engine, mut outbound_engine) = FixEngine::new(
+                Arc::new(net_to_fix_rx),
+                fix_to_ob_tx,
+                er_to_fix_rx,
+                Arc::clone(&shutdown),
+                Arc::clone(&metrics),
+            )
+            .split();
+            let inbound_handle = scope.spawn(move || {
+                let _ = inbound_engine.run();
+            });
+            let outbound_handle = scope.spawn(move || {
+                let _ = outbound_engine.run();
+            });
+
+            let orders = [
+                (b"1".as_slice(), 0.0, 1.5),
+                (b"2".as_slice(), -10.0, 1.5),
+                (b"3".as_slice(), 10.0, 0.0),
+                (b"4".as_slice(), 10.0, -1.5),
+                (b"5".as_slice(), 10.0, 1.5),
+            ];
+            for (cl_ord_id, quantity, price) in orders {
+                let fix_message = FixMessageBuilder::new(b"FIX.4.4", b"SENDER", b"TARGET")
+                    .msg_seq_num(1)
+                    .sending_time(utils::UtcTimestamp::from_fix_bytes(b"20240219-12:30:00.000").unwrap())
+                    .cl_ord_id(cl_ord_id)
+                    .side(Side::Buy)
+                    .order_qty(FixedPointArithmetic::from_f64(quantity))
+                    .price(FixedPointArithmetic::from_f64(price))
+                    .symbol(b"EURUSD")
+                    .build();
+                net_to_fix_tx
+                    .send(FixRawMsg::<1024>::new(&fix_message, None))
+                    .expect("Failed to push message");
+            }
+
+            std::thread::sleep(std::time::Duration::from_millis(100)); // Give the engine some time to process
+            // Only the valid order reaches the order book
+            assert_eq!(fix_to_ob_rx.len(), 1);
+            let order_event = fix_to_ob_rx.pop().unwrap();
+            assert_eq!(field_str(order_event.cl_ord_id.as_ref()), b"5");
+
+            shutdown.store(true, std::sync::atomic::Ordering::Release);
+            kill_fix_inbound_engine(&net_to_fix_tx);
+            kill_fix_outbound_engine(&er_to_fix_tx);
+            inbound_handle
+                .join()
+                .expect("Failed to join inbound FIX engine thread");
+            outbound_handle
+                .join()
+                .expect("Failed to join outbound FIX engine thread");
+        });
+    }
+
     #[test]
     fn test_fix_engine_cancel_request_with_orig_cl_ord_id() {
         let shutdown = Arc::new(AtomicBool::new(false));
@@ -600,17 +825,19 @@ mod tests {
                 let _ = outbound_engine.run();
             });
 
-            let fix_message = b"8=FIX.4.4\x019=0000\x0135=F\x0149=SENDER\x0156=TARGET\x0134=2\x0152=20240219-12:31:00.000\x0111=CXL-1\x0141=ORD-12345\x0154=1\x0138=100\x0144=1.23456\x0155=EURUSD\x0110=123\x01";
-
-            let raw_msg = FixRawMsg {
-                len: fix_message.len() as u16,
-                data: {
-                    let mut data = [0u8; 1024];
-                    data[..fix_message.len()].copy_from_slice(fix_message);
-                    data
-                },
-                resp_queue: None,
-            };
+            let fix_message = FixMessageBuilder::new(b"FIX.4.4", b"SENDER", b"TARGET")
+                .msg_type(msg_types::ORDER_CANCEL_REQUEST)
+                .msg_seq_num(2)
+                .sending_time(utils::UtcTimestamp::from_fix_bytes(b"20240219-12:31:00.000").unwrap())
+                .cl_ord_id(b"CXL-1")
+                .orig_cl_ord_id(b"ORD-12345")
+                .side(Side::Buy)
+                .order_qty(FixedPointArithmetic::from_number(100))
+                .price(FixedPointArithmetic::from_f64(1.23456))
+                .symbol(b"EURUSD")
+                .build();
+
+            let raw_msg = FixRawMsg::<1024>::new(&fix_message, None);
 
             net_to_fix_tx.send(raw_msg).expect("Failed to push message");
 
@@ -0,0 +1,575 @@
+//! Framing of the raw byte stream of a FIX session into messages, with the limits protecting the
+//! server from broken or malicious clients: oversized BodyLength, slow-loris trickles and unbounded buffering.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::time::Instant;
+use utils::market_name;
+
+/// Default maximum size of a message, header and checksum included.
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 8 * 1024;
+/// Default time a client has to send a whole message once its first byte arrived.
+pub const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(5);
+/// Default maximum of bytes received but not parsed yet, per connection.
+pub const DEFAULT_MAX_BUFFERED_BYTES: usize = 64 * 1024;
+
+/// Length of the CheckSum field closing every message, `10=NNN<SOH>`.
+const CHECKSUM_FIELD_LEN: usize = 7;
+/// Longest BeginString or BodyLength field waited for before the stream is declared malformed.
+const MAX_HEADER_FIELD_LEN: usize = 16;
+/// Bytes read from the connection at once.
+const READ_CHUNK_SIZE: usize = 4096;
+const SOH: u8 = 0x01;
+
+/// Limits enforced on every connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FramingLimits {
+    /// Maximum size of a message, checked against its BodyLength (9) before the body is received.
+    pub max_message_size: usize,
+    /// Time a client has to complete a message once its first byte arrived, idle clients between messages are kept.
+    pub read_timeout: Duration,
+    /// Maximum of bytes received but not parsed yet.
+    pub max_buffered_bytes: usize,
+}
+
+impl Default for FramingLimits {
+    fn default() -> Self {
+        FramingLimits {
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            read_timeout: DEFAULT_READ_TIMEOUT,
+            max_buffered_bytes: DEFAULT_MAX_BUFFERED_BYTES,
+        }
+    }
+}
+
+/// Reason a connection is closed by the framing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FramingViolation {
+    /// The BodyLength announces a message larger than `max_message_size`.
+    MessageTooLarge,
+    /// The message was not complete `read_timeout` after its first byte.
+    ReadTimeout,
+    /// More than `max_buffered_bytes` bytes are waiting to be parsed.
+    BufferOverflow,
+    /// The stream does not start with the BeginString (8) and BodyLength (9) fields.
+    Malformed,
+}
+
+impl FramingViolation {
+    pub const ALL: [FramingViolation; 4] = [
+        FramingViolation::MessageTooLarge,
+        FramingViolation::ReadTimeout,
+        FramingViolation::BufferOverflow,
+        FramingViolation::Malformed,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FramingViolation::MessageTooLarge => "message_too_large",
+            FramingViolation::ReadTimeout => "read_timeout",
+            FramingViolation::BufferOverflow => "buffer_overflow",
+            FramingViolation::Malformed => "malformed",
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl fmt::Display for FramingViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Number of connections closed for each violation.
+pub struct FramingViolationCounters {
+    counts: [AtomicU64; 4],
+}
+
+impl FramingViolationCounters {
+    pub const fn new() -> Self {
+        FramingViolationCounters {
+            counts: [const { AtomicU64::new(0) }; 4],
+        }
+    }
+
+    pub fn record(&self, violation: FramingViolation) {
+        self.counts[violation.index()].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn count(&self, violation: FramingViolation) -> u64 {
+        self.counts[violation.index()].load(Ordering::Relaxed)
+    }
+
+    /// One line with the count of every violation, shown by the admin `stats` command.
+    pub fn summary(&self) -> String {
+        let counts = FramingViolation::ALL
+            .iter()
+            .map(|violation| format!("{} {}", violation, self.count(*violation)))
+            .collect::<Vec<_>>()
+            .join(" | ");
+        format!("FIX framing violations: {counts}")
+    }
+}
+
+impl Default for FramingViolationCounters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Violations of every connection served by [`serve_connection`].
+pub static FRAMING_VIOLATIONS: FramingViolationCounters = FramingViolationCounters::new();
+
+/// Splits the bytes received on a connection into complete messages.
+pub struct FixFramer {
+    limits: FramingLimits,
+    buf: Vec<u8>,
+}
+
+impl FixFramer {
+    pub fn new(limits: FramingLimits) -> Self {
+        FixFramer {
+            limits,
+            buf: Vec::with_capacity(limits.max_message_size),
+        }
+    }
+
+    /// Number of bytes received and not returned as a message yet.
+    pub fn buffered(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Whether part of a message has been received.
+    pub fn is_mid_message(&self) -> bool {
+        !self.buf.is_empty()
+    }
+
+    /// Appends bytes read from the connection.
+    /// Returns an error, without buffering them, if they exceed `max_buffered_bytes`.
+    pub fn push(&mut self, bytes: &[u8]) -> Result<(), FramingViolation> {
+        if self.buf.len() + bytes.len() > self.limits.max_buffered_bytes {
+            return Err(FramingViolation::BufferOverflow);
+        }
+        self.buf.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    /// Removes and returns the next complete message, `None` while it is not fully received.
+    /// The size of the message is checked as soon as its BodyLength is received.
+    pub fn next_message(&mut self) -> Result<Option<Vec<u8>>, FramingViolation> {
+        match self.message_len()? {
+            Some(len) if self.buf.len() >= len => Ok(Some(self.buf.drain(..len).collect())),
+            _ => Ok(None),
+        }
+    }
+
+    /// Total length of the message at the start of the buffer, `None` while its header is incomplete.
+    fn message_len(&self) -> Result<Option<usize>, FramingViolation> {
+        let Some((_, begin_len)) = header_field(&self.buf, b"8=")? else {
+            return Ok(None);
+        };
+        let Some((body_length, body_length_len)) = header_field(&self.buf[begin_len..], b"9=")?
+        else {
+            return Ok(None);
+        };
+
+        let body_length = parse_body_length(body_length)?;
+        let len = (begin_len + body_length_len)
+            .saturating_add(body_length)
+            .saturating_add(CHECKSUM_FIELD_LEN);
+        if len > self.limits.max_message_size {
+            return Err(FramingViolation::MessageTooLarge);
+        }
+        Ok(Some(len))
+    }
+}
+
+/// Value and total length of the `prefix` field starting `buf`, `None` while it is incomplete.
+fn header_field<'a>(
+    buf: &'a [u8],
+    prefix: &[u8],
+) -> Result<Option<(&'a [u8], usize)>, FramingViolation> {
+    let received = buf.len().min(prefix.len());
+    if buf[..received] != prefix[..received] {
+        return Err(FramingViolation::Malformed);
+    }
+
+    let searched = &buf[..buf.len().min(MAX_HEADER_FIELD_LEN)];
+    match searched.iter().position(|&b| b == SOH) {
+        Some(end) if end >= prefix.len() => Ok(Some((&buf[prefix.len()..end], end + 1))),
+        Some(_) => Err(FramingViolation::Malformed),
+        None if searched.len() == MAX_HEADER_FIELD_LEN => Err(FramingViolation::Malformed),
+        None => Ok(None),
+    }
+}
+
+fn parse_body_length(value: &[u8]) -> Result<usize, FramingViolation> {
+    if value.is_empty() || !value.iter().all(u8::is_ascii_digit) {
+        return Err(FramingViolation::Malformed);
+    }
+    Ok(value.iter().fold(0usize, |len, digit| {
+        len.saturating_mul(10)
+            .saturating_add((digit - b'0') as usize)
+    }))
+}
+
+/// Reads messages from `reader` until the peer closes the connection or violates the limits.
+/// Every complete message is given to `on_message`.
+/// Returns the violation that ended the reading, `Ok` when the peer closed the connection.
+pub async fn read_messages<R, F>(
+    reader: &mut R,
+    limits: FramingLimits,
+    mut on_message: F,
+) -> Result<(), FramingViolation>
+where
+    R: AsyncRead + Unpin,
+    F: FnMut(Vec<u8>),
+{
+    let mut framer = FixFramer::new(limits);
+    let mut chunk = [0u8; READ_CHUNK_SIZE];
+    // Time the message being received must be complete by, from its first byte
+    let mut deadline: Option<Instant> = None;
+
+    loop {
+        let read = match deadline {
+            Some(deadline) => tokio::time::timeout_at(deadline, reader.read(&mut chunk))
+                .await
+                .map_err(|_| FramingViolation::ReadTimeout)?,
+            None => reader.read(&mut chunk).await,
+        };
+        // The peer closed or reset the connection
+        let n = match read {
+            Ok(0) | Err(_) => return Ok(()),
+            Ok(n) => n,
+        };
+
+        framer.push(&chunk[..n])?;
+        let mut completed = false;
+        while let Some(message) = framer.next_message()? {
+            on_message(message);
+            completed = true;
+        }
+
+        deadline = match (framer.is_mid_message(), deadline) {
+            (false, _) => None,
+            (true, Some(deadline)) if !completed => Some(deadline),
+            (true, _) => Some(Instant::now() + limits.read_timeout),
+        };
+    }
+}
+
+/// Serves a connection with [`read_messages`], then closes it on a violation, counted in [`FRAMING_VIOLATIONS`].
+///
+/// # Arguments
+/// * `stream` - Connection of the session, plain or encrypted.
+/// * `session` - Name of the session in the logs.
+/// * `limits` - Limits enforced on the connection.
+/// * `on_message` - Called with every complete message.
+pub async fn serve_connection<S, F>(
+    mut stream: S,
+    session: &str,
+    limits: FramingLimits,
+    on_message: F,
+) -> Result<(), FramingViolation>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    F: FnMut(Vec<u8>),
+{
+    let result = read_messages(&mut stream, limits, on_message).await;
+    if let Err(violation) = result {
+        FRAMING_VIOLATIONS.record(violation);
+        tracing::warn!(
+            "[{}] Closing FIX session {}: framing violation {}",
+            market_name(),
+            session,
+            violation
+        );
+        let _ = stream.shutdown().await;
+    }
+    result
+}
+
+/// Checks a message received whole against the same limits, for sessions whose messages are not read off a stream,
+/// such as the ones the backend injects into the FIX engine. It must be exactly one complete message.
+/// A violation is counted in [`FRAMING_VIOLATIONS`], the read timeout does not apply.
+///
+/// # Arguments
+/// * `message` - Raw message of the session.
+/// * `session` - Name of the session in the logs.
+/// * `limits` - Limits enforced on the message.
+pub fn check_message(
+    message: &[u8],
+    session: &str,
+    limits: FramingLimits,
+) -> Result<(), FramingViolation> {
+    let mut framer = FixFramer::new(limits);
+    let result = framer
+        .push(message)
+        .and_then(|()| framer.next_message())
+        .and_then(|framed| match framed {
+            Some(_) if framer.buffered() == 0 => Ok(()),
+            // Truncated, or followed by the start of another message
+            _ => Err(FramingViolation::Malformed),
+        });
+    if let Err(violation) = result {
+        FRAMING_VIOLATIONS.record(violation);
+        tracing::warn!(
+            "[{}] Rejecting FIX message of session {}: framing violation {}",
+            market_name(),
+            session,
+            violation
+        );
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::{TcpListener, TcpStream};
+
+    const SOH_STR: &str = "\x01";
+
+    fn message(body: &str) -> Vec<u8> {
+        let body = body.replace('|', SOH_STR);
+        format!(
+            "8=FIX.4.2{SOH_STR}9={}{SOH_STR}{body}10=000{SOH_STR}",
+            body.len()
+        )
+        .into_bytes()
+    }
+
+    #[test]
+    fn test_framer_splits_pipelined_and_partial_messages() {
+        let first = message("35=D|11=A|");
+        let second = message("35=F|11=B|41=A|");
+        let mut stream = first.clone();
+        stream.extend_from_slice(&second);
+
+        let mut framer = FixFramer::new(FramingLimits::default());
+        let (head, tail) = stream.split_at(first.len() + 5);
+        framer.push(head).unwrap();
+        assert_eq!(framer.next_message().unwrap(), Some(first));
+        assert_eq!(framer.next_message().unwrap(), None);
+        assert!(framer.is_mid_message());
+
+        framer.push(tail).unwrap();
+        assert_eq!(framer.next_message().unwrap(), Some(second));
+        assert_eq!(framer.next_message().unwrap(), None);
+        assert_eq!(framer.buffered(), 0);
+    }
+
+    #[test]
+    fn test_framer_rejects_violations() {
+        // Rejected from the header, before the body is received
+        let mut framer = FixFramer::new(FramingLimits::default());
+        framer.push(b"8=FIX.4.2\x019=10485760\x01").unwrap();
+        assert_eq!(
+            framer.next_message(),
+            Err(FramingViolation::MessageTooLarge)
+        );
+
+        let limits = FramingLimits {
+            max_message_size: message("35=D|").len(),
+            ..FramingLimits::default()
+        };
+        let mut framer = FixFramer::new(limits);
+        framer.push(&message("35=D|")).unwrap();
+        assert!(framer.next_message().unwrap().is_some());
+        framer.push(&message("35=D|11=A|")).unwrap();
+        assert_eq!(
+            framer.next_message(),
+            Err(FramingViolation::MessageTooLarge)
+        );
+
+        for malformed in [
+            &b"GET / HTTP/1.1\r\n"[..],
+            b"8=FIX.4.2\x0135=D\x01",
+            b"8=FIX.4.2\x019=12a\x01",
+            b"8=FIX.4.2.0.0.0.0.0.0",
+        ] {
+            let mut framer = FixFramer::new(FramingLimits::default());
+            framer.push(malformed).unwrap();
+            assert_eq!(
+                framer.next_message(),
+                Err(FramingViolation::Malformed),
+                "{:?}",
+                malformed
+            );
+        }
+
+        let limits = FramingLimits {
+            max_buffered_bytes: 16,
+            ..FramingLimits::default()
+        };
+        let mut framer = FixFramer::new(limits);
+        framer.push(b"8=FIX.4.2\x01").unwrap();
+        assert_eq!(
+            framer.push(b"9=100\x0135=D\x01"),
+            Err(FramingViolation::BufferOverflow)
+        );
+        assert_eq!(framer.buffered(), 10);
+    }
+
+    #[test]
+    fn test_check_message_accepts_exactly_one_message() {
+        assert_eq!(
+            check_message(&message("35=D|11=A|"), "test", FramingLimits::default()),
+            Ok(())
+        );
+
+        let before = FRAMING_VIOLATIONS.count(FramingViolation::Malformed);
+        let mut pipelined = message("35=D|11=A|");
+        pipelined.extend_from_slice(b"8=FIX");
+        let truncated = message("35=D|11=A|");
+        for malformed in [&pipelined[..], &truncated[..truncated.len() - 1]] {
+            assert_eq!(
+                check_message(malformed, "test", FramingLimits::default()),
+                Err(FramingViolation::Malformed)
+            );
+        }
+        assert!(FRAMING_VIOLATIONS.count(FramingViolation::Malformed) >= before + 2);
+
+        let limits = FramingLimits {
+            max_message_size: 32,
+            ..FramingLimits::default()
+        };
+        assert_eq!(
+            check_message(&message("35=D|11=A|55=EURUSD|38=100|"), "test", limits),
+            Err(FramingViolation::MessageTooLarge)
+        );
+    }
+
+    #[test]
+    fn test_counters_summary() {
+        let counters = FramingViolationCounters::new();
+        counters.record(FramingViolation::ReadTimeout);
+        counters.record(FramingViolation::ReadTimeout);
+        counters.record(FramingViolation::Malformed);
+        assert_eq!(counters.count(FramingViolation::ReadTimeout), 2);
+        assert_eq!(
+            counters.summary(),
+            "FIX framing violations: message_too_large 0 | read_timeout 2 | buffer_overflow 0 | malformed 1"
+        );
+    }
+
+    /// Serves one connection of a loopback listener, returning the client side and the server task.
+    async fn connect(
+        limits: FramingLimits,
+    ) -> (
+        TcpStream,
+        tokio::task::JoinHandle<(Result<(), FramingViolation>, usize)>,
+    ) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut messages = 0;
+            let result = serve_connection(stream, "test", limits, |_| messages += 1).await;
+            (result, messages)
+        });
+        (TcpStream::connect(addr).await.unwrap(), server)
+    }
+
+    /// Whether the server closed the connection, the client reading EOF or a reset.
+    async fn is_closed(client: &mut TcpStream) -> bool {
+        let mut buf = [0u8; 16];
+        matches!(
+            tokio::time::timeout(Duration::from_secs(1), client.read(&mut buf)).await,
+            Ok(Ok(0)) | Ok(Err(_))
+        )
+    }
+
+    #[tokio::test]
+    async fn test_oversized_body_length_closes_the_connection() {
+        let before = FRAMING_VIOLATIONS.count(FramingViolation::MessageTooLarge);
+        let (mut client, server) = connect(FramingLimits::default()).await;
+        let started = Instant::now();
+
+        client.write_all(&message("35=D|11=A|")).await.unwrap();
+        // Claims a 10 MB body, never sent
+        client
+            .write_all(b"8=FIX.4.2\x019=10485760\x0135=D\x01")
+            .await
+            .unwrap();
+
+        let (result, messages) = tokio::time::timeout(Duration::from_secs(1), server)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(result, Err(FramingViolation::MessageTooLarge));
+        assert_eq!(messages, 1);
+        assert!(is_closed(&mut client).await);
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert!(FRAMING_VIOLATIONS.count(FramingViolation::MessageTooLarge) > before);
+    }
+
+    #[tokio::test]
+    async fn test_byte_per_second_trickle_closes_the_connection() {
+        let before = FRAMING_VIOLATIONS.count(FramingViolation::ReadTimeout);
+        let limits = FramingLimits {
+            read_timeout: Duration::from_secs(2),
+            ..FramingLimits::default()
+        };
+        let (client, server) = connect(limits).await;
+        let (mut reader, mut writer) = client.into_split();
+        let started = Instant::now();
+
+        // A valid message, one byte per second
+        let trickle = tokio::spawn(async move {
+            for byte in message("35=D|11=A|") {
+                if writer.write_all(&[byte]).await.is_err() {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        });
+
+        let (result, messages) = tokio::time::timeout(Duration::from_secs(4), server)
+            .await
+            .unwrap()
+            .unwrap();
+        let elapsed = started.elapsed();
+        assert_eq!(result, Err(FramingViolation::ReadTimeout));
+        assert_eq!(messages, 0);
+        assert!(
+            elapsed >= Duration::from_secs(2) && elapsed < Duration::from_secs(3),
+            "closed after {:?}",
+            elapsed
+        );
+
+        let mut buf = [0u8; 16];
+        assert!(matches!(
+            tokio::time::timeout(Duration::from_secs(1), reader.read(&mut buf)).await,
+            Ok(Ok(0)) | Ok(Err(_))
+        ));
+        assert!(FRAMING_VIOLATIONS.count(FramingViolation::ReadTimeout) > before);
+        trickle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_idle_connection_between_messages_is_kept() {
+        let limits = FramingLimits {
+            read_timeout: Duration::from_millis(100),
+            ..FramingLimits::default()
+        };
+        let (mut client, server) = connect(limits).await;
+
+        client.write_all(&message("35=D|11=A|")).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        client.write_all(&message("35=D|11=B|")).await.unwrap();
+        drop(client);
+
+        let (result, messages) = tokio::time::timeout(Duration::from_secs(1), server)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(result, Ok(()));
+        assert_eq!(messages, 2);
+    }
+}
@@ -1,4 +1,8 @@
 #![feature(portable_simd)]
+pub mod builder;
 pub mod engine;
+pub mod framing;
+pub mod market_data;
 pub mod parser;
+pub mod session;
 pub mod tags;
@@ -0,0 +1,834 @@
+use crate::builder::FixMessageBuilder;
+use crate::parser::{FixField, FixParser};
+use crate::tags::{
+    md_entry_type_code_set, md_req_rej_reason_code_set, md_update_action_code_set, msg_types,
+    subscription_request_type_code_set, tags,
+};
+use order_book::read_model::{BookSnapshot, BookSnapshotReader};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use types::{EntityId, ExecReportData, ExecutionReportMessage, FixedPointArithmetic, SymbolId};
+use utils::{UtcTimestamp, field_str, number_to_bytes};
+
+/// Aggregated price levels of one side, as (price, total resting quantity), best price first.
+type Levels = Vec<(FixedPointArithmetic, FixedPointArithmetic)>;
+
+/// Market data settings of the FIX engine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MarketDataConfig {
+    /// Maximum incremental refreshes sent per second for one subscription, 0 disables throttling.
+    /// Book updates arriving faster are conflated into the next refresh.
+    pub max_updates_per_sec: u32,
+}
+
+impl MarketDataConfig {
+    fn min_update_interval(&self) -> Duration {
+        match self.max_updates_per_sec {
+            0 => Duration::ZERO,
+            max => Duration::from_secs(1) / max,
+        }
+    }
+}
+
+/// SubscriptionRequestType (263) of a MarketDataRequest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscriptionRequestType {
+    /// A single snapshot, no subscription.
+    Snapshot,
+    /// A snapshot followed by incremental refreshes.
+    SnapshotPlusUpdates,
+    /// Cancels a previous subscription.
+    Unsubscribe,
+}
+
+/// Parsed MarketDataRequest (35=V).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarketDataRequest {
+    pub begin_string: Vec<u8>,
+    pub sender_id: EntityId,
+    pub target_id: EntityId,
+    /// MDReqID (262), echoed back in every reply.
+    pub md_req_id: Vec<u8>,
+    pub request_type: SubscriptionRequestType,
+    /// MarketDepth (264), 0 means the full book.
+    pub market_depth: usize,
+    /// Symbols of the NoRelatedSym (146) group.
+    pub symbols: Vec<SymbolId>,
+}
+
+impl MarketDataRequest {
+    /// Parses the fields of a MarketDataRequest.
+    /// Returns an error if a required field is missing or holds an unsupported value.
+    pub fn parse(fields: &[FixField<'_>]) -> Result<Self, &'static str> {
+        let mut begin_string = Vec::new();
+        let mut sender_id = EntityId::default();
+        let mut target_id = EntityId::default();
+        let mut md_req_id = None;
+        let mut request_type = None;
+        let mut market_depth = 0;
+        let mut symbols = Vec::new();
+
+        for field in fields {
+            match field.tag {
+                tags::BEGIN_STRING => begin_string = field.value.to_vec(),
+                tags::MSG_TYPE => {
+                    if field.value != msg_types::MARKET_DATA_REQUEST {
+                        return Err("Not a MarketDataRequest");
+                    }
+                }
+                tags::SENDER_COMP_ID => utils::copy_array(&mut sender_id.0, field.value),
+                tags::TARGET_COMP_ID => utils::copy_array(&mut target_id.0, field.value),
+                tags::MD_REQ_ID => md_req_id = Some(field.value.to_vec()),
+                tags::SUBSCRIPTION_REQUEST_TYPE => {
+                    request_type = Some(match field.value {
+                        subscription_request_type_code_set::SNAPSHOT => {
+                            SubscriptionRequestType::Snapshot
+                        }
+                        subscription_request_type_code_set::SNAPSHOT_PLUS_UPDATES => {
+                            SubscriptionRequestType::SnapshotPlusUpdates
+                        }
+                        subscription_request_type_code_set::DISABLE_PREVIOUS_SNAPSHOT_PLUS_UPDATE_REQUEST => {
+                            SubscriptionRequestType::Unsubscribe
+                        }
+                        _ => return Err("Unsupported SubscriptionRequestType"),
+                    });
+                }
+                tags::MARKET_DEPTH => {
+                    market_depth = std::str::from_utf8(field.value)
+                        .ok()
+                        .and_then(|depth| depth.parse().ok())
+                        .ok_or("Invalid MarketDepth")?;
+                }
+                tags::SYMBOL => {
+                    let mut symbol = SymbolId::default();
+                    utils::copy_array(&mut symbol.0, field.value);
+                    symbols.push(symbol);
+                }
+                _ => continue,
+            }
+        }
+
+        let md_req_id = md_req_id.ok_or("Missing MDReqID")?;
+        let request_type = request_type.ok_or("Missing SubscriptionRequestType")?;
+        if symbols.is_empty() && request_type != SubscriptionRequestType::Unsubscribe {
+            return Err("No symbol in MarketDataRequest");
+        }
+
+        Ok(Self {
+            begin_string,
+            sender_id,
+            target_id,
+            md_req_id,
+            request_type,
+            market_depth,
+            symbols,
+        })
+    }
+}
+
+/// Whether `data` holds a MarketDataRequest, only the header is scanned.
+pub fn is_market_data_request(data: &[u8]) -> bool {
+    let mut parser = FixParser::new(data);
+    while let Some(field) = parser.next_field_scalar() {
+        if field.tag == tags::MSG_TYPE {
+            return field.value == msg_types::MARKET_DATA_REQUEST;
+        }
+    }
+    false
+}
+
+/// FIX message to send to a session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarketDataReply {
+    pub session: EntityId,
+    pub message: Vec<u8>,
+}
+
+/// Subscription of one session to one symbol.
+#[derive(Debug)]
+struct Subscription {
+    begin_string: Vec<u8>,
+    /// Our CompID on this session, the sender of the replies.
+    sender_id: EntityId,
+    md_req_id: Vec<u8>,
+    market_depth: usize,
+    /// Levels last sent to the session, refreshes are computed against them.
+    bids: Levels,
+    asks: Levels,
+    /// Sequence of the book snapshot the session is up to date with.
+    sequence: u64,
+    last_update: Instant,
+}
+
+impl Subscription {
+    fn builder(&self, session: &EntityId, msg_type: &[u8]) -> FixMessageBuilder {
+        FixMessageBuilder::new(
+            &self.begin_string,
+            field_str(self.sender_id.as_ref()),
+            field_str(session.as_ref()),
+        )
+        .msg_type(msg_type)
+        .sending_time(UtcTimestamp::now())
+        .raw_tag(tags::MD_REQ_ID, &self.md_req_id)
+    }
+}
+
+/// Market data subscriptions per session and symbol.
+///
+/// A subscription starts with a full snapshot (35=W) of the book, then gets incremental
+/// refreshes (35=X) computed against the levels it was last sent, so throttled updates
+/// are conflated rather than lost.
+#[derive(Debug, Default)]
+pub struct MarketDataSubscriptions {
+    config: MarketDataConfig,
+    subscriptions: HashMap<(EntityId, SymbolId), Subscription>,
+}
+
+impl MarketDataSubscriptions {
+    pub fn new(config: MarketDataConfig) -> Self {
+        Self {
+            config,
+            subscriptions: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.subscriptions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.subscriptions.is_empty()
+    }
+
+    /// Applies a MarketDataRequest and returns the replies to send to the requesting session:
+    /// one snapshot per known symbol, a reject for unknown ones, nothing for an unsubscription.
+    /// Arguments:
+    /// - `request`: The parsed request.
+    /// - `book`: Latest snapshot of a symbol's book, `None` for an unknown symbol.
+    /// - `now`: Time of the request, starting the throttling window of new subscriptions.
+    pub fn handle_request<F>(
+        &mut self,
+        request: &MarketDataRequest,
+        book: F,
+        now: Instant,
+    ) -> Vec<MarketDataReply>
+    where
+        F: Fn(&SymbolId) -> Option<Arc<BookSnapshot>>,
+    {
+        let session = request.sender_id;
+
+        if request.request_type == SubscriptionRequestType::Unsubscribe {
+            if request.symbols.is_empty() {
+                self.subscriptions.retain(|(sub_session, _), subscription| {
+                    *sub_session != session || subscription.md_req_id != request.md_req_id
+                });
+            } else {
+                for symbol in &request.symbols {
+                    self.subscriptions.remove(&(session, *symbol));
+                }
+            }
+            return Vec::new();
+        }
+
+        let mut replies = Vec::with_capacity(request.symbols.len());
+        for symbol in &request.symbols {
+            let Some(snapshot) = book(symbol) else {
+                replies.push(MarketDataReply {
+                    session,
+                    message: build_reject(request, md_req_rej_reason_code_set::UNKNOWN_SYMBOL),
+                });
+                continue;
+            };
+
+            let subscription = Subscription {
+                begin_string: request.begin_string.clone(),
+                sender_id: request.target_id,
+                md_req_id: request.md_req_id.clone(),
+                market_depth: request.market_depth,
+                bids: top_levels(&snapshot.bids, request.market_depth),
+                asks: top_levels(&snapshot.asks, request.market_depth),
+                sequence: snapshot.sequence,
+                last_update: now,
+            };
+
+            replies.push(MarketDataReply {
+                session,
+                message: build_snapshot(&subscription, &session, symbol),
+            });
+
+            if request.request_type == SubscriptionRequestType::SnapshotPlusUpdates {
+                self.subscriptions.insert((session, *symbol), subscription);
+            }
+        }
+
+        replies
+    }
+
+    /// Computes the incremental refreshes of every subscription to `symbol` whose throttling
+    /// window is over. Subscriptions already up to date with `book` are skipped.
+    pub fn on_book_update(
+        &mut self,
+        symbol: &SymbolId,
+        book: &BookSnapshot,
+        now: Instant,
+    ) -> Vec<MarketDataReply> {
+        let min_interval = self.config.min_update_interval();
+        let mut replies = Vec::new();
+
+        for ((session, sub_symbol), subscription) in self.subscriptions.iter_mut() {
+            if sub_symbol != symbol
+                || subscription.sequence == book.sequence
+                || now.duration_since(subscription.last_update) < min_interval
+            {
+                continue;
+            }
+
+            let bids = top_levels(&book.bids, subscription.market_depth);
+            let asks = top_levels(&book.asks, subscription.market_depth);
+            let mut entries = Vec::new();
+            diff_levels(
+                &subscription.bids,
+                &bids,
+                md_entry_type_code_set::BID,
+                &mut entries,
+            );
+            diff_levels(
+                &subscription.asks,
+                &asks,
+                md_entry_type_code_set::OFFER,
+                &mut entries,
+            );
+
+            subscription.sequence = book.sequence;
+            if entries.is_empty() {
+                // Book changed outside of the subscribed depth, nothing to send
+                continue;
+            }
+
+            replies.push(MarketDataReply {
+                session: *session,
+                message: build_incremental(subscription, session, symbol, &entries),
+            });
+            subscription.bids = bids;
+            subscription.asks = asks;
+            subscription.last_update = now;
+        }
+
+        replies
+    }
+
+    /// Drops every subscription of a disconnected session.
+    pub fn remove_session(&mut self, session: &EntityId) {
+        self.subscriptions
+            .retain(|(sub_session, _), _| sub_session != session);
+    }
+}
+
+/// Market data service of the FIX engine: the subscriptions and the books they are served from.
+pub struct MarketDataService {
+    subscriptions: MarketDataSubscriptions,
+    books: HashMap<SymbolId, BookSnapshotReader>,
+}
+
+impl MarketDataService {
+    /// Arguments:
+    /// - `config`: Market data settings.
+    /// - `books`: Read model of each order book, see `OrderBookEngine::enable_read_model`.
+    pub fn new(config: MarketDataConfig, books: HashMap<SymbolId, BookSnapshotReader>) -> Self {
+        Self {
+            subscriptions: MarketDataSubscriptions::new(config),
+            books,
+        }
+    }
+
+    pub fn handle_request(
+        &mut self,
+        request: &MarketDataRequest,
+        now: Instant,
+    ) -> Vec<MarketDataReply> {
+        let books = &self.books;
+        self.subscriptions.handle_request(
+            request,
+            |symbol| books.get(symbol).map(BookSnapshotReader::snapshot),
+            now,
+        )
+    }
+
+    /// Polls the read models and returns the incremental refreshes due.
+    pub fn poll(&mut self, now: Instant) -> Vec<MarketDataReply> {
+        if self.subscriptions.is_empty() {
+            return Vec::new();
+        }
+
+        let mut replies = Vec::new();
+        for (symbol, reader) in &self.books {
+            let book = reader.snapshot();
+            replies.extend(self.subscriptions.on_book_update(symbol, &book, now));
+        }
+        replies
+    }
+
+    pub fn remove_session(&mut self, session: &EntityId) {
+        self.subscriptions.remove_session(session);
+    }
+}
+
+/// Wraps a market data reply into the message type carried by the session response queues.
+/// Returns `None` if the message does not fit in `N` bytes.
+pub fn to_session_message<const N: usize>(message: &[u8]) -> Option<ExecutionReportMessage<N>> {
+    if message.len() > N {
+        return None;
+    }
+
+    let mut fix_data = [0u8; N];
+    fix_data[..message.len()].copy_from_slice(message);
+    Some(ExecutionReportMessage::new(
+        message.len() as u16,
+        fix_data,
+        ExecReportData {
+            order_id: 0,
+            cl_ord_id: String::new(),
+            symbol: String::new(),
+            side: 0,
+            ord_status: 0,
+            price: 0.0,
+            qty: 0.0,
+            leaves_qty: 0.0,
+        },
+    ))
+}
+
+/// Change of one price level in an incremental refresh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LevelUpdate {
+    action: &'static [u8],
+    entry_type: &'static [u8],
+    price: FixedPointArithmetic,
+    quantity: FixedPointArithmetic,
+}
+
+fn top_levels(levels: &Levels, market_depth: usize) -> Levels {
+    match market_depth {
+        0 => levels.clone(),
+        depth => levels.iter().take(depth).copied().collect(),
+    }
+}
+
+/// Appends the updates turning `old` into `new`, deletions first.
+fn diff_levels(
+    old: &Levels,
+    new: &Levels,
+    entry_type: &'static [u8],
+    updates: &mut Vec<LevelUpdate>,
+) {
+    for (price, quantity) in old {
+        if !new.iter().any(|(new_price, _)| new_price == price) {
+            updates.push(LevelUpdate {
+                action: md_update_action_code_set::DELETE,
+                entry_type,
+                price: *price,
+                quantity: *quantity,
+            });
+        }
+    }
+
+    for (price, quantity) in new {
+        let action = match old.iter().find(|(old_price, _)| old_price == price) {
+            None => md_update_action_code_set::NEW,
+            Some((_, old_quantity)) if old_quantity != quantity => {
+                md_update_action_code_set::CHANGE
+            }
+            Some(_) => continue,
+        };
+        updates.push(LevelUpdate {
+            action,
+            entry_type,
+            price: *price,
+            quantity: *quantity,
+        });
+    }
+}
+
+fn build_snapshot(subscription: &Subscription, session: &EntityId, symbol: &SymbolId) -> Vec<u8> {
+    let entries = subscription.bids.len() + subscription.asks.len();
+    let mut builder = subscription
+        .builder(session, msg_types::MARKET_DATA_SNAPSHOT_FULL_REFRESH)
+        .symbol(field_str(symbol.as_ref()))
+        .raw_tag(tags::NO_MD_ENTRIES, &number_to_bytes(entries as u64));
+
+    let sides = [
+        (md_entry_type_code_set::BID, &subscription.bids),
+        (md_entry_type_code_set::OFFER, &subscription.asks),
+    ];
+    for (entry_type, levels) in sides {
+        for (price, quantity) in levels {
+            builder = builder
+                .raw_tag(tags::MD_ENTRY_TYPE, entry_type)
+                .raw_tag(tags::MD_ENTRY_PX, field_str(&price.to_fix_bytes()))
+                .raw_tag(tags::MD_ENTRY_SIZE, field_str(&quantity.to_fix_bytes()));
+        }
+    }
+
+    builder.build()
+}
+
+fn build_incremental(
+    subscription: &Subscription,
+    session: &EntityId,
+    symbol: &SymbolId,
+    updates: &[LevelUpdate],
+) -> Vec<u8> {
+    let mut builder = subscription
+        .builder(session, msg_types::MARKET_DATA_INCREMENTAL_REFRESH)
+        .raw_tag(tags::NO_MD_ENTRIES, &number_to_bytes(updates.len() as u64));
+
+    for update in updates {
+        builder = builder
+            .raw_tag(tags::MD_UPDATE_ACTION, update.action)
+            .raw_tag(tags::MD_ENTRY_TYPE, update.entry_type)
+            .symbol(field_str(symbol.as_ref()))
+            .raw_tag(tags::MD_ENTRY_PX, field_str(&update.price.to_fix_bytes()));
+        if update.action != md_update_action_code_set::DELETE {
+            builder = builder.raw_tag(
+                tags::MD_ENTRY_SIZE,
+                field_str(&update.quantity.to_fix_bytes()),
+            );
+        }
+    }
+
+    builder.build()
+}
+
+fn build_reject(request: &MarketDataRequest, reason: &[u8]) -> Vec<u8> {
+    FixMessageBuilder::new(
+        &request.begin_string,
+        field_str(request.target_id.as_ref()),
+        field_str(request.sender_id.as_ref()),
+    )
+    .msg_type(msg_types::MARKET_DATA_REQUEST_REJECT)
+    .sending_time(UtcTimestamp::now())
+    .raw_tag(tags::MD_REQ_ID, &request.md_req_id)
+    .raw_tag(tags::MD_REQ_REJ_REASON, reason)
+    .raw_tag(tags::TEXT, b"Unknown symbol")
+    .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use order_book::book::OrderBook;
+    use std::collections::BTreeMap;
+    use types::{OrderEvent, OrderId, Side};
+
+    const SYMBOL_STR: &str = "TEST";
+    const SYMBOL_ID: SymbolId = SymbolId::from_ascii(SYMBOL_STR);
+    const CLIENT: EntityId = EntityId::from_ascii("CLIENT");
+    const EXCHANGE: EntityId = EntityId::from_ascii("EXCHANGE");
+
+    type SideBook = BTreeMap<i64, FixedPointArithmetic>;
+
+    /// Book rebuilt by a client from the W/X messages it received.
+    #[derive(Debug, Default)]
+    struct ClientBook {
+        bids: SideBook,
+        asks: SideBook,
+    }
+
+    impl ClientBook {
+        fn side(&mut self, entry_type: &[u8]) -> &mut SideBook {
+            match entry_type {
+                md_entry_type_code_set::BID => &mut self.bids,
+                md_entry_type_code_set::OFFER => &mut self.asks,
+                _ => panic!("unexpected MDEntryType {:?}", entry_type),
+            }
+        }
+
+        /// Applies a 35=W or 35=X message, returning its MsgType.
+        fn apply(&mut self, message: &[u8]) -> Vec<u8> {
+            let fields = FixParser::new(message).get_fields().fields;
+            let msg_type = fields
+                .iter()
+                .find(|field| field.tag == tags::MSG_TYPE)
+                .unwrap()
+                .value
+                .to_vec();
+
+            if msg_type == msg_types::MARKET_DATA_SNAPSHOT_FULL_REFRESH {
+                *self = ClientBook::default();
+            }
+
+            let mut action: &[u8] = md_update_action_code_set::NEW;
+            let mut entry_type: &[u8] = b"";
+            let mut price = 0;
+            for field in &fields {
+                match field.tag {
+                    tags::MD_UPDATE_ACTION => action = field.value,
+                    tags::MD_ENTRY_TYPE => entry_type = field.value,
+                    tags::MD_ENTRY_PX => {
+                        price = FixedPointArithmetic::from_fix_bytes(field.value)
+                            .unwrap()
+                            .raw();
+                        if action == md_update_action_code_set::DELETE {
+                            self.side(entry_type).remove(&price).unwrap();
+                        }
+                    }
+                    tags::MD_ENTRY_SIZE => {
+                        let quantity = FixedPointArithmetic::from_fix_bytes(field.value).unwrap();
+                        let previous = self.side(entry_type).insert(price, quantity);
+                        match action {
+                            md_update_action_code_set::NEW => assert!(previous.is_none()),
+                            md_update_action_code_set::CHANGE => assert!(previous.is_some()),
+                            _ => panic!("unexpected MDUpdateAction {:?}", action),
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            msg_type
+        }
+
+        fn assert_matches(&self, book: &OrderBook, depth: usize) {
+            let levels = |side: &SideBook, best_first_desc: bool| -> Levels {
+                let mut levels: Levels = side
+                    .iter()
+                    .map(|(price, quantity)| (FixedPointArithmetic::from_raw(*price), *quantity))
+                    .collect();
+                if best_first_desc {
+                    levels.reverse();
+                }
+                levels
+            };
+            assert_eq!(
+                levels(&self.bids, true),
+                book.aggregated_depth(Side::Buy, depth)
+            );
+            assert_eq!(
+                levels(&self.asks, false),
+                book.aggregated_depth(Side::Sell, depth)
+            );
+        }
+    }
+
+    fn snapshot(book: &OrderBook, sequence: u64) -> BookSnapshot {
+        BookSnapshot {
+            sequence,
+            bids: book.aggregated_depth(Side::Buy, 10),
+            asks: book.aggregated_depth(Side::Sell, 10),
+            ..Default::default()
+        }
+    }
+
+    fn order(side: Side, price: f64, quantity: f64, id: &str) -> OrderEvent {
+        OrderEvent::builder()
+            .side(side)
+            .price_f64(price)
+            .qty(quantity)
+            .cl_ord_id(OrderId::from_ascii(id))
+            .sender_id(CLIENT)
+            .target_id(EXCHANGE)
+            .symbol(SYMBOL_ID)
+            .build()
+    }
+
+    fn request(request_type: &[u8], market_depth: usize) -> MarketDataRequest {
+        let message = FixMessageBuilder::new(b"FIX.4.4", b"CLIENT", b"EXCHANGE")
+            .msg_type(msg_types::MARKET_DATA_REQUEST)
+            .msg_seq_num(1)
+            .raw_tag(tags::MD_REQ_ID, b"MD-1")
+            .raw_tag(tags::SUBSCRIPTION_REQUEST_TYPE, request_type)
+            .raw_tag(tags::MARKET_DEPTH, &number_to_bytes(market_depth as u64))
+            .raw_tag(tags::NO_RELATED_SYM, b"1")
+            .symbol(SYMBOL_STR.as_bytes())
+            .build();
+
+        assert!(is_market_data_request(&message));
+        MarketDataRequest::parse(&FixParser::new(&message).get_fields().fields).unwrap()
+    }
+
+    #[test]
+    fn test_parse_market_data_request() {
+        let message = FixMessageBuilder::new(b"FIX.4.4", b"CLIENT", b"EXCHANGE")
+            .msg_type(msg_types::MARKET_DATA_REQUEST)
+            .raw_tag(tags::MD_REQ_ID, b"MD-7")
+            .raw_tag(
+                tags::SUBSCRIPTION_REQUEST_TYPE,
+                subscription_request_type_code_set::SNAPSHOT_PLUS_UPDATES,
+            )
+            .raw_tag(tags::MARKET_DEPTH, b"5")
+            .raw_tag(tags::NO_RELATED_SYM, b"2")
+            .symbol(b"AAPL")
+            .symbol(b"MSFT")
+            .build();
+
+        let fields = FixParser::new(&message).get_fields().fields;
+        let request = MarketDataRequest::parse(&fields).unwrap();
+        assert_eq!(request.begin_string, b"FIX.4.4");
+        assert_eq!(request.sender_id, CLIENT);
+        assert_eq!(request.target_id, EXCHANGE);
+        assert_eq!(request.md_req_id, b"MD-7");
+        assert_eq!(
+            request.request_type,
+            SubscriptionRequestType::SnapshotPlusUpdates
+        );
+        assert_eq!(request.market_depth, 5);
+        assert_eq!(
+            request.symbols,
+            vec![SymbolId::from_ascii("AAPL"), SymbolId::from_ascii("MSFT")]
+        );
+
+        let missing_symbol = FixMessageBuilder::new(b"FIX.4.4", b"CLIENT", b"EXCHANGE")
+            .msg_type(msg_types::MARKET_DATA_REQUEST)
+            .raw_tag(tags::MD_REQ_ID, b"MD-8")
+            .raw_tag(tags::SUBSCRIPTION_REQUEST_TYPE, b"1")
+            .build();
+        let fields = FixParser::new(&missing_symbol).get_fields().fields;
+        assert_eq!(
+            MarketDataRequest::parse(&fields),
+            Err("No symbol in MarketDataRequest")
+        );
+
+        let order = FixMessageBuilder::new(b"FIX.4.4", b"CLIENT", b"EXCHANGE").build();
+        assert!(!is_market_data_request(&order));
+    }
+
+    #[test]
+    fn test_snapshot_and_incremental_refreshes_rebuild_the_book() {
+        let now = Instant::now();
+        let mut book = OrderBook::new(SYMBOL_STR);
+        book.process_order(order(Side::Buy, 99.0, 5.0, "b1"));
+        book.process_order(order(Side::Buy, 98.0, 3.0, "b2"));
+        book.process_order(order(Side::Sell, 101.0, 4.0, "s1"));
+
+        let mut subscriptions = MarketDataSubscriptions::new(MarketDataConfig::default());
+        let initial = Arc::new(snapshot(&book, 1));
+        let replies = subscriptions.handle_request(
+            &request(subscription_request_type_code_set::SNAPSHOT_PLUS_UPDATES, 0),
+            |symbol| (*symbol == SYMBOL_ID).then(|| Arc::clone(&initial)),
+            now,
+        );
+        assert_eq!(replies.len(), 1);
+        assert_eq!(replies[0].session, CLIENT);
+        assert_eq!(subscriptions.len(), 1);
+
+        let mut client = ClientBook::default();
+        assert_eq!(
+            client.apply(&replies[0].message),
+            msg_types::MARKET_DATA_SNAPSHOT_FULL_REFRESH
+        );
+        client.assert_matches(&book, 10);
+
+        let orders = [
+            order(Side::Buy, 100.0, 2.0, "b3"),  // new best bid
+            order(Side::Sell, 99.0, 6.0, "s2"),  // sweeps 100 and part of 99
+            order(Side::Buy, 98.0, 1.0, "b4"),   // adds to an existing level
+            order(Side::Sell, 102.0, 7.0, "s3"), // new ask level
+        ];
+        for (i, order) in orders.into_iter().enumerate() {
+            book.process_order(order);
+            let replies =
+                subscriptions.on_book_update(&SYMBOL_ID, &snapshot(&book, i as u64 + 2), now);
+            assert_eq!(replies.len(), 1);
+            assert_eq!(
+                client.apply(&replies[0].message),
+                msg_types::MARKET_DATA_INCREMENTAL_REFRESH
+            );
+            client.assert_matches(&book, 10);
+        }
+
+        // Same snapshot again, nothing new to send
+        assert!(
+            subscriptions
+                .on_book_update(&SYMBOL_ID, &snapshot(&book, 5), now)
+                .is_empty()
+        );
+
+        // Unsubscribed sessions get no more refreshes
+        subscriptions.handle_request(
+            &request(
+                subscription_request_type_code_set::DISABLE_PREVIOUS_SNAPSHOT_PLUS_UPDATE_REQUEST,
+                0,
+            ),
+            |_| None,
+            now,
+        );
+        assert!(subscriptions.is_empty());
+        book.process_order(order(Side::Buy, 97.0, 1.0, "b5"));
+        assert!(
+            subscriptions
+                .on_book_update(&SYMBOL_ID, &snapshot(&book, 6), now)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_market_depth_and_throttling() {
+        let start = Instant::now();
+        let mut book = OrderBook::new(SYMBOL_STR);
+        book.process_order(order(Side::Buy, 99.0, 5.0, "b1"));
+        book.process_order(order(Side::Sell, 101.0, 4.0, "s1"));
+
+        let mut subscriptions = MarketDataSubscriptions::new(MarketDataConfig {
+            max_updates_per_sec: 2,
+        });
+        let initial = Arc::new(snapshot(&book, 1));
+        let replies = subscriptions.handle_request(
+            &request(subscription_request_type_code_set::SNAPSHOT_PLUS_UPDATES, 1),
+            |_| Some(Arc::clone(&initial)),
+            start,
+        );
+        let mut client = ClientBook::default();
+        client.apply(&replies[0].message);
+        client.assert_matches(&book, 1);
+
+        // Within the 500ms window the update is held back
+        book.process_order(order(Side::Buy, 100.0, 2.0, "b2"));
+        let later = start + Duration::from_millis(100);
+        assert!(
+            subscriptions
+                .on_book_update(&SYMBOL_ID, &snapshot(&book, 2), later)
+                .is_empty()
+        );
+
+        // Both updates are conflated into the next refresh
+        book.process_order(order(Side::Sell, 100.5, 1.0, "s2"));
+        let later = start + Duration::from_millis(500);
+        let replies = subscriptions.on_book_update(&SYMBOL_ID, &snapshot(&book, 3), later);
+        assert_eq!(replies.len(), 1);
+        client.apply(&replies[0].message);
+        client.assert_matches(&book, 1);
+
+        // Levels below the subscribed depth do not trigger refreshes
+        book.process_order(order(Side::Buy, 95.0, 1.0, "b3"));
+        let later = start + Duration::from_millis(1_000);
+        assert!(
+            subscriptions
+                .on_book_update(&SYMBOL_ID, &snapshot(&book, 4), later)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_unknown_symbol_is_rejected() {
+        let mut subscriptions = MarketDataSubscriptions::new(MarketDataConfig::default());
+        let replies = subscriptions.handle_request(
+            &request(subscription_request_type_code_set::SNAPSHOT_PLUS_UPDATES, 0),
+            |_| None,
+            Instant::now(),
+        );
+
+        assert_eq!(replies.len(), 1);
+        assert!(subscriptions.is_empty());
+        let fields = FixParser::new(&replies[0].message).get_fields().fields;
+        let value = |tag| fields.iter().find(|field| field.tag == tag).unwrap().value;
+        assert_eq!(value(tags::MSG_TYPE), msg_types::MARKET_DATA_REQUEST_REJECT);
+        assert_eq!(value(tags::SENDER_COMP_ID), b"EXCHANGE");
+        assert_eq!(value(tags::TARGET_COMP_ID), b"CLIENT");
+        assert_eq!(value(tags::MD_REQ_ID), b"MD-1");
+        assert_eq!(
+            value(tags::MD_REQ_REJ_REASON),
+            md_req_rej_reason_code_set::UNKNOWN_SYMBOL
+        );
+    }
+}
@@ -0,0 +1,181 @@
+//! Session layer of a FIX connection: the stream is framed with the limits of [`crate::framing`], Logons are answered
+//! here and every other message goes to the FIX engine with the response queue of the session, whose replies are
+//! written back to the connection.
+
+use crate::builder::FixMessageBuilder;
+use crate::engine::FixRawMsg;
+use crate::framing::{FRAMING_VIOLATIONS, FramingLimits, FramingViolation, read_messages};
+use crate::parser::FixParser;
+use crate::tags::{msg_types, tags};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc;
+use types::ExecutionReportMessage;
+use utils::{UtcTimestamp, market_name};
+
+/// Messages waiting to be written back to a session before the engine has to retry.
+pub const RESPONSE_QUEUE_SIZE: usize = 1024;
+/// Heartbeat interval acknowledged to a Logon that does not ask for one, in seconds.
+const DEFAULT_HEARTBEAT_INT: &[u8] = b"30";
+
+/// Acknowledgement of a Logon (35=A), sent back from the target of `request` to its sender with the same BeginString
+/// and heartbeat interval. Returns `None` if `request` is not a Logon.
+pub fn logon_reply(request: &[u8]) -> Option<Vec<u8>> {
+    let mut parser = FixParser::new(request);
+    let fields = parser.get_fields().fields;
+    let field = |tag: u32| {
+        fields
+            .iter()
+            .find(|field| field.tag == tag)
+            .map(|field| field.value)
+    };
+    if field(tags::MSG_TYPE)? != msg_types::LOGON {
+        return None;
+    }
+
+    let reply = FixMessageBuilder::new(
+        field(tags::BEGIN_STRING)?,
+        field(tags::TARGET_COMP_ID)?,
+        field(tags::SENDER_COMP_ID)?,
+    )
+    .msg_type(msg_types::LOGON)
+    .msg_seq_num(1)
+    .sending_time(UtcTimestamp::now())
+    .raw_tag(tags::ENCRYPT_METHOD, b"0")
+    .raw_tag(
+        tags::HEARTBEAT_INT,
+        field(tags::HEARTBEAT_INT).unwrap_or(DEFAULT_HEARTBEAT_INT),
+    )
+    .build();
+    Some(reply)
+}
+
+/// Serves a FIX session until the peer closes the connection or violates the framing `limits`, the violation is
+/// counted in [`FRAMING_VIOLATIONS`] and the connection closed.
+///
+/// # Arguments
+/// * `stream` - Connection of the session, plain or encrypted.
+/// * `limits` - Framing limits enforced on the connection.
+/// * `net_to_fix_tx` - Inbound queue of the FIX engine.
+pub async fn serve_session<S, const N: usize>(
+    stream: S,
+    limits: FramingLimits,
+    net_to_fix_tx: crossbeam_channel::Sender<FixRawMsg<N>>,
+) -> Result<(), FramingViolation>
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (mut reader, mut writer) = tokio::io::split(stream);
+    let (client_tx, mut client_rx) =
+        mpsc::channel::<ExecutionReportMessage<N>>(RESPONSE_QUEUE_SIZE);
+
+    let writer_task = tokio::spawn(async move {
+        while let Some(message) = client_rx.recv().await {
+            if writer
+                .write_all(&message.fix_data[..message.fix_len as usize])
+                .await
+                .is_err()
+            {
+                break;
+            }
+        }
+        let _ = writer.shutdown().await;
+    });
+
+    // SenderCompID of the session once logged on, for the logs
+    let mut session = String::new();
+    let result = read_messages(&mut reader, limits, |message| {
+        if message.len() > N {
+            tracing::warn!(
+                "[{}] Dropping a message of {} bytes from FIX session {}, larger than {} bytes",
+                market_name(),
+                message.len(),
+                session,
+                N
+            );
+            return;
+        }
+        if let Some(reply) = logon_reply(&message) {
+            let mut parser = FixParser::new(&message);
+            if let Some(sender) = parser
+                .get_fields()
+                .fields
+                .iter()
+                .find(|field| field.tag == tags::SENDER_COMP_ID)
+            {
+                session = String::from_utf8_lossy(sender.value).to_string();
+            }
+            match crate::market_data::to_session_message(&reply) {
+                Some(reply) => {
+                    let _ = client_tx.try_send(reply);
+                }
+                None => tracing::error!(
+                    "[{}] Logon reply of FIX session {} larger than {} bytes",
+                    market_name(),
+                    session,
+                    N
+                ),
+            }
+            return;
+        }
+        if net_to_fix_tx
+            .send(FixRawMsg::new(&message, Some(client_tx.clone())))
+            .is_err()
+        {
+            tracing::warn!(
+                "[{}] FIX engine stopped, message of session {} dropped",
+                market_name(),
+                session
+            );
+        }
+    })
+    .await;
+
+    if let Err(violation) = result {
+        FRAMING_VIOLATIONS.record(violation);
+        tracing::warn!(
+            "[{}] Closing FIX session {}: framing violation {}",
+            market_name(),
+            session,
+            violation
+        );
+        // Dropping both halves of the stream closes the connection
+        writer_task.abort();
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_logon_reply_swaps_the_comp_ids() {
+        let logon = FixMessageBuilder::new(b"FIX.4.4", b"CLIENT", b"MARKET")
+            .msg_type(msg_types::LOGON)
+            .msg_seq_num(1)
+            .raw_tag(tags::ENCRYPT_METHOD, b"0")
+            .raw_tag(tags::HEARTBEAT_INT, b"10")
+            .build();
+        let reply = logon_reply(&logon).unwrap();
+
+        let mut parser = FixParser::new(&reply);
+        let fields = parser.get_fields().fields;
+        let field = |tag: u32| {
+            fields
+                .iter()
+                .find(|field| field.tag == tag)
+                .map(|field| field.value)
+        };
+        assert_eq!(field(tags::BEGIN_STRING), Some(&b"FIX.4.4"[..]));
+        assert_eq!(field(tags::MSG_TYPE), Some(msg_types::LOGON));
+        assert_eq!(field(tags::SENDER_COMP_ID), Some(&b"MARKET"[..]));
+        assert_eq!(field(tags::TARGET_COMP_ID), Some(&b"CLIENT"[..]));
+        assert_eq!(field(tags::HEARTBEAT_INT), Some(&b"10"[..]));
+
+        let order = FixMessageBuilder::new(b"FIX.4.4", b"CLIENT", b"MARKET")
+            .msg_seq_num(2)
+            .cl_ord_id(b"A")
+            .build();
+        assert_eq!(logon_reply(&order), None);
+    }
+}
@@ -17,18 +17,34 @@ pub mod tags {
     pub const SYMBOL: u32 = 55;
     pub const SIDE: u32 = 54;
     pub const ORDER_QTY: u32 = 38;
+    pub const MIN_QTY: u32 = 110; // Minimum quantity to execute, the order only trades when at least that much is executable
     pub const PRICE: u32 = 44;
     pub const LAST_QTY: u32 = 32;
     pub const LAST_PX: u32 = 31;
     pub const CUM_QTY: u32 = 14;
     pub const LEAVES_QTY: u32 = 151;
+    pub const ENCRYPT_METHOD: u32 = 98;
     pub const HEARTBEAT_INT: u32 = 108;
     pub const TEST_REQ_ID: u32 = 112;
     pub const BEGIN_SEQ_NO: u32 = 7;
     pub const END_SEQ_NO: u32 = 16;
     pub const CHECKSUM: u32 = 10;
     pub const AVG_PX: u32 = 6;
+    pub const ORD_TYPE: u32 = 40;
+    pub const TIME_IN_FORCE: u32 = 59;
+    pub const TRANSACT_TIME: u32 = 60;
     pub const CHECK_SUM: u32 = 10;
+    pub const TEXT: u32 = 58;
+    pub const NO_RELATED_SYM: u32 = 146;
+    pub const MD_REQ_ID: u32 = 262;
+    pub const SUBSCRIPTION_REQUEST_TYPE: u32 = 263;
+    pub const MARKET_DEPTH: u32 = 264;
+    pub const NO_MD_ENTRIES: u32 = 268;
+    pub const MD_ENTRY_TYPE: u32 = 269;
+    pub const MD_ENTRY_PX: u32 = 270;
+    pub const MD_ENTRY_SIZE: u32 = 271;
+    pub const MD_UPDATE_ACTION: u32 = 279;
+    pub const MD_REQ_REJ_REASON: u32 = 281;
 }
 
 /// Pre-defined FIX message types we care about on the hot path.
@@ -46,6 +62,9 @@ pub mod msg_types {
     pub const ORDER_CANCEL_REQUEST: &[u8] = b"F";
     pub const ORDER_CANCEL_REPLACE_REQUEST: &[u8] = b"G";
     pub const MARKET_DATA_REQUEST: &[u8] = b"V";
+    pub const MARKET_DATA_SNAPSHOT_FULL_REFRESH: &[u8] = b"W";
+    pub const MARKET_DATA_INCREMENTAL_REFRESH: &[u8] = b"X";
+    pub const MARKET_DATA_REQUEST_REJECT: &[u8] = b"Y";
 }
 
 /// Pre-defined FIX side types.
@@ -69,6 +88,25 @@ pub mod side_code_set {
     pub const SELL_UNDISCLOSED: &[u8] = b"H";
 }
 
+/// Pre-defined FIX order types.
+pub mod ord_type_code_set {
+    pub const MARKET: &[u8] = b"1";
+    pub const LIMIT: &[u8] = b"2";
+    pub const STOP: &[u8] = b"3";
+    pub const STOP_LIMIT: &[u8] = b"4";
+}
+
+/// Pre-defined FIX time in force values.
+pub mod time_in_force_code_set {
+    pub const DAY: &[u8] = b"0";
+    pub const GOOD_TILL_CANCEL: &[u8] = b"1";
+    pub const AT_THE_OPENING: &[u8] = b"2";
+    pub const IMMEDIATE_OR_CANCEL: &[u8] = b"3";
+    pub const FILL_OR_KILL: &[u8] = b"4";
+    pub const GOOD_TILL_CROSSING: &[u8] = b"5";
+    pub const GOOD_TILL_DATE: &[u8] = b"6";
+}
+
 pub mod ord_status_code_set {
     pub const NEW: &[u8] = b"0";
     pub const PARTIAL_FILL: &[u8] = b"1";
@@ -111,3 +149,32 @@ pub mod exec_type_code_set {
     pub const LOCKED: &[u8] = b"M";
     pub const RELEASED: &[u8] = b"N";
 }
+
+/// Pre-defined FIX SubscriptionRequestType (263) values.
+pub mod subscription_request_type_code_set {
+    pub const SNAPSHOT: &[u8] = b"0";
+    pub const SNAPSHOT_PLUS_UPDATES: &[u8] = b"1";
+    pub const DISABLE_PREVIOUS_SNAPSHOT_PLUS_UPDATE_REQUEST: &[u8] = b"2";
+}
+
+/// Pre-defined FIX MDEntryType (269) values.
+pub mod md_entry_type_code_set {
+    pub const BID: &[u8] = b"0";
+    pub const OFFER: &[u8] = b"1";
+    pub const TRADE: &[u8] = b"2";
+}
+
+/// Pre-defined FIX MDUpdateAction (279) values.
+pub mod md_update_action_code_set {
+    pub const NEW: &[u8] = b"0";
+    pub const CHANGE: &[u8] = b"1";
+    pub const DELETE: &[u8] = b"2";
+}
+
+/// Pre-defined FIX MDReqRejReason (281) values.
+pub mod md_req_rej_reason_code_set {
+    pub const UNKNOWN_SYMBOL: &[u8] = b"0";
+    pub const DUPLICATE_MD_REQ_ID: &[u8] = b"1";
+    pub const UNSUPPORTED_SUBSCRIPTION_REQUEST_TYPE: &[u8] = b"4";
+    pub const UNSUPPORTED_MARKET_DEPTH: &[u8] = b"5";
+}
@@ -88,6 +88,7 @@ pub fn decode_snapshot(bytes: &[u8]) -> Snapshot {
             target_id: EntityId::default(),
             symbol: SymbolId::default(),
             timestamp_ms: snapshot.timestamp_ms,
+            min_quantity: FixedPointArithmetic::ZERO,
         });
         let _side = bytes[offset];
         offset += 1;
@@ -113,6 +114,7 @@ pub fn decode_snapshot(bytes: &[u8]) -> Snapshot {
             target_id: EntityId::default(),
             symbol: SymbolId::default(),
             timestamp_ms: snapshot.timestamp_ms,
+            min_quantity: FixedPointArithmetic::ZERO,
         });
         let _side = bytes[offset];
         offset += 1;
@@ -142,6 +144,7 @@ mod tests {
             target_id: EntityId::from_ascii("exchange"),
             symbol: SymbolId::from_ascii("TEST"),
             timestamp_ms: 1627846267000,
+            min_quantity: FixedPointArithmetic::ZERO,
         };
         let order2 = OrderEvent {
             price: FixedPointArithmetic::from_f64(102.0),
@@ -154,6 +157,7 @@ mod tests {
             target_id: EntityId::from_ascii("exchange"),
             symbol: SymbolId::from_ascii("TEST"),
             timestamp_ms: 1627846267000,
+            min_quantity: FixedPointArithmetic::ZERO,
         };
 
         let mut snapshot = Snapshot {
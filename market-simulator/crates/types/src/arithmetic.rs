@@ -3,6 +3,7 @@ use std::iter::Sum;
 /// Price represented as integer with implicit 8 decimal places
 /// e.g. 123.45678900 -> 12_345_678_900
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(transparent)]
 pub struct FixedPointArithmetic(pub i64);
 
 impl FixedPointArithmetic {
@@ -90,10 +91,27 @@ impl FixedPointArithmetic {
         self.0 as f64 / Self::SCALE as f64
     }
 
+    /// Converts `number`, clamping what has no fixed-point value: NaN becomes zero and values out of range,
+    /// infinities included, saturate to the i64 bounds. Use `try_from_f64` to reject them instead.
     pub fn from_f64(number: f64) -> Self {
+        if number.is_nan() {
+            return FixedPointArithmetic::ZERO;
+        }
+        // Float to integer casts saturate
         FixedPointArithmetic((number * Self::SCALE as f64).round() as i64)
     }
 
+    /// Converts `number`, or returns `None` if it is NaN, infinite or out of the fixed-point range.
+    pub fn try_from_f64(number: f64) -> Option<Self> {
+        let scaled = (number * Self::SCALE as f64).round();
+        // i64::MAX is not representable in f64, its nearest value 2^63 already overflows
+        if scaled.is_finite() && scaled >= i64::MIN as f64 && scaled < i64::MAX as f64 {
+            Some(FixedPointArithmetic(scaled as i64))
+        } else {
+            None
+        }
+    }
+
     pub fn from_option_f64(value: Option<f64>) -> FixedPointArithmetic {
         value
             .map(FixedPointArithmetic::from_f64)
@@ -111,6 +129,136 @@ impl FixedPointArithmetic {
     pub fn from_number<T: Into<i64>>(num: T) -> Self {
         FixedPointArithmetic::from_raw(num.into() * Self::SCALE)
     }
+
+    /// Notional value of `quantity` at this price, computed with a widening multiplication so it cannot overflow.
+    pub fn mul_qty(self, quantity: FixedPointArithmetic) -> Notional {
+        Notional(self.0 as i128 * quantity.0 as i128)
+    }
+}
+
+/// Narrows an intermediate i128 result back to the i64 representation.
+/// Debug builds assert the value fits instead of silently wrapping.
+fn narrow(value: i128) -> i64 {
+    debug_assert!(
+        value >= i64::MIN as i128 && value <= i64::MAX as i128,
+        "fixed-point overflow: {value} does not fit in i64"
+    );
+    value as i64
+}
+
+/// Divides `numerator` by `denominator`, rounding half away from zero.
+fn div_round(numerator: i128, denominator: i128) -> i128 {
+    let quotient = numerator / denominator;
+    let remainder = numerator % denominator;
+    if remainder.abs() * 2 >= denominator.abs() {
+        if (numerator < 0) == (denominator < 0) {
+            quotient + 1
+        } else {
+            quotient - 1
+        }
+    } else {
+        quotient
+    }
+}
+
+/// Notional value (price × quantity) of two fixed-point numbers.
+/// The exact product is kept with `FixedPointArithmetic::SCALE²` implicit decimals in an i128: a single
+/// product of two i64 always fits, and sums only overflow past 2^127 / SCALE² ≈ 1.7e22 units of notional.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Notional(pub i128);
+
+impl Notional {
+    pub const ZERO: Notional = Notional(0);
+    pub const SCALE: i128 =
+        FixedPointArithmetic::SCALE as i128 * FixedPointArithmetic::SCALE as i128;
+
+    pub fn raw(self) -> i128 {
+        self.0
+    }
+
+    pub fn checked_add(self, other: Notional) -> Option<Notional> {
+        self.0.checked_add(other.0).map(Notional)
+    }
+
+    /// Converts to the 8 decimals representation, rounding half away from zero.
+    /// Debug builds assert the value fits in a `FixedPointArithmetic`.
+    pub fn to_fixed_point(self) -> FixedPointArithmetic {
+        FixedPointArithmetic::from_raw(narrow(div_round(
+            self.0,
+            FixedPointArithmetic::SCALE as i128,
+        )))
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / Self::SCALE as f64
+    }
+}
+
+impl std::ops::Add for Notional {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Notional(self.0 + other.0)
+    }
+}
+
+impl std::ops::AddAssign for Notional {
+    fn add_assign(&mut self, other: Self) {
+        self.0 += other.0;
+    }
+}
+
+impl std::ops::SubAssign for Notional {
+    fn sub_assign(&mut self, other: Self) {
+        self.0 -= other.0;
+    }
+}
+
+impl Sum for Notional {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Notional::ZERO, |acc, notional| acc + notional)
+    }
+}
+
+impl std::fmt::Display for Notional {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_fixed_point())
+    }
+}
+
+/// Volume weighted average price accumulator.
+/// Notional and quantity are summed in i128, so no intermediate sum can overflow the i64 representation.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Vwap {
+    notional: Notional,
+    quantity: i128,
+}
+
+impl Vwap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, price: FixedPointArithmetic, quantity: FixedPointArithmetic) {
+        self.notional += price.mul_qty(quantity);
+        self.quantity += quantity.0 as i128;
+    }
+
+    pub fn notional(&self) -> Notional {
+        self.notional
+    }
+
+    /// Total notional divided by total quantity, rounded half away from zero to the 8th decimal.
+    /// `None` when no quantity was accumulated.
+    pub fn price(&self) -> Option<FixedPointArithmetic> {
+        if self.quantity == 0 {
+            return None;
+        }
+        Some(FixedPointArithmetic::from_raw(narrow(div_round(
+            self.notional.0,
+            self.quantity,
+        ))))
+    }
 }
 
 impl Sum for FixedPointArithmetic {
@@ -144,9 +292,9 @@ impl std::ops::Mul for FixedPointArithmetic {
 
     fn mul(self, other: Self) -> Self {
         // (a * b) / SCALE to maintain the fixed-point representation
-        FixedPointArithmetic::from_raw(
-            ((self.0 as i128 * other.0 as i128) / Self::SCALE as i128) as i64,
-        )
+        FixedPointArithmetic::from_raw(narrow(
+            (self.0 as i128 * other.0 as i128) / Self::SCALE as i128,
+        ))
     }
 }
 
@@ -155,9 +303,9 @@ impl std::ops::Div for FixedPointArithmetic {
 
     fn div(self, other: Self) -> Self {
         // (a * SCALE) / b to maintain the fixed-point representation
-        FixedPointArithmetic::from_raw(
-            ((self.0 as i128 * Self::SCALE as i128) / other.0 as i128) as i64,
-        )
+        FixedPointArithmetic::from_raw(narrow(
+            (self.0 as i128 * Self::SCALE as i128) / other.0 as i128,
+        ))
     }
 }
 
@@ -181,3 +329,173 @@ impl std::fmt::Display for FixedPointArithmetic {
         write!(f, "{}.{:08}", integer_part, frac_part)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic stream of (price, quantity) pairs spanning several orders of magnitude.
+    fn scripted_fills(count: usize, seed: u64) -> impl Iterator<Item = (f64, f64)> {
+        let mut seed = seed;
+        let mut next = move || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            seed >> 11
+        };
+        (0..count).map(move |_| {
+            let price = (next() % 100_000_000_000) as f64 / 1_000.0;
+            let quantity = (next() % 10_000_000_000) as f64 / 100.0;
+            (price, quantity)
+        })
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        let tolerance = expected.abs() * 1e-12 + 1e-8;
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{actual} differs from {expected}"
+        );
+    }
+
+    #[test]
+    fn test_mul_qty_matches_f64() {
+        for (price, quantity) in scripted_fills(10_000, 1) {
+            let notional = FixedPointArithmetic::from_f64(price)
+                .mul_qty(FixedPointArithmetic::from_f64(quantity));
+            assert_close(notional.to_f64(), price * quantity);
+        }
+    }
+
+    #[test]
+    fn test_vwap_matches_f64() {
+        for seed in 0..100 {
+            let mut vwap = Vwap::new();
+            let mut notional = 0.0;
+            let mut quantity = 0.0;
+            for (p, q) in scripted_fills(50, seed) {
+                vwap.add(
+                    FixedPointArithmetic::from_f64(p),
+                    FixedPointArithmetic::from_f64(q),
+                );
+                notional += p * q;
+                quantity += q;
+            }
+            assert_close(vwap.notional().to_f64(), notional);
+            let price = vwap.price().unwrap().to_f64();
+            assert!((price - notional / quantity).abs() <= 1e-8 + price * 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_notional_past_u64_boundary() {
+        // 1e6 × 1e6 is 1e28 once scaled, far past u64::MAX ≈ 1.8e19
+        let price = FixedPointArithmetic::from_number(1_000_000);
+        let quantity = FixedPointArithmetic::from_number(1_000_000);
+        let notional = price.mul_qty(quantity);
+        assert!(notional.raw() > u64::MAX as i128);
+        assert_eq!(notional.raw(), 10_i128.pow(28));
+        assert_eq!(notional.to_f64(), 1e12);
+
+        // Raw values straddling the u64 boundary
+        let just_below = FixedPointArithmetic::from_raw(1 << 32)
+            .mul_qty(FixedPointArithmetic::from_raw((1 << 32) - 1));
+        let just_above = FixedPointArithmetic::from_raw(1 << 32)
+            .mul_qty(FixedPointArithmetic::from_raw(1 << 32));
+        assert_eq!(just_below.raw(), u64::MAX as i128 - (1 << 32) + 1);
+        assert_eq!(just_above.raw(), u64::MAX as i128 + 1);
+
+        // The widest product fits, and so does a VWAP over it
+        let max = FixedPointArithmetic::from_raw(i64::MAX);
+        assert_eq!(max.mul_qty(max).raw(), i64::MAX as i128 * i64::MAX as i128);
+        let mut vwap = Vwap::new();
+        vwap.add(max, max);
+        vwap.add(max, max);
+        assert_eq!(vwap.price(), Some(max));
+    }
+
+    #[test]
+    fn test_vwap_rounding() {
+        assert_eq!(Vwap::new().price(), None);
+
+        // 1.000000015 rounds half away from zero
+        let mut vwap = Vwap::new();
+        vwap.add(
+            FixedPointArithmetic::from_raw(100_000_001),
+            FixedPointArithmetic::from_number(1),
+        );
+        vwap.add(
+            FixedPointArithmetic::from_raw(100_000_002),
+            FixedPointArithmetic::from_number(1),
+        );
+        assert_eq!(
+            vwap.price(),
+            Some(FixedPointArithmetic::from_raw(100_000_002))
+        );
+
+        // 1.000000013 rounds down
+        let mut vwap = Vwap::new();
+        vwap.add(
+            FixedPointArithmetic::from_raw(100_000_001),
+            FixedPointArithmetic::from_number(7),
+        );
+        vwap.add(
+            FixedPointArithmetic::from_raw(100_000_002),
+            FixedPointArithmetic::from_number(3),
+        );
+        assert_eq!(
+            vwap.price(),
+            Some(FixedPointArithmetic::from_raw(100_000_001))
+        );
+
+        assert_eq!(div_round(-15, 10), -2);
+        assert_eq!(div_round(15, -10), -2);
+        assert_eq!(div_round(-14, 10), -1);
+        assert_eq!(
+            Notional(-150_000_000).to_fixed_point(),
+            FixedPointArithmetic::from_raw(-2)
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "fixed-point overflow")]
+    fn test_mul_overflow_is_caught() {
+        let price = FixedPointArithmetic::from_number(1_000_000);
+        let _ = price * FixedPointArithmetic::from_number(1_000_000);
+    }
+
+    #[test]
+    fn test_from_f64_never_produces_nan_garbage() {
+        assert_eq!(
+            FixedPointArithmetic::from_f64(f64::NAN),
+            FixedPointArithmetic::ZERO
+        );
+        assert_eq!(
+            FixedPointArithmetic::from_f64(f64::INFINITY),
+            FixedPointArithmetic(i64::MAX)
+        );
+        assert_eq!(
+            FixedPointArithmetic::from_f64(f64::NEG_INFINITY),
+            FixedPointArithmetic(i64::MIN)
+        );
+        assert_eq!(
+            FixedPointArithmetic::from_f64(-1.5),
+            FixedPointArithmetic(-150_000_000)
+        );
+    }
+
+    #[test]
+    fn test_try_from_f64_rejects_non_finite_and_out_of_range() {
+        assert_eq!(
+            FixedPointArithmetic::try_from_f64(123.456),
+            Some(FixedPointArithmetic(12_345_600_000))
+        );
+        assert_eq!(FixedPointArithmetic::try_from_f64(f64::NAN), None);
+        assert_eq!(FixedPointArithmetic::try_from_f64(f64::INFINITY), None);
+        assert_eq!(FixedPointArithmetic::try_from_f64(f64::NEG_INFINITY), None);
+        // Beyond i64::MAX / SCALE ≈ 9.2e10 units
+        assert_eq!(FixedPointArithmetic::try_from_f64(1e11), None);
+        assert_eq!(FixedPointArithmetic::try_from_f64(-1e11), None);
+    }
+}
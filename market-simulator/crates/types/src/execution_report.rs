@@ -6,7 +6,7 @@ pub struct ExecReportData {
     pub cl_ord_id: String, // Effective ClOrdId of the target order
     pub symbol: String,    // FIX field 55
     pub side: u8,          // FIX field 54 (1=Buy, 2=Sell)
-    pub ord_status: u8,    // FIX field 39 (0=New, 1=PartialFill, 2=Fill, 3=DoneForDay, 4=Canceled)
+    pub ord_status: u8,    // FIX field 39 (0=New, 1=PartialFill, 2=Fill, 3=DoneForDay, 4=Canceled, 8=Rejected)
     pub price: f64,        // FIX field 44
     pub qty: f64,          // FIX field 38
     pub leaves_qty: f64,   // FIX field 151
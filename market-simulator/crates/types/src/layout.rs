@@ -0,0 +1,144 @@
+use crate::arithmetic::FixedPointArithmetic;
+use crate::macros::OrderId;
+use crate::order::{OrderEvent, OrderResult};
+use crate::trade::Trade;
+use std::mem::{align_of, size_of};
+
+/// Version of the memory layout of the types exchanged through shared memory.
+/// It is written into the header of every shared queue, a process built with another version refuses to attach.
+/// Bump it whenever one of the sizes or alignments below changes, or when fields are reordered or retyped.
+pub const LAYOUT_VERSION: u32 = 3;
+
+/// Recorded `(size, align)` of each type exchanged through shared memory, for `LAYOUT_VERSION`.
+/// Trade ids are plain `u64`, pinned by the layout of `Trade`.
+pub const ORDER_EVENT_LAYOUT: (usize, usize) = (120, 8);
+pub const ORDER_RESULT_LAYOUT: (usize, usize) = (400, 8);
+pub const TRADE_LAYOUT: (usize, usize) = (88, 8);
+pub const PRICE_LAYOUT: (usize, usize) = (8, 8);
+pub const ORDER_ID_LAYOUT: (usize, usize) = (20, 1);
+
+/// Fails the build when the layout of `T` no longer matches its recorded `(size, align)`.
+macro_rules! assert_layout {
+    ($type:ty, $layout:expr) => {
+        const _: () = assert!(
+            size_of::<$type>() == $layout.0 && align_of::<$type>() == $layout.1,
+            concat!(
+                "layout of ",
+                stringify!($type),
+                " changed: bump LAYOUT_VERSION and update the recorded layouts in types/src/layout.rs"
+            )
+        );
+    };
+}
+
+assert_layout!(OrderEvent, ORDER_EVENT_LAYOUT);
+assert_layout!(OrderResult, ORDER_RESULT_LAYOUT);
+assert_layout!(Trade, TRADE_LAYOUT);
+assert_layout!(FixedPointArithmetic, PRICE_LAYOUT);
+assert_layout!(OrderId, ORDER_ID_LAYOUT);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order::{OrderStatus, OrderType, Side};
+    use crate::trade::Trades;
+    use crate::{EntityId, SymbolId};
+    use std::mem::offset_of;
+
+    /// Layouts of layout version 3, as recorded when `Trade::sender_id` was added after `Trade::cl_ord_id`.
+    /// When this test fails, the layout of a shared-memory type changed: processes built before and after the change
+    /// would read garbage from each other's queues. Make sure the change is intended, bump `LAYOUT_VERSION`, then
+    /// update both this table and the recorded layouts of layout.rs.
+    const GOLDEN: &[(&str, usize, usize)] = &[
+        ("OrderEvent", 120, 8),
+        ("OrderResult", 400, 8),
+        ("Trade", 88, 8),
+        ("Trades<4>", 360, 8),
+        ("Price", 8, 8),
+        ("OrderId", 20, 1),
+        ("EntityId", 20, 1),
+        ("SymbolId", 4, 1),
+        ("Side", 1, 1),
+        ("OrderType", 1, 1),
+        ("OrderStatus", 1, 1),
+    ];
+
+    fn layouts() -> Vec<(&'static str, usize, usize)> {
+        vec![
+            (
+                "OrderEvent",
+                size_of::<OrderEvent>(),
+                align_of::<OrderEvent>(),
+            ),
+            (
+                "OrderResult",
+                size_of::<OrderResult>(),
+                align_of::<OrderResult>(),
+            ),
+            ("Trade", size_of::<Trade>(), align_of::<Trade>()),
+            ("Trades<4>", size_of::<Trades<4>>(), align_of::<Trades<4>>()),
+            (
+                "Price",
+                size_of::<FixedPointArithmetic>(),
+                align_of::<FixedPointArithmetic>(),
+            ),
+            ("OrderId", size_of::<OrderId>(), align_of::<OrderId>()),
+            ("EntityId", size_of::<EntityId>(), align_of::<EntityId>()),
+            ("SymbolId", size_of::<SymbolId>(), align_of::<SymbolId>()),
+            ("Side", size_of::<Side>(), align_of::<Side>()),
+            ("OrderType", size_of::<OrderType>(), align_of::<OrderType>()),
+            (
+                "OrderStatus",
+                size_of::<OrderStatus>(),
+                align_of::<OrderStatus>(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_layouts_match_golden() {
+        assert_eq!(
+            LAYOUT_VERSION, 3,
+            "update GOLDEN to the layouts of the new LAYOUT_VERSION"
+        );
+        for (actual, golden) in layouts().iter().zip(GOLDEN) {
+            assert_eq!(
+                actual, golden,
+                "shared-memory layout of {} changed from (size, align) = ({}, {}) to ({}, {}): if intended, bump \
+                 LAYOUT_VERSION and record the new layouts in GOLDEN and in types/src/layout.rs",
+                golden.0, golden.1, golden.2, actual.1, actual.2
+            );
+        }
+        assert_eq!(layouts().len(), GOLDEN.len());
+    }
+
+    #[test]
+    fn test_field_order_is_declaration_order() {
+        // repr(C): a reorder of the fields moves their offsets even when the size is unchanged
+        assert_eq!(offset_of!(OrderEvent, price), 0);
+        assert_eq!(offset_of!(OrderEvent, quantity), 8);
+        assert_eq!(offset_of!(OrderEvent, side), 16);
+        assert_eq!(offset_of!(OrderEvent, cl_ord_id), 22);
+        assert_eq!(offset_of!(OrderEvent, timestamp_ms), 104);
+        assert_eq!(offset_of!(OrderEvent, min_quantity), 112);
+        assert_eq!(offset_of!(OrderResult, trades), 8);
+        assert_eq!(offset_of!(OrderResult, status), 368);
+        assert_eq!(offset_of!(Trade, cl_ord_id), 24);
+        assert_eq!(offset_of!(Trade, sender_id), 44);
+        assert_eq!(offset_of!(Trade, timestamp), 80);
+    }
+
+    #[test]
+    fn test_enum_discriminants_are_stable() {
+        assert_eq!(Side::Sell as u8, 1);
+        assert_eq!(OrderType::CancelOrder as u8, 2);
+        assert_eq!(OrderType::ImmediateOrCancel as u8, 3);
+        assert_eq!(OrderType::FillOrKill as u8, 4);
+        assert_eq!(OrderStatus::RejectedLevelFull as u8, 6);
+        assert_eq!(OrderStatus::RejectedInvalidQuantity as u8, 7);
+        assert_eq!(OrderStatus::RejectedInvalidPrice as u8, 8);
+        assert_eq!(OrderStatus::RejectedInvalidLot as u8, 9);
+        assert_eq!(OrderStatus::RejectedMinQuantity as u8, 10);
+        assert_eq!(OrderStatus::RejectedExposureLimit as u8, 11);
+    }
+}
@@ -1,15 +1,17 @@
 pub mod arithmetic;
 pub mod consts;
 pub mod execution_report;
+pub mod layout;
 pub mod macros;
 pub mod metrics;
 pub mod multicast;
 pub mod order;
 pub mod trade;
 
-pub use arithmetic::FixedPointArithmetic;
+pub use arithmetic::{FixedPointArithmetic, Notional, Vwap};
 pub use consts::*;
 pub use execution_report::{ExecReportData, ExecutionReportMessage};
+pub use layout::LAYOUT_VERSION;
 pub use macros::{EntityId, OrderId, SymbolId};
 pub use metrics::MarketMetrics;
 pub use order::*;
@@ -5,6 +5,7 @@ macro_rules! define_id {
 
     ($name:ident, $size:expr) => {
         #[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Hash)]
+        #[repr(transparent)]
         pub struct $name(pub [u8; $size]);
 
         impl std::ops::Deref for $name {
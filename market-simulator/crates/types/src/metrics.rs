@@ -26,6 +26,8 @@ pub struct MarketMetrics {
     pub order_book_events: Arc<AtomicUsize>,
     /// Latency from order book event dequeue to fan-out completion (milliseconds)
     pub order_book_event_to_fanout_latency_ms: Arc<Mutex<Vec<u64>>>,
+    /// Total order results dropped by the order book engines because their result queue stayed full
+    pub order_result_queue_dropped: Arc<AtomicUsize>,
     /// Latency from publish to browser send completion (microseconds)
     pub websocket_fanout_to_browser_latency_us: Arc<Mutex<Vec<u64>>>,
     /// Last observed order book size when broadcasting an order book event
@@ -77,6 +79,7 @@ impl MarketMetrics {
             execution_latency_ms: Arc::new(Mutex::new(Vec::new())),
             order_book_events: Arc::new(AtomicUsize::new(0)),
             order_book_event_to_fanout_latency_ms: Arc::new(Mutex::new(Vec::new())),
+            order_result_queue_dropped: Arc::new(AtomicUsize::new(0)),
             websocket_fanout_to_browser_latency_us: Arc::new(Mutex::new(Vec::new())),
             websocket_fanout_order_book_levels: Arc::new(AtomicUsize::new(0)),
             websocket_lagged_events: Arc::new(AtomicUsize::new(0)),
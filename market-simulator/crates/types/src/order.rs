@@ -18,7 +18,9 @@ use std::time::{SystemTime, UNIX_EPOCH};
 /// - `order_type`: The type of the order (limit or market).
 /// - `id`: A unique identifier for the order.
 /// - `broker_id`: The identifier of the broker placing the order.
+/// - `min_quantity`: FIX MinQty (110), the order only trades when at least this quantity executes, zero when absent.
 #[derive(Debug, Clone, Copy)]
+#[repr(C)]
 pub struct OrderEvent {
     pub price: FixedPointArithmetic,
     pub quantity: FixedPointArithmetic, // In FIX, qty is a float but we will use integer for simplicity (e.g. 100.0 -> 100)
@@ -30,6 +32,7 @@ pub struct OrderEvent {
     pub sender_id: EntityId, // FIX SenderCompID can be up to 20 characters, we will use a fixed-size array for simplicity
     pub target_id: EntityId, // FIX TargetCompID can be up to 20 characters, we will use a fixed-size array for simplicity
     pub timestamp_ms: u64, // Timestamp in milliseconds since epoch, added for potential future use in time-priority sorting
+    pub min_quantity: FixedPointArithmetic, // FIX MinQty, zero when the order has no minimum, see `OrderBook::process_order`
 }
 
 impl Default for OrderEvent {
@@ -48,6 +51,7 @@ impl Default for OrderEvent {
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
                 .as_millis() as u64, // current time in milliseconds
+            min_quantity: FixedPointArithmetic::ZERO,
         }
     }
 }
@@ -66,7 +70,8 @@ impl std::fmt::Display for OrderEvent {
             \tsender_id: {}
             \ttarget_id: {}
             \tsymbol: {}
-            \ttimestamp: {}",
+            \ttimestamp: {}
+            \tmin_quantity: {}",
             self.price.raw(),
             self.quantity,
             self.side,
@@ -78,7 +83,8 @@ impl std::fmt::Display for OrderEvent {
             self.sender_id,
             self.target_id,
             self.symbol,
-            self.timestamp_ms
+            self.timestamp_ms,
+            self.min_quantity
         )
     }
 }
@@ -107,9 +113,45 @@ impl OrderEvent {
             target_id,
             symbol,
             timestamp_ms,
+            min_quantity: FixedPointArithmetic::ZERO,
         }
     }
 
+    /// Starts an `OrderEventBuilder` from the `Default` order: a buy limit order with zero
+    /// price and quantity, empty ids and the current timestamp.
+    ///
+    /// ```
+    /// use types::{EntityId, FixedPointArithmetic, OrderEvent, OrderType, Side};
+    ///
+    /// let order = OrderEvent::builder()
+    ///     .sell()
+    ///     .price_f64(101.5)
+    ///     .qty(10.0)
+    ///     .sender_id(EntityId::from_ascii("SENDER"))
+    ///     .build();
+    ///
+    /// assert_eq!(order.side, Side::Sell);
+    /// assert_eq!(order.order_type, OrderType::LimitOrder);
+    /// assert_eq!(order.price, FixedPointArithmetic::from_f64(101.5));
+    /// assert_eq!(order.quantity, FixedPointArithmetic::from_f64(10.0));
+    /// ```
+    pub fn builder() -> OrderEventBuilder {
+        OrderEventBuilder::default()
+    }
+
+    /// End-of-stream sentinel pushed by a producer once it has no more orders to send.
+    /// A real order always carries a sender_id (see `check_valid`), so an empty one marks the end of the stream.
+    pub fn end_of_stream() -> Self {
+        Self {
+            sender_id: EntityId::default(),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_end_of_stream(&self) -> bool {
+        self.sender_id.0.iter().all(|&b| b == 0)
+    }
+
     pub fn check_valid(&self) -> Result<(), &'static str> {
         if self.side != Side::Buy && self.side != Side::Sell {
             return Err("Invalid side");
@@ -155,9 +197,15 @@ impl OrderEvent {
         if self.quantity == FixedPointArithmetic::ZERO {
             return Err("Quantity cannot be zero");
         }
+        if self.quantity < FixedPointArithmetic::ZERO {
+            return Err("Quantity cannot be negative");
+        }
         if self.price == FixedPointArithmetic::ZERO {
             return Err("Price cannot be zero");
         }
+        if self.price < FixedPointArithmetic::ZERO {
+            return Err("Price cannot be negative");
+        }
         Ok(())
     }
 }
@@ -176,22 +224,146 @@ impl PartialOrd for OrderEvent {
     }
 }
 
+/// Orders events by price only. Time priority within a price is kept by the arrival order of the book levels,
+/// not by this ordering, see `OrderBook`.
 impl Ord for OrderEvent {
     fn cmp(&self, other: &Self) -> Ordering {
-        // Higher price first
         self.price.cmp(&other.price)
     }
 }
 
+/// Fluent builder for `OrderEvent`, see `OrderEvent::builder`.
+///
+/// ```
+/// use types::{OrderEvent, OrderId, OrderType};
+///
+/// let cancel = OrderEvent::builder()
+///     .cancel(OrderId::from_ascii("12345"))
+///     .cl_ord_id(OrderId::from_ascii("12346"))
+///     .build();
+///
+/// assert_eq!(cancel.order_type, OrderType::CancelOrder);
+/// assert_eq!(cancel.orig_cl_ord_id, Some(OrderId::from_ascii("12345")));
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OrderEventBuilder {
+    event: OrderEvent,
+}
+
+impl OrderEventBuilder {
+    pub fn price(mut self, price: FixedPointArithmetic) -> Self {
+        self.event.price = price;
+        self
+    }
+
+    pub fn price_f64(self, price: f64) -> Self {
+        self.price(FixedPointArithmetic::from_f64(price))
+    }
+
+    pub fn quantity(mut self, quantity: FixedPointArithmetic) -> Self {
+        self.event.quantity = quantity;
+        self
+    }
+
+    pub fn qty(self, quantity: f64) -> Self {
+        self.quantity(FixedPointArithmetic::from_f64(quantity))
+    }
+
+    pub fn side(mut self, side: Side) -> Self {
+        self.event.side = side;
+        self
+    }
+
+    pub fn buy(self) -> Self {
+        self.side(Side::Buy)
+    }
+
+    pub fn sell(self) -> Self {
+        self.side(Side::Sell)
+    }
+
+    pub fn order_type(mut self, order_type: OrderType) -> Self {
+        self.event.order_type = order_type;
+        self
+    }
+
+    pub fn limit(self) -> Self {
+        self.order_type(OrderType::LimitOrder)
+    }
+
+    pub fn market(self) -> Self {
+        self.order_type(OrderType::MarketOrder)
+    }
+
+    pub fn immediate_or_cancel(self) -> Self {
+        self.order_type(OrderType::ImmediateOrCancel)
+    }
+
+    pub fn fill_or_kill(self) -> Self {
+        self.order_type(OrderType::FillOrKill)
+    }
+
+    /// Turns the order into a cancel of `orig_cl_ord_id`.
+    pub fn cancel(self, orig_cl_ord_id: OrderId) -> Self {
+        self.order_type(OrderType::CancelOrder)
+            .orig_cl_ord_id(orig_cl_ord_id)
+    }
+
+    pub fn symbol(mut self, symbol: SymbolId) -> Self {
+        self.event.symbol = symbol;
+        self
+    }
+
+    pub fn cl_ord_id(mut self, cl_ord_id: OrderId) -> Self {
+        self.event.cl_ord_id = cl_ord_id;
+        self
+    }
+
+    pub fn orig_cl_ord_id(mut self, orig_cl_ord_id: OrderId) -> Self {
+        self.event.orig_cl_ord_id = Some(orig_cl_ord_id);
+        self
+    }
+
+    pub fn sender_id(mut self, sender_id: EntityId) -> Self {
+        self.event.sender_id = sender_id;
+        self
+    }
+
+    pub fn target_id(mut self, target_id: EntityId) -> Self {
+        self.event.target_id = target_id;
+        self
+    }
+
+    pub fn timestamp_ms(mut self, timestamp_ms: u64) -> Self {
+        self.event.timestamp_ms = timestamp_ms;
+        self
+    }
+
+    pub fn min_quantity(mut self, min_quantity: FixedPointArithmetic) -> Self {
+        self.event.min_quantity = min_quantity;
+        self
+    }
+
+    pub fn min_qty(self, min_quantity: f64) -> Self {
+        self.min_quantity(FixedPointArithmetic::from_f64(min_quantity))
+    }
+
+    pub fn build(self) -> OrderEvent {
+        self.event
+    }
+}
+
 // ---------------------------------------
 // ---- OrderResult and related types ----
 // ---------------------------------------
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
 pub struct OrderResult {
     pub internal_order_id: u64, // Internal order ID assigned by the engine, can be used for tracking and debugging
     pub trades: Trades<4>,      // Fixed-size array for trades, adjust size as needed
     pub status: OrderStatus,
     pub timestamp_ms: u64, // Timestamp in milliseconds since epoch, added for potential future use in time-priority sorting
+    pub arrival_mid: Option<FixedPointArithmetic>, // Midpoint of the book when the order arrived, None if a side was empty
 }
 
 impl Default for OrderResult {
@@ -204,6 +376,7 @@ impl Default for OrderResult {
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
                 .as_millis() as u64,
+            arrival_mid: None,
         }
     }
 }
@@ -235,6 +408,7 @@ impl std::fmt::Display for OrderResult {
 
 /// Represents the side of an order (buy or sell).
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
 pub enum Side {
     Buy,
     Sell,
@@ -249,12 +423,17 @@ impl std::fmt::Display for Side {
     }
 }
 
-/// Represents the type of an order (limit or market).
+/// Represents the type of an order (limit, market, cancel, immediate-or-cancel or fill-or-kill).
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
 pub enum OrderType {
     LimitOrder,
     MarketOrder,
     CancelOrder,
+    /// Limit order whose quantity not filled on arrival is cancelled instead of resting in the book.
+    ImmediateOrCancel,
+    /// Limit order that trades its whole quantity on arrival or not at all, leaving the book unchanged.
+    FillOrKill,
 }
 
 impl std::fmt::Display for OrderType {
@@ -263,6 +442,8 @@ impl std::fmt::Display for OrderType {
             OrderType::LimitOrder => write!(f, "Limit Order"),
             OrderType::MarketOrder => write!(f, "Market Order"),
             OrderType::CancelOrder => write!(f, "Cancel Order"),
+            OrderType::ImmediateOrCancel => write!(f, "Immediate Or Cancel Order"),
+            OrderType::FillOrKill => write!(f, "Fill Or Kill Order"),
         }
     }
 }
@@ -271,9 +452,16 @@ impl std::fmt::Display for OrderType {
 /// - `New`: The order is new and has not been processed yet.
 /// - `PartiallyFilled`: The order has been partially filled, meaning some quantity has been matched, but there is still remaining quantity in the order book.
 /// - `Filled`: The order has been completely filled, meaning all quantity has been matched and there is no remaining quantity in the order book.
-/// - `NotMatched`: The order could not be matched with any existing orders in the order book, and remains in the order book as a new order.
-/// - `Canceled`: The order has been canceled and removed from the order book.
+/// - `NotMatched`: The order could not be matched with any existing orders in the order book, and remains in the order book as a new order. A fill-or-kill order whose full quantity could not be matched is killed with this status, without trades and without resting.
+/// - `Canceled`: The order has been canceled and removed from the order book, or it is an immediate-or-cancel order that found nothing to trade with.
+/// - `RejectedLevelFull`: The limit order was rejected because its price level already holds the maximum number of orders.
+/// - `RejectedInvalidQuantity`: The order was rejected because its quantity is zero or negative.
+/// - `RejectedInvalidPrice`: The limit order was rejected because its price is zero or negative.
+/// - `RejectedInvalidLot`: The order was rejected because its quantity is not a multiple of the lot size of the book.
+/// - `RejectedMinQuantity`: The order was rejected because less than its minimum quantity was immediately executable.
+/// - `RejectedExposureLimit`: The order was rejected because it would take the gross exposure of its sender across all symbols past the configured limit.
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
+#[repr(u8)]
 pub enum OrderStatus {
     New,
     PartiallyFilled,
@@ -281,6 +469,27 @@ pub enum OrderStatus {
     Cancelled,
     CancelRejected,
     Unmatched,
+    RejectedLevelFull,
+    RejectedInvalidQuantity,
+    RejectedInvalidPrice,
+    RejectedInvalidLot,
+    RejectedMinQuantity,
+    RejectedExposureLimit,
+}
+
+impl OrderStatus {
+    /// Whether the order book rejected the order: it was neither matched nor added to the book.
+    pub fn is_rejected(self) -> bool {
+        matches!(
+            self,
+            OrderStatus::RejectedLevelFull
+                | OrderStatus::RejectedInvalidQuantity
+                | OrderStatus::RejectedInvalidPrice
+                | OrderStatus::RejectedInvalidLot
+                | OrderStatus::RejectedMinQuantity
+                | OrderStatus::RejectedExposureLimit
+        )
+    }
 }
 
 impl std::fmt::Display for OrderStatus {
@@ -292,6 +501,12 @@ impl std::fmt::Display for OrderStatus {
             OrderStatus::Cancelled => write!(f, "Cancelled"),
             OrderStatus::CancelRejected => write!(f, "Cancel Rejected"),
             OrderStatus::Unmatched => write!(f, "Unmatched"),
+            OrderStatus::RejectedLevelFull => write!(f, "Rejected (Level Full)"),
+            OrderStatus::RejectedInvalidQuantity => write!(f, "Rejected (Invalid Quantity)"),
+            OrderStatus::RejectedInvalidPrice => write!(f, "Rejected (Invalid Price)"),
+            OrderStatus::RejectedInvalidLot => write!(f, "Rejected (Invalid Lot)"),
+            OrderStatus::RejectedMinQuantity => write!(f, "Rejected (Minimum Quantity)"),
+            OrderStatus::RejectedExposureLimit => write!(f, "Rejected (Exposure Limit)"),
         }
     }
 }
@@ -1,5 +1,5 @@
-use crate::arithmetic::FixedPointArithmetic;
-use crate::macros::OrderId;
+use crate::arithmetic::{FixedPointArithmetic, Notional, Vwap};
+use crate::macros::{EntityId, OrderId};
 use std::ops::Index;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -9,13 +9,16 @@ use std::time::{SystemTime, UNIX_EPOCH};
 /// - `quantity`: The quantity that was traded.
 /// - `id`: A unique identifier for the trade.
 /// - `cl_ord_id`: Client order ID of the matched resting order involved in this fill.
+/// - `sender_id`: SenderCompID of the matched resting order, the session its trade reports are routed to.
 /// - `timestamp`: The timestamp when the trade occurred.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
 pub struct Trade {
     pub price: FixedPointArithmetic,
     pub quantity: FixedPointArithmetic,
     pub id: u64, // Trade ID can be up to 20 characters, we will use a fixed-size array for simplicity
     pub cl_ord_id: OrderId,
+    pub sender_id: EntityId,
     pub order_qty: FixedPointArithmetic,
     pub leaves_qty: FixedPointArithmetic,
     pub timestamp: u64, // Timestamp in milliseconds since epoch, added for potential future use in time-priority sorting
@@ -28,6 +31,7 @@ impl Default for Trade {
             quantity: FixedPointArithmetic::ZERO,
             id: 0,
             cl_ord_id: OrderId::default(),
+            sender_id: EntityId::default(),
             order_qty: FixedPointArithmetic::ZERO,
             leaves_qty: FixedPointArithmetic::ZERO,
             timestamp: SystemTime::now()
@@ -62,7 +66,8 @@ impl std::fmt::Display for Trade {
 }
 
 /// Represents the result of processing an order, including any trades that occurred and the final status of the order.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Eq)]
+#[repr(C)]
 pub struct Trades<const N: usize> {
     pub trades: [Trade; N], // Fixed-size array for trades, adjust size as needed
     count: usize,           // Number of valid trades in the array
@@ -76,6 +81,7 @@ impl<const N: usize> Default for Trades<N> {
                 quantity: FixedPointArithmetic::ZERO,
                 id: 0,
                 cl_ord_id: OrderId::default(),
+                sender_id: EntityId::default(),
                 order_qty: FixedPointArithmetic::ZERO,
                 leaves_qty: FixedPointArithmetic::ZERO,
                 timestamp: SystemTime::now()
@@ -107,6 +113,10 @@ impl<const N: usize> Trades<N> {
         self.trades[..self.count].iter()
     }
 
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Trade> {
+        self.trades[..self.count].iter_mut()
+    }
+
     pub fn len(&self) -> usize {
         self.count
     }
@@ -117,20 +127,27 @@ impl<const N: usize> Trades<N> {
         })
     }
 
-    pub fn avg_price(&self) -> FixedPointArithmetic {
-        if self.count == 0 {
-            return FixedPointArithmetic::ZERO;
-        }
+    /// Total traded notional.
+    pub fn notional(&self) -> Notional {
+        self.iter()
+            .map(|trade| trade.price.mul_qty(trade.quantity))
+            .sum()
+    }
 
-        let total_quantity = self.quantity_sum();
-        if total_quantity == FixedPointArithmetic::ZERO {
-            return FixedPointArithmetic::ZERO;
+    /// Volume weighted average price of the trades, zero when nothing traded.
+    pub fn avg_price(&self) -> FixedPointArithmetic {
+        let mut vwap = Vwap::new();
+        for trade in self.iter() {
+            vwap.add(trade.price, trade.quantity);
         }
+        vwap.price().unwrap_or(FixedPointArithmetic::ZERO)
+    }
+}
 
-        let total_value = self.iter().fold(FixedPointArithmetic::ZERO, |acc, trade| {
-            acc + (trade.price * trade.quantity)
-        });
-        total_value / total_quantity
+/// Only the valid trades are compared, the unused slots keep whatever they were initialized with.
+impl<const N: usize> PartialEq for Trades<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
     }
 }
 
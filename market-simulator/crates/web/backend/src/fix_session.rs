@@ -12,6 +12,7 @@ use std::time::Duration;
 
 use crossbeam_channel::TrySendError;
 use fix::engine::FixRawMsg;
+use fix::framing::{FramingLimits, check_message};
 use sqlx::PgPool;
 use tokio::sync::mpsc;
 use types::consts::RB_SIZE;
@@ -79,13 +80,19 @@ pub struct FIXSession {
 #[derive(Clone)]
 pub struct FIXSessionManager {
     fix_tx: Arc<crossbeam_channel::Sender<FixRawMsg<RB_SIZE>>>,
+    /// Limits every message sent through `send_fix` is checked against.
+    framing_limits: FramingLimits,
     inner: Arc<Mutex<HashMap<String, FIXSession>>>,
 }
 
 impl FIXSessionManager {
-    pub fn new(fix_tx: Arc<crossbeam_channel::Sender<FixRawMsg<RB_SIZE>>>) -> Self {
+    pub fn new(
+        fix_tx: Arc<crossbeam_channel::Sender<FixRawMsg<RB_SIZE>>>,
+        framing_limits: FramingLimits,
+    ) -> Self {
         FIXSessionManager {
             fix_tx,
+            framing_limits,
             inner: Arc::new(Mutex::new(HashMap::new())),
         }
     }
@@ -166,6 +173,17 @@ impl FIXSessionManager {
                                 let raw_msg = exec_msg.fix_data[..exec_msg.fix_len as usize].to_vec();
                                 let body = pretty_fix_for_display(&raw_msg);
 
+                                // Market data replies carry no execution report, they are only displayed
+                                if is_market_data_msg(&raw_msg) {
+                                    bus.publish(WsEvent::FixMessage {
+                                        label: classify_fix_msg(&raw_msg),
+                                        body,
+                                        tag: "feed".into(),
+                                        recipient: Some(username.clone()),
+                                    });
+                                    continue;
+                                }
+
                                 // Apply execution report via gRPC to player service
                                 let mut client = player_client.lock().await;
                                 if let Err(e) =
@@ -318,6 +336,8 @@ impl FIXSessionManager {
                 RB_SIZE
             ));
         }
+        check_message(bytes, username, self.framing_limits)
+            .map_err(|violation| format!("FIX message rejected: framing violation {violation}"))?;
 
         let response_tx = self.get_or_create_session(
             username,
@@ -426,6 +446,13 @@ pub fn classify_fix_msg(raw: &[u8]) -> String {
     }
 }
 
+/// Whether a FIX response is a market data reply (W, X or Y) rather than an execution report.
+fn is_market_data_msg(raw: &[u8]) -> bool {
+    raw.split(|&b| b == 0x01)
+        .find_map(|f| f.strip_prefix(b"35="))
+        .is_some_and(|msg_type| matches!(msg_type, b"W" | b"X" | b"Y"))
+}
+
 /// Update backend order book based on ExecutionReport status.
 fn update_backend_order_book_from_exec_report(
     exec: &ExecReportData,
@@ -227,6 +227,15 @@ pub async fn metrics_handler(
         ob_count
     ));
 
+    buffer.push_str("# HELP order_result_queue_dropped_total Total order results dropped because the result queue stayed full\n");
+    buffer.push_str("# TYPE order_result_queue_dropped_total counter\n");
+    buffer.push_str(&format!(
+        "order_result_queue_dropped_total {}\n",
+        metrics
+            .order_result_queue_dropped
+            .load(std::sync::atomic::Ordering::Relaxed)
+    ));
+
     buffer
         .push_str("# HELP execution_report_events_total Total execution report events processed\n");
     buffer.push_str("# TYPE execution_report_events_total counter\n");
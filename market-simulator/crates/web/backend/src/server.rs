@@ -12,6 +12,7 @@ use axum::{
 };
 use db;
 use fix::engine::FixRawMsg;
+use fix::framing::FramingLimits;
 use sqlx::PgPool;
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::error::Error;
@@ -77,6 +78,7 @@ pub struct AppState {
 pub fn run_web_server(
     bus: EventBus,
     fix_tx: Arc<crossbeam_channel::Sender<FixRawMsg<RB_SIZE>>>,
+    framing_limits: FramingLimits,
     metrics: Arc<Metrics>,
     grpc_addr: String,
     ip: &str,
@@ -99,6 +101,7 @@ pub fn run_web_server(
         .block_on(serve(
             bus,
             fix_tx,
+            framing_limits,
             metrics,
             grpc_addr,
             ip,
@@ -122,6 +125,7 @@ pub fn run_web_server(
 async fn serve(
     bus: EventBus,
     fix_tx: Arc<crossbeam_channel::Sender<FixRawMsg<RB_SIZE>>>,
+    framing_limits: FramingLimits,
     metrics: Arc<Metrics>,
     grpc_addr: String,
     ip: &str,
@@ -202,7 +206,7 @@ async fn serve(
         order_book,
         active_visitors: Arc::new(AtomicUsize::new(0)),
         total_visitors: Arc::new(AtomicUsize::new(0)),
-        fix_session_manager: FIXSessionManager::new(fix_tx),
+        fix_session_manager: FIXSessionManager::new(fix_tx, framing_limits),
         trades_queue,
         market_db_pool,
         supported_symbols: supported_symbols.into_iter().collect(),
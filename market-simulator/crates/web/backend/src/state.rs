@@ -92,6 +92,10 @@ pub enum BrowserCommand {
     ResetTokens,
     ResetSeq,
     ClearBook,
+    Stats,
+    Positions {
+        sender: String,
+    },
     Disconnect,
     UiOrderLatency {
         latency_ms: u64,
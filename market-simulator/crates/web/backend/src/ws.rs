@@ -11,6 +11,7 @@ use axum::{
     http::StatusCode,
     response::IntoResponse,
 };
+use fix::framing::FRAMING_VIOLATIONS;
 use futures::{sink::SinkExt, stream::StreamExt};
 use players::players::PendingOrder;
 use serde::Deserialize;
@@ -18,6 +19,7 @@ use sha2::{Digest, Sha256};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Instant;
+use types::{FixedPointArithmetic, Notional};
 use utils::market_name;
 
 const PLAYER_STATE_FEED_DEBOUNCE_MS: u64 = 75;
@@ -744,15 +746,21 @@ async fn handle_browser_message(
                 .await;
 
             if side == "1" {
-                let required_notional = qty * price;
+                let required_notional = FixedPointArithmetic::from_f64(price)
+                    .mul_qty(FixedPointArithmetic::from_f64(qty))
+                    .to_f64();
                 if required_notional.is_finite() && required_notional > 0.0 {
                     if let Some(player) = &player_state {
                         let reserved_notional: f64 = player
                             .pending_orders
                             .iter()
                             .filter(|order| order.side == "1")
-                            .map(|order| order.qty * order.price)
-                            .sum();
+                            .map(|order| {
+                                FixedPointArithmetic::from_f64(order.price)
+                                    .mul_qty(FixedPointArithmetic::from_f64(order.qty))
+                            })
+                            .sum::<Notional>()
+                            .to_f64();
 
                         let available_tokens = player.tokens - reserved_notional;
                         if available_tokens + 1e-9 < required_notional {
@@ -1167,6 +1175,86 @@ async fn handle_browser_message(
             }
         }
 
+        BrowserCommand::Stats => {
+            if !require_admin(&state.bus, username, is_admin) {
+                return;
+            }
+            use grpc::proto::GetStatsRequest;
+            use grpc::proto::market_control_client::MarketControlClient;
+            let (label, body, tag) =
+                match MarketControlClient::connect(state.grpc_addr.clone()).await {
+                    Ok(mut client) => match client.get_stats(GetStatsRequest {}).await {
+                        Ok(resp) => {
+                            let r = resp.into_inner();
+                            if r.success {
+                                let mut lines =
+                                    r.stats.iter().map(format_symbol_stats).collect::<Vec<_>>();
+                                lines.push(FRAMING_VIOLATIONS.summary());
+                                ("STATS".to_string(), lines.join("\n"), "info")
+                            } else {
+                                (format!("STATS FAILED: {}", r.message), r.message, "err")
+                            }
+                        }
+                        Err(e) => (
+                            "ERROR".to_string(),
+                            format!("gRPC GetStats call failed: {e}"),
+                            "err",
+                        ),
+                    },
+                    Err(e) => (
+                        "ERROR".to_string(),
+                        format!("Failed to connect to gRPC server: {e}"),
+                        "err",
+                    ),
+                };
+            state.bus.publish(WsEvent::FixMessage {
+                label,
+                body,
+                tag: tag.into(),
+                recipient: Some(username.to_string()),
+            });
+        }
+
+        BrowserCommand::Positions { sender } => {
+            if !require_admin(&state.bus, username, is_admin) {
+                return;
+            }
+            use grpc::proto::GetPositionsRequest;
+            use grpc::proto::market_control_client::MarketControlClient;
+            let (label, body, tag) =
+                match MarketControlClient::connect(state.grpc_addr.clone()).await {
+                    Ok(mut client) => match client
+                        .get_positions(GetPositionsRequest { sender_id: sender })
+                        .await
+                    {
+                        Ok(resp) => {
+                            let r = resp.into_inner();
+                            if r.success {
+                                ("POSITIONS".to_string(), format_positions(&r), "info")
+                            } else {
+                                (format!("POSITIONS FAILED: {}", r.message), r.message, "err")
+                            }
+                        }
+                        Err(e) => (
+                            "ERROR".to_string(),
+                            format!("gRPC GetPositions call failed: {e}"),
+                            "err",
+                        ),
+                    },
+                    Err(e) => (
+                        "ERROR".to_string(),
+                        format!("Failed to connect to gRPC server: {e}"),
+                        "err",
+                    ),
+                };
+            state.bus.publish(WsEvent::FixMessage {
+                label,
+                body,
+                tag: tag.into(),
+                recipient: Some(username.to_string()),
+            });
+        }
+
         BrowserCommand::ResetTokens => {
             if !require_admin(&state.bus, username, is_admin) {
                 return;
@@ -1213,6 +1301,62 @@ async fn handle_browser_message(
     }
 }
 
+fn format_side_stats(stats: Option<&grpc::proto::SideStats>) -> String {
+    match stats {
+        Some(grpc::proto::SideStats {
+            trades,
+            avg_effective_spread: Some(spread),
+            price_improvement_pct: Some(improvement),
+            ..
+        }) => format!(
+            "{trades} trades, avg effective spread {spread:.4}, price improvement {improvement:.2}%"
+        ),
+        _ => "no trades".to_string(),
+    }
+}
+
+/// One line per symbol of the admin `stats` command.
+fn format_symbol_stats(stats: &grpc::proto::SymbolStats) -> String {
+    format!(
+        "{}: all {} | buy {} | sell {} | excluded {} into an empty side, {} without a midpoint",
+        stats.symbol,
+        format_side_stats(stats.all.as_ref()),
+        format_side_stats(stats.buy.as_ref()),
+        format_side_stats(stats.sell.as_ref()),
+        stats.empty_opposite_side,
+        stats.no_midpoint
+    )
+}
+
+/// One line per symbol of the admin `positions` command, then the gross exposure of the participant.
+fn format_positions(response: &grpc::proto::GetPositionsResponse) -> String {
+    let mut lines: Vec<String> = response
+        .positions
+        .iter()
+        .map(|position| {
+            format!(
+                "{}: net {} | filled notional {:.2} | resting {} for {:.2}",
+                position.symbol,
+                position.net_quantity,
+                position.realized_notional,
+                position.open_quantity,
+                position.open_notional
+            )
+        })
+        .collect();
+    if lines.is_empty() {
+        lines.push(response.message.clone());
+    }
+    lines.push(match response.max_gross_exposure {
+        Some(limit) => format!(
+            "gross exposure {:.2} of {:.2}",
+            response.gross_exposure, limit
+        ),
+        None => format!("gross exposure {:.2}, unlimited", response.gross_exposure),
+    });
+    lines.join("\n")
+}
+
 fn order_idempotency_key(sender_id: &str, clord_id: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(sender_id.as_bytes());
@@ -1271,7 +1415,8 @@ fn checksum(raw: &str) -> String {
 
 fn wrap(body: String) -> Vec<u8> {
     let begin = format!("8=FIX.4.2{SOH}");
-    let blen = format!("9={}{SOH}", begin.len() + body.len());
+    // BodyLength counts the bytes following its own field, up to the CheckSum
+    let blen = format!("9={}{SOH}", body.len());
     let raw = format!("{begin}{blen}{body}");
     let chk = checksum(&raw);
     format!("{raw}10={chk}{SOH}").into_bytes()
@@ -1,22 +1,27 @@
 use backend::order_book::OrderBookState;
 use backend::server::Metrics;
 use backend::state::EventBus;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use config::{MarketConfig, SingleMarketConfig};
 use crossbeam::channel;
 use fix::engine::FixRawMsg;
+use fix::framing::FramingLimits;
 use memory;
-use order_book::OrderBookControl;
+use order_book::read_model::ReadModelConfig;
+use order_book::{OrderBookControl, PositionTracker};
 use std::collections::HashMap;
 use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex};
 use types::consts::RB_SIZE;
 use types::macros::{EntityId, SymbolId};
-use types::{ExecutionReportMessage, OrderEvent, OrderResult};
+use types::{ExecutionReportMessage, FixedPointArithmetic, OrderEvent, OrderResult};
 use utils::market_name;
 
 pub mod startup;
 
+/// Interval at which the main thread checks for a shutdown requested by a panicked thread.
+const PANIC_SHUTDOWN_POLL: std::time::Duration = std::time::Duration::from_millis(200);
+
 #[derive(Parser, Debug)]
 #[command(name = "market-simulator")]
 struct Cli {
@@ -26,6 +31,34 @@ struct Cli {
         default_value = "crates/config/markets/nasdaq.json"
     )]
     config_file: String,
+
+    /// CSV or JSON file of resting orders (symbol, side, price, quantity, sender) loaded into the books at startup.
+    #[arg(long = "book-seed")]
+    book_seed: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print a session journal in human-readable form and exit.
+    JournalDump {
+        /// Journal directory or single segment file.
+        path: String,
+    },
+    /// Replay a session journal through fresh order books and print the books, or check every result with `--verify`.
+    Replay {
+        /// Journal directory or single segment file.
+        #[arg(long)]
+        journal: String,
+        /// Compare every reprocessed result with the journaled one and report the first divergence.
+        #[arg(long)]
+        verify: bool,
+        /// Price level cap the session ran with.
+        #[arg(long)]
+        max_orders_per_level: Option<usize>,
+    },
 }
 
 struct ThreadHandles {
@@ -47,9 +80,10 @@ impl ThreadHandles {
         for handle in self.handles.drain(..) {
             let name = handle.thread().name().unwrap_or("unknown").to_string();
             handle.thread().unpark(); // Unpark the thread in case it's parked, so it can check the shutdown flag and exit.
-            handle
-                .join()
-                .expect(&format!("Failed to join thread {}", name));
+            if handle.join().is_err() {
+                // The panic hook already logged the payload and backtrace
+                tracing::error!("[{}] Thread {} panicked", market_name(), name);
+            }
         }
     }
 }
@@ -64,6 +98,8 @@ pub struct MarketSimulator {
     // Error channel for threads to report startup errors back to main thread for logging.
     err_rx: crossbeam::channel::Receiver<String>,
     err_tx: Arc<crossbeam::channel::Sender<String>>,
+    // Resting orders seeded into each book before it accepts events, taken by `start_market`.
+    book_seed: HashMap<SymbolId, Vec<OrderEvent>>,
 }
 
 impl MarketSimulator {
@@ -133,14 +169,44 @@ fn start_market(
             "no supported symbols configured for market",
         )));
     }
+    if let Some((symbol, lot_size)) = config
+        .lot_sizes
+        .iter()
+        .find(|(_, lot_size)| !lot_size.is_finite() || **lot_size <= 0.0)
+    {
+        return Err(Box::new(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("lot size of '{symbol}' must be positive, got {lot_size}"),
+        )));
+    }
+    if let Some(max_gross_exposure) = config
+        .max_gross_exposure
+        .filter(|limit| !limit.is_finite() || *limit <= 0.0)
+    {
+        return Err(Box::new(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("max gross exposure must be positive, got {max_gross_exposure}"),
+        )));
+    }
+    let mut book_seed = std::mem::take(&mut market_simulator.book_seed);
+    if let Some(symbol) = book_seed.keys().find(|symbol| {
+        !supported_symbols
+            .iter()
+            .any(|supported| SymbolId::from_ascii(supported) == **symbol)
+    }) {
+        return Err(Box::new(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("book seed references unsupported symbol '{symbol}'"),
+        )));
+    }
 
     let (fix_tx, aggregator_rx) = queues.fix_to_ob.take().unwrap().queue.split();
     let (er_tx, fix_resp_rx) = queues.er_to_fix.take().unwrap().queue.split();
     let (_ob_db_tx, _ob_db_rx) = queues.ob_to_db.take().unwrap().queue.split();
 
-    // Create MPSC channels for per-symbol order book outputs (execution report and database)
-    let (mpsc_er_tx, mpsc_er_rx) = crossbeam_channel::unbounded::<(OrderEvent, OrderResult)>();
-    let mpsc_er_tx = Arc::new(mpsc_er_tx);
+    // Results reach the execution report engine through the per-symbol result queues, its channel input stays idle
+    let (_, mpsc_er_rx) = crossbeam_channel::unbounded::<(OrderEvent, OrderResult)>();
+    // Create MPSC channel for per-symbol order book outputs to the database
     let (mpsc_db_tx, mpsc_db_rx) = crossbeam_channel::unbounded::<(OrderEvent, OrderResult)>();
     let mpsc_db_tx = Arc::new(mpsc_db_tx);
 
@@ -148,6 +214,13 @@ fn start_market(
     market_simulator.err_tx = Arc::new(err_tx);
     market_simulator.err_rx = err_rx.clone();
     market_simulator.shutdown = Some(Arc::clone(&global_shutdown));
+    if config.shutdown_on_panic {
+        logging::shutdown_on_panic(Arc::clone(&global_shutdown));
+    }
+
+    // Session journal, the engines get a tap each and the writer thread is registered last
+    let journal = startup::start_journal(config.journal.as_ref())?;
+    let journal_tap = journal.as_ref().map(|(tap, _)| tap.clone());
 
     // DB engine thread - pass MPSC receiver directly
     let mut db_data = startup::start_db_engine(
@@ -164,6 +237,26 @@ fn start_market(
         spsc::spsc_lock_free::Producer<'static, OrderEvent, RB_SIZE>,
     > = HashMap::new();
     let mut ob_control_txs = Vec::new();
+    let mut result_rxs = Vec::new();
+
+    // FIX market data is served from the read model of each book, published on every event
+    let read_model = config
+        .market_data
+        .as_ref()
+        .map(|market_data| ReadModelConfig {
+            depth: market_data.depth,
+            every_events: 1,
+            every_ms: 0,
+        });
+    let mut book_readers = HashMap::new();
+    // Positions span every book of the market, the engines share one tracker
+    let positions = PositionTracker::new()
+        .with_max_gross_exposure(
+            config
+                .max_gross_exposure
+                .map(FixedPointArithmetic::from_f64),
+        )
+        .shared();
 
     for symbol in &supported_symbols {
         let symbol_id = SymbolId::from_ascii(symbol);
@@ -174,6 +267,14 @@ fn start_market(
         let (route_tx, route_rx) = route_queue.queue.split();
         aggregator_routes.insert(symbol_id, route_tx);
 
+        // Each book has its own result queue, shared-memory queues take a single producer
+        let result_queue = memory::open_shared_queue::<RB_SIZE, (OrderEvent, OrderResult)>(
+            &format!("{}_{}_order_result_queue", config.name, symbol),
+            true,
+        );
+        let (result_tx, result_rx) = result_queue.queue.split();
+        result_rxs.push(result_rx);
+
         let (ob_control_tx, ob_control_rx) = crossbeam_channel::bounded::<OrderBookControl>(32);
         ob_control_txs.push(ob_control_tx);
 
@@ -181,19 +282,31 @@ fn start_market(
             .pending_orders
             .remove(&symbol_id)
             .unwrap_or_default();
+        let symbol_seed_orders = book_seed.remove(&symbol_id).unwrap_or_default();
 
-        startup::start_order_book_engine_for_symbol(
+        let reader = startup::start_order_book_engine_for_symbol(
             &mut market_simulator,
             symbol.clone(),
             route_rx,
-            Arc::clone(&mpsc_er_tx),
+            result_tx,
             Arc::clone(&mpsc_db_tx),
             ob_control_rx,
             Arc::clone(&metrics),
+            journal_tap.clone(),
             Arc::clone(&global_shutdown),
             symbol_pending_orders,
+            symbol_seed_orders,
+            read_model,
+            config.max_orders_per_level,
+            config.lot_size(symbol).map(FixedPointArithmetic::from_f64),
+            config.rest_unmet_min_quantity,
+            config.order_capacity,
+            Arc::clone(&positions),
             config.core_mapping.order_book_core,
         )?;
+        if let Some(reader) = reader {
+            book_readers.insert(symbol_id, reader);
+        }
     }
 
     startup::start_order_book_aggregator(
@@ -208,6 +321,7 @@ fn start_market(
     startup::start_execution_report_engine(
         &mut market_simulator,
         mpsc_er_rx,
+        result_rxs,
         er_tx,
         Arc::clone(&metrics),
         Arc::clone(&global_shutdown),
@@ -221,6 +335,15 @@ fn start_market(
         fix_tx,
         fix_resp_rx,
         Arc::clone(&metrics),
+        journal_tap,
+        config.market_data.as_ref().map(|market_data| {
+            (
+                fix::market_data::MarketDataConfig {
+                    max_updates_per_sec: market_data.max_updates_per_sec,
+                },
+                book_readers,
+            )
+        }),
         Arc::clone(&global_shutdown),
         config.core_mapping.fix_inbound_core,
         config.core_mapping.fix_outbound_core,
@@ -237,6 +360,14 @@ fn start_market(
         Arc::clone(&global_shutdown),
         config.web.clone(),
         net_to_fix_tx.clone(),
+        config
+            .fix_framing
+            .as_ref()
+            .map_or_else(FramingLimits::default, |fix_framing| FramingLimits {
+                max_message_size: fix_framing.max_message_size,
+                read_timeout: std::time::Duration::from_millis(fix_framing.read_timeout_ms),
+                max_buffered_bytes: fix_framing.max_buffered_bytes,
+            }),
         config.grpc.clone(),
         player_service_addr,
         config.core_mapping.web_core,
@@ -249,10 +380,15 @@ fn start_market(
         config.grpc.port,
         ob_control_txs,
         Arc::clone(&db_data.pool),
+        positions,
         Arc::clone(&global_shutdown),
         config.core_mapping.global_core,
     )?;
 
+    if let Some((_, journal_handle)) = journal {
+        market_simulator.add_thread_handle(journal_handle);
+    }
+
     // Give the thread a moment to fail fast on init errors
     match err_rx.recv_timeout(std::time::Duration::from_millis(500)) {
         Ok(e) => return Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, e))), // failed during startup
@@ -303,14 +439,77 @@ fn stop_market(market_simulator: Arc<Mutex<MarketSimulator>>) {
     );
 }
 
+fn journal_dump(path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let records = journal::read_journal(std::path::Path::new(path))?;
+    for record in &records {
+        println!("{record}");
+    }
+    println!("{} records", records.len());
+    Ok(())
+}
+
+fn replay(
+    path: &str,
+    verify: bool,
+    max_orders_per_level: Option<usize>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let records = journal::read_journal(std::path::Path::new(path))?;
+
+    if verify {
+        return match order_book::replay::verify_journal(&records, max_orders_per_level) {
+            Ok(summary) => {
+                println!(
+                    "{} results verified, {} events without a journaled result",
+                    summary.verified, summary.unverified
+                );
+                Ok(())
+            }
+            Err(divergence) => {
+                eprintln!("{divergence}");
+                eprintln!("Event: {}", divergence.event);
+                Err("journal verification failed".into())
+            }
+        };
+    }
+
+    let symbols: std::collections::BTreeSet<String> = records
+        .iter()
+        .filter_map(|record| match &record.entry {
+            journal::JournalEntry::OrderEvent(event) => Some(event.symbol.to_string()),
+            _ => None,
+        })
+        .collect();
+    for symbol in &symbols {
+        let order_book = order_book::replay::replay_journal(symbol, &records, max_orders_per_level);
+        println!("{order_book:#}\n");
+    }
+    Ok(())
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
+    match &cli.command {
+        Some(Command::JournalDump { path }) => return journal_dump(path),
+        Some(Command::Replay {
+            journal,
+            verify,
+            max_orders_per_level,
+        }) => return replay(journal, *verify, *max_orders_per_level),
+        None => {}
+    }
+
     let config = SingleMarketConfig::parse_from_file(&cli.config_file);
     utils::set_market_name(&config.market.name);
 
     tracing_subscriber::fmt()
         .with_env_filter("debug,sqlx=warn,h2=warn,tokio_util=warn")
         .init();
+    logging::install_panic_hook();
+
+    let book_seed = match &cli.book_seed {
+        Some(path) => order_book::seed::read_book_seed(std::path::Path::new(path))?,
+        None => HashMap::new(),
+    };
 
     let market_config = config.market.clone();
     let supported_symbols = market_config.normalized_stocks();
@@ -331,6 +530,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         shutdown: None,
         err_tx,
         err_rx,
+        book_seed,
     }));
 
     match start_market(Arc::clone(&simulator)) {
@@ -342,13 +542,23 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    let ctrlc_simulator = Arc::clone(&simulator);
     ctrlc::set_handler(move || {
-        stop_market(Arc::clone(&simulator));
+        stop_market(Arc::clone(&ctrlc_simulator));
         std::process::exit(0);
     })
     .expect("Error setting Ctrl-C handler");
 
     loop {
-        std::thread::park();
+        std::thread::park_timeout(PANIC_SHUTDOWN_POLL);
+        if logging::panic_shutdown_requested() {
+            tracing::error!(
+                "[{}] A worker thread panicked, stopping the market",
+                market_name()
+            );
+            stop_market(Arc::clone(&simulator));
+            logging::flush();
+            std::process::exit(1);
+        }
     }
 }
@@ -5,20 +5,25 @@ use execution_report::ExecutionReportEngine;
 use types::ExecutionReportMessage;
 
 use fix::engine::FixRawMsg;
+use fix::framing::FramingLimits;
+use fix::market_data::MarketDataConfig;
+use journal::JournalTap;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex, atomic::AtomicBool};
 use types::EntityId;
 use types::consts::RB_SIZE;
 use types::macros::SymbolId;
-use types::{OrderEvent, OrderResult};
+use types::{FixedPointArithmetic, OrderEvent, OrderResult};
 
-use order_book::{OrderBookAggregator, OrderBookControl};
+use order_book::read_model::{BookSnapshotReader, ReadModelConfig};
+use order_book::{OrderBookAggregator, OrderBookControl, SharedPositionTracker};
 use utils::market_name;
 
 // ---------------- Execution Report Engine ----------------
 pub fn start_execution_report_engine(
     simulator: &mut crate::MarketSimulator,
     er_rx: crossbeam_channel::Receiver<(OrderEvent, OrderResult)>,
+    result_rxs: Vec<spsc::Consumer<'static, (OrderEvent, OrderResult), RB_SIZE>>,
     er_tx: spsc::Producer<'static, (EntityId, ExecutionReportMessage<RB_SIZE>), RB_SIZE>,
     metrics: Arc<backend::server::Metrics>,
     shutdown: Arc<AtomicBool>,
@@ -27,6 +32,9 @@ pub fn start_execution_report_engine(
     let mut execution_report_engine =
         ExecutionReportEngine::new(er_rx, er_tx, Arc::clone(&shutdown));
     execution_report_engine.set_metrics(metrics);
+    for result_rx in result_rxs {
+        execution_report_engine.add_result_queue(result_rx);
+    }
 
     let err_tx = Arc::clone(&simulator.err_tx);
 
@@ -123,6 +131,27 @@ pub fn start_db_engine(
     Ok(db_data)
 }
 
+// ---------------- Session Journal ----------------
+/// Starts the journal writer thread when a journal is configured.
+/// The returned handle must be registered after every engine holding a tap, the writer only exits once all taps are dropped.
+pub fn start_journal(
+    config: Option<&config::JournalConfig>,
+) -> Result<Option<(JournalTap, std::thread::JoinHandle<()>)>, Box<dyn std::error::Error>> {
+    let Some(config) = config else {
+        return Ok(None);
+    };
+
+    let journal = journal::start_journal(config)?;
+    tracing::info!(
+        "[{}] Session journal -> {} ({:?}, backpressure {:?})",
+        market_name(),
+        config.dir,
+        config.format,
+        config.backpressure
+    );
+    Ok(Some(journal))
+}
+
 // ---------------- gRPC Server ----------------
 pub fn start_grpc_server(
     market_simulator: &mut crate::MarketSimulator,
@@ -130,6 +159,7 @@ pub fn start_grpc_server(
     port: u16,
     ob_control_txs: Vec<crossbeam_channel::Sender<OrderBookControl>>,
     db_pool: Arc<sqlx::Pool<sqlx::Postgres>>,
+    positions: SharedPositionTracker,
     global_shutdown: Arc<AtomicBool>,
     core_id: usize,
 ) -> Result<(), Box<dyn std::error::Error>> {
@@ -137,7 +167,7 @@ pub fn start_grpc_server(
     let grpc_ip = ip;
     let grpc_port = port;
     let grpc_shutdown = Arc::clone(&global_shutdown);
-    let grpc_service = grpc::MarketControlService::new(ob_control_txs, db_pool);
+    let grpc_service = grpc::MarketControlService::new(ob_control_txs, db_pool, positions);
     let err_tx = Arc::clone(&market_simulator.err_tx);
     let _grpc_thread = std::thread::spawn(move || {
         core_affinity::set_for_current(core_affinity::CoreId { id: core_id });
@@ -188,12 +218,14 @@ pub fn start_fix_engine(
     fix_tx: spsc::Producer<'static, OrderEvent, RB_SIZE>,
     fix_resp_rx: spsc::Consumer<'static, (EntityId, ExecutionReportMessage<RB_SIZE>), RB_SIZE>,
     metrics: Arc<backend::server::Metrics>,
+    journal: Option<JournalTap>,
+    market_data: Option<(MarketDataConfig, HashMap<SymbolId, BookSnapshotReader>)>,
     global_shutdown: Arc<AtomicBool>,
     inbound_core_id: usize,
     outbound_core_id: usize,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // fix engine thread
-    let fix_engine = fix::engine::FixEngine::new(
+    let mut fix_engine = fix::engine::FixEngine::new(
         fix_rx,
         // Arc::clone(&queues.net_to_fix_rx.as_ref().unwrap()),
         fix_tx,
@@ -201,8 +233,20 @@ pub fn start_fix_engine(
         Arc::clone(&global_shutdown),
         metrics,
     );
+    if let Some((config, books)) = market_data {
+        tracing::info!(
+            "[{}] FIX market data enabled for {} symbol(s), max {} update(s)/s per subscription",
+            market_name(),
+            books.len(),
+            config.max_updates_per_sec
+        );
+        fix_engine.enable_market_data(config, books);
+    }
 
     let (mut inbound_engine, mut outbound_engine) = fix_engine.split();
+    if let Some(journal) = journal {
+        inbound_engine.set_journal(journal);
+    }
 
     let err_tx = Arc::clone(&market_simulator.err_tx);
     let _fix_inbound_thread = std::thread::spawn(move || {
@@ -236,28 +280,44 @@ pub fn start_fix_engine(
 }
 
 // ---------------- Order Book ----------------
+/// Starts the order book engine of `symbol`.
+/// The `pending_orders` restored from the database, then the `seed_orders` of `--book-seed`, are processed before the
+/// engine accepts events.
+/// Returns the reader of its read model when `read_model` is set, to serve FIX market data.
 pub fn start_order_book_engine_for_symbol(
     market_simulator: &mut crate::MarketSimulator,
     symbol: String,
     ob_rx: spsc::Consumer<'static, OrderEvent, RB_SIZE>,
-    ob_er_tx: Arc<crossbeam_channel::Sender<(OrderEvent, OrderResult)>>,
+    ob_result_tx: spsc::Producer<'static, (OrderEvent, OrderResult), RB_SIZE>,
     ob_db_tx: Arc<crossbeam_channel::Sender<(OrderEvent, OrderResult)>>,
     ob_control_rx: crossbeam::channel::Receiver<OrderBookControl>,
     metrics: Arc<backend::server::Metrics>,
+    journal: Option<JournalTap>,
     global_shutdown: Arc<AtomicBool>,
     pending_orders: Vec<OrderEvent>,
+    seed_orders: Vec<OrderEvent>,
+    read_model: Option<ReadModelConfig>,
+    max_orders_per_level: Option<usize>,
+    lot_size: Option<FixedPointArithmetic>,
+    rest_unmet_min_quantity: bool,
+    order_capacity: Option<usize>,
+    positions: SharedPositionTracker,
     order_book_core_id: usize,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<Option<BookSnapshotReader>, Box<dyn std::error::Error>> {
     tracing::info!(
         "[{}] Initializing order book for symbol '{}'",
         market_name(),
         symbol
     );
 
-    let order_book = order_book::book::OrderBook::new(&symbol);
+    let order_book = order_book::book::OrderBook::new(&symbol)
+        .with_max_orders_per_level(max_orders_per_level)
+        .with_lot_size(lot_size)
+        .with_rest_unmet_min_quantity(rest_unmet_min_quantity)
+        .with_order_capacity(order_capacity.unwrap_or(0));
     let mut order_book_engine = order_book::engine::OrderBookEngine::new(
         ob_rx,
-        Some(ob_er_tx),
+        None,
         None,
         Some(ob_db_tx),
         ob_control_rx,
@@ -266,7 +326,23 @@ pub fn start_order_book_engine_for_symbol(
         Arc::clone(&global_shutdown),
     );
     order_book_engine.set_metrics(Arc::clone(&metrics));
+    order_book_engine.set_result_queue(ob_result_tx, order_book::engine::RESULT_QUEUE_MAX_WAIT);
+    if let Some(journal) = journal {
+        order_book_engine.set_journal(journal);
+    }
+    order_book_engine.set_position_tracker(positions);
     order_book_engine.import_order_book(pending_orders);
+    if !seed_orders.is_empty() {
+        let seeded = seed_orders.len();
+        order_book_engine.import_order_book(seed_orders);
+        tracing::info!(
+            "[{}] Seeded order book '{}': {}",
+            market_name(),
+            symbol,
+            order_book::seed::seed_summary(order_book_engine.order_book(), seeded)
+        );
+    }
+    let reader = read_model.map(|config| order_book_engine.enable_read_model(config));
 
     let err_tx = Arc::clone(&market_simulator.err_tx);
     let _ob_thread = std::thread::spawn(move || {
@@ -283,7 +359,7 @@ pub fn start_order_book_engine_for_symbol(
     });
 
     market_simulator.add_thread_handle(_ob_thread);
-    Ok(())
+    Ok(reader)
 }
 
 pub fn start_order_book_aggregator(
@@ -321,6 +397,7 @@ pub fn start_web_server(
     global_shutdown: Arc<AtomicBool>,
     web_addr: Connection,
     fix_tx: Arc<crossbeam_channel::Sender<FixRawMsg<RB_SIZE>>>,
+    framing_limits: FramingLimits,
     grpc_addr: Connection,
     player_service_addr: String,
     core_id: usize,
@@ -342,6 +419,7 @@ pub fn start_web_server(
             match backend::run_web_server(
                 bus,
                 fix_tx,
+                framing_limits,
                 metrics,
                 grpc_addr,
                 &web_ip,
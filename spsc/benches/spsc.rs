@@ -493,6 +493,118 @@ fn benchmark_throughput(c: &mut Criterion) {
     print_summary(&all_results);
 }
 
+const BATCH_RB_SIZE: usize = 4096; // Size of the ring buffer for the batched benchmarks
+const BATCH: usize = 64; // Items per push_slice/pop_slice call
+
+/// Moves `iters` u64 between two cores one item at a time, with try_push/try_pop.
+fn benchmark_transfer_per_item(iters: u64) -> Duration {
+    let cores = get_cores();
+    let (producer_core, consumer_core) = (
+        cores[PRODUCER_CORE_OFFSET % cores.len()],
+        cores[CONSUMER_CORE_OFFSET % cores.len()],
+    );
+
+    let mut buffer = RingBuffer::<u64, BATCH_RB_SIZE>::new();
+    let start = Instant::now();
+
+    thread::scope(|s| {
+        let (producer, consumer) = buffer.split();
+
+        s.spawn(move || {
+            core_affinity::set_for_current(producer_core);
+            for i in 0..iters {
+                while producer.try_push(i).is_err() {
+                    std::hint::spin_loop();
+                }
+            }
+        });
+
+        core_affinity::set_for_current(consumer_core);
+        let mut received = 0;
+        while received < iters {
+            if consumer.try_pop().is_some() {
+                received += 1;
+            } else {
+                std::hint::spin_loop();
+            }
+        }
+    });
+
+    start.elapsed()
+}
+
+/// Moves `iters` u64 between two cores `BATCH` items at a time, with push_slice/pop_slice.
+fn benchmark_transfer_slice(iters: u64) -> Duration {
+    let cores = get_cores();
+    let (producer_core, consumer_core) = (
+        cores[PRODUCER_CORE_OFFSET % cores.len()],
+        cores[CONSUMER_CORE_OFFSET % cores.len()],
+    );
+
+    let mut buffer = RingBuffer::<u64, BATCH_RB_SIZE>::new();
+    let start = Instant::now();
+
+    thread::scope(|s| {
+        let (producer, consumer) = buffer.split();
+
+        s.spawn(move || {
+            core_affinity::set_for_current(producer_core);
+            let mut batch = [0u64; BATCH];
+            let mut next = 0;
+            while next < iters {
+                let len = BATCH.min((iters - next) as usize);
+                for (offset, item) in batch[..len].iter_mut().enumerate() {
+                    *item = next + offset as u64;
+                }
+                let mut sent = 0;
+                while sent < len {
+                    sent += producer.push_slice(&batch[sent..len]);
+                }
+                next += len as u64;
+            }
+        });
+
+        core_affinity::set_for_current(consumer_core);
+        let mut out = [0u64; BATCH];
+        let mut received = 0;
+        while received < iters {
+            received += consumer.pop_slice(&mut out) as u64;
+        }
+    });
+
+    start.elapsed()
+}
+
+/// Throughput of the per-item path against push_slice/pop_slice, reported in millions of items per second.
+fn benchmark_batched(c: &mut Criterion) {
+    let functions: &[(&str, fn(u64) -> Duration)] = &[
+        ("Transfer RingBuffer per item", benchmark_transfer_per_item),
+        (
+            "Transfer RingBuffer push_slice/pop_slice",
+            benchmark_transfer_slice,
+        ),
+    ];
+
+    for (name, func) in functions {
+        c.bench_function(name, |b| {
+            b.iter_custom(|iters| {
+                let mut total = Duration::ZERO;
+                for _ in 0..iters {
+                    total += func(BENCH_ITERS * 10);
+                }
+                total
+            });
+        });
+
+        let elapsed = func(BENCH_ITERS * 10);
+        println!(
+            "{}: {:.1} M items/s",
+            name,
+            (BENCH_ITERS * 10) as f64 / elapsed.as_secs_f64() / 1e6
+        );
+    }
+}
+
 fn print_summary(results: &[BenchResult]) {
     if results.is_empty() {
         println!("No results.");
@@ -543,5 +655,10 @@ fn print_summary(results: &[BenchResult]) {
     println!();
 }
 
-criterion_group!(benches, benchmark_latency, benchmark_throughput);
+criterion_group!(
+    benches,
+    benchmark_latency,
+    benchmark_throughput,
+    benchmark_batched
+);
 criterion_main!(benches);
@@ -0,0 +1,287 @@
+//! Lock-free SPSC ring buffer whose capacity is chosen at runtime.
+//!
+//! Same layout and index protocol as [`RingBuffer`](crate::spsc_lock_free::RingBuffer), but the storage is a boxed
+//! slice allocated by [`HeapRingBuffer::with_capacity`], for sizes read from a configuration. The slot count is rounded
+//! up to the next power of two so indices wrap with `& mask` instead of a modulo, and one slot always stays free to
+//! distinguish full from empty.
+
+use crate::spsc_lock_free::CachePadded;
+use std::cell::UnsafeCell;
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Lock-free single-producer single-consumer ring buffer with a runtime capacity.
+/// Use [`HeapRingBuffer::split`] to get the [`HeapProducer`] and [`HeapConsumer`] handles, the raw `push` and `pop`
+/// take `&self` like the ones of [`RingBuffer`](crate::spsc_lock_free::RingBuffer).
+#[repr(align(64))]
+pub struct HeapRingBuffer<T> {
+    pub head: CachePadded<AtomicUsize>,
+    pub tail: CachePadded<AtomicUsize>,
+    mask: usize, // Slot count - 1, the slot count is a power of 2
+    buffer: Box<[UnsafeCell<MaybeUninit<T>>]>, // Circular buffer storage, each slot is an UnsafeCell for interior mutability
+}
+
+/// Pushing side of a [`HeapRingBuffer`].
+pub struct HeapProducer<'a, T> {
+    rb: &'a HeapRingBuffer<T>,
+}
+
+/// Popping side of a [`HeapRingBuffer`].
+pub struct HeapConsumer<'a, T> {
+    rb: &'a HeapRingBuffer<T>,
+    _not_sync: PhantomData<std::cell::UnsafeCell<()>>, // !Sync but Send
+}
+
+// Safety: The HeapRingBuffer can be safely sent between threads as long as T is Send
+unsafe impl<T: Send> Send for HeapRingBuffer<T> {}
+// Safety: The HeapRingBuffer can be safely shared between threads as long as T is Send
+unsafe impl<T: Send> Sync for HeapRingBuffer<T> {}
+
+// Need to properly drop any remaining items in the buffer when HeapRingBuffer is dropped
+impl<T> Drop for HeapRingBuffer<T> {
+    fn drop(&mut self) {
+        let head = self.head.0.load(Ordering::Relaxed);
+        let mut tail = self.tail.0.load(Ordering::Relaxed);
+        while head != tail {
+            unsafe {
+                self.buffer
+                    .get_unchecked_mut(tail)
+                    .get_mut()
+                    .assume_init_drop();
+            }
+            tail = (tail + 1) & self.mask;
+        }
+    }
+}
+
+impl<'a, T> HeapProducer<'a, T> {
+    /// Pushes an item into the ring buffer.
+    /// Returns Err(item) if the buffer is full.
+    pub fn push(&self, item: T) -> Result<(), T> {
+        self.rb.push(item)
+    }
+
+    /// Whether a push would fail, the buffer holds `capacity()` items.
+    pub fn is_full(&self) -> bool {
+        self.rb.is_full()
+    }
+
+    /// Number of items in the buffer, emptiness is for the consumer to check.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.rb.len()
+    }
+}
+
+impl<'a, T> HeapConsumer<'a, T> {
+    /// Pops an item from the ring buffer, or returns None immediately if it is empty.
+    pub fn pop(&self) -> Option<T> {
+        self.rb.pop()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rb.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.rb.len()
+    }
+}
+
+impl<T> HeapRingBuffer<T> {
+    /// Creates a ring buffer that holds at least `capacity` items.
+    /// One slot stays free, so the slot count is `capacity + 1` rounded up to the next power of 2:
+    /// `with_capacity(1000)` allocates 1024 slots and holds 1023 items, `with_capacity(1024)` allocates 2048.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let slots = capacity
+            .checked_add(1)
+            .and_then(|slots| slots.max(2).checked_next_power_of_two())
+            .expect("capacity overflows the next power of 2");
+
+        Self {
+            head: CachePadded(AtomicUsize::new(0)),
+            tail: CachePadded(AtomicUsize::new(0)),
+            mask: slots - 1,
+            buffer: (0..slots)
+                .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+                .collect(),
+        }
+    }
+
+    /// Drops the items left in the buffer and splits it into its producer and consumer handles.
+    /// The exclusive borrow keeps the raw API out of reach while the handles are alive.
+    pub fn split<'a>(&'a mut self) -> (HeapProducer<'a, T>, HeapConsumer<'a, T>) {
+        *self = Self::with_capacity(self.mask); // Reset head and tail to 0, ensure buffer is empty
+        (
+            HeapProducer { rb: self },
+            HeapConsumer {
+                rb: self,
+                _not_sync: PhantomData, // !Sync but Send
+            },
+        )
+    }
+
+    /// Pushes an item into the ring buffer.
+    /// Returns Err(item) if the buffer is full.
+    /// Raw API: only one thread may push at a time, prefer [`HeapProducer::push`] from [`HeapRingBuffer::split`].
+    pub fn push(&self, item: T) -> Result<(), T> {
+        let head = self.head.0.load(Ordering::Relaxed); // Relaxed is safe here because only the producer modifies head
+        let next_head = (head + 1) & self.mask; // Bitwise mask because the slot count is a power of 2
+
+        if next_head == self.tail.0.load(Ordering::Acquire) {
+            // Buffer is full
+            return Err(item);
+        }
+
+        unsafe {
+            *self.buffer.get_unchecked(head).get() = MaybeUninit::new(item);
+        }
+        self.head.0.store(next_head, Ordering::Release);
+        Ok(())
+    }
+
+    /// Pops an item from the ring buffer.
+    /// Returns None if the buffer is empty.
+    /// Raw API: only one thread may pop at a time, prefer [`HeapConsumer::pop`] from [`HeapRingBuffer::split`].
+    pub fn pop(&self) -> Option<T> {
+        let tail = self.tail.0.load(Ordering::Relaxed); // Relaxed is safe here because only the consumer modifies tail
+
+        if tail == self.head.0.load(Ordering::Acquire) {
+            // Buffer is empty
+            return None;
+        }
+
+        let item = unsafe { (*self.buffer.get_unchecked(tail).get()).assume_init_read() };
+        self.tail.0.store((tail + 1) & self.mask, Ordering::Release);
+        Some(item)
+    }
+
+    /// Checks if the ring buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        let head = self.head.0.load(Ordering::Acquire);
+        let tail = self.tail.0.load(Ordering::Relaxed);
+        head == tail
+    }
+
+    /// Checks if the ring buffer is full, one slot always stays free so a push fails at `capacity()` items.
+    pub fn is_full(&self) -> bool {
+        let head = self.head.0.load(Ordering::Relaxed);
+        let tail = self.tail.0.load(Ordering::Acquire);
+        (head + 1) & self.mask == tail
+    }
+
+    /// Returns the current number of items in the ring buffer.
+    pub fn len(&self) -> usize {
+        let head = self.head.0.load(Ordering::Acquire);
+        let tail = self.tail.0.load(Ordering::Relaxed);
+        (head + self.mask + 1 - tail) & self.mask // Bitwise mask because the slot count is a power of 2
+    }
+
+    /// Maximum number of items the buffer holds, the slot count minus the free slot.
+    pub fn capacity(&self) -> usize {
+        self.mask
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HeapRingBuffer;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn capacity_is_rounded_up_to_a_power_of_two() {
+        assert_eq!(HeapRingBuffer::<u8>::with_capacity(5).capacity(), 7);
+        assert_eq!(HeapRingBuffer::<u8>::with_capacity(7).capacity(), 7);
+        assert_eq!(HeapRingBuffer::<u8>::with_capacity(8).capacity(), 15);
+        assert_eq!(HeapRingBuffer::<u8>::with_capacity(1000).capacity(), 1023);
+        assert_eq!(HeapRingBuffer::<u8>::with_capacity(1024).capacity(), 2047);
+        assert_eq!(HeapRingBuffer::<u8>::with_capacity(0).capacity(), 1);
+    }
+
+    #[test]
+    fn requested_capacity_fits() {
+        for capacity in [1, 2, 3, 4, 7, 8, 16, 100] {
+            let mut rb = HeapRingBuffer::with_capacity(capacity);
+            let (producer, _consumer) = rb.split();
+            for i in 0..capacity {
+                assert_eq!(producer.push(i), Ok(()), "capacity {capacity}");
+            }
+            assert_eq!(producer.len(), capacity);
+        }
+    }
+
+    #[test]
+    fn push_and_pop_with_a_non_power_of_two_capacity() {
+        // 6 items need 7 slots, rounded up to 8, 7 items fit
+        let mut rb = HeapRingBuffer::with_capacity(6);
+        let (producer, consumer) = rb.split();
+        for i in 0..7 {
+            assert_eq!(producer.push(i), Ok(()));
+        }
+        assert!(producer.is_full());
+        assert_eq!(producer.push(7), Err(7));
+        assert_eq!(producer.len(), 7);
+
+        // Wrap around the end of the storage a few times
+        for i in 7..40 {
+            assert_eq!(consumer.pop(), Some(i - 7));
+            assert_eq!(producer.push(i), Ok(()));
+            assert_eq!(consumer.len(), 7);
+        }
+        for i in 33..40 {
+            assert_eq!(consumer.pop(), Some(i));
+        }
+        assert!(consumer.is_empty());
+        assert_eq!(consumer.pop(), None);
+    }
+
+    #[test]
+    fn leftover_items_are_dropped() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        struct Counted(Arc<AtomicUsize>);
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let rb = HeapRingBuffer::with_capacity(3);
+        for _ in 0..3 {
+            assert!(rb.push(Counted(Arc::clone(&drops))).is_ok());
+        }
+        drop(rb.pop());
+        assert_eq!(drops.load(Ordering::Relaxed), 1);
+        drop(rb);
+        assert_eq!(drops.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn spsc_threads() {
+        const ITEMS: u64 = 100_000;
+        let mut rb = HeapRingBuffer::with_capacity(100);
+        let (producer, consumer) = rb.split();
+
+        std::thread::scope(|s| {
+            s.spawn(move || {
+                for i in 0..ITEMS {
+                    while producer.push(i).is_err() {
+                        std::thread::yield_now();
+                    }
+                }
+            });
+
+            for i in 0..ITEMS {
+                loop {
+                    if let Some(item) = consumer.pop() {
+                        assert_eq!(item, i);
+                        break;
+                    }
+                    std::thread::yield_now();
+                }
+            }
+            assert!(consumer.is_empty());
+        });
+    }
+}
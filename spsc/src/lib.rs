@@ -1,5 +1,10 @@
+pub mod heap_ring_buffer;
+pub mod slot;
 pub mod spsc_lock;
 pub mod spsc_lock_free;
+mod sync;
 
+pub use heap_ring_buffer::HeapRingBuffer;
+pub use slot::Slot;
 pub use spsc_lock_free::Consumer;
 pub use spsc_lock_free::Producer;
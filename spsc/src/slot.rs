@@ -0,0 +1,252 @@
+//! Capacity-one SPSC cell with latest-value semantics.
+//!
+//! The producer always publishes, overwriting the value the consumer did not take yet, and the consumer takes the
+//! latest value at most once. Meant for links where only the freshest value matters (book snapshots, watermarks),
+//! where a ring buffer would only queue stale values.
+//!
+//! The cell is guarded by a three-state machine:
+//!
+//! ```text
+//!   Empty --publish--> Writing --written--> Full
+//!   Full  --publish--> Writing --written--> Full    (overwrite, the unread value is handed back)
+//!   Full  --take-----> Writing --moved----> Empty
+//! ```
+//!
+//! `Writing` means one side owns the cell exclusively: the producer writing a value, or the consumer moving one out.
+//! Entering it is an Acquire CAS and leaving it a Release store, so the accesses to the cell of both sides never
+//! overlap and a value is always fully written before it can be taken.
+//!
+//! The state machine is model checked with loom by the `concurrency-tests` crate.
+
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+
+use crate::sync::{AtomicU8, Ordering, UnsafeCell, hint};
+
+/// No value to take.
+const EMPTY: u8 = 0;
+/// One side owns the cell, the producer writing or the consumer moving the value out.
+const WRITING: u8 = 1;
+/// A value is ready to be taken.
+const FULL: u8 = 2;
+
+/// Single-value SPSC cell, split into a [`SlotProducer`] and a [`SlotConsumer`].
+pub struct Slot<T> {
+    state: AtomicU8,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// Publishing side of a [`Slot`].
+pub struct SlotProducer<'a, T> {
+    slot: &'a Slot<T>,
+    _not_sync: PhantomData<std::cell::UnsafeCell<()>>, // !Sync but Send
+}
+
+/// Taking side of a [`Slot`].
+pub struct SlotConsumer<'a, T> {
+    slot: &'a Slot<T>,
+    _not_sync: PhantomData<std::cell::UnsafeCell<()>>, // !Sync but Send
+}
+
+// Safety: the value is moved between threads, never shared, so T only needs to be Send
+unsafe impl<T: Send> Send for Slot<T> {}
+// Safety: the state machine gives one side at a time access to the value
+unsafe impl<T: Send> Sync for Slot<T> {}
+
+impl<T> Drop for Slot<T> {
+    fn drop(&mut self) {
+        if self.state.load(Ordering::Relaxed) == FULL {
+            // Safety: a Full cell holds an initialized value, and `&mut self` excludes both sides
+            self.value
+                .with_mut(|value| unsafe { (*value).assume_init_drop() });
+        }
+    }
+}
+
+impl<T> Default for Slot<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Slot<T> {
+    /// Creates an empty slot.
+    pub fn new() -> Self {
+        Self {
+            state: AtomicU8::new(EMPTY),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Splits the slot into its producer and consumer, dropping any value left in it.
+    pub fn split<'a>(&'a mut self) -> (SlotProducer<'a, T>, SlotConsumer<'a, T>) {
+        *self = Self::new();
+        (
+            SlotProducer {
+                slot: self,
+                _not_sync: PhantomData,
+            },
+            SlotConsumer {
+                slot: self,
+                _not_sync: PhantomData,
+            },
+        )
+    }
+
+    /// Whether a value is waiting to be taken. Relaxed, meant for monitoring.
+    pub fn is_full(&self) -> bool {
+        self.state.load(Ordering::Relaxed) == FULL
+    }
+
+    /// Stores `value`, returning the previous one if the consumer did not take it.
+    /// Must only be called from the producer side.
+    fn publish(&self, value: T) -> Option<T> {
+        let previous = loop {
+            let state = self.state.load(Ordering::Relaxed);
+            // Acquire: a value moved out by the consumer is fully read before it is overwritten
+            if state != WRITING
+                && self
+                    .state
+                    .compare_exchange_weak(state, WRITING, Ordering::Acquire, Ordering::Relaxed)
+                    .is_ok()
+            {
+                break state;
+            }
+            // The consumer is moving the value out, it only takes the time of a copy
+            hint::spin_loop();
+        };
+
+        // Safety: Writing gives the producer exclusive access to the cell, and a Full cell holds an initialized value
+        let unread = self.value.with_mut(|cell| unsafe {
+            let unread = (previous == FULL).then(|| (*cell).assume_init_read());
+            (*cell).write(value);
+            unread
+        });
+
+        // Release: the value is fully written before the consumer can see Full
+        self.state.store(FULL, Ordering::Release);
+        unread
+    }
+
+    /// Takes the latest value, None if there is none or the producer is writing a new one.
+    /// Must only be called from the consumer side.
+    fn take(&self) -> Option<T> {
+        // Acquire: pairs with the Release store of Full, the value is fully written
+        self.state
+            .compare_exchange(FULL, WRITING, Ordering::Acquire, Ordering::Relaxed)
+            .ok()?;
+
+        // Safety: Writing gives the consumer exclusive access to the cell, which was Full
+        let value = self
+            .value
+            .with_mut(|cell| unsafe { (*cell).assume_init_read() });
+
+        // Release: the value is moved out before the producer can overwrite the cell
+        self.state.store(EMPTY, Ordering::Release);
+        Some(value)
+    }
+}
+
+impl<'a, T> SlotProducer<'a, T> {
+    /// Publishes `value`, always succeeding.
+    /// Returns the previous value if the consumer did not take it, it is overwritten.
+    /// Spins only while the consumer is moving a value out of the slot.
+    pub fn publish(&self, value: T) -> Option<T> {
+        self.slot.publish(value)
+    }
+
+    /// Whether the last published value was not taken yet.
+    pub fn is_full(&self) -> bool {
+        self.slot.is_full()
+    }
+}
+
+impl<'a, T> SlotConsumer<'a, T> {
+    /// Takes the latest published value, each value is returned at most once.
+    /// Returns None if no value was published since the last take, or if the producer is writing one.
+    pub fn take(&self) -> Option<T> {
+        self.slot.take()
+    }
+
+    /// Whether a value is waiting to be taken.
+    pub fn is_full(&self) -> bool {
+        self.slot.is_full()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use super::Slot;
+
+    #[test]
+    fn publish_and_take() {
+        let mut slot = Slot::new();
+        let (producer, consumer) = slot.split();
+        assert_eq!(consumer.take(), None);
+
+        assert_eq!(producer.publish(1), None);
+        assert!(consumer.is_full());
+        assert_eq!(consumer.take(), Some(1));
+        assert_eq!(consumer.take(), None); // At most once
+
+        assert_eq!(producer.publish(2), None);
+        assert_eq!(producer.publish(3), Some(2)); // Unread value is overwritten and handed back
+        assert_eq!(consumer.take(), Some(3));
+        assert!(!producer.is_full());
+    }
+
+    #[test]
+    fn drop_releases_unread_value() {
+        use std::sync::Arc;
+
+        let value = Arc::new(());
+        {
+            let mut slot = Slot::new();
+            let (producer, _consumer) = slot.split();
+            producer.publish(Arc::clone(&value));
+            drop(producer.publish(Arc::clone(&value)));
+            assert_eq!(Arc::strong_count(&value), 2);
+        }
+        assert_eq!(Arc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn no_torn_reads_non_copy() {
+        use std::thread;
+
+        const ITERATIONS: u64 = 200_000;
+        const LEN: usize = 32;
+        let mut slot = Slot::<Vec<u64>>::new();
+
+        thread::scope(|s| {
+            let (producer, consumer) = slot.split();
+
+            s.spawn(move || {
+                for i in 0..ITERATIONS {
+                    if let Some(unread) = producer.publish(vec![i; LEN]) {
+                        assert!(unread.iter().all(|&v| v == unread[0]));
+                    }
+                }
+            });
+
+            let mut last = None;
+            while last != Some(ITERATIONS - 1) {
+                if let Some(value) = consumer.take() {
+                    assert_eq!(value.len(), LEN);
+                    assert!(
+                        value.iter().all(|&v| v == value[0]),
+                        "torn read: {:?}",
+                        value
+                    );
+                    assert!(
+                        last < Some(value[0]),
+                        "stale value {} after {:?}",
+                        value[0],
+                        last
+                    );
+                    last = Some(value[0]);
+                }
+            }
+        });
+    }
+}
@@ -1,10 +1,23 @@
 use std::collections::VecDeque;
+use std::marker::PhantomData;
 use std::sync::Mutex;
 
 pub struct SpscLock<T, const N: usize> {
     buffer: Mutex<VecDeque<T>>,
 }
 
+/// Pushing half of a [`SpscLock`], see [`SpscLock::split`].
+pub struct Producer<'a, T, const N: usize> {
+    queue: &'a SpscLock<T, N>,
+    _not_sync: PhantomData<std::cell::UnsafeCell<()>>, // !Sync but Send
+}
+
+/// Popping half of a [`SpscLock`], see [`SpscLock::split`].
+pub struct Consumer<'a, T, const N: usize> {
+    queue: &'a SpscLock<T, N>,
+    _not_sync: PhantomData<std::cell::UnsafeCell<()>>, // !Sync but Send
+}
+
 impl<T, const N: usize> SpscLock<T, N> {
     pub fn new() -> Self {
         SpscLock {
@@ -12,6 +25,28 @@ impl<T, const N: usize> SpscLock<T, N> {
         }
     }
 
+    /// Splits the queue into its producer and consumer handles, the recommended way to use it.
+    /// The exclusive borrow keeps the raw `push` and `pop` out of reach while the handles are alive.
+    ///
+    /// ```compile_fail
+    /// let mut queue = spsc::spsc_lock::SpscLock::<u32, 8>::new();
+    /// let (producer, _consumer) = queue.split();
+    /// let second_producer = producer.clone();
+    /// ```
+    pub fn split(&mut self) -> (Producer<'_, T, N>, Consumer<'_, T, N>) {
+        (
+            Producer {
+                queue: self,
+                _not_sync: PhantomData,
+            },
+            Consumer {
+                queue: self,
+                _not_sync: PhantomData,
+            },
+        )
+    }
+
+    /// Raw API: prefer [`Producer::push`] from [`SpscLock::split`].
     pub fn push(&self, item: T) -> Result<(), T> {
         let mut buf = self.buffer.lock().unwrap();
         if buf.len() < N {
@@ -22,10 +57,51 @@ impl<T, const N: usize> SpscLock<T, N> {
         }
     }
 
+    /// Raw API: prefer [`Consumer::pop`] from [`SpscLock::split`].
     pub fn pop(&self) -> Option<T> {
         let mut buf = self.buffer.lock().unwrap();
         buf.pop_front()
     }
+
+    pub fn len(&self) -> usize {
+        self.buffer.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.lock().unwrap().is_empty()
+    }
+}
+
+impl<'a, T, const N: usize> Producer<'a, T, N> {
+    /// Pushes an item, returns Err(item) if the queue holds `N` items.
+    pub fn push(&self, item: T) -> Result<(), T> {
+        self.queue.push(item)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.queue.len() == N
+    }
+
+    /// Number of items in the queue, emptiness is for the consumer to check.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+}
+
+impl<'a, T, const N: usize> Consumer<'a, T, N> {
+    /// Pops an item, returns None if the queue is empty.
+    pub fn pop(&self) -> Option<T> {
+        self.queue.pop()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
 }
 
 #[cfg(test)]
@@ -34,18 +110,17 @@ mod tests {
 
     #[test]
     fn spsc_threads() {
-        use std::sync::Arc;
         use std::thread;
 
-        let rb = Arc::new(SpscLock::<usize, 1024>::new());
-        let rb_producer = Arc::clone(&rb);
-        let rb_consumer = Arc::clone(&rb);
+        let mut queue = SpscLock::<usize, 1024>::new();
 
         thread::scope(|s| {
+            let (producer, consumer) = queue.split();
+
             let prod = s.spawn(move || {
                 for i in 0..1_000_000 {
                     loop {
-                        if rb_producer.push(i).is_ok() {
+                        if producer.push(i).is_ok() {
                             break;
                         }
                     }
@@ -54,7 +129,7 @@ mod tests {
 
             let mut expected = 0;
             while expected < 1_000_000 {
-                if let Some(v) = rb_consumer.pop() {
+                if let Some(v) = consumer.pop() {
                     assert_eq!(v, expected);
                     expected += 1;
                 }
@@ -63,4 +138,23 @@ mod tests {
             prod.join().unwrap();
         });
     }
+
+    #[test]
+    fn split_handles_report_fill_state() {
+        let mut queue = SpscLock::<u8, 2>::new();
+        let (producer, consumer) = queue.split();
+        assert!(consumer.is_empty());
+
+        producer.push(1).unwrap();
+        producer.push(2).unwrap();
+        assert!(producer.is_full());
+        assert_eq!(producer.push(3), Err(3));
+        assert_eq!((producer.len(), consumer.len()), (2, 2));
+
+        assert_eq!(consumer.pop(), Some(1));
+        assert!(!producer.is_full());
+        assert_eq!(consumer.pop(), Some(2));
+        assert_eq!(consumer.pop(), None);
+        assert!(consumer.is_empty());
+    }
 }
@@ -1,35 +1,52 @@
-use std::cell::UnsafeCell;
 use std::marker::PhantomData;
 use std::mem::MaybeUninit;
-use std::sync::atomic::{AtomicUsize, Ordering, fence};
 use std::thread::Thread;
+
+#[cfg(feature = "metrics")]
+use crate::sync::AtomicU64;
+use crate::sync::{AtomicBool, AtomicUsize, Ordering, UnsafeCell, fence, hint};
 use std::time::{Duration, Instant};
 // use std::thread;
 
 #[cfg(feature = "cache-padding")]
 #[repr(align(64))]
-pub struct CachePadded<T>(T);
+pub struct CachePadded<T>(pub(crate) T);
 
 #[cfg(not(feature = "cache-padding"))]
-pub struct CachePadded<T>(T);
+pub struct CachePadded<T>(pub(crate) T);
 
+/// Storage of the ring buffer, one cell per slot: the producer writes the slot at head while the consumer reads the
+/// one at tail, and loom checks the accesses of each slot on their own.
 #[cfg(feature = "cache-padding")]
 #[repr(align(64))]
-pub struct AlignedBuffer<T, const N: usize>([MaybeUninit<T>; N]);
+pub struct AlignedBuffer<T, const N: usize>([UnsafeCell<MaybeUninit<T>>; N]);
 
 #[cfg(not(feature = "cache-padding"))]
-pub struct AlignedBuffer<T, const N: usize>([MaybeUninit<T>; N]);
+pub struct AlignedBuffer<T, const N: usize>([UnsafeCell<MaybeUninit<T>>; N]);
 
+/// Lock-free single-producer single-consumer ring buffer holding up to `N - 1` items.
+/// Use [`RingBuffer::split`] to get the [`Producer`] and [`Consumer`] handles: the raw `push` and `pop` take `&self`
+/// and are kept for backwards compatibility, but nothing stops two threads from calling them at the same time.
 #[repr(align(64))]
 pub struct RingBuffer<T, const N: usize> {
     pub head: CachePadded<AtomicUsize>,
     pub tail: CachePadded<AtomicUsize>,
-    buffer: UnsafeCell<AlignedBuffer<T, N>>, // Circular buffer storage, Make the whole buffer UnsafeCell to allow interior mutability
+    buffer: AlignedBuffer<T, N>, // Circular buffer storage, each slot is an UnsafeCell to allow interior mutability
     consumer_thread: std::sync::OnceLock<Thread>, // Store the consumer thread handle to allow for better synchronization in push when buffer is full, by yielding to the consumer thread
+    consumer_parked: AtomicBool, // Set while the consumer is about to park or parked on an empty buffer, the producer only unparks it then
+    producer_thread: std::sync::OnceLock<Thread>, // Producer thread handle, registered by the first push_blocking on a full buffer
+    producer_parked: AtomicBool, // Set while the producer is about to park or parked on a full buffer, the consumer only unparks it then
+    #[cfg(feature = "metrics")]
+    push_count: CachePadded<AtomicU64>, // Successful pushes, written by the producer only
+    #[cfg(feature = "metrics")]
+    full_rejections: CachePadded<AtomicU64>, // Pushes refused on a full buffer, written by the producer only
+    #[cfg(feature = "metrics")]
+    pop_count: CachePadded<AtomicU64>, // Successful pops, written by the consumer only
 }
 
 /// Split the RingBuffer into a Producer and Consumer. The Producer can only push items, and the Consumer can only pop items.
 /// This allows for safe concurrent access from separate threads without needing to use Arc or other synchronization primitives.
+/// Neither handle is `Clone`, so each side has a single owner; both are `Send` to be moved to their thread.
 pub struct Producer<'a, T, const N: usize> {
     rb: &'a RingBuffer<T, N>,
 }
@@ -39,6 +56,29 @@ pub struct Consumer<'a, T, const N: usize> {
     _not_sync: PhantomData<std::cell::UnsafeCell<()>>, // !Sync but Send
 }
 
+/// Point-in-time view of the ring buffer indices, meant for logging and monitoring.
+/// Values are loaded with Relaxed ordering and may be momentarily inconsistent with each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RingBufferStats {
+    pub head: usize,
+    pub tail: usize,
+    pub len: usize,
+    pub capacity: usize,
+}
+
+/// Operation counters of the ring buffer, only with the `metrics` feature.
+/// Counted by `push` and `pop`, so by every single item operation of the buffer and its handles, blocking ones
+/// included: a `push_blocking` waiting on a full buffer counts a rejection for every failed attempt.
+/// Batch, slice, iterator and overwrite operations are not counted.
+/// Values are loaded with Relaxed ordering and may be momentarily inconsistent with each other.
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RingMetrics {
+    pub push_count: u64,
+    pub pop_count: u64,
+    pub full_rejections: u64,
+}
+
 // Safety: The RingBuffer can be safely sent between threads as long as T is Send
 unsafe impl<T: Send, const N: usize> Send for RingBuffer<T, N> {}
 // Safety: The RingBuffer can be safely shared between threads as long as T is Send
@@ -47,37 +87,22 @@ unsafe impl<T: Send, const N: usize> Sync for RingBuffer<T, N> {}
 // Need to properly drop any remaining items in the buffer when RingBuffer is dropped
 impl<T, const N: usize> Drop for RingBuffer<T, N> {
     fn drop(&mut self) {
-        let head = self.head.0.load(Ordering::Relaxed);
-        let mut tail = self.tail.0.load(Ordering::Relaxed);
-        while head != tail {
-            unsafe {
-                self.buffer
-                    .get()
-                    .as_mut()
-                    .unwrap()
-                    .0
-                    .get_unchecked_mut(tail)
-                    .assume_init_drop();
-            }
-            tail = (tail + 1) & (N - 1); // Bitwise mask because N is power of 2
-        }
+        self.drop_items();
     }
 }
 
 impl<'a, T, const N: usize> Producer<'a, T, N> {
-    /// Pushes an item into the ring buffer and wakes up the consumer thread if it was sleeping, to improve latency when buffer is full.
-    /// Returns Err(item) if the buffer is full.            while let Some(_) = ss_consumer.try_pop() {
+    /// Pushes an item into the ring buffer and wakes up the consumer thread if it is parked on the empty buffer.
+    /// Returns Err(item) if the buffer is full.
     pub fn push(&self, item: T) -> Result<(), T> {
-        match self.rb.push(item) {
-            Ok(()) => {
-                // Wake up the consumer thread if it was sleeping which could be possibly sleeping is queue is empty.
-                if let Some(consumer_thread) = self.rb.consumer_thread.get() {
-                    consumer_thread.unpark();
-                }
-                Ok(())
-            }
-            Err(item) => Err(item),
-        }
+        self.rb.push(item)?;
+        self.rb.wake_consumer();
+        Ok(())
+    }
+
+    /// Pushes an item, parking the producer thread while the buffer is full, see [`RingBuffer::push_blocking`].
+    pub fn push_blocking(&self, item: T) {
+        self.rb.push_blocking(item)
     }
 
     /// Pushes an item into the ring buffer.
@@ -93,43 +118,123 @@ impl<'a, T, const N: usize> Producer<'a, T, N> {
         self.rb.push_batch(items)
     }
 
+    /// Copies as many items of `items` as fit into the ring buffer with one index update, see [`RingBuffer::push_slice`].
+    /// Wakes up the consumer thread if anything was pushed. Returns the number of items pushed.
+    pub fn push_slice(&self, items: &[T]) -> usize
+    where
+        T: Copy,
+    {
+        let pushed = self.rb.push_slice(items);
+        if pushed > 0 {
+            self.rb.wake_consumer();
+        }
+        pushed
+    }
+
+    /// Moves items out of `items` until the ring buffer is full or the iterator is exhausted, see [`RingBuffer::push_iter`].
+    /// Wakes up the consumer thread if anything was pushed. Returns the number of items pushed.
+    pub fn push_iter<I>(&self, items: &mut I) -> usize
+    where
+        I: Iterator<Item = T>,
+    {
+        let pushed = self.rb.push_iter(items);
+        if pushed > 0 {
+            self.rb.wake_consumer();
+        }
+        pushed
+    }
+
+    /// Whether a push would fail, the buffer holds `N - 1` items.
+    pub fn is_full(&self) -> bool {
+        self.rb.is_full()
+    }
+
     pub fn len(&self) -> usize {
         self.rb.len()
     }
+
+    /// Approximate number of items in the buffer, see [`RingBuffer::len_relaxed`].
+    pub fn len_relaxed(&self) -> usize {
+        self.rb.len_relaxed()
+    }
+
+    /// Approximate fill ratio of the buffer, see [`RingBuffer::occupancy_ratio`].
+    pub fn occupancy_ratio(&self) -> f64 {
+        self.rb.occupancy_ratio()
+    }
+
+    /// Snapshot of the buffer indices, see [`RingBuffer::stats`].
+    pub fn stats(&self) -> RingBufferStats {
+        self.rb.stats()
+    }
+
+    /// Snapshot of the operation counters, see [`RingBuffer::metrics`].
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> RingMetrics {
+        self.rb.metrics()
+    }
 }
 
 impl<'a, T, const N: usize> Consumer<'a, T, N> {
     /// Pops an item from the ring buffer. If the buffer is empty, it will return None immediately without blocking.
     pub fn try_pop(&self) -> Option<T> {
-        self.rb.pop()
+        let item = self.rb.pop()?;
+        self.rb.wake_producer();
+        Some(item)
     }
 
     /// Pops an item from the ring buffer. If the buffer is empty, it will block until an item is available.
+    /// Always returns Some, see [`Consumer::pop_blocking`].
     pub fn pop(&self) -> Option<T> {
-        // First pop operation, store the consumer thread handle to allow for better synchronization in push when buffer is full.
-        self.rb
-            .consumer_thread
-            .get_or_init(|| std::thread::current());
+        Some(self.rb.pop_blocking())
+    }
 
-        loop {
-            match self.rb.pop() {
-                Some(item) => return Some(item),
-                None => {
-                    std::thread::park(); // Block the current thread until it is unparked
-                }
-            }
-        }
+    /// Pops an item, parking the consumer thread while the buffer is empty, see [`RingBuffer::pop_blocking`].
+    pub fn pop_blocking(&self) -> T {
+        self.rb.pop_blocking()
     }
 
+    /// Pops an item, parking the consumer thread at most `timeout` while the buffer is empty, see [`RingBuffer::pop_timeout`].
     pub fn pop_timeout(&self, timeout: Duration) -> Option<T> {
         self.rb.pop_timeout(timeout)
     }
 
+    /// Returns a reference to the next item to pop without consuming it, or None if the buffer is empty.
+    /// Takes `&mut self` so the reference cannot outlive a subsequent pop.
+    pub fn peek(&mut self) -> Option<&T> {
+        self.peek_at(0)
+    }
+
+    /// Returns a reference to the item `offset` positions after the next item to pop, without consuming it.
+    /// Returns None if fewer than `offset + 1` items are available.
+    /// Takes `&mut self` so the reference cannot outlive a subsequent pop.
+    pub fn peek_at(&mut self, offset: usize) -> Option<&T> {
+        // Safety: only the consumer moves tail, and the exclusive borrow prevents popping while the reference is alive.
+        self.rb.peek_at(offset).map(|ptr| unsafe { &*ptr })
+    }
+
     pub fn pop_batch(&self, items: &mut [T]) -> usize
     where
         T: Copy,
     {
-        self.rb.pop_batch(items)
+        let popped = self.rb.pop_batch(items);
+        if popped > 0 {
+            self.rb.wake_producer();
+        }
+        popped
+    }
+
+    /// Copies up to `out.len()` items out of the ring buffer with one index update, see [`RingBuffer::pop_slice`].
+    /// Wakes up the producer thread if anything was popped. Returns the number of items written at the start of `out`.
+    pub fn pop_slice(&self, out: &mut [T]) -> usize
+    where
+        T: Copy,
+    {
+        let popped = self.rb.pop_slice(out);
+        if popped > 0 {
+            self.rb.wake_producer();
+        }
+        popped
     }
 
     pub fn is_empty(&self) -> bool {
@@ -139,6 +244,27 @@ impl<'a, T, const N: usize> Consumer<'a, T, N> {
     pub fn len(&self) -> usize {
         self.rb.len()
     }
+
+    /// Approximate number of items in the buffer, see [`RingBuffer::len_relaxed`].
+    pub fn len_relaxed(&self) -> usize {
+        self.rb.len_relaxed()
+    }
+
+    /// Approximate fill ratio of the buffer, see [`RingBuffer::occupancy_ratio`].
+    pub fn occupancy_ratio(&self) -> f64 {
+        self.rb.occupancy_ratio()
+    }
+
+    /// Snapshot of the buffer indices, see [`RingBuffer::stats`].
+    pub fn stats(&self) -> RingBufferStats {
+        self.rb.stats()
+    }
+
+    /// Snapshot of the operation counters, see [`RingBuffer::metrics`].
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> RingMetrics {
+        self.rb.metrics()
+    }
 }
 
 const SPIN_THRESHOLD: usize = 256;
@@ -149,14 +275,20 @@ impl<T, const N: usize> RingBuffer<T, N> {
     pub fn new() -> Self {
         assert!(N.is_power_of_two(), "N must be a power of 2");
 
-        let buffer: UnsafeCell<AlignedBuffer<T, N>> =
-            UnsafeCell::new(unsafe { MaybeUninit::uninit().assume_init() });
-
         Self {
-            buffer,
+            buffer: Self::new_storage(),
             head: CachePadded(AtomicUsize::new(0)),
             tail: CachePadded(AtomicUsize::new(0)),
             consumer_thread: std::sync::OnceLock::new(),
+            consumer_parked: AtomicBool::new(false),
+            producer_thread: std::sync::OnceLock::new(),
+            producer_parked: AtomicBool::new(false),
+            #[cfg(feature = "metrics")]
+            push_count: CachePadded(AtomicU64::new(0)),
+            #[cfg(feature = "metrics")]
+            full_rejections: CachePadded(AtomicU64::new(0)),
+            #[cfg(feature = "metrics")]
+            pop_count: CachePadded(AtomicU64::new(0)),
         }
     }
 
@@ -166,6 +298,28 @@ impl<T, const N: usize> RingBuffer<T, N> {
         }
     }
 
+    /// Resets the buffer and splits it into its producer and consumer handles, the recommended way to use it.
+    /// The exclusive borrow keeps the raw API out of reach while the handles are alive, and the handles cannot be
+    /// cloned, so there is a single producer and a single consumer:
+    ///
+    /// ```compile_fail
+    /// let mut rb = spsc::spsc_lock_free::RingBuffer::<u32, 8>::new();
+    /// let (producer, _consumer) = rb.split();
+    /// let second_producer = producer.clone();
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let mut rb = spsc::spsc_lock_free::RingBuffer::<u32, 8>::new();
+    /// let (_producer, consumer) = rb.split();
+    /// let second_consumer = consumer.clone();
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let mut rb = spsc::spsc_lock_free::RingBuffer::<u32, 8>::new();
+    /// let (producer, _consumer) = rb.split();
+    /// rb.push(1).unwrap(); // rb is still borrowed by the handles
+    /// producer.push(2).unwrap();
+    /// ```
     pub fn split<'a>(&'a mut self) -> (Producer<'a, T, N>, Consumer<'a, T, N>) {
         *self = Self::new(); // Reset head and tail to 0, ensure buffer is empty
         (
@@ -179,6 +333,7 @@ impl<T, const N: usize> RingBuffer<T, N> {
 
     /// Pushes an item into the ring buffer.
     /// Returns Err(item) if the buffer is full.
+    /// Raw API: only one thread may push at a time, prefer [`Producer::push`] from [`RingBuffer::split`].
     pub fn push(&self, item: T) -> Result<(), T> {
         let head = self.head.0.load(Ordering::Relaxed); // Relaxed is safe here because only the consumer modifies head
         let next_head = (head + 1) & (N - 1); // Bitwise mask because N is power of 2
@@ -188,17 +343,11 @@ impl<T, const N: usize> RingBuffer<T, N> {
         if next_head != tail_relaxed {
             fence(Ordering::Acquire);
             // Space available, fast path
-            unsafe {
-                *self
-                    .buffer
-                    .get()
-                    .as_mut()
-                    .unwrap()
-                    .0
-                    .get_unchecked_mut(head) = MaybeUninit::new(item);
-            }
+            unsafe { self.write_slot(head, item) }
 
             self.head.0.store(next_head, Ordering::Release);
+            #[cfg(feature = "metrics")]
+            self.push_count.0.fetch_add(1, Ordering::Relaxed);
             return Ok(());
         }
 
@@ -210,7 +359,7 @@ impl<T, const N: usize> RingBuffer<T, N> {
 
             loop {
                 for _ in 0..spin {
-                    std::hint::spin_loop();
+                    hint::spin_loop();
                 }
 
                 tail = self.tail.0.load(Ordering::Acquire); // Acquire to synchronize with consumer
@@ -223,27 +372,24 @@ impl<T, const N: usize> RingBuffer<T, N> {
                 if spin < SPIN_THRESHOLD {
                     spin *= 2; // backoff
                 } else {
+                    #[cfg(feature = "metrics")]
+                    self.full_rejections.0.fetch_add(1, Ordering::Relaxed);
                     return Err(item);
                 }
             }
         }
 
-        unsafe {
-            *self
-                .buffer
-                .get()
-                .as_mut()
-                .unwrap()
-                .0
-                .get_unchecked_mut(head) = MaybeUninit::new(item);
-        }
+        unsafe { self.write_slot(head, item) }
 
         self.head.0.store(next_head, Ordering::Release);
+        #[cfg(feature = "metrics")]
+        self.push_count.0.fetch_add(1, Ordering::Relaxed);
         Ok(())
     }
 
     /// Pops an item from the ring buffer.
     /// Returns None if the buffer is empty.
+    /// Raw API: only one thread may pop at a time, prefer [`Consumer::pop`] from [`RingBuffer::split`].
     pub fn pop(&self) -> Option<T> {
         let relaxed_head = self.head.0.load(Ordering::Relaxed); // Acquire to synchronize with producer
         let tail = self.tail.0.load(Ordering::Relaxed); // Relaxed is safe here because only the consumer modifies tail
@@ -251,19 +397,12 @@ impl<T, const N: usize> RingBuffer<T, N> {
         if relaxed_head != tail {
             fence(Ordering::Acquire);
             // Data available, fast path
-            let item = unsafe {
-                self.buffer
-                    .get()
-                    .as_mut()
-                    .unwrap()
-                    .0
-                    .get_unchecked_mut(tail)
-                    .as_ptr()
-                    .read()
-            };
+            let item = unsafe { self.read_slot(tail) };
 
             let next_tail = (tail + 1) & (N - 1); // Bitwise mask because N is power of 2
             self.tail.0.store(next_tail, Ordering::Release);
+            #[cfg(feature = "metrics")]
+            self.pop_count.0.fetch_add(1, Ordering::Relaxed);
 
             return Some(item);
         }
@@ -274,7 +413,7 @@ impl<T, const N: usize> RingBuffer<T, N> {
 
             loop {
                 for _ in 0..spin {
-                    std::hint::spin_loop();
+                    hint::spin_loop();
                 }
 
                 head = self.head.0.load(Ordering::Acquire); // Acquire to synchronize with producer
@@ -295,22 +434,45 @@ impl<T, const N: usize> RingBuffer<T, N> {
         // Synchronize with producer to ensure we see the latest data, improve atomic load performance when buffer is not empty
         //fence(Ordering::Acquire);
 
-        let item = unsafe {
-            self.buffer
-                .get()
-                .as_mut()
-                .unwrap()
-                .0
-                .get_unchecked_mut(tail)
-                .as_ptr()
-                .read()
-        };
+        let item = unsafe { self.read_slot(tail) };
 
         let next_tail = (tail + 1) & (N - 1); // Bitwise mask because N is power of 2
         self.tail.0.store(next_tail, Ordering::Release);
+        #[cfg(feature = "metrics")]
+        self.pop_count.0.fetch_add(1, Ordering::Relaxed);
         Some(item)
     }
 
+    /// Pushes an item, evicting the oldest one when the buffer is full so the newest data always wins.
+    /// Returns the evicted item, or None if there was room.
+    ///
+    /// Not for the split SPSC usage: evicting moves `tail`, which belongs to the consumer. Only call it from a single
+    /// thread owning both ends, with no [`Producer`] or [`Consumer`] alive and no concurrent `pop`, otherwise the
+    /// consumer may read a slot while it is overwritten.
+    pub fn push_overwrite(&self, item: T) -> Option<T> {
+        if N == 1 {
+            // No slot can hold an item, the new one is the oldest
+            return Some(item);
+        }
+
+        let head = self.head.0.load(Ordering::Relaxed);
+        let tail = self.tail.0.load(Ordering::Relaxed); // Relaxed is enough, this thread owns both ends
+        let next_head = (head + 1) & (N - 1); // Bitwise mask because N is power of 2
+
+        let evicted = if next_head == tail {
+            // Buffer is full, move the oldest item out to free its slot
+            let oldest = unsafe { self.read_slot(tail) };
+            self.tail.0.store((tail + 1) & (N - 1), Ordering::Release);
+            Some(oldest)
+        } else {
+            None
+        };
+
+        unsafe { self.write_slot(head, item) }
+        self.head.0.store(next_head, Ordering::Release);
+        evicted
+    }
+
     /// Pushes a batch of items into the ring buffer.
     /// Returns the number of items successfully pushed.
     pub fn push_batch(&self, items: &[T]) -> usize
@@ -333,15 +495,7 @@ impl<T, const N: usize> RingBuffer<T, N> {
             // Synchronize with consumer to ensure we see the latest data, improve atomic load performance when buffer is not full
             //fence(Ordering::Acquire);
 
-            unsafe {
-                *self
-                    .buffer
-                    .get()
-                    .as_mut()
-                    .unwrap()
-                    .0
-                    .get_unchecked_mut(head) = MaybeUninit::new(item);
-            }
+            unsafe { self.write_slot(head, item) }
 
             head = next_head;
             pushed += 1;
@@ -352,64 +506,115 @@ impl<T, const N: usize> RingBuffer<T, N> {
         pushed
     }
 
-    pub fn pop_timeout(&self, timeout: Duration) -> Option<T> {
-        // Fast path — mirrors your existing pop() fast path exactly
-        let relaxed_head = self.head.0.load(Ordering::Relaxed);
-        let tail = self.tail.0.load(Ordering::Relaxed);
-
-        if relaxed_head != tail {
-            fence(Ordering::Acquire);
-            let item = unsafe {
-                self.buffer
-                    .get()
-                    .as_mut()
-                    .unwrap()
-                    .0
-                    .get_unchecked_mut(tail)
-                    .as_ptr()
-                    .read()
-            };
-            let next_tail = (tail + 1) & (N - 1);
-            self.tail.0.store(next_tail, Ordering::Release);
-            return Some(item);
+    /// Pushes an item, parking the producer thread while the buffer is full.
+    /// Spins with backoff first like [`RingBuffer::push`], then parks until the consumer frees a slot. The consumer is
+    /// only unparked when it is parked on the empty buffer, so a steady flow of items costs no syscall.
+    pub fn push_blocking(&self, item: T) {
+        let mut item = item;
+        loop {
+            match self.push(item) {
+                Ok(()) => {
+                    self.wake_consumer();
+                    return;
+                }
+                Err(rejected) => item = rejected,
+            }
+            self.park_producer();
         }
+    }
 
-        // Register consumer thread so producer can unpark us on push
-        let consumer_thread = std::thread::current();
-        self.consumer_thread.get_or_init(|| consumer_thread);
+    /// Pops an item, parking the consumer thread while the buffer is empty.
+    /// Spins with backoff first like [`RingBuffer::pop`], then parks until the producer pushes an item. The producer is
+    /// only unparked when it is parked on the full buffer.
+    pub fn pop_blocking(&self) -> T {
+        loop {
+            if let Some(item) = self.pop() {
+                self.wake_producer();
+                return item;
+            }
+            self.park_consumer(None);
+        }
+    }
 
-        // Slow path — use park_timeout for efficient waiting instead of busy spinning
+    /// Pops an item like [`RingBuffer::pop_blocking`], giving up after `timeout`, e.g. to check a shutdown flag.
+    /// Returns None if the buffer stayed empty until then.
+    pub fn pop_timeout(&self, timeout: Duration) -> Option<T> {
         let deadline = Instant::now() + timeout;
-
         loop {
-            let head = self.head.0.load(Ordering::Acquire);
-            let tail = self.tail.0.load(Ordering::Relaxed);
-
-            if head != tail {
-                // Data available, consume it
-                fence(Ordering::Acquire);
-                let item = unsafe {
-                    self.buffer
-                        .get()
-                        .as_mut()
-                        .unwrap()
-                        .0
-                        .get_unchecked_mut(tail)
-                        .as_ptr()
-                        .read()
-                };
-                let next_tail = (tail + 1) & (N - 1);
-                self.tail.0.store(next_tail, Ordering::Release);
+            if let Some(item) = self.pop() {
+                self.wake_producer();
                 return Some(item);
             }
-
-            let now = Instant::now();
-            if now >= deadline {
+            if !self.park_consumer(Some(deadline)) {
                 return None;
             }
+        }
+    }
+
+    /// Parks the consumer thread until the buffer is not empty or `deadline` passes.
+    /// Returns whether an item is available.
+    fn park_consumer(&self, deadline: Option<Instant>) -> bool {
+        self.consumer_thread.get_or_init(std::thread::current);
+        loop {
+            self.consumer_parked.store(true, Ordering::Release);
+            fence(Ordering::SeqCst); // Publish the flag before checking head, pairs with the fence in wake_consumer
+            if !self.is_empty() {
+                self.consumer_parked.store(false, Ordering::Relaxed);
+                return true;
+            }
+
+            match deadline {
+                None => std::thread::park(),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        self.consumer_parked.store(false, Ordering::Relaxed);
+                        return false;
+                    }
+                    std::thread::park_timeout(deadline - now);
+                }
+            }
+        }
+    }
+
+    /// Parks the producer thread until the buffer is not full.
+    fn park_producer(&self) {
+        self.producer_thread.get_or_init(std::thread::current);
+        loop {
+            self.producer_parked.store(true, Ordering::Release);
+            fence(Ordering::SeqCst); // Publish the flag before checking tail, pairs with the fence in wake_producer
+            if !self.is_full() {
+                self.producer_parked.store(false, Ordering::Relaxed);
+                return;
+            }
+            std::thread::park();
+        }
+    }
 
-            let remaining = deadline - now;
-            std::thread::park_timeout(remaining);
+    /// Unparks the consumer if it is parked on the empty buffer, called after an item was pushed.
+    fn wake_consumer(&self) {
+        fence(Ordering::SeqCst); // Order the head store before reading the flag, pairs with the fence in park_consumer
+        // The load keeps the hot path free of read-modify-write, the swap makes a single unpark per park
+        if !self.consumer_parked.load(Ordering::Relaxed)
+            || !self.consumer_parked.swap(false, Ordering::Acquire)
+        {
+            return;
+        }
+        if let Some(consumer_thread) = self.consumer_thread.get() {
+            consumer_thread.unpark();
+        }
+    }
+
+    /// Unparks the producer if it is parked on the full buffer, called after an item was popped.
+    fn wake_producer(&self) {
+        fence(Ordering::SeqCst); // Order the tail store before reading the flag, pairs with the fence in park_producer
+        if !self.producer_parked.load(Ordering::Relaxed)
+            || !self.producer_parked.swap(false, Ordering::Acquire)
+        {
+            return;
+        }
+        if let Some(producer_thread) = self.producer_thread.get() {
+            producer_thread.unpark();
         }
     }
 
@@ -419,23 +624,14 @@ impl<T, const N: usize> RingBuffer<T, N> {
     {
         let mut popped = 0;
 
-        let head = self.head.0.load(Ordering::Relaxed);
-        let mut tail = self.tail.0.load(Ordering::Acquire);
+        let head = self.head.0.load(Ordering::Acquire); // Acquire to synchronize with producer
+        let mut tail = self.tail.0.load(Ordering::Relaxed); // Relaxed is safe here because only the consumer modifies tail
 
         while head != tail && popped < items.len() {
             // Synchronize with producer to ensure we see the latest data, improve atomic load performance when buffer is not empty
             //fence(Ordering::Acquire);
 
-            items[popped] = unsafe {
-                self.buffer
-                    .get()
-                    .as_mut()
-                    .unwrap()
-                    .0
-                    .get_unchecked_mut(tail)
-                    .as_ptr()
-                    .read()
-            };
+            items[popped] = unsafe { self.read_slot(tail) };
 
             tail = (tail + 1) & (N - 1); // Bitwise mask because N is power of 2
             popped += 1;
@@ -445,6 +641,113 @@ impl<T, const N: usize> RingBuffer<T, N> {
         popped
     }
 
+    /// Pushes as many items of `items` as fit, in order.
+    /// The free region is reserved with a single load of tail, filled with at most two copies (before and after the
+    /// end of the storage) and published with a single Release store of head, instead of one per item.
+    /// Returns the number of items pushed, less than `items.len()` when the buffer is nearly full.
+    pub fn push_slice(&self, items: &[T]) -> usize
+    where
+        T: Copy,
+    {
+        let head = self.head.0.load(Ordering::Relaxed); // Relaxed is safe here because only the producer modifies head
+        let tail = self.tail.0.load(Ordering::Acquire); // Acquire to synchronize with consumer
+        let free = (tail + N - head - 1) & (N - 1); // One slot is kept free to distinguish full from empty
+        let count = items.len().min(free);
+        if count == 0 {
+            return 0;
+        }
+
+        let first = count.min(N - head); // Items fitting before the end of the storage
+        unsafe {
+            self.write_slots(head, &items[..first]);
+            self.write_slots(0, &items[first..count]);
+        }
+
+        self.head
+            .0
+            .store((head + count) & (N - 1), Ordering::Release);
+        count
+    }
+
+    /// Pops up to `out.len()` items into the start of `out`, in order.
+    /// The filled region is read with a single load of head, copied with at most two copies and released with a single
+    /// Release store of tail. Returns the number of items popped, less than `out.len()` when the buffer is nearly empty.
+    pub fn pop_slice(&self, out: &mut [T]) -> usize
+    where
+        T: Copy,
+    {
+        let head = self.head.0.load(Ordering::Acquire); // Acquire to synchronize with producer
+        let tail = self.tail.0.load(Ordering::Relaxed); // Relaxed is safe here because only the consumer modifies tail
+        let available = (head + N - tail) & (N - 1);
+        let count = out.len().min(available);
+        if count == 0 {
+            return 0;
+        }
+
+        let first = count.min(N - tail); // Items stored before the end of the storage
+        unsafe {
+            self.read_slots(tail, &mut out[..first]);
+            self.read_slots(0, &mut out[first..count]);
+        }
+
+        self.tail
+            .0
+            .store((tail + count) & (N - 1), Ordering::Release);
+        count
+    }
+
+    /// Moves items out of `items` until the buffer is full or the iterator is exhausted, for items that are not `Copy`.
+    /// The free region is reserved once and published with a single Release store of head. No more items than the free
+    /// slots are pulled from the iterator, so the remaining ones stay in it. Returns the number of items pushed.
+    pub fn push_iter<I>(&self, items: &mut I) -> usize
+    where
+        I: Iterator<Item = T>,
+    {
+        let head = self.head.0.load(Ordering::Relaxed); // Relaxed is safe here because only the producer modifies head
+        let tail = self.tail.0.load(Ordering::Acquire); // Acquire to synchronize with consumer
+        let free = (tail + N - head - 1) & (N - 1);
+
+        let mut pushed = 0;
+        while pushed < free {
+            let Some(item) = items.next() else {
+                break;
+            };
+            let index = (head + pushed) & (N - 1);
+            unsafe { self.write_slot(index, item) };
+            pushed += 1;
+        }
+
+        if pushed > 0 {
+            self.head
+                .0
+                .store((head + pushed) & (N - 1), Ordering::Release);
+        }
+        pushed
+    }
+
+    /// Returns a reference to the next item to pop, without consuming it, or None if the buffer is empty.
+    /// Raw API: the reference is only bounded by `&self`, so the caller must make sure the single consumer does not
+    /// `pop` while it is alive. [`Consumer::peek`] enforces this with an exclusive borrow.
+    pub fn peek(&self) -> Option<&T> {
+        // Safety: the slot at tail is initialized and only the consumer moves tail past it.
+        self.peek_at(0).map(|ptr| unsafe { &*ptr })
+    }
+
+    /// Returns a pointer to the item `offset` positions after tail, if it is within the filled range.
+    /// Must only be called from the consumer side.
+    fn peek_at(&self, offset: usize) -> Option<*const T> {
+        let head = self.head.0.load(Ordering::Acquire); // Acquire to synchronize with producer
+        let tail = self.tail.0.load(Ordering::Relaxed); // Relaxed is safe here because only the consumer modifies tail
+        let len = (head + N - tail) & (N - 1); // Bitwise mask because N is power of 2
+
+        if offset >= len {
+            return None;
+        }
+
+        let index = (tail + offset) & (N - 1);
+        Some(unsafe { self.buffer.0.get_unchecked(index) }.with(|slot| slot as *const T))
+    }
+
     /// Checks if the ring buffer is empty.
     pub fn is_empty(&self) -> bool {
         let head = self.head.0.load(Ordering::Acquire);
@@ -452,12 +755,12 @@ impl<T, const N: usize> RingBuffer<T, N> {
         head == tail
     }
 
-    /// Checks if the ring buffer is full.
+    /// Checks if the ring buffer is full, one slot always stays free so a push fails at `N - 1` items.
     pub fn is_full(&self) -> bool {
         let head = self.head.0.load(Ordering::Relaxed);
         let tail = self.tail.0.load(Ordering::Acquire);
-        let next_tail = (tail + 1) & (N - 1); // Bitwise mask because N is power of 2
-        next_tail == head
+        let next_head = (head + 1) & (N - 1); // Bitwise mask because N is power of 2
+        next_head == tail
     }
 
     /// Returns the current number of items in the ring buffer.
@@ -466,11 +769,161 @@ impl<T, const N: usize> RingBuffer<T, N> {
         let tail = self.tail.0.load(Ordering::Relaxed);
         (head + N - tail) & (N - 1) // Bitwise mask because N is power of 2
     }
+
+    /// Maximum number of items the buffer holds, `N - 1` since one slot always stays free.
+    pub fn capacity(&self) -> usize {
+        N - 1
+    }
+
+    /// Drops the items left in the buffer and resets head and tail to 0.
+    /// Takes `&mut self`, so no handle from [`RingBuffer::split`] nor any other thread can hold the buffer.
+    pub fn clear(&mut self) {
+        self.drop_items();
+        // Relaxed is enough, the exclusive borrow already orders them with any other access
+        self.head.0.store(0, Ordering::Relaxed);
+        self.tail.0.store(0, Ordering::Relaxed);
+    }
+
+    /// Returns an approximate number of items in the ring buffer.
+    /// Both indices are loaded Relaxed, so the value may be momentarily inconsistent,
+    /// but it is always clamped to [0, N]. Safe to call from any thread, e.g. a monitoring thread.
+    pub fn len_relaxed(&self) -> usize {
+        let head = self.head.0.load(Ordering::Relaxed);
+        let tail = self.tail.0.load(Ordering::Relaxed);
+        Self::relaxed_len(head, tail)
+    }
+
+    /// Returns the approximate fill ratio of the ring buffer in [0.0, 1.0].
+    /// Same caveats as `len_relaxed`.
+    pub fn occupancy_ratio(&self) -> f64 {
+        let capacity = N - 1; // One slot is kept free to distinguish full from empty
+        if capacity == 0 {
+            return 0.0;
+        }
+        (self.len_relaxed().min(capacity) as f64) / capacity as f64
+    }
+
+    /// Returns a snapshot of head, tail, length and capacity for logging.
+    /// Same caveats as `len_relaxed`.
+    pub fn stats(&self) -> RingBufferStats {
+        let head = self.head.0.load(Ordering::Relaxed);
+        let tail = self.tail.0.load(Ordering::Relaxed);
+        RingBufferStats {
+            head,
+            tail,
+            len: Self::relaxed_len(head, tail),
+            capacity: N - 1,
+        }
+    }
+
+    /// Snapshot of the operation counters, see [`RingMetrics`].
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> RingMetrics {
+        RingMetrics {
+            push_count: self.push_count.0.load(Ordering::Relaxed),
+            pop_count: self.pop_count.0.load(Ordering::Relaxed),
+            full_rejections: self.full_rejections.0.load(Ordering::Relaxed),
+        }
+    }
+
+    // Drops the live items between tail and head, without moving the indices
+    fn drop_items(&mut self) {
+        let head = self.head.0.load(Ordering::Relaxed);
+        let mut tail = self.tail.0.load(Ordering::Relaxed);
+        while head != tail {
+            unsafe {
+                self.buffer
+                    .0
+                    .get_unchecked(tail)
+                    .with_mut(|slot| (*slot).assume_init_drop());
+            }
+            tail = (tail + 1) & (N - 1); // Bitwise mask because N is power of 2
+        }
+    }
+
+    // Uninitialized slots, loom tracks each of its cells so they are built one by one there
+    fn new_storage() -> AlignedBuffer<T, N> {
+        #[cfg(not(loom))]
+        {
+            // Safety: the cells are transparent wrappers of MaybeUninit, which needs no initialization
+            unsafe { MaybeUninit::uninit().assume_init() }
+        }
+        #[cfg(loom)]
+        {
+            AlignedBuffer(std::array::from_fn(|_| {
+                UnsafeCell::new(MaybeUninit::uninit())
+            }))
+        }
+    }
+
+    // Safety: `index < N` and the slot is free, owned by the producer until head moves past it
+    #[inline(always)]
+    unsafe fn write_slot(&self, index: usize, item: T) {
+        unsafe {
+            self.buffer
+                .0
+                .get_unchecked(index)
+                .with_mut(|slot| (*slot).write(item));
+        }
+    }
+
+    // Safety: `index < N` and the slot is filled, owned by the consumer until tail moves past it
+    #[inline(always)]
+    unsafe fn read_slot(&self, index: usize) -> T {
+        unsafe {
+            self.buffer
+                .0
+                .get_unchecked(index)
+                .with(|slot| (*slot).as_ptr().read())
+        }
+    }
+
+    // Copies `items` into the free slots from `index`, which must not run past the end of the storage
+    #[inline(always)]
+    unsafe fn write_slots(&self, index: usize, items: &[T])
+    where
+        T: Copy,
+    {
+        #[cfg(not(loom))]
+        unsafe {
+            let storage = self.buffer.0.as_ptr() as *mut T;
+            std::ptr::copy_nonoverlapping(items.as_ptr(), storage.add(index), items.len());
+        }
+        #[cfg(loom)]
+        for (offset, &item) in items.iter().enumerate() {
+            unsafe { self.write_slot(index + offset, item) };
+        }
+    }
+
+    // Copies the filled slots from `index` into `out`, which must not run past the end of the storage
+    #[inline(always)]
+    unsafe fn read_slots(&self, index: usize, out: &mut [T])
+    where
+        T: Copy,
+    {
+        #[cfg(not(loom))]
+        unsafe {
+            let storage = self.buffer.0.as_ptr() as *const T;
+            std::ptr::copy_nonoverlapping(storage.add(index), out.as_mut_ptr(), out.len());
+        }
+        #[cfg(loom)]
+        for (offset, item) in out.iter_mut().enumerate() {
+            *item = unsafe { self.read_slot(index + offset) };
+        }
+    }
+
+    #[inline(always)]
+    fn relaxed_len(head: usize, tail: usize) -> usize {
+        // Wrapping math never panics, even if a racing load observed an index out of range
+        (head.wrapping_sub(tail) & (N - 1)).min(N)
+    }
 }
 
-#[cfg(test)]
+#[cfg(all(test, not(loom)))]
 mod tests {
     use super::RingBuffer;
+    #[cfg(feature = "metrics")]
+    use super::RingMetrics;
 
     #[test]
     fn it_works() {
@@ -490,7 +943,34 @@ mod tests {
         assert_eq!(producer.push(4), Ok(()));
         assert_eq!(consumer.pop(), Some(3));
         assert_eq!(consumer.pop(), Some(4));
-        assert_eq!(consumer.pop(), None); // Buffer should be empty
+        assert_eq!(consumer.try_pop(), None); // Buffer should be empty, pop would block
+    }
+
+    #[test]
+    fn split_handles_report_fill_state() {
+        let mut rb: RingBuffer<u8, 4> = RingBuffer::new();
+        let (producer, consumer) = rb.split();
+        assert!(!producer.is_full());
+        assert!(consumer.is_empty());
+
+        producer.push(1).unwrap();
+        assert!(!producer.is_full());
+        assert!(!consumer.is_empty());
+        producer.push(2).unwrap();
+        producer.push(3).unwrap();
+        assert!(producer.is_full());
+        assert_eq!((producer.len(), consumer.len()), (3, 3));
+
+        assert_eq!(consumer.try_pop(), Some(1));
+        assert!(!producer.is_full());
+        assert_eq!(producer.len(), 2);
+    }
+
+    #[test]
+    fn split_handles_are_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<super::Producer<'static, String, 8>>();
+        assert_send::<super::Consumer<'static, String, 8>>();
     }
 
     #[test]
@@ -524,6 +1004,379 @@ mod tests {
         });
     }
 
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn metrics_count_pushes_pops_and_rejections() {
+        let mut rb: RingBuffer<u32, 8> = RingBuffer::new();
+        assert_eq!(rb.metrics(), RingMetrics::default());
+
+        for i in 0..10 {
+            let _ = rb.push(i); // The last 3 find the buffer full
+        }
+        for _ in 0..5 {
+            assert!(rb.pop().is_some());
+        }
+        assert_eq!(
+            rb.metrics(),
+            RingMetrics {
+                push_count: 7,
+                pop_count: 5,
+                full_rejections: 3,
+            }
+        );
+
+        let (producer, consumer) = rb.split();
+        assert_eq!(producer.metrics(), RingMetrics::default());
+        producer.push(1).unwrap();
+        assert_eq!(consumer.try_pop(), Some(1));
+        assert_eq!(consumer.try_pop(), None); // An empty pop is not counted
+        assert_eq!(
+            consumer.metrics(),
+            RingMetrics {
+                push_count: 1,
+                pop_count: 1,
+                full_rejections: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn blocking_push_and_pop_stress() {
+        use std::thread;
+
+        // Both sides park on a full or empty buffer instead of spinning
+        const ITEMS: usize = 1_000_000;
+        let mut rb = RingBuffer::<usize, 1024>::new();
+
+        thread::scope(|s| {
+            let (producer, consumer) = rb.split();
+            s.spawn(move || {
+                for i in 0..ITEMS {
+                    producer.push_blocking(i);
+                }
+            });
+            for expected in 0..ITEMS {
+                assert_eq!(consumer.pop_blocking(), expected);
+            }
+            assert!(consumer.is_empty());
+        });
+    }
+
+    #[test]
+    fn blocking_push_and_pop_race_on_the_wakeup_edge() {
+        use std::sync::mpsc;
+        use std::thread;
+        use std::time::Duration;
+
+        // Capacity of one item: every push fills the buffer and every pop empties it, so both sides park and wake
+        // each other on nearly every item
+        const ITEMS: usize = 50_000;
+        let (done_tx, done_rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            let mut rb = RingBuffer::<usize, 2>::new();
+            thread::scope(|s| {
+                let (producer, consumer) = rb.split();
+                s.spawn(move || {
+                    for i in 0..ITEMS {
+                        producer.push_blocking(i);
+                    }
+                });
+                for expected in 0..ITEMS {
+                    assert_eq!(consumer.pop_blocking(), expected);
+                }
+            });
+            done_tx.send(()).unwrap();
+        });
+
+        // A lost wakeup leaves both threads parked forever
+        done_rx
+            .recv_timeout(Duration::from_secs(60))
+            .expect("producer and consumer deadlocked");
+    }
+
+    #[test]
+    fn raw_blocking_push_and_pop() {
+        use std::thread;
+
+        let rb = RingBuffer::<u32, 4>::new();
+        thread::scope(|s| {
+            s.spawn(|| {
+                for i in 0..1_000 {
+                    rb.push_blocking(i);
+                }
+            });
+            for expected in 0..1_000 {
+                assert_eq!(rb.pop_blocking(), expected);
+            }
+        });
+        assert!(rb.is_empty());
+    }
+
+    #[test]
+    fn pop_timeout_waits_for_a_late_push() {
+        use std::thread;
+        use std::time::{Duration, Instant};
+
+        let mut rb = RingBuffer::<u32, 4>::new();
+        let (producer, consumer) = rb.split();
+
+        let start = Instant::now();
+        assert_eq!(consumer.pop_timeout(Duration::from_millis(20)), None);
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        thread::scope(|s| {
+            s.spawn(|| {
+                thread::sleep(Duration::from_millis(20));
+                producer.push(7).unwrap();
+            });
+            // Parked until the push wakes it up, long before the timeout
+            assert_eq!(consumer.pop_timeout(Duration::from_secs(30)), Some(7));
+        });
+    }
+
+    #[test]
+    fn push_overwrite_evicts_the_oldest_items() {
+        let rb: RingBuffer<u32, 4> = RingBuffer::new();
+        assert_eq!(rb.push_overwrite(1), None);
+        assert_eq!(rb.push_overwrite(2), None);
+        assert_eq!(rb.push_overwrite(3), None);
+        assert!(rb.is_full());
+
+        // Oldest first
+        assert_eq!(rb.push_overwrite(4), Some(1));
+        assert_eq!(rb.push_overwrite(5), Some(2));
+        assert_eq!(rb.len(), 3);
+
+        assert_eq!(rb.pop(), Some(3));
+        assert_eq!(rb.push_overwrite(6), None);
+        assert_eq!(rb.push_overwrite(7), Some(4));
+        assert_eq!(rb.pop(), Some(5));
+        assert_eq!(rb.pop(), Some(6));
+        assert_eq!(rb.pop(), Some(7));
+        assert!(rb.is_empty());
+    }
+
+    #[test]
+    fn push_overwrite_drops_each_item_once() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        struct Counted(Arc<AtomicUsize>);
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let drops = Arc::new(AtomicUsize::new(0));
+        let rb: RingBuffer<Counted, 2> = RingBuffer::new();
+        for _ in 0..5 {
+            drop(rb.push_overwrite(Counted(Arc::clone(&drops))));
+        }
+        // Four evicted, one still in the buffer
+        assert_eq!(drops.load(Ordering::Relaxed), 4);
+        drop(rb);
+        assert_eq!(drops.load(Ordering::Relaxed), 5);
+
+        let empty: RingBuffer<u32, 1> = RingBuffer::new();
+        assert_eq!(empty.push_overwrite(1), Some(1));
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn capacity_is_constant() {
+        let rb: RingBuffer<u8, 8> = RingBuffer::new();
+        assert_eq!(rb.capacity(), 7);
+        for i in 0..7 {
+            rb.push(i).unwrap();
+            assert_eq!(rb.capacity(), 7);
+        }
+        assert!(rb.is_full());
+        rb.pop();
+        assert_eq!(rb.capacity(), 7);
+        assert_eq!(RingBuffer::<u8, 2>::new().capacity(), 1);
+    }
+
+    #[test]
+    fn clear_drops_the_live_items() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        struct Counted(Arc<AtomicUsize>);
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let drops = Arc::new(AtomicUsize::new(0));
+        let mut rb: RingBuffer<Counted, 4> = RingBuffer::new();
+        // Move tail off 0 so the live items wrap around the end of the storage
+        for _ in 0..3 {
+            assert!(rb.push(Counted(Arc::clone(&drops))).is_ok());
+        }
+        drop(rb.pop());
+        drop(rb.pop());
+        for _ in 0..2 {
+            assert!(rb.push(Counted(Arc::clone(&drops))).is_ok());
+        }
+        assert_eq!(rb.len(), 3);
+        assert_eq!(drops.load(Ordering::Relaxed), 2);
+
+        rb.clear();
+        assert!(rb.is_empty());
+        assert_eq!(
+            (
+                rb.head.0.load(Ordering::Relaxed),
+                rb.tail.0.load(Ordering::Relaxed)
+            ),
+            (0, 0)
+        );
+        assert_eq!(drops.load(Ordering::Relaxed), 5);
+
+        // Still usable, and the cleared items are not dropped again
+        assert!(rb.push(Counted(Arc::clone(&drops))).is_ok());
+        drop(rb);
+        assert_eq!(drops.load(Ordering::Relaxed), 6);
+    }
+
+    #[test]
+    fn peek_then_pop_return_the_same_value() {
+        let rb: RingBuffer<String, 4> = RingBuffer::new();
+        assert_eq!(rb.peek(), None);
+
+        rb.push("first".to_string()).unwrap();
+        rb.push("second".to_string()).unwrap();
+        assert_eq!(rb.peek().map(String::as_str), Some("first"));
+        assert_eq!(rb.peek().map(String::as_str), Some("first")); // Peeking twice does not advance
+        assert_eq!(rb.len(), 2);
+
+        assert_eq!(rb.pop().as_deref(), Some("first"));
+        assert_eq!(rb.peek().map(String::as_str), Some("second"));
+        assert_eq!(rb.pop().as_deref(), Some("second"));
+        assert_eq!(rb.peek(), None);
+    }
+
+    #[test]
+    fn consumer_peek_follows_pops() {
+        let mut rb: RingBuffer<u32, 4> = RingBuffer::new();
+        let (producer, mut consumer) = rb.split();
+        assert_eq!(consumer.peek(), None);
+
+        producer.push(7).unwrap();
+        producer.push(8).unwrap();
+        let peeked = *consumer.peek().unwrap();
+        assert_eq!(consumer.try_pop(), Some(peeked));
+        assert_eq!(consumer.peek(), Some(&8));
+    }
+
+    #[test]
+    fn peek_at_offsets() {
+        let mut rb: RingBuffer<u32, 8> = RingBuffer::new();
+        let (producer, mut consumer) = rb.split();
+        assert_eq!(consumer.peek_at(0), None);
+
+        producer.push(10).unwrap();
+        producer.push(20).unwrap();
+        producer.push(30).unwrap();
+
+        assert_eq!(consumer.peek_at(0), Some(&10));
+        assert_eq!(consumer.peek_at(1), Some(&20));
+        assert_eq!(consumer.peek_at(2), Some(&30));
+        assert_eq!(consumer.peek_at(3), None);
+        assert_eq!(consumer.len(), 3); // Peeking does not consume
+
+        assert_eq!(consumer.pop(), Some(10));
+        assert_eq!(consumer.peek_at(0), Some(&20));
+        assert_eq!(consumer.peek_at(2), None);
+        assert_eq!(consumer.pop(), Some(20));
+        assert_eq!(consumer.pop(), Some(30));
+        assert_eq!(consumer.peek_at(0), None);
+    }
+
+    #[test]
+    fn peek_at_wraps_around() {
+        let mut rb: RingBuffer<u32, 4> = RingBuffer::new();
+        let (producer, mut consumer) = rb.split();
+        for i in 0..3 {
+            producer.push(i).unwrap();
+        }
+        assert_eq!(consumer.pop(), Some(0));
+        assert_eq!(consumer.pop(), Some(1));
+        producer.push(3).unwrap();
+        producer.push(4).unwrap();
+
+        assert_eq!(consumer.peek_at(0), Some(&2));
+        assert_eq!(consumer.peek_at(1), Some(&3));
+        assert_eq!(consumer.peek_at(2), Some(&4));
+    }
+
+    #[test]
+    fn occupancy_metrics() {
+        let mut rb: RingBuffer<u8, 4> = RingBuffer::new();
+        assert_eq!(rb.len_relaxed(), 0);
+        assert_eq!(rb.occupancy_ratio(), 0.0);
+
+        let (producer, consumer) = rb.split();
+        producer.push(1).unwrap();
+        producer.push(2).unwrap();
+        producer.push(3).unwrap();
+        assert_eq!(producer.len_relaxed(), 3);
+        assert_eq!(producer.occupancy_ratio(), 1.0);
+
+        consumer.pop().unwrap();
+        let stats = consumer.stats();
+        assert_eq!(stats.head, 3);
+        assert_eq!(stats.tail, 1);
+        assert_eq!(stats.len, 2);
+        assert_eq!(stats.capacity, 3);
+    }
+
+    #[test]
+    fn occupancy_metrics_concurrent() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::thread;
+
+        const N: usize = 64;
+        let rb = RingBuffer::<usize, N>::new();
+        let done = AtomicBool::new(false);
+
+        thread::scope(|s| {
+            s.spawn(|| {
+                for i in 0..10_000 {
+                    while rb.push(i).is_err() {}
+                }
+            });
+
+            s.spawn(|| {
+                let mut expected = 0;
+                while expected < 10_000 {
+                    if let Some(v) = rb.pop() {
+                        assert_eq!(v, expected);
+                        expected += 1;
+                    }
+                }
+                done.store(true, Ordering::Release);
+            });
+
+            // Monitoring thread
+            s.spawn(|| {
+                while !done.load(Ordering::Acquire) {
+                    let len = rb.len_relaxed();
+                    assert!(len <= N);
+                    let ratio = rb.occupancy_ratio();
+                    assert!((0.0..=1.0).contains(&ratio));
+                    let stats = rb.stats();
+                    assert!(stats.len <= N);
+                    assert!(stats.head < N && stats.tail < N);
+                }
+            });
+        });
+
+        assert_eq!(rb.len_relaxed(), 0);
+    }
+
     #[test]
     fn spsc_non_blocking() {
         use std::thread;
@@ -554,4 +1407,229 @@ mod tests {
             prod.join().unwrap();
         });
     }
+
+    #[test]
+    fn push_slice_and_pop_slice_wrap_around() {
+        let mut rb: RingBuffer<u32, 8> = RingBuffer::new();
+        let (producer, consumer) = rb.split();
+        let mut out = [0u32; 8];
+
+        // Move head and tail close to the end of the storage
+        assert_eq!(producer.push_slice(&[0, 1, 2, 3, 4, 5]), 6);
+        assert_eq!(consumer.pop_slice(&mut out[..5]), 5);
+        assert_eq!(out[..5], [0, 1, 2, 3, 4]);
+
+        // Only 6 of the 7 slots are free, the push wraps around the end of the storage
+        assert_eq!(producer.push_slice(&[6, 7, 8, 9, 10, 11, 12, 13]), 6);
+        assert_eq!(producer.push_slice(&[12]), 0);
+        assert_eq!(consumer.len(), 7);
+
+        // The pop wraps around too, and stops once the buffer is empty
+        assert_eq!(consumer.pop_slice(&mut out), 7);
+        assert_eq!(out[..7], [5, 6, 7, 8, 9, 10, 11]);
+        assert_eq!(consumer.pop_slice(&mut out), 0);
+        assert!(consumer.is_empty());
+
+        // Per-item and batched paths see the same indices
+        producer.push(12).unwrap();
+        assert_eq!(producer.push_slice(&[13, 14]), 2);
+        assert_eq!(consumer.pop(), Some(12));
+        assert_eq!(consumer.pop_slice(&mut out[..1]), 1);
+        assert_eq!(out[0], 13);
+        assert_eq!(consumer.pop(), Some(14));
+    }
+
+    #[test]
+    fn push_slice_across_wrap_boundary_at_every_offset() {
+        const N: usize = 8;
+
+        for start in 0..N {
+            for len in 0..=N {
+                let mut rb: RingBuffer<usize, N> = RingBuffer::new();
+                let (producer, consumer) = rb.split();
+
+                // Move head and tail to `start` with the single-element path
+                for i in 0..start {
+                    producer.push(i).unwrap();
+                    assert_eq!(consumer.pop(), Some(i));
+                }
+
+                let items: Vec<usize> = (100..100 + len).collect();
+                let pushed = producer.push_slice(&items);
+                assert_eq!(pushed, len.min(N - 1), "start {start}, len {len}");
+                // Either nothing is left or the buffer is full, the caller retries the remainder later
+                assert_eq!(producer.push_slice(&items[pushed..]), 0);
+
+                // Single-element pushes and pops keep working after a batched push
+                for &expected in &items[..pushed] {
+                    assert_eq!(consumer.pop(), Some(expected));
+                }
+                assert!(consumer.is_empty());
+                producer.push(7).unwrap();
+                assert_eq!(consumer.pop(), Some(7));
+            }
+        }
+    }
+
+    #[test]
+    fn interleaved_push_slice_and_pop_slice_with_partial_fills() {
+        const N: usize = 16;
+        let mut rb: RingBuffer<u32, N> = RingBuffer::new();
+        let (producer, consumer) = rb.split();
+        let mut expected = std::collections::VecDeque::new();
+        let mut next = 0u32;
+        let mut out = [0u32; N];
+
+        // Push and pop sizes cycle out of phase, so both sides see partial transfers at every index
+        for round in 0..200 {
+            let push_len = (round * 7) % (N + 3);
+            let items: Vec<u32> = (next..next + push_len as u32).collect();
+            let pushed = producer.push_slice(&items);
+            assert_eq!(
+                pushed,
+                push_len.min(N - 1 - expected.len()),
+                "round {round}"
+            );
+            expected.extend(&items[..pushed]);
+            next += pushed as u32;
+
+            let pop_len = (round * 5) % (N + 1);
+            let popped = consumer.pop_slice(&mut out[..pop_len]);
+            assert_eq!(popped, pop_len.min(expected.len()), "round {round}");
+            for &item in &out[..popped] {
+                assert_eq!(Some(item), expected.pop_front());
+            }
+            assert_eq!(consumer.len(), expected.len());
+        }
+
+        // Drain what is left, then an empty buffer pops nothing
+        let popped = consumer.pop_slice(&mut out);
+        assert!(out[..popped].iter().copied().eq(expected.drain(..)));
+        assert_eq!(consumer.pop_slice(&mut out), 0);
+        assert_eq!(producer.push_slice(&[]), 0);
+    }
+
+    #[test]
+    fn push_iter_stops_when_full() {
+        let mut rb: RingBuffer<String, 4> = RingBuffer::new();
+        let (producer, consumer) = rb.split();
+        let mut items = (0..5).map(|i| i.to_string());
+
+        assert_eq!(producer.push_iter(&mut items), 3);
+        assert_eq!(producer.push_iter(&mut items), 0);
+        // Items that did not fit are still in the iterator
+        assert_eq!(consumer.pop().as_deref(), Some("0"));
+        assert_eq!(producer.push_iter(&mut items), 1);
+        assert_eq!(items.next().as_deref(), Some("4"));
+
+        assert_eq!(consumer.pop().as_deref(), Some("1"));
+        assert_eq!(consumer.pop().as_deref(), Some("2"));
+        assert_eq!(consumer.pop().as_deref(), Some("3"));
+        assert_eq!(producer.push_iter(&mut std::iter::empty()), 0);
+        assert!(consumer.is_empty());
+
+        // Items left in the buffer are dropped with it
+        assert_eq!(producer.push_iter(&mut ["a".to_string()].into_iter()), 1);
+    }
+
+    #[test]
+    fn spsc_slices() {
+        use std::thread;
+
+        const TOTAL: u64 = 1_000_000;
+        let mut rb = RingBuffer::<u64, 1024>::new();
+
+        thread::scope(|s| {
+            let (producer, consumer) = rb.split();
+
+            s.spawn(move || {
+                let items: Vec<u64> = (0..TOTAL).collect();
+                let mut sent = 0;
+                while sent < items.len() {
+                    // Odd-sized chunks so pushes regularly wrap around
+                    let end = (sent + 37).min(items.len());
+                    sent += producer.push_slice(&items[sent..end]);
+                }
+            });
+
+            let mut out = [0u64; 53];
+            let mut expected = 0;
+            while expected < TOTAL {
+                let popped = consumer.pop_slice(&mut out);
+                for &v in &out[..popped] {
+                    assert_eq!(v, expected);
+                    expected += 1;
+                }
+            }
+        });
+    }
+
+    /// Throughput of the per-item and batched paths, run with
+    /// `cargo test --release -- --ignored --nocapture per_item_vs_slice_throughput`.
+    #[test]
+    #[ignore]
+    fn per_item_vs_slice_throughput() {
+        use std::thread;
+        use std::time::Instant;
+
+        const TOTAL: u64 = 50_000_000;
+        const BATCH: usize = 64;
+
+        let per_item = {
+            let mut rb = RingBuffer::<u64, 4096>::new();
+            let started_at = Instant::now();
+            thread::scope(|s| {
+                let (producer, consumer) = rb.split();
+                s.spawn(move || {
+                    for i in 0..TOTAL {
+                        while producer.try_push(i).is_err() {}
+                    }
+                });
+                let mut received = 0;
+                while received < TOTAL {
+                    if consumer.try_pop().is_some() {
+                        received += 1;
+                    }
+                }
+            });
+            started_at.elapsed()
+        };
+
+        let batched = {
+            let mut rb = RingBuffer::<u64, 4096>::new();
+            let started_at = Instant::now();
+            thread::scope(|s| {
+                let (producer, consumer) = rb.split();
+                s.spawn(move || {
+                    let mut batch = [0u64; BATCH];
+                    let mut next = 0;
+                    while next < TOTAL {
+                        let len = BATCH.min((TOTAL - next) as usize);
+                        for (offset, item) in batch[..len].iter_mut().enumerate() {
+                            *item = next + offset as u64;
+                        }
+                        let mut sent = 0;
+                        while sent < len {
+                            sent += producer.push_slice(&batch[sent..len]);
+                        }
+                        next += len as u64;
+                    }
+                });
+                let mut out = [0u64; BATCH];
+                let mut received = 0;
+                while received < TOTAL {
+                    received += consumer.pop_slice(&mut out) as u64;
+                }
+            });
+            started_at.elapsed()
+        };
+
+        let rate = |elapsed: std::time::Duration| TOTAL as f64 / elapsed.as_secs_f64() / 1e6;
+        println!(
+            "per-item: {:.1} M msgs/s, push_slice/pop_slice ({BATCH}): {:.1} M msgs/s ({:.1}x)",
+            rate(per_item),
+            rate(batched),
+            per_item.as_secs_f64() / batched.as_secs_f64()
+        );
+    }
 }
@@ -0,0 +1,42 @@
+//! Atomics, hints and `UnsafeCell` of the lock-free structures, taken from loom when built with `--cfg loom` so the
+//! `concurrency-tests` crate can model check the real code.
+
+#[cfg(loom)]
+pub(crate) use loom::{
+    cell::UnsafeCell,
+    hint,
+    sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering, fence},
+};
+#[cfg(not(loom))]
+pub(crate) use std::{
+    hint,
+    sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering, fence},
+};
+
+#[cfg(all(loom, feature = "metrics"))]
+pub(crate) use loom::sync::atomic::AtomicU64;
+#[cfg(all(not(loom), feature = "metrics"))]
+pub(crate) use std::sync::atomic::AtomicU64;
+
+/// `UnsafeCell` with the closure based API of `loom::cell::UnsafeCell`, so the same code runs under loom.
+/// Transparent, so an array of cells has the layout of an array of values.
+#[cfg(not(loom))]
+#[repr(transparent)]
+pub(crate) struct UnsafeCell<T>(std::cell::UnsafeCell<T>);
+
+#[cfg(not(loom))]
+impl<T> UnsafeCell<T> {
+    pub(crate) const fn new(value: T) -> Self {
+        Self(std::cell::UnsafeCell::new(value))
+    }
+
+    #[inline(always)]
+    pub(crate) fn with<R>(&self, f: impl FnOnce(*const T) -> R) -> R {
+        f(self.0.get())
+    }
+
+    #[inline(always)]
+    pub(crate) fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+        f(self.0.get())
+    }
+}